@@ -23,6 +23,7 @@ setTimeout(async function() {
                 "content-type",
                 "application/problem+json"
             );
+            expect(res.body.code).to.equal("swap-not-found");
         });
 
         it("Returns an empty list when calling GET /swaps when there are no swaps", async () => {
@@ -64,6 +65,7 @@ setTimeout(async function() {
                 "application/problem+json"
             );
             expect(res.body.title).to.equal("Invalid body.");
+            expect(res.body.code).to.equal("invalid-body");
         });
 
         it("[Alice] Returns 400 invalid body for malformed requests", async () => {
@@ -79,6 +81,7 @@ setTimeout(async function() {
                 "application/problem+json"
             );
             expect(res.body.title).to.equal("Invalid body.");
+            expect(res.body.code).to.equal("invalid-body");
         });
 
         it("[Alice] Should have no peers before making a swap request", async () => {
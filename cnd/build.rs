@@ -0,0 +1,19 @@
+use std::process::Command;
+
+/// Makes the commit cnd is built from available as `GIT_HASH`, it is part of
+/// the agent version announced to peers. Builds outside of a git checkout are
+/// announced as `unknown`.
+fn main() {
+    let hash = Command::new("git")
+        .args(&["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned());
+
+    println!("cargo:rustc-env=GIT_HASH={}", hash);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/index");
+}
@@ -0,0 +1,7 @@
+CREATE TABLE rfc003_finished_swaps
+(
+    id INTEGER     	NOT NULL PRIMARY KEY,
+    swap_id UNIQUE 	NOT NULL,
+    finished_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    archived_at DATETIME
+);
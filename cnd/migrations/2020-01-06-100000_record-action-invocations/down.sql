@@ -0,0 +1 @@
+DROP TABLE rfc003_action_invocations;
@@ -0,0 +1,10 @@
+CREATE TABLE rfc003_action_invocations
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id         NOT NULL,
+    action          NOT NULL,
+    parameters      NOT NULL,
+    payload         NOT NULL,
+    payload_hash    NOT NULL,
+    invoked_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
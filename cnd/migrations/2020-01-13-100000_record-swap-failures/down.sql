@@ -0,0 +1 @@
+DROP TABLE swap_failures;
@@ -0,0 +1,8 @@
+CREATE TABLE swap_failures
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE  NOT NULL,
+    category        NOT NULL,
+    message         NOT NULL,
+    failed_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
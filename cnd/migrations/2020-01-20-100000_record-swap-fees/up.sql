@@ -0,0 +1,9 @@
+CREATE TABLE swap_fees
+(
+    id INTEGER              NOT NULL PRIMARY KEY,
+    swap_id                 NOT NULL,
+    ledger                  NOT NULL,
+    transaction_id UNIQUE   NOT NULL,
+    amount                  NOT NULL,
+    recorded_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,9 @@
+CREATE TABLE auto_refunds
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE  NOT NULL,
+    address         NOT NULL,
+    fee_per_wu      NOT NULL,
+    transaction_id,
+    broadcast_at DATETIME
+);
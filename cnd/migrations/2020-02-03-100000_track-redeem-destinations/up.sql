@@ -0,0 +1,7 @@
+CREATE TABLE redeem_destinations
+(
+    id INTEGER                      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE                  NOT NULL,
+    derivation_index INTEGER UNIQUE NOT NULL,
+    used BOOLEAN                    NOT NULL DEFAULT 0
+);
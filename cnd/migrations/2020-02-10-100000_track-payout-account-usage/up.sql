@@ -0,0 +1,7 @@
+CREATE TABLE payout_account_usages
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE  NOT NULL,
+    account         NOT NULL,
+    used_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
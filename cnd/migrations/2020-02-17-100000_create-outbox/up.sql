@@ -0,0 +1,9 @@
+CREATE TABLE rfc003_outbox
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE  NOT NULL,
+    peer_id         NOT NULL,
+    address_hint,
+    enqueued_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    sent_at DATETIME
+);
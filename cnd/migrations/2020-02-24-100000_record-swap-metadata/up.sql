@@ -0,0 +1,8 @@
+CREATE TABLE swap_metadata
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE  NOT NULL,
+    external_id,
+    note
+);
+CREATE INDEX swap_metadata_external_id ON swap_metadata (external_id);
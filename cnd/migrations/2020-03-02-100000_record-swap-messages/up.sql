@@ -0,0 +1,10 @@
+CREATE TABLE swap_messages
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id         NOT NULL,
+    direction       NOT NULL,
+    frame           NOT NULL,
+    size            NOT NULL,
+    recorded_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX swap_messages_swap_id ON swap_messages (swap_id);
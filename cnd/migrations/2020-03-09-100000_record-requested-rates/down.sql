@@ -0,0 +1 @@
+DROP TABLE requested_rates;
@@ -0,0 +1,6 @@
+CREATE TABLE requested_rates
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE  NOT NULL,
+    rate            NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE issued_identities
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id         NOT NULL,
+    identity        NOT NULL
+);
+CREATE INDEX issued_identities_identity ON issued_identities (identity);
@@ -0,0 +1,13 @@
+CREATE TABLE rfc003_outbox_without_attempts
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id UNIQUE  NOT NULL,
+    peer_id         NOT NULL,
+    address_hint,
+    enqueued_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    sent_at DATETIME
+);
+INSERT INTO rfc003_outbox_without_attempts
+    SELECT id, swap_id, peer_id, address_hint, enqueued_at, sent_at FROM rfc003_outbox;
+DROP TABLE rfc003_outbox;
+ALTER TABLE rfc003_outbox_without_attempts RENAME TO rfc003_outbox;
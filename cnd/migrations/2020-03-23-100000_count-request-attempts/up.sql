@@ -0,0 +1 @@
+ALTER TABLE rfc003_outbox ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+DROP TABLE constructed_transactions;
@@ -0,0 +1,9 @@
+CREATE TABLE constructed_transactions
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id         NOT NULL,
+    txid            NOT NULL,
+    hex             NOT NULL,
+    constructed_at DATETIME NOT NULL
+);
+CREATE INDEX constructed_transactions_swap_id ON constructed_transactions (swap_id);
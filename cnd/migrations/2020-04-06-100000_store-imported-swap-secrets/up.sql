@@ -0,0 +1,6 @@
+CREATE TABLE imported_swap_secrets
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    swap_id         UNIQUE NOT NULL,
+    sealed_secrets  NOT NULL
+);
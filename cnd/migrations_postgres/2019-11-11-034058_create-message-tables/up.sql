@@ -0,0 +1,99 @@
+CREATE TABLE rfc003_bitcoin_ethereum_bitcoin_ether_request_messages
+(
+    id SERIAL                PRIMARY KEY,
+    swap_id                  TEXT NOT NULL UNIQUE,
+    bitcoin_network          TEXT NOT NULL,
+    ethereum_chain_id        BIGINT NOT NULL,
+    bitcoin_amount           TEXT NOT NULL,
+    ether_amount             TEXT NOT NULL,
+    hash_function            TEXT NOT NULL,
+    bitcoin_refund_identity  TEXT NOT NULL,
+    ethereum_redeem_identity TEXT NOT NULL,
+    bitcoin_expiry           BIGINT NOT NULL,
+    ethereum_expiry          BIGINT NOT NULL,
+    secret_hash              TEXT NOT NULL
+);
+
+CREATE TABLE rfc003_ethereum_bitcoin_ether_bitcoin_request_messages
+(
+    id SERIAL                PRIMARY KEY,
+    swap_id                  TEXT NOT NULL UNIQUE,
+    bitcoin_network          TEXT NOT NULL,
+    ethereum_chain_id        BIGINT NOT NULL,
+    bitcoin_amount           TEXT NOT NULL,
+    ether_amount             TEXT NOT NULL,
+    hash_function            TEXT NOT NULL,
+    bitcoin_redeem_identity  TEXT NOT NULL,
+    ethereum_refund_identity TEXT NOT NULL,
+    bitcoin_expiry           BIGINT NOT NULL,
+    ethereum_expiry          BIGINT NOT NULL,
+    secret_hash              TEXT NOT NULL
+);
+
+CREATE TABLE rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages
+(
+    id SERIAL                PRIMARY KEY,
+    swap_id                  TEXT NOT NULL UNIQUE,
+    bitcoin_network          TEXT NOT NULL,
+    ethereum_chain_id        BIGINT NOT NULL,
+    bitcoin_amount           TEXT NOT NULL,
+    erc20_amount             TEXT NOT NULL,
+    erc20_token_contract     TEXT NOT NULL,
+    hash_function            TEXT NOT NULL,
+    bitcoin_refund_identity  TEXT NOT NULL,
+    ethereum_redeem_identity TEXT NOT NULL,
+    bitcoin_expiry           BIGINT NOT NULL,
+    ethereum_expiry          BIGINT NOT NULL,
+    secret_hash              TEXT NOT NULL
+);
+
+CREATE TABLE rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages
+(
+    id SERIAL                PRIMARY KEY,
+    swap_id                  TEXT NOT NULL UNIQUE,
+    bitcoin_network          TEXT NOT NULL,
+    ethereum_chain_id        BIGINT NOT NULL,
+    bitcoin_amount           TEXT NOT NULL,
+    erc20_amount             TEXT NOT NULL,
+    erc20_token_contract     TEXT NOT NULL,
+    hash_function            TEXT NOT NULL,
+    bitcoin_redeem_identity  TEXT NOT NULL,
+    ethereum_refund_identity TEXT NOT NULL,
+    bitcoin_expiry           BIGINT NOT NULL,
+    ethereum_expiry          BIGINT NOT NULL,
+    secret_hash              TEXT NOT NULL
+);
+
+-- Timestamps are stored in UTC like SQLite's CURRENT_TIMESTAMP does.
+CREATE TABLE rfc003_ethereum_bitcoin_accept_messages
+(
+    id SERIAL                PRIMARY KEY,
+    swap_id                  TEXT NOT NULL UNIQUE,
+    bitcoin_refund_identity  TEXT NOT NULL,
+    ethereum_redeem_identity TEXT NOT NULL,
+    at TIMESTAMP             NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE rfc003_bitcoin_ethereum_accept_messages
+(
+    id SERIAL                PRIMARY KEY,
+    swap_id                  TEXT NOT NULL UNIQUE,
+    bitcoin_redeem_identity  TEXT NOT NULL,
+    ethereum_refund_identity TEXT NOT NULL,
+    at TIMESTAMP             NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+
+CREATE TABLE rfc003_decline_messages
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    reason          TEXT
+);
+
+CREATE TABLE rfc003_swaps
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    role            TEXT NOT NULL,
+    counterparty    TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE rfc003_finished_swaps
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    finished_at     TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    archived_at     TIMESTAMP
+);
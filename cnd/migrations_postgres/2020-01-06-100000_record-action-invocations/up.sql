@@ -0,0 +1,10 @@
+CREATE TABLE rfc003_action_invocations
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL,
+    action          TEXT NOT NULL,
+    parameters      TEXT NOT NULL,
+    payload         TEXT NOT NULL,
+    payload_hash    TEXT NOT NULL,
+    invoked_at      TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
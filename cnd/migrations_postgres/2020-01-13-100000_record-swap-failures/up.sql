@@ -0,0 +1,8 @@
+CREATE TABLE swap_failures
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    category        TEXT NOT NULL,
+    message         TEXT NOT NULL,
+    failed_at       TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
@@ -0,0 +1,9 @@
+CREATE TABLE swap_fees
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL,
+    ledger          TEXT NOT NULL,
+    transaction_id  TEXT NOT NULL UNIQUE,
+    amount          TEXT NOT NULL,
+    recorded_at     TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
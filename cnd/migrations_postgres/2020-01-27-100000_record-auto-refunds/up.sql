@@ -0,0 +1,9 @@
+CREATE TABLE auto_refunds
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    address         TEXT NOT NULL,
+    fee_per_wu      TEXT NOT NULL,
+    transaction_id  TEXT,
+    broadcast_at    TIMESTAMP
+);
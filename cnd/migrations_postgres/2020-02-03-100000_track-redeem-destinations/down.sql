@@ -0,0 +1 @@
+DROP TABLE redeem_destinations;
@@ -0,0 +1,7 @@
+CREATE TABLE redeem_destinations
+(
+    id SERIAL           PRIMARY KEY,
+    swap_id             TEXT NOT NULL UNIQUE,
+    derivation_index    INTEGER NOT NULL UNIQUE,
+    used                BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1 @@
+DROP TABLE payout_account_usages;
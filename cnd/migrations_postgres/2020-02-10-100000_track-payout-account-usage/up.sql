@@ -0,0 +1,7 @@
+CREATE TABLE payout_account_usages
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    account         TEXT NOT NULL,
+    used_at         TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
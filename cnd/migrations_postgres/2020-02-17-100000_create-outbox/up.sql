@@ -0,0 +1,9 @@
+CREATE TABLE rfc003_outbox
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    peer_id         TEXT NOT NULL,
+    address_hint    TEXT,
+    enqueued_at     TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+    sent_at         TIMESTAMP
+);
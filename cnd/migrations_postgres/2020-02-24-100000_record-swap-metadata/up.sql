@@ -0,0 +1,8 @@
+CREATE TABLE swap_metadata
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    external_id     TEXT,
+    note            TEXT
+);
+CREATE INDEX swap_metadata_external_id ON swap_metadata (external_id);
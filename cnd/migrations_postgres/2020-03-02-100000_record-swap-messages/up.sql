@@ -0,0 +1,10 @@
+CREATE TABLE swap_messages
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL,
+    direction       TEXT NOT NULL,
+    frame           TEXT NOT NULL,
+    size            BIGINT NOT NULL,
+    recorded_at     TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc')
+);
+CREATE INDEX swap_messages_swap_id ON swap_messages (swap_id);
@@ -0,0 +1,6 @@
+CREATE TABLE requested_rates
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL UNIQUE,
+    rate            TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE issued_identities
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL,
+    identity        TEXT NOT NULL
+);
+CREATE INDEX issued_identities_identity ON issued_identities (identity);
@@ -0,0 +1 @@
+ALTER TABLE rfc003_outbox DROP COLUMN attempts;
@@ -0,0 +1,9 @@
+CREATE TABLE constructed_transactions
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT NOT NULL,
+    txid            TEXT NOT NULL,
+    hex             TEXT NOT NULL,
+    constructed_at  TIMESTAMP NOT NULL
+);
+CREATE INDEX constructed_transactions_swap_id ON constructed_transactions (swap_id);
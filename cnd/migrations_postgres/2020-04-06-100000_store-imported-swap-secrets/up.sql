@@ -0,0 +1,6 @@
+CREATE TABLE imported_swap_secrets
+(
+    id SERIAL       PRIMARY KEY,
+    swap_id         TEXT UNIQUE NOT NULL,
+    sealed_secrets  TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE node_lease
+(
+    id INTEGER      PRIMARY KEY,
+    holder          TEXT NOT NULL,
+    expires_at      TIMESTAMP NOT NULL
+);
+INSERT INTO node_lease (id, holder, expires_at) VALUES (1, '', '1970-01-01 00:00:00');
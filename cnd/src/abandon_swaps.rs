@@ -0,0 +1,142 @@
+#![allow(clippy::type_repetition_in_bounds)]
+use crate::{
+    db::{DetermineTypes, LoadAcceptedSwap, Retention, Retrieve, SwapFailures},
+    ethereum::{Erc20Token, EtherQuantity},
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{
+            self, bob,
+            state_machine::{Error as ErrorState, SwapStates},
+            state_store::StateStore,
+            Ledger, LedgerState, SwapCommunication,
+        },
+        Role, SwapEvent, SwapEvents, SwapId, SwapTasks,
+    },
+};
+use chrono::NaiveDateTime;
+use futures_core::compat::Future01CompatExt;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// As Bob, abandon accepted swaps whose alpha ledger was not funded within
+/// `funding_window` after accepting, checking once per `CHECK_INTERVAL`.
+///
+/// Abandoned swaps fail with `rfc003::Error::Abandoned`, their watchers are
+/// cancelled and they are marked as finished. Alice can still refund once her
+/// HTLC expired if she funds it after all.
+pub async fn abandon_unfunded_swaps_periodically<D>(
+    dependencies: D,
+    funding_window: chrono::Duration,
+) where
+    D: Retrieve
+        + Retention
+        + SwapFailures
+        + SwapTasks
+        + SwapEvents
+        + StateStore
+        + DetermineTypes
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    loop {
+        let deadline = chrono::Utc::now().naive_utc() - funding_window;
+
+        match Retrieve::all(&dependencies).await {
+            Ok(swaps) => {
+                for swap in swaps.into_iter().filter(|swap| swap.role == Role::Bob) {
+                    if let Err(e) = abandon_if_unfunded(&dependencies, swap.swap_id, deadline).await
+                    {
+                        log::error!("failed to check funding of swap {}: {:?}", swap.swap_id, e);
+                    }
+                }
+            }
+            Err(e) => log::error!("failed to load swaps: {:?}", e),
+        }
+
+        Delay::new(Instant::now() + CHECK_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
+
+async fn abandon_if_unfunded<D>(
+    dependencies: &D,
+    swap_id: SwapId,
+    deadline: NaiveDateTime,
+) -> anyhow::Result<()>
+where
+    D: Retention
+        + SwapFailures
+        + SwapTasks
+        + SwapEvents
+        + StateStore
+        + DetermineTypes
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let types = DetermineTypes::determine_types(dependencies, &swap_id).await?;
+
+    with_swap_types!(types, {
+        let state = match StateStore::get::<bob::State<AL, BL, AA, BA>>(dependencies, &swap_id)? {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        if !is_waiting_for_funding(&state) {
+            return Ok(());
+        }
+
+        let (_, _, accepted_at) =
+            LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(dependencies, &swap_id).await?;
+        if accepted_at > deadline {
+            return Ok(());
+        }
+
+        log::info!(
+            "abandoning swap {} because its alpha ledger was not funded in time",
+            swap_id
+        );
+
+        StateStore::update::<bob::State<AL, BL, AA, BA>>(
+            dependencies,
+            &swap_id,
+            SwapStates::Error(ErrorState(rfc003::Error::Abandoned)),
+        );
+    });
+
+    SwapTasks::cancel_swap_tasks(dependencies, &swap_id);
+    SwapFailures::record_swap_failure(dependencies, &swap_id, &rfc003::Error::Abandoned).await?;
+    Retention::mark_finished(dependencies, &swap_id).await?;
+    SwapEvents::publish(dependencies, SwapEvent::Failed {
+        swap_id,
+        reason: rfc003::Error::Abandoned.to_string(),
+    });
+
+    Ok(())
+}
+
+fn is_waiting_for_funding<AL, BL, AA, BA>(state: &bob::State<AL, BL, AA, BA>) -> bool
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+{
+    let accepted = match state.swap_communication {
+        SwapCommunication::Accepted { .. } => true,
+        _ => false,
+    };
+    let funded = match state.alpha_ledger_state {
+        LedgerState::NotDeployed | LedgerState::Deployed { .. } => false,
+        _ => true,
+    };
+
+    accepted && !funded && state.error.is_none()
+}
@@ -0,0 +1,267 @@
+use crate::{
+    bitcoind_rpc::BitcoindRpc,
+    db::{AssetKind, AutoRefund, AutoRefunds, DetermineTypes, LedgerKind},
+    ethereum::{Erc20Token, EtherQuantity},
+    notification::Notifications,
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{
+            actions::RefundAction, alice, state_machine::HtlcParams, state_store::StateStore,
+            LedgerState, SwapCommunication,
+        },
+        Role, SwapId,
+    },
+    timestamp::Timestamp,
+    webhook::Event,
+};
+use futures_core::compat::Future01CompatExt;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// What became of a swap that is to be refunded automatically.
+#[derive(Debug)]
+enum Outcome {
+    /// The HTLC is not funded or did not expire yet.
+    Waiting,
+    /// The HTLC was redeemed or refunded.
+    Settled,
+    /// The swap is not one of those the refund can be signed for. Such refunds
+    /// are rejected when the swap is posted, hence this is only reached for
+    /// refunds registered before that.
+    Unsupported,
+    Broadcast(bitcoin::Transaction),
+}
+
+/// Refund the bitcoin of swaps that asked for it once their HTLC expired
+/// without the counterparty redeeming it, checking once per `CHECK_INTERVAL`.
+///
+/// A HTLC counts as expired once the median time of the last blocks passed
+/// its expiry, as that is what the refund is checked against by the network.
+/// Refunds that bitcoind rejects are retried on the next check.
+pub async fn refund_expired_swaps_periodically<D>(
+    dependencies: D,
+    bitcoind: BitcoindRpc,
+    notifications: Notifications,
+) where
+    D: AutoRefunds + DetermineTypes + StateStore,
+{
+    loop {
+        match (
+            AutoRefunds::pending_auto_refunds(&dependencies).await,
+            bitcoind.median_time_past().await,
+        ) {
+            (Ok(pending), Ok(median_time_past)) => {
+                for (swap_id, refund) in pending {
+                    refund_if_expired(
+                        &dependencies,
+                        &bitcoind,
+                        &notifications,
+                        median_time_past,
+                        swap_id,
+                        refund,
+                    )
+                    .await
+                }
+            }
+            (Err(e), _) => log::error!("failed to load pending auto refunds: {:?}", e),
+            (_, Err(e)) => log::warn!(
+                "failed to get the median time past from bitcoind, retrying later: {:?}",
+                e
+            ),
+        }
+
+        Delay::new(Instant::now() + CHECK_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
+
+async fn refund_if_expired<D>(
+    dependencies: &D,
+    bitcoind: &BitcoindRpc,
+    notifications: &Notifications,
+    median_time_past: Timestamp,
+    swap_id: SwapId,
+    refund: AutoRefund,
+) where
+    D: AutoRefunds + DetermineTypes + StateStore,
+{
+    let outcome = match sign_refund(dependencies, swap_id, &refund, median_time_past).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            log::error!("failed to sign auto refund of swap {}: {:?}", swap_id, e);
+            let reason = format!("{:#}", e);
+            notifications
+                .send(Event::AutoRefundFailed { swap_id, reason })
+                .await;
+            return;
+        }
+    };
+
+    match outcome {
+        Outcome::Waiting => {}
+        Outcome::Settled => {
+            if let Err(e) = AutoRefunds::cancel_auto_refund(dependencies, &swap_id).await {
+                log::error!("failed to cancel auto refund of swap {}: {:?}", swap_id, e);
+            }
+        }
+        Outcome::Unsupported => {
+            log::error!("swap {} cannot be refunded automatically", swap_id);
+            notifications
+                .send(Event::AutoRefundFailed {
+                    swap_id,
+                    reason: String::from(
+                        "only the bitcoin Alice funded can be refunded automatically",
+                    ),
+                })
+                .await;
+
+            if let Err(e) = AutoRefunds::cancel_auto_refund(dependencies, &swap_id).await {
+                log::error!("failed to cancel auto refund of swap {}: {:?}", swap_id, e);
+            }
+        }
+        Outcome::Broadcast(transaction) => {
+            let transaction_id = match bitcoind.send_raw_transaction(&transaction).await {
+                Ok(transaction_id) => transaction_id,
+                Err(e) => {
+                    log::warn!(
+                        "bitcoind did not accept the auto refund of swap {}, retrying later: {:?}",
+                        swap_id,
+                        e
+                    );
+                    return;
+                }
+            };
+
+            log::warn!(
+                "counterparty did not redeem swap {} before it expired, broadcast refund transaction {} to {}",
+                swap_id,
+                transaction_id,
+                refund.address
+            );
+
+            let now = chrono::Utc::now().naive_utc();
+            if let Err(e) = AutoRefunds::record_auto_refund_broadcast(
+                dependencies,
+                &swap_id,
+                &transaction_id,
+                now,
+            )
+            .await
+            {
+                log::error!("failed to record auto refund of swap {}: {:?}", swap_id, e);
+            }
+
+            notifications
+                .send(Event::AutoRefundBroadcast {
+                    swap_id,
+                    transaction_id: transaction_id.to_string(),
+                })
+                .await;
+        }
+    }
+}
+
+async fn sign_refund<D>(
+    dependencies: &D,
+    swap_id: SwapId,
+    refund: &AutoRefund,
+    median_time_past: Timestamp,
+) -> anyhow::Result<Outcome>
+where
+    D: DetermineTypes + StateStore,
+{
+    let types = DetermineTypes::determine_types(dependencies, &swap_id).await?;
+
+    match (
+        types.role,
+        types.alpha_ledger,
+        types.beta_ledger,
+        types.alpha_asset,
+        types.beta_asset,
+    ) {
+        (
+            Role::Alice,
+            LedgerKind::Bitcoin,
+            LedgerKind::Ethereum,
+            AssetKind::Bitcoin,
+            AssetKind::Ether,
+        ) => {
+            let state = StateStore::get::<
+                alice::State<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>,
+            >(dependencies, &swap_id)?;
+            Ok(state.map_or(Outcome::Waiting, |state| {
+                outcome(state, refund, median_time_past)
+            }))
+        }
+        (
+            Role::Alice,
+            LedgerKind::Bitcoin,
+            LedgerKind::Ethereum,
+            AssetKind::Bitcoin,
+            AssetKind::Erc20,
+        ) => {
+            let state = StateStore::get::<
+                alice::State<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>,
+            >(dependencies, &swap_id)?;
+            Ok(state.map_or(Outcome::Waiting, |state| {
+                outcome(state, refund, median_time_past)
+            }))
+        }
+        _ => Ok(Outcome::Unsupported),
+    }
+}
+
+fn outcome<BA: Asset>(
+    state: alice::State<Bitcoin, Ethereum, bitcoin::Amount, BA>,
+    refund: &AutoRefund,
+    median_time_past: Timestamp,
+) -> Outcome {
+    let (request, response) = match state.swap_communication {
+        SwapCommunication::Accepted { request, response } => (request, response),
+        _ => return Outcome::Waiting,
+    };
+
+    let (htlc_location, fund_transaction, top_ups) = match state.alpha_ledger_state {
+        LedgerState::Funded {
+            htlc_location,
+            fund_transaction,
+            top_ups,
+            ..
+        } => (htlc_location, fund_transaction, top_ups),
+        LedgerState::Redeemed { .. } | LedgerState::Refunded { .. } => return Outcome::Settled,
+        _ => return Outcome::Waiting,
+    };
+
+    if median_time_past <= request.alpha_expiry {
+        return Outcome::Waiting;
+    }
+
+    let action = <(Bitcoin, bitcoin::Amount)>::refund_action(
+        HtlcParams::new_alpha_params(&request, &response),
+        htlc_location,
+        &*state.secret_source,
+        &fund_transaction,
+        &top_ups,
+    );
+
+    match action
+        .spend_to(refund.address.clone())
+        .sign_with_rate(&*crate::SECP, refund.fee_per_wu)
+    {
+        Ok(transaction) => Outcome::Broadcast(transaction),
+        Err(e) => {
+            log::error!(
+                "cannot refund swap {} at {} per WU: {:?}",
+                request.swap_id,
+                refund.fee_per_wu,
+                e
+            );
+            Outcome::Waiting
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    db,
+    seed::{self, Seed, SEED_LENGTH},
+};
+use async_trait::async_trait;
+use crypto::{
+    aead::{AeadDecryptor, AeadEncryptor},
+    chacha20poly1305::ChaCha20Poly1305,
+    hmac::Hmac,
+    pbkdf2::pbkdf2,
+    sha2::Sha256,
+};
+use rand::Rng;
+use std::{ffi::OsStr, fmt, fs, io, path::Path};
+
+const MAGIC: &[u8] = b"CNDBACKUP";
+const VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 8;
+const TAG_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+/// Produce a snapshot of everything needed to bring a node back to its
+/// current state: the seed and the database.
+#[async_trait]
+pub trait Backup: Send + Sync + 'static {
+    async fn backup(&self) -> anyhow::Result<Archive>;
+}
+
+/// The unencrypted contents of a backup.
+#[derive(Clone, PartialEq)]
+pub struct Archive {
+    seed: Seed,
+    database: Vec<u8>,
+}
+
+impl fmt::Debug for Archive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Archive")
+            .field("seed", &self.seed)
+            .field("database", &format!("{} bytes", self.database.len()))
+            .finish()
+    }
+}
+
+impl Archive {
+    pub fn new(seed: Seed, database: Vec<u8>) -> Self {
+        Self { seed, database }
+    }
+
+    /// Encrypt the archive with a key derived from `passphrase`.
+    ///
+    /// Layout: MAGIC || VERSION || salt || nonce || ciphertext || tag, where
+    /// the plaintext is the seed followed by the raw SQLite database file.
+    pub fn encrypt<R: Rng>(&self, passphrase: &str, mut rng: R) -> Result<Vec<u8>, Error> {
+        let mut salt = [0u8; SALT_LENGTH];
+        rng.try_fill(&mut salt[..])?;
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rng.try_fill(&mut nonce[..])?;
+
+        let key = derive_key(passphrase, &salt);
+
+        let mut plaintext = Vec::with_capacity(SEED_LENGTH + self.database.len());
+        plaintext.extend_from_slice(&self.seed.bytes());
+        plaintext.extend_from_slice(&self.database);
+
+        let mut ciphertext = vec![0u8; plaintext.len()];
+        let mut tag = [0u8; TAG_LENGTH];
+        ChaCha20Poly1305::new(&key, &nonce, MAGIC).encrypt(&plaintext, &mut ciphertext, &mut tag);
+
+        let mut encrypted = Vec::with_capacity(
+            MAGIC.len() + 1 + SALT_LENGTH + NONCE_LENGTH + ciphertext.len() + TAG_LENGTH,
+        );
+        encrypted.extend_from_slice(MAGIC);
+        encrypted.push(VERSION);
+        encrypted.extend_from_slice(&salt);
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+        encrypted.extend_from_slice(&tag);
+
+        Ok(encrypted)
+    }
+
+    pub fn decrypt(encrypted: &[u8], passphrase: &str) -> Result<Self, Error> {
+        let header_length = MAGIC.len() + 1 + SALT_LENGTH + NONCE_LENGTH;
+
+        if encrypted.len() < header_length + SEED_LENGTH + TAG_LENGTH
+            || !encrypted.starts_with(MAGIC)
+        {
+            return Err(Error::NotABackup);
+        }
+
+        let version = encrypted[MAGIC.len()];
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let salt = &encrypted[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LENGTH];
+        let nonce = &encrypted[MAGIC.len() + 1 + SALT_LENGTH..header_length];
+        let (ciphertext, tag) =
+            encrypted[header_length..].split_at(encrypted.len() - header_length - TAG_LENGTH);
+
+        let key = derive_key(passphrase, salt);
+
+        let mut plaintext = vec![0u8; ciphertext.len()];
+        if !ChaCha20Poly1305::new(&key, nonce, MAGIC).decrypt(ciphertext, &mut plaintext, tag) {
+            return Err(Error::Decryption);
+        }
+
+        let mut seed = [0u8; SEED_LENGTH];
+        seed.copy_from_slice(&plaintext[..SEED_LENGTH]);
+
+        Ok(Self {
+            seed: Seed::from(seed),
+            database: plaintext[SEED_LENGTH..].to_vec(),
+        })
+    }
+
+    /// Write the seed and the database into `data_dir`.
+    ///
+    /// Refuses to overwrite an existing seed or database unless `force` is
+    /// set.
+    pub fn restore_in_dir<D: AsRef<OsStr>>(&self, data_dir: D, force: bool) -> Result<(), Error> {
+        let dir = Path::new(&data_dir);
+        let seed_path = seed::seed_path_from_dir(dir);
+        let db_path = db::db_path_from_dir(dir);
+
+        if !force {
+            for path in &[&seed_path, &db_path] {
+                if path.exists() {
+                    return Err(Error::AlreadyExists(path.display().to_string()));
+                }
+            }
+        }
+
+        self.seed.write_to_dir(dir)?;
+        fs::write(&db_path, &self.database)?;
+
+        Ok(())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; KEY_LENGTH] {
+    let mut mac = Hmac::new(Sha256::new(), passphrase.as_bytes());
+    let mut key = [0u8; KEY_LENGTH];
+    pbkdf2(&mut mac, salt, PBKDF2_ROUNDS, &mut key);
+
+    key
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("file is not a cnd backup")]
+    NotABackup,
+    #[error("backup format version {0} is not supported")]
+    UnsupportedVersion(u8),
+    #[error("failed to decrypt backup, wrong passphrase or corrupted file")]
+    Decryption,
+    #[error("refusing to overwrite existing file {0}")]
+    AlreadyExists(String),
+    #[error("seed: {0}")]
+    Seed(#[from] seed::Error),
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+    #[error("RNG: {0}")]
+    Rand(#[from] rand::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use spectral::prelude::*;
+
+    fn archive() -> Archive {
+        Archive::new(
+            Seed::from(*b"hello world, you are beautiful!!"),
+            b"SQLite format 3\0 and some pages".to_vec(),
+        )
+    }
+
+    #[test]
+    fn encrypted_archive_roundtrips() {
+        let archive = archive();
+
+        let encrypted = archive.encrypt("correct horse", OsRng).unwrap();
+        let decrypted = Archive::decrypt(&encrypted, "correct horse");
+
+        assert_that(&decrypted).is_ok_containing(archive);
+    }
+
+    #[test]
+    fn decrypting_with_wrong_passphrase_fails() {
+        let encrypted = archive().encrypt("correct horse", OsRng).unwrap();
+
+        let decrypted = Archive::decrypt(&encrypted, "battery staple");
+
+        match decrypted {
+            Err(Error::Decryption) => {}
+            other => panic!("expected decryption error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn restore_refuses_to_overwrite_existing_seed() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = archive();
+
+        archive.restore_in_dir(dir.path(), false).unwrap();
+
+        assert_that(&archive.restore_in_dir(dir.path(), false)).is_err();
+        assert_that(&archive.restore_in_dir(dir.path(), true)).is_ok();
+    }
+}
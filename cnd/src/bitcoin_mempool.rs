@@ -0,0 +1,533 @@
+use crate::{
+    bitcoind_rpc::BitcoindRpc,
+    db::{DetermineTypes, Retention, Retrieve, SwapTypes},
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{
+            state::Actor, state_machine::HtlcParams, state_store::StateStore, ActorState,
+            LedgerState, State, SwapCommunication,
+        },
+        SwapEvent, SwapId,
+    },
+};
+use bitcoin::{hashes::sha256d, Amount, OutPoint, Script, Transaction};
+use futures::sync::mpsc;
+use futures_core::{
+    compat::{Future01CompatExt, Stream01CompatExt},
+    future,
+    stream::StreamExt,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often the watched swaps are read from the database again, to pick up
+/// swaps that were imported or resumed without an event being published.
+const RESYNC_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// How many transactions are fetched from the mempool per check. The rest is
+/// left for the following checks, such that a full mempool does not flood
+/// bitcoind with requests.
+const MAX_LOOKUPS_PER_CHECK: usize = 500;
+
+/// The Bitcoin HTLC of a swap, no matter if Bitcoin is its alpha or its beta
+/// ledger.
+trait BitcoinLedger: ActorState {
+    /// `None` unless the swap was accepted.
+    fn bitcoin_htlc_params(&self) -> Option<HtlcParams<Bitcoin, Amount>>;
+    fn bitcoin_ledger(&self) -> &LedgerState<Bitcoin>;
+    fn bitcoin_ledger_mut(&mut self) -> &mut LedgerState<Bitcoin>;
+}
+
+impl<BA: Asset, R: Actor> BitcoinLedger for State<Bitcoin, Ethereum, Amount, BA, R> {
+    fn bitcoin_htlc_params(&self) -> Option<HtlcParams<Bitcoin, Amount>> {
+        match &self.swap_communication {
+            SwapCommunication::Accepted { request, response } => {
+                Some(HtlcParams::new_alpha_params(request, response))
+            }
+            _ => None,
+        }
+    }
+
+    fn bitcoin_ledger(&self) -> &LedgerState<Bitcoin> {
+        &self.alpha_ledger_state
+    }
+
+    fn bitcoin_ledger_mut(&mut self) -> &mut LedgerState<Bitcoin> {
+        &mut self.alpha_ledger_state
+    }
+}
+
+impl<AA: Asset, R: Actor> BitcoinLedger for State<Ethereum, Bitcoin, AA, Amount, R> {
+    fn bitcoin_htlc_params(&self) -> Option<HtlcParams<Bitcoin, Amount>> {
+        match &self.swap_communication {
+            SwapCommunication::Accepted { request, response } => {
+                Some(HtlcParams::new_beta_params(request, response))
+            }
+            _ => None,
+        }
+    }
+
+    fn bitcoin_ledger(&self) -> &LedgerState<Bitcoin> {
+        &self.beta_ledger_state
+    }
+
+    fn bitcoin_ledger_mut(&mut self) -> &mut LedgerState<Bitcoin> {
+        &mut self.beta_ledger_state
+    }
+}
+
+/// A Bitcoin HTLC whose fund transaction was not seen yet.
+#[derive(Debug)]
+struct Unfunded {
+    swap_id: SwapId,
+    types: SwapTypes,
+    amount: Amount,
+}
+
+/// The types of the swaps whose Bitcoin HTLC may still be funded, such that
+/// the database is not read on every check.
+///
+/// Swaps are added once they are accepted and removed once they are over.
+type WatchedSwaps = Mutex<HashMap<SwapId, SwapTypes>>;
+
+/// Look for the fund transactions of Bitcoin HTLCs in the mempool of the
+/// node once per `CHECK_INTERVAL` and follow their confirmations.
+///
+/// An HTLC whose fund transaction is in the mempool is `FundedUnconfirmed`
+/// until the transaction has `required_confirmations`, the actions that
+/// depend on the HTLC being funded are only offered afterwards. Fund
+/// transactions that pay a different amount than agreed on are left to the
+/// swap, which reports them as incorrectly funded once they are mined.
+pub async fn watch_mempool_periodically<D>(
+    dependencies: D,
+    bitcoind: BitcoindRpc,
+    required_confirmations: u32,
+    events: mpsc::UnboundedReceiver<SwapEvent>,
+) where
+    D: Retrieve + Retention + DetermineTypes + StateStore,
+{
+    let watched = WatchedSwaps::default();
+
+    future::join(
+        track_swaps(&dependencies, &watched, events),
+        check_periodically(&dependencies, &bitcoind, required_confirmations, &watched),
+    )
+    .await;
+}
+
+async fn track_swaps<D>(
+    dependencies: &D,
+    watched: &WatchedSwaps,
+    events: mpsc::UnboundedReceiver<SwapEvent>,
+) where
+    D: DetermineTypes,
+{
+    let mut events = events.compat();
+
+    while let Some(Ok(event)) = events.next().await {
+        match event {
+            SwapEvent::Accepted { swap_id } => {
+                match DetermineTypes::determine_types(dependencies, &swap_id).await {
+                    Ok(types) => {
+                        watched.lock().expect("poisoned").insert(swap_id, types);
+                    }
+                    Err(e) => log::warn!("failed to watch swap {}: {:?}", swap_id, e),
+                }
+            }
+            SwapEvent::Finished { swap_id } | SwapEvent::Failed { swap_id, .. } => {
+                watched.lock().expect("poisoned").remove(&swap_id);
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn check_periodically<D>(
+    dependencies: &D,
+    bitcoind: &BitcoindRpc,
+    required_confirmations: u32,
+    watched: &WatchedSwaps,
+) where
+    D: Retrieve + Retention + DetermineTypes + StateStore,
+{
+    // Transactions in the mempool that were already looked at.
+    let mut seen = HashSet::new();
+    let mut synced_at: Option<Instant> = None;
+
+    loop {
+        if synced_at.map_or(true, |synced_at| synced_at.elapsed() >= RESYNC_INTERVAL) {
+            match resync(dependencies, watched).await {
+                Ok(()) => synced_at = Some(Instant::now()),
+                Err(e) => log::warn!("failed to read the swaps to watch: {:?}", e),
+            }
+        }
+
+        if let Err(e) = check(
+            dependencies,
+            bitcoind,
+            required_confirmations,
+            watched,
+            &mut seen,
+        )
+        .await
+        {
+            log::warn!("failed to check the mempool: {:?}", e);
+        }
+
+        Delay::new(Instant::now() + CHECK_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
+
+/// Replace the watched swaps with the ones in the database that are not over,
+/// the types of swaps that are watched already are not read again.
+async fn resync<D>(dependencies: &D, watched: &WatchedSwaps) -> anyhow::Result<()>
+where
+    D: Retrieve + Retention + DetermineTypes,
+{
+    let mut swaps = HashMap::new();
+    for swap in Retrieve::all(dependencies).await? {
+        let swap_id = swap.swap_id;
+        if Retention::finished_at(dependencies, &swap_id)
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+
+        let known = watched.lock().expect("poisoned").get(&swap_id).copied();
+        let types = match known {
+            Some(types) => types,
+            None => DetermineTypes::determine_types(dependencies, &swap_id).await?,
+        };
+        swaps.insert(swap_id, types);
+    }
+
+    *watched.lock().expect("poisoned") = swaps;
+
+    Ok(())
+}
+
+async fn check<D>(
+    dependencies: &D,
+    bitcoind: &BitcoindRpc,
+    required_confirmations: u32,
+    watched: &WatchedSwaps,
+    seen: &mut HashSet<sha256d::Hash>,
+) -> anyhow::Result<()>
+where
+    D: StateStore,
+{
+    let swaps = watched
+        .lock()
+        .expect("poisoned")
+        .iter()
+        .map(|(swap_id, types)| (*swap_id, *types))
+        .collect::<Vec<_>>();
+
+    let mut unfunded = HashMap::<Script, Vec<Unfunded>>::new();
+    for (swap_id, types) in swaps {
+        match watch(
+            dependencies,
+            bitcoind,
+            required_confirmations,
+            swap_id,
+            types,
+        )
+        .await
+        {
+            Ok(Some((script_pubkey, htlc))) => {
+                unfunded.entry(script_pubkey).or_default().push(htlc)
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("failed to watch swap {}: {:?}", swap_id, e),
+        }
+    }
+
+    if unfunded.is_empty() {
+        return Ok(());
+    }
+
+    let mempool = bitcoind
+        .raw_mempool()
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    seen.retain(|txid| mempool.contains(txid));
+
+    let unseen = mempool
+        .into_iter()
+        .filter(|txid| !seen.contains(txid))
+        .take(MAX_LOOKUPS_PER_CHECK)
+        .collect::<Vec<_>>();
+
+    for txid in unseen {
+        seen.insert(txid);
+
+        // The transaction may have been mined or evicted in the meantime.
+        let transaction = match bitcoind.raw_transaction(&txid).await {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                log::debug!(
+                    "failed to get transaction {} from the mempool: {:?}",
+                    txid,
+                    e
+                );
+                continue;
+            }
+        };
+
+        for (htlc, vout) in funded_htlcs(&transaction, &unfunded) {
+            log::info!(
+                "fund transaction {} of swap {} is in the mempool",
+                txid,
+                htlc.swap_id
+            );
+            mark_unconfirmed(dependencies, htlc, &transaction, OutPoint { txid, vout });
+        }
+    }
+
+    Ok(())
+}
+
+/// The HTLCs `transaction` pays the agreed on amount to, along with the index
+/// of the output that does.
+fn funded_htlcs<'a>(
+    transaction: &Transaction,
+    unfunded: &'a HashMap<Script, Vec<Unfunded>>,
+) -> Vec<(&'a Unfunded, u32)> {
+    transaction
+        .output
+        .iter()
+        .enumerate()
+        .filter_map(|(vout, output)| {
+            // Bitcoin limits the number of outputs to u32.
+            #[allow(clippy::cast_possible_truncation)]
+            let vout = vout as u32;
+
+            unfunded
+                .get(&output.script_pubkey)
+                .map(|htlcs| (vout, output, htlcs))
+        })
+        .flat_map(|(vout, output, htlcs)| {
+            htlcs
+                .iter()
+                .filter(move |htlc| htlc.amount.as_sat() == output.value)
+                .map(move |htlc| (htlc, vout))
+        })
+        .collect()
+}
+
+/// Update the confirmations of the Bitcoin HTLC of the swap and return it
+/// along with the script_pubkey it is funded to if its fund transaction is
+/// still to be looked for.
+async fn watch<D>(
+    dependencies: &D,
+    bitcoind: &BitcoindRpc,
+    required_confirmations: u32,
+    swap_id: SwapId,
+    types: SwapTypes,
+) -> anyhow::Result<Option<(Script, Unfunded)>>
+where
+    D: StateStore,
+{
+    with_swap_types!(types, {
+        let state = match StateStore::get::<ROLE>(dependencies, &swap_id)? {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+
+        match state.bitcoin_ledger() {
+            LedgerState::NotDeployed => Ok(state.bitcoin_htlc_params().map(|htlc_params| {
+                (htlc_params.compute_address().script_pubkey(), Unfunded {
+                    swap_id,
+                    types,
+                    amount: htlc_params.asset,
+                })
+            })),
+            LedgerState::FundedUnconfirmed { htlc_location, .. } => {
+                let htlc_location = *htlc_location;
+                let confirmations = bitcoind.confirmations(&htlc_location).await?;
+
+                StateStore::modify::<ROLE, _>(dependencies, &swap_id, |state| {
+                    update_confirmations(
+                        state.bitcoin_ledger_mut(),
+                        htlc_location,
+                        confirmations,
+                        required_confirmations,
+                    )
+                });
+
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    })
+}
+
+fn mark_unconfirmed<D>(
+    dependencies: &D,
+    htlc: &Unfunded,
+    transaction: &Transaction,
+    htlc_location: OutPoint,
+) where
+    D: StateStore,
+{
+    with_swap_types!(htlc.types, {
+        StateStore::modify::<ROLE, _>(dependencies, &htlc.swap_id, |state| {
+            // The swap may have seen the transaction in a block meanwhile.
+            let ledger = state.bitcoin_ledger_mut();
+            if let LedgerState::NotDeployed = ledger {
+                *ledger = LedgerState::FundedUnconfirmed {
+                    htlc_location,
+                    deploy_transaction: transaction.clone(),
+                    fund_transaction: transaction.clone(),
+                    confirmations: 0,
+                };
+            }
+        })
+    });
+}
+
+fn update_confirmations(
+    ledger: &mut LedgerState<Bitcoin>,
+    checked_htlc_location: OutPoint,
+    confirmations: Option<u32>,
+    required_confirmations: u32,
+) {
+    let updated = match ledger {
+        LedgerState::FundedUnconfirmed {
+            htlc_location,
+            deploy_transaction,
+            fund_transaction,
+            confirmations: known_confirmations,
+        } if *htlc_location == checked_htlc_location => match confirmations {
+            Some(confirmations) if confirmations >= required_confirmations => LedgerState::Funded {
+                htlc_location: *htlc_location,
+                deploy_transaction: deploy_transaction.clone(),
+                fund_transaction: fund_transaction.clone(),
+                top_ups: vec![],
+            },
+            Some(confirmations) => LedgerState::FundedUnconfirmed {
+                htlc_location: *htlc_location,
+                deploy_transaction: deploy_transaction.clone(),
+                fund_transaction: fund_transaction.clone(),
+                confirmations,
+            },
+            // The transaction was dropped from the mempool.
+            None if *known_confirmations == 0 => LedgerState::NotDeployed,
+            // The HTLC was spent, the swap sees the redeem or refund.
+            None => return,
+        },
+        _ => return,
+    };
+
+    *ledger = updated;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{AssetKind, LedgerKind},
+        swap_protocols::Role,
+    };
+    use bitcoin::TxOut;
+    use spectral::prelude::*;
+
+    fn unconfirmed(confirmations: u32) -> LedgerState<Bitcoin> {
+        let transaction = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: Vec::new(),
+            output: Vec::new(),
+        };
+
+        LedgerState::FundedUnconfirmed {
+            htlc_location: OutPoint::default(),
+            deploy_transaction: transaction.clone(),
+            fund_transaction: transaction,
+            confirmations,
+        }
+    }
+
+    #[test]
+    fn htlc_is_funded_once_the_required_confirmations_are_reached() {
+        let mut ledger = unconfirmed(0);
+
+        update_confirmations(&mut ledger, OutPoint::default(), Some(2), 3);
+        assert_that(&ledger).is_equal_to(unconfirmed(2));
+
+        update_confirmations(&mut ledger, OutPoint::default(), Some(3), 3);
+        assert_that(&ledger).matches(|ledger| match ledger {
+            LedgerState::Funded { .. } => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn dropped_fund_transaction_is_forgotten() {
+        let mut ledger = unconfirmed(0);
+
+        update_confirmations(&mut ledger, OutPoint::default(), None, 1);
+
+        assert_that(&ledger).is_equal_to(LedgerState::NotDeployed);
+    }
+
+    #[test]
+    fn outputs_are_matched_by_script_pubkey_and_amount() {
+        let htlc = |swap_id, sats| Unfunded {
+            swap_id,
+            types: SwapTypes {
+                alpha_ledger: LedgerKind::Bitcoin,
+                beta_ledger: LedgerKind::Ethereum,
+                alpha_asset: AssetKind::Bitcoin,
+                beta_asset: AssetKind::Ether,
+                role: Role::Alice,
+            },
+            amount: Amount::from_sat(sats),
+        };
+        let (first, second, third) = (SwapId::default(), SwapId::default(), SwapId::default());
+        let (script, other_script) = (Script::from(vec![0x51]), Script::from(vec![0x52]));
+
+        let mut unfunded = HashMap::new();
+        unfunded.insert(script.clone(), vec![htlc(first, 1000), htlc(second, 2000)]);
+        unfunded.insert(other_script.clone(), vec![htlc(third, 3000)]);
+
+        let transaction = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: Vec::new(),
+            output: vec![
+                TxOut {
+                    value: 2000,
+                    script_pubkey: other_script,
+                },
+                TxOut {
+                    value: 1000,
+                    script_pubkey: script.clone(),
+                },
+                TxOut {
+                    value: 2000,
+                    script_pubkey: script,
+                },
+            ],
+        };
+
+        let funded = funded_htlcs(&transaction, &unfunded)
+            .into_iter()
+            .map(|(htlc, vout)| (htlc.swap_id, vout))
+            .collect::<Vec<_>>();
+
+        assert_that(&funded).is_equal_to(vec![(first, 1), (second, 2)]);
+    }
+}
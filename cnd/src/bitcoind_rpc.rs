@@ -0,0 +1,210 @@
+use crate::{
+    btsieve::{bitcoin::decode_response, socks5_client},
+    config::RpcCredentials,
+    timestamp::Timestamp,
+};
+use bitcoin::{hashes::sha256d, Address, Amount, OutPoint, Transaction};
+use futures::Future;
+use futures_core::compat::Future01CompatExt;
+use reqwest::{r#async::Client, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::net::SocketAddr;
+
+#[derive(Deserialize)]
+struct RpcResponse<R> {
+    result: Option<R>,
+    error: Option<RpcError>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, thiserror::Error)]
+#[error("bitcoind returned error {code}: {message}")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("reqwest: ")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("rpc: ")]
+    Rpc(#[from] RpcError),
+    #[error("bitcoind returned neither a result nor an error")]
+    MissingResult,
+    #[error("transaction: ")]
+    Transaction(#[from] crate::btsieve::bitcoin::Error),
+}
+
+#[derive(Deserialize)]
+struct TxOut {
+    confirmations: u32,
+}
+
+#[derive(Deserialize)]
+struct BlockchainInfo {
+    mediantime: u32,
+}
+
+/// Talks to the JSON-RPC interface of bitcoind for everything the REST
+/// interface used by btsieve does not offer, i.e. broadcasting transactions
+/// and looking into the mempool.
+#[derive(Clone, Debug)]
+pub struct BitcoindRpc {
+    url: Url,
+    credentials: RpcCredentials,
+    client: Client,
+}
+
+impl BitcoindRpc {
+    pub fn new(url: Url, credentials: RpcCredentials) -> Self {
+        Self {
+            url,
+            credentials,
+            client: Client::new(),
+        }
+    }
+
+    /// Send all requests to the node through the given SOCKS5 proxy.
+    pub fn with_socks5_proxy(self, proxy: SocketAddr) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: socks5_client(proxy)?,
+            ..self
+        })
+    }
+
+    pub async fn send_raw_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<sha256d::Hash, Error> {
+        self.call(
+            "sendrawtransaction",
+            serde_json::json!([bitcoin::consensus::encode::serialize_hex(transaction)]),
+        )
+        .await
+    }
+
+    /// Pay `amount` to `address` from the wallet of the node.
+    pub async fn send_to_address(
+        &self,
+        address: &Address,
+        amount: Amount,
+    ) -> Result<sha256d::Hash, Error> {
+        self.call(
+            "sendtoaddress",
+            serde_json::json!([address.to_string(), amount.as_btc()]),
+        )
+        .await
+    }
+
+    /// The ids of all transactions in the mempool of the node.
+    pub async fn raw_mempool(&self) -> Result<Vec<sha256d::Hash>, Error> {
+        self.call("getrawmempool", serde_json::json!([])).await
+    }
+
+    /// Only finds transactions in the mempool unless the node keeps a
+    /// transaction index.
+    pub async fn raw_transaction(&self, txid: &sha256d::Hash) -> Result<Transaction, Error> {
+        let hex: String = self
+            .call("getrawtransaction", serde_json::json!([txid.to_string()]))
+            .await?;
+
+        Ok(decode_response(hex)?)
+    }
+
+    /// The number of confirmations of the transaction that created `outpoint`,
+    /// `0` while it is in the mempool. `None` if the output was spent or the
+    /// transaction is unknown, e.g. because it was dropped from the mempool.
+    pub async fn confirmations(&self, outpoint: &OutPoint) -> Result<Option<u32>, Error> {
+        let tx_out = self
+            .call::<TxOut>(
+                "gettxout",
+                serde_json::json!([outpoint.txid.to_string(), outpoint.vout, true]),
+            )
+            .await;
+
+        match tx_out {
+            Ok(tx_out) => Ok(Some(tx_out.confirmations)),
+            Err(Error::MissingResult) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The median time of the last 11 blocks, which is what the expiry of a
+    /// HTLC is checked against when its refund is included in a block.
+    pub async fn median_time_past(&self) -> Result<Timestamp, Error> {
+        let info = self
+            .call::<BlockchainInfo>("getblockchaininfo", serde_json::json!([]))
+            .await?;
+
+        Ok(Timestamp::from(info.mediantime))
+    }
+
+    /// Mine `blocks` blocks to a new address of the wallet of the node, only
+    /// works on regtest.
+    pub async fn generate(&self, blocks: u32) -> Result<Vec<sha256d::Hash>, Error> {
+        let address: String = self.call("getnewaddress", serde_json::json!([])).await?;
+
+        self.call("generatetoaddress", serde_json::json!([blocks, address]))
+            .await
+    }
+
+    async fn call<R: DeserializeOwned + Send + 'static>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<R, Error> {
+        // bitcoind answers failed calls with an error status but still puts
+        // the error into the body.
+        let response = self
+            .client
+            .post(self.url.clone())
+            .basic_auth(&self.credentials.username, Some(&self.credentials.password))
+            .json(&serde_json::json!({
+                "jsonrpc": "1.0",
+                "id": "cnd",
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .and_then(|mut response| response.json::<RpcResponse<R>>())
+            .compat()
+            .await
+            .map_err(|e| {
+                log::error!("Error when calling {} on bitcoind", method);
+                Error::Reqwest(e)
+            })?;
+
+        match response {
+            RpcResponse {
+                error: Some(error), ..
+            } => Err(Error::Rpc(error)),
+            RpcResponse {
+                result: Some(result),
+                ..
+            } => Ok(result),
+            _ => Err(Error::MissingResult),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn rejected_transaction_deserializes_into_error() {
+        let response =
+            r#"{"result":null,"error":{"code":-26,"message":"non-final (code 64)"},"id":"cnd"}"#;
+
+        let response = serde_json::from_str::<RpcResponse<sha256d::Hash>>(response).unwrap();
+
+        assert_that(&response.result).is_none();
+        assert_that(&response.error)
+            .is_some()
+            .is_equal_to(RpcError {
+                code: -26,
+                message: String::from("non-final (code 64)"),
+            });
+    }
+}
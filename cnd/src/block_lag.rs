@@ -0,0 +1,296 @@
+use crate::{
+    btsieve::{bitcoin::BitcoinConnector, ethereum::Web3Connector, LatestBlock},
+    db::LedgerKind,
+    timestamp::Timestamp,
+};
+use bitcoin::BitcoinHash;
+use futures_core::compat::Future01CompatExt;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+/// Used if `block_lag.bitcoin_max_lag_seconds` is not configured.
+pub const DEFAULT_BITCOIN_MAX_LAG_SECONDS: u32 = 2 * 60 * 60;
+/// Used if `block_lag.ethereum_max_lag_seconds` is not configured.
+pub const DEFAULT_ETHEREUM_MAX_LAG_SECONDS: u32 = 5 * 60;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The latest block of a ledger as it was last fetched from its connector.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tip {
+    pub hash: String,
+    /// Unknown for Bitcoin blocks whose coinbase does not commit to it.
+    pub height: Option<u64>,
+    pub block_time: Timestamp,
+}
+
+/// How far behind a ledger is, as shown with the swaps on it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct LedgerLag {
+    #[serde(serialize_with = "serialize_ledger")]
+    pub ledger: LedgerKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    pub lag_seconds: u32,
+}
+
+// This is the API serde expects, can't do much about the trivial copy :(
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serialize_ledger<S: serde::Serializer>(
+    ledger: &LedgerKind,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&ledger.to_string().to_lowercase())
+}
+
+#[derive(Clone, Debug)]
+struct Freshness {
+    tip: Tip,
+    /// When the tip was first seen, a node that stalled keeps returning the
+    /// same tip.
+    advanced_at: Timestamp,
+}
+
+impl Freshness {
+    /// The lag is measured from the older of the block time and the time the
+    /// tip was first seen, such that a Bitcoin block timestamped ahead of time
+    /// does not hide a stalled node.
+    fn lag_seconds(&self, now: Timestamp) -> u32 {
+        let since = std::cmp::min(u32::from(self.tip.block_time), u32::from(self.advanced_at));
+
+        u32::from(now).saturating_sub(since)
+    }
+}
+
+/// Keeps track of how fresh the latest block of each ledger is. If a node
+/// stalls or is cut off from the network, the swaps on its ledger silently
+/// make no progress, hence they are annotated with the lag once it exceeds
+/// the threshold of the ledger.
+///
+/// Not persisted, the ledgers are measured again after a restart.
+#[derive(Clone, Debug)]
+pub struct BlockLagMonitor {
+    bitcoin_max_lag_seconds: u32,
+    ethereum_max_lag_seconds: u32,
+    ledgers: Arc<Mutex<HashMap<LedgerKind, Freshness>>>,
+}
+
+impl BlockLagMonitor {
+    pub fn new(bitcoin_max_lag_seconds: u32, ethereum_max_lag_seconds: u32) -> Self {
+        Self {
+            bitcoin_max_lag_seconds,
+            ethereum_max_lag_seconds,
+            ledgers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn max_lag_seconds(&self, ledger: LedgerKind) -> u32 {
+        match ledger {
+            LedgerKind::Bitcoin => self.bitcoin_max_lag_seconds,
+            LedgerKind::Ethereum => self.ethereum_max_lag_seconds,
+        }
+    }
+
+    /// Replaces the previous tip of the same ledger, the tip counts as
+    /// advanced if its hash changed.
+    pub fn record(&self, ledger: LedgerKind, tip: Tip, now: Timestamp) {
+        let mut ledgers = self.ledgers.lock().unwrap();
+
+        let advanced_at = match ledgers.get(&ledger) {
+            Some(previous) if previous.tip.hash == tip.hash => previous.advanced_at,
+            _ => now,
+        };
+        let freshness = Freshness { tip, advanced_at };
+
+        let lag_seconds = freshness.lag_seconds(now);
+        if lag_seconds > self.max_lag_seconds(ledger) {
+            log::warn!(
+                "the latest {} block is {} seconds old, check whether the node is synced and connected, swaps on {} make no progress until it is",
+                ledger,
+                lag_seconds,
+                ledger
+            );
+        }
+
+        ledgers.insert(ledger, freshness);
+    }
+
+    /// The lag of every ledger that was measured, for the metrics.
+    pub fn lags(&self) -> Vec<LedgerLag> {
+        self.lags_at(Timestamp::now())
+    }
+
+    fn lags_at(&self, now: Timestamp) -> Vec<LedgerLag> {
+        let mut lags = self
+            .ledgers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(ledger, freshness)| LedgerLag {
+                ledger: *ledger,
+                height: freshness.tip.height,
+                lag_seconds: freshness.lag_seconds(now),
+            })
+            .collect::<Vec<_>>();
+        lags.sort_by_key(|lag| lag.ledger as u8);
+
+        lags
+    }
+
+    /// The lags of `ledgers` that exceed their threshold, empty if the data of
+    /// all of them is fresh.
+    pub fn stale_ledgers(&self, ledgers: &[LedgerKind]) -> Vec<LedgerLag> {
+        self.stale_ledgers_at(ledgers, Timestamp::now())
+    }
+
+    fn stale_ledgers_at(&self, ledgers: &[LedgerKind], now: Timestamp) -> Vec<LedgerLag> {
+        self.lags_at(now)
+            .into_iter()
+            .filter(|lag| ledgers.contains(&lag.ledger))
+            .filter(|lag| lag.lag_seconds > self.max_lag_seconds(lag.ledger))
+            .collect()
+    }
+}
+
+/// Fetch the latest block of each ledger once per `CHECK_INTERVAL`.
+pub async fn watch_ledgers_periodically(
+    bitcoin_connector: BitcoinConnector,
+    ethereum_connector: Web3Connector,
+    monitor: BlockLagMonitor,
+) {
+    loop {
+        match latest_bitcoin_tip(bitcoin_connector.clone()).await {
+            Ok(tip) => monitor.record(LedgerKind::Bitcoin, tip, Timestamp::now()),
+            Err(e) => log::warn!("failed to fetch the latest Bitcoin block: {:?}", e),
+        }
+
+        match latest_ethereum_tip(ethereum_connector.clone()).await {
+            Ok(tip) => monitor.record(LedgerKind::Ethereum, tip, Timestamp::now()),
+            Err(e) => log::warn!("failed to fetch the latest Ethereum block: {:?}", e),
+        }
+
+        Delay::new(Instant::now() + CHECK_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
+
+async fn latest_bitcoin_tip(mut connector: BitcoinConnector) -> anyhow::Result<Tip> {
+    let block = connector.latest_block().await?;
+
+    Ok(Tip {
+        hash: block.bitcoin_hash().to_string(),
+        height: bip34_height(&block),
+        block_time: Timestamp::from(block.header.time),
+    })
+}
+
+async fn latest_ethereum_tip(mut connector: Web3Connector) -> anyhow::Result<Tip> {
+    let block = connector
+        .latest_block()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("the Ethereum node did not return the latest block"))?;
+    let hash = block
+        .hash
+        .ok_or_else(|| anyhow::anyhow!("the latest Ethereum block is still pending"))?;
+
+    Ok(Tip {
+        hash: format!("{:x}", hash),
+        height: block.number.map(|number| number.as_u64()),
+        block_time: Timestamp::from(block.timestamp.low_u32()),
+    })
+}
+
+/// The height the coinbase of the block commits to, as the first push of its
+/// input script.
+fn bip34_height(block: &bitcoin::Block) -> Option<u64> {
+    let script = block.txdata.first()?.input.first()?.script_sig.as_bytes();
+    let (&opcode, bytes) = script.split_first()?;
+
+    match opcode {
+        // OP_0 and OP_1 to OP_16, used for the first blocks on regtest
+        0x00 => Some(0),
+        0x51..=0x60 => Some(u64::from(opcode - 0x50)),
+        1..=8 if bytes.len() >= usize::from(opcode) => Some(
+            bytes[..usize::from(opcode)]
+                .iter()
+                .rev()
+                .fold(0, |height, byte| height << 8 | u64::from(*byte)),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn tip(hash: &str, block_time: u32) -> Tip {
+        Tip {
+            hash: String::from(hash),
+            height: Some(100),
+            block_time: Timestamp::from(block_time),
+        }
+    }
+
+    #[test]
+    fn a_tip_that_does_not_advance_becomes_stale() {
+        let monitor = BlockLagMonitor::new(7200, 300);
+        let ethereum = [LedgerKind::Ethereum];
+
+        monitor.record(
+            LedgerKind::Ethereum,
+            tip("a", 1_000_000),
+            Timestamp::from(1_000_010),
+        );
+        assert_that(&monitor.stale_ledgers_at(&ethereum, Timestamp::from(1_000_200))).is_empty();
+
+        monitor.record(
+            LedgerKind::Ethereum,
+            tip("a", 1_000_000),
+            Timestamp::from(1_000_400),
+        );
+        assert_that(&monitor.stale_ledgers_at(&ethereum, Timestamp::from(1_000_400))).is_equal_to(
+            vec![LedgerLag {
+                ledger: LedgerKind::Ethereum,
+                height: Some(100),
+                lag_seconds: 400,
+            }],
+        );
+
+        monitor.record(
+            LedgerKind::Ethereum,
+            tip("b", 1_000_410),
+            Timestamp::from(1_000_420),
+        );
+        assert_that(&monitor.stale_ledgers_at(&ethereum, Timestamp::from(1_000_420))).is_empty();
+    }
+
+    #[test]
+    fn a_block_timestamped_ahead_does_not_hide_a_stalled_node() {
+        let monitor = BlockLagMonitor::new(7200, 300);
+        let bitcoin = [LedgerKind::Bitcoin];
+
+        monitor.record(
+            LedgerKind::Bitcoin,
+            tip("a", 1_010_000),
+            Timestamp::from(1_000_000),
+        );
+        monitor.record(
+            LedgerKind::Bitcoin,
+            tip("a", 1_010_000),
+            Timestamp::from(1_008_000),
+        );
+
+        assert_that(&monitor.stale_ledgers_at(&bitcoin, Timestamp::from(1_008_000))).has_length(1);
+        assert_that(&monitor.stale_ledgers_at(&[LedgerKind::Ethereum], Timestamp::from(1_008_000)))
+            .is_empty();
+    }
+}
@@ -2,9 +2,14 @@
 #![forbid(unsafe_code)]
 
 pub mod bitcoin;
+mod concurrency_limit;
 pub mod ethereum;
 
-use tokio::prelude::{Future, Stream};
+pub use self::concurrency_limit::{ConcurrencyLimit, Permit, DEFAULT_MAX_CONCURRENT_REQUESTS};
+
+use async_trait::async_trait;
+use futures_core::stream::BoxStream;
+use std::net::SocketAddr;
 
 pub trait MatchingTransactions<P>: Send + Sync + 'static {
     type Transaction;
@@ -13,37 +18,91 @@ pub trait MatchingTransactions<P>: Send + Sync + 'static {
         &self,
         pattern: P,
         timestamp: Option<u32>,
-    ) -> Box<dyn Stream<Item = Self::Transaction, Error = ()> + Send>;
+    ) -> BoxStream<'static, Self::Transaction>;
+}
+
+/// Look through the blocks mined since `timestamp` for a transaction matching
+/// a pattern.
+///
+/// Unlike `MatchingTransactions` this does not wait for new blocks, hence it
+/// resolves with `None` if there is no such transaction yet.
+#[async_trait]
+pub trait PastTransactions<P>: Send + Sync + 'static {
+    type Transaction;
+
+    async fn past_transaction(
+        &self,
+        pattern: P,
+        timestamp: u32,
+    ) -> anyhow::Result<Option<Self::Transaction>>;
 }
 
+#[async_trait]
 pub trait LatestBlock: Send + Sync + 'static {
     type Error: std::fmt::Debug;
     type Block;
     type BlockHash;
 
-    fn latest_block(
-        &mut self,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static>;
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error>;
 }
 
+#[async_trait]
 pub trait BlockByHash: Send + Sync + 'static {
     type Error: std::fmt::Debug;
     type Block;
     type BlockHash;
 
-    fn block_by_hash(
-        &self,
-        block_hash: Self::BlockHash,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static>;
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error>;
 }
 
+#[async_trait]
 pub trait ReceiptByHash: Send + Sync + 'static {
-    type Receipt;
-    type TransactionHash;
+    type Receipt: Send;
+    type TransactionHash: Send;
+    type Error: std::fmt::Debug;
+
+    async fn receipt_by_hash(
+        &self,
+        transaction_hash: Self::TransactionHash,
+    ) -> Result<Self::Receipt, Self::Error>;
+
+    /// Fetch the receipts of all given transactions, in the same order.
+    ///
+    /// Connectors that can fetch several receipts in a single round trip
+    /// should override this, by default the receipts are fetched one by one.
+    async fn receipts_by_hashes(
+        &self,
+        transaction_hashes: Vec<Self::TransactionHash>,
+    ) -> Result<Vec<Self::Receipt>, Self::Error> {
+        let mut receipts = Vec::with_capacity(transaction_hashes.len());
+
+        for transaction_hash in transaction_hashes {
+            receipts.push(self.receipt_by_hash(transaction_hash).await?);
+        }
+
+        Ok(receipts)
+    }
+}
+
+/// Prove that a transaction is in a block, in a way that can be checked by
+/// anyone who knows the block but does not trust the source of the proof.
+#[async_trait]
+pub trait InclusionProof: Send + Sync + 'static {
+    type Proof: Send;
+    type TransactionHash: Send;
     type Error: std::fmt::Debug;
 
-    fn receipt_by_hash(
+    /// `None` if the transaction is not in a block yet.
+    async fn inclusion_proof(
         &self,
         transaction_hash: Self::TransactionHash,
-    ) -> Box<dyn Future<Item = Self::Receipt, Error = Self::Error> + Send + 'static>;
+    ) -> Result<Option<Self::Proof>, Self::Error>;
+}
+
+/// Build an HTTP client that sends all requests through the given SOCKS5
+/// proxy. Host names are resolved by the proxy so that they do not leak.
+pub fn socks5_client(proxy: SocketAddr) -> Result<reqwest::r#async::Client, reqwest::Error> {
+    reqwest::r#async::Client::builder()
+        .proxy(reqwest::Proxy::all(&format!("socks5h://{}", proxy))?)
+        .build()
 }
@@ -1,21 +1,42 @@
-use crate::btsieve::{
-    bitcoin::bitcoin_http_request_for_hex_encoded_object, BlockByHash, LatestBlock,
+use crate::{
+    btsieve::{
+        bitcoin::{
+            bitcoin_http_request_for_hex_encoded_object, FilteredBlocks, TransactionPattern,
+            TransactionProof, VerboseBlock,
+        },
+        socks5_client, BlockByHash, ConcurrencyLimit, InclusionProof, LatestBlock,
+    },
+    chaos::Chaos,
 };
+use async_trait::async_trait;
 use bitcoin::{hashes::sha256d, Network};
+use futures::Future;
+use futures_core::compat::Future01CompatExt;
 use reqwest::{r#async::Client, Url};
 use serde::Deserialize;
-use tokio::prelude::Future;
+use std::net::SocketAddr;
 
 #[derive(Deserialize)]
 struct ChainInfo {
     bestblockhash: sha256d::Hash,
 }
 
+/// Only transactions of the mempool are known to the node unless it keeps an
+/// index of all transactions (`-txindex`).
+#[derive(Deserialize)]
+struct TransactionInfo {
+    blockhash: Option<sha256d::Hash>,
+}
+
 #[derive(Clone, Debug)]
 pub struct BitcoindConnector {
     chaininfo_url: Url,
     raw_block_by_hash_url: Url,
+    transaction_url: Url,
     client: Client,
+    concurrency_limit: ConcurrencyLimit,
+    chaos: Chaos,
+    verbose_blocks: bool,
 }
 
 impl BitcoindConnector {
@@ -23,67 +44,186 @@ impl BitcoindConnector {
         Ok(Self {
             chaininfo_url: base_url.join("rest/chaininfo.json")?,
             raw_block_by_hash_url: base_url.join("rest/block/")?,
+            transaction_url: base_url.join("rest/tx/")?,
             client: Client::new(),
+            concurrency_limit: ConcurrencyLimit::default(),
+            chaos: Chaos::default(),
+            verbose_blocks: false,
+        })
+    }
+
+    pub fn with_concurrency_limit(self, concurrency_limit: ConcurrencyLimit) -> Self {
+        Self {
+            concurrency_limit,
+            ..self
+        }
+    }
+
+    pub fn with_chaos(self, chaos: Chaos) -> Self {
+        Self { chaos, ..self }
+    }
+
+    /// Send all requests to the node through the given SOCKS5 proxy.
+    pub fn with_socks5_proxy(self, proxy: SocketAddr) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: socks5_client(proxy)?,
+            ..self
         })
     }
 
+    /// Fetch verbose blocks when looking for transactions to an address so
+    /// that only the transactions paying to it need to be decoded.
+    pub fn with_verbose_blocks(self, verbose_blocks: bool) -> Self {
+        Self {
+            verbose_blocks,
+            ..self
+        }
+    }
+
     fn raw_block_by_hash_url(&self, block_hash: &sha256d::Hash) -> Url {
         self.raw_block_by_hash_url
             .join(&format!("{}.hex", block_hash))
             .expect("building url should work")
     }
+
+    fn verbose_block_by_hash_url(&self, block_hash: &sha256d::Hash) -> Url {
+        self.raw_block_by_hash_url
+            .join(&format!("{}.json", block_hash))
+            .expect("building url should work")
+    }
+
+    fn transaction_url(&self, txid: &sha256d::Hash) -> Url {
+        self.transaction_url
+            .join(&format!("{}.json", txid))
+            .expect("building url should work")
+    }
+
+    async fn best_block_hash(&self) -> Result<sha256d::Hash, crate::btsieve::bitcoin::Error> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let chain_info = self
+            .client
+            .get(self.chaininfo_url.clone())
+            .send()
+            .and_then(|mut response| response.json::<ChainInfo>())
+            .compat()
+            .await
+            .map_err(|e| {
+                log::error!("Error when fetching the chain info from bitcoind");
+                crate::btsieve::bitcoin::Error::Reqwest(e)
+            })?;
+
+        Ok(chain_info.bestblockhash)
+    }
 }
 
+#[async_trait]
 impl LatestBlock for BitcoindConnector {
     type Error = crate::btsieve::bitcoin::Error;
     type Block = bitcoin::Block;
     type BlockHash = sha256d::Hash;
 
-    fn latest_block(
-        &mut self,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static> {
-        let latest_block_hash = self
-            .client
-            .get(self.chaininfo_url.clone())
-            .send()
-            .map_err(|e| {
-                log::error!("Error when sending request to bitcoind");
-                Self::Error::Reqwest(e)
-            })
-            .and_then(move |mut response| {
-                response.json::<ChainInfo>().map_err(|e| {
-                    log::error!("Error when deserialising the response from bitcoind");
-                    Self::Error::Reqwest(e)
-                })
-            })
-            .map(move |blockchain_info| blockchain_info.bestblockhash);
-
-        let cloned_self = self.clone();
-
-        Box::new(
-            latest_block_hash
-                .and_then(move |latest_block_hash| cloned_self.block_by_hash(latest_block_hash)),
-        )
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error> {
+        let block_hash = self.best_block_hash().await?;
+
+        self.block_by_hash(block_hash).await
     }
 }
 
+#[async_trait]
 impl BlockByHash for BitcoindConnector {
     type Error = crate::btsieve::bitcoin::Error;
     type Block = bitcoin::Block;
     type BlockHash = sha256d::Hash;
 
-    fn block_by_hash(
-        &self,
-        block_hash: Self::BlockHash,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static> {
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
         let url = self.raw_block_by_hash_url(&block_hash);
 
         let block =
-            bitcoin_http_request_for_hex_encoded_object::<Self::Block>(url, self.client.clone());
+            bitcoin_http_request_for_hex_encoded_object::<Self::Block>(url, self.client.clone())
+                .await?;
+
+        log::trace!("Fetched block from bitcoind: {:?}", block);
+
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl FilteredBlocks for BitcoindConnector {
+    async fn latest_filtered_block(
+        &mut self,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        let block_hash = self.best_block_hash().await?;
+
+        self.filtered_block_by_hash(block_hash, pattern).await
+    }
+
+    async fn filtered_block_by_hash(
+        &self,
+        block_hash: sha256d::Hash,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        // Only transactions to an address can be selected without decoding them.
+        let to_address = match (self.verbose_blocks, &pattern.to_address) {
+            (true, Some(to_address)) => to_address,
+            _ => return self.block_by_hash(block_hash).await,
+        };
+
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+        let url = self.verbose_block_by_hash_url(&block_hash);
 
-        Box::new(block.inspect(|block| {
-            log::trace!("Fetched block from bitcoind: {:?}", block);
-        }))
+        let verbose_block = self
+            .client
+            .get(url)
+            .send()
+            .and_then(|mut response| response.json::<VerboseBlock>())
+            .compat()
+            .await?;
+
+        let block = verbose_block.into_block_paying_to(&to_address.script_pubkey())?;
+
+        log::trace!("Fetched verbose block from bitcoind: {:?}", block);
+
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl InclusionProof for BitcoindConnector {
+    type Proof = TransactionProof;
+    type TransactionHash = sha256d::Hash;
+    type Error = crate::btsieve::bitcoin::Error;
+
+    async fn inclusion_proof(
+        &self,
+        txid: sha256d::Hash,
+    ) -> Result<Option<TransactionProof>, Self::Error> {
+        let transaction_info = {
+            let _permit = self.concurrency_limit.acquire().await;
+            self.chaos.before_request().await?;
+
+            self.client
+                .get(self.transaction_url(&txid))
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|mut response| response.json::<TransactionInfo>())
+                .compat()
+                .await?
+        };
+        let block_hash = match transaction_info.blockhash {
+            Some(block_hash) => block_hash,
+            None => return Ok(None),
+        };
+
+        let block = self.block_by_hash(block_hash).await?;
+
+        Ok(TransactionProof::from_block(&block, txid))
     }
 }
 
@@ -142,6 +282,12 @@ mod tests {
                 .unwrap();
             let raw_block_by_hash_url = blocksource.raw_block_by_hash_url(&block_id);
             assert_eq!(raw_block_by_hash_url, Url::parse("http://localhost:8080/rest/block/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02.hex").unwrap());
+
+            let verbose_block_by_hash_url = blocksource.verbose_block_by_hash_url(&block_id);
+            assert_eq!(verbose_block_by_hash_url, Url::parse("http://localhost:8080/rest/block/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02.json").unwrap());
+
+            let transaction_url = blocksource.transaction_url(&block_id);
+            assert_eq!(transaction_url, Url::parse("http://localhost:8080/rest/tx/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02.json").unwrap());
         }
     }
 }
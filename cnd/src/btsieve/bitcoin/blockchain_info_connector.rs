@@ -1,19 +1,76 @@
 use crate::btsieve::{
-    bitcoin::bitcoin_http_request_for_hex_encoded_object, BlockByHash, LatestBlock,
+    bitcoin::{decode_response, FilteredBlocks},
+    socks5_client, BlockByHash, LatestBlock,
 };
+use async_trait::async_trait;
 use bitcoin::{hashes::sha256d, Network};
-use reqwest::{r#async::Client, Url};
+use futures_core::compat::Future01CompatExt;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED, RETRY_AFTER,
+    },
+    r#async::{Client, Response},
+    StatusCode, Url,
+};
 use serde::Deserialize;
-use tokio::prelude::Future;
+use std::{
+    cmp,
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+/// How many blocks are kept in memory, blocks never change hence a cached
+/// block is only dropped to make room for a newer one.
+const BLOCK_CACHE_SIZE: usize = 144;
+
+/// How long to wait after the first `429 Too Many Requests` if the response
+/// does not say, the wait doubles with every further one.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
 
 #[derive(Deserialize)]
 struct BlockchainInfoLatestBlock {
     hash: sha256d::Hash,
 }
 
-#[derive(Clone, Debug)]
+/// Fetches blocks from the public API of blockchain.info.
+///
+/// The API is rate limited, hence fetched blocks are cached, the latest block
+/// is only re-fetched once the caching headers of the previous response say
+/// it is stale and requests are held back after a `429 Too Many Requests`.
+/// Clones share the cache and the backoff.
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
 pub struct BlockchainInfoConnector {
     client: Client,
+    #[derivative(Debug = "ignore")]
+    cache: Arc<Mutex<Cache>>,
+}
+
+#[derive(Default)]
+struct Cache {
+    blocks: HashMap<sha256d::Hash, bitcoin::Block>,
+    insertion_order: VecDeque<sha256d::Hash>,
+    latest_block: Option<CachedLatestBlock>,
+    backoff: Backoff,
+}
+
+#[derive(Clone, Debug)]
+struct CachedLatestBlock {
+    hash: sha256d::Hash,
+    fresh_until: Option<Instant>,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Backoff {
+    until: Option<Instant>,
+    next: Duration,
 }
 
 impl BlockchainInfoConnector {
@@ -34,6 +91,15 @@ impl BlockchainInfoConnector {
 
         Ok(Self {
             client: Client::new(),
+            cache: Arc::new(Mutex::new(Cache::default())),
+        })
+    }
+
+    /// Send all requests to blockchain.info through the given SOCKS5 proxy.
+    pub fn with_socks5_proxy(self, proxy: SocketAddr) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: socks5_client(proxy)?,
+            ..self
         })
     }
 
@@ -47,61 +113,238 @@ impl BlockchainInfoConnector {
 
         url
     }
+
+    async fn latest_block_hash(&self) -> Result<sha256d::Hash, crate::btsieve::bitcoin::Error> {
+        let cached = self.cache.lock().unwrap().latest_block.clone();
+        if let Some(cached) = &cached {
+            if cached.is_fresh(Instant::now()) {
+                return Ok(cached.hash);
+            }
+        }
+
+        let latest_block_url = Url::parse("https://blockchain.info/latestblock").unwrap();
+        let mut response = self.get(latest_block_url, cached.as_ref()).await?;
+
+        let hash = match &cached {
+            Some(cached) if response.status() == StatusCode::NOT_MODIFIED => cached.hash,
+            _ => {
+                response
+                    .json::<BlockchainInfoLatestBlock>()
+                    .compat()
+                    .await?
+                    .hash
+            }
+        };
+
+        // A `304 Not Modified` does not need to repeat the validators.
+        let (previous_etag, previous_last_modified) = match cached {
+            Some(cached) => (cached.etag, cached.last_modified),
+            None => (None, None),
+        };
+        let headers = response.headers();
+        self.cache.lock().unwrap().latest_block = Some(CachedLatestBlock {
+            hash,
+            fresh_until: max_age(headers).map(|max_age| Instant::now() + max_age),
+            etag: headers.get(ETAG).cloned().or(previous_etag),
+            last_modified: headers
+                .get(LAST_MODIFIED)
+                .cloned()
+                .or(previous_last_modified),
+        });
+
+        Ok(hash)
+    }
+
+    /// Send a GET request, waiting out the backoff first and retrying for as
+    /// long as blockchain.info responds with `429 Too Many Requests`.
+    async fn get(
+        &self,
+        url: Url,
+        validators: Option<&CachedLatestBlock>,
+    ) -> Result<Response, crate::btsieve::bitcoin::Error> {
+        loop {
+            let wait = self.cache.lock().unwrap().backoff.remaining(Instant::now());
+            if let Some(wait) = wait {
+                Delay::new(Instant::now() + wait)
+                    .compat()
+                    .await
+                    .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+            }
+
+            let mut request = self.client.get(url.clone());
+            if let Some(validators) = validators {
+                if let Some(etag) = &validators.etag {
+                    request = request.header(IF_NONE_MATCH, etag.clone());
+                }
+                if let Some(last_modified) = &validators.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+                }
+            }
+            let response = request.send().compat().await?;
+
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                let wait = self
+                    .cache
+                    .lock()
+                    .unwrap()
+                    .backoff
+                    .rate_limited(Instant::now(), retry_after(response.headers()));
+                log::warn!(
+                    "Rate limited by blockchain.info, retrying {} in {:?}",
+                    url,
+                    wait
+                );
+                continue;
+            }
+            self.cache.lock().unwrap().backoff.reset();
+
+            return Ok(response.error_for_status()?);
+        }
+    }
 }
 
+impl Cache {
+    fn insert_block(&mut self, block_hash: sha256d::Hash, block: bitcoin::Block) {
+        if self.blocks.insert(block_hash, block).is_some() {
+            return;
+        }
+
+        self.insertion_order.push_back(block_hash);
+        if self.insertion_order.len() > BLOCK_CACHE_SIZE {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.blocks.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl CachedLatestBlock {
+    fn is_fresh(&self, now: Instant) -> bool {
+        self.fresh_until
+            .map_or(false, |fresh_until| now < fresh_until)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            until: None,
+            next: INITIAL_BACKOFF,
+        }
+    }
+}
+
+impl Backoff {
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    /// Back off for as long as the server asked for or otherwise twice as
+    /// long as last time and return how long that is.
+    fn rate_limited(&mut self, now: Instant, retry_after: Option<Duration>) -> Duration {
+        let wait = retry_after.unwrap_or(self.next);
+
+        self.until = Some(now + wait);
+        self.next = cmp::min(self.next * 2, MAX_BACKOFF);
+
+        wait
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// How long a response may be used without asking again, `None` if it must
+/// not be reused at all.
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    let cache_control = headers.get(CACHE_CONTROL)?.to_str().ok()?;
+
+    let directives = cache_control
+        .split(',')
+        .map(|directive| directive.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    if directives
+        .iter()
+        .any(|directive| directive == "no-cache" || directive == "no-store")
+    {
+        return None;
+    }
+
+    directives
+        .iter()
+        .filter(|directive| directive.starts_with("max-age="))
+        .filter_map(|directive| directive["max-age=".len()..].parse().ok())
+        .next()
+        .map(Duration::from_secs)
+}
+
+/// Only the delay-seconds form of `Retry-After` is supported, an HTTP-date
+/// falls back to the exponential backoff.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[async_trait]
 impl LatestBlock for BlockchainInfoConnector {
     type Error = crate::btsieve::bitcoin::Error;
     type Block = bitcoin::Block;
     type BlockHash = sha256d::Hash;
 
-    fn latest_block(
-        &mut self,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static> {
-        let latest_block_url = "https://blockchain.info/latestblock";
-        let latest_block_without_tx = self
-            .client
-            .get(latest_block_url)
-            .send()
-            .map_err(crate::btsieve::bitcoin::Error::Reqwest)
-            .and_then(move |mut response| {
-                response
-                    .json::<BlockchainInfoLatestBlock>()
-                    .map_err(crate::btsieve::bitcoin::Error::Reqwest)
-            });
-
-        let cloned_self = self.clone();
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error> {
+        let block_hash = self.latest_block_hash().await?;
 
-        Box::new(
-            latest_block_without_tx
-                .and_then(move |latest_block| cloned_self.block_by_hash(latest_block.hash)),
-        )
+        self.block_by_hash(block_hash).await
     }
 }
 
+#[async_trait]
 impl BlockByHash for BlockchainInfoConnector {
     type Error = crate::btsieve::bitcoin::Error;
     type Block = bitcoin::Block;
     type BlockHash = sha256d::Hash;
 
-    fn block_by_hash(
-        &self,
-        block_hash: Self::BlockHash,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static> {
-        let block = bitcoin_http_request_for_hex_encoded_object::<Self::Block>(
-            Self::block_by_hash_url(&block_hash),
-            self.client.clone(),
-        );
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error> {
+        let cached = self.cache.lock().unwrap().blocks.get(&block_hash).cloned();
+        if let Some(block) = cached {
+            return Ok(block);
+        }
+
+        let response_text = self
+            .get(Self::block_by_hash_url(&block_hash), None)
+            .await?
+            .text()
+            .compat()
+            .await?;
+        let block = decode_response::<Self::Block>(response_text)?;
+
+        log::trace!("Fetched block from blockchain.info: {:?}", block);
 
-        Box::new(block.inspect(|block| {
-            log::trace!("Fetched block from blockchain.info: {:?}", block);
-        }))
+        self.cache
+            .lock()
+            .unwrap()
+            .insert_block(block_hash, block.clone());
+
+        Ok(block)
     }
 }
 
+impl FilteredBlocks for BlockchainInfoConnector {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::quickcheck::Quickcheck;
+    use spectral::prelude::*;
     use std::str::FromStr;
 
     #[test]
@@ -127,4 +370,37 @@ mod tests {
 
         assert_eq!(actual_url, expected_url);
     }
+
+    fn headers(name: reqwest::header::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+
+        headers
+    }
+
+    #[test]
+    fn max_age_is_only_honored_if_caching_is_allowed() {
+        assert_that(&max_age(&headers(CACHE_CONTROL, "public, max-age=30")))
+            .is_equal_to(Some(Duration::from_secs(30)));
+        assert_that(&max_age(&headers(CACHE_CONTROL, "no-cache, max-age=30"))).is_none();
+        assert_that(&max_age(&HeaderMap::new())).is_none();
+    }
+
+    #[test]
+    fn backoff_honors_retry_after_and_doubles_otherwise() {
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+
+        let first = backoff.rate_limited(now, retry_after(&headers(RETRY_AFTER, "3")));
+        let second = backoff.rate_limited(now, None);
+        let third = backoff.rate_limited(now, None);
+
+        assert_that(&first).is_equal_to(Duration::from_secs(3));
+        assert_that(&second).is_equal_to(INITIAL_BACKOFF * 2);
+        assert_that(&third).is_equal_to(INITIAL_BACKOFF * 4);
+        assert_that(&backoff.remaining(now)).is_equal_to(Some(third));
+
+        backoff.reset();
+        assert_that(&backoff.remaining(now)).is_none();
+    }
 }
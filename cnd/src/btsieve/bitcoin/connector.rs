@@ -0,0 +1,167 @@
+#[cfg(feature = "bitcoind")]
+use crate::btsieve::bitcoin::BitcoindConnector;
+use crate::{
+    btsieve::{
+        bitcoin::{
+            Error, EsploraConnector, FailoverConnector, FilteredBlocks, SpvConnector,
+            TransactionPattern, TransactionProof,
+        },
+        BlockByHash, InclusionProof, LatestBlock,
+    },
+    swap_tracing::in_span,
+};
+use async_trait::async_trait;
+use bitcoin::hashes::sha256d;
+
+/// The source of Bitcoin blocks chosen in the config file.
+///
+/// Blocks are fetched from the node unless an Esplora instance is configured,
+/// which is required if cnd was built without the `bitcoind` feature. Either
+/// of them falls back to another Esplora instance if one is configured, and
+/// their blocks are verified if light client verification is enabled.
+#[derive(Clone, Debug)]
+pub enum BitcoinConnector {
+    #[cfg(feature = "bitcoind")]
+    Bitcoind(BitcoindConnector),
+    Esplora(EsploraConnector),
+    Failover(FailoverConnector),
+    Spv(SpvConnector),
+}
+
+#[cfg(feature = "bitcoind")]
+impl From<BitcoindConnector> for BitcoinConnector {
+    fn from(connector: BitcoindConnector) -> Self {
+        BitcoinConnector::Bitcoind(connector)
+    }
+}
+
+impl From<EsploraConnector> for BitcoinConnector {
+    fn from(connector: EsploraConnector) -> Self {
+        BitcoinConnector::Esplora(connector)
+    }
+}
+
+impl From<FailoverConnector> for BitcoinConnector {
+    fn from(connector: FailoverConnector) -> Self {
+        BitcoinConnector::Failover(connector)
+    }
+}
+
+impl From<SpvConnector> for BitcoinConnector {
+    fn from(connector: SpvConnector) -> Self {
+        BitcoinConnector::Spv(connector)
+    }
+}
+
+#[async_trait]
+impl LatestBlock for BitcoinConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error> {
+        in_span("bitcoin.latest-block", async move {
+            match self {
+                #[cfg(feature = "bitcoind")]
+                BitcoinConnector::Bitcoind(connector) => connector.latest_block().await,
+                BitcoinConnector::Esplora(connector) => connector.latest_block().await,
+                BitcoinConnector::Failover(connector) => connector.latest_block().await,
+                BitcoinConnector::Spv(connector) => connector.latest_block().await,
+            }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl BlockByHash for BitcoinConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error> {
+        in_span("bitcoin.block-by-hash", async move {
+            match self {
+                #[cfg(feature = "bitcoind")]
+                BitcoinConnector::Bitcoind(connector) => connector.block_by_hash(block_hash).await,
+                BitcoinConnector::Esplora(connector) => connector.block_by_hash(block_hash).await,
+                BitcoinConnector::Failover(connector) => connector.block_by_hash(block_hash).await,
+                BitcoinConnector::Spv(connector) => connector.block_by_hash(block_hash).await,
+            }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl FilteredBlocks for BitcoinConnector {
+    async fn latest_filtered_block(
+        &mut self,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        in_span("bitcoin.latest-filtered-block", async move {
+            match self {
+                #[cfg(feature = "bitcoind")]
+                BitcoinConnector::Bitcoind(connector) => {
+                    connector.latest_filtered_block(pattern).await
+                }
+                BitcoinConnector::Esplora(connector) => {
+                    connector.latest_filtered_block(pattern).await
+                }
+                BitcoinConnector::Failover(connector) => {
+                    connector.latest_filtered_block(pattern).await
+                }
+                BitcoinConnector::Spv(connector) => connector.latest_filtered_block(pattern).await,
+            }
+        })
+        .await
+    }
+
+    async fn filtered_block_by_hash(
+        &self,
+        block_hash: sha256d::Hash,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        in_span("bitcoin.filtered-block-by-hash", async move {
+            match self {
+                #[cfg(feature = "bitcoind")]
+                BitcoinConnector::Bitcoind(connector) => {
+                    connector.filtered_block_by_hash(block_hash, pattern).await
+                }
+                BitcoinConnector::Esplora(connector) => {
+                    connector.filtered_block_by_hash(block_hash, pattern).await
+                }
+                BitcoinConnector::Failover(connector) => {
+                    connector.filtered_block_by_hash(block_hash, pattern).await
+                }
+                BitcoinConnector::Spv(connector) => {
+                    connector.filtered_block_by_hash(block_hash, pattern).await
+                }
+            }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl InclusionProof for BitcoinConnector {
+    type Proof = TransactionProof;
+    type TransactionHash = sha256d::Hash;
+    type Error = Error;
+
+    async fn inclusion_proof(
+        &self,
+        txid: sha256d::Hash,
+    ) -> Result<Option<TransactionProof>, Error> {
+        in_span("bitcoin.inclusion-proof", async move {
+            match self {
+                #[cfg(feature = "bitcoind")]
+                BitcoinConnector::Bitcoind(connector) => connector.inclusion_proof(txid).await,
+                BitcoinConnector::Esplora(connector) => connector.inclusion_proof(txid).await,
+                BitcoinConnector::Failover(connector) => connector.inclusion_proof(txid).await,
+                BitcoinConnector::Spv(connector) => connector.inclusion_proof(txid).await,
+            }
+        })
+        .await
+    }
+}
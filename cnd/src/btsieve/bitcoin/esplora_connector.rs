@@ -0,0 +1,300 @@
+use crate::{
+    btsieve::{
+        bitcoin::{
+            bitcoin_http_request_for_hex_encoded_object, Error, FilteredBlocks, TransactionProof,
+        },
+        socks5_client, BlockByHash, ConcurrencyLimit, InclusionProof, LatestBlock,
+    },
+    chaos::Chaos,
+};
+use async_trait::async_trait;
+use bitcoin::{consensus::encode::deserialize, hashes::sha256d, BlockHeader};
+use futures::{Future, Stream};
+use futures_core::compat::Future01CompatExt;
+use reqwest::{r#async::Client, Url};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, str::FromStr};
+
+/// Fetches blocks from the REST API of an Esplora instance, e.g.
+/// `https://blockstream.info/api/`, `https://blockstream.info/testnet/api/` or
+/// a self-hosted one.
+///
+/// Blocks are fetched in their binary encoding which is about half the size of
+/// the hex encoding the other connectors use.
+#[derive(Clone, Debug)]
+pub struct EsploraConnector {
+    tip_hash_url: Url,
+    block_by_hash_url: Url,
+    transaction_url: Url,
+    client: Client,
+    concurrency_limit: ConcurrencyLimit,
+    chaos: Chaos,
+}
+
+impl EsploraConnector {
+    pub fn new(mut base_url: Url) -> Result<Self, reqwest::UrlError> {
+        // The API is usually served under a path, e.g. `/testnet/api`, which
+        // would be replaced when joining if it does not end with a slash.
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+
+        Ok(Self {
+            tip_hash_url: base_url.join("blocks/tip/hash")?,
+            block_by_hash_url: base_url.join("block/")?,
+            transaction_url: base_url.join("tx/")?,
+            client: Client::new(),
+            concurrency_limit: ConcurrencyLimit::default(),
+            chaos: Chaos::default(),
+        })
+    }
+
+    pub fn with_concurrency_limit(self, concurrency_limit: ConcurrencyLimit) -> Self {
+        Self {
+            concurrency_limit,
+            ..self
+        }
+    }
+
+    pub fn with_chaos(self, chaos: Chaos) -> Self {
+        Self { chaos, ..self }
+    }
+
+    /// Send all requests to the Esplora instance through the given SOCKS5
+    /// proxy.
+    pub fn with_socks5_proxy(self, proxy: SocketAddr) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: socks5_client(proxy)?,
+            ..self
+        })
+    }
+
+    fn raw_block_by_hash_url(&self, block_hash: &sha256d::Hash) -> Url {
+        self.block_by_hash_url
+            .join(&format!("{}/raw", block_hash))
+            .expect("building url should work")
+    }
+
+    fn block_status_url(&self, block_hash: &sha256d::Hash) -> Url {
+        self.block_by_hash_url
+            .join(&format!("{}/status", block_hash))
+            .expect("building url should work")
+    }
+
+    fn block_header_url(&self, block_hash: &sha256d::Hash) -> Url {
+        self.block_by_hash_url
+            .join(&format!("{}/header", block_hash))
+            .expect("building url should work")
+    }
+
+    fn transaction_status_url(&self, txid: &sha256d::Hash) -> Url {
+        self.transaction_url
+            .join(&format!("{}/status", txid))
+            .expect("building url should work")
+    }
+
+    fn merkle_proof_url(&self, txid: &sha256d::Hash) -> Url {
+        self.transaction_url
+            .join(&format!("{}/merkle-proof", txid))
+            .expect("building url should work")
+    }
+
+    /// The merkle branch that proves that the transaction is in the block
+    /// the Esplora instance has it in.
+    pub async fn merkle_proof(&self, txid: sha256d::Hash) -> Result<MerkleProof, Error> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let proof = self
+            .client
+            .get(self.merkle_proof_url(&txid))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<MerkleProof>())
+            .compat()
+            .await?;
+
+        Ok(proof)
+    }
+
+    /// Whether the block is part of the chain the Esplora instance follows,
+    /// which is not the case for blocks it does not know either.
+    pub async fn is_in_best_chain(&self, block_hash: sha256d::Hash) -> Result<bool, Error> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let status = self
+            .client
+            .get(self.block_status_url(&block_hash))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<BlockStatus>())
+            .compat()
+            .await?;
+
+        Ok(status.in_best_chain)
+    }
+
+    async fn tip_hash(&self) -> Result<sha256d::Hash, Error> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let response_text = self
+            .client
+            .get(self.tip_hash_url.clone())
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.text())
+            .compat()
+            .await
+            .map_err(|e| {
+                log::error!("Error when fetching the tip hash from esplora");
+                Error::Reqwest(e)
+            })?;
+
+        let tip_hash = sha256d::Hash::from_str(response_text.trim())?;
+
+        Ok(tip_hash)
+    }
+}
+
+#[async_trait]
+impl LatestBlock for EsploraConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error> {
+        let block_hash = self.tip_hash().await?;
+
+        self.block_by_hash(block_hash).await
+    }
+}
+
+#[async_trait]
+impl BlockByHash for EsploraConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+        let url = self.raw_block_by_hash_url(&block_hash);
+
+        let bytes = self
+            .client
+            .get(url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.into_body().concat2())
+            .compat()
+            .await?;
+        let block = deserialize::<Self::Block>(&bytes)?;
+
+        log::trace!("Fetched block from esplora: {:?}", block);
+
+        Ok(block)
+    }
+}
+
+impl FilteredBlocks for EsploraConnector {}
+
+#[async_trait]
+impl InclusionProof for EsploraConnector {
+    type Proof = TransactionProof;
+    type TransactionHash = sha256d::Hash;
+    type Error = Error;
+
+    async fn inclusion_proof(
+        &self,
+        txid: sha256d::Hash,
+    ) -> Result<Option<TransactionProof>, Error> {
+        let status = {
+            let _permit = self.concurrency_limit.acquire().await;
+            self.chaos.before_request().await?;
+
+            self.client
+                .get(self.transaction_status_url(&txid))
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|mut response| response.json::<TransactionStatus>())
+                .compat()
+                .await?
+        };
+        let block_hash = match status.block_hash {
+            Some(block_hash) if status.confirmed => block_hash,
+            _ => return Ok(None),
+        };
+
+        let header = {
+            let _permit = self.concurrency_limit.acquire().await;
+            self.chaos.before_request().await?;
+
+            bitcoin_http_request_for_hex_encoded_object::<BlockHeader>(
+                self.block_header_url(&block_hash),
+                self.client.clone(),
+            )
+            .await?
+        };
+        let merkle_proof = self.merkle_proof(txid).await?;
+
+        Ok(Some(TransactionProof::new(&header, merkle_proof)))
+    }
+}
+
+/// The hashes needed to get from a transaction to the merkle root of its
+/// block, `pos` is the index of the transaction in the block.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MerkleProof {
+    pub merkle: Vec<sha256d::Hash>,
+    pub pos: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockStatus {
+    in_best_chain: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransactionStatus {
+    confirmed: bool,
+    block_hash: Option<sha256d::Hash>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_different_base_urls_correct_sub_urls_are_built() {
+        let base_urls: Vec<Url> = vec![
+            "https://blockstream.info/testnet/api".parse().unwrap(),
+            "https://blockstream.info/testnet/api/".parse().unwrap(),
+        ];
+
+        for base_url in base_urls {
+            let connector = EsploraConnector::new(base_url).unwrap();
+
+            assert_eq!(
+                connector.tip_hash_url,
+                Url::parse("https://blockstream.info/testnet/api/blocks/tip/hash").unwrap()
+            );
+
+            let block_id = "2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02"
+                .parse()
+                .unwrap();
+            let raw_block_by_hash_url = connector.raw_block_by_hash_url(&block_id);
+            assert_eq!(raw_block_by_hash_url, Url::parse("https://blockstream.info/testnet/api/block/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02/raw").unwrap());
+            let block_status_url = connector.block_status_url(&block_id);
+            assert_eq!(block_status_url, Url::parse("https://blockstream.info/testnet/api/block/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02/status").unwrap());
+            let block_header_url = connector.block_header_url(&block_id);
+            assert_eq!(block_header_url, Url::parse("https://blockstream.info/testnet/api/block/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02/header").unwrap());
+            let transaction_status_url = connector.transaction_status_url(&block_id);
+            assert_eq!(transaction_status_url, Url::parse("https://blockstream.info/testnet/api/tx/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02/status").unwrap());
+            let merkle_proof_url = connector.merkle_proof_url(&block_id);
+            assert_eq!(merkle_proof_url, Url::parse("https://blockstream.info/testnet/api/tx/2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02/merkle-proof").unwrap());
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use crate::btsieve::{
+    bitcoin::{
+        BitcoinConnector, Error, EsploraConnector, FilteredBlocks, TransactionPattern,
+        TransactionProof,
+    },
+    BlockByHash, InclusionProof, LatestBlock,
+};
+use async_trait::async_trait;
+use bitcoin::{hashes::sha256d, BitcoinHash};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How long blocks are fetched from the secondary source after the primary
+/// one failed, before the primary one is tried again.
+const RETRY_PRIMARY_AFTER: Duration = Duration::from_secs(30);
+
+/// How long a disagreement between the sources is kept before the same tip is
+/// cross-checked again, e.g. because the secondary source was behind.
+const RECHECK_DISAGREEMENT_AFTER: Duration = Duration::from_secs(30);
+
+/// Fetches blocks from a primary source and falls back to an Esplora instance
+/// while the primary one fails.
+///
+/// The latest blocks of the primary source are cross-checked against the
+/// Esplora instance. If the two do not follow the same chain, e.g. because one
+/// of them is malicious or on a fork, no latest block is returned until they
+/// agree again, as it cannot be told which of them is right.
+#[derive(Clone, Debug)]
+pub struct FailoverConnector {
+    primary: Box<BitcoinConnector>,
+    secondary: EsploraConnector,
+    health: Arc<Mutex<Health>>,
+}
+
+impl FailoverConnector {
+    pub fn new(primary: BitcoinConnector, secondary: EsploraConnector) -> Self {
+        Self {
+            primary: Box::new(primary),
+            secondary,
+            health: Arc::new(Mutex::new(Health::default())),
+        }
+    }
+
+    fn uses_primary(&self) -> bool {
+        self.health.lock().unwrap().uses_primary(Instant::now())
+    }
+
+    fn primary_failed(&self, e: &Error) {
+        let mut health = self.health.lock().unwrap();
+
+        if health.primary_failed_at.is_none() {
+            log::warn!(
+                "failing over to the secondary Bitcoin source because the primary one failed: {:?}",
+                e
+            );
+        }
+        health.primary_failed_at = Some(Instant::now());
+    }
+
+    fn primary_succeeded(&self) {
+        let mut health = self.health.lock().unwrap();
+
+        if health.primary_failed_at.take().is_some() {
+            log::info!("switching back to the primary Bitcoin source");
+        }
+    }
+
+    async fn fetch_latest_block(
+        &mut self,
+        pattern: Option<&TransactionPattern>,
+    ) -> Result<bitcoin::Block, Error> {
+        if self.uses_primary() {
+            let latest_block = match pattern {
+                Some(pattern) => self.primary.latest_filtered_block(pattern).await,
+                None => self.primary.latest_block().await,
+            };
+
+            match latest_block {
+                Ok(block) => {
+                    self.primary_succeeded();
+                    self.cross_check(&block).await?;
+
+                    return Ok(block);
+                }
+                Err(e) => self.primary_failed(&e),
+            }
+        }
+
+        match pattern {
+            Some(pattern) => self.secondary.latest_filtered_block(pattern).await,
+            None => self.secondary.latest_block().await,
+        }
+    }
+
+    /// Check that the secondary source follows the chain of the latest block
+    /// of the primary one.
+    ///
+    /// The parent of the block is looked up because the block itself may not
+    /// have reached the secondary source yet.
+    async fn cross_check(&self, block: &bitcoin::Block) -> Result<(), Error> {
+        let tip = block.bitcoin_hash();
+        let parent = block.header.prev_blockhash;
+
+        let cached = self
+            .health
+            .lock()
+            .unwrap()
+            .cached_agreement(tip, Instant::now());
+        let agreed = match cached {
+            Some(agreed) => agreed,
+            None => match self.secondary.is_in_best_chain(parent).await {
+                Ok(agreed) => {
+                    if !agreed {
+                        log::error!(
+                            "the secondary Bitcoin source does not know block {} of the primary one, the sources follow different chains",
+                            parent
+                        );
+                    }
+                    self.health.lock().unwrap().cross_checked = Some(CrossCheck {
+                        tip,
+                        agreed,
+                        at: Instant::now(),
+                    });
+
+                    agreed
+                }
+                // An unreachable secondary source is no reason to distrust
+                // the primary one.
+                Err(e) => {
+                    log::warn!("failed to cross-check block {}: {:?}", parent, e);
+                    true
+                }
+            },
+        };
+
+        if agreed {
+            Ok(())
+        } else {
+            Err(Error::SourcesDisagree(parent))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Health {
+    primary_failed_at: Option<Instant>,
+    /// Only the latest tip is cross-checked, once for all swaps.
+    cross_checked: Option<CrossCheck>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct CrossCheck {
+    tip: sha256d::Hash,
+    agreed: bool,
+    at: Instant,
+}
+
+impl Health {
+    fn uses_primary(&self, now: Instant) -> bool {
+        match self.primary_failed_at {
+            Some(failed_at) => now >= failed_at + RETRY_PRIMARY_AFTER,
+            None => true,
+        }
+    }
+
+    /// `None` if `tip` needs to be cross-checked.
+    fn cached_agreement(&self, tip: sha256d::Hash, now: Instant) -> Option<bool> {
+        match self.cross_checked {
+            Some(check) if check.tip == tip => {
+                if check.agreed || now < check.at + RECHECK_DISAGREEMENT_AFTER {
+                    Some(check.agreed)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl LatestBlock for FailoverConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error> {
+        self.fetch_latest_block(None).await
+    }
+}
+
+#[async_trait]
+impl BlockByHash for FailoverConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error> {
+        if self.uses_primary() {
+            match self.primary.block_by_hash(block_hash).await {
+                Ok(block) => return Ok(block),
+                Err(e) => self.primary_failed(&e),
+            }
+        }
+
+        self.secondary.block_by_hash(block_hash).await
+    }
+}
+
+#[async_trait]
+impl FilteredBlocks for FailoverConnector {
+    async fn latest_filtered_block(
+        &mut self,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        self.fetch_latest_block(Some(pattern)).await
+    }
+
+    async fn filtered_block_by_hash(
+        &self,
+        block_hash: sha256d::Hash,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        if self.uses_primary() {
+            match self
+                .primary
+                .filtered_block_by_hash(block_hash, pattern)
+                .await
+            {
+                Ok(block) => return Ok(block),
+                Err(e) => self.primary_failed(&e),
+            }
+        }
+
+        self.secondary
+            .filtered_block_by_hash(block_hash, pattern)
+            .await
+    }
+}
+
+#[async_trait]
+impl InclusionProof for FailoverConnector {
+    type Proof = TransactionProof;
+    type TransactionHash = sha256d::Hash;
+    type Error = Error;
+
+    async fn inclusion_proof(
+        &self,
+        txid: sha256d::Hash,
+    ) -> Result<Option<TransactionProof>, Error> {
+        if self.uses_primary() {
+            match self.primary.inclusion_proof(txid).await {
+                Ok(proof) => return Ok(proof),
+                Err(e) => self.primary_failed(&e),
+            }
+        }
+
+        self.secondary.inclusion_proof(txid).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn hash(byte: u8) -> sha256d::Hash {
+        use bitcoin::hashes::Hash;
+
+        sha256d::Hash::from_slice(&[byte; 32]).unwrap()
+    }
+
+    #[test]
+    fn primary_is_retried_after_failing_over() {
+        let now = Instant::now();
+        let health = Health {
+            primary_failed_at: Some(now),
+            cross_checked: None,
+        };
+
+        assert_that(&health.uses_primary(now + Duration::from_secs(1))).is_false();
+        assert_that(&health.uses_primary(now + RETRY_PRIMARY_AFTER)).is_true();
+    }
+
+    #[test]
+    fn a_disagreement_is_cross_checked_again_but_an_agreement_is_not() {
+        let now = Instant::now();
+        let later = now + RECHECK_DISAGREEMENT_AFTER;
+        let mut health = Health {
+            primary_failed_at: None,
+            cross_checked: Some(CrossCheck {
+                tip: hash(1),
+                agreed: false,
+                at: now,
+            }),
+        };
+
+        assert_that(&health.cached_agreement(hash(1), now)).is_equal_to(Some(false));
+        assert_that(&health.cached_agreement(hash(1), later)).is_equal_to(None);
+        assert_that(&health.cached_agreement(hash(2), now)).is_equal_to(None);
+
+        health.cross_checked = Some(CrossCheck {
+            tip: hash(1),
+            agreed: true,
+            at: now,
+        });
+        assert_that(&health.cached_agreement(hash(1), later)).is_equal_to(Some(true));
+    }
+}
@@ -1,31 +1,78 @@
+#[cfg(feature = "bitcoind")]
 mod bitcoind_connector;
+#[cfg(feature = "blockchain-info")]
 mod blockchain_info_connector;
+mod connector;
+mod esplora_connector;
+mod failover_connector;
+mod spv_connector;
 mod transaction_ext;
 mod transaction_pattern;
+mod transaction_proof;
+mod verbose_block;
 
+#[cfg(feature = "bitcoind")]
+pub use self::bitcoind_connector::BitcoindConnector;
+#[cfg(feature = "blockchain-info")]
+pub use self::blockchain_info_connector::BlockchainInfoConnector;
 pub use self::{
-    bitcoind_connector::BitcoindConnector, blockchain_info_connector::BlockchainInfoConnector,
-    transaction_ext::TransactionExt, transaction_pattern::TransactionPattern,
+    connector::BitcoinConnector,
+    esplora_connector::{EsploraConnector, MerkleProof},
+    failover_connector::FailoverConnector,
+    spv_connector::SpvConnector,
+    transaction_ext::TransactionExt,
+    transaction_pattern::{Spend, TransactionPattern},
+    transaction_proof::TransactionProof,
+    verbose_block::VerboseBlock,
 };
 
-use crate::btsieve::{BlockByHash, LatestBlock, MatchingTransactions};
+use crate::btsieve::{BlockByHash, LatestBlock, MatchingTransactions, PastTransactions};
+use async_trait::async_trait;
 use bitcoin::{
     consensus::{encode::deserialize, Decodable},
     hashes::sha256d,
     BitcoinHash,
 };
-use futures_core::{compat::Future01CompatExt, TryFutureExt};
+use futures::Future;
+use futures_core::{
+    compat::Future01CompatExt,
+    stream::{self, BoxStream, StreamExt},
+};
 use reqwest::{r#async::Client, Url};
 use std::{collections::HashSet, fmt::Debug, ops::Add};
-use tokio::{
-    prelude::{future::Future, stream, Stream},
-    timer::Delay,
-};
+use tokio::timer::Delay;
+
+/// Fetch blocks of which only the transactions that may match a pattern are
+/// decoded.
+///
+/// The returned blocks have a complete header but may lack transactions that
+/// cannot match the pattern. By default the full blocks are fetched.
+#[async_trait]
+pub trait FilteredBlocks:
+    LatestBlock<Block = bitcoin::Block, BlockHash = sha256d::Hash>
+    + BlockByHash<Block = bitcoin::Block, BlockHash = sha256d::Hash>
+{
+    async fn latest_filtered_block(
+        &mut self,
+        _pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        self.latest_block().await
+    }
+
+    async fn filtered_block_by_hash(
+        &self,
+        block_hash: sha256d::Hash,
+        _pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as BlockByHash>::Error> {
+        self.block_by_hash(block_hash).await
+    }
+}
 
 impl<C, E> MatchingTransactions<TransactionPattern> for C
 where
     C: LatestBlock<Block = bitcoin::Block, Error = E>
         + BlockByHash<Block = bitcoin::Block, BlockHash = sha256d::Hash, Error = E>
+        + FilteredBlocks
         + Clone,
     E: Debug + Send + 'static,
 {
@@ -35,10 +82,53 @@ where
         &self,
         pattern: TransactionPattern,
         timestamp: Option<u32>,
-    ) -> Box<dyn Stream<Item = Self::Transaction, Error = ()> + Send + 'static> {
-        let matching_transaction =
-            Box::pin(matching_transaction(self.clone(), pattern, timestamp)).compat();
-        Box::new(stream::futures_unordered(vec![matching_transaction]))
+    ) -> BoxStream<'static, Self::Transaction> {
+        stream::once(matching_transaction(self.clone(), pattern, timestamp)).boxed()
+    }
+}
+
+#[async_trait]
+impl<C, E> PastTransactions<TransactionPattern> for C
+where
+    C: LatestBlock<Block = bitcoin::Block, Error = E>
+        + BlockByHash<Block = bitcoin::Block, BlockHash = sha256d::Hash, Error = E>
+        + FilteredBlocks
+        + Clone,
+    E: Debug + Send + 'static,
+{
+    type Transaction = bitcoin::Transaction;
+
+    async fn past_transaction(
+        &self,
+        pattern: TransactionPattern,
+        timestamp: u32,
+    ) -> anyhow::Result<Option<bitcoin::Transaction>> {
+        let mut connector = self.clone();
+
+        let mut block = connector
+            .latest_filtered_block(&pattern)
+            .await
+            .map_err(|e| anyhow::anyhow!("could not get latest block: {:?}", e))?;
+
+        loop {
+            if let Some(transaction) = check_block_against_pattern(&block, &pattern) {
+                return Ok(Some(transaction.clone()));
+            }
+
+            // The first block older than `timestamp` was looked at as well
+            // because block times are not strictly increasing.
+            if block.header.time < timestamp {
+                return Ok(None);
+            }
+
+            let prev_blockhash = block.header.prev_blockhash;
+            block = connector
+                .filtered_block_by_hash(prev_blockhash, &pattern)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!("could not get block with hash {}: {:?}", prev_blockhash, e)
+                })?;
+        }
     }
 }
 
@@ -46,17 +136,18 @@ async fn matching_transaction<C, E>(
     mut blockchain_connector: C,
     pattern: TransactionPattern,
     reference_timestamp: Option<u32>,
-) -> Result<bitcoin::Transaction, ()>
+) -> bitcoin::Transaction
 where
     C: LatestBlock<Block = bitcoin::Block, Error = E>
         + BlockByHash<Block = bitcoin::Block, BlockHash = sha256d::Hash, Error = E>
+        + FilteredBlocks
         + Clone,
     E: Debug + Send + 'static,
 {
     let mut oldest_block: Option<bitcoin::Block> = None;
 
     let mut prev_blockhashes: HashSet<sha256d::Hash> = HashSet::new();
-    let mut missing_block_futures: Vec<_> = Vec::new();
+    let mut missing_blocks: Vec<sha256d::Hash> = Vec::new();
 
     loop {
         // Delay so that we don't overload the CPU in the event that
@@ -66,20 +157,21 @@ where
             .await
             .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
 
-        let mut new_missing_block_futures = Vec::new();
-        for (block_future, blockhash) in missing_block_futures.into_iter() {
-            match block_future.await {
+        let mut new_missing_blocks = Vec::new();
+        for blockhash in missing_blocks.into_iter() {
+            match blockchain_connector
+                .filtered_block_by_hash(blockhash, &pattern)
+                .await
+            {
                 Ok(block) => {
                     match check_block_against_pattern(&block, &pattern) {
-                        Some(transaction) => return Ok(transaction.clone()),
+                        Some(transaction) => return transaction.clone(),
                         None => {
                             let prev_blockhash = block.header.prev_blockhash;
                             let unknown_parent = prev_blockhashes.insert(prev_blockhash);
 
                             if unknown_parent {
-                                let future =
-                                    blockchain_connector.block_by_hash(prev_blockhash).compat();
-                                new_missing_block_futures.push((future, prev_blockhash));
+                                new_missing_blocks.push(prev_blockhash);
                             }
                         }
                     };
@@ -87,24 +179,22 @@ where
                 Err(e) => {
                     log::warn!("Could not get block with hash {}: {:?}", blockhash, e);
 
-                    let future = blockchain_connector.block_by_hash(blockhash).compat();
-                    new_missing_block_futures.push((future, blockhash));
+                    new_missing_blocks.push(blockhash);
                 }
             };
         }
-        missing_block_futures = new_missing_block_futures;
+        missing_blocks = new_missing_blocks;
 
         if let (Some(block), Some(reference_timestamp)) =
             (oldest_block.as_ref(), reference_timestamp)
         {
             if block.header.time >= reference_timestamp {
                 match blockchain_connector
-                    .block_by_hash(block.header.prev_blockhash)
-                    .compat()
+                    .filtered_block_by_hash(block.header.prev_blockhash, &pattern)
                     .await
                 {
                     Ok(block) => match check_block_against_pattern(&block, &pattern) {
-                        Some(transaction) => return Ok(transaction.clone()),
+                        Some(transaction) => return transaction.clone(),
                         None => {
                             oldest_block.replace(block);
                         }
@@ -118,7 +208,7 @@ where
             }
         }
 
-        let latest_block = match blockchain_connector.latest_block().compat().await {
+        let latest_block = match blockchain_connector.latest_filtered_block(&pattern).await {
             Ok(block) => block,
             Err(e) => {
                 log::warn!("Could not get latest block: {:?}", e,);
@@ -133,16 +223,13 @@ where
         }
 
         if let Some(transaction) = check_block_against_pattern(&latest_block, &pattern) {
-            return Ok(transaction.clone());
+            return transaction.clone();
         };
 
         if prev_blockhashes.len() > 1
             && !prev_blockhashes.contains(&latest_block.header.prev_blockhash)
         {
-            let prev_blockhash = latest_block.header.prev_blockhash;
-            let future = blockchain_connector.block_by_hash(prev_blockhash).compat();
-
-            missing_block_futures.push((future, prev_blockhash));
+            missing_blocks.push(latest_block.header.prev_blockhash);
         }
     }
 }
@@ -157,16 +244,18 @@ fn check_block_against_pattern<'b>(
         .find(|transaction| pattern.matches(transaction))
 }
 
-pub fn bitcoin_http_request_for_hex_encoded_object<T: Decodable>(
+pub async fn bitcoin_http_request_for_hex_encoded_object<T: Decodable>(
     request_url: Url,
     client: Client,
-) -> impl Future<Item = T, Error = Error> {
-    client
+) -> Result<T, Error> {
+    let response_text = client
         .get(request_url)
         .send()
         .and_then(|mut response| response.text())
-        .map_err(Error::Reqwest)
-        .and_then(decode_response)
+        .compat()
+        .await?;
+
+    decode_response(response_text)
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -177,8 +266,25 @@ pub enum Error {
     Reqwest(#[from] reqwest::Error),
     #[error("hex: ")]
     Hex(#[from] hex::FromHexError),
+    #[error("block hash: ")]
+    BlockHash(#[from] bitcoin::hashes::hex::Error),
     #[error("deserialization: ")]
     Deserialization(#[from] bitcoin::consensus::encode::Error),
+    #[error("malformed difficulty bits: {0}")]
+    MalformedBits(String),
+    #[error("chaos: ")]
+    Chaos(#[from] crate::chaos::InjectedFault),
+    #[error("the Bitcoin data sources disagree on block {0}")]
+    SourcesDisagree(sha256d::Hash),
+    #[error("block {received} was returned instead of block {requested}")]
+    UnexpectedBlock {
+        requested: sha256d::Hash,
+        received: sha256d::Hash,
+    },
+    #[error("block {0} does not have enough proof of work")]
+    InsufficientWork(sha256d::Hash),
+    #[error("transaction {0} is not proven to be in its block")]
+    UnprovenTransaction(sha256d::Hash),
 }
 
 pub fn decode_response<T: Decodable>(response_text: String) -> Result<T, Error> {
@@ -0,0 +1,356 @@
+use crate::btsieve::{
+    bitcoin::{
+        BitcoinConnector, Error, EsploraConnector, FilteredBlocks, MerkleProof, TransactionPattern,
+        TransactionProof,
+    },
+    BlockByHash, InclusionProof, LatestBlock,
+};
+use async_trait::async_trait;
+use bitcoin::{
+    hashes::{sha256d, Hash, HashEngine},
+    BitcoinHash, BlockHeader, Network,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+/// About two weeks of blocks, enough to always know the parent of the blocks
+/// a swap is watched in.
+const MAX_HEADERS: usize = 2016;
+
+/// Verifies the blocks of another source instead of trusting it, the way a
+/// light client does.
+///
+/// The header of every block has to have the proof of work its difficulty
+/// requires, which has to be at least the minimum difficulty of the network.
+/// On mainnet the difficulty cannot drop by more than the factor a retarget
+/// allows from one block to the next. The transactions of a block have to add
+/// up to the merkle root of its header. Blocks that only contain some of their
+/// transactions, e.g. verbose blocks of the node, are verified with a merkle
+/// proof per transaction from an Esplora instance.
+///
+/// Like any light client, this cannot tell whether a source leaves out a
+/// transaction.
+#[derive(Clone, Debug)]
+pub struct SpvConnector {
+    source: Box<BitcoinConnector>,
+    merkle_proofs: Option<EsploraConnector>,
+    network: Network,
+    headers: Arc<Mutex<HeaderChain>>,
+}
+
+impl SpvConnector {
+    pub fn new(
+        source: BitcoinConnector,
+        merkle_proofs: Option<EsploraConnector>,
+        network: Network,
+    ) -> Self {
+        Self {
+            source: Box::new(source),
+            merkle_proofs,
+            network,
+            headers: Arc::new(Mutex::new(HeaderChain::default())),
+        }
+    }
+
+    async fn verify(
+        &self,
+        block: bitcoin::Block,
+        requested: Option<sha256d::Hash>,
+    ) -> Result<bitcoin::Block, Error> {
+        let received = block.bitcoin_hash();
+        if let Some(requested) = requested {
+            if requested != received {
+                return Err(Error::UnexpectedBlock {
+                    requested,
+                    received,
+                });
+            }
+        }
+
+        {
+            let mut headers = self.headers.lock().unwrap();
+            verify_header(
+                &block.header,
+                headers.get(&block.header.prev_blockhash),
+                self.network,
+            )?;
+            headers.insert(block.header);
+        }
+
+        let txids = block
+            .txdata
+            .iter()
+            .map(|transaction| transaction.txid())
+            .collect::<Vec<_>>();
+        if txids.is_empty() || merkle_root(txids.clone()) == Some(block.header.merkle_root) {
+            return Ok(block);
+        }
+
+        // Only some of the transactions of the block were returned.
+        let merkle_proofs = match &self.merkle_proofs {
+            Some(merkle_proofs) => merkle_proofs,
+            None => return Err(Error::UnprovenTransaction(txids[0])),
+        };
+        for txid in txids {
+            let proof = merkle_proofs.merkle_proof(txid).await?;
+
+            if merkle_root_of_proof(txid, &proof) != block.header.merkle_root {
+                log::error!(
+                    "transaction {} of block {} does not match its merkle proof",
+                    txid,
+                    received
+                );
+                return Err(Error::UnprovenTransaction(txid));
+            }
+        }
+
+        Ok(block)
+    }
+}
+
+/// The headers verified most recently.
+#[derive(Debug, Default)]
+struct HeaderChain {
+    headers: HashMap<sha256d::Hash, BlockHeader>,
+    order: VecDeque<sha256d::Hash>,
+}
+
+impl HeaderChain {
+    fn get(&self, hash: &sha256d::Hash) -> Option<&BlockHeader> {
+        self.headers.get(hash)
+    }
+
+    fn insert(&mut self, header: BlockHeader) {
+        let hash = header.bitcoin_hash();
+
+        if self.headers.insert(hash, header).is_none() {
+            self.order.push_back(hash);
+        }
+        while self.order.len() > MAX_HEADERS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.headers.remove(&oldest);
+            }
+        }
+    }
+}
+
+fn verify_header(
+    header: &BlockHeader,
+    parent: Option<&BlockHeader>,
+    network: Network,
+) -> Result<(), Error> {
+    let hash = header.bitcoin_hash();
+    let target = header.target();
+
+    let minimum_difficulty = BlockHeader {
+        bits: minimum_difficulty_bits(network),
+        ..*header
+    };
+    if target > minimum_difficulty.target() || header.validate_pow(&target).is_err() {
+        return Err(Error::InsufficientWork(hash));
+    }
+
+    // Testnet allows blocks of minimum difficulty in between, the difficulty
+    // of a retarget changes by at most a factor of 4.
+    if let (Network::Bitcoin, Some(parent)) = (network, parent) {
+        if target > parent.target() << 2 {
+            return Err(Error::InsufficientWork(hash));
+        }
+    }
+
+    Ok(())
+}
+
+fn minimum_difficulty_bits(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin | Network::Testnet => 0x1d00_ffff,
+        Network::Regtest => 0x207f_ffff,
+    }
+}
+
+pub(super) fn merkle_root(mut hashes: Vec<sha256d::Hash>) -> Option<sha256d::Hash> {
+    if hashes.is_empty() {
+        return None;
+    }
+
+    while hashes.len() > 1 {
+        hashes = hashes
+            .chunks(2)
+            .map(|pair| {
+                // The last hash of an odd level is paired with itself.
+                let left = pair[0];
+                let right = pair.get(1).copied().unwrap_or(left);
+
+                merkle_parent(left, right)
+            })
+            .collect();
+    }
+
+    hashes.pop()
+}
+
+pub(super) fn merkle_root_of_proof(txid: sha256d::Hash, proof: &MerkleProof) -> sha256d::Hash {
+    proof
+        .merkle
+        .iter()
+        .enumerate()
+        .fold(txid, |hash, (level, sibling)| {
+            if (proof.pos >> level) & 1 == 0 {
+                merkle_parent(hash, *sibling)
+            } else {
+                merkle_parent(*sibling, hash)
+            }
+        })
+}
+
+pub(super) fn merkle_parent(left: sha256d::Hash, right: sha256d::Hash) -> sha256d::Hash {
+    let mut engine = sha256d::Hash::engine();
+    engine.input(&left[..]);
+    engine.input(&right[..]);
+
+    sha256d::Hash::from_engine(engine)
+}
+
+#[async_trait]
+impl LatestBlock for SpvConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error> {
+        let block = self.source.latest_block().await?;
+
+        self.verify(block, None).await
+    }
+}
+
+#[async_trait]
+impl BlockByHash for SpvConnector {
+    type Error = Error;
+    type Block = bitcoin::Block;
+    type BlockHash = sha256d::Hash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error> {
+        let block = self.source.block_by_hash(block_hash).await?;
+
+        self.verify(block, Some(block_hash)).await
+    }
+}
+
+#[async_trait]
+impl FilteredBlocks for SpvConnector {
+    async fn latest_filtered_block(
+        &mut self,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        let block = self.source.latest_filtered_block(pattern).await?;
+
+        self.verify(block, None).await
+    }
+
+    async fn filtered_block_by_hash(
+        &self,
+        block_hash: sha256d::Hash,
+        pattern: &TransactionPattern,
+    ) -> Result<bitcoin::Block, <Self as LatestBlock>::Error> {
+        let block = self
+            .source
+            .filtered_block_by_hash(block_hash, pattern)
+            .await?;
+
+        self.verify(block, Some(block_hash)).await
+    }
+}
+
+/// The proof is checked the same way as the blocks, so that no proof is handed
+/// out that would not convince a third party.
+#[async_trait]
+impl InclusionProof for SpvConnector {
+    type Proof = TransactionProof;
+    type TransactionHash = sha256d::Hash;
+    type Error = Error;
+
+    async fn inclusion_proof(
+        &self,
+        txid: sha256d::Hash,
+    ) -> Result<Option<TransactionProof>, Error> {
+        let proof = match self.source.inclusion_proof(txid).await? {
+            Some(proof) => proof,
+            None => return Ok(None),
+        };
+
+        let header = proof.header()?;
+        if header.bitcoin_hash() != proof.block_hash {
+            return Err(Error::UnexpectedBlock {
+                requested: proof.block_hash,
+                received: header.bitcoin_hash(),
+            });
+        }
+        verify_header(
+            &header,
+            self.headers.lock().unwrap().get(&header.prev_blockhash),
+            self.network,
+        )?;
+        if merkle_root_of_proof(txid, &proof.merkle_proof) != header.merkle_root {
+            return Err(Error::UnprovenTransaction(txid));
+        }
+
+        Ok(Some(proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::blockdata::constants::genesis_block;
+    use spectral::prelude::*;
+
+    #[test]
+    fn the_genesis_block_has_enough_work_unless_tampered_with() {
+        let genesis = genesis_block(Network::Bitcoin).header;
+        let tampered = BlockHeader {
+            nonce: genesis.nonce + 1,
+            ..genesis
+        };
+
+        assert_that(&verify_header(&genesis, None, Network::Bitcoin)).is_ok();
+        assert_that(&verify_header(&tampered, None, Network::Bitcoin)).is_err();
+    }
+
+    #[test]
+    fn the_merkle_root_is_computed_like_in_the_header() {
+        let genesis = genesis_block(Network::Bitcoin);
+        let txids = genesis
+            .txdata
+            .iter()
+            .map(|transaction| transaction.txid())
+            .collect::<Vec<_>>();
+
+        assert_that(&merkle_root(txids)).is_equal_to(Some(genesis.header.merkle_root));
+    }
+
+    #[test]
+    fn a_merkle_proof_leads_to_the_merkle_root() {
+        let txids = (0u8..3)
+            .map(|byte| sha256d::Hash::hash(&[byte]))
+            .collect::<Vec<_>>();
+        let root = merkle_root(txids.clone()).unwrap();
+
+        // The third transaction is paired with itself on the first level.
+        let proof = MerkleProof {
+            merkle: vec![txids[2], merkle_parent(txids[0], txids[1])],
+            pos: 2,
+        };
+        // Position 3 would pair the hashes in the same order as position 2,
+        // the third transaction being its own sibling.
+        let wrong_position = MerkleProof {
+            pos: 0,
+            ..proof.clone()
+        };
+
+        assert_that(&merkle_root_of_proof(txids[2], &proof)).is_equal_to(root);
+        assert_that(&merkle_root_of_proof(txids[2], &wrong_position)).is_not_equal_to(root);
+    }
+}
@@ -9,7 +9,7 @@ pub trait TransactionExt {
     fn spends_from(&self, outpoint: &OutPoint) -> bool;
     fn spends_from_with(&self, outpoint: &OutPoint, script: &[Vec<u8>]) -> bool;
     fn spends_with(&self, script: &[Vec<u8>]) -> bool;
-    fn find_output(&self, to_address: &BitcoinAddress) -> Option<(u32, &TxOut)>;
+    fn find_outputs(&self, to_address: &BitcoinAddress) -> Vec<(u32, &TxOut)>;
 }
 
 impl TransactionExt for Transaction {
@@ -42,7 +42,7 @@ impl TransactionExt for Transaction {
             .any(|txin| any_unlock_script_matches(txin, unlock_script))
     }
 
-    fn find_output(&self, to_address: &BitcoinAddress) -> Option<(u32, &TxOut)> {
+    fn find_outputs(&self, to_address: &BitcoinAddress) -> Vec<(u32, &TxOut)> {
         let to_address_script_pubkey = to_address.script_pubkey();
 
         self.output
@@ -55,7 +55,8 @@ impl TransactionExt for Transaction {
                 #[allow(clippy::cast_possible_truncation)]
                 (index as u32, txout)
             })
-            .find(|(_, txout)| txout.script_pubkey == to_address_script_pubkey)
+            .filter(|(_, txout)| txout.script_pubkey == to_address_script_pubkey)
+            .collect()
     }
 }
 
@@ -179,6 +180,37 @@ mod tests {
         assert_that(&tx.spends_to(&address2)).is_false();
     }
 
+    #[test]
+    fn find_outputs_returns_every_output_to_the_address() {
+        let address: BitcoinAddress = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".parse().unwrap();
+        let other_address: BitcoinAddress = "bc1qu5t5yrh75zca6msxzszx5mm0egu2vepu09lwqh"
+            .parse()
+            .unwrap();
+        let output = |value, address: &BitcoinAddress| TxOut {
+            value,
+            script_pubkey: address.script_pubkey(),
+        };
+
+        let tx = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: Vec::new(),
+            output: vec![
+                output(1, &address),
+                output(2, &other_address),
+                output(3, &address),
+            ],
+        };
+
+        let values = tx
+            .find_outputs(&address)
+            .into_iter()
+            .map(|(vout, txout)| (vout, txout.value))
+            .collect::<Vec<_>>();
+
+        assert_that(&values).is_equal_to(vec![(0, 1), (2, 3)]);
+    }
+
     #[test]
     fn a_witness_tx_with_unlock_script_then_unlock_script_contains_matches() {
         let tx = parse_raw_tx(WITNESS_TX);
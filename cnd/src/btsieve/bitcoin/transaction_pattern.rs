@@ -1,4 +1,7 @@
-use crate::btsieve::bitcoin::transaction_ext::TransactionExt;
+use crate::{
+    btsieve::bitcoin::transaction_ext::TransactionExt,
+    swap_protocols::rfc003::{Secret, SecretHash},
+};
 use ::bitcoin::{Address, OutPoint, Transaction};
 
 #[derive(Clone, Default, Debug, Eq, PartialEq)]
@@ -50,6 +53,45 @@ impl TransactionPattern {
             }
         }
     }
+
+    /// Classifies how `transaction` spends the HTLC at `from_outpoint`.
+    ///
+    /// The witness of an HTLC spend is `<signature> <public key> <secret>
+    /// <true> <script>` on the redeem path and `<signature> <public key>
+    /// <false> <script>` on the timeout path. `None` if the pattern has no
+    /// outpoint, the transaction does not spend it or the witness does not
+    /// follow either path, e.g. because the secret does not match
+    /// `secret_hash`.
+    pub fn spend(&self, transaction: &Transaction, secret_hash: &SecretHash) -> Option<Spend> {
+        let from_outpoint = self.from_outpoint.as_ref()?;
+        let witness = &transaction
+            .input
+            .iter()
+            .find(|txin| &txin.previous_output == from_outpoint)?
+            .witness;
+
+        let branch = witness.len().checked_sub(2).map(|index| &witness[index])?;
+        match branch.as_slice() {
+            [1] => witness
+                .iter()
+                .find_map(|item| match Secret::from_vec(item) {
+                    Ok(secret) if secret.hash() == *secret_hash => Some(secret),
+                    _ => None,
+                })
+                .map(|secret| Spend::Redeem { secret }),
+            [] => Some(Spend::Refund),
+            _ => None,
+        }
+    }
+}
+
+/// How an HTLC was spent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Spend {
+    /// Through the redeem path, which reveals the secret.
+    Redeem { secret: Secret },
+    /// Through the timeout path.
+    Refund,
 }
 
 #[cfg(test)]
@@ -58,8 +100,10 @@ mod tests {
     use bitcoin::{
         consensus::deserialize,
         hashes::{hex::FromHex, sha256d},
+        Script, TxIn,
     };
     use spectral::prelude::*;
+    use std::str::FromStr;
 
     const WITNESS_TX: & str = "0200000000010124e06fe5594b941d06c7385dc7307ec694a41f7d307423121855ee17e47e06ad0100000000ffffffff0137aa0b000000000017a914050377baa6e8c5a07aed125d0ef262c6d5b67a038705483045022100d780139514f39ed943179e4638a519101bae875ec1220b226002bcbcb147830b0220273d1efb1514a77ee3dd4adee0e896b7e76be56c6d8e73470ae9bd91c91d700c01210344f8f459494f74ebb87464de9b74cdba3709692df4661159857988966f94262f20ec9e9fb3c669b2354ea026ab3da82968a2e7ab9398d5cbed4e78e47246f2423e01015b63a82091d6a24697ed31932537ae598d3de3131e1fcd0641b9ac4be7afcb376386d71e8876a9149f4a0cf348b478336cb1d87ea4c8313a7ca3de1967029000b27576a91465252e57f727a27f32c77098e14d88d8dbec01816888ac00000000";
 
@@ -149,4 +193,99 @@ mod tests {
         let result = pattern.matches(&tx);
         assert_that(&result).is_true();
     }
+
+    fn htlc_spend(witness: Vec<Vec<u8>>) -> Transaction {
+        Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Script::new(),
+                sequence: 0,
+                witness,
+            }],
+            output: vec![],
+        }
+    }
+
+    fn spent_pattern(outpoint: OutPoint) -> TransactionPattern {
+        TransactionPattern {
+            to_address: None,
+            from_outpoint: Some(outpoint),
+            unlock_script: None,
+        }
+    }
+
+    #[test]
+    fn a_spend_revealing_the_secret_is_a_redeem() {
+        let secret = Secret::from(*b"This is our favourite passphrase");
+        let tx = htlc_spend(vec![
+            vec![],                          // Signature
+            vec![],                          // Public key
+            secret.as_raw_secret().to_vec(), // Secret
+            vec![1u8],                       // Bool to enter redeem branch
+            vec![],                          // Previous Script
+        ]);
+
+        assert_that(&spent_pattern(OutPoint::null()).spend(&tx, &secret.hash()))
+            .is_equal_to(Some(Spend::Redeem { secret }));
+    }
+
+    #[test]
+    fn a_spend_through_the_timeout_path_is_a_refund() {
+        let secret = Secret::from(*b"This is our favourite passphrase");
+        let tx = htlc_spend(vec![
+            vec![], // Signature
+            vec![], // Public key
+            vec![], // Bool to enter refund branch
+            vec![], // Previous Script
+        ]);
+
+        assert_that(&spent_pattern(OutPoint::null()).spend(&tx, &secret.hash()))
+            .is_equal_to(Some(Spend::Refund));
+    }
+
+    #[test]
+    fn a_redeem_with_a_different_secret_is_not_classified() {
+        let secret = Secret::from(*b"This is our favourite passphrase");
+        let tx = htlc_spend(vec![
+            vec![],
+            vec![],
+            secret.as_raw_secret().to_vec(),
+            vec![1u8],
+            vec![],
+        ]);
+        let secret_hash = SecretHash::from_str(
+            "bfbfbfbfbfbfbfbfbfbfbfbfbfbfbfbf\
+             bfbfbfbfbfbfbfbfbfbfbfbfbfbfbfbf",
+        )
+        .unwrap();
+
+        assert_that(&spent_pattern(OutPoint::null()).spend(&tx, &secret_hash)).is_none();
+    }
+
+    #[test]
+    fn the_secret_is_extracted_from_a_mainnet_redeem() {
+        let tx = parse_raw_tx(WITNESS_TX);
+        let secret = Secret::from_vec(
+            &hex::decode("ec9e9fb3c669b2354ea026ab3da82968a2e7ab9398d5cbed4e78e47246f2423e")
+                .unwrap(),
+        )
+        .unwrap();
+        let outpoint = create_outpoint(
+            "ad067ee417ee5518122374307d1fa494c67e30c75d38c7061d944b59e56fe024",
+            1u32,
+        );
+
+        assert_that(&spent_pattern(outpoint).spend(&tx, &secret.hash()))
+            .is_equal_to(Some(Spend::Redeem { secret }));
+    }
+
+    #[test]
+    fn a_transaction_not_spending_the_outpoint_is_not_classified() {
+        let tx = parse_raw_tx(WITNESS_TX);
+        let secret = Secret::from(*b"This is our favourite passphrase");
+
+        assert_that(&spent_pattern(OutPoint::null()).spend(&tx, &secret.hash())).is_none();
+    }
 }
@@ -0,0 +1,102 @@
+use crate::btsieve::bitcoin::{decode_response, spv_connector::merkle_parent, Error, MerkleProof};
+use bitcoin::{consensus::encode::serialize_hex, hashes::sha256d, BitcoinHash, BlockHeader};
+use serde::Serialize;
+
+/// Proves that a transaction is in a block: the merkle branch leads from the
+/// transaction to the merkle root in the header of the block, whose hash in
+/// turn has to meet the difficulty its header commits to.
+///
+/// Whether the block is part of the chain can be checked against any source
+/// of Bitcoin headers, this does not need to be trusted.
+#[derive(Clone, Debug, Serialize)]
+pub struct TransactionProof {
+    pub block_hash: sha256d::Hash,
+    /// Hex encoded header in its consensus encoding.
+    pub header: String,
+    #[serde(flatten)]
+    pub merkle_proof: MerkleProof,
+}
+
+impl TransactionProof {
+    pub fn new(header: &BlockHeader, merkle_proof: MerkleProof) -> Self {
+        Self {
+            block_hash: header.bitcoin_hash(),
+            header: serialize_hex(header),
+            merkle_proof,
+        }
+    }
+
+    /// The proof for a transaction of a complete block, `None` if the
+    /// transaction is not in the block.
+    pub fn from_block(block: &bitcoin::Block, txid: sha256d::Hash) -> Option<Self> {
+        let mut hashes = block
+            .txdata
+            .iter()
+            .map(|transaction| transaction.txid())
+            .collect::<Vec<_>>();
+        let pos = hashes.iter().position(|hash| *hash == txid)?;
+
+        let mut merkle = Vec::new();
+        let mut index = pos;
+        while hashes.len() > 1 {
+            // The last hash of an odd level is paired with itself.
+            let sibling = hashes.get(index ^ 1).copied().unwrap_or(hashes[index]);
+            merkle.push(sibling);
+
+            hashes = hashes
+                .chunks(2)
+                .map(|pair| merkle_parent(pair[0], pair.get(1).copied().unwrap_or(pair[0])))
+                .collect();
+            index /= 2;
+        }
+
+        Some(Self::new(&block.header, MerkleProof {
+            merkle,
+            pos: pos as u32,
+        }))
+    }
+
+    pub fn header(&self) -> Result<BlockHeader, Error> {
+        decode_response(self.header.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btsieve::bitcoin::spv_connector::{merkle_root, merkle_root_of_proof};
+    use bitcoin::{blockdata::constants::genesis_block, hashes::Hash, Network};
+    use spectral::prelude::*;
+
+    #[test]
+    fn the_proof_of_a_transaction_leads_to_the_merkle_root_of_its_block() {
+        let genesis = genesis_block(Network::Bitcoin);
+        let mut block = genesis.clone();
+        block.txdata = (0..5).map(|_| genesis.txdata[0].clone()).collect();
+        for (lock_time, transaction) in block.txdata.iter_mut().enumerate() {
+            transaction.lock_time = lock_time as u32;
+        }
+        let txids = block
+            .txdata
+            .iter()
+            .map(|transaction| transaction.txid())
+            .collect::<Vec<_>>();
+        block.header.merkle_root = merkle_root(txids.clone()).unwrap();
+
+        for txid in txids {
+            let proof = TransactionProof::from_block(&block, txid).unwrap();
+
+            assert_that(&merkle_root_of_proof(txid, &proof.merkle_proof))
+                .is_equal_to(block.header.merkle_root);
+            assert_that(&proof.header().unwrap()).is_equal_to(block.header);
+        }
+    }
+
+    #[test]
+    fn there_is_no_proof_for_a_transaction_of_another_block() {
+        let genesis = genesis_block(Network::Bitcoin);
+        let other_txid = sha256d::Hash::hash(b"some other transaction");
+
+        assert_that(&TransactionProof::from_block(&genesis, other_txid)).is_none();
+    }
+}
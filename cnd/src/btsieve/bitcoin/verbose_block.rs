@@ -0,0 +1,123 @@
+use crate::btsieve::bitcoin::{decode_response, Error};
+use bitcoin::{hashes::sha256d, BlockHeader, Script};
+use serde::Deserialize;
+
+/// A block as returned by bitcoind's verbose block endpoint, equivalent to
+/// `getblock <hash> 2`.
+///
+/// Only the fields needed to rebuild the header and to select transactions by
+/// their output scripts are deserialized.
+#[derive(Debug, Deserialize)]
+pub struct VerboseBlock {
+    version: u32,
+    #[serde(rename = "previousblockhash")]
+    prev_blockhash: Option<sha256d::Hash>,
+    #[serde(rename = "merkleroot")]
+    merkle_root: sha256d::Hash,
+    time: u32,
+    bits: String,
+    nonce: u32,
+    tx: Vec<VerboseTransaction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseTransaction {
+    hex: String,
+    vout: Vec<VerboseOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseOutput {
+    #[serde(rename = "scriptPubKey")]
+    script_pubkey: VerboseScript,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseScript {
+    hex: String,
+}
+
+impl VerboseBlock {
+    /// Rebuild the block, decoding only the transactions that pay to
+    /// `script_pubkey`.
+    pub fn into_block_paying_to(self, script_pubkey: &Script) -> Result<bitcoin::Block, Error> {
+        let Self {
+            version,
+            prev_blockhash,
+            merkle_root,
+            time,
+            bits,
+            nonce,
+            tx,
+        } = self;
+        let script_pubkey = hex::encode(script_pubkey.as_bytes());
+
+        let header = BlockHeader {
+            version,
+            // The genesis block does not have a parent.
+            prev_blockhash: prev_blockhash.unwrap_or_default(),
+            merkle_root,
+            time,
+            bits: u32::from_str_radix(&bits, 16).map_err(|_| Error::MalformedBits(bits.clone()))?,
+            nonce,
+        };
+
+        let txdata = tx
+            .into_iter()
+            .filter(|transaction| {
+                transaction
+                    .vout
+                    .iter()
+                    .any(|output| output.script_pubkey.hex == script_pubkey)
+            })
+            .map(|transaction| decode_response(transaction.hex))
+            .collect::<Result<_, _>>()?;
+
+        Ok(bitcoin::Block { header, txdata })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    const BLOCK: &str = r#"{
+        "hash": "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+        "version": 536870912,
+        "merkleroot": "b8fa8e5fb94e8e2fd6ee0f5f5e2e3c5e8fa1bd7cd5bc07dc4e09cb93a2b6e1fd",
+        "time": 1575277862,
+        "nonce": 1,
+        "bits": "207fffff",
+        "tx": [
+            {
+                "txid": "5e2ca1b5ba9ed0b5bbb1a6e2df66c2b4af96daa80c9ab2de8346c4c1a3ea7cd1",
+                "vout": [
+                    { "value": 47.0, "n": 0, "scriptPubKey": { "hex": "a9142464790f3a3fddb132691fac9fd02549cdc09ff487" } },
+                    { "value": 3.0, "n": 1, "scriptPubKey": { "hex": "a914c40a2c4fd9dcad5e1694a41ca46d337eb59369d787" } }
+                ],
+                "hex": "02000000014135047eff77c95bce4955f630bc3e334690d31517176dbc23e9345493c48ecf000000004847304402200da78118d6970bca6f152a6ca81fa8c4dde856680eb6564edb329ce1808207c402203b3b4890dd203cc4c9361bbbeb7ebce70110d4b07f411208b2540b10373755ba01feffffff02644024180100000017a9142464790f3a3fddb132691fac9fd02549cdc09ff48700a3e1110000000017a914c40a2c4fd9dcad5e1694a41ca46d337eb59369d78765000000"
+            },
+            {
+                "txid": "not decoded because it does not pay to the script",
+                "vout": [
+                    { "value": 50.0, "n": 0, "scriptPubKey": { "hex": "0014aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa" } }
+                ],
+                "hex": "not valid hex"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn only_transactions_paying_to_the_script_are_decoded() {
+        let verbose_block = serde_json::from_str::<VerboseBlock>(BLOCK).unwrap();
+        let script_pubkey =
+            Script::from(hex::decode("a914c40a2c4fd9dcad5e1694a41ca46d337eb59369d787").unwrap());
+
+        let block = verbose_block.into_block_paying_to(&script_pubkey).unwrap();
+
+        assert_that(&block.txdata).has_length(1);
+        assert_that(&block.header.bits).is_equal_to(0x207f_ffff);
+        assert_that(&block.header.prev_blockhash).is_equal_to(sha256d::Hash::default());
+    }
+}
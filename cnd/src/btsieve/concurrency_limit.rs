@@ -0,0 +1,120 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// How many requests a connector sends to its node at the same time if not
+/// configured otherwise.
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// A semaphore that bounds the number of in-flight requests of a connector.
+///
+/// Clones share the same permits, hence all clones of a connector are
+/// limited together.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimit {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    available: usize,
+    waiting: Vec<Waker>,
+}
+
+/// Allows a single request to be in-flight, the permit is returned once this
+/// is dropped.
+#[derive(Debug)]
+pub struct Permit {
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for ConcurrencyLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_REQUESTS)
+    }
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max_concurrent_requests: usize) -> Self {
+        assert!(
+            max_concurrent_requests > 0,
+            "at least one request needs to be allowed"
+        );
+
+        Self {
+            state: Arc::new(Mutex::new(State {
+                available: max_concurrent_requests,
+                waiting: Vec::new(),
+            })),
+        }
+    }
+
+    /// Wait until a request may be sent.
+    pub fn acquire(&self) -> impl Future<Output = Permit> {
+        Acquire {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    pub fn available(&self) -> usize {
+        self.state.lock().unwrap().available
+    }
+}
+
+struct Acquire {
+    state: Arc<Mutex<State>>,
+}
+
+impl Future for Acquire {
+    type Output = Permit;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.available == 0 {
+            state.waiting.push(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        state.available -= 1;
+
+        Poll::Ready(Permit {
+            state: Arc::clone(&self.state),
+        })
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.available += 1;
+
+        // Waiters may have given up in the meantime, waking all of them
+        // guarantees the permit is picked up by someone who still needs it.
+        for waker in state.waiting.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_core::executor::block_on;
+    use spectral::prelude::*;
+
+    #[test]
+    fn permits_are_returned_once_dropped() {
+        let limit = ConcurrencyLimit::new(2);
+
+        let first = block_on(limit.acquire());
+        let _second = block_on(limit.acquire());
+        assert_that(&limit.available()).is_equal_to(0);
+
+        drop(first);
+        assert_that(&limit.available()).is_equal_to(1);
+    }
+}
@@ -1,20 +1,34 @@
+mod quorum_transport;
+mod receipts_root;
 mod transaction_pattern;
 mod web3_connector;
+mod web3_transport;
 
 pub use self::{
-    transaction_pattern::{Event, Topic, TransactionPattern},
+    quorum_transport::QuorumTransport,
+    receipts_root::ReceiptProof,
+    transaction_pattern::{
+        Event, Spend, Topic, TransactionPattern, REDEEM_LOG_MSG, REFUND_LOG_MSG,
+    },
     web3_connector::Web3Connector,
+    web3_transport::{EventLoopHandle, ReqwestHttp, Web3Transport},
 };
 use crate::{
-    btsieve::{BlockByHash, LatestBlock, MatchingTransactions, ReceiptByHash},
+    btsieve::{BlockByHash, LatestBlock, MatchingTransactions, PastTransactions, ReceiptByHash},
     ethereum::{Block, Transaction, TransactionAndReceipt, TransactionReceipt, H256, U256},
+    swap_tracing::{current_scope, InScope},
 };
-use futures_core::{compat::Future01CompatExt, future::join, FutureExt, TryFutureExt};
-use std::{collections::HashSet, fmt::Debug, ops::Add};
-use tokio::{
-    prelude::{stream, Stream},
-    timer::Delay,
+use async_trait::async_trait;
+use futures_core::{
+    channel::mpsc,
+    compat::Future01CompatExt,
+    future::{self, join, AbortHandle, Either},
+    sink::SinkExt,
+    stream::{self, BoxStream, StreamExt},
+    FutureExt, TryFutureExt,
 };
+use std::{collections::HashSet, fmt::Debug, ops::Add, panic::AssertUnwindSafe};
+use tokio::timer::Delay;
 
 impl<C, E> MatchingTransactions<TransactionPattern> for C
 where
@@ -31,18 +45,24 @@ where
         &self,
         pattern: TransactionPattern,
         reference_timestamp: Option<u32>,
-    ) -> Box<dyn Stream<Item = Self::Transaction, Error = ()> + Send> {
-        let (block_queue, next_block) = async_std::sync::channel(1);
-        let (find_parent_queue, next_find_parent) = async_std::sync::channel(5);
-        let (look_in_the_past_queue, next_look_in_the_past) = async_std::sync::channel(5);
+    ) -> BoxStream<'static, Self::Transaction> {
+        let (block_queue, mut next_block) = mpsc::channel(1);
+        let (mut find_parent_queue, mut next_find_parent) = mpsc::channel(5);
+        let (look_in_the_past_queue, mut next_look_in_the_past) = mpsc::channel(5);
+
+        // Every lookup task runs until it is aborted, if one of them stops
+        // nonetheless the others would wait for it forever. Sending to a task
+        // only fails once it stopped, hence the results of sending are ignored.
+        let (terminated_queue, mut terminated) = mpsc::channel(1);
 
         let reference_timestamp = reference_timestamp.map(U256::from);
+        let mut tasks = Vec::new();
 
-        spawn(self.clone(), {
+        spawn(self.clone(), &mut tasks, &terminated_queue, {
             let mut connector = self.clone();
-            let block_queue = block_queue.clone();
-            let find_parent_queue = find_parent_queue.clone();
-            let look_in_the_past_queue = look_in_the_past_queue.clone();
+            let mut block_queue = block_queue.clone();
+            let mut find_parent_queue = find_parent_queue.clone();
+            let mut look_in_the_past_queue = look_in_the_past_queue.clone();
 
             async move {
                 let mut sent_blockhashes: HashSet<H256> = HashSet::new();
@@ -51,24 +71,24 @@ where
                     Delay::new(std::time::Instant::now().add(std::time::Duration::from_secs(1)))
                         .compat()
                         .await
-                        .unwrap();
+                        .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
 
-                    match connector.latest_block().compat().await {
+                    match connector.latest_block().await {
                         Ok(Some(block)) if block.hash.is_some() => {
                             let blockhash = block.hash.expect("cannot fail");
 
                             if !sent_blockhashes.contains(&blockhash) {
                                 sent_blockhashes.insert(blockhash);
 
-                                join(
+                                let _ = join(
                                     block_queue.send(block.clone()),
                                     find_parent_queue.send((blockhash, block.parent_hash)),
                                 )
                                 .await;
 
                                 if sent_blockhashes.len() == 1 {
-                                    look_in_the_past_queue.send(block.parent_hash).await
-                                };
+                                    let _ = look_in_the_past_queue.send(block.parent_hash).await;
+                                }
                             }
                         }
                         Ok(Some(_)) => {
@@ -85,20 +105,20 @@ where
             }
         });
 
-        let (fetch_block_by_hash_queue, next_hash) = async_std::sync::channel(5);
+        let (fetch_block_by_hash_queue, mut next_hash) = mpsc::channel(5);
 
-        spawn(self.clone(), {
+        spawn(self.clone(), &mut tasks, &terminated_queue, {
             let connector = self.clone();
-            let block_queue = block_queue.clone();
-            let fetch_block_by_hash_queue = fetch_block_by_hash_queue.clone();
+            let mut block_queue = block_queue.clone();
+            let mut fetch_block_by_hash_queue = fetch_block_by_hash_queue.clone();
 
             async move {
                 loop {
-                    match next_hash.recv().await {
+                    match next_hash.next().await {
                         Some(blockhash) => {
-                            match connector.block_by_hash(blockhash).compat().await {
+                            match connector.block_by_hash(blockhash).await {
                                 Ok(Some(block)) => {
-                                    join(
+                                    let _ = join(
                                         block_queue.send(block.clone()),
                                         find_parent_queue.send((blockhash, block.parent_hash)),
                                     )
@@ -114,49 +134,55 @@ where
                                         e
                                     );
 
-                                    fetch_block_by_hash_queue.send(blockhash).await
+                                    let _ = fetch_block_by_hash_queue.send(blockhash).await;
                                 }
                             };
                         }
-                        None => unreachable!("sender cannot be dropped"),
+                        None => {
+                            log::warn!("Queue of blocks to fetch by hash was closed");
+                            return;
+                        }
                     }
                 }
             }
         });
 
-        spawn(self.clone(), {
-            let fetch_block_by_hash_queue = fetch_block_by_hash_queue.clone();
+        spawn(self.clone(), &mut tasks, &terminated_queue, {
+            let mut fetch_block_by_hash_queue = fetch_block_by_hash_queue.clone();
 
             async move {
                 let mut prev_blockhashes: HashSet<H256> = HashSet::new();
 
                 loop {
-                    match next_find_parent.recv().await {
+                    match next_find_parent.next().await {
                         Some((blockhash, parent_blockhash)) => {
                             prev_blockhashes.insert(blockhash);
 
                             if !prev_blockhashes.contains(&parent_blockhash)
                                 && prev_blockhashes.len() > 1
                             {
-                                fetch_block_by_hash_queue.send(parent_blockhash).await
+                                let _ = fetch_block_by_hash_queue.send(parent_blockhash).await;
                             }
                         }
-                        None => unreachable!("senders cannot be dropped"),
+                        None => {
+                            log::warn!("Queue of blocks to find the parent of was closed");
+                            return;
+                        }
                     }
                 }
             }
         });
 
-        spawn(self.clone(), {
+        spawn(self.clone(), &mut tasks, &terminated_queue, {
             let connector = self.clone();
-            let block_queue = block_queue.clone();
-            let look_in_the_past_queue = look_in_the_past_queue.clone();
+            let mut block_queue = block_queue.clone();
+            let mut look_in_the_past_queue = look_in_the_past_queue.clone();
 
             async move {
                 loop {
-                    match next_look_in_the_past.recv().await {
+                    match next_look_in_the_past.next().await {
                         Some(parent_blockhash) => {
-                            match connector.block_by_hash(parent_blockhash).compat().await {
+                            match connector.block_by_hash(parent_blockhash).await {
                                 Ok(Some(block)) => {
                                     let younger_than_reference_timestamp = reference_timestamp
                                         .map(|reference_timestamp| {
@@ -164,7 +190,7 @@ where
                                         })
                                         .unwrap_or(false);
                                     if younger_than_reference_timestamp {
-                                        join(
+                                        let _ = join(
                                             block_queue.send(block.clone()),
                                             look_in_the_past_queue.send(block.parent_hash),
                                         )
@@ -184,60 +210,81 @@ where
                                         e
                                     );
 
-                                    look_in_the_past_queue.send(parent_blockhash).await
+                                    let _ = look_in_the_past_queue.send(parent_blockhash).await;
                                 }
                             }
                         }
-                        None => unreachable!("senders cannot be dropped"),
+                        None => {
+                            log::warn!("Queue of blocks to look at in the past was closed");
+                            return;
+                        }
                     }
                 }
             }
         });
 
-        let (matching_transaction_queue, matching_transaction) = async_std::sync::channel(1);
+        let (matching_transaction_queue, mut matching_transaction) = mpsc::channel(1);
 
-        spawn(self.clone(), {
+        spawn(self.clone(), &mut tasks, &terminated_queue, {
             let connector = self.clone();
-            let matching_transaction_queue = matching_transaction_queue.clone();
+            let mut matching_transaction_queue = matching_transaction_queue.clone();
 
             async move {
                 loop {
-                    match next_block.recv().await {
+                    match next_block.next().await {
                         Some(block) => {
-                            let needs_receipt = pattern.needs_receipts(&block);
-
-                            for transaction in block.transactions.into_iter() {
-                                if needs_receipt {
-                                    let result =
-                                        connector.receipt_by_hash(transaction.hash).compat().await;
+                            if pattern.needs_receipts(&block) {
+                                let transaction_hashes = block
+                                    .transactions
+                                    .iter()
+                                    .map(|transaction| transaction.hash)
+                                    .collect();
+
+                                let receipts = match connector
+                                    .receipts_by_hashes(transaction_hashes)
+                                    .await
+                                {
+                                    Ok(receipts) => receipts,
+                                    Err(e) => {
+                                        log::warn!(
+                                                "Could not retrieve transaction receipts for block {:?}: {:?}",
+                                                block.hash,
+                                                e
+                                            );
+                                        continue;
+                                    }
+                                };
 
-                                    let receipt = match result {
-                                        Ok(Some(receipt)) => receipt,
-                                        Ok(None) => {
-                                            log::warn!("Could not get transaction receipt");
-                                            continue;
-                                        }
-                                        Err(e) => {
+                                for (transaction, receipt) in
+                                    block.transactions.into_iter().zip(receipts)
+                                {
+                                    let receipt = match receipt {
+                                        Some(receipt) => receipt,
+                                        None => {
                                             log::warn!(
-                                            "Could not retrieve transaction receipt for {}: {:?}",
-                                            transaction.hash,
-                                            e
-                                        );
+                                                "Could not get transaction receipt for {}",
+                                                transaction.hash
+                                            );
                                             continue;
                                         }
                                     };
 
                                     if pattern.matches(&transaction, Some(&receipt)) {
-                                        matching_transaction_queue
+                                        let _ = matching_transaction_queue
                                             .send(TransactionAndReceipt {
                                                 transaction,
                                                 receipt,
                                             })
                                             .await;
                                     }
-                                } else if pattern.matches(&transaction, None) {
-                                    let result =
-                                        connector.receipt_by_hash(transaction.hash).compat().await;
+                                }
+                            } else {
+                                for transaction in block.transactions.into_iter() {
+                                    if !pattern.matches(&transaction, None) {
+                                        continue;
+                                    }
+
+                                    let result = connector.receipt_by_hash(transaction.hash).await;
 
                                     let receipt = match result {
                                         Ok(Some(receipt)) => receipt,
@@ -247,15 +294,15 @@ where
                                         }
                                         Err(e) => {
                                             log::warn!(
-                                                            "Could not retrieve transaction receipt for matching transaction {}: {:?}",
-                                                            transaction.hash,
-                                                            e
-                                                );
+                                                "Could not retrieve transaction receipt for matching transaction {}: {:?}",
+                                                transaction.hash,
+                                                e
+                                            );
                                             continue;
                                         }
                                     };
 
-                                    matching_transaction_queue
+                                    let _ = matching_transaction_queue
                                         .send(TransactionAndReceipt {
                                             transaction,
                                             receipt,
@@ -264,31 +311,177 @@ where
                                 }
                             }
                         }
-                        None => unreachable!("senders cannot be dropped"),
+                        None => {
+                            log::warn!("Queue of blocks to match was closed");
+                            return;
+                        }
                     }
                 }
             }
         });
 
-        let matching_transaction = async move {
-            matching_transaction
-                .recv()
+        let tasks = AbortOnDrop(tasks);
+
+        // The stream ends without a transaction if a lookup task stopped, the
+        // swap is then restarted by the supervisor.
+        stream::once(async move {
+            // Keep the lookup tasks alive until a transaction was found or the
+            // stream was dropped.
+            let _tasks = tasks;
+
+            match future::select(matching_transaction.next(), terminated.next()).await {
+                Either::Left((transaction, _)) => transaction,
+                Either::Right(_) => {
+                    log::warn!("Stopped looking for matching transactions");
+                    None
+                }
+            }
+        })
+        .filter_map(future::ready)
+        .boxed()
+    }
+}
+
+#[async_trait]
+impl<C, E> PastTransactions<TransactionPattern> for C
+where
+    C: LatestBlock<Block = Option<Block<Transaction>>, Error = E>
+        + BlockByHash<Block = Option<Block<Transaction>>, BlockHash = H256, Error = E>
+        + ReceiptByHash<Receipt = Option<TransactionReceipt>, TransactionHash = H256, Error = E>
+        + Clone,
+    E: Debug + Send + 'static,
+{
+    type Transaction = TransactionAndReceipt;
+
+    async fn past_transaction(
+        &self,
+        pattern: TransactionPattern,
+        timestamp: u32,
+    ) -> anyhow::Result<Option<TransactionAndReceipt>> {
+        let mut connector = self.clone();
+        let timestamp = U256::from(timestamp);
+
+        let mut block = connector
+            .latest_block()
+            .await
+            .map_err(|e| anyhow::anyhow!("could not get latest block: {:?}", e))?
+            .ok_or_else(|| anyhow::anyhow!("could not get latest block"))?;
+
+        loop {
+            if let Some(transaction) = matching_transaction_in(&connector, &pattern, &block).await?
+            {
+                return Ok(Some(transaction));
+            }
+
+            // The first block older than `timestamp` was looked at as well
+            // because block times are not strictly increasing.
+            if block.timestamp < timestamp {
+                return Ok(None);
+            }
+
+            let parent_hash = block.parent_hash;
+            block = connector
+                .block_by_hash(parent_hash)
                 .await
-                .expect("sender cannot be dropped")
-        };
+                .map_err(|e| {
+                    anyhow::anyhow!("could not get block with hash {}: {:?}", parent_hash, e)
+                })?
+                .ok_or_else(|| anyhow::anyhow!("block with hash {} does not exist", parent_hash))?;
+        }
+    }
+}
 
-        Box::new(stream::futures_unordered(vec![matching_transaction
-            .unit_error()
-            .boxed()
-            .compat()]))
+async fn matching_transaction_in<C, E>(
+    connector: &C,
+    pattern: &TransactionPattern,
+    block: &Block<Transaction>,
+) -> anyhow::Result<Option<TransactionAndReceipt>>
+where
+    C: ReceiptByHash<Receipt = Option<TransactionReceipt>, TransactionHash = H256, Error = E>,
+    E: Debug,
+{
+    // Without a receipt only the fields of the transaction itself are
+    // matched, the events are checked once the receipts are there.
+    let candidates = block
+        .transactions
+        .iter()
+        .filter(|transaction| pattern.matches(transaction, None))
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return Ok(None);
     }
+
+    let receipts = connector
+        .receipts_by_hashes(
+            candidates
+                .iter()
+                .map(|transaction| transaction.hash)
+                .collect(),
+        )
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "could not retrieve transaction receipts for block {:?}: {:?}",
+                block.hash,
+                e
+            )
+        })?;
+
+    for (transaction, receipt) in candidates.into_iter().zip(receipts) {
+        let receipt = receipt.ok_or_else(|| {
+            anyhow::anyhow!("could not get transaction receipt for {}", transaction.hash)
+        })?;
+
+        if pattern.matches(transaction, Some(&receipt)) {
+            return Ok(Some(TransactionAndReceipt {
+                transaction: transaction.clone(),
+                receipt,
+            }));
+        }
+    }
+
+    Ok(None)
 }
 
+/// Spawn a lookup task, which reports on `terminated_queue` if it stops other
+/// than by being aborted.
+///
+/// The task stays in the scope of the swap that spawned it.
 fn spawn(
     mut executor: impl tokio::executor::Executor,
+    tasks: &mut Vec<AbortHandle>,
+    terminated_queue: &mpsc::Sender<()>,
     future: impl std::future::Future<Output = ()> + Send + 'static + Sized,
 ) {
+    let mut terminated_queue = terminated_queue.clone();
+    let future = AssertUnwindSafe(future)
+        .catch_unwind()
+        .then(|result| async move {
+            if result.is_err() {
+                log::error!("Lookup task for matching transactions panicked");
+            }
+
+            let _ = terminated_queue.send(()).await;
+        });
+    let (future, handle) = future::abortable(future);
+    tasks.push(handle);
+
     executor
-        .spawn(Box::new(future.unit_error().boxed().compat()))
+        .spawn(Box::new(InScope::new(
+            current_scope(),
+            future.map(|_| ()).unit_error().boxed().compat(),
+        )))
         .unwrap()
 }
+
+/// Aborts the wrapped tasks once dropped.
+#[derive(Debug)]
+struct AbortOnDrop(Vec<AbortHandle>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for handle in self.0.iter() {
+            handle.abort();
+        }
+    }
+}
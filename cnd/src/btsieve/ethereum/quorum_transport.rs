@@ -0,0 +1,376 @@
+use crate::{
+    btsieve::ethereum::Web3Transport,
+    ethereum::web3::{self, rpc, BatchTransport, RequestId, Transport},
+};
+use futures::Future;
+use futures_core::{
+    compat::Future01CompatExt,
+    future::{join_all, FutureExt, TryFutureExt},
+};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How long a node that failed is only asked once the other nodes failed as
+/// well.
+const RETRY_FAILED_NODE_AFTER: Duration = Duration::from_secs(30);
+
+/// Sends requests to several nodes of the same chain, e.g. an own node and
+/// RPC providers.
+///
+/// Blocks and receipts decide which state a swap is in, a node that lies about
+/// them could e.g. make cnd believe an HTLC was funded. Hence they are only
+/// trusted if `quorum` nodes return the same and none of them returns a
+/// different one. All other requests are sent to the first node that works.
+#[derive(Clone, Debug)]
+pub struct QuorumTransport {
+    nodes: Arc<Vec<Web3Transport>>,
+    quorum: usize,
+    failed_at: Arc<Mutex<Vec<Option<Instant>>>>,
+}
+
+impl QuorumTransport {
+    pub fn new(nodes: Vec<Web3Transport>, quorum: usize) -> Self {
+        let failed_at = vec![None; nodes.len()];
+
+        Self {
+            nodes: Arc::new(nodes),
+            quorum,
+            failed_at: Arc::new(Mutex::new(failed_at)),
+        }
+    }
+
+    /// The indices of the nodes, the ones that did not fail recently first.
+    fn nodes_by_health(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let failed_at = self.failed_at.lock().unwrap();
+
+        let (mut healthy, unhealthy): (Vec<_>, Vec<_>) =
+            (0..self.nodes.len()).partition(|index| match failed_at[*index] {
+                Some(failed_at) => now >= failed_at + RETRY_FAILED_NODE_AFTER,
+                None => true,
+            });
+        healthy.extend(unhealthy);
+
+        healthy
+    }
+
+    fn record_health(&self, index: usize, result: &Result<Value, web3::Error>) {
+        let mut failed_at = self.failed_at.lock().unwrap();
+
+        match result {
+            Ok(_) | Err(web3::Error::Rpc(_)) => {
+                if failed_at[index].take().is_some() {
+                    log::info!("Ethereum node {} is answering again", index);
+                }
+            }
+            Err(e) => {
+                if failed_at[index].is_none() {
+                    log::warn!(
+                        "Ethereum node {} failed, failing over to the other nodes: {:?}",
+                        index,
+                        e
+                    );
+                }
+                failed_at[index] = Some(Instant::now());
+            }
+        }
+    }
+
+    async fn execute_on(
+        self,
+        index: usize,
+        method: String,
+        params: Vec<Value>,
+    ) -> Result<Value, web3::Error> {
+        let result = self.nodes[index].execute(&method, params).compat().await;
+        self.record_health(index, &result);
+
+        result
+    }
+
+    async fn fail_over(self, method: String, params: Vec<Value>) -> Result<Value, web3::Error> {
+        let mut result = Err(web3::Error::Unreachable);
+
+        for index in self.nodes_by_health() {
+            result = self
+                .clone()
+                .execute_on(index, method.clone(), params.clone())
+                .await;
+
+            match result {
+                // An error of the JSON-RPC API is an answer, the other nodes
+                // would most likely return the same.
+                Ok(_) | Err(web3::Error::Rpc(_)) => return result,
+                Err(_) => continue,
+            }
+        }
+
+        result
+    }
+
+    async fn ask_quorum(self, method: String, params: Vec<Value>) -> Result<Value, web3::Error> {
+        let responses = join_all((0..self.nodes.len()).map(|index| {
+            self.clone()
+                .execute_on(index, method.clone(), params.clone())
+        }))
+        .await
+        .into_iter()
+        .map(Result::ok)
+        .collect::<Vec<_>>();
+
+        agreed_response(&method, responses, self.quorum)
+    }
+
+    async fn send_call(self, method: String, params: Vec<Value>) -> Result<Value, web3::Error> {
+        match method.as_str() {
+            "eth_getBlockByHash" | "eth_getTransactionReceipt" => {
+                self.ask_quorum(method, params).await
+            }
+            // Nodes that follow the same chain can still be at different
+            // heights, the latest block of one of them is confirmed by asking
+            // all of them for the block with its hash instead.
+            "eth_getBlockByNumber" if params.first() == Some(&json!("latest")) => {
+                let block = self.clone().fail_over(method, params.clone()).await?;
+                let hash = match block.get("hash") {
+                    Some(hash) if !hash.is_null() => hash.clone(),
+                    _ => return Ok(block),
+                };
+                let full_transactions = params.get(1).cloned().unwrap_or(json!(false));
+
+                self.ask_quorum(String::from("eth_getBlockByHash"), vec![
+                    hash,
+                    full_transactions,
+                ])
+                .await
+            }
+            _ => self.fail_over(method, params).await,
+        }
+    }
+}
+
+impl Transport for QuorumTransport {
+    type Out = Box<dyn Future<Item = Value, Error = web3::Error> + Send>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, rpc::Call) {
+        self.nodes[0].prepare(method, params)
+    }
+
+    fn send(&self, _: RequestId, request: rpc::Call) -> Self::Out {
+        let (method, params) = match method_and_params(request) {
+            Ok(call) => call,
+            Err(e) => return Box::new(futures::future::err(e)),
+        };
+
+        Box::new(self.clone().send_call(method, params).boxed().compat())
+    }
+}
+
+/// The requests of a batch are sent one by one, as each of them may have to
+/// be confirmed by other nodes.
+impl BatchTransport for QuorumTransport {
+    type Batch =
+        Box<dyn Future<Item = Vec<Result<Value, web3::Error>>, Error = web3::Error> + Send>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let responses = requests
+            .into_iter()
+            .map(|(_, request)| {
+                let transport = self.clone();
+
+                async move {
+                    let (method, params) = method_and_params(request)?;
+
+                    transport.send_call(method, params).await
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Box::new(
+            async move { Ok::<_, web3::Error>(join_all(responses).await) }
+                .boxed()
+                .compat(),
+        )
+    }
+}
+
+fn method_and_params(request: rpc::Call) -> Result<(String, Vec<Value>), web3::Error> {
+    match request {
+        rpc::Call::MethodCall(rpc::MethodCall { method, params, .. }) => match params {
+            rpc::Params::Array(params) => Ok((method, params)),
+            rpc::Params::None => Ok((method, Vec::new())),
+            rpc::Params::Map(_) => Err(web3::Error::Transport(format!(
+                "cannot send {} with named parameters to several nodes",
+                method
+            ))),
+        },
+        _ => Err(web3::Error::Transport(String::from(
+            "only method calls can be sent to several nodes",
+        ))),
+    }
+}
+
+/// The response `quorum` of the nodes agree on, `responses` has `None` for
+/// the nodes that did not answer.
+///
+/// A block or receipt that too few nodes know is returned as unknown (`null`)
+/// for now, as it may not have reached all of them yet. A block or receipt
+/// that some nodes return differently is an error.
+fn agreed_response(
+    method: &str,
+    responses: Vec<Option<Value>>,
+    quorum: usize,
+) -> Result<Value, web3::Error> {
+    let answered = responses
+        .iter()
+        .filter(|response| response.is_some())
+        .count();
+    if answered < quorum {
+        return Err(web3::Error::Transport(format!(
+            "only {} of {} Ethereum nodes answered {}, {} are needed",
+            answered,
+            responses.len(),
+            method,
+            quorum
+        )));
+    }
+
+    let mut known = HashMap::<String, (Value, usize)>::new();
+    for response in responses.into_iter().flatten() {
+        if response.is_null() {
+            continue;
+        }
+
+        let fingerprint = fingerprint(method, &response).to_string();
+        known.entry(fingerprint).or_insert((response, 0)).1 += 1;
+    }
+
+    if known.len() > 1 {
+        log::error!(
+            "the Ethereum nodes return different results for {}, at least one of them is on another chain or lying",
+            method
+        );
+        return Err(web3::Error::InvalidResponse(format!(
+            "the Ethereum nodes disagree on {}",
+            method
+        )));
+    }
+
+    match known.into_iter().next() {
+        Some((_, (response, count))) if count >= quorum => Ok(response),
+        _ => Ok(Value::Null),
+    }
+}
+
+/// The parts of a block or receipt that matter to a swap, other fields differ
+/// between node implementations.
+fn fingerprint(method: &str, response: &Value) -> Value {
+    match method {
+        "eth_getBlockByHash" => {
+            let transactions = response["transactions"]
+                .as_array()
+                .map(|transactions| {
+                    transactions
+                        .iter()
+                        .map(|transaction| {
+                            if transaction.is_object() {
+                                json!([
+                                    transaction["hash"],
+                                    transaction["from"],
+                                    transaction["to"],
+                                    transaction["value"],
+                                    transaction["input"]
+                                ])
+                            } else {
+                                transaction.clone()
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            json!([response["hash"], transactions])
+        }
+        "eth_getTransactionReceipt" => {
+            let logs = response["logs"]
+                .as_array()
+                .map(|logs| {
+                    logs.iter()
+                        .map(|log| json!([log["address"], log["topics"], log["data"]]))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            json!([
+                response["transactionHash"],
+                response["blockHash"],
+                response["status"],
+                response["contractAddress"],
+                logs
+            ])
+        }
+        _ => response.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn receipt(status: &str) -> Option<Value> {
+        Some(json!({
+            "transactionHash": "0x01",
+            "blockHash": "0x02",
+            "status": status,
+            "contractAddress": null,
+            "logs": [],
+            "cumulativeGasUsed": "0x5208"
+        }))
+    }
+
+    #[test]
+    fn a_receipt_known_to_the_quorum_is_returned() {
+        let mut other_implementation = receipt("0x1");
+        other_implementation.as_mut().unwrap()["cumulativeGasUsed"] = json!("0x05208");
+
+        let response = agreed_response(
+            "eth_getTransactionReceipt",
+            vec![receipt("0x1"), other_implementation, Some(Value::Null)],
+            2,
+        );
+
+        assert_that(&response.unwrap()).is_equal_to(receipt("0x1").unwrap());
+    }
+
+    #[test]
+    fn a_receipt_known_to_too_few_nodes_is_unknown() {
+        let response = agreed_response(
+            "eth_getTransactionReceipt",
+            vec![receipt("0x1"), Some(Value::Null), None],
+            2,
+        );
+
+        assert_that(&response.unwrap()).is_equal_to(Value::Null);
+    }
+
+    #[test]
+    fn nodes_that_disagree_or_do_not_answer_are_an_error() {
+        let disagreeing = agreed_response(
+            "eth_getTransactionReceipt",
+            vec![receipt("0x1"), receipt("0x0"), receipt("0x1")],
+            1,
+        );
+        let unanswered =
+            agreed_response("eth_getTransactionReceipt", vec![receipt("0x1"), None], 2);
+
+        assert_that(&disagreeing).is_err();
+        assert_that(&unanswered).is_err();
+    }
+}
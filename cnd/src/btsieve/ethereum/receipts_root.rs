@@ -0,0 +1,263 @@
+use crate::ethereum::{H256, U256};
+use rlp::RlpStream;
+use serde::Serialize;
+use serde_json::Value;
+use tiny_keccak::{Hasher, Keccak};
+
+/// Proves that a transaction is in a block and what its receipt says: all
+/// receipts of the block add up to the `receiptsRoot` in its header.
+///
+/// The receipts are given as returned by `eth_getTransactionReceipt`, the
+/// header with its `receiptsRoot` can be looked up by `block_hash` on any node
+/// or block explorer.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReceiptProof {
+    pub block_hash: H256,
+    pub receipts_root: H256,
+    /// The index of the receipt of the transaction in `receipts`.
+    pub index: usize,
+    pub receipts: Vec<Value>,
+}
+
+/// Recompute the `receiptsRoot` of a block from all of its receipts as
+/// returned by `eth_getTransactionReceipt`, in the order of its transactions.
+pub fn receipts_root(receipts: &[Value]) -> anyhow::Result<H256> {
+    let mut pairs = Vec::with_capacity(receipts.len());
+    for (index, receipt) in receipts.iter().enumerate() {
+        let mut key = RlpStream::new();
+        key.append(&(index as u64));
+
+        pairs.push((key.out(), encode_receipt(receipt)?));
+    }
+
+    Ok(H256::from(trie_root(pairs)))
+}
+
+/// The consensus encoding of a receipt, receipts of typed transactions
+/// (EIP-2718) are prefixed with their type.
+fn encode_receipt(receipt: &Value) -> anyhow::Result<Vec<u8>> {
+    let mut stream = RlpStream::new_list(4);
+
+    // Receipts of blocks before Byzantium have the state root instead.
+    match &receipt["root"] {
+        Value::Null => stream.append(&number(&receipt["status"])?),
+        root => stream.append(&bytes(root)?),
+    };
+    stream.append(&number(&receipt["cumulativeGasUsed"])?);
+    stream.append(&bytes(&receipt["logsBloom"])?);
+
+    let logs = receipt["logs"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("receipt without logs"))?;
+    stream.begin_list(logs.len());
+    for log in logs {
+        let topics = log["topics"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("log without topics"))?;
+
+        stream.begin_list(3);
+        stream.append(&bytes(&log["address"])?);
+        stream.begin_list(topics.len());
+        for topic in topics {
+            stream.append(&bytes(topic)?);
+        }
+        stream.append(&bytes(&log["data"])?);
+    }
+
+    let transaction_type = match &receipt["type"] {
+        Value::Null => 0,
+        transaction_type => number(transaction_type)?.low_u32() as u8,
+    };
+    let mut encoded = stream.out();
+    if transaction_type != 0 {
+        encoded.insert(0, transaction_type);
+    }
+
+    Ok(encoded)
+}
+
+fn number(value: &Value) -> anyhow::Result<U256> {
+    Ok(serde_json::from_value(value.clone())?)
+}
+
+fn bytes(value: &Value) -> anyhow::Result<Vec<u8>> {
+    let hex = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("expected hex encoded bytes, got {}", value))?;
+
+    Ok(hex::decode(hex.trim_start_matches("0x"))?)
+}
+
+/// The root of the Merkle Patricia trie with the given keys and values.
+fn trie_root(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> [u8; 32] {
+    let mut pairs = pairs
+        .into_iter()
+        .map(|(key, value)| (nibbles(&key), value))
+        .collect::<Vec<_>>();
+    pairs.sort();
+
+    let mut stream = RlpStream::new();
+    encode_node(&pairs, 0, &mut stream);
+
+    keccak256(&stream.out())
+}
+
+/// Append the node holding `pairs`, whose keys share the first `depth`
+/// nibbles.
+fn encode_node(pairs: &[(Vec<u8>, Vec<u8>)], depth: usize, stream: &mut RlpStream) {
+    let (key, value) = match pairs.first() {
+        Some(pair) => pair,
+        None => {
+            stream.append_empty_data();
+            return;
+        }
+    };
+
+    if pairs.len() == 1 {
+        stream.begin_list(2);
+        stream.append(&hex_prefix(&key[depth..], true));
+        stream.append(value);
+        return;
+    }
+
+    let shared = pairs[1..].iter().fold(key.len(), |shared, (other, _)| {
+        let common = key
+            .iter()
+            .zip(other.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        std::cmp::min(shared, common)
+    });
+    if shared > depth {
+        stream.begin_list(2);
+        stream.append(&hex_prefix(&key[depth..shared], false));
+        encode_child(pairs, shared, stream);
+        return;
+    }
+
+    // The pairs are sorted, a key that ends at this branch comes first.
+    let (value, mut begin) = if key.len() == depth {
+        (Some(value), 1)
+    } else {
+        (None, 0)
+    };
+
+    stream.begin_list(17);
+    for nibble in 0..16 {
+        let len = pairs[begin..]
+            .iter()
+            .take_while(|(key, _)| key[depth] == nibble)
+            .count();
+
+        if len == 0 {
+            stream.append_empty_data();
+        } else {
+            encode_child(&pairs[begin..begin + len], depth + 1, stream);
+        }
+        begin += len;
+    }
+    match value {
+        Some(value) => stream.append(value),
+        None => stream.append_empty_data(),
+    };
+}
+
+/// Children of 32 bytes or more are referred to by their hash.
+fn encode_child(pairs: &[(Vec<u8>, Vec<u8>)], depth: usize, stream: &mut RlpStream) {
+    let mut child = RlpStream::new();
+    encode_node(pairs, depth, &mut child);
+    let child = child.out();
+
+    if child.len() >= 32 {
+        stream.append(&keccak256(&child).to_vec());
+    } else {
+        stream.append_raw(&child, 1);
+    }
+}
+
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| vec![byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 0x20 } else { 0x00 };
+
+    let (mut encoded, rest) = if nibbles.len() % 2 == 1 {
+        (vec![flag | 0x10 | nibbles[0]], &nibbles[1..])
+    } else {
+        (vec![flag], nibbles)
+    };
+    encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+
+    encoded
+}
+
+fn keccak256(input: &[u8]) -> [u8; 32] {
+    let mut output = [0u8; 32];
+
+    let mut hasher = Keccak::v256();
+    hasher.update(input);
+    hasher.finalize(&mut output);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn trie_root_matches_the_ethereum_test_vectors() {
+        let empty = trie_root(Vec::new());
+        let dogs = trie_root(vec![
+            (b"doe".to_vec(), b"reindeer".to_vec()),
+            (b"dog".to_vec(), b"puppy".to_vec()),
+            (b"dogglesworth".to_vec(), b"cat".to_vec()),
+        ]);
+
+        assert_eq!(
+            hex::encode(empty),
+            "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+        );
+        assert_eq!(
+            hex::encode(dogs),
+            "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+        );
+    }
+
+    #[test]
+    fn receipts_root_covers_status_logs_and_type() {
+        let bloom = format!("0x{}", "00".repeat(256));
+        let receipts = vec![
+            json!({
+                "status": "0x1",
+                "cumulativeGasUsed": "0x5208",
+                "logsBloom": bloom,
+                "logs": []
+            }),
+            json!({
+                "type": "0x2",
+                "status": "0x0",
+                "cumulativeGasUsed": "0xa410",
+                "logsBloom": bloom,
+                "logs": [{
+                    "address": "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+                    "topics": ["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"],
+                    "data": "0x01"
+                }]
+            }),
+        ];
+
+        assert_eq!(
+            receipts_root(&receipts).unwrap(),
+            H256::from_str("deca5658a0e1854908a3c1a3fd66ba38bc2c7a5fcab76c1edca5d5edb0341240")
+                .unwrap()
+        );
+    }
+}
@@ -1,6 +1,16 @@
-use crate::ethereum::{Address, Block, Bytes, Transaction, TransactionReceipt, H256};
+use crate::{
+    ethereum::{Address, Block, Bytes, Transaction, TransactionReceipt, H256},
+    swap_protocols::rfc003::{Secret, SecretHash},
+};
 use ethbloom::Input;
 
+lazy_static::lazy_static! {
+    /// keccak256(Redeemed())
+    pub static ref REDEEM_LOG_MSG: H256 = "B8CAC300E37F03AD332E581DEA21B2F0B84EAAADC184A295FEF71E81F44A7413".parse().expect("to be valid hex");
+    /// keccak256(Refunded())
+    pub static ref REFUND_LOG_MSG: H256 = "5D26862916391BF49478B2F5103B0720A842B45EF145A268F2CD1FB2AED55178".parse().expect("to be valid hex");
+}
+
 #[derive(Clone, Default, Debug, Eq, PartialEq)]
 /// If the field is set to Some(foo) then only transactions matching foo are
 /// returned. Otherwise, when the field is set to None, no pattern matching is
@@ -86,6 +96,64 @@ impl TransactionPattern {
             }),
         }
     }
+
+    /// Classifies how `transaction` spends the HTLC that emits the events of
+    /// this pattern.
+    ///
+    /// The HTLC emits `Redeemed()` with the secret as data or `Refunded()`.
+    /// Without a receipt, e.g. for pending transactions, only a call of the
+    /// HTLC with the secret as data is recognised, as a redeem. `None` if the
+    /// pattern has no event address, the transaction does not spend the HTLC
+    /// or the secret does not match `secret_hash`.
+    pub fn spend(
+        &self,
+        transaction: &Transaction,
+        receipt: Option<&TransactionReceipt>,
+        secret_hash: &SecretHash,
+    ) -> Option<Spend> {
+        let htlc_location = self
+            .events
+            .as_ref()?
+            .iter()
+            .find_map(|event| event.address)?;
+        // Anyone can call the HTLC with arbitrary data, hence the secret has
+        // to match the hash.
+        let redeem = |data: &[u8]| {
+            Secret::from_vec(data)
+                .ok()
+                .filter(|secret| secret.hash() == *secret_hash)
+                .map(|secret| Spend::Redeem { secret })
+        };
+
+        let receipt = match receipt {
+            Some(receipt) => receipt,
+            None if transaction.to == Some(htlc_location) => return redeem(&transaction.input.0),
+            None => return None,
+        };
+
+        receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == htlc_location)
+            .find_map(|log| {
+                if log.topics.contains(&*REDEEM_LOG_MSG) {
+                    redeem(&log.data.0)
+                } else if log.topics.contains(&*REFUND_LOG_MSG) {
+                    Some(Spend::Refund)
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+/// How an HTLC was spent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Spend {
+    /// Through a call that reveals the secret.
+    Redeem { secret: Secret },
+    /// Through a call after the expiry.
+    Refund,
 }
 
 fn events_exist_in_receipt(events: &[Event], receipt: &TransactionReceipt) -> bool {
@@ -357,9 +425,6 @@ mod tests {
     lazy_static::lazy_static! {
         pub static ref CONTRACT_ADDRESS: H160 = Address::from_str("e46FB33e4DB653De84cB0E0E8b810A6c4cD39d59").unwrap();
     }
-    lazy_static::lazy_static! {
-        pub static ref REDEEM_LOG_MSG: H256 = H256::from_str("B8CAC300E37F03AD332E581DEA21B2F0B84EAAADC184A295FEF71E81F44A7413").unwrap();
-    }
     lazy_static::lazy_static! {
         pub static ref UNKNOWN_LOG_MSG: H256 = H256::from_str("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
     }
@@ -657,4 +722,98 @@ mod tests {
 
         assert_that!(events_exist_in_receipt(&events, &receipt)).is_true();
     }
+
+    #[test]
+    fn a_redeemed_log_with_the_secret_is_a_redeem() {
+        let secret = Secret::from(*b"hello world, you are beautiful!!");
+        let pattern = transaction_pattern_from_event(
+            Event::new()
+                .for_contract(*CONTRACT_ADDRESS)
+                .with_topics(vec![Some(Topic(*REDEEM_LOG_MSG))]),
+        );
+        let receipt = |data: &[u8]| TransactionReceipt {
+            logs: vec![Log {
+                address: *CONTRACT_ADDRESS,
+                topics: vec![*REDEEM_LOG_MSG],
+                data: Bytes(data.to_vec()),
+                ..default_log()
+            }],
+            ..TransactionReceipt::default()
+        };
+
+        assert_that!(pattern.spend(
+            &Transaction::default(),
+            Some(&receipt(secret.as_raw_secret())),
+            &secret.hash()
+        ))
+        .is_equal_to(Some(Spend::Redeem { secret }));
+        assert_that!(pattern.spend(
+            &Transaction::default(),
+            Some(&receipt(&[0u8; 32])),
+            &secret.hash()
+        ))
+        .is_none();
+    }
+
+    #[test]
+    fn a_refunded_log_of_the_htlc_is_a_refund() {
+        let secret = Secret::from(*b"hello world, you are beautiful!!");
+        let pattern = transaction_pattern_from_event(
+            Event::new()
+                .for_contract(*CONTRACT_ADDRESS)
+                .with_topics(vec![Some(Topic(*REFUND_LOG_MSG))]),
+        );
+        let receipt = |address| TransactionReceipt {
+            logs: vec![Log {
+                address,
+                topics: vec![*REFUND_LOG_MSG],
+                ..default_log()
+            }],
+            ..TransactionReceipt::default()
+        };
+
+        assert_that!(pattern.spend(
+            &Transaction::default(),
+            Some(&receipt(*CONTRACT_ADDRESS)),
+            &secret.hash()
+        ))
+        .is_equal_to(Some(Spend::Refund));
+        assert_that!(pattern.spend(
+            &Transaction::default(),
+            Some(&receipt(Address::zero())),
+            &secret.hash()
+        ))
+        .is_none();
+    }
+
+    #[test]
+    fn without_a_receipt_only_a_call_of_the_htlc_with_the_secret_is_a_redeem() {
+        let secret = Secret::from(*b"hello world, you are beautiful!!");
+        let pattern = transaction_pattern_from_event(
+            Event::new()
+                .for_contract(*CONTRACT_ADDRESS)
+                .with_topics(vec![Some(Topic(*REDEEM_LOG_MSG))]),
+        );
+        let call = |to, input: &[u8]| Transaction {
+            to: Some(to),
+            input: Bytes(input.to_vec()),
+            ..Transaction::default()
+        };
+
+        assert_that!(pattern.spend(
+            &call(*CONTRACT_ADDRESS, secret.as_raw_secret()),
+            None,
+            &secret.hash()
+        ))
+        .is_equal_to(Some(Spend::Redeem { secret }));
+        assert_that!(pattern.spend(&call(*CONTRACT_ADDRESS, &[0u8; 32]), None, &secret.hash()))
+            .is_none();
+        assert_that!(pattern.spend(
+            &call(Address::zero(), secret.as_raw_secret()),
+            None,
+            &secret.hash()
+        ))
+        .is_none();
+        assert_that!(pattern.spend(&call(*CONTRACT_ADDRESS, &[]), None, &secret.hash())).is_none();
+    }
 }
@@ -1,81 +1,483 @@
 use crate::{
-    btsieve::{BlockByHash, LatestBlock, ReceiptByHash},
-    ethereum::{
-        web3::{
-            self,
-            transports::{EventLoopHandle, Http},
-            Web3,
+    btsieve::{
+        ethereum::{
+            receipts_root::{receipts_root, ReceiptProof},
+            EventLoopHandle, Web3Transport,
         },
-        BlockId, BlockNumber,
+        BlockByHash, ConcurrencyLimit, InclusionProof, LatestBlock, ReceiptByHash,
+    },
+    chaos::Chaos,
+    ethereum::{
+        web3::{self, transports::Batch, Transport, Web3},
+        Address, BlockId, BlockNumber, GasOracle, GasPricing, Transaction, TransactionReceipt,
+        H256, U256,
     },
+    swap_protocols::ledger::ethereum::ChainId,
+    swap_tracing::in_span,
 };
+use async_trait::async_trait;
 use futures::Future;
+use futures_core::compat::Future01CompatExt;
 use reqwest::Url;
-use std::sync::Arc;
+use serde_json::{json, Value};
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+/// How many blocks the verified receipts are kept of, the receipts of a swap
+/// are usually looked up in the same few blocks.
+const MAX_VERIFIED_BLOCKS: usize = 8;
 
 #[derive(Clone, Debug)]
 pub struct Web3Connector {
-    web3: Arc<Web3<Http>>,
+    web3: Arc<Web3<Web3Transport>>,
     task_executor: tokio::runtime::TaskExecutor,
+    concurrency_limit: ConcurrencyLimit,
+    chaos: Chaos,
+    watch_pending_transactions: bool,
+    verify_receipts: bool,
+    verified_blocks: Arc<Mutex<VecDeque<VerifiedBlock>>>,
 }
 
+/// The hash of a block and the receipts that were verified against it.
+type VerifiedBlock = (H256, Vec<Value>);
+
 impl Web3Connector {
     pub fn new(
         node_url: Url,
         task_executor: tokio::runtime::TaskExecutor,
     ) -> Result<(Self, EventLoopHandle), web3::Error> {
-        let (event_loop_handle, http_transport) = Http::new(node_url.as_str())?;
+        let (transport, event_loop_handle) = Web3Transport::new(node_url)?;
+
         Ok((
-            Self {
-                web3: Arc::new(Web3::new(http_transport)),
-                task_executor,
-            },
+            Self::with_transport(transport, task_executor),
             event_loop_handle,
         ))
     }
+
+    /// Send all requests to the node through the given SOCKS5 proxy.
+    pub fn new_with_socks5_proxy(
+        node_url: Url,
+        proxy: SocketAddr,
+        task_executor: tokio::runtime::TaskExecutor,
+    ) -> Result<Self, reqwest::Error> {
+        Ok(Self::with_transport(
+            Web3Transport::new_with_socks5_proxy(node_url, proxy)?,
+            task_executor,
+        ))
+    }
+
+    /// Send requests to several nodes, see [`QuorumTransport`].
+    pub fn with_transport(
+        transport: Web3Transport,
+        task_executor: tokio::runtime::TaskExecutor,
+    ) -> Self {
+        Self {
+            web3: Arc::new(Web3::new(transport)),
+            task_executor,
+            concurrency_limit: ConcurrencyLimit::default(),
+            chaos: Chaos::default(),
+            watch_pending_transactions: false,
+            verify_receipts: false,
+            verified_blocks: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    pub fn with_concurrency_limit(self, concurrency_limit: ConcurrencyLimit) -> Self {
+        Self {
+            concurrency_limit,
+            ..self
+        }
+    }
+
+    pub fn with_chaos(self, chaos: Chaos) -> Self {
+        Self { chaos, ..self }
+    }
+
+    /// Also look for redeem transactions that are not mined yet, see
+    /// [`Web3Connector::pending_transactions`].
+    pub fn with_pending_transactions(self, watch_pending_transactions: bool) -> Self {
+        Self {
+            watch_pending_transactions,
+            ..self
+        }
+    }
+
+    pub fn watches_pending_transactions(&self) -> bool {
+        self.watch_pending_transactions
+    }
+
+    /// Only return receipts that add up to the `receiptsRoot` of their block
+    /// with the other receipts of the block, see
+    /// [`Web3Connector::verified_receipts`].
+    pub fn with_receipt_verification(self, verify_receipts: bool) -> Self {
+        Self {
+            verify_receipts,
+            ..self
+        }
+    }
+
+    /// All receipts of the block, once they were checked to add up to its
+    /// `receiptsRoot`.
+    ///
+    /// This detects a node that returns receipts which are not in the block,
+    /// e.g. to make cnd believe an HTLC was funded. The block itself is taken
+    /// from the node, hence this is best combined with a quorum of nodes.
+    async fn verified_receipts(&self, block_hash: H256) -> anyhow::Result<Vec<Value>> {
+        if let Some(receipts) = self.cached_receipts(&block_hash) {
+            return Ok(receipts);
+        }
+
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let transport = self.web3.transport();
+
+        let block = transport
+            .execute("eth_getBlockByHash", vec![json!(block_hash), json!(false)])
+            .compat()
+            .await?;
+        if block.is_null() {
+            anyhow::bail!("block {:x} is unknown to the Ethereum node", block_hash);
+        }
+        let receipts_root_of_block = serde_json::from_value::<H256>(block["receiptsRoot"].clone())?;
+        let transaction_hashes =
+            serde_json::from_value::<Vec<H256>>(block["transactions"].clone())?;
+
+        let batch = Batch::new(transport.clone());
+        let receipts = transaction_hashes
+            .into_iter()
+            .map(|transaction_hash| {
+                batch.execute("eth_getTransactionReceipt", vec![json!(transaction_hash)])
+            })
+            .collect::<Vec<_>>();
+        batch.submit_batch().compat().await?;
+        let receipts = futures::future::join_all(receipts).compat().await?;
+
+        if receipts_root(&receipts)? != receipts_root_of_block {
+            log::error!(
+                "the receipts of block {:x} do not add up to its receiptsRoot, the Ethereum node returns receipts that are not in the block",
+                block_hash
+            );
+            anyhow::bail!(
+                "the receipts of block {:x} are not in the block",
+                block_hash
+            );
+        }
+
+        let mut verified_blocks = self.verified_blocks.lock().unwrap();
+        verified_blocks.push_back((block_hash, receipts.clone()));
+        if verified_blocks.len() > MAX_VERIFIED_BLOCKS {
+            verified_blocks.pop_front();
+        }
+
+        Ok(receipts)
+    }
+
+    fn cached_receipts(&self, block_hash: &H256) -> Option<Vec<Value>> {
+        self.verified_blocks
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(hash, _)| hash == block_hash)
+            .map(|(_, receipts)| receipts.clone())
+    }
+
+    /// The receipt as it was verified if receipts are verified, otherwise the
+    /// receipt as it is.
+    async fn verified(
+        &self,
+        receipt: Option<TransactionReceipt>,
+    ) -> Result<Option<TransactionReceipt>, web3::Error> {
+        let receipt = match receipt {
+            Some(receipt) if self.verify_receipts => receipt,
+            receipt => return Ok(receipt),
+        };
+        // Not mined yet, there is nothing to verify it against.
+        let block_hash = match receipt.block_hash {
+            Some(block_hash) => block_hash,
+            None => return Ok(Some(receipt)),
+        };
+
+        let receipts = self
+            .verified_receipts(block_hash)
+            .await
+            .map_err(|e| web3::Error::InvalidResponse(format!("{:#}", e)))?;
+        let verified = receipts
+            .into_iter()
+            .find(|verified| {
+                serde_json::from_value::<H256>(verified["transactionHash"].clone()).ok()
+                    == Some(receipt.transaction_hash)
+            })
+            .ok_or_else(|| {
+                web3::Error::InvalidResponse(format!(
+                    "transaction {:x} is not in block {:x}",
+                    receipt.transaction_hash, block_hash
+                ))
+            })?;
+
+        serde_json::from_value(verified)
+            .map(Some)
+            .map_err(|e| web3::Error::InvalidResponse(e.to_string()))
+    }
+
+    /// The transactions the node would include in the next block, which is
+    /// as much of its transaction pool as nodes expose over the standard
+    /// JSON-RPC API.
+    pub async fn pending_transactions(&self) -> Result<Vec<Transaction>, web3::Error> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos
+            .before_request()
+            .await
+            .map_err(|e| web3::Error::Transport(e.to_string()))?;
+
+        let block = self
+            .web3
+            .eth()
+            .block_with_txs(BlockId::Number(BlockNumber::Pending))
+            .compat()
+            .await?;
+
+        Ok(block.map(|block| block.transactions).unwrap_or_default())
+    }
+
+    pub async fn chain_id(&self) -> anyhow::Result<ChainId> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let chain_id = self
+            .web3
+            .transport()
+            .execute("eth_chainId", vec![])
+            .compat()
+            .await?;
+        let chain_id = serde_json::from_value::<U256>(chain_id)?;
+
+        Ok(ChainId::new(chain_id.low_u32()))
+    }
+
+    /// Send `value` to `to` from the first account of the node, which is only
+    /// unlocked on development chains.
+    pub async fn send_from_first_account(&self, to: Address, value: U256) -> anyhow::Result<H256> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let transport = self.web3.transport();
+
+        let accounts = transport.execute("eth_accounts", vec![]).compat().await?;
+        let from = serde_json::from_value::<Vec<Address>>(accounts)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("the Ethereum node does not manage any account"))?;
+
+        let transaction_hash = transport
+            .execute("eth_sendTransaction", vec![
+                json!({ "from": from, "to": to, "value": value }),
+            ])
+            .compat()
+            .await?;
+
+        Ok(serde_json::from_value(transaction_hash)?)
+    }
 }
 
+#[async_trait]
 impl LatestBlock for Web3Connector {
     type Error = crate::ethereum::web3::Error;
     type Block = Option<crate::ethereum::Block<crate::ethereum::Transaction>>;
     type BlockHash = crate::ethereum::H256;
 
-    fn latest_block(
-        &mut self,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static> {
-        let web = self.web3.clone();
-        Box::new(
-            web.eth()
-                .block_with_txs(BlockId::Number(BlockNumber::Latest)),
-        )
+    async fn latest_block(&mut self) -> Result<Self::Block, Self::Error> {
+        in_span("ethereum.latest-block", async move {
+            let _permit = self.concurrency_limit.acquire().await;
+            self.chaos
+                .before_request()
+                .await
+                .map_err(|e| web3::Error::Transport(e.to_string()))?;
+
+            self.web3
+                .eth()
+                .block_with_txs(BlockId::Number(BlockNumber::Latest))
+                .compat()
+                .await
+        })
+        .await
     }
 }
 
+#[async_trait]
 impl BlockByHash for Web3Connector {
     type Error = crate::ethereum::web3::Error;
     type Block = Option<crate::ethereum::Block<crate::ethereum::Transaction>>;
     type BlockHash = crate::ethereum::H256;
 
-    fn block_by_hash(
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block, Self::Error> {
+        in_span("ethereum.block-by-hash", async move {
+            let _permit = self.concurrency_limit.acquire().await;
+            self.chaos
+                .before_request()
+                .await
+                .map_err(|e| web3::Error::Transport(e.to_string()))?;
+
+            self.web3
+                .eth()
+                .block_with_txs(BlockId::Hash(block_hash))
+                .compat()
+                .await
+        })
+        .await
+    }
+}
+
+/// The receipts of the block are always verified for a proof, regardless of
+/// whether receipts are verified otherwise.
+#[async_trait]
+impl InclusionProof for Web3Connector {
+    type Proof = ReceiptProof;
+    type TransactionHash = H256;
+    type Error = anyhow::Error;
+
+    async fn inclusion_proof(
         &self,
-        block_hash: Self::BlockHash,
-    ) -> Box<dyn Future<Item = Self::Block, Error = Self::Error> + Send + 'static> {
-        let web = self.web3.clone();
-        Box::new(web.eth().block_with_txs(BlockId::Hash(block_hash)))
+        transaction_hash: H256,
+    ) -> anyhow::Result<Option<ReceiptProof>> {
+        let block_hash = match self.receipt_by_hash(transaction_hash).await? {
+            Some(TransactionReceipt {
+                block_hash: Some(block_hash),
+                ..
+            }) => block_hash,
+            _ => return Ok(None),
+        };
+
+        let receipts = self.verified_receipts(block_hash).await?;
+        let index = receipts
+            .iter()
+            .position(|receipt| {
+                serde_json::from_value::<H256>(receipt["transactionHash"].clone()).ok()
+                    == Some(transaction_hash)
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "transaction {:x} is not in block {:x}",
+                    transaction_hash,
+                    block_hash
+                )
+            })?;
+
+        Ok(Some(ReceiptProof {
+            block_hash,
+            receipts_root: receipts_root(&receipts)?,
+            index,
+            receipts,
+        }))
     }
 }
 
+#[async_trait]
 impl ReceiptByHash for Web3Connector {
     type Receipt = Option<crate::ethereum::TransactionReceipt>;
-    type TransactionHash = crate::ethereum::H256;
+    type TransactionHash = H256;
     type Error = crate::ethereum::web3::Error;
 
-    fn receipt_by_hash(
+    async fn receipt_by_hash(
         &self,
         transaction_hash: Self::TransactionHash,
-    ) -> Box<dyn Future<Item = Self::Receipt, Error = Self::Error> + Send + 'static> {
-        let web = self.web3.clone();
-        Box::new(web.eth().transaction_receipt(transaction_hash))
+    ) -> Result<Self::Receipt, Self::Error> {
+        in_span("ethereum.receipt-by-hash", async move {
+            let receipt = {
+                let _permit = self.concurrency_limit.acquire().await;
+                self.chaos
+                    .before_request()
+                    .await
+                    .map_err(|e| web3::Error::Transport(e.to_string()))?;
+
+                self.web3
+                    .eth()
+                    .transaction_receipt(transaction_hash)
+                    .compat()
+                    .await?
+            };
+
+            self.verified(receipt).await
+        })
+        .await
+    }
+
+    /// Fetches all receipts with a single JSON-RPC batch request.
+    async fn receipts_by_hashes(
+        &self,
+        transaction_hashes: Vec<H256>,
+    ) -> Result<Vec<Self::Receipt>, Self::Error> {
+        in_span("ethereum.receipts-by-hashes", async move {
+            if transaction_hashes.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let receipts = {
+                let _permit = self.concurrency_limit.acquire().await;
+                self.chaos
+                    .before_request()
+                    .await
+                    .map_err(|e| web3::Error::Transport(e.to_string()))?;
+
+                let batch = Batch::new(self.web3.transport().clone());
+                let eth = Web3::new(batch.clone()).eth();
+
+                // The requests are only queued here and sent once the batch is submitted.
+                let receipts = transaction_hashes
+                    .into_iter()
+                    .map(|transaction_hash| eth.transaction_receipt(transaction_hash))
+                    .collect::<Vec<_>>();
+
+                batch.submit_batch().compat().await?;
+
+                futures::future::join_all(receipts).compat().await?
+            };
+
+            let mut verified = Vec::with_capacity(receipts.len());
+            for receipt in receipts {
+                verified.push(self.verified(receipt).await?);
+            }
+
+            Ok(verified)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl GasOracle for Web3Connector {
+    /// Blocks of chains that activated EIP-1559 carry a `baseFeePerGas`, which
+    /// the block type of our web3 version does not know about yet, hence the
+    /// raw JSON-RPC requests.
+    async fn gas_pricing(&self) -> anyhow::Result<GasPricing> {
+        let _permit = self.concurrency_limit.acquire().await;
+        self.chaos.before_request().await?;
+
+        let transport = self.web3.transport();
+
+        let block = transport
+            .execute("eth_getBlockByNumber", vec![json!("latest"), json!(false)])
+            .compat()
+            .await?;
+        let base_fee_per_gas =
+            match serde_json::from_value::<Option<U256>>(block["baseFeePerGas"].clone())? {
+                Some(base_fee_per_gas) => base_fee_per_gas,
+                None => return Ok(GasPricing::Legacy),
+            };
+
+        let max_priority_fee_per_gas = transport
+            .execute("eth_maxPriorityFeePerGas", vec![])
+            .compat()
+            .await?;
+        let max_priority_fee_per_gas = serde_json::from_value::<U256>(max_priority_fee_per_gas)?;
+
+        Ok(GasPricing::eip1559(
+            base_fee_per_gas,
+            max_priority_fee_per_gas,
+        ))
     }
 }
 
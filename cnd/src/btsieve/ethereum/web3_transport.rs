@@ -0,0 +1,170 @@
+#[cfg(feature = "web3-http")]
+use crate::ethereum::web3::transports::Http;
+use crate::{
+    btsieve::{ethereum::QuorumTransport, socks5_client},
+    ethereum::web3::{self, helpers, rpc, BatchTransport, RequestId, Transport},
+};
+use futures::Future;
+use reqwest::{r#async::Client, Url};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Drives the HTTP transport of web3, requests are no longer answered once it
+/// is dropped.
+#[cfg(feature = "web3-http")]
+pub use crate::ethereum::web3::transports::EventLoopHandle;
+
+/// Requests sent with reqwest run on the tokio runtime, there is no event loop
+/// to keep around.
+#[cfg(not(feature = "web3-http"))]
+#[derive(Clone, Copy, Debug)]
+pub struct EventLoopHandle;
+
+/// The HTTP transport of web3 cannot send requests through a proxy, requests
+/// to a node that is reached through one are sent with reqwest instead. So are
+/// all requests if cnd was built without the `web3-http` feature.
+#[derive(Clone, Debug)]
+pub enum Web3Transport {
+    #[cfg(feature = "web3-http")]
+    Http(Http),
+    Reqwest(ReqwestHttp),
+    Quorum(QuorumTransport),
+}
+
+impl Web3Transport {
+    #[cfg(feature = "web3-http")]
+    pub fn new(node_url: Url) -> Result<(Self, EventLoopHandle), web3::Error> {
+        let (event_loop_handle, http_transport) = Http::new(node_url.as_str())?;
+
+        Ok((Web3Transport::Http(http_transport), event_loop_handle))
+    }
+
+    #[cfg(not(feature = "web3-http"))]
+    pub fn new(node_url: Url) -> Result<(Self, EventLoopHandle), web3::Error> {
+        Ok((
+            Web3Transport::Reqwest(ReqwestHttp::new(node_url)),
+            EventLoopHandle,
+        ))
+    }
+
+    /// Send all requests to the node through the given SOCKS5 proxy.
+    pub fn new_with_socks5_proxy(node_url: Url, proxy: SocketAddr) -> Result<Self, reqwest::Error> {
+        Ok(Web3Transport::Reqwest(
+            ReqwestHttp::new(node_url).with_socks5_proxy(proxy)?,
+        ))
+    }
+}
+
+impl Transport for Web3Transport {
+    type Out = Box<dyn Future<Item = rpc::Value, Error = web3::Error> + Send>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        match self {
+            #[cfg(feature = "web3-http")]
+            Web3Transport::Http(http) => http.prepare(method, params),
+            Web3Transport::Reqwest(reqwest) => reqwest.prepare(method, params),
+            Web3Transport::Quorum(quorum) => quorum.prepare(method, params),
+        }
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        match self {
+            #[cfg(feature = "web3-http")]
+            Web3Transport::Http(http) => Box::new(http.send(id, request)),
+            Web3Transport::Reqwest(reqwest) => Box::new(
+                reqwest
+                    .send(rpc::Request::Single(request))
+                    .and_then(|response| match response {
+                        rpc::Response::Single(output) => helpers::to_result_from_output(output),
+                        rpc::Response::Batch(_) => Err(web3::Error::InvalidResponse(
+                            "expected a single response".to_string(),
+                        )),
+                    }),
+            ),
+            Web3Transport::Quorum(quorum) => quorum.send(id, request),
+        }
+    }
+}
+
+impl BatchTransport for Web3Transport {
+    type Batch =
+        Box<dyn Future<Item = Vec<Result<rpc::Value, web3::Error>>, Error = web3::Error> + Send>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        match self {
+            #[cfg(feature = "web3-http")]
+            Web3Transport::Http(http) => Box::new(http.send_batch(requests)),
+            Web3Transport::Reqwest(reqwest) => {
+                let calls = requests.into_iter().map(|(_, call)| call).collect();
+
+                Box::new(
+                    reqwest
+                        .send(rpc::Request::Batch(calls))
+                        .and_then(|response| match response {
+                            rpc::Response::Batch(outputs) => Ok(outputs
+                                .into_iter()
+                                .map(helpers::to_result_from_output)
+                                .collect()),
+                            rpc::Response::Single(_) => Err(web3::Error::InvalidResponse(
+                                "expected a batch response".to_string(),
+                            )),
+                        }),
+                )
+            }
+            Web3Transport::Quorum(quorum) => quorum.send_batch(requests),
+        }
+    }
+}
+
+/// Sends JSON-RPC requests over HTTP with reqwest, optionally through a
+/// SOCKS5 proxy.
+#[derive(Clone, Debug)]
+pub struct ReqwestHttp {
+    client: Client,
+    url: Url,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl ReqwestHttp {
+    pub fn new(url: Url) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            next_id: Arc::new(AtomicUsize::new(1)),
+        }
+    }
+
+    /// Send all requests through the given SOCKS5 proxy.
+    pub fn with_socks5_proxy(self, proxy: SocketAddr) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: socks5_client(proxy)?,
+            ..self
+        })
+    }
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let id = self.next_id.fetch_add(1, Ordering::AcqRel);
+
+        (id, helpers::build_request(id, method, params))
+    }
+
+    fn send(
+        &self,
+        request: rpc::Request,
+    ) -> impl Future<Item = rpc::Response, Error = web3::Error> + Send {
+        self.client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .and_then(|mut response| response.json::<rpc::Response>())
+            .map_err(|e| web3::Error::Transport(e.to_string()))
+    }
+}
@@ -0,0 +1,180 @@
+#![allow(clippy::type_repetition_in_bounds)]
+use crate::{
+    config,
+    db::{DetermineTypes, LoadAcceptedSwap, Retention, Retrieve, SwapFailures},
+    ethereum::{Erc20Token, EtherQuantity},
+    load_swaps,
+    seed::SwapSeed,
+    swap_protocols::{
+        ledger::{Bitcoin, Ethereum},
+        rfc003::state_store::StateStore,
+        FeeAccounting, HtlcScanner, LedgerEventsCreator, SwapEvents, SwapId, SwapTasks,
+    },
+};
+use futures_core::compat::Future01CompatExt;
+use rand::{seq::SliceRandom, Rng};
+use std::time::{Duration, Instant};
+use tokio::{executor::Executor, timer::Delay};
+
+/// Returned instead of the response of a node if the `[chaos]` section says
+/// so.
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("fault injected as configured in the [chaos] section")]
+pub struct InjectedFault;
+
+/// Injects the faults configured in the `[chaos]` section into a running cnd,
+/// does nothing if the section is missing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Chaos {
+    config: Option<config::Chaos>,
+}
+
+impl Chaos {
+    pub fn new(config: Option<config::Chaos>) -> anyhow::Result<Self> {
+        let config = match config {
+            Some(config) => config,
+            None => return Ok(Self::default()),
+        };
+
+        if !cfg!(feature = "chaos") {
+            anyhow::bail!("cnd was built without the chaos feature, remove the [chaos] section");
+        }
+
+        for rate in &[config.connector_failure_rate, config.response_drop_rate] {
+            if let Some(rate) = rate {
+                if *rate < 0.0 || *rate > 1.0 {
+                    anyhow::bail!("rates in the [chaos] section must be between 0 and 1");
+                }
+            }
+        }
+        if config.task_restart_interval_secs == Some(0) {
+            anyhow::bail!("task_restart_interval_secs in the [chaos] section must not be 0");
+        }
+
+        log::warn!(
+            "injecting faults as configured in the [chaos] section, do not use this node for \
+             real swaps"
+        );
+
+        Ok(Self {
+            config: Some(config),
+        })
+    }
+
+    /// To be awaited before every request to a node, delays the request and
+    /// fails it with the configured probability.
+    pub async fn before_request(&self) -> Result<(), InjectedFault> {
+        let config = match self.config {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+
+        let (delay, fail) = {
+            let mut rng = rand::thread_rng();
+            let delay = config
+                .connector_max_delay_ms
+                .filter(|max| *max > 0)
+                .map(|max| Duration::from_millis(rng.gen_range(0, max)));
+            let fail = rng.gen_bool(config.connector_failure_rate.unwrap_or(0.0));
+
+            (delay, fail)
+        };
+
+        if let Some(delay) = delay {
+            let _ = Delay::new(Instant::now() + delay).compat().await;
+        }
+
+        if fail {
+            log::debug!("failing a request to a node on purpose");
+            return Err(InjectedFault);
+        }
+
+        Ok(())
+    }
+
+    /// Whether the response to a swap request should never be sent.
+    pub fn drop_response(&self) -> bool {
+        self.config
+            .and_then(|config| config.response_drop_rate)
+            .map(|rate| rand::thread_rng().gen_bool(rate))
+            .unwrap_or(false)
+    }
+
+    pub fn task_restart_interval(&self) -> Option<Duration> {
+        self.config
+            .and_then(|config| config.task_restart_interval_secs)
+            .map(Duration::from_secs)
+    }
+}
+
+/// Every `interval`, cancel the tasks of a random ongoing swap and resume it
+/// from the database, the way it would be after cnd crashed and restarted.
+pub async fn restart_swaps_periodically<D>(dependencies: D, interval: Duration, expiry_margin: u32)
+where
+    D: StateStore
+        + Executor
+        + Clone
+        + SwapSeed
+        + LedgerEventsCreator
+        + Retrieve
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + HtlcScanner
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    loop {
+        let _ = Delay::new(Instant::now() + interval).compat().await;
+
+        match random_ongoing_swap(&dependencies).await {
+            Ok(Some(swap_id)) => {
+                log::warn!("restarting the tasks of swap {} on purpose", swap_id);
+
+                if let Err(e) =
+                    load_swaps::restart_swap(&dependencies, swap_id, expiry_margin).await
+                {
+                    log::warn!("failed to restart swap {}: {:?}", swap_id, e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("failed to pick a swap to restart: {:?}", e),
+        }
+    }
+}
+
+async fn random_ongoing_swap<D: Retrieve + Retention>(
+    dependencies: &D,
+) -> anyhow::Result<Option<SwapId>> {
+    let mut ongoing = Vec::new();
+    for swap in Retrieve::all(dependencies).await? {
+        if Retention::finished_at(dependencies, &swap.swap_id)
+            .await?
+            .is_none()
+        {
+            ongoing.push(swap.swap_id);
+        }
+    }
+
+    Ok(ongoing.choose(&mut rand::thread_rng()).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn nothing_is_injected_without_the_section() {
+        let chaos = Chaos::new(None).unwrap();
+
+        assert_that(&futures_core::executor::block_on(chaos.before_request())).is_ok();
+        assert_that(&chaos.drop_response()).is_false();
+        assert_that(&chaos.task_restart_interval()).is_none();
+    }
+}
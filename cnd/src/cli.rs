@@ -1,3 +1,4 @@
+use cnd::swap_protocols::SwapId;
 use std::path::PathBuf;
 
 #[derive(structopt::StructOpt, Debug)]
@@ -10,4 +11,57 @@ pub struct Options {
     /// Dump the current configuration and exit
     #[structopt(long = "dump-config")]
     pub dump_config: bool,
+
+    /// Exercise the COMIT messaging layer of connecting peers with well-formed
+    /// and malformed SWAP requests and responses instead of taking part in
+    /// swaps
+    #[structopt(long = "compliance-server")]
+    pub compliance_server: bool,
+
+    /// Follow the database of another node read-only and take over its swaps
+    /// once promoted via `POST /internal/standby/promote`
+    #[structopt(long = "standby")]
+    pub standby: bool,
+
+    /// Follow swaps imported via `POST /watch` on both ledgers without a seed,
+    /// hence without being able to take part in swaps
+    #[structopt(long = "watch-only")]
+    pub watch_only: bool,
+
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+#[derive(structopt::StructOpt, Debug)]
+pub enum Command {
+    /// Restore the seed and database from a backup created via
+    /// `GET /internal/backup` into the configured data directory and exit
+    #[structopt(name = "restore")]
+    Restore {
+        /// Path to the backup file
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+
+        /// Overwrite an existing seed and database
+        #[structopt(long = "force")]
+        force: bool,
+    },
+
+    /// Print the refund transaction or contract call of a swap and exit
+    ///
+    /// The refund is reconstructed from the database and the ledger only, hence
+    /// this also works for swaps the node lost track of.
+    #[structopt(name = "recover")]
+    Recover {
+        /// The id of the swap to recover
+        swap_id: SwapId,
+
+        /// Bitcoin address to send the refunded funds to
+        #[structopt(long = "address", requires = "fee_per_wu")]
+        address: Option<bitcoin::Address>,
+
+        /// Fee per weight unit of the Bitcoin refund transaction
+        #[structopt(long = "fee-per-wu", requires = "address")]
+        fee_per_wu: Option<String>,
+    },
 }
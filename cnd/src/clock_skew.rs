@@ -0,0 +1,228 @@
+use crate::{
+    btsieve::{bitcoin::BitcoinConnector, ethereum::Web3Connector, LatestBlock},
+    swap_protocols::SwapId,
+    timestamp::Timestamp,
+};
+use futures_core::compat::Future01CompatExt;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+/// Used if `clock_skew.max_skew_seconds` is not configured.
+pub const DEFAULT_MAX_SKEW_SECONDS: u32 = 300;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// What the local clock is compared against.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Bitcoin,
+    Ethereum,
+    /// The time the counterparty sent its swap request at.
+    Counterparty,
+}
+
+impl Source {
+    /// How far the time of the source may be off without any clock being
+    /// wrong. Bitcoin blocks may be timestamped up to two hours ahead and can
+    /// be an hour apart, Ethereum blocks follow each other within seconds.
+    fn tolerance_seconds(self) -> i64 {
+        match self {
+            Source::Bitcoin => 2 * 60 * 60,
+            Source::Ethereum => 60,
+            Source::Counterparty => 0,
+        }
+    }
+}
+
+/// How many seconds the local clock is ahead of `source`, negative if it is
+/// behind.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct ClockSkew {
+    pub source: Source,
+    pub seconds: i64,
+}
+
+impl ClockSkew {
+    pub fn measure(source: Source, now: Timestamp, theirs: Timestamp) -> Self {
+        ClockSkew {
+            source,
+            seconds: i64::from(now) - i64::from(theirs),
+        }
+    }
+
+    fn exceeds(self, max_skew_seconds: u32) -> bool {
+        self.seconds.abs() > i64::from(max_skew_seconds) + self.source.tolerance_seconds()
+    }
+}
+
+/// Keeps the clock skews that exceed the threshold, such that swaps can be
+/// annotated with them. Expiries are compared against the local clock, a
+/// stale one makes cnd refund too early or miss the time to redeem.
+///
+/// Not persisted, the ledgers are measured again after a restart but the skew
+/// of counterparties is only known for swaps requested since.
+#[derive(Clone, Debug)]
+pub struct ClockMonitor {
+    max_skew_seconds: u32,
+    ledgers: Arc<Mutex<HashMap<Source, ClockSkew>>>,
+    counterparties: Arc<Mutex<HashMap<SwapId, ClockSkew>>>,
+}
+
+impl ClockMonitor {
+    pub fn new(max_skew_seconds: u32) -> Self {
+        Self {
+            max_skew_seconds,
+            ledgers: Arc::new(Mutex::new(HashMap::new())),
+            counterparties: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Replaces the previous measurement against the same ledger.
+    pub fn record_ledger(&self, skew: ClockSkew) {
+        let mut ledgers = self.ledgers.lock().unwrap();
+
+        if skew.exceeds(self.max_skew_seconds) {
+            log::warn!(
+                "the local clock is {} seconds off from the latest {:?} block, check the system time and whether the node is synced, swaps may expire unnoticed",
+                skew.seconds,
+                skew.source
+            );
+            ledgers.insert(skew.source, skew);
+        } else {
+            ledgers.remove(&skew.source);
+        }
+    }
+
+    pub fn record_counterparty(&self, swap_id: SwapId, skew: ClockSkew) {
+        if skew.exceeds(self.max_skew_seconds) {
+            log::warn!(
+                "the local clock is {} seconds off from the one of the counterparty of swap {}, one of them is wrong and the swap may expire unnoticed",
+                skew.seconds,
+                swap_id
+            );
+            self.counterparties.lock().unwrap().insert(swap_id, skew);
+        }
+    }
+
+    /// The skews that exceed the threshold and affect `swap_id`, empty if all
+    /// clocks agree.
+    pub fn skews_of(&self, swap_id: &SwapId) -> Vec<ClockSkew> {
+        let mut skews = self
+            .ledgers
+            .lock()
+            .unwrap()
+            .values()
+            .copied()
+            .collect::<Vec<_>>();
+        skews.extend(self.counterparties.lock().unwrap().get(swap_id).copied());
+        skews.sort_by_key(|skew| skew.source as u8);
+
+        skews
+    }
+}
+
+/// Compare the local clock against the latest block of each ledger once per
+/// `CHECK_INTERVAL`.
+pub async fn watch_ledgers_periodically(
+    bitcoin_connector: BitcoinConnector,
+    ethereum_connector: Web3Connector,
+    monitor: ClockMonitor,
+) {
+    loop {
+        match latest_bitcoin_block_time(bitcoin_connector.clone()).await {
+            Ok(block_time) => monitor.record_ledger(ClockSkew::measure(
+                Source::Bitcoin,
+                Timestamp::now(),
+                block_time,
+            )),
+            Err(e) => log::warn!("failed to fetch the latest Bitcoin block: {:?}", e),
+        }
+
+        match latest_ethereum_block_time(ethereum_connector.clone()).await {
+            Ok(block_time) => monitor.record_ledger(ClockSkew::measure(
+                Source::Ethereum,
+                Timestamp::now(),
+                block_time,
+            )),
+            Err(e) => log::warn!("failed to fetch the latest Ethereum block: {:?}", e),
+        }
+
+        Delay::new(Instant::now() + CHECK_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
+
+async fn latest_bitcoin_block_time(mut connector: BitcoinConnector) -> anyhow::Result<Timestamp> {
+    let block = connector.latest_block().await?;
+
+    Ok(Timestamp::from(block.header.time))
+}
+
+async fn latest_ethereum_block_time(mut connector: Web3Connector) -> anyhow::Result<Timestamp> {
+    let block = connector
+        .latest_block()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("the Ethereum node did not return the latest block"))?;
+
+    Ok(Timestamp::from(block.timestamp.low_u32()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn skews_within_the_tolerance_of_the_source_are_not_kept() {
+        let monitor = ClockMonitor::new(300);
+        let swap_id = SwapId::default();
+        let now = Timestamp::from(1_000_000);
+
+        monitor.record_ledger(ClockSkew::measure(
+            Source::Bitcoin,
+            now,
+            Timestamp::from(1_000_000 - 3600),
+        ));
+        monitor.record_ledger(ClockSkew::measure(
+            Source::Ethereum,
+            now,
+            Timestamp::from(1_000_000 + 600),
+        ));
+        monitor.record_counterparty(
+            swap_id,
+            ClockSkew::measure(Source::Counterparty, now, Timestamp::from(1_000_000 - 200)),
+        );
+
+        assert_that(&monitor.skews_of(&swap_id)).is_equal_to(vec![ClockSkew {
+            source: Source::Ethereum,
+            seconds: -600,
+        }]);
+    }
+
+    #[test]
+    fn a_new_ledger_measurement_replaces_the_previous_one() {
+        let monitor = ClockMonitor::new(300);
+        let now = Timestamp::from(1_000_000);
+
+        monitor.record_ledger(ClockSkew::measure(
+            Source::Ethereum,
+            now,
+            Timestamp::from(1_000_000 - 900),
+        ));
+        monitor.record_ledger(ClockSkew::measure(
+            Source::Ethereum,
+            now,
+            Timestamp::from(1_000_000 - 15),
+        ));
+
+        assert_that(&monitor.skews_of(&SwapId::default())).is_empty();
+    }
+}
@@ -4,9 +4,10 @@ use crate::{
     swap_protocols::{
         asset::AssetKind,
         ledger::{Bitcoin, Ethereum, LedgerKind},
-        rfc003::messages::Decision,
+        rfc003::{messages::Decision, Rate},
         SwapId, SwapProtocol,
     },
+    timestamp::Timestamp,
 };
 use bitcoin::util::amount::Denomination;
 use libp2p_comit::frame::Header;
@@ -143,6 +144,30 @@ impl FromHeader for Decision {
     }
 }
 
+impl FromHeader for Timestamp {
+    fn from_header(header: Header) -> Result<Self, serde_json::Error> {
+        header.value::<Timestamp>()
+    }
+}
+
+impl ToHeader for Timestamp {
+    fn to_header(&self) -> Result<Header, serde_json::Error> {
+        Header::with_value(self)
+    }
+}
+
+impl FromHeader for Rate {
+    fn from_header(header: Header) -> Result<Self, serde_json::Error> {
+        header.value::<Rate>()
+    }
+}
+
+impl ToHeader for Rate {
+    fn to_header(&self) -> Result<Header, serde_json::Error> {
+        Header::with_value(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,7 +1,14 @@
-use crate::config::{Bitcoin, Data, Ethereum, Network, Socket};
+use crate::config::{
+    ApiKey, Backup, Bitcoin, BlockLag, Chaos, ClockSkew, Data, Database, Derivation, Ethereum,
+    Expiries, FundingWindow, HtlcTemplates, IdentityReuse, Listener, LogFile, ModuleLevels,
+    Network, Notifications, PriceFeed, Retention, ScanWindow, Socket, Tracing, Webhook, WireLog,
+};
 use config as config_rs;
 use log::LevelFilter;
-use std::{ffi::OsStr, path::Path};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
 /// This struct aims to represent the configuration file as it appears on disk.
 ///
@@ -16,6 +23,22 @@ pub struct File {
     pub logging: Option<Logging>,
     pub bitcoin: Option<Bitcoin>,
     pub ethereum: Option<Ethereum>,
+    pub backup: Option<Backup>,
+    pub retention: Option<Retention>,
+    pub webhook: Option<Webhook>,
+    pub funding_window: Option<FundingWindow>,
+    pub scan_window: Option<ScanWindow>,
+    pub clock_skew: Option<ClockSkew>,
+    pub block_lag: Option<BlockLag>,
+    pub price_feed: Option<PriceFeed>,
+    pub htlc_templates: Option<HtlcTemplates>,
+    pub expiries: Option<Expiries>,
+    pub notifications: Option<Notifications>,
+    pub derivation: Option<Derivation>,
+    pub database: Option<Database>,
+    pub identity_reuse: Option<IdentityReuse>,
+    pub chaos: Option<Chaos>,
+    pub tracing: Option<Tracing>,
 }
 
 impl File {
@@ -27,6 +50,22 @@ impl File {
             logging: Option::None,
             bitcoin: Option::None,
             ethereum: Option::None,
+            backup: Option::None,
+            retention: Option::None,
+            webhook: Option::None,
+            funding_window: Option::None,
+            scan_window: Option::None,
+            clock_skew: Option::None,
+            block_lag: Option::None,
+            price_feed: Option::None,
+            htlc_templates: Option::None,
+            expiries: Option::None,
+            notifications: Option::None,
+            derivation: Option::None,
+            database: Option::None,
+            identity_reuse: Option::None,
+            chaos: Option::None,
+            tracing: Option::None,
         }
     }
 
@@ -43,12 +82,19 @@ impl File {
 pub struct Logging {
     pub level: Option<LevelFilter>,
     pub structured: Option<bool>,
+    pub wire_log: Option<WireLog>,
+    pub file: Option<LogFile>,
+    pub modules: Option<ModuleLevels>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct HttpApi {
     pub socket: Socket,
     pub cors: Option<Cors>,
+    pub listeners: Option<Vec<Listener>>,
+    /// Serve the API on this unix socket instead of `socket` and `listeners`.
+    pub unix_socket: Option<PathBuf>,
+    pub api_keys: Option<Vec<ApiKey>>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -79,7 +125,12 @@ pub enum None {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::Settings;
+    use crate::{
+        config::{PayoutAccount, Settings, Telegram, Tls, Token, Web3Node},
+        http_api::Permission,
+        swap_protocols::ledger::ethereum::ChainId,
+        webhook::EventKind,
+    };
     use log::LevelFilter;
     use spectral::prelude::*;
     use std::{
@@ -105,6 +156,67 @@ mod tests {
             logging: Logging {
                 level: Option::Some(LevelFilter::Debug),
                 structured: Option::None,
+                wire_log: Option::None,
+                file: Option::None,
+                modules: Option::None,
+            },
+        });
+    }
+
+    #[test]
+    fn wire_log_limits_are_optional() {
+        let file_contents = r#"
+        [logging.wire_log]
+        path = "/tmp/comit/wire.log"
+        max_files = 3
+        "#;
+
+        let config_file = toml::from_str(file_contents);
+
+        assert_that(&config_file).is_ok_containing(LoggingOnlyConfig {
+            logging: Logging {
+                level: Option::None,
+                structured: Option::None,
+                wire_log: Option::Some(WireLog {
+                    path: PathBuf::from("/tmp/comit/wire.log"),
+                    max_file_size: Option::None,
+                    max_files: Option::Some(3),
+                }),
+                file: Option::None,
+                modules: Option::None,
+            },
+        });
+    }
+
+    #[test]
+    fn log_file_and_module_levels_are_optional() {
+        let file_contents = r#"
+        [logging.file]
+        path = "/tmp/comit/cnd.log"
+        max_age_hours = 24
+
+        [logging.modules]
+        btsieve = "TRACE"
+        "#;
+
+        let config_file = toml::from_str(file_contents);
+
+        assert_that(&config_file).is_ok_containing(LoggingOnlyConfig {
+            logging: Logging {
+                level: Option::None,
+                structured: Option::None,
+                wire_log: Option::None,
+                file: Option::Some(LogFile {
+                    path: PathBuf::from("/tmp/comit/cnd.log"),
+                    max_file_size: Option::None,
+                    max_age_hours: Option::Some(24),
+                    max_files: Option::None,
+                }),
+                modules: Option::Some(ModuleLevels {
+                    network: Option::None,
+                    btsieve: Option::Some(LevelFilter::Trace),
+                    http_api: Option::None,
+                }),
             },
         });
     }
@@ -160,6 +272,22 @@ port = 8000
 [http_api.cors]
 allowed_origins = "all"
 
+[[http_api.listeners]]
+address = "192.168.1.10"
+port = 8443
+
+[http_api.listeners.tls]
+cert = "/etc/cnd/cert.pem"
+key = "/etc/cnd/key.pem"
+
+[[http_api.api_keys]]
+key = "dashboard-key"
+permission = "read_only"
+
+[[http_api.api_keys]]
+key = "trading-key"
+permission = "trade"
+
 [data]
 dir = "/tmp/comit/"
 
@@ -173,11 +301,98 @@ node_url = "http://example.com/"
 
 [ethereum]
 node_url = "http://example.com/"
+max_concurrent_requests = 2
+watch_pending_transactions = true
+quorum = 2
+verify_receipts = true
+
+[[ethereum.fallback_nodes]]
+node_url = "https://example.org/"
+
+[[ethereum.tokens]]
+symbol = "dai"
+chain_id = 3
+token_contract = "0xb97048628db6b661d4c2aa833e95dbe1a905b280"
+decimals = 18
+
+[[ethereum.payout_accounts]]
+name = "cold-wallet"
+address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+
+[backup]
+passphrase = "correct horse battery staple"
+
+[retention]
+max_age_days = 30
+max_finished_swaps = 1000
+
+[webhook]
+url = "http://localhost:3000/cnd-events"
+
+[funding_window]
+minutes = 30
+
+[scan_window]
+expiry_margin_minutes = 120
+
+[clock_skew]
+max_skew_seconds = 60
+
+[block_lag]
+ethereum_max_lag_seconds = 120
+
+[price_feed]
+url = "https://api.coingecko.com/api/v3/"
+currency = "eur"
+
+[htlc_templates]
+ether = "/etc/cnd/ether_htlc.hex"
+
+[expiries]
+bitcoin_confirmations = 3
+safety_margin_minutes = 60
+
+[notifications]
+expiry_warning_minutes = 120
+
+[notifications.telegram]
+bot_token = "123456:ABC-DEF"
+chat_id = "42"
+events = ["swap-request-received", "swap-failed"]
+
+[derivation]
+account_path = "m/84'/0'/0'"
+alice_branch = 2
+bob_branch = 3
+
+[database]
+backend = "postgres"
+url = "postgres://cnd@localhost/cnd"
+
+[identity_reuse]
+refuse = true
+
+[chaos]
+connector_failure_rate = 0.1
+connector_max_delay_ms = 2000
+response_drop_rate = 0.5
+task_restart_interval_secs = 300
+
+[tracing]
+otlp_endpoint = "http://localhost:55681/"
 "#;
 
         let file = File {
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                max_connections: None,
+                max_connections_per_peer: None,
+                max_pending_inbound_substreams: None,
+                max_frame_size: None,
+                max_swaps_per_peer: None,
+                socks5_proxy: None,
+                external_addresses: None,
+                known_headers: None,
             }),
             http_api: Some(HttpApi {
                 socket: Socket {
@@ -187,6 +402,25 @@ node_url = "http://example.com/"
                 cors: Some(Cors {
                     allowed_origins: AllowedOrigins::All(All::All),
                 }),
+                listeners: Some(vec![Listener {
+                    address: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10)),
+                    port: 8443,
+                    tls: Some(Tls {
+                        cert: PathBuf::from("/etc/cnd/cert.pem"),
+                        key: PathBuf::from("/etc/cnd/key.pem"),
+                    }),
+                }]),
+                unix_socket: None,
+                api_keys: Some(vec![
+                    ApiKey {
+                        key: String::from("dashboard-key"),
+                        permission: Permission::ReadOnly,
+                    },
+                    ApiKey {
+                        key: String::from("trading-key"),
+                        permission: Permission::Trade,
+                    },
+                ]),
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/comit/"),
@@ -194,13 +428,106 @@ node_url = "http://example.com/"
             logging: Some(Logging {
                 level: Some(LevelFilter::Debug),
                 structured: Some(false),
+                wire_log: None,
+                file: None,
+                modules: None,
             }),
             bitcoin: Some(Bitcoin {
                 network: bitcoin::Network::Bitcoin,
                 node_url: "http://example.com".parse().unwrap(),
+                max_concurrent_requests: None,
+                verbose_blocks: None,
+                memo: None,
+                rpc_credentials: None,
+                redeem_destinations: None,
+                esplora: None,
+                fallback: None,
+                mempool: None,
+                spv: None,
             }),
             ethereum: Some(Ethereum {
                 node_url: "http://example.com".parse().unwrap(),
+                max_concurrent_requests: Some(2),
+                tokens: Some(vec![Token {
+                    symbol: String::from("dai"),
+                    chain_id: ChainId::ropsten(),
+                    token_contract: "b97048628db6b661d4c2aa833e95dbe1a905b280".parse().unwrap(),
+                    decimals: 18,
+                }]),
+                payout_accounts: Some(vec![PayoutAccount {
+                    name: String::from("cold-wallet"),
+                    address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+                        .parse()
+                        .unwrap(),
+                }]),
+                watch_pending_transactions: Some(true),
+                fallback_nodes: Some(vec![Web3Node {
+                    node_url: "https://example.org".parse().unwrap(),
+                }]),
+                quorum: Some(2),
+                verify_receipts: Some(true),
+            }),
+            backup: Some(Backup {
+                passphrase: String::from("correct horse battery staple"),
+            }),
+            retention: Some(Retention {
+                max_age_days: Some(30),
+                max_finished_swaps: Some(1000),
+            }),
+            webhook: Some(Webhook {
+                url: "http://localhost:3000/cnd-events".parse().unwrap(),
+            }),
+            funding_window: Some(FundingWindow { minutes: 30 }),
+            scan_window: Some(ScanWindow {
+                expiry_margin_minutes: Some(120),
+            }),
+            clock_skew: Some(ClockSkew {
+                max_skew_seconds: Some(60),
+            }),
+            block_lag: Some(BlockLag {
+                bitcoin_max_lag_seconds: None,
+                ethereum_max_lag_seconds: Some(120),
+            }),
+            price_feed: Some(PriceFeed {
+                url: "https://api.coingecko.com/api/v3/".parse().unwrap(),
+                currency: Some(String::from("eur")),
+            }),
+            htlc_templates: Some(HtlcTemplates {
+                ether: Some(PathBuf::from("/etc/cnd/ether_htlc.hex")),
+                erc20: None,
+            }),
+            expiries: Some(Expiries {
+                bitcoin_confirmations: Some(3),
+                ethereum_confirmations: None,
+                safety_margin_minutes: Some(60),
+            }),
+            notifications: Some(Notifications {
+                expiry_warning_minutes: Some(120),
+                smtp: None,
+                telegram: Some(Telegram {
+                    bot_token: String::from("123456:ABC-DEF"),
+                    chat_id: String::from("42"),
+                    events: Some(vec![EventKind::SwapRequestReceived, EventKind::SwapFailed]),
+                }),
+            }),
+            derivation: Some(Derivation {
+                account_path: String::from("m/84'/0'/0'"),
+                alice_branch: 2,
+                bob_branch: 3,
+            }),
+            database: Some(Database::Postgres {
+                url: String::from("postgres://cnd@localhost/cnd"),
+            }),
+            identity_reuse: Some(IdentityReuse { refuse: Some(true) }),
+            chaos: Some(Chaos {
+                connector_failure_rate: Some(0.1),
+                connector_max_delay_ms: Some(2000),
+                response_drop_rate: Some(0.5),
+                task_restart_interval_secs: Some(300),
+            }),
+            tracing: Some(Tracing {
+                otlp_endpoint: "http://localhost:55681".parse().unwrap(),
+                service_name: None,
             }),
         };
 
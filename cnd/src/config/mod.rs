@@ -2,9 +2,20 @@ pub mod file;
 mod serde_bitcoin_network;
 pub mod settings;
 
+use crate::{
+    ethereum::{Address, ChecksumAddress},
+    http_api::Permission,
+    swap_protocols::ledger::ethereum::ChainId,
+    webhook::EventKind,
+};
 use libp2p::Multiaddr;
+use log::LevelFilter;
 use serde::{Deserialize, Serialize};
-use std::{net::IpAddr, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
 
 pub use self::{file::File, settings::Settings};
 
@@ -16,6 +27,27 @@ pub struct Data {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Network {
     pub listen: Vec<Multiaddr>,
+    /// Upper bound of connections to all peers together.
+    pub max_connections: Option<usize>,
+    /// Upper bound of connections to a single peer.
+    pub max_connections_per_peer: Option<usize>,
+    /// Upper bound of requests a peer may have pending on one connection.
+    pub max_pending_inbound_substreams: Option<usize>,
+    /// Upper bound in bytes of a single frame a peer sends us, larger ones
+    /// are rejected before they are fully received.
+    pub max_frame_size: Option<usize>,
+    /// Upper bound of swaps a single peer may have requested from us that are
+    /// not over yet, further requests are declined.
+    pub max_swaps_per_peer: Option<usize>,
+    /// Dial peers and connect to the blockchain nodes through this SOCKS5
+    /// proxy, e.g. Tor.
+    pub socks5_proxy: Option<SocketAddr>,
+    /// Addresses other than the listen addresses that peers can reach us on,
+    /// e.g. the onion address of a hidden service forwarding to one of them.
+    pub external_addresses: Option<Vec<Multiaddr>>,
+    /// Mandatory headers we understand per request type in addition to the
+    /// ones cnd implements, e.g. for messages handled by a plugin.
+    pub known_headers: Option<BTreeMap<String, Vec<String>>>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -24,18 +56,377 @@ pub struct Socket {
     pub port: u16,
 }
 
+/// An address the HTTP API is served on in addition to `http_api.socket`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Listener {
+    pub address: IpAddr,
+    pub port: u16,
+    /// Serve HTTPS instead of plain HTTP on this address.
+    pub tls: Option<Tls>,
+}
+
+/// A key clients of the HTTP API send as `Authorization: Bearer <key>`.
+///
+/// Once any key is configured, requests without a known key are rejected.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ApiKey {
+    pub key: String,
+    pub permission: Permission,
+}
+
+/// PEM encoded certificate chain and private key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Tls {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Bitcoin {
     #[serde(with = "crate::config::serde_bitcoin_network")]
     pub network: bitcoin::Network,
     #[serde(with = "url_serde")]
     pub node_url: reqwest::Url,
+    /// Upper bound of requests sent to the node at the same time.
+    pub max_concurrent_requests: Option<usize>,
+    /// Fetch verbose blocks to only decode transactions paying to a watched
+    /// address.
+    pub verbose_blocks: Option<bool>,
+    /// Tag fund transactions constructed by cnd with an OP_RETURN output
+    /// holding this memo followed by the swap id.
+    pub memo: Option<String>,
+    /// Credentials for the JSON-RPC interface of the node, which is needed to
+    /// broadcast automatic refunds.
+    pub rpc_credentials: Option<RpcCredentials>,
+    /// Send redeemed and refunded bitcoin to addresses of this wallet if no
+    /// address is given when executing the action.
+    pub redeem_destinations: Option<RedeemDestinations>,
+    pub esplora: Option<Esplora>,
+    /// Fetch blocks from this Esplora instance while the node or the Esplora
+    /// instance above fails. The latest blocks of the latter are checked
+    /// against it to detect a data source that is on another chain.
+    pub fallback: Option<Esplora>,
+    pub mempool: Option<Mempool>,
+    pub spv: Option<Spv>,
+}
+
+/// Fetch blocks from the REST API of an Esplora instance instead of the node,
+/// e.g. `https://blockstream.info/testnet/api/` or a self-hosted one. The node
+/// is still used to broadcast automatic refunds.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Esplora {
+    #[serde(with = "url_serde")]
+    pub url: reqwest::Url,
 }
 
+/// Verify the proof of work and the merkle roots of the blocks that are
+/// fetched instead of trusting the node or the Esplora instance.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Spv {
+    /// The Esplora instance that merkle proofs of the transactions of verbose
+    /// blocks are requested from, defaults to `esplora` or `fallback`.
+    pub merkle_proofs: Option<Esplora>,
+}
+
+/// Watch the mempool of the node, which needs `rpc_credentials`, to report
+/// Bitcoin HTLCs as `FUNDED_UNCONFIRMED` as soon as their fund transaction
+/// is broadcast.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Mempool {
+    /// How many confirmations a fund transaction seen in the mempool needs
+    /// for the HTLC to count as funded, defaults to 1.
+    pub confirmations: Option<u32>,
+}
+
+pub const DEFAULT_BITCOIN_CONFIRMATIONS: u32 = 1;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RpcCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// The P2WPKH receive addresses `<xpub>/0/<index>` are handed out in order,
+/// one per swap.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RedeemDestinations {
+    pub xpub: String,
+    /// How many addresses in a row may be handed out without receiving funds,
+    /// defaults to 20 which is how far most wallets look ahead.
+    pub gap_limit: Option<u32>,
+}
+
+/// OP_RETURN outputs are relayed with up to 80 bytes of data, 36 of which are
+/// taken by the swap id.
+pub const MAX_BITCOIN_MEMO_LENGTH: usize = 44;
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Ethereum {
     #[serde(with = "url_serde")]
     pub node_url: reqwest::Url,
+    /// Upper bound of requests sent to the node at the same time.
+    pub max_concurrent_requests: Option<usize>,
+    /// ERC20 tokens in addition to the built-in ones, or their contracts on
+    /// other chains.
+    pub tokens: Option<Vec<Token>>,
+    /// Addresses that swap requests and accept bodies can refer to by name
+    /// instead of giving a redeem identity.
+    pub payout_accounts: Option<Vec<PayoutAccount>>,
+    /// Learn the secret from redeem transactions that are not mined yet.
+    pub watch_pending_transactions: Option<bool>,
+    /// Further nodes of the same chain, e.g. RPC providers. Requests fail over
+    /// to them if the node above fails.
+    pub fallback_nodes: Option<Vec<Web3Node>>,
+    /// How many of all nodes have to return the same block or receipt for it
+    /// to be trusted, defaults to a majority of them.
+    pub quorum: Option<usize>,
+    /// Check that receipts add up to the receiptsRoot of their block before
+    /// acting on them, which fetches all receipts of the block.
+    pub verify_receipts: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Web3Node {
+    #[serde(with = "url_serde")]
+    pub node_url: reqwest::Url,
+}
+
+/// An ERC20 token that assets on the chain with the given id can refer to by
+/// its symbol in the HTTP API, e.g. `{"name":"dai","quantity":"..."}`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Token {
+    pub symbol: String,
+    pub chain_id: ChainId,
+    pub token_contract: Address,
+    pub decimals: u8,
+}
+
+/// The address has to be written with its EIP-55 checksum so that typos are
+/// caught when cnd starts rather than burning the funds of a swap.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PayoutAccount {
+    pub name: String,
+    pub address: ChecksumAddress,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Backup {
+    pub passphrase: String,
+}
+
+/// Finished swaps are archived once they exceed either limit. A limit that
+/// is not set is not enforced.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Retention {
+    pub max_age_days: Option<u32>,
+    pub max_finished_swaps: Option<u32>,
+}
+
+/// As Bob, abandon accepted swaps whose alpha ledger is not funded within this
+/// many minutes after accepting, rather than watching the ledgers until the
+/// HTLCs expire.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct FundingWindow {
+    pub minutes: u32,
+}
+
+/// How long after both expiries of a swap passed cnd gives up on a swap whose
+/// HTLCs were never deployed, rather than scanning the ledgers for them
+/// forever.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ScanWindow {
+    /// Defaults to 60.
+    pub expiry_margin_minutes: Option<u32>,
+}
+
+/// How far the local clock may be off from the latest blocks and the clocks of
+/// counterparties before cnd warns about it.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ClockSkew {
+    /// Defaults to 300.
+    pub max_skew_seconds: Option<u32>,
+}
+
+/// How long the latest block of a ledger may stay the same before cnd warns
+/// that its node stalled or is cut off from the network.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BlockLag {
+    /// Defaults to 7200, Bitcoin blocks are an hour apart every now and then.
+    pub bitcoin_max_lag_seconds: Option<u32>,
+    /// Defaults to 300.
+    pub ethereum_max_lag_seconds: Option<u32>,
+}
+
+/// A CoinGecko compatible API, e.g. `https://api.coingecko.com/api/v3/`, that
+/// is asked for the prices of Bitcoin and Ether. Swaps and actions then show
+/// an indicative value of their amounts in `currency`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PriceFeed {
+    #[serde(with = "url_serde")]
+    pub url: reqwest::Url,
+    /// Defaults to `usd`.
+    pub currency: Option<String>,
+}
+
+/// Files with hex encoded EVM bytecode that replaces the Ether and ERC20 HTLCs
+/// cnd ships with. The parameters of the HTLC are written as placeholders, e.g.
+/// `{secret_hash}`, see `swap_protocols::rfc003::ethereum::htlc_templates`.
+/// The gas limits of the shipped HTLCs are used for them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HtlcTemplates {
+    pub ether: Option<PathBuf>,
+    pub erc20: Option<PathBuf>,
+}
+
+/// How the expiries of swaps that are requested without them, as well as
+/// those recommended by `GET /expiry-recommendation`, are computed.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Expiries {
+    /// Confirmations after which a Bitcoin transaction is considered final,
+    /// defaults to 6.
+    pub bitcoin_confirmations: Option<u32>,
+    /// Confirmations after which an Ethereum transaction is considered final,
+    /// defaults to 30.
+    pub ethereum_confirmations: Option<u32>,
+    /// Added to each step of a swap to cover slow blocks and the time the
+    /// parties need to act, defaults to two hours.
+    pub safety_margin_minutes: Option<u32>,
+}
+
+/// Events that need the attention of the user, e.g. an automatic refund, and
+/// everything that happens to a swap are POSTed as JSON to this URL.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Webhook {
+    #[serde(with = "url_serde")]
+    pub url: reqwest::Url,
+}
+
+/// Export a trace per swap to an OpenTelemetry collector, e.g. to follow the
+/// lifecycle of a swap in Jaeger.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Tracing {
+    /// Base URL of the OTLP/HTTP receiver, spans are POSTed to `/v1/traces`.
+    #[serde(with = "url_serde")]
+    pub otlp_endpoint: reqwest::Url,
+    pub service_name: Option<String>,
+}
+
+/// Send the events that are POSTed to the webhook by email and Telegram too.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Notifications {
+    /// How many minutes before an HTLC expires to warn about it, defaults to
+    /// an hour.
+    pub expiry_warning_minutes: Option<u32>,
+    pub smtp: Option<Smtp>,
+    pub telegram: Option<Telegram>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Smtp {
+    /// Host name of the server, connected to with STARTTLS on port 587.
+    pub server: String,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    pub to: String,
+    /// Only send these events, defaults to all of them.
+    pub events: Option<Vec<EventKind>>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Telegram {
+    pub bot_token: String,
+    pub chat_id: String,
+    /// Only send these events, defaults to all of them.
+    pub events: Option<Vec<EventKind>>,
+}
+
+/// Derive the redeem and refund identities of swaps from the BIP32 tree of the
+/// seed, e.g. to recover them with an HD wallet. Without this section they are
+/// hashed from the seed of each swap.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Derivation {
+    /// Path of the account key below the master key of the seed, e.g.
+    /// "m/84'/0'/0'".
+    pub account_path: String,
+    /// Non-hardened index of the branch below the account that swaps we
+    /// started derive from.
+    pub alice_branch: u32,
+    /// Non-hardened index of the branch below the account that swaps the
+    /// counterparty started derive from.
+    pub bob_branch: u32,
+}
+
+/// Identities cnd derived for a swap that were already issued for another swap
+/// link both swaps, e.g. because the seed was restored on another node. They
+/// are logged and reported in the swap resource.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct IdentityReuse {
+    /// Refuse to start or accept a swap that would re-use an identity instead,
+    /// defaults to false.
+    pub refuse: Option<bool>,
+}
+
+/// Inject faults to verify that swaps complete or are refunded under adverse
+/// conditions. Requires cnd to be built with the `chaos` feature, never use
+/// this for real swaps.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Chaos {
+    /// Probability between 0 and 1 that a request to a Bitcoin or Ethereum
+    /// node fails.
+    pub connector_failure_rate: Option<f64>,
+    /// Requests to nodes are delayed by a random duration up to this.
+    pub connector_max_delay_ms: Option<u64>,
+    /// Probability between 0 and 1 that a swap request from a peer is never
+    /// answered.
+    pub response_drop_rate: Option<f64>,
+    /// Every this many seconds the tasks of a random ongoing swap are
+    /// cancelled and the swap is resumed as if cnd was restarted.
+    pub task_restart_interval_secs: Option<u64>,
+}
+
+/// Where swaps are stored, without this section in SQLite in the data
+/// directory.
+///
+/// Only the swaps themselves and whether they are finished live in PostgreSQL,
+/// bookkeeping such as action invocations, fees and failures as well as
+/// `GET /stats` and backups stay with the SQLite database.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum Database {
+    Sqlite,
+    Postgres { url: String },
+}
+
+/// Record the COMIT frames exchanged with peers, with secrets and identities
+/// redacted, e.g. to debug the interoperability with another implementation.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WireLog {
+    pub path: PathBuf,
+    /// Size in bytes after which the log file is rotated.
+    pub max_file_size: Option<u64>,
+    /// Number of rotated log files that are kept.
+    pub max_files: Option<usize>,
+}
+
+/// Write the log to a file in addition to stdout.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LogFile {
+    pub path: PathBuf,
+    /// Size in bytes after which the log file is rotated.
+    pub max_file_size: Option<u64>,
+    /// Hours after which the log file is rotated even if it is not full.
+    pub max_age_hours: Option<u64>,
+    /// Number of rotated log files that are kept.
+    pub max_files: Option<usize>,
+}
+
+/// Levels that override `logging.level` for the records of parts of cnd.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ModuleLevels {
+    pub network: Option<LevelFilter>,
+    pub btsieve: Option<LevelFilter>,
+    pub http_api: Option<LevelFilter>,
 }
 
 #[cfg(test)]
@@ -52,17 +443,95 @@ mod tests {
             r#"
             listen = ["/ip4/0.0.0.0/tcp/9939", "/ip4/127.0.0.1/tcp/9939"]
             "#,
+            r#"
+            listen = ["/ip4/0.0.0.0/tcp/9939"]
+            max_connections = 64
+            max_connections_per_peer = 2
+            max_pending_inbound_substreams = 8
+            max_frame_size = 65536
+            max_swaps_per_peer = 16
+            "#,
+            r#"
+            listen = ["/ip4/127.0.0.1/tcp/9939"]
+            socks5_proxy = "127.0.0.1:9050"
+            external_addresses = ["/dns4/3g2upl4pq6kufc4m.onion/tcp/9939"]
+            "#,
+            r#"
+            listen = ["/ip4/0.0.0.0/tcp/9939"]
+
+            [known_headers]
+            PING = ["id"]
+            SWAP = ["fee_rate"]
+            "#,
         ];
 
         let expected = vec![
             Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                max_connections: None,
+                max_connections_per_peer: None,
+                max_pending_inbound_substreams: None,
+                max_frame_size: None,
+                max_swaps_per_peer: None,
+                socks5_proxy: None,
+                external_addresses: None,
+                known_headers: None,
             },
             Network {
                 listen: (vec![
                     "/ip4/0.0.0.0/tcp/9939".parse().unwrap(),
                     "/ip4/127.0.0.1/tcp/9939".parse().unwrap(),
                 ]),
+                max_connections: None,
+                max_connections_per_peer: None,
+                max_pending_inbound_substreams: None,
+                max_frame_size: None,
+                max_swaps_per_peer: None,
+                socks5_proxy: None,
+                external_addresses: None,
+                known_headers: None,
+            },
+            Network {
+                listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                max_connections: Some(64),
+                max_connections_per_peer: Some(2),
+                max_pending_inbound_substreams: Some(8),
+                max_frame_size: Some(65536),
+                max_swaps_per_peer: Some(16),
+                socks5_proxy: None,
+                external_addresses: None,
+                known_headers: None,
+            },
+            Network {
+                listen: vec!["/ip4/127.0.0.1/tcp/9939".parse().unwrap()],
+                max_connections: None,
+                max_connections_per_peer: None,
+                max_pending_inbound_substreams: None,
+                max_frame_size: None,
+                max_swaps_per_peer: None,
+                socks5_proxy: Some("127.0.0.1:9050".parse().unwrap()),
+                external_addresses: Some(vec!["/dns4/3g2upl4pq6kufc4m.onion/tcp/9939"
+                    .parse()
+                    .unwrap()]),
+                known_headers: None,
+            },
+            Network {
+                listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                max_connections: None,
+                max_connections_per_peer: None,
+                max_pending_inbound_substreams: None,
+                max_frame_size: None,
+                max_swaps_per_peer: None,
+                socks5_proxy: None,
+                external_addresses: None,
+                known_headers: Some(
+                    vec![
+                        (String::from("PING"), vec![String::from("id")]),
+                        (String::from("SWAP"), vec![String::from("fee_rate")]),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
             },
         ];
 
@@ -85,10 +554,28 @@ mod tests {
             r#"
             network = "testnet"
             node_url = "http://example.com:8545"
+
+            [esplora]
+            url = "https://blockstream.info/testnet/api/"
+
+            [fallback]
+            url = "https://mempool.space/testnet/api/"
             "#,
             r#"
             network = "regtest"
             node_url = "http://example.com:8545"
+            max_concurrent_requests = 4
+            verbose_blocks = true
+            memo = "comit"
+            rpc_credentials = { username = "bitcoin", password = "secret" }
+            mempool = { confirmations = 3 }
+
+            [spv.merkle_proofs]
+            url = "https://blockstream.info/api/"
+
+            [redeem_destinations]
+            xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"
+            gap_limit = 50
             "#,
         ];
 
@@ -96,14 +583,57 @@ mod tests {
             Bitcoin {
                 network: bitcoin::Network::Bitcoin,
                 node_url: Url::parse("http://example.com:8545").unwrap(),
+                max_concurrent_requests: None,
+                verbose_blocks: None,
+                memo: None,
+                rpc_credentials: None,
+                redeem_destinations: None,
+                esplora: None,
+                fallback: None,
+                mempool: None,
+                spv: None,
             },
             Bitcoin {
                 network: bitcoin::Network::Testnet,
                 node_url: Url::parse("http://example.com:8545").unwrap(),
+                max_concurrent_requests: None,
+                verbose_blocks: None,
+                memo: None,
+                rpc_credentials: None,
+                redeem_destinations: None,
+                esplora: Some(Esplora {
+                    url: Url::parse("https://blockstream.info/testnet/api/").unwrap(),
+                }),
+                fallback: Some(Esplora {
+                    url: Url::parse("https://mempool.space/testnet/api/").unwrap(),
+                }),
+                mempool: None,
+                spv: None,
             },
             Bitcoin {
                 network: bitcoin::Network::Regtest,
                 node_url: Url::parse("http://example.com:8545").unwrap(),
+                max_concurrent_requests: Some(4),
+                verbose_blocks: Some(true),
+                memo: Some(String::from("comit")),
+                rpc_credentials: Some(RpcCredentials {
+                    username: String::from("bitcoin"),
+                    password: String::from("secret"),
+                }),
+                redeem_destinations: Some(RedeemDestinations {
+                    xpub: String::from("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"),
+                    gap_limit: Some(50),
+                }),
+                esplora: None,
+                fallback: None,
+                mempool: Some(Mempool {
+                    confirmations: Some(3),
+                }),
+                spv: Some(Spv {
+                    merkle_proofs: Some(Esplora {
+                        url: Url::parse("https://blockstream.info/api/").unwrap(),
+                    }),
+                }),
             },
         ];
 
@@ -1,8 +1,17 @@
-use crate::config::{file, Bitcoin, Data, Ethereum, File, Network, Socket};
+use crate::config::{
+    file, ApiKey, Backup, Bitcoin, BlockLag, Chaos, ClockSkew, Data, Database, Derivation,
+    Ethereum, Expiries, File, FundingWindow, HtlcTemplates, IdentityReuse, Listener, LogFile,
+    Mempool, ModuleLevels, Network, Notifications, PriceFeed, Retention, ScanWindow, Socket, Spv,
+    Tracing, Webhook, WireLog, MAX_BITCOIN_MEMO_LENGTH,
+};
 use anyhow::Context;
 use log::LevelFilter;
 use reqwest::Url;
-use std::net::{IpAddr, Ipv4Addr};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+};
 
 /// This structs represents the settings as they are used through out the code.
 ///
@@ -18,17 +27,56 @@ pub struct Settings {
     pub logging: Logging,
     pub bitcoin: Bitcoin,
     pub ethereum: Ethereum,
+    pub backup: Option<Backup>,
+    pub retention: Option<Retention>,
+    pub webhook: Option<Webhook>,
+    pub funding_window: Option<FundingWindow>,
+    pub scan_window: Option<ScanWindow>,
+    pub clock_skew: Option<ClockSkew>,
+    pub block_lag: Option<BlockLag>,
+    pub price_feed: Option<PriceFeed>,
+    pub htlc_templates: Option<HtlcTemplates>,
+    pub expiries: Option<Expiries>,
+    pub notifications: Option<Notifications>,
+    pub derivation: Option<Derivation>,
+    pub database: Option<Database>,
+    pub identity_reuse: Option<IdentityReuse>,
+    pub chaos: Option<Chaos>,
+    pub tracing: Option<Tracing>,
 }
 
 impl From<Settings> for File {
     fn from(settings: Settings) -> Self {
         let Settings {
             network,
-            http_api: HttpApi { socket, cors },
+            http_api:
+                HttpApi {
+                    socket,
+                    cors,
+                    listeners,
+                    unix_socket,
+                    api_keys,
+                },
             data,
-            logging: Logging { level, structured },
+            logging,
             bitcoin,
             ethereum,
+            backup,
+            retention,
+            webhook,
+            funding_window,
+            scan_window,
+            clock_skew,
+            block_lag,
+            price_feed,
+            htlc_templates,
+            expiries,
+            notifications,
+            derivation,
+            database,
+            identity_reuse,
+            chaos,
+            tracing,
         } = settings;
 
         File {
@@ -42,14 +90,44 @@ impl From<Settings> for File {
                         AllowedOrigins::Some(origins) => file::AllowedOrigins::Some(origins),
                     },
                 }),
+                listeners: if listeners.is_empty() {
+                    None
+                } else {
+                    Some(listeners)
+                },
+                unix_socket,
+                api_keys: if api_keys.is_empty() {
+                    None
+                } else {
+                    Some(api_keys)
+                },
             }),
             data: Some(data),
             logging: Some(file::Logging {
-                level: Some(level),
-                structured: Some(structured),
+                level: Some(logging.level),
+                structured: Some(logging.structured),
+                wire_log: logging.wire_log,
+                file: logging.file,
+                modules: Some(logging.modules),
             }),
             bitcoin: Some(bitcoin),
             ethereum: Some(ethereum),
+            backup,
+            retention,
+            webhook,
+            funding_window,
+            scan_window,
+            clock_skew,
+            block_lag,
+            price_feed,
+            htlc_templates,
+            expiries,
+            notifications,
+            derivation,
+            database,
+            identity_reuse,
+            chaos,
+            tracing,
         }
     }
 }
@@ -58,6 +136,10 @@ impl From<Settings> for File {
 pub struct HttpApi {
     pub socket: Socket,
     pub cors: Cors,
+    pub listeners: Vec<Listener>,
+    pub unix_socket: Option<PathBuf>,
+    /// Empty if the HTTP API is open to everyone who can reach it.
+    pub api_keys: Vec<ApiKey>,
 }
 
 impl Default for HttpApi {
@@ -68,6 +150,9 @@ impl Default for HttpApi {
                 port: 8000,
             },
             cors: Cors::default(),
+            listeners: Vec::new(),
+            unix_socket: None,
+            api_keys: Vec::new(),
         }
     }
 }
@@ -98,6 +183,9 @@ pub struct Logging {
     #[derivative(Default(value = "LevelFilter::Debug"))]
     pub level: LevelFilter,
     pub structured: bool,
+    pub wire_log: Option<WireLog>,
+    pub file: Option<LogFile>,
+    pub modules: ModuleLevels,
 }
 
 impl Settings {
@@ -109,8 +197,105 @@ impl Settings {
             logging,
             bitcoin,
             ethereum,
+            backup,
+            retention,
+            webhook,
+            funding_window,
+            scan_window,
+            clock_skew,
+            block_lag,
+            price_feed,
+            htlc_templates,
+            expiries,
+            notifications,
+            derivation,
+            database,
+            identity_reuse,
+            chaos,
+            tracing,
         } = config_file;
 
+        if let Some(memo) = bitcoin.as_ref().and_then(|bitcoin| bitcoin.memo.as_ref()) {
+            if memo.len() > MAX_BITCOIN_MEMO_LENGTH {
+                anyhow::bail!(
+                    "memo in the [bitcoin] section must not be longer than {} bytes",
+                    MAX_BITCOIN_MEMO_LENGTH
+                );
+            }
+        }
+
+        if let Some(bitcoin) = bitcoin.as_ref() {
+            if bitcoin.mempool.is_some() && bitcoin.rpc_credentials.is_none() {
+                anyhow::bail!(
+                    "watching the mempool needs rpc_credentials in the [bitcoin] section"
+                );
+            }
+            if let Some(Mempool {
+                confirmations: Some(0),
+            }) = bitcoin.mempool
+            {
+                anyhow::bail!("mempool.confirmations in the [bitcoin] section must be at least 1");
+            }
+            if let Some(Spv {
+                merkle_proofs: None,
+            }) = bitcoin.spv
+            {
+                if bitcoin.verbose_blocks == Some(true)
+                    && bitcoin.esplora.is_none()
+                    && bitcoin.fallback.is_none()
+                {
+                    anyhow::bail!(
+                        "verifying verbose blocks needs an Esplora instance for merkle proofs in \
+                         the [bitcoin.spv.merkle_proofs] section"
+                    );
+                }
+            }
+            if bitcoin.fallback.is_some() && bitcoin.fallback == bitcoin.esplora {
+                anyhow::bail!(
+                    "the fallback in the [bitcoin] section must be another Esplora instance than \
+                     the one in [bitcoin.esplora]"
+                );
+            }
+        }
+
+        let mut api_keys = HashSet::new();
+        for api_key in http_api
+            .iter()
+            .flat_map(|http_api| http_api.api_keys.iter().flatten())
+        {
+            if api_key.key.is_empty() {
+                anyhow::bail!("api_keys in the [http_api] section must not be empty");
+            }
+            if !api_keys.insert(&api_key.key) {
+                anyhow::bail!("an API key is configured more than once in the [http_api] section");
+            }
+        }
+
+        let mut payout_account_names = HashSet::new();
+        for account in ethereum
+            .iter()
+            .flat_map(|ethereum| ethereum.payout_accounts.iter().flatten())
+        {
+            if !payout_account_names.insert(&account.name) {
+                anyhow::bail!(
+                    "payout account {} is configured more than once in the [ethereum] section",
+                    account.name
+                );
+            }
+        }
+
+        if let Some(ethereum) = ethereum.as_ref() {
+            let nodes = 1 + ethereum.fallback_nodes.as_ref().map_or(0, Vec::len);
+            if let Some(quorum) = ethereum.quorum {
+                if quorum == 0 || quorum > nodes {
+                    anyhow::bail!(
+                        "quorum in the [ethereum] section must be between 1 and the number of nodes, {}",
+                        nodes
+                    );
+                }
+            }
+        }
+
         Ok(Self {
             network: network.unwrap_or_else(|| {
                 let default_socket = "/ip4/0.0.0.0/tcp/9939"
@@ -119,10 +304,25 @@ impl Settings {
 
                 Network {
                     listen: vec![default_socket],
+                    max_connections: None,
+                    max_connections_per_peer: None,
+                    max_pending_inbound_substreams: None,
+                    max_frame_size: None,
+                    max_swaps_per_peer: None,
+                    socks5_proxy: None,
+                    external_addresses: None,
+                    known_headers: None,
                 }
             }),
             http_api: http_api
-                .map(|file::HttpApi { socket, cors }| {
+                .map(|http_api| {
+                    let file::HttpApi {
+                        socket,
+                        cors,
+                        listeners,
+                        unix_socket,
+                        api_keys,
+                    } = http_api;
                     let cors = cors
                         .map(|cors| {
                             let allowed_origins = match cors.allowed_origins {
@@ -137,7 +337,13 @@ impl Settings {
                         })
                         .unwrap_or_default();
 
-                    HttpApi { socket, cors }
+                    HttpApi {
+                        socket,
+                        cors,
+                        listeners: listeners.unwrap_or_default(),
+                        unix_socket,
+                        api_keys: api_keys.unwrap_or_default(),
+                    }
                 })
                 .unwrap_or_default(),
             data: {
@@ -152,11 +358,15 @@ impl Settings {
                 let Logging {
                     level: default_level,
                     structured: default_structured,
+                    ..
                 } = Logging::default();
                 logging
                     .map(|logging| Logging {
                         level: logging.level.unwrap_or(default_level),
                         structured: logging.structured.unwrap_or(default_structured),
+                        wire_log: logging.wire_log,
+                        file: logging.file,
+                        modules: logging.modules.unwrap_or_default(),
                     })
                     .unwrap_or_default()
             },
@@ -164,11 +374,43 @@ impl Settings {
                 network: bitcoin::Network::Regtest,
                 node_url: Url::parse("http://localhost:18443")
                     .expect("static string to be a valid url"),
+                max_concurrent_requests: None,
+                verbose_blocks: None,
+                memo: None,
+                rpc_credentials: None,
+                redeem_destinations: None,
+                esplora: None,
+                fallback: None,
+                mempool: None,
+                spv: None,
             }),
             ethereum: ethereum.unwrap_or_else(|| Ethereum {
                 node_url: Url::parse("http://localhost:8545")
                     .expect("static string to be a valid url"),
+                max_concurrent_requests: None,
+                tokens: None,
+                payout_accounts: None,
+                watch_pending_transactions: None,
+                fallback_nodes: None,
+                quorum: None,
+                verify_receipts: None,
             }),
+            backup,
+            retention,
+            webhook,
+            funding_window,
+            scan_window,
+            clock_skew,
+            block_lag,
+            price_feed,
+            htlc_templates,
+            expiries,
+            notifications,
+            derivation,
+            database,
+            identity_reuse,
+            chaos,
+            tracing,
         })
     }
 }
@@ -187,6 +429,9 @@ mod tests {
             logging: Some(file::Logging {
                 level: None,
                 structured: None,
+                wire_log: None,
+                file: None,
+                modules: None,
             }),
             ..File::default()
         };
@@ -205,6 +450,9 @@ mod tests {
             logging: Some(file::Logging {
                 level: None,
                 structured: Some(true),
+                wire_log: None,
+                file: None,
+                modules: None,
             }),
             ..File::default()
         };
@@ -232,6 +480,9 @@ mod tests {
             .is_equal_to(Logging {
                 level: LevelFilter::Debug,
                 structured: false,
+                wire_log: None,
+                file: None,
+                modules: ModuleLevels::default(),
             })
     }
 
@@ -244,6 +495,9 @@ mod tests {
                     port: 8000,
                 },
                 cors: None,
+                listeners: None,
+                unix_socket: None,
+                api_keys: None,
             }),
             ..File::default()
         };
@@ -278,6 +532,9 @@ mod tests {
                 cors: Cors {
                     allowed_origins: AllowedOrigins::None,
                 },
+                listeners: Vec::new(),
+                unix_socket: None,
+                api_keys: Vec::new(),
             })
     }
 
@@ -295,6 +552,64 @@ mod tests {
             .map(|settings| &settings.network)
             .is_equal_to(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                max_connections: None,
+                max_connections_per_peer: None,
+                max_pending_inbound_substreams: None,
+                max_frame_size: None,
+                max_swaps_per_peer: None,
+                socks5_proxy: None,
+                external_addresses: None,
+                known_headers: None,
             })
     }
+
+    #[test]
+    fn bitcoin_memo_that_does_not_fit_into_op_return_is_rejected() {
+        let config_file = File {
+            bitcoin: Some(Bitcoin {
+                network: bitcoin::Network::Regtest,
+                node_url: Url::parse("http://localhost:18443").unwrap(),
+                max_concurrent_requests: None,
+                verbose_blocks: None,
+                memo: Some("m".repeat(MAX_BITCOIN_MEMO_LENGTH + 1)),
+                rpc_credentials: None,
+                redeem_destinations: None,
+                esplora: None,
+                fallback: None,
+                mempool: None,
+                spv: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file);
+
+        assert_that(&settings).is_err();
+    }
+
+    #[test]
+    fn watching_the_mempool_without_rpc_credentials_is_rejected() {
+        let config_file = File {
+            bitcoin: Some(Bitcoin {
+                network: bitcoin::Network::Regtest,
+                node_url: Url::parse("http://localhost:18443").unwrap(),
+                max_concurrent_requests: None,
+                verbose_blocks: None,
+                memo: None,
+                rpc_credentials: None,
+                redeem_destinations: None,
+                esplora: None,
+                fallback: None,
+                mempool: Some(Mempool {
+                    confirmations: None,
+                }),
+                spv: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file);
+
+        assert_that(&settings).is_err();
+    }
 }
@@ -0,0 +1,210 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, rfc003_action_invocations},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl},
+    swap_protocols::{rfc003::actions::ActionKind, SwapId},
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use crypto::{digest::Digest, sha2::Sha256};
+
+/// An action that was invoked through the HTTP API together with the payload
+/// that was returned for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionInvocation {
+    pub action: ActionKind,
+    pub parameters: serde_json::Value,
+    pub payload: serde_json::Value,
+    pub payload_hash: String,
+    pub invoked_at: NaiveDateTime,
+}
+
+/// Keep track of every action invocation of a swap.
+///
+/// Actions like fund depend on things that change over time (fees, UTXOs), so
+/// invoking them twice may produce two different transactions. Looking up the
+/// previous invocation allows us to hand out the same payload again.
+#[async_trait]
+pub trait ActionHistory: Send + Sync + 'static {
+    async fn record_action_invocation(
+        &self,
+        swap_id: &SwapId,
+        action: ActionKind,
+        parameters: &serde_json::Value,
+        payload: &serde_json::Value,
+    ) -> anyhow::Result<()>;
+
+    /// Return the most recent invocation of `action` with exactly these
+    /// `parameters`, if any.
+    async fn previous_action_invocation(
+        &self,
+        swap_id: &SwapId,
+        action: ActionKind,
+        parameters: &serde_json::Value,
+    ) -> anyhow::Result<Option<ActionInvocation>>;
+
+    /// Return all invocations of the swap's actions, oldest first.
+    async fn action_history(&self, swap_id: &SwapId) -> anyhow::Result<Vec<ActionInvocation>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "rfc003_action_invocations"]
+struct InsertableActionInvocation {
+    swap_id: Text<SwapId>,
+    action: Text<ActionKind>,
+    parameters: Text<serde_json::Value>,
+    payload: Text<serde_json::Value>,
+    payload_hash: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableActionInvocation {
+    action: Text<ActionKind>,
+    parameters: Text<serde_json::Value>,
+    payload: Text<serde_json::Value>,
+    payload_hash: String,
+    invoked_at: NaiveDateTime,
+}
+
+const COLUMNS: (
+    rfc003_action_invocations::action,
+    rfc003_action_invocations::parameters,
+    rfc003_action_invocations::payload,
+    rfc003_action_invocations::payload_hash,
+    rfc003_action_invocations::invoked_at,
+) = (
+    rfc003_action_invocations::action,
+    rfc003_action_invocations::parameters,
+    rfc003_action_invocations::payload,
+    rfc003_action_invocations::payload_hash,
+    rfc003_action_invocations::invoked_at,
+);
+
+impl From<QueryableActionInvocation> for ActionInvocation {
+    fn from(record: QueryableActionInvocation) -> Self {
+        ActionInvocation {
+            action: *record.action,
+            parameters: record.parameters.0,
+            payload: record.payload.0,
+            payload_hash: record.payload_hash,
+            invoked_at: record.invoked_at,
+        }
+    }
+}
+
+#[async_trait]
+impl ActionHistory for Sqlite {
+    async fn record_action_invocation(
+        &self,
+        swap_id: &SwapId,
+        action: ActionKind,
+        parameters: &serde_json::Value,
+        payload: &serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let record = InsertableActionInvocation {
+            swap_id: Text(*swap_id),
+            action: Text(action),
+            parameters: Text(parameters.clone()),
+            payload: Text(payload.clone()),
+            payload_hash: payload_hash(payload),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(rfc003_action_invocations::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn previous_action_invocation(
+        &self,
+        swap_id: &SwapId,
+        action: ActionKind,
+        parameters: &serde_json::Value,
+    ) -> anyhow::Result<Option<ActionInvocation>> {
+        use self::schema::rfc003_action_invocations as invocations;
+
+        let record: Option<QueryableActionInvocation> = self
+            .do_in_transaction(|connection| {
+                invocations::table
+                    .filter(invocations::swap_id.eq(Text(*swap_id)))
+                    .filter(invocations::action.eq(Text(action)))
+                    .filter(invocations::parameters.eq(Text(parameters.clone())))
+                    .order(invocations::id.desc())
+                    .select(COLUMNS)
+                    .first(connection)
+                    .optional()
+            })
+            .await?;
+
+        Ok(record.map(ActionInvocation::from))
+    }
+
+    async fn action_history(&self, swap_id: &SwapId) -> anyhow::Result<Vec<ActionInvocation>> {
+        use self::schema::rfc003_action_invocations as invocations;
+
+        let records: Vec<QueryableActionInvocation> = self
+            .do_in_transaction(|connection| {
+                invocations::table
+                    .filter(invocations::swap_id.eq(Text(*swap_id)))
+                    .order(invocations::id.asc())
+                    .select(COLUMNS)
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records.into_iter().map(ActionInvocation::from).collect())
+    }
+}
+
+fn payload_hash(payload: &serde_json::Value) -> String {
+    let mut sha = Sha256::new();
+    sha.input_str(&payload.to_string());
+    sha.result_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn previous_invocation_is_only_found_for_identical_parameters() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let parameters = json!({ "address": "bcrt1qq3ny0ynglf8ykr9e7d3wxa5xznhudkp6e3uxsc", "fee_per_wu": "10" });
+        let payload =
+            json!({ "type": "bitcoin-broadcast-signed-transaction", "payload": { "hex": "0200" } });
+
+        let (same, other, history) = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.record_action_invocation(&swap_id, ActionKind::Refund, &parameters, &payload)
+                .await?;
+
+            let same = db
+                .previous_action_invocation(&swap_id, ActionKind::Refund, &parameters)
+                .await?;
+            let other = db
+                .previous_action_invocation(&swap_id, ActionKind::Refund, &json!({}))
+                .await?;
+            let history = db.action_history(&swap_id).await?;
+
+            Ok((same, other, history))
+        })
+        .unwrap();
+
+        assert_that(&same.map(|invocation| invocation.payload))
+            .is_some()
+            .is_equal_to(&payload);
+        assert_that(&other).is_none();
+        assert_that(&history).has_length(1);
+        assert_that(&history[0].payload_hash).is_equal_to(payload_hash(&payload));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, auto_refunds},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use bitcoin::hashes::sha256d;
+use chrono::NaiveDateTime;
+
+/// Where and at which fee rate the bitcoin of a swap are refunded to once its
+/// HTLC expired without being redeemed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutoRefund {
+    pub address: bitcoin::Address,
+    pub fee_per_wu: usize,
+}
+
+#[async_trait]
+pub trait AutoRefunds: Send + Sync + 'static {
+    /// Refund the swap automatically once it expired, enabling it again
+    /// replaces the previous address and fee rate.
+    async fn enable_auto_refund(&self, swap_id: &SwapId, refund: AutoRefund) -> anyhow::Result<()>;
+
+    /// All swaps that are to be refunded automatically but whose refund was
+    /// not broadcast yet.
+    async fn pending_auto_refunds(&self) -> anyhow::Result<Vec<(SwapId, AutoRefund)>>;
+
+    async fn record_auto_refund_broadcast(
+        &self,
+        swap_id: &SwapId,
+        transaction_id: &sha256d::Hash,
+        broadcast_at: NaiveDateTime,
+    ) -> anyhow::Result<()>;
+
+    /// Stop watching the swap, e.g. because it was redeemed or refunded
+    /// manually.
+    async fn cancel_auto_refund(&self, swap_id: &SwapId) -> anyhow::Result<()>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "auto_refunds"]
+struct InsertableAutoRefund {
+    swap_id: Text<SwapId>,
+    address: Text<bitcoin::Address>,
+    fee_per_wu: Text<usize>,
+}
+
+#[async_trait]
+impl AutoRefunds for Sqlite {
+    async fn enable_auto_refund(&self, swap_id: &SwapId, refund: AutoRefund) -> anyhow::Result<()> {
+        let record = InsertableAutoRefund {
+            swap_id: Text(*swap_id),
+            address: Text(refund.address),
+            fee_per_wu: Text(refund.fee_per_wu),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::replace_into(auto_refunds::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn pending_auto_refunds(&self) -> anyhow::Result<Vec<(SwapId, AutoRefund)>> {
+        use self::schema::auto_refunds as refunds;
+
+        let records: Vec<(Text<SwapId>, Text<bitcoin::Address>, Text<usize>)> = self
+            .do_in_transaction(|connection| {
+                refunds::table
+                    .filter(refunds::broadcast_at.is_null())
+                    .select((refunds::swap_id, refunds::address, refunds::fee_per_wu))
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(swap_id, address, fee_per_wu)| {
+                (*swap_id, AutoRefund {
+                    address: address.0,
+                    fee_per_wu: *fee_per_wu,
+                })
+            })
+            .collect())
+    }
+
+    async fn record_auto_refund_broadcast(
+        &self,
+        swap_id: &SwapId,
+        transaction_id: &sha256d::Hash,
+        broadcast_at: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        use self::schema::auto_refunds as refunds;
+
+        let transaction_id = transaction_id.to_string();
+
+        self.do_in_transaction(|connection| {
+            diesel::update(refunds::table.filter(refunds::swap_id.eq(Text(*swap_id))))
+                .set((
+                    refunds::transaction_id.eq(Some(transaction_id.clone())),
+                    refunds::broadcast_at.eq(Some(broadcast_at)),
+                ))
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn cancel_auto_refund(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        use self::schema::auto_refunds as refunds;
+
+        self.do_in_transaction(|connection| {
+            diesel::delete(refunds::table.filter(refunds::swap_id.eq(Text(*swap_id))))
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn broadcast_refunds_are_no_longer_pending() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let refund = AutoRefund {
+            address: "2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9".parse().unwrap(),
+            fee_per_wu: 10,
+        };
+        let transaction_id = "b6e0ebbd2a3e5ebe57a4fcbd4c3fa87a5de0fb8d1f5e8ac1c6bd4f78b8a5ba4e"
+            .parse()
+            .unwrap();
+
+        let (pending_before, pending_after) =
+            crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                db.enable_auto_refund(&swap_id, refund.clone()).await?;
+                let pending_before = db.pending_auto_refunds().await?;
+
+                db.record_auto_refund_broadcast(
+                    &swap_id,
+                    &transaction_id,
+                    chrono::Utc::now().naive_utc(),
+                )
+                .await?;
+                let pending_after = db.pending_auto_refunds().await?;
+
+                Ok((pending_before, pending_after))
+            })
+            .unwrap();
+
+        assert_that(&pending_before).is_equal_to(vec![(swap_id, refund)]);
+        assert_that(&pending_after).is_empty();
+    }
+}
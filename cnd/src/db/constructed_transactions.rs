@@ -0,0 +1,137 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, constructed_transactions},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use bitcoin::consensus::encode::{deserialize, serialize_hex};
+use chrono::NaiveDateTime;
+
+/// The signed bitcoin transactions cnd handed out for a swap, such that the
+/// outputs they create can be spent again before they confirm, e.g. to bump
+/// the fee of a redeem transaction.
+#[async_trait]
+pub trait ConstructedTransactions: Send + Sync + 'static {
+    async fn record_constructed_transaction(
+        &self,
+        swap_id: &SwapId,
+        transaction: &bitcoin::Transaction,
+    ) -> anyhow::Result<()>;
+
+    /// Newest first.
+    async fn constructed_transactions(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<bitcoin::Transaction>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "constructed_transactions"]
+struct InsertableConstructedTransaction {
+    swap_id: Text<SwapId>,
+    txid: String,
+    hex: String,
+    constructed_at: NaiveDateTime,
+}
+
+#[async_trait]
+impl ConstructedTransactions for Sqlite {
+    async fn record_constructed_transaction(
+        &self,
+        swap_id: &SwapId,
+        transaction: &bitcoin::Transaction,
+    ) -> anyhow::Result<()> {
+        let record = InsertableConstructedTransaction {
+            swap_id: Text(*swap_id),
+            txid: transaction.txid().to_string(),
+            hex: serialize_hex(transaction),
+            constructed_at: chrono::Utc::now().naive_utc(),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(constructed_transactions::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn constructed_transactions(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<bitcoin::Transaction>> {
+        use self::schema::constructed_transactions as constructed;
+
+        let records: Vec<String> = self
+            .do_in_transaction(|connection| {
+                constructed::table
+                    .filter(constructed::swap_id.eq(Text(*swap_id)))
+                    .order(constructed::id.desc())
+                    .select(constructed::hex)
+                    .load(connection)
+            })
+            .await?;
+
+        records
+            .into_iter()
+            .map(|record| {
+                let bytes = hex::decode(record)?;
+                let transaction = deserialize(&bytes)?;
+
+                Ok(transaction)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{OutPoint, Script, TxIn, TxOut};
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    fn transaction(value: u64) -> bitcoin::Transaction {
+        bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Script::new(),
+                sequence: 0xFFFF_FFFF,
+                witness: vec![vec![1, 2, 3]],
+            }],
+            output: vec![TxOut {
+                value,
+                script_pubkey: Script::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn transactions_are_returned_newest_first_for_their_swap_only() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let other_swap_id = SwapId::default();
+
+        let constructed = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.record_constructed_transaction(&swap_id, &transaction(1))
+                .await?;
+            db.record_constructed_transaction(&other_swap_id, &transaction(2))
+                .await?;
+            db.record_constructed_transaction(&swap_id, &transaction(3))
+                .await?;
+
+            db.constructed_transactions(&swap_id).await
+        })
+        .unwrap();
+
+        assert_that(&constructed).is_equal_to(vec![transaction(3), transaction(1)]);
+    }
+}
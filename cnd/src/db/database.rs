@@ -0,0 +1,319 @@
+use crate::{
+    config,
+    db::{
+        AcceptedSwap, DetermineTypes, Enqueue, Enqueuer, Import, Importer, Lease, LoadAcceptedSwap,
+        LoadRequest, Outbox, PendingRequest, Retention, RetentionPolicy, Retrieve, Save, Saver,
+        Sqlite, Swap, SwapTypes, UnansweredRequest,
+    },
+    network::DialInformation,
+    swap_protocols::{
+        asset::Asset,
+        rfc003::{Accept, Ledger, Request},
+        SwapId,
+    },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use std::path::Path;
+
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
+
+/// What the generic impls below require of Postgres. Attributes cannot leave
+/// out a bound, hence without the `postgres` feature this requires the same of
+/// SQLite again.
+#[cfg(feature = "postgres")]
+type PostgresBackend = Postgres;
+#[cfg(not(feature = "postgres"))]
+type PostgresBackend = Sqlite;
+
+/// The backend swaps are stored in.
+#[derive(Clone, Debug)]
+pub enum Database {
+    Sqlite(Sqlite),
+    /// Only if cnd was built with the `postgres` feature.
+    #[cfg(feature = "postgres")]
+    Postgres(Postgres),
+}
+
+impl Database {
+    /// Return a handle to the configured backend, `sqlite` is the SQLite
+    /// database in the data directory.
+    pub fn new(config: Option<&config::Database>, sqlite: &Sqlite) -> anyhow::Result<Self> {
+        match config {
+            None | Some(config::Database::Sqlite) => Ok(Database::Sqlite(sqlite.clone())),
+            #[cfg(feature = "postgres")]
+            Some(config::Database::Postgres { url }) => Ok(Database::Postgres(Postgres::new(url)?)),
+            #[cfg(not(feature = "postgres"))]
+            Some(config::Database::Postgres { .. }) => Err(postgres_not_built()),
+        }
+    }
+
+    /// Return a read-only handle to the configured backend.
+    pub fn new_read_only(
+        config: Option<&config::Database>,
+        data_dir: &Path,
+    ) -> anyhow::Result<Self> {
+        match config {
+            None | Some(config::Database::Sqlite) => {
+                Ok(Database::Sqlite(Sqlite::new_read_only_in_dir(data_dir)?))
+            }
+            #[cfg(feature = "postgres")]
+            Some(config::Database::Postgres { url }) => {
+                Ok(Database::Postgres(Postgres::new_read_only(url)?))
+            }
+            #[cfg(not(feature = "postgres"))]
+            Some(config::Database::Postgres { .. }) => Err(postgres_not_built()),
+        }
+    }
+
+    /// Return a consistent copy of the database file, Postgres databases have
+    /// to be backed up with the tools of Postgres.
+    pub async fn snapshot(&self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Database::Sqlite(sqlite) => sqlite.snapshot().await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(_) => {
+                Err(anyhow::Error::from(crate::db::Error::SnapshotUnsupported))
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
+fn postgres_not_built() -> anyhow::Error {
+    anyhow::anyhow!(
+        "cnd was built without the postgres feature, store swaps in SQLite by leaving out the \
+         [database] section"
+    )
+}
+
+impl Saver for Database {}
+
+impl Enqueuer for Database {}
+
+#[async_trait]
+impl<T> Save<T> for Database
+where
+    T: Send + 'static,
+    Sqlite: Save<T>,
+    PostgresBackend: Save<T>,
+{
+    async fn save(&self, swap: T) -> anyhow::Result<()> {
+        match self {
+            Database::Sqlite(db) => db.save(swap).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.save(swap).await,
+        }
+    }
+}
+
+#[async_trait]
+impl Retrieve for Database {
+    async fn get(&self, key: &SwapId) -> anyhow::Result<Swap> {
+        match self {
+            Database::Sqlite(db) => db.get(key).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.get(key).await,
+        }
+    }
+
+    async fn all(&self) -> anyhow::Result<Vec<Swap>> {
+        match self {
+            Database::Sqlite(db) => db.all().await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.all().await,
+        }
+    }
+}
+
+#[async_trait]
+impl<AL, BL, AA, BA> LoadAcceptedSwap<AL, BL, AA, BA> for Database
+where
+    AL: Ledger + Send + 'static,
+    BL: Ledger + Send + 'static,
+    AA: Asset + Send + 'static,
+    BA: Asset + Send + 'static,
+    Sqlite: LoadAcceptedSwap<AL, BL, AA, BA>,
+    PostgresBackend: LoadAcceptedSwap<AL, BL, AA, BA>,
+{
+    async fn load_accepted_swap(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<AcceptedSwap<AL, BL, AA, BA>> {
+        match self {
+            Database::Sqlite(db) => db.load_accepted_swap(swap_id).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.load_accepted_swap(swap_id).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<AL, BL, AA, BA> LoadRequest<AL, BL, AA, BA> for Database
+where
+    AL: Ledger + Send + 'static,
+    BL: Ledger + Send + 'static,
+    AA: Asset + Send + 'static,
+    BA: Asset + Send + 'static,
+    Sqlite: LoadRequest<AL, BL, AA, BA>,
+    PostgresBackend: LoadRequest<AL, BL, AA, BA>,
+{
+    async fn load_request(&self, swap_id: &SwapId) -> anyhow::Result<Request<AL, BL, AA, BA>> {
+        match self {
+            Database::Sqlite(db) => db.load_request(swap_id).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.load_request(swap_id).await,
+        }
+    }
+}
+
+#[async_trait]
+impl DetermineTypes for Database {
+    async fn determine_types(&self, key: &SwapId) -> anyhow::Result<SwapTypes> {
+        match self {
+            Database::Sqlite(db) => db.determine_types(key).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.determine_types(key).await,
+        }
+    }
+}
+
+/// Lives with the swaps because `Retrieve` leaves out archived ones.
+#[async_trait]
+impl Retention for Database {
+    async fn mark_finished(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        match self {
+            Database::Sqlite(db) => db.mark_finished(swap_id).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.mark_finished(swap_id).await,
+        }
+    }
+
+    async fn finished_at(&self, swap_id: &SwapId) -> anyhow::Result<Option<NaiveDateTime>> {
+        match self {
+            Database::Sqlite(db) => db.finished_at(swap_id).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.finished_at(swap_id).await,
+        }
+    }
+
+    async fn archive_finished_swaps(
+        &self,
+        policy: RetentionPolicy,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Vec<SwapId>> {
+        match self {
+            Database::Sqlite(db) => db.archive_finished_swaps(policy, now).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.archive_finished_swaps(policy, now).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Enqueue<T> for Database
+where
+    T: Send + 'static,
+    Sqlite: Enqueue<T>,
+    PostgresBackend: Enqueue<T>,
+{
+    async fn save_and_enqueue(
+        &self,
+        swap: Swap,
+        request: T,
+        peer: DialInformation,
+    ) -> anyhow::Result<()> {
+        match self {
+            Database::Sqlite(db) => db.save_and_enqueue(swap, request, peer).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.save_and_enqueue(swap, request, peer).await,
+        }
+    }
+}
+
+impl Importer for Database {}
+
+#[async_trait]
+impl<AL, BL, AA, BA> Import<AL, BL, AA, BA> for Database
+where
+    AL: Ledger + Send + 'static,
+    BL: Ledger + Send + 'static,
+    AA: Asset + Send + 'static,
+    BA: Asset + Send + 'static,
+    Sqlite: Import<AL, BL, AA, BA>,
+    PostgresBackend: Import<AL, BL, AA, BA>,
+{
+    async fn import(
+        &self,
+        swap: Swap,
+        request: Request<AL, BL, AA, BA>,
+        accept: Accept<AL, BL>,
+        accepted_at: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        match self {
+            Database::Sqlite(db) => db.import(swap, request, accept, accepted_at).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.import(swap, request, accept, accepted_at).await,
+        }
+    }
+}
+
+#[async_trait]
+impl Outbox for Database {
+    async fn pending_requests(&self) -> anyhow::Result<Vec<PendingRequest>> {
+        match self {
+            Database::Sqlite(db) => db.pending_requests().await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.pending_requests().await,
+        }
+    }
+
+    async fn mark_sent(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        match self {
+            Database::Sqlite(db) => db.mark_sent(swap_id).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.mark_sent(swap_id).await,
+        }
+    }
+
+    async fn record_attempt(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        match self {
+            Database::Sqlite(db) => db.record_attempt(swap_id).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.record_attempt(swap_id).await,
+        }
+    }
+
+    async fn unanswered_requests(&self) -> anyhow::Result<Vec<UnansweredRequest>> {
+        match self {
+            Database::Sqlite(db) => db.unanswered_requests().await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.unanswered_requests().await,
+        }
+    }
+}
+
+#[async_trait]
+impl Lease for Database {
+    async fn acquire_lease(
+        &self,
+        holder: &str,
+        now: NaiveDateTime,
+        expires_at: NaiveDateTime,
+    ) -> anyhow::Result<bool> {
+        match self {
+            Database::Sqlite(db) => db.acquire_lease(holder, now, expires_at).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.acquire_lease(holder, now, expires_at).await,
+        }
+    }
+
+    async fn lease_expires_at(&self) -> anyhow::Result<NaiveDateTime> {
+        match self {
+            Database::Sqlite(db) => db.lease_expires_at().await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(db) => db.lease_expires_at().await,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
+use crate::{
+    db::{save::Insert, Sqlite, Swap},
+    diesel::sqlite::SqliteConnection,
+    ethereum::{Erc20Token, EtherQuantity},
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{Accept, Ledger, Request},
+    },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+#[cfg(feature = "postgres")]
+use diesel::pg::PgConnection;
+
+/// Save a swap that was accepted on another node, such that it can be
+/// resumed on this one.
+///
+/// Nothing can have happened to the HTLCs before the swap was accepted, hence
+/// `accepted_at` is kept as it was to know from when on to scan the ledgers.
+#[async_trait]
+pub trait Import<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>: Send + Sync + 'static {
+    async fn import(
+        &self,
+        swap: Swap,
+        request: Request<AL, BL, AA, BA>,
+        accept: Accept<AL, BL>,
+        accepted_at: NaiveDateTime,
+    ) -> anyhow::Result<()>;
+}
+
+pub trait Importer:
+    Import<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+    + Import<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+    + Import<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+    + Import<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>
+{
+}
+
+macro_rules! impl_import {
+    ($database:ident, $connection:ty) => {
+        impl Importer for $database {}
+
+        #[async_trait]
+        impl<AL, BL, AA, BA> Import<AL, BL, AA, BA> for $database
+        where
+            AL: Ledger,
+            BL: Ledger,
+            AA: Asset,
+            BA: Asset,
+            $connection: Insert<Request<AL, BL, AA, BA>> + Insert<(Accept<AL, BL>, NaiveDateTime)>,
+        {
+            async fn import(
+                &self,
+                swap: Swap,
+                request: Request<AL, BL, AA, BA>,
+                accept: Accept<AL, BL>,
+                accepted_at: NaiveDateTime,
+            ) -> anyhow::Result<()> {
+                let accept = (accept, accepted_at);
+
+                self.do_in_transaction(|connection| {
+                    connection.insert(&swap)?;
+                    connection.insert(&request)?;
+                    connection.insert(&accept)
+                })
+                .await?;
+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_import!(Sqlite, SqliteConnection);
+#[cfg(feature = "postgres")]
+impl_import!(Postgres, PgConnection);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::LoadAcceptedSwap, quickcheck::Quickcheck};
+    use std::path::Path;
+
+    #[test]
+    fn imported_swap_is_loaded_with_its_original_accept_time() {
+        fn prop(
+            swap: Quickcheck<Swap>,
+            request: Quickcheck<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>,
+            accept: Quickcheck<Accept<Bitcoin, Ethereum>>,
+        ) -> anyhow::Result<bool> {
+            let swap_id = swap.swap_id;
+            let request = Request {
+                swap_id,
+                ..*request
+            };
+            let accept = Accept { swap_id, ..*accept };
+            let accepted_at = chrono::NaiveDate::from_ymd(2020, 1, 1).and_hms(12, 0, 0);
+
+            let db = Sqlite::new(&Path::new(":memory:"))?;
+
+            let (loaded_request, loaded_accept, loaded_accepted_at) =
+                crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                    db.import(swap.0.clone(), request.clone(), accept, accepted_at)
+                        .await?;
+
+                    db.load_accepted_swap(&swap_id).await
+                })?;
+
+            Ok(request == loaded_request
+                && accept == loaded_accept
+                && accepted_at == loaded_accepted_at)
+        }
+
+        quickcheck::quickcheck(
+            prop as fn(
+                Quickcheck<Swap>,
+                Quickcheck<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>,
+                Quickcheck<Accept<Bitcoin, Ethereum>>,
+            ) -> anyhow::Result<bool>,
+        );
+    }
+}
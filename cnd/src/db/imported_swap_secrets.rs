@@ -0,0 +1,252 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, imported_swap_secrets},
+        Sqlite,
+    },
+    derivation::Identities,
+    diesel::{QueryDsl, RunQueryDsl},
+    seed::{Seed, SEED_LENGTH},
+    swap_protocols::{rfc003::SwapSecrets, SwapId},
+};
+use async_trait::async_trait;
+use bitcoin::secp256k1::{constants::SECRET_KEY_SIZE, SecretKey};
+use crypto::{
+    aead::{AeadDecryptor, AeadEncryptor},
+    chacha20poly1305::ChaCha20Poly1305,
+};
+use rand::{rngs::OsRng, Rng};
+use std::convert::TryFrom;
+
+const NONCE_LENGTH: usize = 8;
+const TAG_LENGTH: usize = 16;
+
+/// The secrets of swaps that were imported from a node with another seed,
+/// they cannot be derived from the seed of this node.
+///
+/// They are stored sealed with a key derived from `node_seed`, the seed of
+/// this node, see `seal`.
+#[async_trait]
+pub trait ImportedSwapSecrets: Send + Sync + 'static {
+    async fn save_imported_swap_secrets(
+        &self,
+        swap_id: &SwapId,
+        secrets: SwapSecrets,
+        node_seed: &Seed,
+    ) -> anyhow::Result<()>;
+
+    async fn imported_swap_secrets(
+        &self,
+        node_seed: &Seed,
+    ) -> anyhow::Result<Vec<(SwapId, SwapSecrets)>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "imported_swap_secrets"]
+struct InsertableImportedSwapSecrets {
+    swap_id: Text<SwapId>,
+    sealed_secrets: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableImportedSwapSecrets {
+    swap_id: Text<SwapId>,
+    sealed_secrets: String,
+}
+
+#[async_trait]
+impl ImportedSwapSecrets for Sqlite {
+    async fn save_imported_swap_secrets(
+        &self,
+        swap_id: &SwapId,
+        secrets: SwapSecrets,
+        node_seed: &Seed,
+    ) -> anyhow::Result<()> {
+        let record = InsertableImportedSwapSecrets {
+            swap_id: Text(*swap_id),
+            sealed_secrets: hex::encode(seal(node_seed, swap_id, secrets, OsRng)?),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(imported_swap_secrets::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn imported_swap_secrets(
+        &self,
+        node_seed: &Seed,
+    ) -> anyhow::Result<Vec<(SwapId, SwapSecrets)>> {
+        use self::schema::imported_swap_secrets as imported;
+
+        let records: Vec<QueryableImportedSwapSecrets> = self
+            .do_in_transaction(|connection| {
+                imported::table
+                    .select((imported::swap_id, imported::sealed_secrets))
+                    .load(connection)
+            })
+            .await?;
+
+        records
+            .into_iter()
+            .map(|record| {
+                let swap_id = *record.swap_id;
+                let secrets = open(node_seed, &swap_id, &hex::decode(record.sealed_secrets)?)?;
+
+                Ok((swap_id, secrets))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    }
+}
+
+/// Encrypt the secrets of an imported swap with a key derived from the seed of
+/// this node and the swap id.
+///
+/// The database can be read in places the seed file is not, e.g. on the
+/// PostgreSQL server, in its backups or by a standby. Whoever reads the
+/// secrets of an imported swap can spend its HTLCs, the secrets of all other
+/// swaps are derived from the seed and are not stored at all. Sealing them
+/// keeps the database as sensitive as it is without imported swaps. It does
+/// not protect against anyone who also has the seed, e.g. from the data
+/// directory of the node or from a backup together with its passphrase. The
+/// key is bound to the swap id, such that sealed secrets cannot be moved to
+/// another swap.
+///
+/// Layout: nonce || ciphertext || tag, where the plaintext is the seed of the
+/// swap followed by its redeem and refund key if it has them.
+fn seal<R: Rng>(
+    node_seed: &Seed,
+    swap_id: &SwapId,
+    secrets: SwapSecrets,
+    mut rng: R,
+) -> anyhow::Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LENGTH];
+    rng.try_fill(&mut nonce[..])?;
+
+    let mut plaintext = secrets.seed().bytes().to_vec();
+    if let Some(identities) = secrets.identities() {
+        plaintext.extend_from_slice(&identities.redeem[..]);
+        plaintext.extend_from_slice(&identities.refund[..]);
+    }
+
+    let mut ciphertext = vec![0u8; plaintext.len()];
+    let mut tag = [0u8; TAG_LENGTH];
+    ChaCha20Poly1305::new(&sealing_key(node_seed, swap_id), &nonce, &[]).encrypt(
+        &plaintext,
+        &mut ciphertext,
+        &mut tag,
+    );
+
+    let mut sealed = Vec::with_capacity(NONCE_LENGTH + ciphertext.len() + TAG_LENGTH);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    sealed.extend_from_slice(&tag);
+
+    Ok(sealed)
+}
+
+fn open(node_seed: &Seed, swap_id: &SwapId, sealed: &[u8]) -> anyhow::Result<SwapSecrets> {
+    if sealed.len() < NONCE_LENGTH + TAG_LENGTH {
+        anyhow::bail!("sealed secrets of swap {} are truncated", swap_id);
+    }
+
+    let (nonce, rest) = sealed.split_at(NONCE_LENGTH);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+
+    let mut plaintext = vec![0u8; ciphertext.len()];
+    if !ChaCha20Poly1305::new(&sealing_key(node_seed, swap_id), nonce, &[]).decrypt(
+        ciphertext,
+        &mut plaintext,
+        tag,
+    ) {
+        anyhow::bail!(
+            "failed to open the secrets of swap {}, they were sealed with another seed",
+            swap_id
+        );
+    }
+
+    let (seed, keys) = plaintext.split_at(SEED_LENGTH);
+    let seed = Seed::from(<[u8; SEED_LENGTH]>::try_from(seed)?);
+    let identities = match keys.len() {
+        0 => None,
+        length if length == 2 * SECRET_KEY_SIZE => {
+            let (redeem, refund) = keys.split_at(SECRET_KEY_SIZE);
+
+            Some(Identities {
+                redeem: SecretKey::from_slice(redeem)?,
+                refund: SecretKey::from_slice(refund)?,
+            })
+        }
+        length => anyhow::bail!(
+            "sealed secrets of swap {} hold {} bytes of keys",
+            swap_id,
+            length
+        ),
+    };
+
+    Ok(SwapSecrets::new(seed, identities))
+}
+
+fn sealing_key(node_seed: &Seed, swap_id: &SwapId) -> [u8; SEED_LENGTH] {
+    node_seed.sha256_with_seed(&[b"IMPORTED_SWAP_SECRETS", swap_id.0.as_bytes()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swap_protocols::rfc003::SecretSource;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    fn node_seed() -> Seed {
+        Seed::from(*b"hello world, you are beautiful!!")
+    }
+
+    #[test]
+    fn imported_secrets_yield_the_same_keys() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let identities = Identities {
+            redeem: SecretKey::from_slice(&[1u8; 32]).unwrap(),
+            refund: SecretKey::from_slice(&[2u8; 32]).unwrap(),
+        };
+        let secrets = SwapSecrets::new(node_seed().swap_seed(swap_id), Some(identities));
+
+        let imported = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.save_imported_swap_secrets(&swap_id, secrets, &node_seed())
+                .await?;
+
+            db.imported_swap_secrets(&node_seed()).await
+        })
+        .unwrap();
+
+        assert_that(&imported).has_length(1);
+        let (imported_swap_id, imported_secrets) = imported[0];
+        assert_that(&imported_swap_id).is_equal_to(swap_id);
+        assert_that(&imported_secrets.secret()).is_equal_to(secrets.secret());
+        assert_that(&imported_secrets.secp256k1_redeem()).is_equal_to(identities.redeem);
+        assert_that(&imported_secrets.secp256k1_refund()).is_equal_to(identities.refund);
+    }
+
+    #[test]
+    fn sealed_secrets_only_open_with_the_node_seed_for_their_swap() {
+        let swap_id = SwapId::default();
+        let seed = node_seed().swap_seed(swap_id);
+        let sealed = seal(&node_seed(), &swap_id, SwapSecrets::new(seed, None), OsRng).unwrap();
+
+        assert!(!sealed
+            .windows(SEED_LENGTH)
+            .any(|window| window == &seed.bytes()[..]));
+        assert_that(&open(&node_seed(), &swap_id, &sealed).map(|secrets| secrets.seed()))
+            .is_ok()
+            .is_equal_to(seed);
+
+        let other_seed = Seed::from([1u8; SEED_LENGTH]);
+        assert_that(&open(&other_seed, &swap_id, &sealed)).is_err();
+        assert_that(&open(&node_seed(), &SwapId::default(), &sealed)).is_err();
+    }
+}
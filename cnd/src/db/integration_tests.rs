@@ -48,7 +48,7 @@ macro_rules! db_roundtrip_test {
                     };
 
                     let (loaded_swap, loaded_request, loaded_accept, loaded_swap_types) =
-                    async_std::task::block_on::<_, Result<_, anyhow::Error>>(async {
+                    crate::executor::block_on::<_, Result<_, anyhow::Error>>(async {
                         db.save(saved_swap.clone()).await?;
                         db.save(saved_request.clone()).await?;
                         db.save(saved_accept.clone()).await?;
@@ -0,0 +1,135 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, issued_identities},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+
+/// The identities cnd derived for swaps, as they appear in the swap resource,
+/// such that an identity that is derived again, e.g. because the seed was
+/// restored on another node, is noticed.
+#[async_trait]
+pub trait IssuedIdentities: Send + Sync + 'static {
+    async fn record_issued_identities(
+        &self,
+        swap_id: &SwapId,
+        identities: &[String],
+    ) -> anyhow::Result<()>;
+
+    /// The swaps `identity` was issued for.
+    async fn swaps_issued(&self, identity: &str) -> anyhow::Result<Vec<SwapId>>;
+
+    /// The identities of the swap that were issued for other swaps as well.
+    async fn reused_identities(&self, swap_id: &SwapId) -> anyhow::Result<Vec<String>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "issued_identities"]
+struct InsertableIssuedIdentity {
+    swap_id: Text<SwapId>,
+    identity: String,
+}
+
+#[async_trait]
+impl IssuedIdentities for Sqlite {
+    async fn record_issued_identities(
+        &self,
+        swap_id: &SwapId,
+        identities: &[String],
+    ) -> anyhow::Result<()> {
+        let records = identities
+            .iter()
+            .map(|identity| InsertableIssuedIdentity {
+                swap_id: Text(*swap_id),
+                identity: identity.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(issued_identities::table)
+                .values(&records)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn swaps_issued(&self, identity: &str) -> anyhow::Result<Vec<SwapId>> {
+        use self::schema::issued_identities as issued;
+
+        let records: Vec<Text<SwapId>> = self
+            .do_in_transaction(|connection| {
+                issued::table
+                    .filter(issued::identity.eq(identity))
+                    .select(issued::swap_id)
+                    .distinct()
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records.into_iter().map(|Text(swap_id)| swap_id).collect())
+    }
+
+    async fn reused_identities(&self, swap_id: &SwapId) -> anyhow::Result<Vec<String>> {
+        use self::schema::issued_identities as issued;
+
+        let records: Vec<String> = self
+            .do_in_transaction(|connection| {
+                let identities: Vec<String> = issued::table
+                    .filter(issued::swap_id.eq(Text(*swap_id)))
+                    .select(issued::identity)
+                    .load(connection)?;
+
+                issued::table
+                    .filter(issued::identity.eq_any(&identities))
+                    .filter(issued::swap_id.ne(Text(*swap_id)))
+                    .select(issued::identity)
+                    .distinct()
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn identities_issued_for_several_swaps_are_reported_as_reused() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let (first, second) = (SwapId::default(), SwapId::default());
+        let reused =
+            String::from("02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275");
+        let fresh = String::from("0x00a329c0648769a73afac7f9381e08fb43dbea72");
+
+        let (swaps, reused_by_second, reused_by_other) =
+            crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                db.record_issued_identities(&first, &[reused.clone()])
+                    .await?;
+                db.record_issued_identities(&second, &[reused.clone(), fresh.clone()])
+                    .await?;
+
+                let swaps = db.swaps_issued(&reused).await?;
+                let reused_by_second = db.reused_identities(&second).await?;
+                let reused_by_other = db.reused_identities(&SwapId::default()).await?;
+
+                Ok((swaps, reused_by_second, reused_by_other))
+            })
+            .unwrap();
+
+        assert_that(&swaps).has_length(2);
+        assert_that(&swaps).contains(first);
+        assert_that(&reused_by_second).is_equal_to(vec![reused]);
+        assert_that(&reused_by_other).is_empty();
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
+use crate::{
+    db::{schema, Sqlite},
+    diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl},
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+/// Which node may execute the swaps stored in the database.
+///
+/// Two nodes with the same seed, e.g. a standby that was promoted while the
+/// node it stands in for is still running, would otherwise both act on the
+/// same swaps with the same PeerId.
+#[async_trait]
+pub trait Lease: Send + Sync + 'static {
+    /// Give the lease to `holder` until `expires_at` if it expired before
+    /// `now` or `holder` has it already.
+    ///
+    /// Returns whether `holder` has the lease now.
+    async fn acquire_lease(
+        &self,
+        holder: &str,
+        now: NaiveDateTime,
+        expires_at: NaiveDateTime,
+    ) -> anyhow::Result<bool>;
+
+    /// Until when the lease was last given to a node.
+    async fn lease_expires_at(&self) -> anyhow::Result<NaiveDateTime>;
+}
+
+macro_rules! impl_lease {
+    ($database:ident) => {
+        #[async_trait]
+        impl Lease for $database {
+            async fn acquire_lease(
+                &self,
+                holder: &str,
+                now: NaiveDateTime,
+                expires_at: NaiveDateTime,
+            ) -> anyhow::Result<bool> {
+                use self::schema::node_lease as lease;
+
+                // A single conditional update, such that two nodes racing for an
+                // expired lease cannot both get it.
+                let updated = self
+                    .do_in_transaction(|connection| {
+                        diesel::update(
+                            lease::table
+                                .filter(lease::holder.eq(holder).or(lease::expires_at.le(now))),
+                        )
+                        .set((lease::holder.eq(holder), lease::expires_at.eq(expires_at)))
+                        .execute(connection)
+                    })
+                    .await?;
+
+                Ok(updated > 0)
+            }
+
+            async fn lease_expires_at(&self) -> anyhow::Result<NaiveDateTime> {
+                use self::schema::node_lease as lease;
+
+                let expires_at = self
+                    .do_in_transaction(|connection| {
+                        lease::table.select(lease::expires_at).first(connection)
+                    })
+                    .await?;
+
+                Ok(expires_at)
+            }
+        }
+    };
+}
+
+impl_lease!(Sqlite);
+#[cfg(feature = "postgres")]
+impl_lease!(Postgres);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn lease_is_only_given_to_another_holder_once_it_expired() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let (active, standby) = ("active", "standby");
+        let now = chrono::Utc::now().naive_utc();
+        let lease_duration = Duration::seconds(30);
+
+        crate::executor::block_on(async {
+            let acquired = db.acquire_lease(active, now, now + lease_duration).await;
+            assert_that(&acquired).is_ok().is_true();
+
+            let later = now + Duration::seconds(10);
+            let taken_over = db
+                .acquire_lease(standby, later, later + lease_duration)
+                .await;
+            assert_that(&taken_over).is_ok().is_false();
+            let renewed = db
+                .acquire_lease(active, later, later + lease_duration)
+                .await;
+            assert_that(&renewed).is_ok().is_true();
+            let expires_at = db.lease_expires_at().await;
+            assert_that(&expires_at)
+                .is_ok()
+                .is_equal_to(later + lease_duration);
+
+            let expired = later + lease_duration;
+            let taken_over = db
+                .acquire_lease(standby, expired, expired + lease_duration)
+                .await;
+            assert_that(&taken_over).is_ok().is_true();
+            let renewed = db
+                .acquire_lease(active, expired, expired + lease_duration)
+                .await;
+            assert_that(&renewed).is_ok().is_false();
+        })
+    }
+}
@@ -1,35 +1,73 @@
+mod action_history;
+mod auto_refunds;
+mod constructed_transactions;
 mod custom_sql_types;
+mod database;
+mod import;
+mod imported_swap_secrets;
 #[cfg(test)]
 mod integration_tests;
+mod issued_identities;
+mod lease;
 mod load_swaps;
 mod new_types;
+mod outbox;
+mod payout_account_usages;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod redeem_destinations;
+mod requested_rates;
+mod retention;
 mod save;
 mod schema;
 #[cfg(test)]
 mod serialization_format_stability_tests;
+mod stats;
 mod swap;
+mod swap_failures;
+mod swap_fees;
+mod swap_messages;
+mod swap_metadata;
 mod swap_types;
 #[macro_use]
 pub mod with_swap_types;
 embed_migrations!("./migrations");
 
 pub use self::{
-    load_swaps::{AcceptedSwap, LoadAcceptedSwap},
+    action_history::{ActionHistory, ActionInvocation},
+    auto_refunds::{AutoRefund, AutoRefunds},
+    constructed_transactions::ConstructedTransactions,
+    database::Database,
+    import::{Import, Importer},
+    imported_swap_secrets::ImportedSwapSecrets,
+    issued_identities::IssuedIdentities,
+    lease::Lease,
+    load_swaps::{AcceptedSwap, LoadAcceptedSwap, LoadRequest},
+    outbox::{Enqueue, Enqueuer, Outbox, PendingRequest, UnansweredRequest},
+    payout_account_usages::{PayoutAccountUsage, PayoutAccountUsages},
+    redeem_destinations::{GapLimitReached, RedeemDestinations},
+    requested_rates::RequestedRates,
+    retention::{Retention, RetentionPolicy},
     save::*,
+    stats::{Stats, SwapStats, Volume},
     swap::*,
+    swap_failures::{SwapFailure, SwapFailures},
+    swap_fees::{PaidFee, RecordedFee, SwapFees},
+    swap_messages::{Direction, SwapMessage, SwapMessages, MAX_RECORDED_FRAME_SIZE},
+    swap_metadata::{MetadataStore, SwapMetadata},
     swap_types::*,
 };
 
-use crate::{
-    db::custom_sql_types::Text,
-    swap_protocols::{Role, SwapId},
-};
-use diesel::{self, prelude::*, sqlite::SqliteConnection};
+#[cfg(feature = "postgres")]
+pub use self::postgres::Postgres;
+
+use diesel::{self, connection::SimpleConnection, prelude::*, sql_types, sqlite::SqliteConnection};
 use std::{
     ffi::OsStr,
     path::{Path, PathBuf},
     sync::Arc,
 };
+use uuid::Uuid;
 
 /// This module provides persistent storage by way of Sqlite.
 
@@ -37,7 +75,8 @@ use std::{
 #[derivative(Debug)]
 pub struct Sqlite {
     #[derivative(Debug = "ignore")]
-    connection: Arc<async_std::sync::Mutex<SqliteConnection>>,
+    connection: Arc<futures_core::lock::Mutex<SqliteConnection>>,
+    path: PathBuf,
 }
 
 impl Sqlite {
@@ -66,10 +105,77 @@ impl Sqlite {
         log::info!("SQLite database file: {}", file.display());
 
         Ok(Sqlite {
-            connection: Arc::new(async_std::sync::Mutex::new(connection)),
+            connection: Arc::new(futures_core::lock::Mutex::new(connection)),
+            path: file.to_path_buf(),
+        })
+    }
+
+    /// Return a read-only handle to the database file in 'dir'.
+    ///
+    /// Neither creates the file nor runs the migrations, the database is
+    /// expected to be owned by another node.
+    pub fn new_read_only_in_dir<D: AsRef<OsStr>>(dir: D) -> anyhow::Result<Self> {
+        let path = db_path_from_dir(Path::new(&dir));
+
+        let connection = SqliteConnection::establish(&format!("file:{}?mode=ro", path.display()))?;
+
+        log::info!("SQLite database file (read-only): {}", path.display());
+
+        Ok(Sqlite {
+            connection: Arc::new(futures_core::lock::Mutex::new(connection)),
+            path,
         })
     }
 
+    /// Return a consistent copy of the database file.
+    ///
+    /// The bundled SQLite predates `VACUUM INTO` and its backup API cannot be
+    /// used without unsafe code, hence the rows of all tables are copied in a
+    /// single transaction into a database that was migrated to the same
+    /// schema.
+    pub async fn snapshot(&self) -> anyhow::Result<Vec<u8>> {
+        // Every snapshot gets a file of its own, such that concurrent backups
+        // do not write into each other's.
+        let path = self
+            .path
+            .with_extension(format!("snapshot-{}", Uuid::new_v4()));
+
+        let copied = self.copy_into(&path).await;
+
+        crate::executor::spawn_blocking(move || {
+            let snapshot = copied.and_then(|()| Ok(std::fs::read(&path)?));
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+
+            snapshot
+        })
+        .await
+    }
+
+    async fn copy_into(&self, path: &Path) -> anyhow::Result<()> {
+        let url = format!("file:{}", path.display());
+        crate::executor::spawn_blocking(move || -> anyhow::Result<()> {
+            let snapshot = SqliteConnection::establish(&url)?;
+            embedded_migrations::run(&snapshot)?;
+
+            Ok(())
+        })
+        .await?;
+
+        let guard = self.connection.lock().await;
+        let connection = &*guard;
+
+        // Databases cannot be attached within a transaction.
+        diesel::sql_query("ATTACH DATABASE ? AS snapshot")
+            .bind::<sql_types::Text, _>(path.display().to_string())
+            .execute(connection)?;
+        let copied = connection.transaction(|| copy_tables(connection));
+        connection.batch_execute("DETACH DATABASE snapshot")?;
+
+        Ok(copied?)
+    }
+
     async fn do_in_transaction<F, T, E>(&self, f: F) -> Result<T, E>
     where
         F: Fn(&SqliteConnection) -> Result<T, E>,
@@ -82,29 +188,40 @@ impl Sqlite {
 
         Ok(result)
     }
+}
 
-    async fn role(&self, key: &SwapId) -> anyhow::Result<Role> {
-        use self::schema::rfc003_swaps as swaps;
-
-        let record: QueryableSwapRole = self
-            .do_in_transaction(|connection| {
-                let key = Text(key);
-
-                swaps::table
-                    .filter(swaps::swap_id.eq(key))
-                    .select((swaps::swap_id, swaps::role))
-                    .first(connection)
-                    .optional()
-            })
-            .await?
-            .ok_or(Error::SwapNotFound)?;
+#[derive(QueryableByName, Debug)]
+struct Table {
+    #[sql_type = "sql_types::Text"]
+    name: String,
+}
 
-        Ok(*record.role)
+/// Replace the rows of the tables of the attached snapshot with the ones of
+/// the main database, including the migrations that were run.
+///
+/// `sqlite_sequence` is copied last, after inserting the rows moved the
+/// AUTOINCREMENT counters of the snapshot, such that they continue where the
+/// ones of the main database are.
+fn copy_tables(connection: &SqliteConnection) -> Result<(), diesel::result::Error> {
+    let tables = diesel::sql_query(
+        "SELECT name FROM main.sqlite_master \
+         WHERE type = 'table' AND (name NOT LIKE 'sqlite_%' OR name = 'sqlite_sequence') \
+         ORDER BY name = 'sqlite_sequence'",
+    )
+    .load::<Table>(connection)?;
+
+    for Table { name } in tables {
+        connection.batch_execute(&format!(
+            "DELETE FROM snapshot.\"{0}\"; INSERT INTO snapshot.\"{0}\" SELECT * FROM main.\"{0}\";",
+            name
+        ))?;
     }
+
+    Ok(())
 }
 
 // Construct an absolute path to the database file using 'dir' as the base.
-fn db_path_from_dir(dir: &Path) -> PathBuf {
+pub(crate) fn db_path_from_dir(dir: &Path) -> PathBuf {
     let path = dir.to_path_buf();
     path.join("cnd.sqlite")
 }
@@ -117,21 +234,19 @@ fn ensure_folder_tree_exists(path: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
-#[derive(Queryable, Debug, Clone, PartialEq)]
-struct QueryableSwapRole {
-    pub swap_id: Text<SwapId>,
-    pub role: Text<Role>,
-}
-
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("swap not found")]
     SwapNotFound,
+    #[error("snapshots can only be taken of SQLite databases")]
+    SnapshotUnsupported,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::swap_protocols::{Role, SwapId};
+    use libp2p::PeerId;
     use spectral::prelude::*;
     use std::path::PathBuf;
 
@@ -186,4 +301,30 @@ mod tests {
         assert_that(&db).is_ok();
         assert_that(&path).exists();
     }
+
+    #[test]
+    fn snapshot_can_be_opened_as_the_database_it_was_taken_of() {
+        let db = Sqlite::new(&temp_db()).unwrap();
+        let swap = Swap::new(SwapId::default(), Role::Alice, PeerId::random());
+
+        let snapshot = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            Save::save(&db, swap.clone()).await?;
+
+            db.snapshot().await
+        })
+        .unwrap();
+        let path = temp_db();
+        std::fs::write(&path, snapshot).unwrap();
+        let restored = Sqlite::new(&path).unwrap();
+
+        assert_that(&crate::executor::block_on(restored.get(&swap.swap_id)).unwrap())
+            .is_equal_to(swap);
+        let snapshot_prefix = db.path.with_extension("snapshot-").display().to_string();
+        let leftovers = std::fs::read_dir(db.path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path().display().to_string())
+            .filter(|path| path.starts_with(&snapshot_prefix))
+            .count();
+        assert_that(&leftovers).is_equal_to(0);
+    }
 }
@@ -0,0 +1,338 @@
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        save::Insert,
+        schema::{self, rfc003_outbox},
+        Sqlite, Swap,
+    },
+    diesel::{sqlite::SqliteConnection, ExpressionMethods, QueryDsl, RunQueryDsl},
+    ethereum::{Erc20Token, EtherQuantity},
+    network::DialInformation,
+    swap_protocols::{
+        ledger::{Bitcoin, Ethereum},
+        rfc003::Request,
+        SwapId,
+    },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+#[cfg(feature = "postgres")]
+use diesel::pg::PgConnection;
+use libp2p::{Multiaddr, PeerId};
+
+/// A swap request that was saved but not answered by the peer yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingRequest {
+    pub swap_id: SwapId,
+    pub peer: DialInformation,
+}
+
+/// A pending request together with how long it has been waiting for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnansweredRequest {
+    pub swap_id: SwapId,
+    pub peer: DialInformation,
+    pub enqueued_at: NaiveDateTime,
+    /// How often the request was sent, including attempts that did not reach
+    /// the peer.
+    pub attempts: u32,
+}
+
+/// Save a swap together with the request that initiates it.
+///
+/// The request is only sent by draining the `Outbox`, hence a swap that was
+/// saved is sent even if the node crashes right afterwards.
+#[async_trait]
+pub trait Enqueue<T>: Send + Sync + 'static {
+    async fn save_and_enqueue(
+        &self,
+        swap: Swap,
+        request: T,
+        peer: DialInformation,
+    ) -> anyhow::Result<()>;
+}
+
+pub trait Enqueuer:
+    Enqueue<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>
+    + Enqueue<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>>
+    + Enqueue<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>>
+    + Enqueue<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>>
+{
+}
+
+#[async_trait]
+pub trait Outbox: Send + Sync + 'static {
+    /// All requests that did not get a response yet, in the order they were
+    /// enqueued.
+    async fn pending_requests(&self) -> anyhow::Result<Vec<PendingRequest>>;
+
+    /// The peer answered the request, it is not sent again.
+    async fn mark_sent(&self, swap_id: &SwapId) -> anyhow::Result<()>;
+
+    /// The request is about to be sent to the peer.
+    async fn record_attempt(&self, swap_id: &SwapId) -> anyhow::Result<()>;
+
+    /// The same requests as `pending_requests`, with when they were enqueued
+    /// and how often they were sent.
+    async fn unanswered_requests(&self) -> anyhow::Result<Vec<UnansweredRequest>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "rfc003_outbox"]
+struct InsertableOutgoingRequest {
+    swap_id: Text<SwapId>,
+    peer_id: Text<PeerId>,
+    address_hint: Option<Text<Multiaddr>>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryablePendingRequest {
+    swap_id: Text<SwapId>,
+    peer_id: Text<PeerId>,
+    address_hint: Option<Text<Multiaddr>>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableUnansweredRequest {
+    swap_id: Text<SwapId>,
+    peer_id: Text<PeerId>,
+    address_hint: Option<Text<Multiaddr>>,
+    enqueued_at: NaiveDateTime,
+    attempts: i32,
+}
+
+macro_rules! impl_outbox {
+    ($database:ident, $connection:ty) => {
+        impl Enqueuer for $database {}
+
+        #[async_trait]
+        impl<T> Enqueue<T> for $database
+        where
+            T: Send + Sync + 'static,
+            $connection: Insert<Swap> + Insert<T>,
+        {
+            async fn save_and_enqueue(
+                &self,
+                swap: Swap,
+                request: T,
+                peer: DialInformation,
+            ) -> anyhow::Result<()> {
+                let record = InsertableOutgoingRequest {
+                    swap_id: Text(swap.swap_id),
+                    peer_id: Text(peer.peer_id),
+                    address_hint: peer.address_hint.map(Text),
+                };
+
+                self.do_in_transaction(|connection| {
+                    Insert::<Swap>::insert(connection, &swap)?;
+                    Insert::<T>::insert(connection, &request)?;
+                    diesel::insert_into(rfc003_outbox::table)
+                        .values(&record)
+                        .execute(connection)
+                })
+                .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Outbox for $database {
+            async fn pending_requests(&self) -> anyhow::Result<Vec<PendingRequest>> {
+                use self::schema::rfc003_outbox as outbox;
+
+                let records: Vec<QueryablePendingRequest> = self
+                    .do_in_transaction(|connection| {
+                        outbox::table
+                            .filter(outbox::sent_at.is_null())
+                            .order(outbox::id.asc())
+                            .select((outbox::swap_id, outbox::peer_id, outbox::address_hint))
+                            .load(connection)
+                    })
+                    .await?;
+
+                Ok(records
+                    .into_iter()
+                    .map(|record| PendingRequest {
+                        swap_id: record.swap_id.0,
+                        peer: DialInformation {
+                            peer_id: record.peer_id.0,
+                            address_hint: record.address_hint.map(|address_hint| address_hint.0),
+                        },
+                    })
+                    .collect())
+            }
+
+            async fn mark_sent(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+                use self::schema::rfc003_outbox as outbox;
+
+                self.do_in_transaction(|connection| {
+                    diesel::update(outbox::table.filter(outbox::swap_id.eq(Text(*swap_id))))
+                        .set(outbox::sent_at.eq(diesel::dsl::now))
+                        .execute(connection)
+                })
+                .await?;
+
+                Ok(())
+            }
+
+            async fn record_attempt(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+                use self::schema::rfc003_outbox as outbox;
+
+                self.do_in_transaction(|connection| {
+                    diesel::update(outbox::table.filter(outbox::swap_id.eq(Text(*swap_id))))
+                        .set(outbox::attempts.eq(outbox::attempts + 1))
+                        .execute(connection)
+                })
+                .await?;
+
+                Ok(())
+            }
+
+            async fn unanswered_requests(&self) -> anyhow::Result<Vec<UnansweredRequest>> {
+                use self::schema::rfc003_outbox as outbox;
+
+                let records: Vec<QueryableUnansweredRequest> = self
+                    .do_in_transaction(|connection| {
+                        outbox::table
+                            .filter(outbox::sent_at.is_null())
+                            .order(outbox::id.asc())
+                            .select((
+                                outbox::swap_id,
+                                outbox::peer_id,
+                                outbox::address_hint,
+                                outbox::enqueued_at,
+                                outbox::attempts,
+                            ))
+                            .load(connection)
+                    })
+                    .await?;
+
+                Ok(records
+                    .into_iter()
+                    .map(|record| UnansweredRequest {
+                        swap_id: record.swap_id.0,
+                        peer: DialInformation {
+                            peer_id: record.peer_id.0,
+                            address_hint: record.address_hint.map(|address_hint| address_hint.0),
+                        },
+                        enqueued_at: record.enqueued_at,
+                        attempts: record.attempts as u32,
+                    })
+                    .collect())
+            }
+        }
+    };
+}
+
+impl_outbox!(Sqlite, SqliteConnection);
+#[cfg(feature = "postgres")]
+impl_outbox!(Postgres, PgConnection);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{LoadRequest, Retrieve},
+        quickcheck::Quickcheck,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn enqueued_request_is_saved_and_pending_until_marked_as_sent() {
+        fn prop(
+            swap: Quickcheck<Swap>,
+            request: Quickcheck<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>,
+        ) -> anyhow::Result<bool> {
+            let swap = swap.0;
+            let request = Request {
+                swap_id: swap.swap_id,
+                ..*request
+            };
+            let pending = PendingRequest {
+                swap_id: swap.swap_id,
+                peer: DialInformation {
+                    peer_id: swap.counterparty.clone(),
+                    address_hint: None,
+                },
+            };
+
+            let db = Sqlite::new(&Path::new(":memory:"))?;
+
+            let (loaded_swap, loaded_request, pending_before, pending_after) =
+                crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                    db.save_and_enqueue(swap.clone(), request.clone(), pending.peer.clone())
+                        .await?;
+
+                    let loaded_swap = Retrieve::get(&db, &swap.swap_id).await?;
+                    let loaded_request = db.load_request(&swap.swap_id).await?;
+                    let pending_before = db.pending_requests().await?;
+                    db.mark_sent(&swap.swap_id).await?;
+                    let pending_after = db.pending_requests().await?;
+
+                    Ok((loaded_swap, loaded_request, pending_before, pending_after))
+                })?;
+
+            Ok(loaded_swap == swap
+                && loaded_request == request
+                && pending_before == vec![pending]
+                && pending_after.is_empty())
+        }
+
+        quickcheck::quickcheck(
+            prop as fn(
+                Quickcheck<Swap>,
+                Quickcheck<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>,
+            ) -> anyhow::Result<bool>,
+        );
+    }
+
+    #[test]
+    fn attempts_are_counted_until_the_request_is_answered() {
+        fn prop(
+            swap: Quickcheck<Swap>,
+            request: Quickcheck<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>,
+        ) -> anyhow::Result<bool> {
+            let swap = swap.0;
+            let request = Request {
+                swap_id: swap.swap_id,
+                ..*request
+            };
+            let peer = DialInformation {
+                peer_id: swap.counterparty.clone(),
+                address_hint: None,
+            };
+
+            let db = Sqlite::new(&Path::new(":memory:"))?;
+
+            let (unanswered_before, unanswered_after) =
+                crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                    db.save_and_enqueue(swap.clone(), request, peer.clone())
+                        .await?;
+                    db.record_attempt(&swap.swap_id).await?;
+                    db.record_attempt(&swap.swap_id).await?;
+
+                    let unanswered_before = db.unanswered_requests().await?;
+                    db.mark_sent(&swap.swap_id).await?;
+                    let unanswered_after = db.unanswered_requests().await?;
+
+                    Ok((unanswered_before, unanswered_after))
+                })?;
+
+            Ok(unanswered_before.len() == 1
+                && unanswered_before[0].swap_id == swap.swap_id
+                && unanswered_before[0].peer == peer
+                && unanswered_before[0].attempts == 2
+                && unanswered_after.is_empty())
+        }
+
+        quickcheck::quickcheck(
+            prop as fn(
+                Quickcheck<Swap>,
+                Quickcheck<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>,
+            ) -> anyhow::Result<bool>,
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, payout_account_usages},
+        Sqlite,
+    },
+    diesel::{QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+/// How often a payout account was used as redeem identity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PayoutAccountUsage {
+    pub swaps: u64,
+    pub last_used_at: NaiveDateTime,
+}
+
+#[async_trait]
+pub trait PayoutAccountUsages: Send + Sync + 'static {
+    async fn record_payout_account_usage(
+        &self,
+        swap_id: &SwapId,
+        account: &str,
+    ) -> anyhow::Result<()>;
+
+    /// The usage of every payout account that was used at least once, by
+    /// name.
+    async fn payout_account_usages(&self) -> anyhow::Result<BTreeMap<String, PayoutAccountUsage>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "payout_account_usages"]
+struct InsertablePayoutAccountUsage {
+    swap_id: Text<SwapId>,
+    account: String,
+}
+
+#[async_trait]
+impl PayoutAccountUsages for Sqlite {
+    async fn record_payout_account_usage(
+        &self,
+        swap_id: &SwapId,
+        account: &str,
+    ) -> anyhow::Result<()> {
+        let record = InsertablePayoutAccountUsage {
+            swap_id: Text(*swap_id),
+            account: account.to_owned(),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(payout_account_usages::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn payout_account_usages(&self) -> anyhow::Result<BTreeMap<String, PayoutAccountUsage>> {
+        use self::schema::payout_account_usages as usages;
+
+        let records: Vec<(String, NaiveDateTime)> = self
+            .do_in_transaction(|connection| {
+                usages::table
+                    .select((usages::account, usages::used_at))
+                    .load(connection)
+            })
+            .await?;
+
+        let mut usages = BTreeMap::<String, PayoutAccountUsage>::new();
+        for (account, used_at) in records {
+            let usage = usages.entry(account).or_insert(PayoutAccountUsage {
+                swaps: 0,
+                last_used_at: used_at,
+            });
+            usage.swaps += 1;
+            usage.last_used_at = usage.last_used_at.max(used_at);
+        }
+
+        Ok(usages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn usages_are_counted_per_account() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+
+        let usages = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.record_payout_account_usage(&SwapId::default(), "cold-wallet")
+                .await?;
+            db.record_payout_account_usage(&SwapId::default(), "cold-wallet")
+                .await?;
+            db.record_payout_account_usage(&SwapId::default(), "exchange")
+                .await?;
+
+            db.payout_account_usages().await
+        })
+        .unwrap();
+
+        assert_that(&usages.get("cold-wallet").map(|usage| usage.swaps)).is_equal_to(Some(2));
+        assert_that(&usages.get("exchange").map(|usage| usage.swaps)).is_equal_to(Some(1));
+    }
+}
@@ -0,0 +1,58 @@
+use diesel::{connection::SimpleConnection, pg::PgConnection, prelude::*};
+use std::sync::Arc;
+
+embed_migrations!("./migrations_postgres");
+
+/// Stores swaps in a PostgreSQL database, e.g. one that is shared by several
+/// deployments or managed with the tooling of an existing setup.
+#[derive(Clone, derivative::Derivative)]
+#[derivative(Debug)]
+pub struct Postgres {
+    #[derivative(Debug = "ignore")]
+    connection: Arc<futures_core::lock::Mutex<PgConnection>>,
+}
+
+impl Postgres {
+    /// Return a handle that can be used to access the database at 'url'.
+    ///
+    /// When this returns, a successful connection to the database has been
+    /// made and the database migrations have been run.
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        let connection = PgConnection::establish(url)?;
+        embedded_migrations::run(&connection)?;
+
+        log::info!("Connected to PostgreSQL database");
+
+        Ok(Postgres {
+            connection: Arc::new(futures_core::lock::Mutex::new(connection)),
+        })
+    }
+
+    /// Return a handle that can only read from the database at 'url'.
+    ///
+    /// Does not run the migrations, the database is expected to be owned by
+    /// another node.
+    pub fn new_read_only(url: &str) -> anyhow::Result<Self> {
+        let connection = PgConnection::establish(url)?;
+        connection.batch_execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY")?;
+
+        log::info!("Connected to PostgreSQL database (read-only)");
+
+        Ok(Postgres {
+            connection: Arc::new(futures_core::lock::Mutex::new(connection)),
+        })
+    }
+
+    pub(super) async fn do_in_transaction<F, T, E>(&self, f: F) -> Result<T, E>
+    where
+        F: Fn(&PgConnection) -> Result<T, E>,
+        E: From<diesel::result::Error>,
+    {
+        let guard = self.connection.lock().await;
+        let connection = &*guard;
+
+        let result = connection.transaction(|| f(&connection))?;
+
+        Ok(result)
+    }
+}
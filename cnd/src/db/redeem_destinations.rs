@@ -0,0 +1,139 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, redeem_destinations},
+        Sqlite,
+    },
+    diesel::{dsl::max, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+/// The receive addresses of the configured xpub are handed out in order, one
+/// per swap.
+#[async_trait]
+pub trait RedeemDestinations: Send + Sync + 'static {
+    /// The index of the address the swap redeems and refunds to, the next one
+    /// is assigned if the swap does not have one yet.
+    ///
+    /// Fails with `GapLimitReached` rather than handing out an address a
+    /// wallet that scans `gap_limit` addresses ahead would not find.
+    async fn redeem_destination_index(
+        &self,
+        swap_id: &SwapId,
+        gap_limit: u32,
+    ) -> anyhow::Result<u32>;
+
+    /// An HTLC of the swap was spent, i.e. funds arrived at its destination.
+    async fn mark_redeem_destination_used(&self, swap_id: &SwapId) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("the last {unused} redeem destinations did not receive funds yet, another one would exceed the gap limit")]
+pub struct GapLimitReached {
+    pub unused: u32,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "redeem_destinations"]
+struct InsertableRedeemDestination {
+    swap_id: Text<SwapId>,
+    derivation_index: i32,
+}
+
+#[async_trait]
+impl RedeemDestinations for Sqlite {
+    async fn redeem_destination_index(
+        &self,
+        swap_id: &SwapId,
+        gap_limit: u32,
+    ) -> anyhow::Result<u32> {
+        use self::schema::redeem_destinations as destinations;
+
+        let index = self
+            .do_in_transaction(|connection| -> anyhow::Result<i32> {
+                let assigned = destinations::table
+                    .filter(destinations::swap_id.eq(Text(*swap_id)))
+                    .select(destinations::derivation_index)
+                    .first::<i32>(connection)
+                    .optional()?;
+                if let Some(index) = assigned {
+                    return Ok(index);
+                }
+
+                let next = destinations::table
+                    .select(max(destinations::derivation_index))
+                    .first::<Option<i32>>(connection)?
+                    .map_or(0, |index| index + 1);
+                let first_unused = destinations::table
+                    .filter(destinations::used.eq(true))
+                    .select(max(destinations::derivation_index))
+                    .first::<Option<i32>>(connection)?
+                    .map_or(0, |index| index + 1);
+
+                let unused = u32::try_from(next - first_unused)?;
+                if unused >= gap_limit {
+                    return Err(anyhow::Error::from(GapLimitReached { unused }));
+                }
+
+                diesel::insert_into(destinations::table)
+                    .values(&InsertableRedeemDestination {
+                        swap_id: Text(*swap_id),
+                        derivation_index: next,
+                    })
+                    .execute(connection)?;
+
+                Ok(next)
+            })
+            .await?;
+
+        Ok(u32::try_from(index)?)
+    }
+
+    async fn mark_redeem_destination_used(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        use self::schema::redeem_destinations as destinations;
+
+        self.do_in_transaction(|connection| {
+            diesel::update(destinations::table.filter(destinations::swap_id.eq(Text(*swap_id))))
+                .set(destinations::used.eq(true))
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn destinations_are_assigned_in_order_within_the_gap_limit() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let (first, second, third) = (SwapId::default(), SwapId::default(), SwapId::default());
+
+        let (indices, beyond_gap_limit, after_first_was_used) =
+            crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                let indices = vec![
+                    db.redeem_destination_index(&first, 2).await?,
+                    db.redeem_destination_index(&second, 2).await?,
+                    db.redeem_destination_index(&first, 2).await?,
+                ];
+                let beyond_gap_limit = db.redeem_destination_index(&third, 2).await;
+
+                db.mark_redeem_destination_used(&first).await?;
+                let after_first_was_used = db.redeem_destination_index(&third, 2).await?;
+
+                Ok((indices, beyond_gap_limit, after_first_was_used))
+            })
+            .unwrap();
+
+        assert_that(&indices).is_equal_to(vec![0, 1, 0]);
+        assert_that(&beyond_gap_limit).is_err();
+        assert_that(&after_first_was_used).is_equal_to(2);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, requested_rates},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl},
+    swap_protocols::{rfc003::Rate, SwapId},
+};
+use async_trait::async_trait;
+
+/// The rate Alice attached to her swap request, on both sides of the swap.
+#[async_trait]
+pub trait RequestedRates: Send + Sync + 'static {
+    async fn save_requested_rate(&self, swap_id: &SwapId, rate: &Rate) -> anyhow::Result<()>;
+
+    /// `None` if the request did not come with a rate.
+    async fn requested_rate(&self, swap_id: &SwapId) -> anyhow::Result<Option<Rate>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "requested_rates"]
+struct InsertableRequestedRate {
+    swap_id: Text<SwapId>,
+    rate: Text<Rate>,
+}
+
+#[async_trait]
+impl RequestedRates for Sqlite {
+    async fn save_requested_rate(&self, swap_id: &SwapId, rate: &Rate) -> anyhow::Result<()> {
+        let record = InsertableRequestedRate {
+            swap_id: Text(*swap_id),
+            rate: Text(rate.clone()),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::replace_into(requested_rates::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn requested_rate(&self, swap_id: &SwapId) -> anyhow::Result<Option<Rate>> {
+        use self::schema::requested_rates as rates;
+
+        let record: Option<Text<Rate>> = self
+            .do_in_transaction(|connection| {
+                rates::table
+                    .filter(rates::swap_id.eq(Text(*swap_id)))
+                    .select(rates::rate)
+                    .first(connection)
+                    .optional()
+            })
+            .await?;
+
+        Ok(record.map(|rate| rate.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::{path::Path, str::FromStr};
+
+    #[test]
+    fn saved_rates_are_returned_per_swap() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let rate = Rate::from_str("40.5").unwrap();
+
+        let (saved, not_saved) = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.save_requested_rate(&swap_id, &rate).await?;
+
+            let saved = db.requested_rate(&swap_id).await?;
+            let not_saved = db.requested_rate(&SwapId::default()).await?;
+
+            Ok((saved, not_saved))
+        })
+        .unwrap();
+
+        assert_that(&saved).is_equal_to(Some(rate));
+        assert_that(&not_saved).is_none();
+    }
+}
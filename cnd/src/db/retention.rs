@@ -0,0 +1,255 @@
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, rfc003_finished_swaps},
+        Sqlite,
+    },
+    diesel::{
+        sqlite::SqliteConnection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    },
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime};
+#[cfg(feature = "postgres")]
+use diesel::pg::PgConnection;
+
+/// Determines which finished swaps are kept around.
+///
+/// A finished swap is archived as soon as it is older than `max_age` or there
+/// are more than `max_finished_swaps` newer finished swaps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_finished_swaps: Option<usize>,
+}
+
+/// Track swaps that reached a terminal state and archive them.
+///
+/// Archived swaps are no longer returned by `Retrieve` and hence are neither
+/// loaded at startup nor listed on the HTTP API.
+#[async_trait]
+pub trait Retention: Send + Sync + 'static {
+    async fn mark_finished(&self, swap_id: &SwapId) -> anyhow::Result<()>;
+
+    /// When the swap was marked as finished, `None` if it is still ongoing.
+    async fn finished_at(&self, swap_id: &SwapId) -> anyhow::Result<Option<NaiveDateTime>>;
+
+    /// Archive all finished swaps that are not covered by `policy` anymore
+    /// and return their ids.
+    async fn archive_finished_swaps(
+        &self,
+        policy: RetentionPolicy,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Vec<SwapId>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "rfc003_finished_swaps"]
+struct InsertableFinishedSwap {
+    swap_id: Text<SwapId>,
+}
+
+macro_rules! impl_retention {
+    ($database:ident) => {
+        #[async_trait]
+        impl Retention for $database {
+            async fn mark_finished(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+                self.do_in_transaction(|connection| connection.mark_finished(swap_id))
+                    .await?;
+
+                Ok(())
+            }
+
+            async fn finished_at(&self, swap_id: &SwapId) -> anyhow::Result<Option<NaiveDateTime>> {
+                use self::schema::rfc003_finished_swaps as finished_swaps;
+
+                let finished_at = self
+                    .do_in_transaction(|connection| {
+                        finished_swaps::table
+                            .filter(finished_swaps::swap_id.eq(Text(*swap_id)))
+                            .select(finished_swaps::finished_at)
+                            .first(connection)
+                            .optional()
+                    })
+                    .await?;
+
+                Ok(finished_at)
+            }
+
+            async fn archive_finished_swaps(
+                &self,
+                policy: RetentionPolicy,
+                now: NaiveDateTime,
+            ) -> anyhow::Result<Vec<SwapId>> {
+                use self::schema::rfc003_finished_swaps as finished_swaps;
+
+                let archived = self
+                    .do_in_transaction(|connection| {
+                        let records: Vec<(Text<SwapId>, NaiveDateTime)> = finished_swaps::table
+                            .filter(finished_swaps::archived_at.is_null())
+                            .order(finished_swaps::finished_at.desc())
+                            .select((finished_swaps::swap_id, finished_swaps::finished_at))
+                            .load(connection)?;
+
+                        let expired = records
+                            .into_iter()
+                            .enumerate()
+                            .filter(|(newer_swaps, (_, finished_at))| {
+                                let too_old = policy
+                                    .max_age
+                                    .map_or(false, |max_age| *finished_at < now - max_age);
+                                let too_many = policy
+                                    .max_finished_swaps
+                                    .map_or(false, |max| *newer_swaps >= max);
+
+                                too_old || too_many
+                            })
+                            .map(|(_, (swap_id, _))| swap_id)
+                            .collect::<Vec<_>>();
+
+                        diesel::update(
+                            finished_swaps::table.filter(finished_swaps::swap_id.eq_any(&expired)),
+                        )
+                        .set(finished_swaps::archived_at.eq(now))
+                        .execute(connection)?;
+
+                        Ok::<_, diesel::result::Error>(expired)
+                    })
+                    .await?;
+
+                Ok(archived.into_iter().map(|Text(swap_id)| swap_id).collect())
+            }
+        }
+    };
+}
+
+impl_retention!(Sqlite);
+#[cfg(feature = "postgres")]
+impl_retention!(Postgres);
+
+pub(crate) trait MarkFinished {
+    /// Insert a finished record for the swap, does nothing if it already
+    /// exists.
+    fn mark_finished(&self, swap_id: &SwapId) -> Result<(), diesel::result::Error>;
+}
+
+impl MarkFinished for SqliteConnection {
+    fn mark_finished(&self, swap_id: &SwapId) -> Result<(), diesel::result::Error> {
+        diesel::insert_or_ignore_into(rfc003_finished_swaps::table)
+            .values(&InsertableFinishedSwap {
+                swap_id: Text(*swap_id),
+            })
+            .execute(self)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl MarkFinished for PgConnection {
+    fn mark_finished(&self, swap_id: &SwapId) -> Result<(), diesel::result::Error> {
+        diesel::insert_into(rfc003_finished_swaps::table)
+            .values(&InsertableFinishedSwap {
+                swap_id: Text(*swap_id),
+            })
+            .on_conflict_do_nothing()
+            .execute(self)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{Retrieve, Save, Swap},
+        swap_protocols::Role,
+    };
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    fn swap() -> Swap {
+        Swap::new(
+            SwapId::default(),
+            Role::Alice,
+            "QmfUfpC2frwFvcDzpspnfZitHt5wct6n4kpG5jzgRdsxkY"
+                .parse()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn finished_swaps_beyond_max_count_are_archived_and_not_retrieved() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let finished = swap();
+        let ongoing = swap();
+        let policy = RetentionPolicy {
+            max_age: None,
+            max_finished_swaps: Some(0),
+        };
+
+        let (archived, retrieved) = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.save(finished.clone()).await?;
+            db.save(ongoing.clone()).await?;
+            db.mark_finished(&finished.swap_id).await?;
+
+            let now = chrono::Utc::now().naive_utc();
+            let archived = db.archive_finished_swaps(policy, now).await?;
+            let retrieved = db.all().await?;
+
+            Ok((archived, retrieved))
+        })
+        .unwrap();
+
+        assert_that(&archived).is_equal_to(vec![finished.swap_id]);
+        assert_that(&retrieved).is_equal_to(vec![ongoing]);
+    }
+
+    #[test]
+    fn finished_swaps_within_policy_are_kept() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let finished = swap();
+        let policy = RetentionPolicy {
+            max_age: Some(Duration::days(1)),
+            max_finished_swaps: Some(10),
+        };
+
+        let archived = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.save(finished.clone()).await?;
+            db.mark_finished(&finished.swap_id).await?;
+
+            let now = chrono::Utc::now().naive_utc();
+            db.archive_finished_swaps(policy, now).await
+        })
+        .unwrap();
+
+        assert_that(&archived).is_empty();
+    }
+
+    #[test]
+    fn only_finished_swaps_have_a_finish_time() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let finished = swap();
+        let ongoing = swap();
+
+        let (finished_at, ongoing_finished_at) =
+            crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                db.save(finished.clone()).await?;
+                db.save(ongoing.clone()).await?;
+                db.mark_finished(&finished.swap_id).await?;
+
+                Ok((
+                    db.finished_at(&finished.swap_id).await?,
+                    db.finished_at(&ongoing.swap_id).await?,
+                ))
+            })
+            .unwrap();
+
+        assert_that(&finished_at).is_some();
+        assert_that(&ongoing_finished_at).is_none();
+    }
+}
@@ -1,10 +1,14 @@
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
 use crate::{
     db::{
         custom_sql_types::{Text, U32},
         new_types::{DecimalU256, EthereumAddress, Satoshis},
+        retention::MarkFinished,
         schema::{self, *},
         Sqlite, Swap,
     },
+    diesel::sqlite::SqliteConnection,
     ethereum::{Erc20Token, EtherQuantity},
     swap_protocols::{
         ledger::{Bitcoin, Ethereum},
@@ -13,6 +17,9 @@ use crate::{
     },
 };
 use async_trait::async_trait;
+use chrono::NaiveDateTime;
+#[cfg(feature = "postgres")]
+use diesel::pg::PgConnection;
 use diesel::RunQueryDsl;
 use libp2p::{self, PeerId};
 
@@ -28,31 +35,25 @@ pub trait Saver:
     + Save<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>>
     + Save<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>>
     + Save<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>>
-    + Save<Accept<Bitcoin, Ethereum>>
+    + Save<(
+        Swap,
+        Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>,
+    )> + Save<(
+        Swap,
+        Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>,
+    )> + Save<(
+        Swap,
+        Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>,
+    )> + Save<(
+        Swap,
+        Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+    )> + Save<Accept<Bitcoin, Ethereum>>
     + Save<Accept<Ethereum, Bitcoin>>
     + Save<Decline>
     + Save<Swap>
 {
 }
 
-impl Saver for Sqlite {}
-
-#[async_trait]
-impl Save<Swap> for Sqlite {
-    async fn save(&self, swap: Swap) -> anyhow::Result<()> {
-        let insertable = InsertableSwap::from(swap);
-
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(schema::rfc003_swaps::dsl::rfc003_swaps)
-                .values(&insertable)
-                .execute(&*connection)
-        })
-        .await?;
-
-        Ok(())
-    }
-}
-
 #[derive(Insertable, Debug, Clone)]
 #[table_name = "rfc003_swaps"]
 struct InsertableSwap {
@@ -87,12 +88,85 @@ struct InsertableBitcoinEthereumBitcoinEtherRequestMessage {
     secret_hash: Text<SecretHash>,
 }
 
-#[async_trait]
-impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>> for Sqlite {
-    async fn save(
-        &self,
-        message: Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>,
-    ) -> anyhow::Result<()> {
+#[derive(Insertable, Debug, Copy, Clone)]
+#[table_name = "rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages"]
+struct InsertableBitcoinEthereumBitcoinErc20RequestMessage {
+    swap_id: Text<SwapId>,
+    bitcoin_network: Text<bitcoin::Network>,
+    ethereum_chain_id: U32,
+    bitcoin_amount: Text<Satoshis>,
+    erc20_amount: Text<DecimalU256>,
+    erc20_token_contract: Text<EthereumAddress>,
+    hash_function: Text<HashFunction>,
+    bitcoin_refund_identity: Text<bitcoin::PublicKey>,
+    ethereum_redeem_identity: Text<EthereumAddress>,
+    bitcoin_expiry: U32,
+    ethereum_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[derive(Insertable, Debug, Copy, Clone)]
+#[table_name = "rfc003_ethereum_bitcoin_ether_bitcoin_request_messages"]
+struct InsertableEthereumBitcoinEtherBitcoinRequestMessage {
+    swap_id: Text<SwapId>,
+    ethereum_chain_id: U32,
+    bitcoin_network: Text<bitcoin::Network>,
+    ether_amount: Text<DecimalU256>,
+    bitcoin_amount: Text<Satoshis>,
+    hash_function: Text<HashFunction>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    bitcoin_redeem_identity: Text<bitcoin::PublicKey>,
+    ethereum_expiry: U32,
+    bitcoin_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[derive(Insertable, Debug, Copy, Clone)]
+#[table_name = "rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages"]
+struct InsertableEthereumBitcoinErc20BitcoinRequestMessage {
+    swap_id: Text<SwapId>,
+    ethereum_chain_id: U32,
+    bitcoin_network: Text<bitcoin::Network>,
+    erc20_amount: Text<DecimalU256>,
+    erc20_token_contract: Text<EthereumAddress>,
+    bitcoin_amount: Text<Satoshis>,
+    hash_function: Text<HashFunction>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    bitcoin_redeem_identity: Text<bitcoin::PublicKey>,
+    ethereum_expiry: U32,
+    bitcoin_expiry: U32,
+    secret_hash: Text<SecretHash>,
+}
+
+#[derive(Insertable, Debug, Copy, Clone)]
+#[table_name = "rfc003_ethereum_bitcoin_accept_messages"]
+struct InsertableEthereumBitcoinAcceptMessage {
+    swap_id: Text<SwapId>,
+    ethereum_redeem_identity: Text<EthereumAddress>,
+    bitcoin_refund_identity: Text<bitcoin::PublicKey>,
+    at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Copy, Clone)]
+#[table_name = "rfc003_bitcoin_ethereum_accept_messages"]
+struct InsertableBitcoinEthereumAcceptMessage {
+    swap_id: Text<SwapId>,
+    bitcoin_redeem_identity: Text<bitcoin::PublicKey>,
+    ethereum_refund_identity: Text<EthereumAddress>,
+    at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "rfc003_decline_messages"]
+struct InsertableDeclineMessage {
+    swap_id: Text<SwapId>,
+    reason: Option<String>,
+}
+
+impl From<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>
+    for InsertableBitcoinEthereumBitcoinEtherRequestMessage
+{
+    fn from(request: Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>) -> Self {
         let Request {
             swap_id,
             alpha_ledger,
@@ -105,9 +179,9 @@ impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>> for Sqlite
             alpha_expiry,
             beta_expiry,
             secret_hash,
-        } = message;
+        } = request;
 
-        let insertable = InsertableBitcoinEthereumBitcoinEtherRequestMessage {
+        InsertableBitcoinEthereumBitcoinEtherRequestMessage {
             swap_id: Text(swap_id),
             bitcoin_network: Text(alpha_ledger.network),
             ethereum_chain_id: U32(beta_ledger.chain_id.into()),
@@ -119,42 +193,14 @@ impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>> for Sqlite
             bitcoin_expiry: U32(alpha_expiry.into()),
             ethereum_expiry: U32(beta_expiry.into()),
             secret_hash: Text(secret_hash),
-        };
-
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(rfc003_bitcoin_ethereum_bitcoin_ether_request_messages::table)
-                .values(&insertable)
-                .execute(connection)
-        })
-        .await?;
-
-        Ok(())
+        }
     }
 }
 
-#[derive(Insertable, Debug, Copy, Clone)]
-#[table_name = "rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages"]
-struct InsertableBitcoinEthereumBitcoinErc20RequestMessage {
-    swap_id: Text<SwapId>,
-    bitcoin_network: Text<bitcoin::Network>,
-    ethereum_chain_id: U32,
-    bitcoin_amount: Text<Satoshis>,
-    erc20_amount: Text<DecimalU256>,
-    erc20_token_contract: Text<EthereumAddress>,
-    hash_function: Text<HashFunction>,
-    bitcoin_refund_identity: Text<bitcoin::PublicKey>,
-    ethereum_redeem_identity: Text<EthereumAddress>,
-    bitcoin_expiry: U32,
-    ethereum_expiry: U32,
-    secret_hash: Text<SecretHash>,
-}
-
-#[async_trait]
-impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>> for Sqlite {
-    async fn save(
-        &self,
-        message: Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>,
-    ) -> anyhow::Result<()> {
+impl From<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>>
+    for InsertableBitcoinEthereumBitcoinErc20RequestMessage
+{
+    fn from(request: Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>) -> Self {
         let Request {
             swap_id,
             alpha_ledger,
@@ -167,9 +213,9 @@ impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>> for Sqlite {
             alpha_expiry,
             beta_expiry,
             secret_hash,
-        } = message;
+        } = request;
 
-        let insertable = InsertableBitcoinEthereumBitcoinErc20RequestMessage {
+        InsertableBitcoinEthereumBitcoinErc20RequestMessage {
             swap_id: Text(swap_id),
             bitcoin_network: Text(alpha_ledger.network),
             ethereum_chain_id: U32(beta_ledger.chain_id.into()),
@@ -182,41 +228,14 @@ impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>> for Sqlite {
             bitcoin_expiry: U32(alpha_expiry.into()),
             ethereum_expiry: U32(beta_expiry.into()),
             secret_hash: Text(secret_hash),
-        };
-
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages::table)
-                .values(&insertable)
-                .execute(connection)
-        })
-        .await?;
-
-        Ok(())
+        }
     }
 }
 
-#[derive(Insertable, Debug, Copy, Clone)]
-#[table_name = "rfc003_ethereum_bitcoin_ether_bitcoin_request_messages"]
-struct InsertableEthereumBitcoinEtherBitcoinRequestMessage {
-    swap_id: Text<SwapId>,
-    ethereum_chain_id: U32,
-    bitcoin_network: Text<bitcoin::Network>,
-    ether_amount: Text<DecimalU256>,
-    bitcoin_amount: Text<Satoshis>,
-    hash_function: Text<HashFunction>,
-    ethereum_refund_identity: Text<EthereumAddress>,
-    bitcoin_redeem_identity: Text<bitcoin::PublicKey>,
-    ethereum_expiry: U32,
-    bitcoin_expiry: U32,
-    secret_hash: Text<SecretHash>,
-}
-
-#[async_trait]
-impl Save<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>> for Sqlite {
-    async fn save(
-        &self,
-        message: Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>,
-    ) -> anyhow::Result<()> {
+impl From<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>>
+    for InsertableEthereumBitcoinEtherBitcoinRequestMessage
+{
+    fn from(request: Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>) -> Self {
         let Request {
             swap_id,
             alpha_ledger,
@@ -229,9 +248,9 @@ impl Save<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>> for Sqlite
             alpha_expiry,
             beta_expiry,
             secret_hash,
-        } = message;
+        } = request;
 
-        let insertable = InsertableEthereumBitcoinEtherBitcoinRequestMessage {
+        InsertableEthereumBitcoinEtherBitcoinRequestMessage {
             swap_id: Text(swap_id),
             ethereum_chain_id: U32(alpha_ledger.chain_id.into()),
             bitcoin_network: Text(beta_ledger.network),
@@ -243,41 +262,14 @@ impl Save<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>> for Sqlite
             ethereum_expiry: U32(alpha_expiry.into()),
             bitcoin_expiry: U32(beta_expiry.into()),
             secret_hash: Text(secret_hash),
-        };
-
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(rfc003_ethereum_bitcoin_ether_bitcoin_request_messages::table)
-                .values(&insertable)
-                .execute(connection)
-        })
-        .await?;
-
-        Ok(())
+        }
     }
 }
-#[derive(Insertable, Debug, Copy, Clone)]
-#[table_name = "rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages"]
-struct InsertableEthereumBitcoinErc20BitcoinRequestMessage {
-    swap_id: Text<SwapId>,
-    ethereum_chain_id: U32,
-    bitcoin_network: Text<bitcoin::Network>,
-    erc20_amount: Text<DecimalU256>,
-    erc20_token_contract: Text<EthereumAddress>,
-    bitcoin_amount: Text<Satoshis>,
-    hash_function: Text<HashFunction>,
-    ethereum_refund_identity: Text<EthereumAddress>,
-    bitcoin_redeem_identity: Text<bitcoin::PublicKey>,
-    ethereum_expiry: U32,
-    bitcoin_expiry: U32,
-    secret_hash: Text<SecretHash>,
-}
 
-#[async_trait]
-impl Save<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>> for Sqlite {
-    async fn save(
-        &self,
-        message: Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
-    ) -> anyhow::Result<()> {
+impl From<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>>
+    for InsertableEthereumBitcoinErc20BitcoinRequestMessage
+{
+    fn from(request: Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>) -> Self {
         let Request {
             swap_id,
             alpha_ledger,
@@ -290,9 +282,9 @@ impl Save<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>> for Sqlite {
             alpha_expiry,
             beta_expiry,
             secret_hash,
-        } = message;
+        } = request;
 
-        let insertable = InsertableEthereumBitcoinErc20BitcoinRequestMessage {
+        InsertableEthereumBitcoinErc20BitcoinRequestMessage {
             swap_id: Text(swap_id),
             ethereum_chain_id: U32(alpha_ledger.chain_id.into()),
             bitcoin_network: Text(beta_ledger.network),
@@ -305,113 +297,281 @@ impl Save<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>> for Sqlite {
             ethereum_expiry: U32(alpha_expiry.into()),
             bitcoin_expiry: U32(beta_expiry.into()),
             secret_hash: Text(secret_hash),
-        };
-
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages::table)
-                .values(&insertable)
-                .execute(connection)
-        })
-        .await?;
-
-        Ok(())
+        }
     }
 }
 
-#[derive(Insertable, Debug, Copy, Clone)]
-#[table_name = "rfc003_ethereum_bitcoin_accept_messages"]
-struct InsertableEthereumBitcoinAcceptMessage {
-    swap_id: Text<SwapId>,
-    ethereum_redeem_identity: Text<EthereumAddress>,
-    bitcoin_refund_identity: Text<bitcoin::PublicKey>,
+/// Insert a record with a connection that is already used for a
+/// transaction, so that several records can be saved atomically.
+pub trait Insert<T> {
+    fn insert(&self, record: &T) -> Result<(), diesel::result::Error>;
 }
 
-#[async_trait]
-impl Save<Accept<Ethereum, Bitcoin>> for Sqlite {
-    async fn save(&self, message: Accept<Ethereum, Bitcoin>) -> anyhow::Result<()> {
-        let Accept {
-            swap_id,
-            alpha_ledger_redeem_identity,
-            beta_ledger_refund_identity,
-        } = message;
+macro_rules! impl_insert {
+    ($connection:ty) => {
+        impl Insert<Swap> for $connection {
+            fn insert(&self, swap: &Swap) -> Result<(), diesel::result::Error> {
+                diesel::insert_into(schema::rfc003_swaps::dsl::rfc003_swaps)
+                    .values(&InsertableSwap::from(swap.clone()))
+                    .execute(self)?;
 
-        let insertable = InsertableEthereumBitcoinAcceptMessage {
-            swap_id: Text(swap_id),
-            ethereum_redeem_identity: Text(EthereumAddress(alpha_ledger_redeem_identity)),
-            bitcoin_refund_identity: Text(beta_ledger_refund_identity.into_inner()),
-        };
-
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(rfc003_ethereum_bitcoin_accept_messages::table)
-                .values(&insertable)
-                .execute(&*connection)
-        })
-        .await?;
-
-        Ok(())
-    }
-}
-#[derive(Insertable, Debug, Copy, Clone)]
-#[table_name = "rfc003_bitcoin_ethereum_accept_messages"]
-struct InsertableBitcoinEthereumAcceptMessage {
-    swap_id: Text<SwapId>,
-    bitcoin_redeem_identity: Text<bitcoin::PublicKey>,
-    ethereum_refund_identity: Text<EthereumAddress>,
-}
+                Ok(())
+            }
+        }
 
-#[async_trait]
-impl Save<Accept<Bitcoin, Ethereum>> for Sqlite {
-    async fn save(&self, message: Accept<Bitcoin, Ethereum>) -> anyhow::Result<()> {
-        let Accept {
-            swap_id,
-            alpha_ledger_redeem_identity,
-            beta_ledger_refund_identity,
-        } = message;
+        impl Insert<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>> for $connection {
+            fn insert(
+                &self,
+                request: &Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>,
+            ) -> Result<(), diesel::result::Error> {
+                diesel::insert_into(rfc003_bitcoin_ethereum_bitcoin_ether_request_messages::table)
+                    .values(&InsertableBitcoinEthereumBitcoinEtherRequestMessage::from(
+                        request.clone(),
+                    ))
+                    .execute(self)?;
+
+                Ok(())
+            }
+        }
 
-        let insertable = InsertableBitcoinEthereumAcceptMessage {
-            swap_id: Text(swap_id),
-            bitcoin_redeem_identity: Text(alpha_ledger_redeem_identity.into_inner()),
-            ethereum_refund_identity: Text(EthereumAddress(beta_ledger_refund_identity)),
-        };
-
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(rfc003_bitcoin_ethereum_accept_messages::table)
-                .values(&insertable)
-                .execute(&*connection)
-        })
-        .await?;
-
-        Ok(())
-    }
-}
-#[derive(Insertable, Debug, Clone)]
-#[table_name = "rfc003_decline_messages"]
-struct InsertableDeclineMessage {
-    swap_id: Text<SwapId>,
-    reason: Option<String>,
+        impl Insert<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>> for $connection {
+            fn insert(
+                &self,
+                request: &Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>,
+            ) -> Result<(), diesel::result::Error> {
+                diesel::insert_into(rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages::table)
+                    .values(&InsertableBitcoinEthereumBitcoinErc20RequestMessage::from(
+                        request.clone(),
+                    ))
+                    .execute(self)?;
+
+                Ok(())
+            }
+        }
+
+        impl Insert<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>> for $connection {
+            fn insert(
+                &self,
+                request: &Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>,
+            ) -> Result<(), diesel::result::Error> {
+                diesel::insert_into(rfc003_ethereum_bitcoin_ether_bitcoin_request_messages::table)
+                    .values(&InsertableEthereumBitcoinEtherBitcoinRequestMessage::from(
+                        request.clone(),
+                    ))
+                    .execute(self)?;
+
+                Ok(())
+            }
+        }
+
+        impl Insert<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>> for $connection {
+            fn insert(
+                &self,
+                request: &Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+            ) -> Result<(), diesel::result::Error> {
+                diesel::insert_into(rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages::table)
+                    .values(&InsertableEthereumBitcoinErc20BitcoinRequestMessage::from(
+                        request.clone(),
+                    ))
+                    .execute(self)?;
+
+                Ok(())
+            }
+        }
+
+        impl Insert<(Accept<Ethereum, Bitcoin>, NaiveDateTime)> for $connection {
+            fn insert(
+                &self,
+                (accept, at): &(Accept<Ethereum, Bitcoin>, NaiveDateTime),
+            ) -> Result<(), diesel::result::Error> {
+                let Accept {
+                    swap_id,
+                    alpha_ledger_redeem_identity,
+                    beta_ledger_refund_identity,
+                } = *accept;
+
+                diesel::insert_into(rfc003_ethereum_bitcoin_accept_messages::table)
+                    .values(&InsertableEthereumBitcoinAcceptMessage {
+                        swap_id: Text(swap_id),
+                        ethereum_redeem_identity: Text(EthereumAddress(
+                            alpha_ledger_redeem_identity,
+                        )),
+                        bitcoin_refund_identity: Text(beta_ledger_refund_identity.into_inner()),
+                        at: *at,
+                    })
+                    .execute(self)?;
+
+                Ok(())
+            }
+        }
+
+        impl Insert<(Accept<Bitcoin, Ethereum>, NaiveDateTime)> for $connection {
+            fn insert(
+                &self,
+                (accept, at): &(Accept<Bitcoin, Ethereum>, NaiveDateTime),
+            ) -> Result<(), diesel::result::Error> {
+                let Accept {
+                    swap_id,
+                    alpha_ledger_redeem_identity,
+                    beta_ledger_refund_identity,
+                } = *accept;
+
+                diesel::insert_into(rfc003_bitcoin_ethereum_accept_messages::table)
+                    .values(&InsertableBitcoinEthereumAcceptMessage {
+                        swap_id: Text(swap_id),
+                        bitcoin_redeem_identity: Text(alpha_ledger_redeem_identity.into_inner()),
+                        ethereum_refund_identity: Text(EthereumAddress(
+                            beta_ledger_refund_identity,
+                        )),
+                        at: *at,
+                    })
+                    .execute(self)?;
+
+                Ok(())
+            }
+        }
+    };
 }
 
-#[async_trait]
-impl Save<Decline> for Sqlite {
-    async fn save(&self, message: Decline) -> anyhow::Result<()> {
-        let Decline {
-            swap_id,
-            reason: _reason, /* we don't map reason to a DB type because will be gone soon
-                              * (hopefully) */
-        } = message;
+impl_insert!(SqliteConnection);
+#[cfg(feature = "postgres")]
+impl_insert!(PgConnection);
 
-        let insertable = InsertableDeclineMessage {
-            swap_id: Text(swap_id),
-            reason: None,
-        };
+macro_rules! impl_save {
+    ($database:ident, $connection:ty) => {
+        impl Saver for $database {}
 
-        self.do_in_transaction(|connection| {
-            diesel::insert_into(rfc003_decline_messages::table)
-                .values(&insertable)
-                .execute(&*connection)
-        })
-        .await?;
+        #[async_trait]
+        impl Save<Swap> for $database {
+            async fn save(&self, swap: Swap) -> anyhow::Result<()> {
+                self.do_in_transaction(|connection| connection.insert(&swap))
+                    .await?;
 
-        Ok(())
-    }
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>> for $database {
+            async fn save(
+                &self,
+                message: Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>,
+            ) -> anyhow::Result<()> {
+                self.do_in_transaction(|connection| connection.insert(&message))
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Save<Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>> for $database {
+            async fn save(
+                &self,
+                message: Request<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>,
+            ) -> anyhow::Result<()> {
+                self.do_in_transaction(|connection| connection.insert(&message))
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Save<Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>> for $database {
+            async fn save(
+                &self,
+                message: Request<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>,
+            ) -> anyhow::Result<()> {
+                self.do_in_transaction(|connection| connection.insert(&message))
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Save<Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>> for $database {
+            async fn save(
+                &self,
+                message: Request<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+            ) -> anyhow::Result<()> {
+                self.do_in_transaction(|connection| connection.insert(&message))
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        /// Either both or neither are saved, hence saving them can be retried.
+        #[async_trait]
+        impl<T> Save<(Swap, T)> for $database
+        where
+            T: Send + Sync + 'static,
+            $connection: Insert<Swap> + Insert<T>,
+        {
+            async fn save(&self, message: (Swap, T)) -> anyhow::Result<()> {
+                let (swap, request) = message;
+
+                self.do_in_transaction(|connection| {
+                    Insert::<Swap>::insert(connection, &swap)?;
+                    Insert::<T>::insert(connection, &request)
+                })
+                .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Save<Accept<Ethereum, Bitcoin>> for $database {
+            async fn save(&self, message: Accept<Ethereum, Bitcoin>) -> anyhow::Result<()> {
+                let record = (message, chrono::Utc::now().naive_utc());
+
+                self.do_in_transaction(|connection| connection.insert(&record))
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Save<Accept<Bitcoin, Ethereum>> for $database {
+            async fn save(&self, message: Accept<Bitcoin, Ethereum>) -> anyhow::Result<()> {
+                let record = (message, chrono::Utc::now().naive_utc());
+
+                self.do_in_transaction(|connection| connection.insert(&record))
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl Save<Decline> for $database {
+            async fn save(&self, message: Decline) -> anyhow::Result<()> {
+                let Decline { swap_id, reason } = message;
+
+                let insertable = InsertableDeclineMessage {
+                    swap_id: Text(swap_id),
+                    reason: reason.map(|reason| reason.to_string()),
+                };
+
+                // A declined swap will never make any progress, hence it is finished.
+                self.do_in_transaction(|connection| {
+                    diesel::insert_into(rfc003_decline_messages::table)
+                        .values(&insertable)
+                        .execute(&*connection)?;
+                    connection.mark_finished(&swap_id)
+                })
+                .await?;
+
+                Ok(())
+            }
+        }
+    };
 }
+
+impl_save!(Sqlite, SqliteConnection);
+#[cfg(feature = "postgres")]
+impl_save!(Postgres, PgConnection);
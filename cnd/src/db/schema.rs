@@ -104,3 +104,160 @@ table! {
        counterparty -> Text,
    }
 }
+
+table! {
+   rfc003_finished_swaps {
+       id -> Integer,
+       swap_id -> Text,
+       finished_at -> Timestamp,
+       archived_at -> Nullable<Timestamp>,
+   }
+}
+
+allow_tables_to_appear_in_same_query!(rfc003_swaps, rfc003_finished_swaps);
+allow_tables_to_appear_in_same_query!(
+    rfc003_bitcoin_ethereum_accept_messages,
+    rfc003_bitcoin_ethereum_bitcoin_ether_request_messages,
+    rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages
+);
+allow_tables_to_appear_in_same_query!(
+    rfc003_ethereum_bitcoin_accept_messages,
+    rfc003_ethereum_bitcoin_ether_bitcoin_request_messages,
+    rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages
+);
+
+table! {
+   rfc003_action_invocations {
+       id -> Integer,
+       swap_id -> Text,
+       action -> Text,
+       parameters -> Text,
+       payload -> Text,
+       payload_hash -> Text,
+       invoked_at -> Timestamp,
+   }
+}
+
+table! {
+   swap_failures {
+       id -> Integer,
+       swap_id -> Text,
+       category -> Text,
+       message -> Text,
+       failed_at -> Timestamp,
+   }
+}
+
+table! {
+   swap_fees {
+       id -> Integer,
+       swap_id -> Text,
+       ledger -> Text,
+       transaction_id -> Text,
+       amount -> Text,
+       recorded_at -> Timestamp,
+   }
+}
+
+table! {
+   auto_refunds {
+       id -> Integer,
+       swap_id -> Text,
+       address -> Text,
+       fee_per_wu -> Text,
+       transaction_id -> Nullable<Text>,
+       broadcast_at -> Nullable<Timestamp>,
+   }
+}
+
+table! {
+   redeem_destinations {
+       id -> Integer,
+       swap_id -> Text,
+       derivation_index -> Integer,
+       used -> Bool,
+   }
+}
+
+table! {
+   payout_account_usages {
+       id -> Integer,
+       swap_id -> Text,
+       account -> Text,
+       used_at -> Timestamp,
+   }
+}
+
+table! {
+   rfc003_outbox {
+       id -> Integer,
+       swap_id -> Text,
+       peer_id -> Text,
+       address_hint -> Nullable<Text>,
+       enqueued_at -> Timestamp,
+       sent_at -> Nullable<Timestamp>,
+       attempts -> Integer,
+   }
+}
+
+table! {
+   swap_metadata {
+       id -> Integer,
+       swap_id -> Text,
+       external_id -> Nullable<Text>,
+       note -> Nullable<Text>,
+   }
+}
+
+table! {
+   swap_messages {
+       id -> Integer,
+       swap_id -> Text,
+       direction -> Text,
+       frame -> Text,
+       size -> BigInt,
+       recorded_at -> Timestamp,
+   }
+}
+
+table! {
+   requested_rates {
+       id -> Integer,
+       swap_id -> Text,
+       rate -> Text,
+   }
+}
+
+table! {
+   issued_identities {
+       id -> Integer,
+       swap_id -> Text,
+       identity -> Text,
+   }
+}
+
+table! {
+   constructed_transactions {
+       id -> Integer,
+       swap_id -> Text,
+       txid -> Text,
+       hex -> Text,
+       constructed_at -> Timestamp,
+   }
+}
+
+table! {
+   imported_swap_secrets {
+       id -> Integer,
+       swap_id -> Text,
+       sealed_secrets -> Text,
+   }
+}
+
+table! {
+   node_lease {
+       id -> Integer,
+       holder -> Text,
+       expires_at -> Timestamp,
+   }
+}
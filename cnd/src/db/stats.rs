@@ -0,0 +1,229 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        new_types::{DecimalU256, EthereumAddress, Satoshis},
+        Sqlite,
+    },
+    diesel::{sql_types, RunQueryDsl},
+    ethereum::{Address, U256},
+    swap_protocols::rfc003::messages::SwapDeclineReason,
+};
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+/// Swaps that finished without being declined or failing.
+const COMPLETED_SWAPS: &str = "
+    SELECT swap_id FROM rfc003_finished_swaps
+    WHERE swap_id NOT IN (SELECT swap_id FROM rfc003_decline_messages)
+    AND swap_id NOT IN (SELECT swap_id FROM swap_failures)";
+
+/// Statistics over all swaps in the database, including archived ones.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapStats {
+    /// Number of completed swaps per day they finished on, oldest first.
+    pub swaps_per_day: Vec<(NaiveDate, u64)>,
+    pub volume: Volume,
+    /// Average time from accepting a swap to the first invocation of its
+    /// redeem action, `None` if no completed swap was redeemed through cnd.
+    pub average_time_to_redeem: Option<Duration>,
+    /// Number of declined swaps per reason, `None` if no reason was given.
+    pub decline_reasons: Vec<(Option<SwapDeclineReason>, u64)>,
+}
+
+/// Total quantity of each asset exchanged in completed swaps, in the smallest
+/// unit of the asset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Volume {
+    pub bitcoin: u128,
+    pub ether: U256,
+    /// Keyed by token contract.
+    pub erc20: BTreeMap<Address, U256>,
+}
+
+#[async_trait]
+pub trait Stats: Send + Sync + 'static {
+    async fn stats(&self) -> anyhow::Result<SwapStats>;
+}
+
+#[derive(QueryableByName, Debug)]
+struct SwapsPerDay {
+    #[sql_type = "sql_types::Text"]
+    day: Text<NaiveDate>,
+    #[sql_type = "sql_types::BigInt"]
+    swaps: i64,
+}
+
+#[derive(QueryableByName, Debug)]
+struct SwapVolume {
+    #[sql_type = "sql_types::Text"]
+    bitcoin_amount: Text<Satoshis>,
+    #[sql_type = "sql_types::Text"]
+    ethereum_amount: Text<DecimalU256>,
+    #[sql_type = "sql_types::Nullable<sql_types::Text>"]
+    erc20_token_contract: Option<Text<EthereumAddress>>,
+}
+
+#[derive(QueryableByName, Debug)]
+struct AverageTimeToRedeem {
+    #[sql_type = "sql_types::Nullable<sql_types::Double>"]
+    seconds: Option<f64>,
+}
+
+#[derive(QueryableByName, Debug)]
+struct DeclineReason {
+    #[sql_type = "sql_types::Nullable<sql_types::Text>"]
+    reason: Option<Text<SwapDeclineReason>>,
+    #[sql_type = "sql_types::BigInt"]
+    declines: i64,
+}
+
+#[async_trait]
+impl Stats for Sqlite {
+    async fn stats(&self) -> anyhow::Result<SwapStats> {
+        let (swaps_per_day, volumes, average_time_to_redeem, decline_reasons) = self
+            .do_in_transaction(|connection| {
+                let swaps_per_day: Vec<SwapsPerDay> = diesel::sql_query(format!(
+                    "SELECT date(finished_at) AS day, COUNT(*) AS swaps
+                    FROM rfc003_finished_swaps
+                    WHERE swap_id IN ({})
+                    GROUP BY day
+                    ORDER BY day",
+                    COMPLETED_SWAPS
+                ))
+                .load(connection)?;
+
+                // Amounts of ether and tokens do not fit into an SQLite
+                // integer, hence they are only selected here and summed up
+                // below.
+                let volumes: Vec<SwapVolume> = diesel::sql_query(format!(
+                    "SELECT bitcoin_amount,
+                        ether_amount AS ethereum_amount,
+                        NULL AS erc20_token_contract
+                    FROM rfc003_bitcoin_ethereum_bitcoin_ether_request_messages
+                    WHERE swap_id IN ({completed})
+                    UNION ALL
+                    SELECT bitcoin_amount, ether_amount, NULL
+                    FROM rfc003_ethereum_bitcoin_ether_bitcoin_request_messages
+                    WHERE swap_id IN ({completed})
+                    UNION ALL
+                    SELECT bitcoin_amount, erc20_amount, erc20_token_contract
+                    FROM rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages
+                    WHERE swap_id IN ({completed})
+                    UNION ALL
+                    SELECT bitcoin_amount, erc20_amount, erc20_token_contract
+                    FROM rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages
+                    WHERE swap_id IN ({completed})",
+                    completed = COMPLETED_SWAPS
+                ))
+                .load(connection)?;
+
+                let average_time_to_redeem: AverageTimeToRedeem = diesel::sql_query(format!(
+                    "SELECT AVG(
+                        (julianday(redeems.invoked_at) - julianday(accepts.at)) * 86400.0
+                    ) AS seconds
+                    FROM (
+                        SELECT swap_id, MIN(invoked_at) AS invoked_at
+                        FROM rfc003_action_invocations
+                        WHERE action = 'redeem'
+                        GROUP BY swap_id
+                    ) AS redeems
+                    JOIN (
+                        SELECT swap_id, at FROM rfc003_bitcoin_ethereum_accept_messages
+                        UNION ALL
+                        SELECT swap_id, at FROM rfc003_ethereum_bitcoin_accept_messages
+                    ) AS accepts
+                    ON accepts.swap_id = redeems.swap_id
+                    WHERE redeems.swap_id IN ({})",
+                    COMPLETED_SWAPS
+                ))
+                .get_result(connection)?;
+
+                let decline_reasons: Vec<DeclineReason> = diesel::sql_query(
+                    "SELECT reason, COUNT(*) AS declines
+                    FROM rfc003_decline_messages
+                    GROUP BY reason
+                    ORDER BY declines DESC",
+                )
+                .load(connection)?;
+
+                Ok::<_, diesel::result::Error>((
+                    swaps_per_day,
+                    volumes,
+                    average_time_to_redeem,
+                    decline_reasons,
+                ))
+            })
+            .await?;
+
+        let mut volume = Volume::default();
+        for swap in volumes {
+            volume.bitcoin += u128::from(u64::from(*swap.bitcoin_amount));
+
+            let amount = swap.ethereum_amount.0;
+            let sum = match swap.erc20_token_contract {
+                Some(Text(EthereumAddress(contract))) => volume.erc20.entry(contract).or_default(),
+                None => &mut volume.ether,
+            };
+            *sum = sum.saturating_add(U256::from(amount));
+        }
+
+        Ok(SwapStats {
+            swaps_per_day: swaps_per_day
+                .into_iter()
+                .map(|record| (*record.day, record.swaps as u64))
+                .collect(),
+            volume,
+            average_time_to_redeem: average_time_to_redeem
+                .seconds
+                .map(|seconds| Duration::milliseconds((seconds * 1000.0) as i64)),
+            decline_reasons: decline_reasons
+                .into_iter()
+                .map(|record| (record.reason.map(|reason| reason.0), record.declines as u64))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{Retention, Save},
+        swap_protocols::{rfc003::Decline, SwapId},
+    };
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn declined_swaps_are_counted_per_reason_but_are_not_completed() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let completed = SwapId::default();
+
+        let stats = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            for reason in vec![
+                Some(SwapDeclineReason::UnsatisfactoryRate),
+                None,
+                Some(SwapDeclineReason::UnsatisfactoryRate),
+            ] {
+                db.save(Decline {
+                    swap_id: SwapId::default(),
+                    reason,
+                })
+                .await?;
+            }
+            db.mark_finished(&completed).await?;
+
+            db.stats().await
+        })
+        .unwrap();
+
+        assert_that(&stats.decline_reasons).is_equal_to(vec![
+            (Some(SwapDeclineReason::UnsatisfactoryRate), 2),
+            (None, 1),
+        ]);
+        let completed_swaps: u64 = stats.swaps_per_day.iter().map(|(_, swaps)| swaps).sum();
+        assert_that(&completed_swaps).is_equal_to(1);
+        assert_that(&stats.average_time_to_redeem).is_none();
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
 use crate::{
     db::{custom_sql_types::Text, schema, Error, Sqlite},
     diesel::{ExpressionMethods, OptionalExtension, QueryDsl},
@@ -31,37 +33,62 @@ impl Swap {
     }
 }
 
-#[async_trait]
-impl Retrieve for Sqlite {
-    async fn get(&self, key: &SwapId) -> anyhow::Result<Swap> {
-        use self::schema::rfc003_swaps::dsl::*;
+macro_rules! impl_retrieve {
+    ($database:ident) => {
+        #[async_trait]
+        impl Retrieve for $database {
+            async fn get(&self, key: &SwapId) -> anyhow::Result<Swap> {
+                use self::schema::{rfc003_finished_swaps as finished_swaps, rfc003_swaps::dsl::*};
 
-        let record: QueryableSwap = self
-            .do_in_transaction(|connection| {
-                let key = Text(key);
+                let record: QueryableSwap = self
+                    .do_in_transaction(|connection| {
+                        let key = Text(key);
 
-                rfc003_swaps
-                    .filter(swap_id.eq(key))
-                    .first(&*connection)
-                    .optional()
-            })
-            .await?
-            .ok_or(Error::SwapNotFound)?;
+                        rfc003_swaps
+                            .filter(swap_id.eq(key))
+                            .filter(
+                                swap_id.ne_all(
+                                    finished_swaps::table
+                                        .filter(finished_swaps::archived_at.is_not_null())
+                                        .select(finished_swaps::swap_id),
+                                ),
+                            )
+                            .first(&*connection)
+                            .optional()
+                    })
+                    .await?
+                    .ok_or(Error::SwapNotFound)?;
 
-        Ok(Swap::from(record))
-    }
+                Ok(Swap::from(record))
+            }
 
-    async fn all(&self) -> anyhow::Result<Vec<Swap>> {
-        use self::schema::rfc003_swaps::dsl::*;
+            async fn all(&self) -> anyhow::Result<Vec<Swap>> {
+                use self::schema::{rfc003_finished_swaps as finished_swaps, rfc003_swaps::dsl::*};
 
-        let records: Vec<QueryableSwap> = self
-            .do_in_transaction(|connection| rfc003_swaps.load(&*connection))
-            .await?;
+                let records: Vec<QueryableSwap> = self
+                    .do_in_transaction(|connection| {
+                        rfc003_swaps
+                            .filter(
+                                swap_id.ne_all(
+                                    finished_swaps::table
+                                        .filter(finished_swaps::archived_at.is_not_null())
+                                        .select(finished_swaps::swap_id),
+                                ),
+                            )
+                            .load(&*connection)
+                    })
+                    .await?;
 
-        Ok(records.into_iter().map(|q| q.into()).collect())
-    }
+                Ok(records.into_iter().map(|q| q.into()).collect())
+            }
+        }
+    };
 }
 
+impl_retrieve!(Sqlite);
+#[cfg(feature = "postgres")]
+impl_retrieve!(Postgres);
+
 #[derive(Queryable, Debug, Clone, PartialEq)]
 struct QueryableSwap {
     pub id: i32,
@@ -0,0 +1,131 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, swap_failures},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl},
+    swap_protocols::{
+        rfc003::{self, FailureCategory},
+        SwapId,
+    },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+/// Why a swap stopped executing, kept around so that failures can still be
+/// investigated after a restart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapFailure {
+    pub category: FailureCategory,
+    pub message: String,
+    pub failed_at: NaiveDateTime,
+}
+
+#[async_trait]
+pub trait SwapFailures: Send + Sync + 'static {
+    /// Record that the swap failed, replacing any earlier failure of it.
+    async fn record_swap_failure(
+        &self,
+        swap_id: &SwapId,
+        error: &rfc003::Error,
+    ) -> anyhow::Result<()>;
+
+    async fn swap_failure(&self, swap_id: &SwapId) -> anyhow::Result<Option<SwapFailure>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "swap_failures"]
+struct InsertableSwapFailure {
+    swap_id: Text<SwapId>,
+    category: Text<FailureCategory>,
+    message: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableSwapFailure {
+    category: Text<FailureCategory>,
+    message: String,
+    failed_at: NaiveDateTime,
+}
+
+#[async_trait]
+impl SwapFailures for Sqlite {
+    async fn record_swap_failure(
+        &self,
+        swap_id: &SwapId,
+        error: &rfc003::Error,
+    ) -> anyhow::Result<()> {
+        let record = InsertableSwapFailure {
+            swap_id: Text(*swap_id),
+            category: Text(error.category()),
+            message: error.to_string(),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::replace_into(swap_failures::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn swap_failure(&self, swap_id: &SwapId) -> anyhow::Result<Option<SwapFailure>> {
+        use self::schema::swap_failures as failures;
+
+        let record: Option<QueryableSwapFailure> = self
+            .do_in_transaction(|connection| {
+                failures::table
+                    .filter(failures::swap_id.eq(Text(*swap_id)))
+                    .select((failures::category, failures::message, failures::failed_at))
+                    .first(connection)
+                    .optional()
+            })
+            .await?;
+
+        Ok(record.map(|record| SwapFailure {
+            category: *record.category,
+            message: record.message,
+            failed_at: record.failed_at,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn latest_failure_of_a_swap_is_kept() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+
+        let failure = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.record_swap_failure(&swap_id, &rfc003::Error::TimerError)
+                .await?;
+            db.record_swap_failure(&swap_id, &rfc003::Error::Btsieve)
+                .await?;
+
+            db.swap_failure(&swap_id).await
+        })
+        .unwrap()
+        .map(|failure| (failure.category, failure.message));
+
+        assert_that(&failure)
+            .is_some()
+            .is_equal_to((FailureCategory::Blockchain, "btsieve".to_string()));
+    }
+
+    #[test]
+    fn swaps_without_failure_have_none() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+
+        let failure = crate::executor::block_on(db.swap_failure(&SwapId::default())).unwrap();
+
+        assert_that(&failure).is_none();
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, swap_fees},
+        LedgerKind, Sqlite,
+    },
+    diesel::{ExpressionMethods, QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+/// The on-chain fee one of our transactions of a swap paid, in the smallest
+/// unit of the ledger's native asset (satoshi, wei).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaidFee {
+    pub ledger: LedgerKind,
+    pub transaction_id: String,
+    pub amount: u128,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFee {
+    pub swap_id: SwapId,
+    pub fee: PaidFee,
+    pub recorded_at: NaiveDateTime,
+}
+
+#[async_trait]
+pub trait SwapFees: Send + Sync + 'static {
+    /// Record the fees paid for a swap, fees of transactions that were already
+    /// recorded are ignored.
+    async fn record_swap_fees(&self, swap_id: &SwapId, fees: &[PaidFee]) -> anyhow::Result<()>;
+
+    /// Return all fees recorded in the given period, oldest first.
+    async fn swap_fees(
+        &self,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> anyhow::Result<Vec<RecordedFee>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "swap_fees"]
+struct InsertableSwapFee {
+    swap_id: Text<SwapId>,
+    ledger: Text<LedgerKind>,
+    transaction_id: String,
+    amount: Text<u128>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableSwapFee {
+    swap_id: Text<SwapId>,
+    ledger: Text<LedgerKind>,
+    transaction_id: String,
+    amount: Text<u128>,
+    recorded_at: NaiveDateTime,
+}
+
+#[async_trait]
+impl SwapFees for Sqlite {
+    async fn record_swap_fees(&self, swap_id: &SwapId, fees: &[PaidFee]) -> anyhow::Result<()> {
+        let records = fees
+            .iter()
+            .map(|fee| InsertableSwapFee {
+                swap_id: Text(*swap_id),
+                ledger: Text(fee.ledger),
+                transaction_id: fee.transaction_id.clone(),
+                amount: Text(fee.amount),
+            })
+            .collect::<Vec<_>>();
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_or_ignore_into(swap_fees::table)
+                .values(&records)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn swap_fees(
+        &self,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> anyhow::Result<Vec<RecordedFee>> {
+        use self::schema::swap_fees as fees;
+
+        let records: Vec<QueryableSwapFee> = self
+            .do_in_transaction(|connection| {
+                let mut query = fees::table
+                    .select((
+                        fees::swap_id,
+                        fees::ledger,
+                        fees::transaction_id,
+                        fees::amount,
+                        fees::recorded_at,
+                    ))
+                    .order(fees::recorded_at.asc())
+                    .into_boxed();
+
+                if let Some(from) = from {
+                    query = query.filter(fees::recorded_at.ge(from));
+                }
+                if let Some(to) = to {
+                    query = query.filter(fees::recorded_at.lt(to));
+                }
+
+                query.load(connection)
+            })
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| RecordedFee {
+                swap_id: *record.swap_id,
+                fee: PaidFee {
+                    ledger: *record.ledger,
+                    transaction_id: record.transaction_id,
+                    amount: *record.amount,
+                },
+                recorded_at: record.recorded_at,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn fees_of_a_transaction_are_only_recorded_once() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let fee = PaidFee {
+            ledger: LedgerKind::Bitcoin,
+            transaction_id: "b6e0ebbd2a3e5ebe57a4fcbd4c3fa87a5de0fb8d1f5e8ac1c6bd4f78b8a5ba4e"
+                .to_string(),
+            amount: 1_000,
+        };
+
+        let fees = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.record_swap_fees(&swap_id, &[fee.clone()]).await?;
+            db.record_swap_fees(&swap_id, &[fee.clone()]).await?;
+
+            db.swap_fees(None, None).await
+        })
+        .unwrap()
+        .into_iter()
+        .map(|recorded| (recorded.swap_id, recorded.fee))
+        .collect::<Vec<_>>();
+
+        assert_that(&fees).is_equal_to(vec![(swap_id, fee)]);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    db::{
+        custom_sql_types::{Text, U32},
+        schema::{self, swap_messages},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use libp2p_comit::Frame;
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Frames are cut off after this many bytes when they are recorded, such that
+/// a peer cannot fill the database with a few large requests.
+pub const MAX_RECORDED_FRAME_SIZE: usize = 64 * 1024;
+
+/// Whether we received the message from the counterparty or sent it.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Serialize, strum_macros::Display, strum_macros::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// A request or response of a swap as it was serialized on the wire.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapMessage {
+    pub direction: Direction,
+    /// The serialized frame, cut off after `MAX_RECORDED_FRAME_SIZE` bytes.
+    pub frame: String,
+    /// The size of the whole serialized frame in bytes.
+    pub size: u32,
+    pub recorded_at: NaiveDateTime,
+}
+
+impl SwapMessage {
+    pub fn is_truncated(&self) -> bool {
+        self.frame.len() < self.size as usize
+    }
+}
+
+/// Keep the requests and responses exchanged for a swap, for disputes with the
+/// counterparty and debugging.
+#[async_trait]
+pub trait SwapMessages: Send + Sync + 'static {
+    async fn record_message(
+        &self,
+        swap_id: &SwapId,
+        direction: Direction,
+        frame: &Frame,
+    ) -> anyhow::Result<()>;
+
+    /// Return all messages of the swap, oldest first.
+    async fn swap_messages(&self, swap_id: &SwapId) -> anyhow::Result<Vec<SwapMessage>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "swap_messages"]
+struct InsertableSwapMessage {
+    swap_id: Text<SwapId>,
+    direction: Text<Direction>,
+    frame: String,
+    size: U32,
+}
+
+#[derive(Queryable, Debug, Clone)]
+struct QueryableSwapMessage {
+    direction: Text<Direction>,
+    frame: String,
+    size: U32,
+    recorded_at: NaiveDateTime,
+}
+
+#[async_trait]
+impl SwapMessages for Sqlite {
+    async fn record_message(
+        &self,
+        swap_id: &SwapId,
+        direction: Direction,
+        frame: &Frame,
+    ) -> anyhow::Result<()> {
+        let mut frame = serde_json::to_string(frame)?;
+        let size = u32::try_from(frame.len())?;
+        truncate(&mut frame, MAX_RECORDED_FRAME_SIZE);
+
+        let record = InsertableSwapMessage {
+            swap_id: Text(*swap_id),
+            direction: Text(direction),
+            frame,
+            size: U32(size),
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::insert_into(swap_messages::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn swap_messages(&self, swap_id: &SwapId) -> anyhow::Result<Vec<SwapMessage>> {
+        use self::schema::swap_messages as messages;
+
+        let records: Vec<QueryableSwapMessage> = self
+            .do_in_transaction(|connection| {
+                messages::table
+                    .filter(messages::swap_id.eq(Text(*swap_id)))
+                    .order(messages::id.asc())
+                    .select((
+                        messages::direction,
+                        messages::frame,
+                        messages::size,
+                        messages::recorded_at,
+                    ))
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| SwapMessage {
+                direction: *record.direction,
+                frame: record.frame,
+                size: record.size.into(),
+                recorded_at: record.recorded_at,
+            })
+            .collect())
+    }
+}
+
+/// Cut `frame` off after at most `max` bytes without splitting a character.
+fn truncate(frame: &mut String, max: usize) {
+    if frame.len() <= max {
+        return;
+    }
+
+    let mut end = max;
+    while !frame.is_char_boundary(end) {
+        end -= 1;
+    }
+    frame.truncate(end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p_comit::FrameType;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn messages_are_returned_in_the_order_they_were_recorded() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let request = Frame::new(FrameType::Request, serde_json::json!({ "type": "SWAP" }));
+        let response = Frame::new(
+            FrameType::Response,
+            serde_json::json!({ "body": "x".repeat(MAX_RECORDED_FRAME_SIZE) }),
+        );
+
+        let messages = crate::executor::block_on::<_, anyhow::Result<_>>(async {
+            db.record_message(&swap_id, Direction::Outbound, &request)
+                .await?;
+            db.record_message(&swap_id, Direction::Inbound, &response)
+                .await?;
+            db.record_message(&SwapId::default(), Direction::Outbound, &request)
+                .await?;
+
+            db.swap_messages(&swap_id).await
+        })
+        .unwrap();
+
+        assert_that(&messages).has_length(2);
+        assert_that(&messages[0].direction).is_equal_to(Direction::Outbound);
+        assert_that(&messages[0].frame.as_str())
+            .is_equal_to(r#"{"type":"REQUEST","payload":{"type":"SWAP"}}"#);
+        assert_that(&messages[0].is_truncated()).is_false();
+        assert_that(&messages[1].direction).is_equal_to(Direction::Inbound);
+        assert_that(&messages[1].frame.len()).is_equal_to(MAX_RECORDED_FRAME_SIZE);
+        assert_that(&messages[1].is_truncated()).is_true();
+    }
+
+    #[test]
+    fn frames_are_not_truncated_within_a_character() {
+        let mut frame = String::from("aä");
+
+        truncate(&mut frame, 2);
+
+        assert_that(&frame.as_str()).is_equal_to("a");
+    }
+}
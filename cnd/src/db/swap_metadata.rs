@@ -0,0 +1,135 @@
+use crate::{
+    db::{
+        custom_sql_types::Text,
+        schema::{self, swap_metadata},
+        Sqlite,
+    },
+    diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl},
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+
+/// What a client attached to a swap to correlate it with its own records,
+/// cnd does not interpret either field.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapMetadata {
+    pub external_id: Option<String>,
+    pub note: Option<String>,
+}
+
+#[async_trait]
+pub trait MetadataStore: Send + Sync + 'static {
+    /// Replaces the metadata that was saved for the swap before.
+    async fn save_metadata(&self, swap_id: &SwapId, metadata: SwapMetadata) -> anyhow::Result<()>;
+
+    /// Empty for swaps that nothing was attached to.
+    async fn swap_metadata(&self, swap_id: &SwapId) -> anyhow::Result<SwapMetadata>;
+
+    async fn swaps_with_external_id(&self, external_id: &str) -> anyhow::Result<Vec<SwapId>>;
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "swap_metadata"]
+struct InsertableSwapMetadata {
+    swap_id: Text<SwapId>,
+    external_id: Option<String>,
+    note: Option<String>,
+}
+
+#[async_trait]
+impl MetadataStore for Sqlite {
+    async fn save_metadata(&self, swap_id: &SwapId, metadata: SwapMetadata) -> anyhow::Result<()> {
+        let record = InsertableSwapMetadata {
+            swap_id: Text(*swap_id),
+            external_id: metadata.external_id,
+            note: metadata.note,
+        };
+
+        self.do_in_transaction(|connection| {
+            diesel::replace_into(swap_metadata::table)
+                .values(&record)
+                .execute(connection)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn swap_metadata(&self, swap_id: &SwapId) -> anyhow::Result<SwapMetadata> {
+        use self::schema::swap_metadata as metadata;
+
+        let record: Option<(Option<String>, Option<String>)> = self
+            .do_in_transaction(|connection| {
+                metadata::table
+                    .filter(metadata::swap_id.eq(Text(*swap_id)))
+                    .select((metadata::external_id, metadata::note))
+                    .first(connection)
+                    .optional()
+            })
+            .await?;
+
+        Ok(record
+            .map(|(external_id, note)| SwapMetadata { external_id, note })
+            .unwrap_or_default())
+    }
+
+    async fn swaps_with_external_id(&self, external_id: &str) -> anyhow::Result<Vec<SwapId>> {
+        use self::schema::swap_metadata as metadata;
+
+        let records: Vec<Text<SwapId>> = self
+            .do_in_transaction(|connection| {
+                metadata::table
+                    .filter(metadata::external_id.eq(external_id))
+                    .select(metadata::swap_id)
+                    .load(connection)
+            })
+            .await?;
+
+        Ok(records.into_iter().map(|Text(swap_id)| swap_id).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn saved_metadata_replaces_the_previous_one_and_is_found_by_external_id() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let swap_id = SwapId::default();
+        let other_swap_id = SwapId::default();
+        let metadata = SwapMetadata {
+            external_id: Some(String::from("order-42")),
+            note: Some(String::from("first fill")),
+        };
+
+        let (before, saved, with_external_id) =
+            crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                let before = db.swap_metadata(&swap_id).await?;
+
+                db.save_metadata(&swap_id, SwapMetadata {
+                    external_id: Some(String::from("order-41")),
+                    note: None,
+                })
+                .await?;
+                db.save_metadata(&swap_id, metadata.clone()).await?;
+                db.save_metadata(&other_swap_id, SwapMetadata {
+                    external_id: Some(String::from("order-43")),
+                    note: None,
+                })
+                .await?;
+
+                let saved = db.swap_metadata(&swap_id).await?;
+                let with_external_id = db.swaps_with_external_id("order-42").await?;
+
+                Ok((before, saved, with_external_id))
+            })
+            .unwrap();
+
+        assert_that(&before).is_equal_to(SwapMetadata::default());
+        assert_that(&saved).is_equal_to(metadata);
+        assert_that(&with_external_id).is_equal_to(vec![swap_id]);
+    }
+}
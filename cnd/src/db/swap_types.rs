@@ -1,5 +1,7 @@
+#[cfg(feature = "postgres")]
+use crate::db::Postgres;
 use crate::{
-    db::{custom_sql_types::Text, schema, Sqlite},
+    db::{custom_sql_types::Text, schema, Error, Sqlite},
     diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl},
     swap_protocols::{asset, ledger, Role, SwapId},
 };
@@ -15,67 +17,6 @@ pub trait DetermineTypes: Send + Sync + 'static {
     async fn determine_types(&self, key: &SwapId) -> anyhow::Result<SwapTypes>;
 }
 
-#[async_trait]
-impl DetermineTypes for Sqlite {
-    async fn determine_types(&self, key: &SwapId) -> anyhow::Result<SwapTypes> {
-        let role = self.role(key).await?;
-
-        if self
-            .rfc003_bitcoin_ethereum_bitcoin_ether_request_messages_has_swap(key)
-            .await?
-        {
-            return Ok(SwapTypes {
-                alpha_ledger: LedgerKind::Bitcoin,
-                beta_ledger: LedgerKind::Ethereum,
-                alpha_asset: AssetKind::Bitcoin,
-                beta_asset: AssetKind::Ether,
-                role,
-            });
-        }
-
-        if self
-            .rfc003_ethereum_bitcoin_ether_bitcoin_request_messages_has_swap(key)
-            .await?
-        {
-            return Ok(SwapTypes {
-                alpha_ledger: LedgerKind::Ethereum,
-                beta_ledger: LedgerKind::Bitcoin,
-                alpha_asset: AssetKind::Ether,
-                beta_asset: AssetKind::Bitcoin,
-                role,
-            });
-        }
-
-        if self
-            .rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages_has_swap(key)
-            .await?
-        {
-            return Ok(SwapTypes {
-                alpha_ledger: LedgerKind::Bitcoin,
-                beta_ledger: LedgerKind::Ethereum,
-                alpha_asset: AssetKind::Bitcoin,
-                beta_asset: AssetKind::Erc20,
-                role,
-            });
-        }
-
-        if self
-            .rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages_has_swap(key)
-            .await?
-        {
-            return Ok(SwapTypes {
-                alpha_ledger: LedgerKind::Ethereum,
-                beta_ledger: LedgerKind::Bitcoin,
-                alpha_asset: AssetKind::Erc20,
-                beta_asset: AssetKind::Bitcoin,
-                role,
-            });
-        }
-
-        unreachable!("we got role for swap so the swap_id must exist")
-    }
-}
-
 macro_rules! impl_has_swap {
     ($table:ident) => {
         paste::item! {
@@ -98,18 +39,114 @@ macro_rules! impl_has_swap {
     };
 }
 
-impl Sqlite {
-    impl_has_swap!(rfc003_bitcoin_ethereum_bitcoin_ether_request_messages);
-    impl_has_swap!(rfc003_ethereum_bitcoin_ether_bitcoin_request_messages);
-    impl_has_swap!(rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages);
-    impl_has_swap!(rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages);
+macro_rules! impl_determine_types {
+    ($database:ident) => {
+        // False positive on the code generated by `async_trait`
+        #[allow(clippy::suspicious_else_formatting)]
+        #[async_trait]
+        impl DetermineTypes for $database {
+            async fn determine_types(&self, key: &SwapId) -> anyhow::Result<SwapTypes> {
+                let role = self.role(key).await?;
+
+                if self
+                    .rfc003_bitcoin_ethereum_bitcoin_ether_request_messages_has_swap(key)
+                    .await?
+                {
+                    return Ok(SwapTypes {
+                        alpha_ledger: LedgerKind::Bitcoin,
+                        beta_ledger: LedgerKind::Ethereum,
+                        alpha_asset: AssetKind::Bitcoin,
+                        beta_asset: AssetKind::Ether,
+                        role,
+                    });
+                }
+
+                if self
+                    .rfc003_ethereum_bitcoin_ether_bitcoin_request_messages_has_swap(key)
+                    .await?
+                {
+                    return Ok(SwapTypes {
+                        alpha_ledger: LedgerKind::Ethereum,
+                        beta_ledger: LedgerKind::Bitcoin,
+                        alpha_asset: AssetKind::Ether,
+                        beta_asset: AssetKind::Bitcoin,
+                        role,
+                    });
+                }
+
+                if self
+                    .rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages_has_swap(key)
+                    .await?
+                {
+                    return Ok(SwapTypes {
+                        alpha_ledger: LedgerKind::Bitcoin,
+                        beta_ledger: LedgerKind::Ethereum,
+                        alpha_asset: AssetKind::Bitcoin,
+                        beta_asset: AssetKind::Erc20,
+                        role,
+                    });
+                }
+
+                if self
+                    .rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages_has_swap(key)
+                    .await?
+                {
+                    return Ok(SwapTypes {
+                        alpha_ledger: LedgerKind::Ethereum,
+                        beta_ledger: LedgerKind::Bitcoin,
+                        alpha_asset: AssetKind::Erc20,
+                        beta_asset: AssetKind::Bitcoin,
+                        role,
+                    });
+                }
+
+                unreachable!("we got role for swap so the swap_id must exist")
+            }
+        }
+
+        impl $database {
+            async fn role(&self, key: &SwapId) -> anyhow::Result<Role> {
+                use self::schema::rfc003_swaps as swaps;
+
+                let record: QueryableSwapRole = self
+                    .do_in_transaction(|connection| {
+                        let key = Text(key);
+
+                        swaps::table
+                            .filter(swaps::swap_id.eq(key))
+                            .select((swaps::swap_id, swaps::role))
+                            .first(connection)
+                            .optional()
+                    })
+                    .await?
+                    .ok_or(Error::SwapNotFound)?;
+
+                Ok(*record.role)
+            }
+
+            impl_has_swap!(rfc003_bitcoin_ethereum_bitcoin_ether_request_messages);
+            impl_has_swap!(rfc003_ethereum_bitcoin_ether_bitcoin_request_messages);
+            impl_has_swap!(rfc003_bitcoin_ethereum_bitcoin_erc20_request_messages);
+            impl_has_swap!(rfc003_ethereum_bitcoin_erc20_bitcoin_request_messages);
+        }
+    };
 }
 
+impl_determine_types!(Sqlite);
+#[cfg(feature = "postgres")]
+impl_determine_types!(Postgres);
+
 #[derive(Queryable, Debug, Clone, PartialEq)]
 struct QueryableSwap {
     swap_id: Text<SwapId>,
 }
 
+#[derive(Queryable, Debug, Clone, PartialEq)]
+struct QueryableSwapRole {
+    swap_id: Text<SwapId>,
+    role: Text<Role>,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SwapTypes {
     pub alpha_ledger: LedgerKind,
@@ -119,7 +156,7 @@ pub struct SwapTypes {
     pub role: Role,
 }
 
-#[derive(Debug, Clone, Copy, Display, EnumString, PartialEq)]
+#[derive(Debug, Clone, Copy, Display, EnumString, PartialEq, Eq, Hash)]
 pub enum LedgerKind {
     Bitcoin,
     Ethereum,
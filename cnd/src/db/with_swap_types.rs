@@ -50,7 +50,7 @@ macro_rules! with_swap_types {
                 #[allow(dead_code)]
                 type BA = EtherQuantity;
                 #[allow(dead_code)]
-                type AcceptBody = crate::http_api::routes::rfc003::accept::OnlyRefund<BL>;
+                type AcceptBody = crate::http_api::routes::rfc003::accept::AcceptIdentities<AL, BL>;
 
                 _match_role!(role, $fn)
             }
@@ -70,7 +70,7 @@ macro_rules! with_swap_types {
                 #[allow(dead_code)]
                 type BA = Erc20Token;
                 #[allow(dead_code)]
-                type AcceptBody = crate::http_api::routes::rfc003::accept::OnlyRefund<BL>;
+                type AcceptBody = crate::http_api::routes::rfc003::accept::AcceptIdentities<AL, BL>;
 
                 _match_role!(role, $fn)
             }
@@ -90,7 +90,7 @@ macro_rules! with_swap_types {
                 #[allow(dead_code)]
                 type BA = Amount;
                 #[allow(dead_code)]
-                type AcceptBody = crate::http_api::routes::rfc003::accept::OnlyRedeem<AL>;
+                type AcceptBody = crate::http_api::routes::rfc003::accept::AcceptIdentities<AL, BL>;
 
                 _match_role!(role, $fn)
             }
@@ -110,7 +110,7 @@ macro_rules! with_swap_types {
                 #[allow(dead_code)]
                 type BA = Amount;
                 #[allow(dead_code)]
-                type AcceptBody = crate::http_api::routes::rfc003::accept::OnlyRedeem<AL>;
+                type AcceptBody = crate::http_api::routes::rfc003::accept::AcceptIdentities<AL, BL>;
 
                 _match_role!(role, $fn)
             }
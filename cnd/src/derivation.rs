@@ -0,0 +1,154 @@
+use crate::{
+    config,
+    seed::Seed,
+    swap_protocols::{Role, SwapId},
+};
+use anyhow::Context;
+use bitcoin::{
+    secp256k1::SecretKey,
+    util::bip32::{ChildNumber, DerivationPath, ExtendedPrivKey},
+};
+use crypto::{digest::Digest, sha2::Sha256};
+use std::{fmt, str::FromStr};
+
+/// The keys a swap uses to redeem and refund its HTLCs.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Identities {
+    pub redeem: SecretKey,
+    pub refund: SecretKey,
+}
+
+// Does not print the keys.
+impl fmt::Debug for Identities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Identities([*****])")
+    }
+}
+
+/// Derives the identities of swaps from the BIP32 tree of the seed at
+/// `<account_path>/<branch of the role>/<swap index>/<0 for redeem, 1 for
+/// refund>`.
+///
+/// The swap index is the first four bytes of the SHA256 of the swap id,
+/// read as big endian and with the hardened bit cleared.
+#[derive(Clone, Copy)]
+pub struct Derivation {
+    account: ExtendedPrivKey,
+    alice_branch: ChildNumber,
+    bob_branch: ChildNumber,
+}
+
+// Does not print the account key.
+impl fmt::Debug for Derivation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Derivation")
+            .field("alice_branch", &self.alice_branch)
+            .field("bob_branch", &self.bob_branch)
+            .finish()
+    }
+}
+
+impl Derivation {
+    pub fn new(
+        seed: &Seed,
+        network: bitcoin::Network,
+        config: &config::Derivation,
+    ) -> anyhow::Result<Self> {
+        let account_path = DerivationPath::from_str(&config.account_path)
+            .with_context(|| format!("invalid account path {}", config.account_path))?;
+        let account = ExtendedPrivKey::new_master(network, &seed.bytes())?
+            .derive_priv(&*crate::SECP, &account_path)?;
+
+        Ok(Self {
+            account,
+            alice_branch: ChildNumber::from_normal_idx(config.alice_branch)?,
+            bob_branch: ChildNumber::from_normal_idx(config.bob_branch)?,
+        })
+    }
+
+    pub fn identities(&self, id: SwapId, role: Role) -> Identities {
+        let swap = self.path(id, role);
+        let key = |leaf: u32| {
+            let path = [
+                swap[0],
+                swap[1],
+                ChildNumber::from_normal_idx(leaf).expect("leaf index is not hardened"),
+            ];
+
+            self.account
+                .derive_priv(&*crate::SECP, &path)
+                .expect("The probability of this happening is < 1 in 2^127")
+                .private_key
+                .key
+        };
+
+        Identities {
+            redeem: key(0),
+            refund: key(1),
+        }
+    }
+
+    /// The path of the swap relative to the account, the identities are its
+    /// children.
+    fn path(&self, id: SwapId, role: Role) -> [ChildNumber; 2] {
+        let branch = match role {
+            Role::Alice => self.alice_branch,
+            Role::Bob => self.bob_branch,
+        };
+
+        [branch, swap_index(id)]
+    }
+}
+
+fn swap_index(id: SwapId) -> ChildNumber {
+    let mut sha = Sha256::new();
+    sha.input(id.0.as_bytes());
+    let mut hash = [0u8; 32];
+    sha.result(&mut hash);
+
+    let index = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]) & 0x7fff_ffff;
+
+    ChildNumber::from_normal_idx(index).expect("hardened bit is cleared")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn derivation(alice_branch: u32, bob_branch: u32) -> Derivation {
+        let seed = Seed::from(*b"hello world, you are beautiful!!");
+        let config = config::Derivation {
+            account_path: String::from("m/84'/0'/0'"),
+            alice_branch,
+            bob_branch,
+        };
+
+        Derivation::new(&seed, bitcoin::Network::Bitcoin, &config).unwrap()
+    }
+
+    #[test]
+    fn roles_derive_from_their_own_branch() {
+        let id = SwapId::default();
+
+        let alice = derivation(2, 3).identities(id, Role::Alice);
+        let bob = derivation(2, 3).identities(id, Role::Bob);
+        let bob_on_alice_branch = derivation(3, 2).identities(id, Role::Alice);
+
+        assert_that(&alice).is_not_equal_to(bob);
+        assert_that(&alice.redeem).is_not_equal_to(alice.refund);
+        assert_that(&bob_on_alice_branch).is_equal_to(bob);
+    }
+
+    #[test]
+    fn account_path_must_be_valid() {
+        let seed = Seed::from(*b"hello world, you are beautiful!!");
+        let config = config::Derivation {
+            account_path: String::from("84'/0'/0'"),
+            alice_branch: 0,
+            bob_branch: 1,
+        };
+
+        assert_that(&Derivation::new(&seed, bitcoin::Network::Bitcoin, &config)).is_err();
+    }
+}
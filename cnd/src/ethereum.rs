@@ -2,7 +2,8 @@
 #![forbid(unsafe_code)]
 
 pub use self::{
-    contract_address::*, erc20_quantity::*, erc20_token::*, ether_quantity::*, u256_ext::*,
+    checksum_address::*, contract_address::*, erc20_quantity::*, erc20_token::*, ether_quantity::*,
+    gas_oracle::*, signed_transaction::*, u256_ext::*,
 };
 pub use ::web3::types::{
     Address, Block, BlockId, BlockNumber, Bytes, Log, Transaction, TransactionReceipt,
@@ -10,13 +11,17 @@ pub use ::web3::types::{
 };
 
 pub mod web3 {
-    pub use ::web3::{transports, Error, Web3};
+    pub use ::web3::{helpers, transports, BatchTransport, Error, RequestId, Transport, Web3};
+    pub use jsonrpc_core as rpc;
 }
 
+mod checksum_address;
 mod contract_address;
 mod erc20_quantity;
 mod erc20_token;
 mod ether_quantity;
+mod gas_oracle;
+mod signed_transaction;
 mod u256_ext;
 
 #[derive(Debug, PartialEq)]
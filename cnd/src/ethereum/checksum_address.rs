@@ -0,0 +1,113 @@
+use crate::ethereum::Address;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+use tiny_keccak::{Hasher, Keccak};
+
+/// An address that is written in the mixed-case checksum encoding of EIP-55,
+/// parsing fails if a single character was mistyped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChecksumAddress(pub Address);
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum InvalidChecksumAddress {
+    #[error("{0} is not a hex encoded address")]
+    NotAnAddress(String),
+    #[error("{0} does not match its checksum, expected {1}")]
+    ChecksumMismatch(String, String),
+}
+
+impl FromStr for ChecksumAddress {
+    type Err = InvalidChecksumAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim_start_matches("0x");
+        if hex.len() != 40 {
+            return Err(InvalidChecksumAddress::NotAnAddress(s.to_owned()));
+        }
+        let address = Address::from_str(hex)
+            .map_err(|_| InvalidChecksumAddress::NotAnAddress(s.to_owned()))?;
+
+        let checksummed = ChecksumAddress(address).to_string();
+        if checksummed[2..] != *hex {
+            return Err(InvalidChecksumAddress::ChecksumMismatch(
+                s.to_owned(),
+                checksummed,
+            ));
+        }
+
+        Ok(ChecksumAddress(address))
+    }
+}
+
+impl fmt::Display for ChecksumAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lowercase = format!("{:x}", self.0);
+
+        let mut hash = [0u8; 32];
+        let mut hasher = Keccak::v256();
+        hasher.update(lowercase.as_bytes());
+        hasher.finalize(&mut hash);
+
+        write!(f, "0x")?;
+        for (i, c) in lowercase.chars().enumerate() {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                write!(f, "{}", c.to_ascii_uppercase())?;
+            } else {
+                write!(f, "{}", c)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for ChecksumAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChecksumAddress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        ChecksumAddress::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn eip55_test_vectors_roundtrip() {
+        for s in &[
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address = ChecksumAddress::from_str(s).unwrap();
+
+            assert_that(&address.to_string().as_str()).is_equal_to(*s);
+        }
+    }
+
+    #[test]
+    fn mistyped_address_is_rejected() {
+        let address = ChecksumAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD");
+
+        assert_that(&address).is_err_containing(InvalidChecksumAddress::ChecksumMismatch(
+            String::from("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"),
+            String::from("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+        ));
+    }
+}
@@ -1,9 +1,11 @@
 use crate::ethereum::{
-    u256_ext::{FromDecimalStr, ToBigInt},
+    u256_ext::{FromBigUInt, FromDecimalStr, ToBigInt},
     U256,
 };
+use bigdecimal::BigDecimal;
+use num::BigInt;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct Erc20Quantity(pub U256);
@@ -12,6 +14,57 @@ impl Erc20Quantity {
     pub fn zero() -> Self {
         Self(U256::zero())
     }
+
+    /// Convert a quantity given in whole tokens, e.g. "1.5", into base units
+    /// of a token with the given number of decimals.
+    pub fn from_human_quantity(quantity: &str, decimals: u8) -> Result<Self, InvalidHumanQuantity> {
+        let tokens = BigDecimal::from_str(quantity)
+            .map_err(|_| InvalidHumanQuantity::NotANumber(quantity.to_owned()))?;
+
+        let scaled = tokens.with_scale(i64::from(decimals));
+        if scaled != tokens {
+            return Err(InvalidHumanQuantity::FractionalBaseUnits {
+                quantity: quantity.to_owned(),
+                decimals,
+            });
+        }
+
+        let (base_units, _) = scaled.as_bigint_and_exponent();
+        match base_units.to_biguint() {
+            Some(base_units) if base_units.bits() <= 256 => {
+                Ok(Erc20Quantity(U256::from_biguint(base_units)))
+            }
+            _ => Err(InvalidHumanQuantity::OutOfRange(quantity.to_owned())),
+        }
+    }
+
+    /// Whether this quantity looks like it was given in base units of a token
+    /// with the given number of decimals.
+    ///
+    /// Anything below a millionth or above a trillion tokens is likely off by
+    /// the token's decimals.
+    pub fn is_plausible(&self, decimals: u8) -> bool {
+        if self.0.is_zero() {
+            return true;
+        }
+
+        let base_units = self.0.to_bigint();
+        let one_token = num::pow(BigInt::from(10), usize::from(decimals));
+        let smallest = &one_token / BigInt::from(1_000_000);
+        let largest = &one_token * BigInt::from(1_000_000_000_000u64);
+
+        base_units >= smallest && base_units <= largest
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum InvalidHumanQuantity {
+    #[error("{0} is not a decimal number")]
+    NotANumber(String),
+    #[error("{quantity} has more than {decimals} decimal places which is less than the smallest unit of the token")]
+    FractionalBaseUnits { quantity: String, decimals: u8 },
+    #[error("{0} is out of range for an ERC20 quantity")]
+    OutOfRange(String),
 }
 
 impl fmt::Display for Erc20Quantity {
@@ -56,3 +109,41 @@ impl Serialize for Erc20Quantity {
         serializer.serialize_str(self.to_string().as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn human_quantity_is_converted_into_base_units() {
+        let quantity = Erc20Quantity::from_human_quantity("1.5", 18);
+
+        assert_that(&quantity).is_ok_containing(Erc20Quantity(
+            U256::from_decimal_str("1500000000000000000").unwrap(),
+        ));
+    }
+
+    #[test]
+    fn human_quantity_with_more_places_than_decimals_is_rejected() {
+        let quantity = Erc20Quantity::from_human_quantity("0.001", 2);
+
+        assert_that(&quantity).is_err_containing(InvalidHumanQuantity::FractionalBaseUnits {
+            quantity: "0.001".to_owned(),
+            decimals: 2,
+        });
+    }
+
+    #[test]
+    fn negative_human_quantity_is_rejected() {
+        let quantity = Erc20Quantity::from_human_quantity("-1", 18);
+
+        assert_that(&quantity).is_err_containing(InvalidHumanQuantity::OutOfRange("-1".to_owned()));
+    }
+
+    #[test]
+    fn quantity_given_in_whole_tokens_is_not_plausible() {
+        assert_that(&Erc20Quantity(U256::from(100)).is_plausible(18)).is_false();
+        assert_that(&Erc20Quantity(U256::from(100)).is_plausible(2)).is_true();
+    }
+}
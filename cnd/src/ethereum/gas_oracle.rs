@@ -0,0 +1,64 @@
+use crate::ethereum::U256;
+use async_trait::async_trait;
+use serde::Serialize;
+
+/// How the fee of an Ethereum transaction is to be specified.
+///
+/// Chains that activated EIP-1559 accept both kinds of transactions, but only
+/// type 2 transactions benefit from the base fee mechanism.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "transaction_type", rename_all = "lowercase")]
+pub enum GasPricing {
+    Legacy,
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+impl Default for GasPricing {
+    fn default() -> Self {
+        GasPricing::Legacy
+    }
+}
+
+impl GasPricing {
+    /// The max fee leaves room for the base fee to double before the
+    /// transaction becomes unattractive for inclusion.
+    pub fn eip1559(base_fee_per_gas: U256, max_priority_fee_per_gas: U256) -> Self {
+        GasPricing::Eip1559 {
+            max_fee_per_gas: base_fee_per_gas
+                .saturating_mul(U256::from(2))
+                .saturating_add(max_priority_fee_per_gas),
+            max_priority_fee_per_gas,
+        }
+    }
+}
+
+#[async_trait]
+pub trait GasOracle: Send + Sync + 'static {
+    /// Suggest fee parameters for a transaction that is sent right now.
+    async fn gas_pricing(&self) -> anyhow::Result<GasPricing>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_fee_covers_a_doubled_base_fee() {
+        let pricing = GasPricing::eip1559(U256::from(100), U256::from(2));
+
+        assert_eq!(pricing, GasPricing::Eip1559 {
+            max_fee_per_gas: U256::from(202),
+            max_priority_fee_per_gas: U256::from(2),
+        });
+    }
+
+    #[test]
+    fn legacy_pricing_only_serializes_the_transaction_type() {
+        let pricing = serde_json::to_string(&GasPricing::Legacy).unwrap();
+
+        assert_eq!(pricing, r#"{"transaction_type":"legacy"}"#);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::ethereum::{Address, U256};
+use rlp::{DecoderError, Rlp};
+
+const EIP2930_TRANSACTION_TYPE: u8 = 0x01;
+const EIP1559_TRANSACTION_TYPE: u8 = 0x02;
+
+/// The parts of a signed, RLP encoded transaction that determine what it does
+/// once it is mined.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedTransaction {
+    /// `None` for transactions that deploy a contract.
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub gas_limit: U256,
+    /// The gas price of legacy transactions.
+    pub max_fee_per_gas: U256,
+    /// `None` for legacy transactions that are not replay protected.
+    pub chain_id: Option<u64>,
+}
+
+impl SignedTransaction {
+    pub fn decode(raw: &[u8]) -> Result<Self, DecoderError> {
+        match raw.first() {
+            Some(&EIP1559_TRANSACTION_TYPE) => {
+                let rlp = Rlp::new(&raw[1..]);
+                expect_items(&rlp, 12)?;
+
+                Ok(SignedTransaction {
+                    chain_id: Some(rlp.val_at(0)?),
+                    max_fee_per_gas: rlp.val_at(3)?,
+                    gas_limit: rlp.val_at(4)?,
+                    to: recipient(&rlp, 5)?,
+                    value: rlp.val_at(6)?,
+                    data: rlp.val_at(7)?,
+                })
+            }
+            Some(&EIP2930_TRANSACTION_TYPE) => {
+                let rlp = Rlp::new(&raw[1..]);
+                expect_items(&rlp, 11)?;
+
+                Ok(SignedTransaction {
+                    chain_id: Some(rlp.val_at(0)?),
+                    max_fee_per_gas: rlp.val_at(2)?,
+                    gas_limit: rlp.val_at(3)?,
+                    to: recipient(&rlp, 4)?,
+                    value: rlp.val_at(5)?,
+                    data: rlp.val_at(6)?,
+                })
+            }
+            _ => {
+                let rlp = Rlp::new(raw);
+                expect_items(&rlp, 9)?;
+
+                // EIP-155 encodes the chain id into `v`, `v` is 27 or 28
+                // otherwise.
+                let v: u64 = rlp.val_at(6)?;
+                let chain_id = if v >= 35 { Some((v - 35) / 2) } else { None };
+
+                Ok(SignedTransaction {
+                    chain_id,
+                    max_fee_per_gas: rlp.val_at(1)?,
+                    gas_limit: rlp.val_at(2)?,
+                    to: recipient(&rlp, 3)?,
+                    value: rlp.val_at(4)?,
+                    data: rlp.val_at(5)?,
+                })
+            }
+        }
+    }
+
+    /// The most the transaction can cost in fees.
+    pub fn max_fee(&self) -> U256 {
+        self.gas_limit.saturating_mul(self.max_fee_per_gas)
+    }
+}
+
+fn expect_items(rlp: &Rlp<'_>, count: usize) -> Result<(), DecoderError> {
+    if rlp.item_count()? != count {
+        return Err(DecoderError::RlpIncorrectListLen);
+    }
+
+    Ok(())
+}
+
+fn recipient(rlp: &Rlp<'_>, index: usize) -> Result<Option<Address>, DecoderError> {
+    let to = rlp.at(index)?.data()?;
+
+    match to.len() {
+        0 => Ok(None),
+        20 => Ok(Some(Address::from_slice(to))),
+        _ => Err(DecoderError::RlpInvalidLength),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlp::RlpStream;
+    use std::str::FromStr;
+
+    #[test]
+    fn decodes_replay_protected_legacy_transactions() {
+        let to = Address::from_str("0A81e8be41b21f651a71aaB1A85c6813b8bBcCf8").unwrap();
+        let to_bytes: &[u8] = to.as_ref();
+        let data = vec![0x12u8, 0x34];
+
+        let mut stream = RlpStream::new_list(9);
+        stream
+            .append(&U256::from(7))
+            .append(&U256::from(20_000_000_000u64))
+            .append(&U256::from(100_000))
+            .append(&to_bytes)
+            .append(&U256::from(1_000))
+            .append(&data)
+            .append(&(17u64 * 2 + 35))
+            .append(&U256::from(1))
+            .append(&U256::from(2));
+
+        let transaction = SignedTransaction::decode(&stream.out()).unwrap();
+
+        assert_eq!(transaction, SignedTransaction {
+            to: Some(to),
+            value: U256::from(1_000),
+            data,
+            gas_limit: U256::from(100_000),
+            max_fee_per_gas: U256::from(20_000_000_000u64),
+            chain_id: Some(17),
+        });
+    }
+}
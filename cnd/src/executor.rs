@@ -0,0 +1,32 @@
+//! Helpers for running code that does not fit the tokio runtime cnd is
+//! driven by.
+
+use futures_core::channel::oneshot;
+use std::thread;
+
+/// Run the blocking function `f` on a thread of its own, such that it does
+/// not hold up the tasks of the executor the returned future is polled on.
+pub async fn spawn_blocking<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+
+    thread::spawn(move || {
+        let _ = sender.send(f());
+    });
+
+    receiver
+        .await
+        .expect("the blocking function does not panic")
+}
+
+/// Run `future` to completion on the current thread.
+#[cfg(test)]
+pub fn block_on<F, T>(future: F) -> T
+where
+    F: std::future::Future<Output = T>,
+{
+    futures_core::executor::block_on(future)
+}
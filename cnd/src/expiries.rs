@@ -0,0 +1,252 @@
+use crate::{
+    btsieve::{bitcoin::BitcoinConnector, ethereum::Web3Connector, BlockByHash, LatestBlock},
+    config,
+    timestamp::Timestamp,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp,
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_BITCOIN_CONFIRMATIONS: u32 = 6;
+pub const DEFAULT_ETHEREUM_CONFIRMATIONS: u32 = 30;
+pub const DEFAULT_SAFETY_MARGIN_MINUTES: u32 = 120;
+
+/// Number of recent blocks the block interval of a chain is averaged over.
+const SAMPLE_SIZE: u32 = 6;
+/// How long a measured block interval is used before it is measured again.
+const MEASUREMENT_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    Bitcoin,
+    Ethereum,
+}
+
+impl Chain {
+    /// Blocks are never assumed to come faster than the chain aims for, a
+    /// few quick blocks in a row must not shorten the expiries.
+    fn target_block_interval(self) -> Duration {
+        match self {
+            Chain::Bitcoin => Duration::from_secs(10 * 60),
+            Chain::Ethereum => Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChainConditions {
+    pub block_interval: Duration,
+    pub confirmations: u32,
+}
+
+impl ChainConditions {
+    /// How long it takes until a transaction that is broadcast now has the
+    /// required confirmations.
+    fn time_to_finality(self) -> Duration {
+        self.block_interval * self.confirmations
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct RecommendedExpiries {
+    pub alpha_expiry: Timestamp,
+    pub beta_expiry: Timestamp,
+}
+
+/// Before the beta HTLC expires, Alice funds the alpha HTLC, Bob funds the
+/// beta HTLC once the alpha HTLC is final and Alice redeems the beta HTLC once
+/// that one is final. Bob then has to redeem the alpha HTLC with the revealed
+/// secret before it expires.
+///
+/// Each of these steps gets `safety_margin` on top of the time it takes for
+/// its transaction to be final.
+pub fn recommend(
+    now: Timestamp,
+    alpha: ChainConditions,
+    beta: ChainConditions,
+    safety_margin: Duration,
+) -> RecommendedExpiries {
+    let step = |conditions: ChainConditions| seconds(conditions.time_to_finality() + safety_margin);
+
+    let beta_expiry = now.plus(step(alpha)).plus(step(beta)).plus(step(beta));
+    let alpha_expiry = beta_expiry.plus(step(alpha));
+
+    RecommendedExpiries {
+        alpha_expiry,
+        beta_expiry,
+    }
+}
+
+fn seconds(duration: Duration) -> u32 {
+    u32::try_from(duration.as_secs()).unwrap_or(std::u32::MAX)
+}
+
+/// Recommends expiries for swaps based on the block intervals the connected
+/// nodes currently see and the confirmations and safety margin configured in
+/// the `[expiries]` section.
+#[derive(Clone, Debug)]
+pub struct ExpiryCalculator {
+    bitcoin_connector: BitcoinConnector,
+    ethereum_connector: Web3Connector,
+    bitcoin_confirmations: u32,
+    ethereum_confirmations: u32,
+    safety_margin: Duration,
+    block_intervals: Arc<Mutex<HashMap<Chain, (Instant, Duration)>>>,
+}
+
+impl ExpiryCalculator {
+    pub fn new(
+        bitcoin_connector: BitcoinConnector,
+        ethereum_connector: Web3Connector,
+        config: Option<config::Expiries>,
+    ) -> Self {
+        Self {
+            bitcoin_connector,
+            ethereum_connector,
+            bitcoin_confirmations: config
+                .and_then(|config| config.bitcoin_confirmations)
+                .unwrap_or(DEFAULT_BITCOIN_CONFIRMATIONS),
+            ethereum_confirmations: config
+                .and_then(|config| config.ethereum_confirmations)
+                .unwrap_or(DEFAULT_ETHEREUM_CONFIRMATIONS),
+            safety_margin: Duration::from_secs(
+                60 * u64::from(
+                    config
+                        .and_then(|config| config.safety_margin_minutes)
+                        .unwrap_or(DEFAULT_SAFETY_MARGIN_MINUTES),
+                ),
+            ),
+            block_intervals: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn recommend(&self, alpha: Chain, beta: Chain) -> RecommendedExpiries {
+        let alpha = self.conditions(alpha).await;
+        let beta = self.conditions(beta).await;
+
+        recommend(Timestamp::now(), alpha, beta, self.safety_margin)
+    }
+
+    async fn conditions(&self, chain: Chain) -> ChainConditions {
+        ChainConditions {
+            block_interval: self.block_interval(chain).await,
+            confirmations: match chain {
+                Chain::Bitcoin => self.bitcoin_confirmations,
+                Chain::Ethereum => self.ethereum_confirmations,
+            },
+        }
+    }
+
+    /// Falls back to the target block interval if the recent blocks cannot be
+    /// fetched.
+    async fn block_interval(&self, chain: Chain) -> Duration {
+        let cached = self.block_intervals.lock().unwrap().get(&chain).copied();
+        if let Some((measured_at, block_interval)) = cached {
+            if measured_at.elapsed() < MEASUREMENT_MAX_AGE {
+                return block_interval;
+            }
+        }
+
+        let measured = match chain {
+            Chain::Bitcoin => self.measure_bitcoin_block_interval().await,
+            Chain::Ethereum => self.measure_ethereum_block_interval().await,
+        };
+        let block_interval = match measured {
+            Ok(measured) => cmp::max(measured, chain.target_block_interval()),
+            Err(e) => {
+                log::warn!(
+                    "failed to measure the block interval of {:?}: {:?}",
+                    chain,
+                    e
+                );
+                return chain.target_block_interval();
+            }
+        };
+
+        self.block_intervals
+            .lock()
+            .unwrap()
+            .insert(chain, (Instant::now(), block_interval));
+
+        block_interval
+    }
+
+    async fn measure_bitcoin_block_interval(&self) -> anyhow::Result<Duration> {
+        let mut connector = self.bitcoin_connector.clone();
+
+        let latest = connector.latest_block().await?.header;
+        let mut oldest = latest;
+        for _ in 0..SAMPLE_SIZE {
+            oldest = connector.block_by_hash(oldest.prev_blockhash).await?.header;
+        }
+
+        Ok(mean_block_interval(
+            u64::from(latest.time),
+            u64::from(oldest.time),
+        ))
+    }
+
+    async fn measure_ethereum_block_interval(&self) -> anyhow::Result<Duration> {
+        let mut connector = self.ethereum_connector.clone();
+
+        let latest = connector
+            .latest_block()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("the Ethereum node did not return the latest block"))?;
+        let mut parent_hash = latest.parent_hash;
+        let mut oldest_timestamp = latest.timestamp;
+        for _ in 0..SAMPLE_SIZE {
+            let block = connector
+                .block_by_hash(parent_hash)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("block {:?} does not exist", parent_hash))?;
+            parent_hash = block.parent_hash;
+            oldest_timestamp = block.timestamp;
+        }
+
+        Ok(mean_block_interval(
+            latest.timestamp.low_u64(),
+            oldest_timestamp.low_u64(),
+        ))
+    }
+}
+
+/// Block timestamps are not strictly increasing, in the rare case that the
+/// latest block claims to be older the interval comes out as zero.
+fn mean_block_interval(latest_timestamp: u64, oldest_timestamp: u64) -> Duration {
+    Duration::from_secs(latest_timestamp.saturating_sub(oldest_timestamp) / u64::from(SAMPLE_SIZE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn each_step_of_the_swap_gets_time_to_finality_and_safety_margin() {
+        let bitcoin = ChainConditions {
+            block_interval: Duration::from_secs(600),
+            confirmations: 6,
+        };
+        let ethereum = ChainConditions {
+            block_interval: Duration::from_secs(15),
+            confirmations: 30,
+        };
+        let now = Timestamp::from(1_000_000);
+
+        let expiries = recommend(now, bitcoin, ethereum, Duration::from_secs(3600));
+
+        // Funding the alpha HTLC takes 3600 + 3600 seconds, funding and
+        // redeeming the beta HTLC 450 + 3600 seconds each.
+        assert_that(&expiries).is_equal_to(RecommendedExpiries {
+            alpha_expiry: Timestamp::from(1_000_000 + 7200 + 4050 + 4050 + 7200),
+            beta_expiry: Timestamp::from(1_000_000 + 7200 + 4050 + 4050),
+        });
+    }
+}
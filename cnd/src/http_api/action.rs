@@ -1,10 +1,11 @@
 use crate::{
+    ethereum::GasPricing,
     http_api::{
         ethereum_network, problem, Http, MissingQueryParameters, UnexpectedQueryParameters,
     },
     swap_protocols::{
         actions::{
-            bitcoin::{SendToAddress, SpendOutput},
+            bitcoin::{BumpFee, CannotBumpFee, SendToAddress, SpendOutput},
             ethereum,
         },
         ledger, SwapId,
@@ -12,6 +13,11 @@ use crate::{
     timestamp::Timestamp,
 };
 use anyhow::Context;
+use bitcoin::{
+    blockdata::{opcodes, script::Builder},
+    util::psbt::PartiallySignedTransaction,
+    TxOut,
+};
 use blockchain_contracts::bitcoin::witness;
 use http_api_problem::HttpApiProblem;
 use serde::{Deserialize, Serialize};
@@ -26,16 +32,44 @@ pub trait ListRequiredFields {
     fn list_required_fields() -> Vec<siren::Field>;
 }
 
-#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[serde(untagged)]
 pub enum ActionExecutionParameters {
     BitcoinAddressAndFee {
         address: bitcoin::Address,
         fee_per_wu: String,
+        #[serde(default)]
+        format: BitcoinTransactionFormat,
+    },
+    /// Spend to the configured redeem destination.
+    BitcoinFee {
+        fee_per_wu: String,
+        #[serde(default)]
+        format: BitcoinTransactionFormat,
+    },
+    BitcoinFormat {
+        format: BitcoinTransactionFormat,
     },
     None {},
 }
 
+/// How Bitcoin actions are handed out.
+///
+/// `Psbt` returns a partially signed transaction (BIP 174) which allows
+/// hardware wallets and multi-sig setups to complete it externally.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BitcoinTransactionFormat {
+    Raw,
+    Psbt,
+}
+
+impl Default for BitcoinTransactionFormat {
+    fn default() -> Self {
+        BitcoinTransactionFormat::Raw
+    }
+}
+
 /// `network` field here for backward compatibility, to be removed with #1580
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -52,12 +86,18 @@ pub enum ActionResponseBody {
         #[serde(skip_serializing_if = "Option::is_none")]
         min_median_block_time: Option<Timestamp>,
     },
+    BitcoinPsbt {
+        psbt: Http<PartiallySignedTransaction>,
+        network: Http<bitcoin::Network>,
+    },
     EthereumDeployContract {
         data: crate::ethereum::Bytes,
         amount: crate::ethereum::EtherQuantity,
         gas_limit: crate::ethereum::U256,
         network: ethereum_network::Network,
         chain_id: ledger::ethereum::ChainId,
+        #[serde(flatten)]
+        gas_pricing: GasPricing,
     },
     EthereumCallContract {
         contract_address: crate::ethereum::Address,
@@ -68,6 +108,8 @@ pub enum ActionResponseBody {
         network: ethereum_network::Network,
         #[serde(skip_serializing_if = "Option::is_none")]
         min_block_timestamp: Option<Timestamp>,
+        #[serde(flatten)]
+        gas_pricing: GasPricing,
     },
     None,
 }
@@ -92,6 +134,127 @@ impl ActionResponseBody {
             min_median_block_time,
         }
     }
+
+    pub fn is_ethereum_transaction(&self) -> bool {
+        match self {
+            ActionResponseBody::EthereumDeployContract { .. }
+            | ActionResponseBody::EthereumCallContract { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Replace the fee parameters of Ethereum transactions, other payloads
+    /// are returned unchanged.
+    pub fn with_gas_pricing(self, new_gas_pricing: GasPricing) -> Self {
+        match self {
+            ActionResponseBody::EthereumDeployContract {
+                data,
+                amount,
+                gas_limit,
+                network,
+                chain_id,
+                ..
+            } => ActionResponseBody::EthereumDeployContract {
+                data,
+                amount,
+                gas_limit,
+                network,
+                chain_id,
+                gas_pricing: new_gas_pricing,
+            },
+            ActionResponseBody::EthereumCallContract {
+                contract_address,
+                data,
+                gas_limit,
+                chain_id,
+                network,
+                min_block_timestamp,
+                ..
+            } => ActionResponseBody::EthereumCallContract {
+                contract_address,
+                data,
+                gas_limit,
+                chain_id,
+                network,
+                min_block_timestamp,
+                gas_pricing: new_gas_pricing,
+            },
+            other => other,
+        }
+    }
+
+    /// Wrap `transaction` into a PSBT, signatures already present in the
+    /// transaction are kept as finalized inputs.
+    fn bitcoin_psbt(transaction: &bitcoin::Transaction, network: bitcoin::Network) -> Self {
+        let mut unsigned_transaction = transaction.clone();
+        for input in unsigned_transaction.input.iter_mut() {
+            input.script_sig = bitcoin::Script::new();
+            input.witness = Vec::new();
+        }
+
+        let mut psbt = PartiallySignedTransaction::from_unsigned_tx(unsigned_transaction)
+            .expect("transaction has no signatures");
+        for (psbt_input, input) in psbt.inputs.iter_mut().zip(&transaction.input) {
+            if !input.witness.is_empty() {
+                psbt_input.final_script_witness = Some(input.witness.clone());
+            }
+        }
+
+        ActionResponseBody::BitcoinPsbt {
+            psbt: Http(psbt),
+            network: Http(network),
+        }
+    }
+
+    /// The transaction of a Bitcoin payload if it is fully signed, i.e. can
+    /// be broadcast as is.
+    pub fn signed_bitcoin_transaction(&self) -> Option<bitcoin::Transaction> {
+        match self {
+            ActionResponseBody::BitcoinBroadcastSignedTransaction { hex, .. } => {
+                bitcoin::consensus::encode::deserialize(&hex::decode(hex).ok()?).ok()
+            }
+            ActionResponseBody::BitcoinPsbt { psbt, .. } => {
+                let mut transaction = psbt.0.global.unsigned_tx.clone();
+                for (input, psbt_input) in transaction.input.iter_mut().zip(&psbt.0.inputs) {
+                    input.witness = psbt_input.final_script_witness.clone()?;
+                }
+
+                Some(transaction)
+            }
+            _ => None,
+        }
+    }
+
+    /// Add an OP_RETURN output carrying `data` to unsigned Bitcoin
+    /// transactions, other payloads are returned unchanged.
+    ///
+    /// Transactions are only unsigned if handed out as PSBT, the wallet
+    /// constructs them otherwise.
+    pub fn with_op_return(self, data: &[u8]) -> Self {
+        match self {
+            ActionResponseBody::BitcoinPsbt { mut psbt, network } => {
+                let is_unsigned = psbt
+                    .0
+                    .inputs
+                    .iter()
+                    .all(|input| input.final_script_witness.is_none());
+
+                if is_unsigned {
+                    psbt.0.global.unsigned_tx.output.push(TxOut {
+                        value: 0,
+                        script_pubkey: Builder::new()
+                            .push_opcode(opcodes::all::OP_RETURN)
+                            .push_slice(data)
+                            .into_script(),
+                    });
+                    psbt.0.outputs.push(Default::default());
+                }
+
+                ActionResponseBody::BitcoinPsbt { psbt, network }
+            }
+            other => other,
+        }
+    }
 }
 
 pub trait IntoResponsePayload {
@@ -107,7 +270,16 @@ impl IntoResponsePayload for SendToAddress {
         query_params: ActionExecutionParameters,
     ) -> anyhow::Result<ActionResponseBody> {
         match query_params {
-            ActionExecutionParameters::None {} => Ok(self.into()),
+            ActionExecutionParameters::None {}
+            | ActionExecutionParameters::BitcoinFormat {
+                format: BitcoinTransactionFormat::Raw,
+            } => Ok(self.into()),
+            ActionExecutionParameters::BitcoinFormat {
+                format: BitcoinTransactionFormat::Psbt,
+            } => Ok(ActionResponseBody::bitcoin_psbt(
+                &self.unsigned_transaction(),
+                self.network,
+            )),
             _ => Err(anyhow::Error::from(UnexpectedQueryParameters {
                 action: "bitcoin::SendToAddress",
                 parameters: &["address", "fee_per_wu"],
@@ -146,41 +318,31 @@ impl IntoResponsePayload for SpendOutput {
             ActionExecutionParameters::BitcoinAddressAndFee {
                 address,
                 fee_per_wu,
+                format,
             } => {
                 let fee_per_wu = fee_per_wu.parse::<usize>().with_context(|| {
-                    HttpApiProblem::new("Invalid query parameter.")
+                    problem::Code::InvalidQueryParameter.problem("Invalid query parameter.")
                         .set_status(StatusCode::BAD_REQUEST)
                         .set_detail("Query parameter fee-per-byte is not a valid unsigned integer.")
                 })?;
 
                 let network = self.network;
-                let transaction =
-                    self.spend_to(address)
-                        .sign_with_rate(&*crate::SECP, fee_per_wu)
-                        .map_err(|e| {
-                            log::error!("Could not sign Bitcoin transaction: {:?}", e);
-                            match e {
-                                witness::Error::FeeHigherThanInputValue => HttpApiProblem::new(
-                                    "Fee is too high.",
-                                )
-                                .set_status(StatusCode::BAD_REQUEST)
-                                .set_detail(
-                                    "The Fee per byte/WU provided makes the total fee higher than the spendable input value.",
-                                ),
-                                witness::Error::OverflowingFee => HttpApiProblem::new(
-                                    "Fee is too high.",
-                                )
-                                    .set_status(StatusCode::BAD_REQUEST)
-                                    .set_detail(
-                                        "The Fee per byte/WU provided makes the total fee higher than the system supports.",
-                                    )
-                            }
-                        })?;
-
-                Ok(ActionResponseBody::bitcoin_broadcast_signed_transaction(
-                    &transaction,
-                    network,
-                ))
+                let transaction = self
+                    .spend_to(address)
+                    .sign_with_rate(&*crate::SECP, fee_per_wu)
+                    .map_err(signing_failed)?;
+
+                Ok(match format {
+                    BitcoinTransactionFormat::Raw => {
+                        ActionResponseBody::bitcoin_broadcast_signed_transaction(
+                            &transaction,
+                            network,
+                        )
+                    }
+                    BitcoinTransactionFormat::Psbt => {
+                        ActionResponseBody::bitcoin_psbt(&transaction, network)
+                    }
+                })
             }
             _ => Err(anyhow::Error::from(MissingQueryParameters {
                 action: "bitcoin::SpendOutput",
@@ -202,6 +364,24 @@ impl IntoResponsePayload for SpendOutput {
     }
 }
 
+fn signing_failed(e: witness::Error) -> HttpApiProblem {
+    log::error!("Could not sign Bitcoin transaction: {:?}", e);
+    match e {
+        witness::Error::FeeHigherThanInputValue => problem::Code::FeeTooHigh
+            .problem("Fee is too high.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(
+                "The Fee per byte/WU provided makes the total fee higher than the spendable input value.",
+            ),
+        witness::Error::OverflowingFee => problem::Code::FeeTooHigh
+            .problem("Fee is too high.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(
+                "The Fee per byte/WU provided makes the total fee higher than the system supports.",
+            ),
+    }
+}
+
 impl ListRequiredFields for SpendOutput {
     fn list_required_fields() -> Vec<siren::Field> {
         vec![
@@ -229,6 +409,148 @@ impl ListRequiredFields for SpendOutput {
     }
 }
 
+/// The child transaction of `action` is always handed out as PSBT, it spends
+/// an output cnd does not hold the key of.
+pub fn bump_fee_payload(
+    action: &BumpFee,
+    parent: &bitcoin::Transaction,
+    query_params: ActionExecutionParameters,
+) -> anyhow::Result<ActionResponseBody> {
+    let fee_per_wu = match query_params {
+        ActionExecutionParameters::BitcoinFee { fee_per_wu, .. } => fee_per_wu,
+        _ => {
+            return Err(anyhow::Error::from(MissingQueryParameters {
+                action: "bitcoin::BumpFee",
+                parameters: &[problem::MissingQueryParameter {
+                    name: "fee_per_wu",
+                    data_type: "uint",
+                    description: "The fee per weight unit you want the redeem transaction and \
+                                  the transaction bumping it to pay together in satoshis.",
+                }],
+            }))
+        }
+    };
+    let fee_per_wu = fee_per_wu.parse::<u64>().with_context(|| {
+        problem::Code::InvalidQueryParameter
+            .problem("Invalid query parameter.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail("Query parameter fee_per_wu is not a valid unsigned integer.")
+    })?;
+
+    let child = action.child(parent, fee_per_wu).map_err(|e| {
+        let detail = match e {
+            CannotBumpFee::FeeHigherThanOutputValue => {
+                "The fee per WU provided makes the total fee higher than the redeemed value."
+            }
+            CannotBumpFee::OverflowingFee => {
+                "The fee per WU provided makes the total fee higher than the system supports."
+            }
+        };
+        problem::Code::FeeTooHigh
+            .problem("Fee is too high.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(detail)
+    })?;
+
+    let mut psbt =
+        PartiallySignedTransaction::from_unsigned_tx(child).expect("transaction has no signatures");
+    // Lets the wallet sign without looking up the unconfirmed parent.
+    psbt.inputs[0].witness_utxo = Some(parent.output[0].clone());
+
+    Ok(ActionResponseBody::BitcoinPsbt {
+        psbt: Http(psbt),
+        network: Http(action.network),
+    })
+}
+
+impl ListRequiredFields for BumpFee {
+    fn list_required_fields() -> Vec<siren::Field> {
+        vec![fee_per_wu_field()]
+    }
+}
+
+/// Signs the transaction spending the outputs of `action` again, to the same
+/// address as the `previous` transaction but at a fee rate high enough to
+/// replace it (BIP 125).
+pub fn rebroadcast_payload(
+    action: SpendOutput,
+    previous: &bitcoin::Transaction,
+    query_params: ActionExecutionParameters,
+) -> anyhow::Result<ActionResponseBody> {
+    let (fee_per_wu, format) = match query_params {
+        ActionExecutionParameters::BitcoinFee { fee_per_wu, format } => (fee_per_wu, format),
+        _ => {
+            return Err(anyhow::Error::from(MissingQueryParameters {
+                action: "bitcoin::Rebroadcast",
+                parameters: &[problem::MissingQueryParameter {
+                    name: "fee_per_wu",
+                    data_type: "uint",
+                    description: "The fee per weight unit you want the replacing transaction \
+                                  to pay in satoshis.",
+                }],
+            }))
+        }
+    };
+    let fee_per_wu = fee_per_wu.parse::<usize>().with_context(|| {
+        problem::Code::InvalidQueryParameter
+            .problem("Invalid query parameter.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail("Query parameter fee_per_wu is not a valid unsigned integer.")
+    })?;
+
+    let network = action.network;
+    let spent = action.value().as_sat();
+    let fee = |transaction: &bitcoin::Transaction| {
+        spent.saturating_sub(transaction.output.iter().map(|output| output.value).sum())
+    };
+
+    let address = previous
+        .output
+        .first()
+        .and_then(|output| bitcoin::Address::from_script(&output.script_pubkey, network))
+        .with_context(|| format!("transaction {} does not pay to an address", previous.txid()))?;
+    let replacement = action
+        .spend_to(address)
+        .sign_with_rate(&*crate::SECP, fee_per_wu)
+        .map_err(signing_failed)?;
+
+    // On top of what the replaced transaction paid, the replacement pays the
+    // incremental relay fee of 1 satoshi per vbyte.
+    let min_fee = fee(previous) + (replacement.get_weight() as u64 + 3) / 4;
+    if fee(&replacement) < min_fee {
+        return Err(anyhow::Error::from(
+            problem::Code::InvalidQueryParameter
+                .problem("Fee is too low.")
+                .set_status(StatusCode::BAD_REQUEST)
+                .set_detail(format!(
+                    "The replacing transaction has to pay at least {} satoshis in fees.",
+                    min_fee
+                )),
+        ));
+    }
+
+    Ok(match format {
+        BitcoinTransactionFormat::Raw => {
+            ActionResponseBody::bitcoin_broadcast_signed_transaction(&replacement, network)
+        }
+        BitcoinTransactionFormat::Psbt => ActionResponseBody::bitcoin_psbt(&replacement, network),
+    })
+}
+
+pub fn rebroadcast_required_fields() -> Vec<siren::Field> {
+    vec![fee_per_wu_field()]
+}
+
+fn fee_per_wu_field() -> siren::Field {
+    siren::Field {
+        name: "fee_per_wu".to_owned(),
+        class: vec!["bitcoin".to_owned(), "feePerWU".to_owned()],
+        _type: Some("number".to_owned()),
+        value: None,
+        title: None,
+    }
+}
+
 impl IntoResponsePayload for ethereum::DeployContract {
     fn into_response_payload(
         self,
@@ -247,6 +569,7 @@ impl IntoResponsePayload for ethereum::DeployContract {
                 gas_limit,
                 chain_id,
                 network: chain_id.try_into()?,
+                gas_pricing: GasPricing::default(),
             }),
             _ => Err(anyhow::Error::from(UnexpectedQueryParameters {
                 action: "ethereum::ContractDeploy",
@@ -282,6 +605,7 @@ impl IntoResponsePayload for ethereum::CallContract {
                 chain_id,
                 network: chain_id.try_into()?,
                 min_block_timestamp,
+                gas_pricing: GasPricing::default(),
             }),
             _ => Err(anyhow::Error::from(UnexpectedQueryParameters {
                 action: "ethereum::SendTransaction",
@@ -340,10 +664,136 @@ mod test {
             Ok(ActionExecutionParameters::BitcoinAddressAndFee {
                 address: "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".parse().unwrap(),
                 fee_per_wu: "10.59".to_string(),
+                format: BitcoinTransactionFormat::Raw,
+            })
+        );
+    }
+
+    #[test]
+    fn given_only_fee_deserialize_to_bitcoin_fee() {
+        let s = "fee_per_wu=10";
+
+        let res = serde_urlencoded::from_str::<ActionExecutionParameters>(s);
+        assert_eq!(
+            res,
+            Ok(ActionExecutionParameters::BitcoinFee {
+                fee_per_wu: "10".to_string(),
+                format: BitcoinTransactionFormat::Raw,
+            })
+        );
+    }
+
+    #[test]
+    fn given_only_format_deserialize_to_bitcoin_format() {
+        let s = "format=psbt";
+
+        let res = serde_urlencoded::from_str::<ActionExecutionParameters>(s);
+        assert_eq!(
+            res,
+            Ok(ActionExecutionParameters::BitcoinFormat {
+                format: BitcoinTransactionFormat::Psbt,
             })
         );
     }
 
+    #[test]
+    fn send_to_address_in_psbt_format_pays_the_address() {
+        let to = BitcoinAddress::from_str("2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9").unwrap();
+        let action = SendToAddress {
+            to: to.clone(),
+            amount: bitcoin::Amount::from_btc(1.0).unwrap(),
+            network: bitcoin::Network::Regtest,
+        };
+
+        let payload = action
+            .into_response_payload(ActionExecutionParameters::BitcoinFormat {
+                format: BitcoinTransactionFormat::Psbt,
+            })
+            .unwrap();
+
+        let psbt = match payload {
+            ActionResponseBody::BitcoinPsbt { psbt, .. } => psbt,
+            _ => panic!("expected a PSBT but got {:?}", payload),
+        };
+        assert_eq!(psbt.global.unsigned_tx.output[0].value, 100_000_000);
+        assert_eq!(
+            psbt.global.unsigned_tx.output[0].script_pubkey,
+            to.script_pubkey()
+        );
+    }
+
+    #[test]
+    fn bumping_the_fee_pays_for_the_parent_as_well() {
+        let to = BitcoinAddress::from_str("2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9").unwrap();
+        let htlc_output = bitcoin::OutPoint {
+            txid: Default::default(),
+            vout: 1,
+        };
+        let parent = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![bitcoin::TxIn {
+                previous_output: htlc_output,
+                script_sig: bitcoin::Script::new(),
+                sequence: 0xFFFF_FFFF,
+                witness: vec![vec![0; 72], vec![0; 33], vec![0; 32], vec![1], vec![0; 97]],
+            }],
+            output: vec![TxOut {
+                value: 99_000,
+                script_pubkey: to.script_pubkey(),
+            }],
+        };
+        let action = BumpFee {
+            htlc_outputs: vec![(htlc_output, bitcoin::Amount::from_sat(100_000))],
+            network: bitcoin::Network::Regtest,
+        };
+        assert!(action.is_parent(&parent));
+
+        let payload = bump_fee_payload(&action, &parent, ActionExecutionParameters::BitcoinFee {
+            fee_per_wu: "10".to_string(),
+            format: BitcoinTransactionFormat::Raw,
+        })
+        .unwrap();
+
+        let psbt = match payload {
+            ActionResponseBody::BitcoinPsbt { psbt, .. } => psbt,
+            _ => panic!("expected a PSBT but got {:?}", payload),
+        };
+        let child = &psbt.global.unsigned_tx;
+        assert_eq!(child.input[0].previous_output.txid, parent.txid());
+        assert_eq!(psbt.inputs[0].witness_utxo, Some(parent.output[0].clone()));
+        assert_eq!(child.output[0].script_pubkey, to.script_pubkey());
+
+        let package_fee = 100_000 - child.output[0].value;
+        assert!(package_fee >= (parent.get_weight() + child.get_weight()) as u64 * 10);
+    }
+
+    #[test]
+    fn op_return_is_added_to_unsigned_transactions() {
+        let action = SendToAddress {
+            to: BitcoinAddress::from_str("2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9").unwrap(),
+            amount: bitcoin::Amount::from_btc(1.0).unwrap(),
+            network: bitcoin::Network::Regtest,
+        };
+
+        let payload = action
+            .into_response_payload(ActionExecutionParameters::BitcoinFormat {
+                format: BitcoinTransactionFormat::Psbt,
+            })
+            .unwrap()
+            .with_op_return(b"comit");
+
+        let psbt = match payload {
+            ActionResponseBody::BitcoinPsbt { psbt, .. } => psbt,
+            _ => panic!("expected a PSBT but got {:?}", payload),
+        };
+        assert_eq!(psbt.global.unsigned_tx.output.len(), 2);
+        assert_eq!(psbt.outputs.len(), 2);
+        assert!(psbt.global.unsigned_tx.output[1]
+            .script_pubkey
+            .is_op_return());
+    }
+
     #[test]
     fn call_contract_serializes_correctly_to_json_with_none() {
         let addr = EthereumAddress::from_str("0A81e8be41b21f651a71aaB1A85c6813b8bBcCf8").unwrap();
@@ -355,11 +805,33 @@ mod test {
             chain_id,
             network: chain_id.try_into().unwrap(),
             min_block_timestamp: None,
+            gas_pricing: GasPricing::Legacy,
         };
         let serialized = serde_json::to_string(&contract).unwrap();
         assert_eq!(
             serialized,
-            r#"{"type":"ethereum-call-contract","payload":{"contract_address":"0x0a81e8be41b21f651a71aab1a85c6813b8bbccf8","gas_limit":"0x1","chain_id":3,"network":"ropsten"}}"#
+            r#"{"type":"ethereum-call-contract","payload":{"contract_address":"0x0a81e8be41b21f651a71aab1a85c6813b8bbccf8","gas_limit":"0x1","chain_id":3,"network":"ropsten","transaction_type":"legacy"}}"#
+        );
+    }
+
+    #[test]
+    fn call_contract_serializes_eip1559_fees_to_json() {
+        let addr = EthereumAddress::from_str("0A81e8be41b21f651a71aaB1A85c6813b8bBcCf8").unwrap();
+        let chain_id = ChainId::new(3);
+        let contract = ActionResponseBody::EthereumCallContract {
+            contract_address: addr,
+            data: None,
+            gas_limit: U256::from(1),
+            chain_id,
+            network: chain_id.try_into().unwrap(),
+            min_block_timestamp: None,
+            gas_pricing: GasPricing::Legacy,
+        }
+        .with_gas_pricing(GasPricing::eip1559(U256::from(10), U256::from(1)));
+        let serialized = serde_json::to_string(&contract).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"ethereum-call-contract","payload":{"contract_address":"0x0a81e8be41b21f651a71aab1a85c6813b8bbccf8","gas_limit":"0x1","chain_id":3,"network":"ropsten","transaction_type":"eip1559","max_fee_per_gas":"0x15","max_priority_fee_per_gas":"0x1"}}"#
         );
     }
 
@@ -389,7 +861,7 @@ mod test {
         let expected = &[
             r#"{"type":"bitcoin-send-amount-to-address","payload":{"to":"2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9","amount":"100000000","network":"mainnet"}}"#,
             r#"{"type":"bitcoin-send-amount-to-address","payload":{"to":"2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9","amount":"100000000","network":"testnet"}}"#,
-            r#"{"type":"bitcoin-send-amount-to-address","payload":{"to":"2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9","amount":"100000000","network":"regtest"}}"#
+            r#"{"type":"bitcoin-send-amount-to-address","payload":{"to":"2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9","amount":"100000000","network":"regtest"}}"#,
         ];
 
         let actual = input
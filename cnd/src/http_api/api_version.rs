@@ -0,0 +1,42 @@
+use crate::http_api::{problem, routes::into_rejection};
+use warp::{filters::BoxedFilter, Filter, Reply};
+
+pub const API_VERSION_HEADER: &str = "api-version";
+/// Served without a version prefix and assumed if a request does not ask
+/// for a version.
+pub const LATEST_API_VERSION: u32 = 1;
+pub const SUPPORTED_API_VERSIONS: &[u32] = &[1];
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[error("API version {requested} is not supported")]
+pub struct UnsupportedApiVersion {
+    pub requested: String,
+}
+
+/// Serves `v1` under `/v1` as well as without a prefix.
+///
+/// Either way, a request may ask for a version with the `Api-Version` header,
+/// which has to match the prefix if there is one. Breaking changes to
+/// resources ship as a new version next to the existing ones.
+pub fn versioned<R>(v1: BoxedFilter<(R,)>) -> BoxedFilter<(R,)>
+where
+    R: Reply + Send + 'static,
+{
+    let prefixed = warp::path("v1").and(negotiate(1)).and(v1.clone());
+    let unprefixed = negotiate(LATEST_API_VERSION).and(v1);
+
+    prefixed.or(unprefixed).unify().boxed()
+}
+
+/// Rejects requests that ask for a version other than `version`.
+fn negotiate(version: u32) -> BoxedFilter<()> {
+    warp::header::optional::<String>(API_VERSION_HEADER)
+        .and_then(move |requested: Option<String>| match requested {
+            Some(requested) if requested.trim() != version.to_string() => Err(into_rejection(
+                problem::from_anyhow(anyhow::Error::from(UnsupportedApiVersion { requested })),
+            )),
+            _ => Ok(()),
+        })
+        .untuple_one()
+        .boxed()
+}
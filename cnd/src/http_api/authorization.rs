@@ -0,0 +1,148 @@
+use crate::{
+    config::ApiKey,
+    http_api::{problem, routes::into_rejection},
+};
+use crypto::{digest::Digest, sha2::Sha256, util::fixed_time_eq};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use warp::{filters::BoxedFilter, Filter};
+
+const BEARER_PREFIX: &str = "Bearer ";
+const DIGEST_LENGTH: usize = 32;
+
+/// What a client may do with the HTTP API, each level includes the ones
+/// before it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// List swaps and read their state, e.g. for monitoring dashboards.
+    ReadOnly,
+    /// Also create, accept and decline swaps and get the payloads of their
+    /// actions.
+    Trade,
+}
+
+/// The keys clients send as `Authorization: Bearer <key>`.
+///
+/// Without any keys configured, every client may trade.
+///
+/// Only the digests of the keys are kept. The digest of the key of a request
+/// is compared against all of them in constant time, such that neither the
+/// time it takes to authorize a request nor its outcome tell how much of a
+/// key was guessed correctly.
+#[derive(Clone, Debug, Default)]
+pub struct ApiKeys(Arc<Vec<([u8; DIGEST_LENGTH], Permission)>>);
+
+#[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+pub enum Unauthorized {
+    #[error("the request does not carry a known API key")]
+    UnknownApiKey,
+    #[error("the API key does not permit this request")]
+    Forbidden(Permission),
+}
+
+impl ApiKeys {
+    pub fn new(api_keys: &[ApiKey]) -> Self {
+        ApiKeys(Arc::new(
+            api_keys
+                .iter()
+                .map(|api_key| (digest(&api_key.key), api_key.permission))
+                .collect(),
+        ))
+    }
+
+    fn authorize(
+        &self,
+        authorization: Option<&str>,
+        required: Permission,
+    ) -> Result<(), Unauthorized> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let key = authorization
+            .filter(|authorization| authorization.starts_with(BEARER_PREFIX))
+            .map(|authorization| digest(&authorization[BEARER_PREFIX.len()..]))
+            .ok_or(Unauthorized::UnknownApiKey)?;
+        let permission = self
+            .0
+            .iter()
+            .fold(None, |found, (candidate, permission)| {
+                if fixed_time_eq(candidate, &key) {
+                    Some(*permission)
+                } else {
+                    found
+                }
+            })
+            .ok_or(Unauthorized::UnknownApiKey)?;
+
+        if permission < required {
+            return Err(Unauthorized::Forbidden(required));
+        }
+
+        Ok(())
+    }
+}
+
+fn digest(key: &str) -> [u8; DIGEST_LENGTH] {
+    let mut hasher = Sha256::new();
+    hasher.input_str(key);
+
+    let mut digest = [0u8; DIGEST_LENGTH];
+    hasher.result(&mut digest);
+
+    digest
+}
+
+/// Rejects requests whose API key does not grant `permission`.
+///
+/// Goes after the path and method of a route, such that requests for other
+/// routes are not rejected because of it.
+pub fn require(api_keys: ApiKeys, permission: Permission) -> BoxedFilter<()> {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |authorization: Option<String>| {
+            api_keys
+                .authorize(authorization.as_ref().map(String::as_str), permission)
+                .map_err(|e| into_rejection(problem::from_anyhow(anyhow::Error::from(e))))
+        })
+        .untuple_one()
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn api_keys() -> ApiKeys {
+        ApiKeys::new(&[
+            ApiKey {
+                key: String::from("dashboard"),
+                permission: Permission::ReadOnly,
+            },
+            ApiKey {
+                key: String::from("trader"),
+                permission: Permission::Trade,
+            },
+        ])
+    }
+
+    #[test]
+    fn read_only_keys_cannot_trade() {
+        let api_keys = api_keys();
+
+        assert_that(&api_keys.authorize(Some("Bearer dashboard"), Permission::ReadOnly)).is_ok();
+        assert_that(&api_keys.authorize(Some("Bearer dashboard"), Permission::Trade))
+            .is_err_containing(Unauthorized::Forbidden(Permission::Trade));
+        assert_that(&api_keys.authorize(Some("Bearer trader"), Permission::Trade)).is_ok();
+    }
+
+    #[test]
+    fn requests_without_a_known_key_are_rejected_once_keys_are_configured() {
+        assert_that(&api_keys().authorize(None, Permission::ReadOnly))
+            .is_err_containing(Unauthorized::UnknownApiKey);
+        assert_that(&api_keys().authorize(Some("dashboard"), Permission::ReadOnly))
+            .is_err_containing(Unauthorized::UnknownApiKey);
+        assert_that(&ApiKeys::default().authorize(None, Permission::Trade)).is_ok();
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::DateTime;
+use warp::{
+    filters::reply::WithHeaders,
+    http::header::{HeaderMap, HeaderName, HeaderValue, WARNING},
+};
+
+/// The routes whose responses can contain something that is deprecated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Route {
+    GetSwap,
+    GetSwapSubResource,
+    GetSwaps,
+    Action,
+}
+
+/// Something the HTTP API is going to stop serving.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deprecation {
+    /// Sent to clients in the `Warning` header.
+    pub description: &'static str,
+    /// The routes whose responses contain it.
+    pub routes: &'static [Route],
+    /// The HTTP-date after which it may be removed, `None` until the removal
+    /// is scheduled.
+    pub sunset: Option<&'static str>,
+}
+
+/// Everything that is deprecated in the current API version.
+///
+/// Responses of the affected routes carry a `Deprecation` header, a `Warning`
+/// per entry and a `Sunset` header once the removal is scheduled. Entries are
+/// removed together with what they deprecate.
+pub const DEPRECATIONS: &[Deprecation] = &[
+    Deprecation {
+        description: "the network field of Ethereum ledgers and actions will be removed, use chain_id instead (#1580)",
+        routes: &[
+            Route::GetSwap,
+            Route::GetSwapSubResource,
+            Route::GetSwaps,
+            Route::Action,
+        ],
+        sunset: None,
+    },
+    Deprecation {
+        description: "the feePerByte class of the fee_per_wu field will be removed, use feePerWU instead",
+        routes: &[Route::GetSwap, Route::GetSwapSubResource],
+        sunset: None,
+    },
+];
+
+/// Adds the deprecation headers of `route` to its responses.
+pub fn headers(route: Route) -> WithHeaders {
+    warp::reply::with::headers(header_map(DEPRECATIONS, route))
+}
+
+fn header_map(deprecations: &[Deprecation], route: Route) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let affected = deprecations
+        .iter()
+        .filter(|deprecation| deprecation.routes.contains(&route));
+
+    for deprecation in affected.clone() {
+        headers.insert(
+            HeaderName::from_static("deprecation"),
+            HeaderValue::from_static("true"),
+        );
+        headers.append(
+            WARNING,
+            HeaderValue::from_str(&format!("299 cnd \"{}\"", deprecation.description))
+                .expect("descriptions are valid header values"),
+        );
+    }
+
+    // The earliest sunset is the one clients have to act on.
+    if let Some(sunset) = affected
+        .filter_map(|deprecation| deprecation.sunset)
+        .min_by_key(|sunset| {
+            DateTime::parse_from_rfc2822(sunset).expect("sunsets are valid HTTP-dates")
+        })
+    {
+        headers.insert(
+            HeaderName::from_static("sunset"),
+            HeaderValue::from_static(sunset),
+        );
+    }
+
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_affected_routes_carry_deprecation_headers() {
+        let deprecations = &[Deprecation {
+            description: "the foo field will be removed",
+            routes: &[Route::GetSwap],
+            sunset: Some("Sat, 01 Jan 2022 00:00:00 GMT"),
+        }];
+
+        let headers = header_map(deprecations, Route::GetSwap);
+
+        assert_eq!(headers["deprecation"], "true");
+        assert_eq!(headers["sunset"], "Sat, 01 Jan 2022 00:00:00 GMT");
+        assert_eq!(
+            headers[WARNING],
+            "299 cnd \"the foo field will be removed\""
+        );
+        assert!(header_map(deprecations, Route::Action).is_empty());
+    }
+
+    #[test]
+    fn earliest_sunset_is_sent() {
+        let deprecations = &[
+            Deprecation {
+                description: "the foo field will be removed",
+                routes: &[Route::GetSwap],
+                sunset: Some("Sat, 01 Jan 2022 00:00:00 GMT"),
+            },
+            Deprecation {
+                description: "the bar field will be removed",
+                routes: &[Route::GetSwap],
+                sunset: Some("Wed, 01 Dec 2021 00:00:00 GMT"),
+            },
+            Deprecation {
+                description: "the baz field will be removed",
+                routes: &[Route::GetSwap],
+                sunset: None,
+            },
+        ];
+
+        let headers = header_map(deprecations, Route::GetSwap);
+
+        assert_eq!(headers["sunset"], "Wed, 01 Dec 2021 00:00:00 GMT");
+        assert_eq!(headers.get_all(WARNING).iter().count(), 3);
+    }
+
+    #[test]
+    fn all_deprecations_are_valid_header_values() {
+        for route in &[
+            Route::GetSwap,
+            Route::GetSwapSubResource,
+            Route::GetSwaps,
+            Route::Action,
+        ] {
+            header_map(DEPRECATIONS, *route);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::config::{settings::HttpApi, Tls};
+use anyhow::Context;
+use futures::{Future, Stream};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    net::SocketAddr,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio_rustls::{
+    rustls::{internal::pemfile, NoClientAuth, PrivateKey, ServerConfig},
+    TlsAcceptor,
+};
+use warp::{filters::BoxedFilter, Reply};
+
+/// Only the user cnd runs as may connect to the unix socket.
+const UNIX_SOCKET_MODE: u32 = 0o600;
+
+/// How many clients may be in the middle of the TLS handshake at once.
+const PENDING_TLS_HANDSHAKES: usize = 64;
+
+/// An address the HTTP API is served on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Listener {
+    Tcp(SocketAddr),
+    Tls { address: SocketAddr, tls: Tls },
+    Unix(PathBuf),
+}
+
+impl Listener {
+    /// If a unix socket is configured, the API is served on nothing else.
+    pub fn from_settings(http_api: &HttpApi) -> Vec<Self> {
+        if let Some(path) = &http_api.unix_socket {
+            return vec![Listener::Unix(path.clone())];
+        }
+
+        let socket = Listener::Tcp(SocketAddr::new(
+            http_api.socket.address,
+            http_api.socket.port,
+        ));
+        let additional = http_api.listeners.iter().map(|listener| {
+            let address = SocketAddr::new(listener.address, listener.port);
+
+            match &listener.tls {
+                Some(tls) => Listener::Tls {
+                    address,
+                    tls: tls.clone(),
+                },
+                None => Listener::Tcp(address),
+            }
+        });
+
+        std::iter::once(socket).chain(additional).collect()
+    }
+
+    /// Bind the listener and return the server to be spawned on the runtime.
+    pub fn serve<R>(
+        self,
+        routes: BoxedFilter<(R,)>,
+    ) -> anyhow::Result<Box<dyn Future<Item = (), Error = ()> + Send>>
+    where
+        R: Reply + 'static,
+    {
+        let server: Box<dyn Future<Item = (), Error = ()> + Send> = match self {
+            Listener::Tcp(address) => {
+                log::info!("Starting HTTP server on {:?}", address);
+
+                Box::new(warp::serve(routes).bind(address))
+            }
+            Listener::Tls { address, tls } => {
+                let acceptor = TlsAcceptor::from(Arc::new(server_config(&tls)?));
+                let listener = tokio::net::TcpListener::bind(&address)
+                    .with_context(|| format!("failed to bind {}", address))?;
+
+                log::info!("Starting HTTPS server on {:?}", address);
+
+                // A client that fails the handshake must neither stop the server nor hold
+                // up the clients behind it.
+                let incoming = listener
+                    .incoming()
+                    .map(move |stream| {
+                        acceptor.accept(stream).then(|result| match result {
+                            Ok(stream) => Ok(Some(stream)),
+                            Err(e) => {
+                                log::debug!("TLS handshake failed: {}", e);
+                                Ok(None)
+                            }
+                        })
+                    })
+                    .buffer_unordered(PENDING_TLS_HANDSHAKES)
+                    .filter_map(|stream| stream);
+
+                Box::new(warp::serve(routes).serve_incoming(incoming))
+            }
+            Listener::Unix(path) => {
+                // A socket file left behind by a previous run would make binding fail.
+                if path.exists() {
+                    fs::remove_file(&path).with_context(|| {
+                        format!("failed to remove stale unix socket {}", path.display())
+                    })?;
+                }
+
+                let listener = tokio::net::UnixListener::bind(&path)
+                    .with_context(|| format!("failed to bind unix socket {}", path.display()))?;
+                fs::set_permissions(&path, fs::Permissions::from_mode(UNIX_SOCKET_MODE))
+                    .with_context(|| {
+                        format!(
+                            "failed to restrict access to unix socket {}",
+                            path.display()
+                        )
+                    })?;
+
+                log::info!("Starting HTTP server on unix socket {}", path.display());
+
+                Box::new(warp::serve(routes).serve_incoming(listener.incoming()))
+            }
+        };
+
+        Ok(server)
+    }
+}
+
+fn server_config(tls: &Tls) -> anyhow::Result<ServerConfig> {
+    let certs = pemfile::certs(&mut open(&tls.cert)?)
+        .map_err(|()| anyhow::anyhow!("failed to parse certificates in {}", tls.cert.display()))?;
+    let key = private_key(&tls.key)?;
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(certs, key)
+        .with_context(|| format!("invalid TLS certificate {}", tls.cert.display()))?;
+    config.set_protocols(&["h2".into(), "http/1.1".into()]);
+
+    Ok(config)
+}
+
+/// The first PKCS8 or, failing that, RSA private key in the file.
+fn private_key(path: &Path) -> anyhow::Result<PrivateKey> {
+    let parse_error = || anyhow::anyhow!("failed to parse private key in {}", path.display());
+
+    let pkcs8 = pemfile::pkcs8_private_keys(&mut open(path)?).map_err(|()| parse_error())?;
+    let rsa = pemfile::rsa_private_keys(&mut open(path)?).map_err(|()| parse_error())?;
+
+    pkcs8
+        .into_iter()
+        .chain(rsa)
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("no private key found in {}", path.display()))
+}
+
+fn open(path: &Path) -> anyhow::Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+    Ok(BufReader::new(file))
+}
@@ -3,20 +3,32 @@ pub mod routes;
 #[macro_use]
 pub mod impl_serialize_http;
 pub mod action;
+pub mod api_version;
+pub mod authorization;
+pub mod deprecation;
 mod ethereum_network;
+mod listener;
+mod payout_accounts;
 mod problem;
 mod swap_resource;
+mod token_registry;
+mod transaction_verification;
 
 pub use self::{
+    authorization::{ApiKeys, Permission},
+    listener::Listener,
+    payout_accounts::{PayoutAccounts, UnknownPayoutAccount},
     problem::*,
-    swap_resource::{SwapParameters, SwapResource, SwapStatus},
+    swap_resource::{swap_status, SwapParameters, SwapResource, SwapStatus, SwapSubResource},
+    token_registry::{TokenRegistry, UnresolvableToken},
 };
 
 pub const PATH: &str = "swaps";
 
 use crate::{
     ethereum::{self, Erc20Token},
-    network::DialInformation,
+    network::{parse_address_hint, DialInformation},
+    price_feed::{Coin, IndicativeFiatValue, Quotes},
     swap_protocols::{
         ledger::{self, ethereum::ChainId},
         SwapId, SwapProtocol,
@@ -24,7 +36,6 @@ use crate::{
 };
 use bitcoin::util::amount::Denomination;
 use libp2p::PeerId;
-use libp2p_core::Multiaddr;
 use serde::{
     de::{self, Error as _, MapAccess},
     ser::SerializeStruct,
@@ -77,6 +88,18 @@ impl Serialize for Http<bitcoin::Transaction> {
     }
 }
 
+/// Base64 encoded as defined in BIP 174.
+impl Serialize for Http<bitcoin::util::psbt::PartiallySignedTransaction> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&base64::encode(&bitcoin::consensus::encode::serialize(
+            &self.0,
+        )))
+    }
+}
+
 impl Serialize for Http<crate::ethereum::Transaction> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -211,7 +234,9 @@ impl<'de> Deserialize<'de> for DialInformation {
                             if address_hint.is_some() {
                                 return Err(de::Error::duplicate_field("address_hint"));
                             }
-                            address_hint = Some(map.next_value::<Multiaddr>()?)
+                            let value = map.next_value::<String>()?;
+                            address_hint =
+                                Some(parse_address_hint(&value).map_err(de::Error::custom)?)
                         }
                         _ => {
                             return Err(de::Error::unknown_field(key.as_str(), &[
@@ -308,9 +333,16 @@ pub struct EtherAssetParams {
     quantity: ethereum::EtherQuantity,
 }
 
+/// The quantity can either be given in base units through `quantity` or in
+/// whole tokens through `human_quantity` together with the token's `decimals`.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Erc20AssetParams {
-    quantity: ethereum::Erc20Quantity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quantity: Option<ethereum::Erc20Quantity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    human_quantity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    decimals: Option<u8>,
     token_contract: ethereum::Address,
 }
 
@@ -388,13 +420,15 @@ impl From<ledger::Ethereum> for EthereumLedgerParams {
     }
 }
 
-impl From<HttpAssetParams> for HttpAsset {
-    fn from(params: HttpAssetParams) -> Self {
-        match params {
+impl TryFrom<HttpAssetParams> for HttpAsset {
+    type Error = anyhow::Error;
+
+    fn try_from(params: HttpAssetParams) -> Result<Self, Self::Error> {
+        Ok(match params {
             HttpAssetParams::Bitcoin(params) => HttpAsset::Bitcoin(params.into()),
             HttpAssetParams::Ether(params) => HttpAsset::Ether(params.into()),
-            HttpAssetParams::Erc20(params) => HttpAsset::Erc20(params.into()),
-        }
+            HttpAssetParams::Erc20(params) => HttpAsset::Erc20(params.try_into()?),
+        })
     }
 }
 
@@ -434,19 +468,54 @@ impl From<ethereum::EtherQuantity> for EtherAssetParams {
     }
 }
 
-impl From<Erc20AssetParams> for ethereum::Erc20Token {
-    fn from(params: Erc20AssetParams) -> Self {
-        Self {
+#[derive(Debug, thiserror::Error)]
+#[error("An ERC20 asset requires either a quantity or a human_quantity together with decimals.")]
+pub struct InvalidErc20AssetParams;
+
+impl TryFrom<Erc20AssetParams> for ethereum::Erc20Token {
+    type Error = anyhow::Error;
+
+    fn try_from(params: Erc20AssetParams) -> Result<Self, Self::Error> {
+        let quantity = match params {
+            Erc20AssetParams {
+                quantity: Some(quantity),
+                human_quantity: None,
+                decimals,
+                ..
+            } => {
+                if let Some(decimals) = decimals {
+                    if !quantity.is_plausible(decimals) {
+                        log::warn!(
+                            "ERC20 quantity {} is implausible for a token with {} decimals, quantities are expected in base units",
+                            quantity,
+                            decimals
+                        );
+                    }
+                }
+                quantity
+            }
+            Erc20AssetParams {
+                quantity: None,
+                human_quantity: Some(ref human_quantity),
+                decimals: Some(decimals),
+                ..
+            } => ethereum::Erc20Quantity::from_human_quantity(human_quantity, decimals)?,
+            _ => return Err(anyhow::Error::from(InvalidErc20AssetParams)),
+        };
+
+        Ok(Self {
             token_contract: params.token_contract,
-            quantity: params.quantity,
-        }
+            quantity,
+        })
     }
 }
 
 impl From<ethereum::Erc20Token> for Erc20AssetParams {
     fn from(erc20: Erc20Token) -> Self {
         Self {
-            quantity: erc20.quantity,
+            quantity: Some(erc20.quantity),
+            human_quantity: None,
+            decimals: None,
             token_contract: erc20.token_contract,
         }
     }
@@ -482,11 +551,23 @@ impl From<ethereum::Erc20Token> for HttpAsset {
     }
 }
 
+impl HttpAsset {
+    /// Tokens are not quoted by the price feed and have no value.
+    pub fn indicative_fiat_value(&self, quotes: &Quotes) -> Option<IndicativeFiatValue> {
+        match self {
+            HttpAsset::Bitcoin(amount) => quotes.value_of(Coin::Bitcoin, amount.as_btc()),
+            HttpAsset::Ether(quantity) => quotes.value_of(Coin::Ether, quantity.ethereum()),
+            HttpAsset::Erc20(_) => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         ethereum::{Erc20Quantity, Erc20Token, EtherQuantity, H160, H256, U256},
         http_api::{Http, HttpAsset, HttpLedger},
+        network::DialInformation,
         swap_protocols::{
             ledger::{ethereum, Bitcoin, Ethereum},
             HashFunction, SwapId, SwapProtocol,
@@ -520,7 +601,35 @@ mod tests {
             &ether_serialized,
             r#"{"name":"ether","quantity":"1000000000000000000"}"#
         );
-        assert_eq!(&pay_serialized, r#"{"name":"erc20","quantity":"100000000000","token_contract":"0xb97048628db6b661d4c2aa833e95dbe1a905b280"}"#);
+        assert_eq!(
+            &pay_serialized,
+            r#"{"name":"erc20","quantity":"100000000000","token_contract":"0xb97048628db6b661d4c2aa833e95dbe1a905b280"}"#
+        );
+    }
+
+    #[test]
+    fn erc20_http_asset_deserializes_from_human_quantity() {
+        let asset = serde_json::from_str::<HttpAsset>(
+            r#"{"name":"erc20","human_quantity":"1.5","decimals":3,"token_contract":"0xb97048628db6b661d4c2aa833e95dbe1a905b280"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            asset,
+            HttpAsset::from(Erc20Token::new(
+                "B97048628DB6B661D4C2aA833e95Dbe1A905B280".parse().unwrap(),
+                Erc20Quantity(U256::from(1500)),
+            ))
+        );
+    }
+
+    #[test]
+    fn erc20_http_asset_with_fractional_base_units_fails_to_deserialize() {
+        let result = serde_json::from_str::<HttpAsset>(
+            r#"{"name":"erc20","human_quantity":"1.5","decimals":0,"token_contract":"0xb97048628db6b661d4c2aa833e95dbe1a905b280"}"#,
+        );
+
+        assert!(result.is_err());
     }
 
     #[test]
@@ -683,4 +792,23 @@ mod tests {
             r#""QmfUfpC2frwFvcDzpspnfZitHt5wct6n4kpG5jzgRdsxkY""#
         );
     }
+
+    #[test]
+    fn dial_information_with_onion3_address_hint_deserializes_correctly() {
+        let json = r#"{
+            "peer_id": "QmfUfpC2frwFvcDzpspnfZitHt5wct6n4kpG5jzgRdsxkY",
+            "address_hint": "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:9939"
+        }"#;
+
+        let dial_information = serde_json::from_str::<DialInformation>(json).unwrap();
+
+        assert_eq!(
+            dial_information.address_hint,
+            Some(
+                "/dns4/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd.onion/tcp/9939"
+                    .parse()
+                    .unwrap()
+            )
+        );
+    }
 }
@@ -0,0 +1,104 @@
+use crate::{config, ethereum::Address};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+
+/// Ethereum addresses that swap requests and accept bodies can give as redeem
+/// identity by their configured name, e.g.
+/// `"beta_ledger_redeem_identity": "cold-wallet"`.
+#[derive(Clone, Debug, Default)]
+pub struct PayoutAccounts {
+    accounts: BTreeMap<String, Address>,
+}
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[error("{name} is neither an address nor a configured payout account")]
+pub struct UnknownPayoutAccount {
+    pub name: String,
+}
+
+impl PayoutAccounts {
+    pub fn new(configured: &[config::PayoutAccount]) -> Self {
+        Self {
+            accounts: configured
+                .iter()
+                .map(|account| (account.name.clone(), account.address.0))
+                .collect(),
+        }
+    }
+
+    /// All configured accounts, ordered by name.
+    pub fn accounts(&self) -> impl Iterator<Item = (&String, &Address)> {
+        self.accounts.iter()
+    }
+
+    /// Replace the identity under `key` with the address of the payout
+    /// account it names and return the name of that account.
+    ///
+    /// Identities that are addresses are left as they are.
+    pub fn resolve(
+        &self,
+        body: &mut JsonValue,
+        key: &str,
+    ) -> Result<Option<String>, UnknownPayoutAccount> {
+        let name = match body.get(key).and_then(JsonValue::as_str) {
+            Some(identity) if !identity.starts_with("0x") => identity.to_owned(),
+            _ => return Ok(None),
+        };
+
+        let address = self
+            .accounts
+            .get(&name)
+            .ok_or_else(|| UnknownPayoutAccount { name: name.clone() })?;
+        body[key] = JsonValue::from(format!("{:#x}", address));
+
+        Ok(Some(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn payout_accounts() -> PayoutAccounts {
+        PayoutAccounts::new(&[config::PayoutAccount {
+            name: String::from("cold-wallet"),
+            address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+                .parse()
+                .unwrap(),
+        }])
+    }
+
+    #[test]
+    fn name_resolves_to_the_address_of_the_account() {
+        let mut body = serde_json::json!({
+            "alpha_ledger_redeem_identity": "cold-wallet",
+        });
+
+        let account = payout_accounts().resolve(&mut body, "alpha_ledger_redeem_identity");
+
+        assert_that(&account).is_ok_containing(Some(String::from("cold-wallet")));
+        assert_that(&body["alpha_ledger_redeem_identity"]).is_equal_to(&JsonValue::from(
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+        ));
+    }
+
+    #[test]
+    fn addresses_are_left_alone_and_unknown_names_rejected() {
+        let mut address = serde_json::json!({
+            "beta_ledger_redeem_identity": "0x00a329c0648769a73afac7f9381e08fb43dbea72",
+        });
+        let mut typo = serde_json::json!({
+            "beta_ledger_redeem_identity": "cold-walet",
+        });
+
+        let payout_accounts = payout_accounts();
+
+        assert_that(&payout_accounts.resolve(&mut address, "beta_ledger_redeem_identity"))
+            .is_ok_containing(None);
+        assert_that(&payout_accounts.resolve(&mut typo, "beta_ledger_redeem_identity"))
+            .is_err_containing(UnknownPayoutAccount {
+                name: String::from("cold-walet"),
+            });
+    }
+}
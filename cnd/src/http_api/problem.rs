@@ -1,8 +1,26 @@
 use crate::{
-    db,
-    http_api::routes::rfc003::handlers::{
-        post_swap::UnsupportedSwap, InvalidAction, InvalidActionInvocation,
+    db::{self, GapLimitReached},
+    http_api::{
+        api_version::{UnsupportedApiVersion, SUPPORTED_API_VERSIONS},
+        authorization::Unauthorized,
+        routes::{
+            export::InvalidExport,
+            internal::{BackupNotConfigured, FaucetUnavailable, UnsupportedHtlc},
+            metadata::InvalidMetadata,
+            rfc003::{
+                handlers::{
+                    post_swap::{InvalidAutoRefund, UnsupportedSwap},
+                    InvalidAction, InvalidActionInvocation, NothingToBump, NothingToReplace,
+                    ProofUnavailable, ReceiptUnavailable, UnverifiedTransaction,
+                },
+                identities::{InvalidIdentity, MissingIdentity},
+            },
+        },
+        UnknownPayoutAccount, UnresolvableToken,
     },
+    identity_reuse::IdentityReused,
+    maintenance::UnderMaintenance,
+    standby::AlreadyPromoted,
 };
 use http_api_problem::HttpApiProblem;
 use warp::{
@@ -10,6 +28,105 @@ use warp::{
     Rejection, Reply,
 };
 
+/// Stable, machine-readable identifier of a problem.
+///
+/// Every problem returned by the HTTP API carries one of these as its `code`
+/// member. Titles and details are meant for humans and may change, clients
+/// should branch on the code instead. Codes are never renamed or reused, new
+/// ones may be added.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Code {
+    /// There is no swap with the given id.
+    SwapNotFound,
+    /// The action was given query parameters it does not take.
+    UnexpectedQueryParameters,
+    /// The action requires query parameters that were not given.
+    MissingQueryParameters,
+    /// A query parameter could not be parsed.
+    InvalidQueryParameter,
+    /// The request body could not be deserialized.
+    InvalidBody,
+    /// The action cannot be invoked with this HTTP method.
+    InvalidActionInvocation,
+    /// The action is not available in the current state of the swap.
+    ActionConflict,
+    /// The swap has not finished yet, so there is no receipt.
+    ReceiptUnavailable,
+    /// The swap has not finished yet, so its outcome cannot be proven.
+    ProofUnavailable,
+    /// The combination of ledgers and assets is not supported.
+    UnsupportedPair,
+    /// The auto refund cannot be set up for this swap.
+    InvalidAutoRefund,
+    /// The external id or note of a swap is too long.
+    InvalidMetadata,
+    /// The token symbol is not configured under `[[ethereum.tokens]]`.
+    UnknownToken,
+    /// The swap export cannot be created or imported.
+    InvalidExport,
+    /// A swap with the id of the imported one exists already.
+    SwapExists,
+    /// The payout account is not configured under
+    /// `[[ethereum.payout_accounts]]`.
+    UnknownPayoutAccount,
+    /// There is no HTLC for the combination of ledger and asset.
+    UnsupportedHtlc,
+    /// The faucet is not configured or cannot be used on this network.
+    FaucetUnavailable,
+    /// The standby is already taking over as the active node.
+    AlreadyPromoted,
+    /// No more redeem addresses can be derived within the gap limit.
+    GapLimitReached,
+    /// An identity cnd derived for the swap was issued for another swap and
+    /// `[identity_reuse]` is configured to refuse that.
+    IdentityReused,
+    /// Backups are not enabled.
+    BackupNotConfigured,
+    /// Backups cannot be taken of the database swaps are stored in.
+    BackupUnsupported,
+    /// The fee exceeds the spendable value or what is supported.
+    FeeTooHigh,
+    /// The signed transaction does not do what any of the available actions
+    /// asks for.
+    TransactionMismatch,
+    /// API keys are configured but the request does not carry a known one.
+    Unauthorized,
+    /// The API key of the request does not permit it, e.g. a read-only key
+    /// was used to accept a swap.
+    Forbidden,
+    /// The version asked for with the `Api-Version` header is not served, or
+    /// does not match the version prefix of the path.
+    UnsupportedApiVersion,
+    /// cnd is in maintenance mode and does not take new swaps.
+    UnderMaintenance,
+    /// Something went wrong inside cnd, the logs have the details.
+    InternalError,
+}
+
+impl Code {
+    /// Creates a problem with the given title that carries this code.
+    pub fn problem<T: Into<String>>(self, title: T) -> HttpApiProblem {
+        let mut problem = HttpApiProblem::new(title);
+        problem
+            .set_value("code", &self)
+            .expect("codes will never fail to serialize");
+
+        problem
+    }
+}
+
+/// The problem for failures the client cannot do anything about.
+pub fn internal_error() -> HttpApiProblem {
+    let mut problem =
+        HttpApiProblem::with_title_and_type_from_status(StatusCode::INTERNAL_SERVER_ERROR);
+    problem
+        .set_value("code", &Code::InternalError)
+        .expect("codes will never fail to serialize");
+
+    problem
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("Missing GET parameters for a {} action type. Expected: {:?}", action, parameters.iter().map(|parameter| parameter.name).collect::<Vec<&str>>())]
 pub struct MissingQueryParameters {
@@ -31,6 +148,7 @@ pub struct UnexpectedQueryParameters {
     pub parameters: &'static [&'static str],
 }
 
+#[allow(clippy::cognitive_complexity)]
 pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
     let e = match e.downcast::<HttpApiProblem>() {
         Ok(problem) => return problem,
@@ -38,13 +156,25 @@ pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
     };
 
     if let Some(db::Error::SwapNotFound) = e.downcast_ref::<db::Error>() {
-        return HttpApiProblem::new("Swap not found.").set_status(StatusCode::NOT_FOUND);
+        return Code::SwapNotFound
+            .problem("Swap not found.")
+            .set_status(StatusCode::NOT_FOUND);
+    }
+
+    if let Some(db::Error::SnapshotUnsupported) = e.downcast_ref::<db::Error>() {
+        log::warn!("{}", e);
+
+        return Code::BackupUnsupported
+            .problem("Backups not supported.")
+            .set_status(StatusCode::NOT_IMPLEMENTED)
+            .set_detail("Swaps are stored in Postgres, back up the Postgres database instead.");
     }
 
     if let Some(e) = e.downcast_ref::<UnexpectedQueryParameters>() {
         log::error!("{}", e);
 
-        let mut problem = HttpApiProblem::new("Unexpected query parameter(s).")
+        let mut problem = Code::UnexpectedQueryParameters
+            .problem("Unexpected query parameter(s).")
             .set_status(StatusCode::BAD_REQUEST)
             .set_detail("This action does not take any query parameters.");
 
@@ -58,7 +188,8 @@ pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
     if let Some(e) = e.downcast_ref::<MissingQueryParameters>() {
         log::error!("{}", e);
 
-        let mut problem = HttpApiProblem::new("Missing query parameter(s).")
+        let mut problem = Code::MissingQueryParameters
+            .problem("Missing query parameter(s).")
             .set_status(StatusCode::BAD_REQUEST)
             .set_detail("This action requires additional query parameters.");
 
@@ -72,37 +203,274 @@ pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
     if e.is::<serde_json::Error>() {
         log::error!("deserialization error: {:?}", e);
 
-        return HttpApiProblem::new("Invalid body.")
+        return Code::InvalidBody
+            .problem("Invalid body.")
             .set_status(StatusCode::BAD_REQUEST)
             .set_detail(format!("{:?}", e));
     }
 
+    if e.is::<MissingIdentity>() || e.is::<InvalidIdentity>() {
+        log::warn!("{}", e);
+
+        return Code::InvalidBody
+            .problem("Invalid body.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!("{}.", e));
+    }
+
     if e.is::<InvalidActionInvocation>() {
         log::warn!("{:?}", e);
 
-        return HttpApiProblem::new("Invalid action invocation")
+        return Code::InvalidActionInvocation
+            .problem("Invalid action invocation")
             .set_status(http::StatusCode::METHOD_NOT_ALLOWED);
     }
 
     if e.is::<InvalidAction>() {
         log::warn!("{:?}", e);
 
-        return HttpApiProblem::new("Invalid action.")
+        return Code::ActionConflict
+            .problem("Invalid action.")
             .set_status(StatusCode::CONFLICT)
             .set_detail("Cannot perform requested action for this swap.");
     }
 
+    if e.is::<NothingToBump>() {
+        log::warn!("{}", e);
+
+        return Code::ActionConflict
+            .problem("Nothing to bump.")
+            .set_status(StatusCode::CONFLICT)
+            .set_detail("Only redeem transactions handed out signed by cnd can be bumped.");
+    }
+
+    if e.is::<NothingToReplace>() {
+        log::warn!("{}", e);
+
+        return Code::ActionConflict
+            .problem("Nothing to replace.")
+            .set_status(StatusCode::CONFLICT)
+            .set_detail("Only transactions handed out signed by cnd can be replaced.");
+    }
+
+    if e.is::<ReceiptUnavailable>() {
+        log::warn!("{}", e);
+
+        return Code::ReceiptUnavailable
+            .problem("Receipt not available.")
+            .set_status(StatusCode::CONFLICT)
+            .set_detail("Receipts are only issued for accepted swaps that finished.");
+    }
+
+    if e.is::<ProofUnavailable>() {
+        log::warn!("{}", e);
+
+        return Code::ProofUnavailable
+            .problem("Proof not available.")
+            .set_status(StatusCode::CONFLICT)
+            .set_detail("Proofs are only available for accepted swaps that finished.");
+    }
+
     if e.is::<UnsupportedSwap>() {
         log::warn!("{:?}", e);
 
-        return HttpApiProblem::new("Swap not supported.")
+        return Code::UnsupportedPair
+            .problem("Swap not supported.")
             .set_status(StatusCode::BAD_REQUEST)
             .set_detail("The requested combination of ledgers and assets is not supported.");
     }
 
+    if let Some(e) = e.downcast_ref::<InvalidAutoRefund>() {
+        log::warn!("{}", e);
+
+        return Code::InvalidAutoRefund
+            .problem("Invalid auto refund.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!("{}.", e));
+    }
+
+    if let Some(e) = e.downcast_ref::<InvalidMetadata>() {
+        log::warn!("{}", e);
+
+        return Code::InvalidMetadata
+            .problem("Invalid metadata.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!("{}.", e));
+    }
+
+    if let Some(e) = e.downcast_ref::<InvalidExport>() {
+        log::warn!("{}", e);
+
+        return match e {
+            InvalidExport::NotAccepted => Code::InvalidExport
+                .problem("Swap cannot be exported.")
+                .set_status(StatusCode::CONFLICT)
+                .set_detail(format!("{}.", e)),
+            InvalidExport::UnsupportedVersion(_) => Code::InvalidExport
+                .problem("Invalid export.")
+                .set_status(StatusCode::BAD_REQUEST)
+                .set_detail(format!("{}.", e)),
+            InvalidExport::SwapExists(_) => Code::SwapExists
+                .problem("Swap exists already.")
+                .set_status(StatusCode::CONFLICT)
+                .set_detail(format!("{}.", e)),
+        };
+    }
+
+    if let Some(e) = e.downcast_ref::<UnverifiedTransaction>() {
+        log::warn!("{}: {:?}", e, e.mismatches);
+
+        if e.mismatches.is_empty() {
+            return Code::ActionConflict
+                .problem("Invalid action.")
+                .set_status(StatusCode::CONFLICT)
+                .set_detail("There is no ledger action available for this swap.");
+        }
+
+        let mut problem = Code::TransactionMismatch
+            .problem("Transaction does not match.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail("The transaction does not do what any of the available actions asks for, do not broadcast it.");
+
+        problem
+            .set_value("mismatches", &e.mismatches)
+            .expect("mismatches will never fail to serialize");
+
+        return problem;
+    }
+
+    if let Some(e) = e.downcast_ref::<UnresolvableToken>() {
+        log::warn!("{}", e);
+
+        return Code::UnknownToken.problem("Unknown token.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!(
+                "{}, use an erc20 asset with its token_contract or configure the token under [[ethereum.tokens]].",
+                e
+            ));
+    }
+
+    if let Some(e) = e.downcast_ref::<UnknownPayoutAccount>() {
+        log::warn!("{}", e);
+
+        return Code::UnknownPayoutAccount
+            .problem("Unknown payout account.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!(
+                "{}, use an address or configure the account under [[ethereum.payout_accounts]].",
+                e
+            ));
+    }
+
+    if e.is::<UnsupportedHtlc>() {
+        log::warn!("{}", e);
+
+        return Code::UnsupportedHtlc
+            .problem("HTLC not supported.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail("There is no HTLC for the requested combination of ledger and asset.");
+    }
+
+    if let Some(e) = e.downcast_ref::<FaucetUnavailable>() {
+        log::warn!("{}", e);
+
+        return Code::FaucetUnavailable
+            .problem("Faucet not available.")
+            .set_status(StatusCode::NOT_FOUND)
+            .set_detail(format!("{}.", e));
+    }
+
+    if e.is::<AlreadyPromoted>() {
+        log::warn!("{}", e);
+
+        return Code::AlreadyPromoted
+            .problem("Already promoted.")
+            .set_status(StatusCode::CONFLICT)
+            .set_detail("The standby is already taking over as the active node.");
+    }
+
+    if let Some(e) = e.downcast_ref::<GapLimitReached>() {
+        log::warn!("{}", e);
+
+        return Code::GapLimitReached
+            .problem("Gap limit reached.")
+            .set_status(StatusCode::CONFLICT)
+            .set_detail(format!(
+                "{}, pass an address or raise the gap_limit under [bitcoin.redeem_destinations].",
+                e
+            ));
+    }
+
+    if let Some(e) = e.downcast_ref::<IdentityReused>() {
+        log::warn!("{}", e);
+
+        return Code::IdentityReused
+            .problem("Identity reused.")
+            .set_status(StatusCode::CONFLICT)
+            .set_detail(format!(
+                "{}, supply the identity yourself or allow reuse under [identity_reuse].",
+                e
+            ));
+    }
+
+    if e.is::<BackupNotConfigured>() {
+        log::warn!("{}", e);
+
+        return Code::BackupNotConfigured
+            .problem("Backups not enabled.")
+            .set_status(StatusCode::NOT_FOUND)
+            .set_detail(
+                "Set a passphrase in the [backup] section of the config file to enable backups.",
+            );
+    }
+
+    if let Some(e) = e.downcast_ref::<UnsupportedApiVersion>() {
+        log::warn!("{}", e);
+
+        let mut problem = Code::UnsupportedApiVersion
+            .problem("Unsupported API version.")
+            .set_status(StatusCode::NOT_ACCEPTABLE)
+            .set_detail(format!(
+                "{}, use one of the supported versions and make the Api-Version header match the version prefix of the path.",
+                e
+            ));
+
+        problem
+            .set_value("supported_versions", &SUPPORTED_API_VERSIONS)
+            .expect("versions will never fail to serialize");
+
+        return problem;
+    }
+
+    if e.is::<UnderMaintenance>() {
+        log::warn!("{}", e);
+
+        return Code::UnderMaintenance
+            .problem("Under maintenance.")
+            .set_status(StatusCode::SERVICE_UNAVAILABLE)
+            .set_detail("New swaps are declined until maintenance mode is turned off again.");
+    }
+
+    if let Some(e) = e.downcast_ref::<Unauthorized>() {
+        log::warn!("{}", e);
+
+        return match e {
+            Unauthorized::UnknownApiKey => Code::Unauthorized
+                .problem("Unauthorized.")
+                .set_status(StatusCode::UNAUTHORIZED)
+                .set_detail(
+                    "Send one of the configured API keys as `Authorization: Bearer <key>`.",
+                ),
+            Unauthorized::Forbidden(_) => Code::Forbidden
+                .problem("Forbidden.")
+                .set_status(StatusCode::FORBIDDEN)
+                .set_detail(format!("{}, use a key with the trade permission.", e)),
+        };
+    }
+
     log::error!("internal error occurred: {:#}", e);
 
-    HttpApiProblem::with_title_and_type_from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    internal_error()
 }
 
 pub fn unpack_problem(rejection: Rejection) -> Result<impl Reply, Rejection> {
@@ -122,3 +490,21 @@ pub fn unpack_problem(rejection: Rejection) -> Result<impl Reply, Rejection> {
 
     Err(rejection)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn problems_carry_their_code() {
+        let not_found = from_anyhow(anyhow::Error::from(db::Error::SwapNotFound));
+        let internal = from_anyhow(anyhow::anyhow!("something broke"));
+
+        let not_found = serde_json::to_value(&not_found).unwrap();
+        let internal = serde_json::to_value(&internal).unwrap();
+
+        assert_that(&not_found["code"]).is_equal_to(serde_json::json!("swap-not-found"));
+        assert_that(&internal["code"]).is_equal_to(serde_json::json!("internal-error"));
+    }
+}
@@ -1,12 +1,41 @@
 use crate::{
+    backup::Backup,
+    block_lag::BlockLagMonitor,
+    clock_skew::ClockMonitor,
     config::settings::AllowedOrigins,
-    db::{DetermineTypes, Retrieve, Saver},
-    http_api,
-    network::{Network, SendRequest},
-    seed::SwapSeed,
-    swap_protocols::{self, rfc003::state_store::StateStore, LedgerEventsCreator, SwapId},
+    db::{
+        ActionHistory, AutoRefunds, ConstructedTransactions, DetermineTypes, Enqueuer, Importer,
+        IssuedIdentities, LoadAcceptedSwap, MetadataStore, Outbox, PayoutAccountUsages,
+        RequestedRates, Retention, Retrieve, Saver, Stats, SwapFailures, SwapMessages,
+    },
+    ethereum::{Erc20Token, EtherQuantity, GasOracle},
+    expiries::ExpiryCalculator,
+    http_api::{
+        self,
+        api_version::{self, API_VERSION_HEADER, LATEST_API_VERSION},
+        deprecation,
+        routes::internal::Faucet,
+        ApiKeys, PayoutAccounts, Permission, TokenRegistry,
+    },
+    identity_reuse::IssueIdentities,
+    logging::LogLevels,
+    maintenance::Maintenance,
+    network::Network,
+    price_feed::PriceFeed,
+    redeem_destinations::RedeemDestination,
+    seed::{ImportSwapSecrets, SwapSeed},
+    standby::Standby,
+    swap_protocols::{
+        self,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::state_store::StateStore,
+        FeeAccounting, HtlcFinder, HtlcScanner, InclusionProver, LedgerEventsCreator, SwapEvents,
+        SwapId, SwapTasks,
+    },
+    swap_tracing::{SwapTracing, TRACEPARENT_HEADER},
+    watch_only::WatchOnly,
 };
-use libp2p::PeerId;
+use libp2p::{identity, PeerId};
 use tokio::executor::Executor;
 use warp::{self, filters::BoxedFilter, Filter, Reply};
 
@@ -20,60 +49,237 @@ pub fn new_action_link(id: &SwapId, action: &str) -> String {
     format!("{}/{}", swap_path(*id), action)
 }
 
+#[allow(clippy::type_repetition_in_bounds, clippy::too_many_arguments)]
 pub fn create<
     D: Clone
         + StateStore
         + Executor
         + Network
-        + SendRequest
         + SwapSeed
         + DetermineTypes
         + Retrieve
         + LedgerEventsCreator
-        + Saver,
+        + Saver
+        + Enqueuer
+        + Outbox
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + Stats
+        + ActionHistory
+        + GasOracle
+        + HtlcFinder
+        + SwapTasks
+        + SwapEvents
+        + RedeemDestination
+        + PayoutAccountUsages
+        + AutoRefunds
+        + MetadataStore
+        + SwapMessages
+        + ConstructedTransactions
+        + RequestedRates
+        + IssuedIdentities
+        + IssueIdentities
+        + HtlcScanner
+        + InclusionProver
+        + Importer
+        + ImportSwapSecrets
+        + SwapTracing
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>
+        + Backup,
 >(
-    peer_id: PeerId,
+    key_pair: identity::Keypair,
     dependencies: D,
     allowed_origins: &AllowedOrigins,
+    api_keys: ApiKeys,
+    backup_passphrase: Option<String>,
+    bitcoin_memo: Option<String>,
+    token_registry: TokenRegistry,
+    payout_accounts: PayoutAccounts,
+    expiry_calculator: ExpiryCalculator,
+    auto_refund_enabled: bool,
+    faucet: Faucet,
+    maintenance: Maintenance,
+    clock_monitor: ClockMonitor,
+    block_lag_monitor: BlockLagMonitor,
+    price_feed: PriceFeed,
+    expiry_margin: u32,
+    log_levels: LogLevels,
 ) -> BoxedFilter<(impl Reply,)> {
     let swaps = warp::path(http_api::PATH);
     let rfc003 = swaps.and(warp::path(RFC003));
+    let peer_id = PeerId::from(key_pair.public());
     let peer_id = warp::any().map(move || peer_id.clone());
+    let key_pair = warp::any().map(move || key_pair.clone());
     let empty_json_body = warp::any().map(|| serde_json::json!({}));
     let dependencies = warp::any().map(move || dependencies.clone());
+    let backup_passphrase = warp::any().map(move || backup_passphrase.clone());
+    let bitcoin_memo = warp::any().map(move || bitcoin_memo.clone());
+    let token_registry = warp::any().map(move || token_registry.clone());
+    let payout_accounts = warp::any().map(move || payout_accounts.clone());
+    let expiry_calculator = warp::any().map(move || expiry_calculator.clone());
+    let auto_refund_enabled = warp::any().map(move || auto_refund_enabled);
+    let faucet = warp::any().map(move || faucet.clone());
+    let maintenance = warp::any().map(move || maintenance.clone());
+    let clock_monitor = warp::any().map(move || clock_monitor.clone());
+    let block_lag_monitor = warp::any().map(move || block_lag_monitor.clone());
+    let price_feed = warp::any().map(move || price_feed.clone());
+    let expiry_margin = warp::any().map(move || expiry_margin);
+    let log_levels = warp::any().map(move || log_levels.clone());
+    let read_only = http_api::authorization::require(api_keys.clone(), Permission::ReadOnly);
+    let trade = http_api::authorization::require(api_keys, Permission::Trade);
 
-    let cors = warp::cors()
-        .allow_methods(vec!["GET", "POST"])
-        .allow_header("content-type");
-    let cors = match allowed_origins {
-        AllowedOrigins::None => cors.allow_origins(Vec::<&str>::new()),
-        AllowedOrigins::All => cors.allow_any_origin(),
-        AllowedOrigins::Some(hosts) => {
-            cors.allow_origins::<Vec<&str>>(hosts.iter().map(|host| host.as_str()).collect())
-        }
-    };
+    let cors = cors(allowed_origins);
 
     let preflight_cors_route = warp::options().map(warp::reply);
 
     let rfc003_post_swap = rfc003
         .and(warp::path::end())
         .and(warp::post2())
+        .and(trade.clone())
         .and(dependencies.clone())
+        .and(token_registry)
+        .and(payout_accounts.clone())
+        .and(expiry_calculator.clone())
+        .and(auto_refund_enabled)
+        .and(maintenance.clone())
         .and(warp::body::json())
+        .and(warp::header::optional::<String>(TRACEPARENT_HEADER))
         .and_then(http_api::routes::rfc003::post_swap);
 
     let rfc003_get_swap = rfc003
         .and(warp::get2())
         .and(dependencies.clone())
+        .and(clock_monitor.clone())
+        .and(block_lag_monitor.clone())
+        .and(price_feed.clone())
         .and(warp::path::param())
         .and(warp::path::end())
-        .and_then(http_api::routes::rfc003::get_swap);
+        .and(read_only.clone())
+        .and_then(http_api::routes::rfc003::get_swap)
+        .with(deprecation::headers(deprecation::Route::GetSwap));
+
+    let rfc003_get_swap_sub_resource = rfc003
+        .and(warp::get2())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path::param::<http_api::SwapSubResource>())
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::rfc003::get_swap_sub_resource)
+        .with(deprecation::headers(deprecation::Route::GetSwapSubResource));
+
+    let patch_metadata = swaps
+        .and(warp::patch())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("metadata"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(warp::body::json())
+        .and_then(http_api::routes::metadata::patch_metadata);
+
+    let get_messages = swaps
+        .and(warp::get2())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("messages"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::messages::get_messages);
+
+    let get_export = swaps
+        .and(warp::get2())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("export"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(warp::query::<http_api::routes::export::ExportQuery>())
+        .and_then(http_api::routes::export::get_export);
+
+    let post_import = swaps
+        .and(warp::post2())
+        .and(warp::path("import"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(dependencies.clone())
+        .and(expiry_margin)
+        .and(warp::body::json())
+        .and_then(http_api::routes::export::post_import);
+
+    let verify_transaction = swaps
+        .and(warp::post2())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("verify-transaction"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(warp::body::json())
+        .and_then(http_api::routes::rfc003::verify_transaction);
 
     let get_swaps = swaps
         .and(warp::get2())
         .and(warp::path::end())
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and(clock_monitor)
+        .and(block_lag_monitor.clone())
+        .and(price_feed.clone())
+        .and(warp::query::<http_api::routes::index::GetSwapsQuery>())
+        .and_then(http_api::routes::index::get_swaps)
+        .with(deprecation::headers(deprecation::Route::GetSwaps));
+
+    let rfc003_get_action_history = rfc003
+        .and(warp::get2())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("actions"))
+        .and(warp::path("history"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::rfc003::get_action_history);
+
+    let rfc003_get_counterparty = rfc003
+        .and(warp::get2())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("counterparty"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::rfc003::get_counterparty);
+
+    let rfc003_get_recovery = rfc003
+        .and(warp::get2())
+        .and(dependencies.clone())
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("recovery"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(warp::query::<http_api::action::ActionExecutionParameters>())
+        .and_then(http_api::routes::rfc003::get_recovery);
+
+    let rfc003_get_receipt = rfc003
+        .and(warp::get2())
+        .and(dependencies.clone())
+        .and(key_pair)
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("receipt"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::rfc003::get_receipt);
+
+    let rfc003_get_proof = rfc003
+        .and(warp::get2())
         .and(dependencies.clone())
-        .and_then(http_api::routes::index::get_swaps);
+        .and(warp::path::param::<SwapId>())
+        .and(warp::path("proof"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::rfc003::get_proof);
 
     let rfc003_action = warp::method()
         .and(rfc003)
@@ -82,32 +288,291 @@ pub fn create<
             swap_protocols::rfc003::actions::ActionKind,
         >())
         .and(warp::path::end())
+        .and(trade.clone())
         .and(warp::query::<http_api::action::ActionExecutionParameters>())
         .and(dependencies.clone())
+        .and(bitcoin_memo)
+        .and(payout_accounts.clone())
+        .and(price_feed)
         .and(warp::body::json().or(empty_json_body).unify())
-        .and_then(http_api::routes::rfc003::action);
+        .and_then(http_api::routes::rfc003::action)
+        .with(deprecation::headers(deprecation::Route::Action));
 
     let get_peers = warp::get2()
         .and(warp::path("peers"))
         .and(warp::path::end())
+        .and(read_only.clone())
         .and(dependencies.clone())
         .and_then(http_api::routes::peers::get_peers);
 
     let get_info = warp::get2()
         .and(warp::path::end())
+        .and(read_only.clone())
         .and(peer_id.clone())
         .and(dependencies.clone())
         .and_then(http_api::routes::index::get_info);
 
-    preflight_cors_route
-        .or(rfc003_get_swap)
+    let get_backup = warp::get2()
+        .and(warp::path("internal"))
+        .and(warp::path("backup"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(dependencies.clone())
+        .and(backup_passphrase)
+        .and_then(http_api::routes::internal::get_backup);
+
+    let get_metrics = warp::get2()
+        .and(warp::path("internal"))
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and(block_lag_monitor)
+        .and_then(http_api::routes::internal::get_metrics);
+
+    let get_outbound = warp::get2()
+        .and(warp::path("internal"))
+        .and(warp::path("outbound"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and_then(http_api::routes::internal::get_outbound);
+
+    let post_htlc_vectors = warp::post2()
+        .and(warp::path("internal"))
+        .and(warp::path("htlc-vectors"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(warp::body::json())
+        .and_then(http_api::routes::internal::post_htlc_vectors);
+
+    let post_faucet = warp::post2()
+        .and(warp::path("internal"))
+        .and(warp::path("faucet"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(faucet)
+        .and(warp::body::json())
+        .and_then(http_api::routes::internal::post_faucet);
+
+    let post_maintenance = warp::post2()
+        .and(warp::path("internal"))
+        .and(warp::path("maintenance"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(maintenance)
+        .and(warp::query::<http_api::routes::internal::MaintenanceQuery>())
+        .and_then(http_api::routes::internal::post_maintenance);
+
+    let get_log_level = warp::get2()
+        .and(warp::path("internal"))
+        .and(warp::path("log-level"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(log_levels.clone())
+        .and_then(http_api::routes::internal::get_log_level);
+
+    let put_log_level = warp::put2()
+        .and(warp::path("internal"))
+        .and(warp::path("log-level"))
+        .and(warp::path::end())
+        .and(trade.clone())
+        .and(log_levels)
+        .and(warp::body::json())
+        .and_then(http_api::routes::internal::put_log_level);
+
+    let get_fees_report = warp::get2()
+        .and(warp::path("reports"))
+        .and(warp::path("fees"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and(warp::query::<http_api::routes::reports::FeesReportQuery>())
+        .and_then(http_api::routes::reports::get_fees);
+
+    let get_stats = warp::get2()
+        .and(warp::path("stats"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and_then(http_api::routes::stats::get_stats);
+
+    let get_payout_accounts = warp::get2()
+        .and(warp::path("payout-accounts"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(dependencies.clone())
+        .and(payout_accounts)
+        .and_then(http_api::routes::payout_accounts::get_payout_accounts);
+
+    let get_expiry_recommendation = warp::get2()
+        .and(warp::path("expiry-recommendation"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(expiry_calculator)
+        .and(warp::query::<
+            http_api::routes::expiry_recommendation::ExpiryRecommendationQuery,
+        >())
+        .and_then(http_api::routes::expiry_recommendation::get_expiry_recommendation);
+
+    let get_ui = warp::get2()
+        .and(warp::path("ui"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and_then(http_api::routes::ui::get_ui);
+
+    let v1 = rfc003_get_swap
+        .or(rfc003_get_swap_sub_resource)
+        .or(rfc003_get_action_history)
+        .or(rfc003_get_counterparty)
+        .or(rfc003_get_recovery)
+        .or(rfc003_get_receipt)
+        .or(rfc003_get_proof)
         .or(rfc003_post_swap)
         .or(rfc003_action)
+        .or(patch_metadata)
+        .or(get_messages)
+        .or(get_export)
+        .or(post_import)
+        .or(verify_transaction)
         .or(get_swaps)
         .or(get_peers)
         .or(get_info)
+        .or(get_backup)
+        .or(get_metrics)
+        .or(get_outbound)
+        .or(post_htlc_vectors)
+        .or(post_faucet)
+        .or(post_maintenance)
+        .or(get_log_level)
+        .or(put_log_level)
+        .or(get_fees_report)
+        .or(get_stats)
+        .or(get_payout_accounts)
+        .or(get_expiry_recommendation)
+        .or(get_ui)
+        .boxed();
+
+    preflight_cors_route
+        .or(api_version::versioned(v1))
         .recover(http_api::unpack_problem)
+        .with(api_version_header())
         .with(warp::log("http"))
         .with(cors)
         .boxed()
 }
+
+/// The routes of a standby, all others are only served once it is promoted.
+pub fn create_standby(
+    standby: Standby,
+    allowed_origins: &AllowedOrigins,
+    api_keys: ApiKeys,
+) -> BoxedFilter<(impl Reply,)> {
+    let standby = warp::any().map(move || standby.clone());
+    let read_only = http_api::authorization::require(api_keys.clone(), Permission::ReadOnly);
+    let trade = http_api::authorization::require(api_keys, Permission::Trade);
+    let cors = cors(allowed_origins);
+
+    let preflight_cors_route = warp::options().map(warp::reply);
+
+    let get_standby = warp::get2()
+        .and(warp::path("internal"))
+        .and(warp::path("standby"))
+        .and(warp::path::end())
+        .and(read_only)
+        .and(standby.clone())
+        .and_then(http_api::routes::internal::get_standby);
+
+    let post_promote = warp::post2()
+        .and(warp::path("internal"))
+        .and(warp::path("standby"))
+        .and(warp::path("promote"))
+        .and(warp::path::end())
+        .and(trade)
+        .and(standby)
+        .and_then(http_api::routes::internal::post_promote);
+
+    let v1 = get_standby.or(post_promote).boxed();
+
+    preflight_cors_route
+        .or(api_version::versioned(v1))
+        .recover(http_api::unpack_problem)
+        .with(api_version_header())
+        .with(warp::log("http"))
+        .with(cors)
+        .boxed()
+}
+
+pub fn create_watch_only(
+    watch_only: WatchOnly,
+    allowed_origins: &AllowedOrigins,
+    api_keys: ApiKeys,
+) -> BoxedFilter<(impl Reply,)> {
+    let watch_only = warp::any().map(move || watch_only.clone());
+    let read_only = http_api::authorization::require(api_keys.clone(), Permission::ReadOnly);
+    let trade = http_api::authorization::require(api_keys, Permission::Trade);
+    let cors = cors(allowed_origins);
+
+    let preflight_cors_route = warp::options().map(warp::reply);
+
+    let post_watch = warp::post2()
+        .and(warp::path("watch"))
+        .and(warp::path::end())
+        .and(trade)
+        .and(watch_only.clone())
+        .and(warp::body::json())
+        .and_then(http_api::routes::watch::post_watch);
+
+    let get_watched_swaps = warp::get2()
+        .and(warp::path("watch"))
+        .and(warp::path::end())
+        .and(read_only.clone())
+        .and(watch_only.clone())
+        .and_then(http_api::routes::watch::get_watched_swaps);
+
+    let get_watched_swap = warp::get2()
+        .and(warp::path("watch"))
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(read_only)
+        .and(watch_only)
+        .and_then(http_api::routes::watch::get_watched_swap);
+
+    let v1 = post_watch
+        .or(get_watched_swaps)
+        .or(get_watched_swap)
+        .boxed();
+
+    preflight_cors_route
+        .or(api_version::versioned(v1))
+        .recover(http_api::unpack_problem)
+        .with(api_version_header())
+        .with(warp::log("http"))
+        .with(cors)
+        .boxed()
+}
+
+/// Problems are served with the latest version as well, such that every
+/// response says which version it was served with.
+fn api_version_header() -> warp::filters::reply::WithHeader {
+    warp::reply::with::header(API_VERSION_HEADER, LATEST_API_VERSION.to_string())
+}
+
+fn cors(allowed_origins: &AllowedOrigins) -> warp::filters::cors::Cors {
+    let cors = warp::cors()
+        .allow_methods(vec!["GET", "POST", "PATCH", "PUT"])
+        .allow_headers(vec![
+            "content-type",
+            "authorization",
+            API_VERSION_HEADER,
+            TRACEPARENT_HEADER,
+        ]);
+
+    match allowed_origins {
+        AllowedOrigins::None => cors.allow_origins(Vec::<&str>::new()),
+        AllowedOrigins::All => cors.allow_any_origin(),
+        AllowedOrigins::Some(hosts) => {
+            cors.allow_origins::<Vec<&str>>(hosts.iter().map(|host| host.as_str()).collect())
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::expiries::{Chain, ExpiryCalculator};
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use serde::Deserialize;
+use warp::{Rejection, Reply};
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct ExpiryRecommendationQuery {
+    alpha: Chain,
+    beta: Chain,
+}
+
+pub fn get_expiry_recommendation(
+    expiry_calculator: ExpiryCalculator,
+    query: ExpiryRecommendationQuery,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        let expiries = expiry_calculator.recommend(query.alpha, query.beta).await;
+
+        Ok::<_, Rejection>(warp::reply::json(&expiries))
+    }
+    .boxed()
+    .compat()
+}
@@ -0,0 +1,473 @@
+use crate::{
+    db::{DetermineTypes, Import, LoadAcceptedSwap, Retention, Retrieve, Swap, SwapFailures},
+    derivation::Identities,
+    ethereum::{Erc20Token, EtherQuantity},
+    http_api::{
+        problem,
+        route_factory::swap_path,
+        routes::{
+            into_rejection,
+            rfc003::{handlers::post_swap::UnsupportedSwap, LedgerState},
+        },
+        Http, HttpAsset, HttpLedger,
+    },
+    load_swaps,
+    seed::{ImportSwapSecrets, Seed, SwapSeed},
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{self, state_store::StateStore, Accept, Ledger, Request, SecretHash, SwapSecrets},
+        FeeAccounting, HashFunction, HtlcScanner, LedgerEventsCreator, Role, SwapEvents, SwapId,
+        SwapTasks,
+    },
+    timestamp::Timestamp,
+};
+use bitcoin::secp256k1::SecretKey;
+use chrono::NaiveDateTime;
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tokio::executor::Executor;
+use warp::{http::StatusCode, Rejection, Reply};
+
+/// Bumped whenever an export can no longer be imported by older versions.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Everything needed to resume an accepted swap on another node.
+///
+/// The HTLCs are scanned for from `accepted_at` on after an import, hence the
+/// ledger states are only informational.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SwapExport {
+    version: u32,
+    id: SwapId,
+    role: String,
+    counterparty: Http<PeerId>,
+    alpha_ledger: HttpLedger,
+    beta_ledger: HttpLedger,
+    alpha_asset: HttpAsset,
+    beta_asset: HttpAsset,
+    hash_function: HashFunction,
+    /// The identities are ledger specific, hence they can only be
+    /// deserialized once the ledger is known.
+    alpha_ledger_refund_identity: serde_json::Value,
+    alpha_ledger_redeem_identity: serde_json::Value,
+    beta_ledger_redeem_identity: serde_json::Value,
+    beta_ledger_refund_identity: serde_json::Value,
+    alpha_expiry: Timestamp,
+    beta_expiry: Timestamp,
+    secret_hash: SecretHash,
+    accepted_at: NaiveDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secrets: Option<ExportedSecrets>,
+    #[serde(default)]
+    alpha_ledger_state: serde_json::Value,
+    #[serde(default)]
+    beta_ledger_state: serde_json::Value,
+}
+
+/// The secrets of the swap, without them the importing node can only follow
+/// the swap but not act on it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedSecrets {
+    seed: Seed,
+    /// Hex encoded, only present if the keys were issued instead of derived
+    /// from the seed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    redeem_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refund_key: Option<String>,
+}
+
+impl From<SwapSecrets> for ExportedSecrets {
+    fn from(secrets: SwapSecrets) -> Self {
+        let identities = secrets.identities();
+
+        ExportedSecrets {
+            seed: secrets.seed(),
+            redeem_key: identities.map(|identities| hex::encode(&identities.redeem[..])),
+            refund_key: identities.map(|identities| hex::encode(&identities.refund[..])),
+        }
+    }
+}
+
+impl ExportedSecrets {
+    fn into_swap_secrets(self) -> anyhow::Result<SwapSecrets> {
+        let identities = match (self.redeem_key, self.refund_key) {
+            (Some(redeem), Some(refund)) => Some(Identities {
+                redeem: SecretKey::from_slice(&hex::decode(redeem)?)?,
+                refund: SecretKey::from_slice(&hex::decode(refund)?)?,
+            }),
+            (None, None) => None,
+            _ => anyhow::bail!("redeem_key and refund_key must be given together"),
+        };
+
+        Ok(SwapSecrets::new(self.seed, identities))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    /// The secrets allow to take funds out of the HTLCs, they are only
+    /// exported if asked for.
+    #[serde(default)]
+    include_secrets: bool,
+}
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+pub enum InvalidExport {
+    #[error("only accepted swaps can be exported")]
+    NotAccepted,
+    #[error(
+        "version {0} of the export format is not supported, expected version {}",
+        EXPORT_VERSION
+    )]
+    UnsupportedVersion(u32),
+    #[error("swap {0} already exists on this node")]
+    SwapExists(SwapId),
+}
+
+#[derive(Debug, Serialize)]
+struct ImportedSwap {
+    id: SwapId,
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_export<D>(
+    dependencies: D,
+    id: SwapId,
+    query: ExportQuery,
+) -> impl Future<Item = impl Reply, Error = Rejection>
+where
+    D: Retrieve
+        + DetermineTypes
+        + StateStore
+        + SwapSeed
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    handle_get_export(dependencies, id, query)
+        .boxed()
+        .compat()
+        .map(|export| warp::reply::json(&export))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn post_import<D>(
+    dependencies: D,
+    expiry_margin: u32,
+    body: serde_json::Value,
+) -> impl Future<Item = impl Reply, Error = Rejection>
+where
+    D: StateStore
+        + Executor
+        + Clone
+        + SwapSeed
+        + LedgerEventsCreator
+        + Retrieve
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + HtlcScanner
+        + ImportSwapSecrets
+        + Import<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + Import<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + Import<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + Import<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    handle_post_import(dependencies, expiry_margin, body)
+        .boxed()
+        .compat()
+        .map(|id| {
+            let body = warp::reply::json(&ImportedSwap { id });
+            let response = warp::reply::with_header(body, "Location", swap_path(id));
+
+            warp::reply::with_status(response, StatusCode::CREATED)
+        })
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+async fn handle_get_export<D>(
+    dependencies: D,
+    id: SwapId,
+    query: ExportQuery,
+) -> anyhow::Result<SwapExport>
+where
+    D: Retrieve
+        + DetermineTypes
+        + StateStore
+        + SwapSeed
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let swap = Retrieve::get(&dependencies, &id).await?;
+    let types = DetermineTypes::determine_types(&dependencies, &id).await?;
+    let secrets = if query.include_secrets {
+        Some(ExportedSecrets::from(SwapSeed::swap_secrets(
+            &dependencies,
+            id,
+            swap.role,
+        )))
+    } else {
+        None
+    };
+
+    with_swap_types!(types, {
+        let state = StateStore::get::<ROLE>(&dependencies, &id)?
+            .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", id))?;
+        match state.swap_communication {
+            rfc003::SwapCommunication::Accepted { .. } => {}
+            _ => return Err(InvalidExport::NotAccepted.into()),
+        }
+
+        let (request, accept, accepted_at) =
+            LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(&dependencies, &id).await?;
+
+        Ok(SwapExport {
+            version: EXPORT_VERSION,
+            id,
+            role: swap.role.to_string(),
+            counterparty: Http(swap.counterparty),
+            alpha_ledger: HttpLedger::from(request.alpha_ledger),
+            beta_ledger: HttpLedger::from(request.beta_ledger),
+            alpha_asset: HttpAsset::from(request.alpha_asset),
+            beta_asset: HttpAsset::from(request.beta_asset),
+            hash_function: request.hash_function,
+            alpha_ledger_refund_identity: serde_json::to_value(
+                request.alpha_ledger_refund_identity,
+            )?,
+            alpha_ledger_redeem_identity: serde_json::to_value(
+                accept.alpha_ledger_redeem_identity,
+            )?,
+            beta_ledger_redeem_identity: serde_json::to_value(request.beta_ledger_redeem_identity)?,
+            beta_ledger_refund_identity: serde_json::to_value(accept.beta_ledger_refund_identity)?,
+            alpha_expiry: request.alpha_expiry,
+            beta_expiry: request.beta_expiry,
+            secret_hash: request.secret_hash,
+            accepted_at,
+            secrets,
+            alpha_ledger_state: serde_json::to_value(LedgerState::from(state.alpha_ledger_state))?,
+            beta_ledger_state: serde_json::to_value(LedgerState::from(state.beta_ledger_state))?,
+        })
+    })
+}
+
+async fn handle_post_import<D>(
+    dependencies: D,
+    expiry_margin: u32,
+    body: serde_json::Value,
+) -> anyhow::Result<SwapId>
+where
+    D: StateStore
+        + Executor
+        + Clone
+        + SwapSeed
+        + LedgerEventsCreator
+        + Retrieve
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + HtlcScanner
+        + ImportSwapSecrets
+        + Import<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + Import<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + Import<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + Import<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let mut export = serde_json::from_value::<SwapExport>(body)?;
+    if export.version != EXPORT_VERSION {
+        return Err(InvalidExport::UnsupportedVersion(export.version).into());
+    }
+
+    let id = export.id;
+    if Retrieve::get(&dependencies, &id).await.is_ok() {
+        return Err(InvalidExport::SwapExists(id).into());
+    }
+
+    let role = Role::from_str(&export.role)
+        .map_err(|_| anyhow::anyhow!("unknown role {}", export.role))?;
+    // Parsed up front so that nothing is imported if the secrets are invalid.
+    let secrets = export
+        .secrets
+        .take()
+        .map(ExportedSecrets::into_swap_secrets)
+        .transpose()?;
+
+    match (
+        export.alpha_ledger.clone(),
+        export.beta_ledger.clone(),
+        export.alpha_asset.clone(),
+        export.beta_asset.clone(),
+    ) {
+        (
+            HttpLedger::Bitcoin(alpha_ledger),
+            HttpLedger::Ethereum(beta_ledger),
+            HttpAsset::Bitcoin(alpha_asset),
+            HttpAsset::Ether(beta_asset),
+        ) => {
+            import(
+                &dependencies,
+                export,
+                role,
+                (alpha_ledger, beta_ledger, alpha_asset, beta_asset),
+            )
+            .await?
+        }
+        (
+            HttpLedger::Ethereum(alpha_ledger),
+            HttpLedger::Bitcoin(beta_ledger),
+            HttpAsset::Ether(alpha_asset),
+            HttpAsset::Bitcoin(beta_asset),
+        ) => {
+            import(
+                &dependencies,
+                export,
+                role,
+                (alpha_ledger, beta_ledger, alpha_asset, beta_asset),
+            )
+            .await?
+        }
+        (
+            HttpLedger::Bitcoin(alpha_ledger),
+            HttpLedger::Ethereum(beta_ledger),
+            HttpAsset::Bitcoin(alpha_asset),
+            HttpAsset::Erc20(beta_asset),
+        ) => {
+            import(
+                &dependencies,
+                export,
+                role,
+                (alpha_ledger, beta_ledger, alpha_asset, beta_asset),
+            )
+            .await?
+        }
+        (
+            HttpLedger::Ethereum(alpha_ledger),
+            HttpLedger::Bitcoin(beta_ledger),
+            HttpAsset::Erc20(alpha_asset),
+            HttpAsset::Bitcoin(beta_asset),
+        ) => {
+            import(
+                &dependencies,
+                export,
+                role,
+                (alpha_ledger, beta_ledger, alpha_asset, beta_asset),
+            )
+            .await?
+        }
+        _ => {
+            return Err(anyhow::Error::from(UnsupportedSwap {
+                alpha_ledger: export.alpha_ledger,
+                beta_ledger: export.beta_ledger,
+                alpha_asset: export.alpha_asset,
+                beta_asset: export.beta_asset,
+            }))
+        }
+    }
+
+    if let Some(secrets) = secrets {
+        dependencies.import_swap_secrets(id, secrets).await?;
+    }
+
+    load_swaps::restart_swap(&dependencies, id, expiry_margin).await?;
+
+    Ok(id)
+}
+
+async fn import<D, AL, BL, AA, BA>(
+    dependencies: &D,
+    export: SwapExport,
+    role: Role,
+    (alpha_ledger, beta_ledger, alpha_asset, beta_asset): (AL, BL, AA, BA),
+) -> anyhow::Result<()>
+where
+    D: Import<AL, BL, AA, BA>,
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+{
+    let request = Request {
+        swap_id: export.id,
+        alpha_ledger,
+        beta_ledger,
+        alpha_asset,
+        beta_asset,
+        hash_function: export.hash_function,
+        alpha_ledger_refund_identity: serde_json::from_value(export.alpha_ledger_refund_identity)?,
+        beta_ledger_redeem_identity: serde_json::from_value(export.beta_ledger_redeem_identity)?,
+        alpha_expiry: export.alpha_expiry,
+        beta_expiry: export.beta_expiry,
+        secret_hash: export.secret_hash,
+    };
+    let accept = Accept {
+        swap_id: export.id,
+        beta_ledger_refund_identity: serde_json::from_value(export.beta_ledger_refund_identity)?,
+        alpha_ledger_redeem_identity: serde_json::from_value(export.alpha_ledger_redeem_identity)?,
+    };
+    let swap = Swap::new(export.id, role, export.counterparty.0);
+
+    Import::<AL, BL, AA, BA>::import(dependencies, swap, request, accept, export.accepted_at).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn exported_secrets_yield_the_same_keys() {
+        let seed = Seed::from(*b"hello world, you are beautiful!!");
+        let identities = Identities {
+            redeem: SecretKey::from_slice(&[1u8; 32]).unwrap(),
+            refund: SecretKey::from_slice(&[2u8; 32]).unwrap(),
+        };
+        let secrets = SwapSecrets::new(seed, Some(identities));
+
+        let exported = serde_json::to_value(ExportedSecrets::from(secrets)).unwrap();
+        let imported = serde_json::from_value::<ExportedSecrets>(exported)
+            .unwrap()
+            .into_swap_secrets()
+            .unwrap();
+
+        assert_that(&imported.seed()).is_equal_to(seed);
+        assert_that(&imported.identities()).is_equal_to(Some(identities));
+    }
+
+    #[test]
+    fn secrets_without_both_keys_are_rejected() {
+        let exported = serde_json::json!({
+            "seed": "68656c6c6f20776f726c642c20796f75206172652062656175746966756c2121",
+            "redeem_key": "0101010101010101010101010101010101010101010101010101010101010101"
+        });
+
+        let imported = serde_json::from_value::<ExportedSecrets>(exported)
+            .unwrap()
+            .into_swap_secrets();
+
+        assert_that(&imported).is_err();
+    }
+}
@@ -1,20 +1,124 @@
 use crate::{
-    db::{DetermineTypes, Retrieve},
-    http_api::swap_resource::{build_rfc003_siren_entity, IncludeState},
-    swap_protocols::rfc003::state_store::StateStore,
+    block_lag::BlockLagMonitor,
+    clock_skew::ClockMonitor,
+    db::{DetermineTypes, IssuedIdentities, MetadataStore, RequestedRates, Retrieve, SwapFailures},
+    http_api::swap_resource::{
+        build_rfc003_siren_entity, rfc003_swap_status, IncludeState, SwapStatus,
+    },
+    price_feed::PriceFeed,
+    swap_protocols::{rfc003::state_store::StateStore, SwapTasks},
 };
+use serde::Deserialize;
 
-pub async fn handle_get_swaps<D: DetermineTypes + Retrieve + StateStore>(
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct GetSwapsQuery {
+    /// Only list swaps with this status.
+    status: Option<SwapStatus>,
+    /// Only list swaps that were given this external id.
+    external_id: Option<String>,
+}
+
+pub async fn handle_get_swaps<
+    D: DetermineTypes
+        + Retrieve
+        + StateStore
+        + SwapFailures
+        + MetadataStore
+        + RequestedRates
+        + IssuedIdentities
+        + SwapTasks,
+>(
     dependencies: D,
+    clock_monitor: ClockMonitor,
+    block_lag_monitor: BlockLagMonitor,
+    price_feed: PriceFeed,
+    query: GetSwapsQuery,
 ) -> anyhow::Result<siren::Entity> {
     let mut entity = siren::Entity::default().with_class_member("swaps");
+    let quotes = price_feed.quotes().await;
+
+    let with_external_id = match &query.external_id {
+        Some(external_id) => {
+            Some(MetadataStore::swaps_with_external_id(&dependencies, external_id).await?)
+        }
+        None => None,
+    };
 
     for swap in Retrieve::all(&dependencies).await?.into_iter() {
+        if let Some(with_external_id) = &with_external_id {
+            if !with_external_id.contains(&swap.swap_id) {
+                continue;
+            }
+        }
+
         let types = dependencies.determine_types(&swap.swap_id).await?;
+        let failure = SwapFailures::swap_failure(&dependencies, &swap.swap_id).await?;
 
-        let sub_entity = build_rfc003_siren_entity(&dependencies, swap, types, IncludeState::No)?;
+        if let Some(status) = query.status {
+            if rfc003_swap_status(&dependencies, &swap, types, &failure)? != status {
+                continue;
+            }
+        }
+
+        let metadata = MetadataStore::swap_metadata(&dependencies, &swap.swap_id).await?;
+        let rate = RequestedRates::requested_rate(&dependencies, &swap.swap_id).await?;
+        let reused_identities =
+            IssuedIdentities::reused_identities(&dependencies, &swap.swap_id).await?;
+        let clock_skews = clock_monitor.skews_of(&swap.swap_id);
+        let stale_ledgers =
+            block_lag_monitor.stale_ledgers(&[types.alpha_ledger, types.beta_ledger]);
+        let degraded_tasks = SwapTasks::degraded(&dependencies, &swap.swap_id);
+        let sub_entity = build_rfc003_siren_entity(
+            &dependencies,
+            swap,
+            types,
+            failure,
+            metadata,
+            rate,
+            reused_identities,
+            clock_skews,
+            stale_ledgers,
+            degraded_tasks,
+            quotes.as_ref(),
+            IncludeState::No,
+        )?;
         entity.push_sub_entity(siren::SubEntity::from_entity(sub_entity, &["item"]));
     }
 
     Ok(entity)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_is_parsed_from_the_query_string() {
+        let query = serde_urlencoded::from_str::<GetSwapsQuery>("status=INTERNAL_FAILURE");
+
+        assert_eq!(
+            query,
+            Ok(GetSwapsQuery {
+                status: Some(SwapStatus::InternalFailure),
+                external_id: None,
+            })
+        );
+        assert_eq!(
+            serde_urlencoded::from_str::<GetSwapsQuery>(""),
+            Ok(GetSwapsQuery::default())
+        );
+    }
+
+    #[test]
+    fn external_id_is_parsed_from_the_query_string() {
+        let query = serde_urlencoded::from_str::<GetSwapsQuery>("external_id=order-42");
+
+        assert_eq!(
+            query,
+            Ok(GetSwapsQuery {
+                status: None,
+                external_id: Some(String::from("order-42")),
+            })
+        );
+    }
+}
@@ -1,3 +1,3 @@
 mod get_swaps;
 
-pub use self::get_swaps::handle_get_swaps;
+pub use self::get_swaps::{handle_get_swaps, GetSwapsQuery};
@@ -1,11 +1,16 @@
 mod handlers;
 
+pub use self::handlers::GetSwapsQuery;
+
 use self::handlers::handle_get_swaps;
 use crate::{
-    db::{DetermineTypes, Retrieve},
+    block_lag::BlockLagMonitor,
+    clock_skew::ClockMonitor,
+    db::{DetermineTypes, IssuedIdentities, MetadataStore, RequestedRates, Retrieve, SwapFailures},
     http_api::{problem, routes::into_rejection, Http},
     network::Network,
-    swap_protocols::rfc003::state_store::StateStore,
+    price_feed::PriceFeed,
+    swap_protocols::{rfc003::state_store::StateStore, SwapTasks},
 };
 use futures::Future;
 use futures_core::future::{FutureExt, TryFutureExt};
@@ -20,29 +25,56 @@ pub struct InfoResource {
 }
 
 #[allow(clippy::needless_pass_by_value)]
-pub fn get_info<D: Network>(id: PeerId, dependencies: D) -> Result<impl Reply, Rejection> {
-    let listen_addresses: Vec<Multiaddr> = Network::listen_addresses(&dependencies).to_vec();
-
-    Ok(warp::reply::json(&InfoResource {
-        id: Http(id),
-        listen_addresses,
-    }))
-}
-
-#[allow(clippy::needless_pass_by_value)]
-pub fn get_swaps<D: DetermineTypes + Retrieve + StateStore>(
+pub fn get_info<D: Network>(
+    id: PeerId,
     dependencies: D,
 ) -> impl Future<Item = impl Reply, Error = Rejection> {
-    handle_get_swaps(dependencies)
+    async move { Network::listen_addresses(&dependencies).await }
         .boxed()
         .compat()
-        .map(|swaps| {
-            Ok(warp::reply::with_header(
-                warp::reply::json(&swaps),
-                "content-type",
-                "application/vnd.siren+json",
-            ))
+        .map(move |listen_addresses| {
+            warp::reply::json(&InfoResource {
+                id: Http(id),
+                listen_addresses,
+            })
         })
         .map_err(problem::from_anyhow)
         .map_err(into_rejection)
 }
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_swaps<
+    D: DetermineTypes
+        + Retrieve
+        + StateStore
+        + SwapFailures
+        + MetadataStore
+        + RequestedRates
+        + IssuedIdentities
+        + SwapTasks,
+>(
+    dependencies: D,
+    clock_monitor: ClockMonitor,
+    block_lag_monitor: BlockLagMonitor,
+    price_feed: PriceFeed,
+    query: GetSwapsQuery,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    handle_get_swaps(
+        dependencies,
+        clock_monitor,
+        block_lag_monitor,
+        price_feed,
+        query,
+    )
+    .boxed()
+    .compat()
+    .map(|swaps| {
+        Ok(warp::reply::with_header(
+            warp::reply::json(&swaps),
+            "content-type",
+            "application/vnd.siren+json",
+        ))
+    })
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
@@ -0,0 +1,95 @@
+use crate::{
+    bitcoind_rpc::BitcoindRpc,
+    btsieve::ethereum::Web3Connector,
+    ethereum::{self, EtherQuantity},
+    http_api::Http,
+    swap_protocols::ledger::ethereum::ChainId,
+};
+use serde::{Deserialize, Serialize};
+
+/// Funds addresses on test networks, from the wallet of the connected bitcoind
+/// and from the first account of the connected Ethereum node.
+#[derive(Clone, Debug)]
+pub struct Faucet {
+    bitcoind: Option<BitcoindRpc>,
+    bitcoin_network: bitcoin::Network,
+    web3: Web3Connector,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "ledger", rename_all = "lowercase")]
+pub enum FaucetBody {
+    Bitcoin {
+        address: bitcoin::Address,
+        quantity: Http<bitcoin::Amount>,
+    },
+    Ethereum {
+        address: ethereum::Address,
+        quantity: EtherQuantity,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct Funded {
+    transaction_id: String,
+}
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+pub enum FaucetUnavailable {
+    #[error("there is no faucet on mainnet")]
+    Mainnet,
+    #[error("bitcoin can only be sent if bitcoin.rpc_credentials are configured")]
+    BitcoindNotConfigured,
+}
+
+impl Faucet {
+    pub fn new(
+        bitcoind: Option<BitcoindRpc>,
+        bitcoin_network: bitcoin::Network,
+        web3: Web3Connector,
+    ) -> Self {
+        Self {
+            bitcoind,
+            bitcoin_network,
+            web3,
+        }
+    }
+
+    /// On regtest, a block is mined right away so that the funds can be spent.
+    pub async fn fund(&self, body: FaucetBody) -> anyhow::Result<Funded> {
+        match body {
+            FaucetBody::Bitcoin { address, quantity } => {
+                if self.bitcoin_network == bitcoin::Network::Bitcoin {
+                    return Err(FaucetUnavailable::Mainnet.into());
+                }
+                let bitcoind = self
+                    .bitcoind
+                    .as_ref()
+                    .ok_or(FaucetUnavailable::BitcoindNotConfigured)?;
+
+                let transaction_id = bitcoind.send_to_address(&address, quantity.0).await?;
+                if self.bitcoin_network == bitcoin::Network::Regtest {
+                    bitcoind.generate(1).await?;
+                }
+
+                Ok(Funded {
+                    transaction_id: transaction_id.to_string(),
+                })
+            }
+            FaucetBody::Ethereum { address, quantity } => {
+                if self.web3.chain_id().await? == ChainId::mainnet() {
+                    return Err(FaucetUnavailable::Mainnet.into());
+                }
+
+                let transaction_hash = self
+                    .web3
+                    .send_from_first_account(address, quantity.wei())
+                    .await?;
+
+                Ok(Funded {
+                    transaction_id: format!("{:#x}", transaction_hash),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    ethereum::{Bytes, Erc20Token, EtherQuantity},
+    http_api::{HttpAsset, HttpLedger},
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{state_machine::HtlcParams, Ledger, SecretHash},
+    },
+    timestamp::Timestamp,
+};
+use bitcoin::{Address, Amount};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct HtlcVectorsBody {
+    ledger: HttpLedger,
+    asset: HttpAsset,
+    /// The identities are ledger specific, hence they can only be
+    /// deserialized once the ledger is known.
+    redeem_identity: serde_json::Value,
+    refund_identity: serde_json::Value,
+    secret_hash: SecretHash,
+    expiry: Timestamp,
+}
+
+/// The HTLC cnd would use for the given parameters.
+///
+/// The address of an Ethereum HTLC depends on the account that deploys it,
+/// hence only its bytecode is given.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "ledger", rename_all = "lowercase")]
+pub enum HtlcVectors {
+    Bitcoin { script: String, address: Address },
+    Ethereum { bytecode: Bytes },
+}
+
+impl From<HtlcParams<Bitcoin, Amount>> for HtlcVectors {
+    fn from(htlc_params: HtlcParams<Bitcoin, Amount>) -> Self {
+        HtlcVectors::Bitcoin {
+            script: hex::encode(htlc_params.script().as_bytes()),
+            address: htlc_params.compute_address(),
+        }
+    }
+}
+
+impl From<HtlcParams<Ethereum, EtherQuantity>> for HtlcVectors {
+    fn from(htlc_params: HtlcParams<Ethereum, EtherQuantity>) -> Self {
+        HtlcVectors::Ethereum {
+            bytecode: htlc_params.bytecode(),
+        }
+    }
+}
+
+impl From<HtlcParams<Ethereum, Erc20Token>> for HtlcVectors {
+    fn from(htlc_params: HtlcParams<Ethereum, Erc20Token>) -> Self {
+        HtlcVectors::Ethereum {
+            bytecode: htlc_params.bytecode(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("no HTLC is defined for the given combination of ledger and asset")]
+pub struct UnsupportedHtlc;
+
+pub fn handle_post_htlc_vectors(body: serde_json::Value) -> anyhow::Result<HtlcVectors> {
+    let HtlcVectorsBody {
+        ledger,
+        asset,
+        redeem_identity,
+        refund_identity,
+        secret_hash,
+        expiry,
+    } = serde_json::from_value(body)?;
+
+    match (ledger, asset) {
+        (HttpLedger::Bitcoin(ledger), HttpAsset::Bitcoin(asset)) => {
+            Ok(HtlcVectors::from(htlc_params(
+                ledger,
+                asset,
+                redeem_identity,
+                refund_identity,
+                secret_hash,
+                expiry,
+            )?))
+        }
+        (HttpLedger::Ethereum(ledger), HttpAsset::Ether(asset)) => {
+            Ok(HtlcVectors::from(htlc_params(
+                ledger,
+                asset,
+                redeem_identity,
+                refund_identity,
+                secret_hash,
+                expiry,
+            )?))
+        }
+        (HttpLedger::Ethereum(ledger), HttpAsset::Erc20(asset)) => {
+            Ok(HtlcVectors::from(htlc_params(
+                ledger,
+                asset,
+                redeem_identity,
+                refund_identity,
+                secret_hash,
+                expiry,
+            )?))
+        }
+        _ => Err(anyhow::Error::from(UnsupportedHtlc)),
+    }
+}
+
+pub fn htlc_params<L: Ledger, A: Asset>(
+    ledger: L,
+    asset: A,
+    redeem_identity: serde_json::Value,
+    refund_identity: serde_json::Value,
+    secret_hash: SecretHash,
+    expiry: Timestamp,
+) -> anyhow::Result<HtlcParams<L, A>> {
+    Ok(HtlcParams {
+        asset,
+        ledger,
+        redeem_identity: serde_json::from_value(redeem_identity)?,
+        refund_identity: serde_json::from_value(refund_identity)?,
+        expiry,
+        secret_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    const PUBLIC_KEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    const SECRET_HASH: &str = "68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4cec";
+
+    #[test]
+    fn bitcoin_htlc_is_paid_to_a_p2wsh_address_of_the_requested_network() {
+        let body = serde_json::json!({
+            "ledger": { "name": "bitcoin", "network": "regtest" },
+            "asset": { "name": "bitcoin", "quantity": "100000000" },
+            "redeem_identity": PUBLIC_KEY,
+            "refund_identity": PUBLIC_KEY,
+            "secret_hash": SECRET_HASH,
+            "expiry": 2_000_000_000,
+        });
+
+        let vectors = handle_post_htlc_vectors(body).unwrap();
+
+        match vectors {
+            HtlcVectors::Bitcoin { script, address } => {
+                assert_that(&script.is_empty()).is_false();
+                assert_that(&address.network).is_equal_to(bitcoin::Network::Regtest);
+                assert_that(&address.script_pubkey().is_v0_p2wsh()).is_true();
+
+                let script = bitcoin::Script::from(hex::decode(script).unwrap());
+                assert_that(&bitcoin::Address::p2wsh(&script, bitcoin::Network::Regtest))
+                    .is_equal_to(address);
+            }
+            _ => panic!("expected a bitcoin HTLC"),
+        }
+    }
+
+    #[test]
+    fn ether_on_bitcoin_is_rejected() {
+        let body = serde_json::json!({
+            "ledger": { "name": "bitcoin", "network": "regtest" },
+            "asset": { "name": "ether", "quantity": "1000000000000000000" },
+            "redeem_identity": PUBLIC_KEY,
+            "refund_identity": PUBLIC_KEY,
+            "secret_hash": SECRET_HASH,
+            "expiry": 2_000_000_000,
+        });
+
+        let error = handle_post_htlc_vectors(body).unwrap_err();
+
+        assert_that(&error.is::<UnsupportedHtlc>()).is_true();
+    }
+}
@@ -0,0 +1,515 @@
+mod faucet;
+mod htlc_vectors;
+
+pub use self::{
+    faucet::{Faucet, FaucetUnavailable},
+    htlc_vectors::{htlc_params, HtlcVectors, UnsupportedHtlc},
+};
+
+use self::htlc_vectors::handle_post_htlc_vectors;
+use crate::{
+    backup::Backup,
+    block_lag::{BlockLagMonitor, LedgerLag},
+    db::{Outbox, UnansweredRequest},
+    http_api::{problem, routes::into_rejection, Http},
+    logging::{Levels, LogLevels},
+    maintenance::Maintenance,
+    network::Network,
+    standby::Standby,
+    swap_protocols::{SwapEvents, SwapId, SwapTasks},
+};
+use chrono::{NaiveDateTime, Utc};
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use libp2p::{Multiaddr, PeerId};
+use libp2p_comit::{frame::Direction, RequestKind, RequestMetrics, Unrecognized};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+use warp::{Rejection, Reply};
+
+#[derive(Debug, thiserror::Error)]
+#[error("backups are disabled because no passphrase is configured")]
+pub struct BackupNotConfigured;
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_backup<D: Backup>(
+    dependencies: D,
+    passphrase: Option<String>,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    handle_get_backup(dependencies, passphrase)
+        .boxed()
+        .compat()
+        .map(|encrypted_archive| {
+            warp::reply::with_header(
+                encrypted_archive,
+                "content-disposition",
+                "attachment; filename=\"cnd.backup\"",
+            )
+        })
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+async fn handle_get_backup<D: Backup>(
+    dependencies: D,
+    passphrase: Option<String>,
+) -> anyhow::Result<Vec<u8>> {
+    let passphrase = passphrase.ok_or(BackupNotConfigured)?;
+
+    let archive = dependencies.backup().await?;
+    let encrypted_archive = archive.encrypt(&passphrase, OsRng)?;
+
+    Ok(encrypted_archive)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_metrics<D: SwapTasks + SwapEvents + Network>(
+    dependencies: D,
+    block_lag_monitor: BlockLagMonitor,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        let latencies = Network::latencies(&dependencies).await?;
+        let unrecognized = Network::unrecognized(&dependencies).await?;
+        let request_metrics = Network::request_metrics(&dependencies).await?;
+        let unsaved_requests = Network::unsaved_requests(&dependencies).await?;
+
+        Ok::<_, anyhow::Error>(metrics(
+            dependencies.active_watchers(),
+            latencies,
+            dependencies.published_events(),
+            unrecognized,
+            request_metrics,
+            unsaved_requests,
+            block_lag_monitor.lags(),
+        ))
+    }
+    .boxed()
+    .compat()
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
+
+fn metrics(
+    active_watchers: usize,
+    latencies: Vec<(PeerId, Duration)>,
+    swap_events: BTreeMap<&'static str, u64>,
+    unrecognized: BTreeMap<Unrecognized, u64>,
+    request_metrics: BTreeMap<RequestKind, RequestMetrics>,
+    unsaved_requests: u64,
+    ledger_lags: Vec<LedgerLag>,
+) -> String {
+    let mut metrics = format!("watchers {}\n", active_watchers);
+    for (peer, latency) in latencies {
+        let _ = writeln!(
+            metrics,
+            "peer_latency_seconds{{peer_id=\"{}\"}} {}",
+            peer,
+            latency.as_secs_f64()
+        );
+    }
+    for (event, count) in swap_events {
+        let _ = writeln!(metrics, "swap_events_total{{type=\"{}\"}} {}", event, count);
+    }
+    for (unrecognized, count) in unrecognized {
+        let _ = match unrecognized {
+            Unrecognized::RequestType(request_type) => writeln!(
+                metrics,
+                "unrecognized_request_types_total{{type=\"{}\"}} {}",
+                request_type, count
+            ),
+            Unrecognized::MandatoryHeader(header) => writeln!(
+                metrics,
+                "unrecognized_mandatory_headers_total{{header=\"{}\"}} {}",
+                header, count
+            ),
+        };
+    }
+    for (kind, request_metrics) in request_metrics {
+        let _ = writeln!(
+            metrics,
+            "comit_requests_total{{direction=\"{}\",type=\"{}\"}} {}",
+            kind.direction.to_string().to_lowercase(),
+            kind.request_type,
+            request_metrics.count
+        );
+
+        // Only the responses to our own requests have a latency.
+        if kind.direction == Direction::Inbound {
+            continue;
+        }
+        let latency = request_metrics.latency;
+        for (upper_bound, count) in latency.buckets() {
+            let _ = writeln!(
+                metrics,
+                "comit_request_latency_seconds_bucket{{type=\"{}\",le=\"{}\"}} {}",
+                kind.request_type, upper_bound, count
+            );
+        }
+        let _ = writeln!(
+            metrics,
+            "comit_request_latency_seconds_bucket{{type=\"{}\",le=\"+Inf\"}} {}",
+            kind.request_type,
+            latency.count()
+        );
+        let _ = writeln!(
+            metrics,
+            "comit_request_latency_seconds_sum{{type=\"{}\"}} {}",
+            kind.request_type,
+            latency.sum()
+        );
+        let _ = writeln!(
+            metrics,
+            "comit_request_latency_seconds_count{{type=\"{}\"}} {}",
+            kind.request_type,
+            latency.count()
+        );
+    }
+    // Like the counters above, only present once something was counted.
+    if unsaved_requests > 0 {
+        let _ = writeln!(metrics, "unsaved_swap_requests_total {}", unsaved_requests);
+    }
+    for lag in ledger_lags {
+        let ledger = lag.ledger.to_string().to_lowercase();
+        if let Some(height) = lag.height {
+            let _ = writeln!(
+                metrics,
+                "ledger_block_height{{ledger=\"{}\"}} {}",
+                ledger, height
+            );
+        }
+        let _ = writeln!(
+            metrics,
+            "ledger_block_lag_seconds{{ledger=\"{}\"}} {}",
+            ledger, lag.lag_seconds
+        );
+    }
+
+    metrics
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutboundResource {
+    requests: Vec<OutboundRequest>,
+}
+
+/// A swap request we sent that the peer did not answer yet.
+#[derive(Debug, Serialize)]
+pub struct OutboundRequest {
+    swap_id: SwapId,
+    peer_id: Http<PeerId>,
+    address_hint: Option<Multiaddr>,
+    /// Seconds since the swap was created.
+    age_secs: u64,
+    /// How often the request was sent, including attempts that did not reach
+    /// the peer.
+    attempts: u32,
+}
+
+/// Lists the swaps that wait for the peer to answer their request, as
+/// opposed to waiting for ledger events.
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_outbound<D: Outbox>(
+    dependencies: D,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move { Outbox::unanswered_requests(&dependencies).await }
+        .boxed()
+        .compat()
+        .map(|requests| {
+            let now = Utc::now().naive_utc();
+
+            warp::reply::json(&OutboundResource {
+                requests: requests
+                    .into_iter()
+                    .map(|request| outbound_request(request, now))
+                    .collect(),
+            })
+        })
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+fn outbound_request(request: UnansweredRequest, now: NaiveDateTime) -> OutboundRequest {
+    // The clock may have been set back since the request was enqueued.
+    let age_secs = (now - request.enqueued_at)
+        .to_std()
+        .map(|age| age.as_secs())
+        .unwrap_or(0);
+
+    OutboundRequest {
+        swap_id: request.swap_id,
+        peer_id: Http(request.peer.peer_id),
+        address_hint: request.peer.address_hint,
+        age_secs,
+        attempts: request.attempts,
+    }
+}
+
+pub fn post_htlc_vectors(body: serde_json::Value) -> Result<impl Reply, Rejection> {
+    handle_post_htlc_vectors(body)
+        .map(|vectors| warp::reply::json(&vectors))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn post_faucet(
+    faucet: Faucet,
+    body: serde_json::Value,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        let body = serde_json::from_value(body)?;
+
+        faucet.fund(body).await
+    }
+    .boxed()
+    .compat()
+    .map(|funded| warp::reply::json(&funded))
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct MaintenanceQuery {
+    pub on: bool,
+}
+
+/// Turns maintenance mode on or off, new swaps are declined while it is on.
+#[allow(clippy::needless_pass_by_value)]
+pub fn post_maintenance(
+    maintenance: Maintenance,
+    query: MaintenanceQuery,
+) -> Result<impl Reply, Rejection> {
+    maintenance.set(query.on);
+
+    if query.on {
+        log::info!("Maintenance mode on, declining new swaps");
+    } else {
+        log::info!("Maintenance mode off, taking new swaps again");
+    }
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "on": maintenance.is_on()
+    })))
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_log_level(log_levels: LogLevels) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&log_levels.get()))
+}
+
+/// Changes the log levels until cnd is restarted, the configuration file is
+/// left as it is.
+#[allow(clippy::needless_pass_by_value)]
+pub fn put_log_level(log_levels: LogLevels, levels: Levels) -> Result<impl Reply, Rejection> {
+    log_levels.set(levels);
+
+    log::info!(
+        "Log level set to {} with module levels {:?}",
+        levels.level,
+        levels.modules
+    );
+
+    Ok(warp::reply::json(&log_levels.get()))
+}
+
+pub fn get_standby(standby: Standby) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&standby.replica()))
+}
+
+/// Responds with what the standby is about to take over, the HTTP API is
+/// restarted as the one of a regular node right after.
+pub fn post_promote(standby: Standby) -> Result<impl Reply, Rejection> {
+    let replica = standby
+        .promote()
+        .map_err(anyhow::Error::from)
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)?;
+
+    log::info!(
+        "Promoted via HTTP API, taking over {} active swaps",
+        replica.active_swaps.len()
+    );
+
+    Ok(warp::reply::json(&replica))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::LedgerKind, network::DialInformation};
+    use chrono::NaiveDate;
+    use libp2p_comit::Histogram;
+
+    #[test]
+    fn latencies_are_exposed_in_seconds_per_peer() {
+        let peer = PeerId::random();
+
+        let metrics = metrics(
+            2,
+            vec![(peer.clone(), Duration::from_millis(250))],
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            0,
+            Vec::new(),
+        );
+
+        assert_eq!(
+            metrics,
+            format!(
+                "watchers 2\npeer_latency_seconds{{peer_id=\"{}\"}} 0.25\n",
+                peer
+            )
+        );
+    }
+
+    #[test]
+    fn block_heights_and_lags_are_exposed_per_ledger() {
+        let metrics = metrics(
+            0,
+            Vec::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            0,
+            vec![
+                LedgerLag {
+                    ledger: LedgerKind::Bitcoin,
+                    height: None,
+                    lag_seconds: 600,
+                },
+                LedgerLag {
+                    ledger: LedgerKind::Ethereum,
+                    height: Some(9_000_000),
+                    lag_seconds: 15,
+                },
+            ],
+        );
+
+        assert_eq!(
+            metrics,
+            "watchers 0\n\
+             ledger_block_lag_seconds{ledger=\"bitcoin\"} 600\n\
+             ledger_block_height{ledger=\"ethereum\"} 9000000\n\
+             ledger_block_lag_seconds{ledger=\"ethereum\"} 15\n"
+        );
+    }
+
+    #[test]
+    fn swap_events_are_counted_per_type() {
+        let mut swap_events = BTreeMap::new();
+        swap_events.insert("alpha-funded", 3);
+        swap_events.insert("created", 5);
+
+        let metrics = metrics(
+            0,
+            Vec::new(),
+            swap_events,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            0,
+            Vec::new(),
+        );
+
+        assert_eq!(
+            metrics,
+            "watchers 0\nswap_events_total{type=\"alpha-funded\"} 3\nswap_events_total{type=\"created\"} 5\n"
+        );
+    }
+
+    #[test]
+    fn unrecognized_request_types_and_headers_are_counted() {
+        let mut unrecognized = BTreeMap::new();
+        unrecognized.insert(Unrecognized::RequestType(String::from("PING")), 2);
+        unrecognized.insert(Unrecognized::MandatoryHeader(String::from("fee_rate")), 1);
+
+        let metrics = metrics(
+            0,
+            Vec::new(),
+            BTreeMap::new(),
+            unrecognized,
+            BTreeMap::new(),
+            0,
+            Vec::new(),
+        );
+
+        assert_eq!(
+            metrics,
+            "watchers 0\nunrecognized_request_types_total{type=\"PING\"} 2\nunrecognized_mandatory_headers_total{header=\"fee_rate\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn requests_are_counted_per_direction_and_type_with_latencies_of_ours() {
+        let mut latency = Histogram::default();
+        latency.observe(Duration::from_millis(250));
+
+        let mut request_metrics = BTreeMap::new();
+        request_metrics.insert(
+            RequestKind {
+                direction: Direction::Inbound,
+                request_type: String::from("SWAP"),
+            },
+            RequestMetrics {
+                count: 2,
+                ..RequestMetrics::default()
+            },
+        );
+        request_metrics.insert(
+            RequestKind {
+                direction: Direction::Outbound,
+                request_type: String::from("SWAP"),
+            },
+            RequestMetrics { count: 1, latency },
+        );
+
+        let metrics = metrics(
+            0,
+            Vec::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            request_metrics,
+            0,
+            Vec::new(),
+        );
+
+        assert_eq!(
+            metrics,
+            "watchers 0\n\
+             comit_requests_total{direction=\"inbound\",type=\"SWAP\"} 2\n\
+             comit_requests_total{direction=\"outbound\",type=\"SWAP\"} 1\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"0.01\"} 0\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"0.05\"} 0\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"0.1\"} 0\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"0.5\"} 1\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"1\"} 1\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"5\"} 1\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"10\"} 1\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"30\"} 1\n\
+             comit_request_latency_seconds_bucket{type=\"SWAP\",le=\"+Inf\"} 1\n\
+             comit_request_latency_seconds_sum{type=\"SWAP\"} 0.25\n\
+             comit_request_latency_seconds_count{type=\"SWAP\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn age_of_outbound_requests_is_never_negative() {
+        let enqueued_at = NaiveDate::from_ymd(2020, 3, 23).and_hms(12, 0, 0);
+        let request = UnansweredRequest {
+            swap_id: SwapId::default(),
+            peer: DialInformation {
+                peer_id: PeerId::random(),
+                address_hint: None,
+            },
+            enqueued_at,
+            attempts: 1,
+        };
+
+        let later = outbound_request(request.clone(), enqueued_at + chrono::Duration::seconds(90));
+        let earlier = outbound_request(request, enqueued_at - chrono::Duration::seconds(90));
+
+        assert_eq!(later.age_secs, 90);
+        assert_eq!(earlier.age_secs, 0);
+    }
+}
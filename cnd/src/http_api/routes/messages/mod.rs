@@ -0,0 +1,61 @@
+use crate::{
+    db::{Direction, Retrieve, SwapMessage, SwapMessages},
+    http_api::{problem, routes::into_rejection},
+    swap_protocols::SwapId,
+};
+use chrono::NaiveDateTime;
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use serde::Serialize;
+use warp::{Rejection, Reply};
+
+#[derive(Debug, Serialize)]
+pub struct MessagesResource {
+    messages: Vec<MessageResource>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MessageResource {
+    direction: Direction,
+    /// The frame as it was serialized on the wire, cut off if `truncated`.
+    frame: String,
+    size: u32,
+    truncated: bool,
+    recorded_at: NaiveDateTime,
+}
+
+impl From<SwapMessage> for MessageResource {
+    fn from(message: SwapMessage) -> Self {
+        MessageResource {
+            truncated: message.is_truncated(),
+            direction: message.direction,
+            frame: message.frame,
+            size: message.size,
+            recorded_at: message.recorded_at,
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_messages<D: Retrieve + SwapMessages>(
+    dependencies: D,
+    id: SwapId,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        // Answers with 404 for swaps that do not exist.
+        Retrieve::get(&dependencies, &id).await?;
+
+        let messages = SwapMessages::swap_messages(&dependencies, &id)
+            .await?
+            .into_iter()
+            .map(MessageResource::from)
+            .collect();
+
+        Ok::<_, anyhow::Error>(MessagesResource { messages })
+    }
+    .boxed()
+    .compat()
+    .map(|messages| warp::reply::json(&messages))
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
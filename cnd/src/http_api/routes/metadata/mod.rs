@@ -0,0 +1,126 @@
+use crate::{
+    db::{MetadataStore, Retrieve, SwapMetadata},
+    http_api::{problem, routes::into_rejection},
+    swap_protocols::SwapId,
+};
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use serde::{Deserialize, Serialize};
+use warp::{Rejection, Reply};
+
+pub const MAX_EXTERNAL_ID_LENGTH: usize = 128;
+pub const MAX_NOTE_LENGTH: usize = 1024;
+
+/// The metadata given when creating a swap or with
+/// `PATCH /swaps/:id/metadata`.
+///
+/// Fields that are not given are left as they are, empty strings remove them.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MetadataBody {
+    pub external_id: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+pub enum InvalidMetadata {
+    #[error("external_id must not be longer than {} bytes", MAX_EXTERNAL_ID_LENGTH)]
+    ExternalIdTooLong,
+    #[error("note must not be longer than {} bytes", MAX_NOTE_LENGTH)]
+    NoteTooLong,
+}
+
+impl MetadataBody {
+    pub fn apply(self, metadata: SwapMetadata) -> Result<SwapMetadata, InvalidMetadata> {
+        if self.external_id.as_ref().map_or(0, String::len) > MAX_EXTERNAL_ID_LENGTH {
+            return Err(InvalidMetadata::ExternalIdTooLong);
+        }
+        if self.note.as_ref().map_or(0, String::len) > MAX_NOTE_LENGTH {
+            return Err(InvalidMetadata::NoteTooLong);
+        }
+
+        Ok(SwapMetadata {
+            external_id: merge(self.external_id, metadata.external_id),
+            note: merge(self.note, metadata.note),
+        })
+    }
+}
+
+fn merge(given: Option<String>, current: Option<String>) -> Option<String> {
+    match given {
+        Some(given) if given.is_empty() => None,
+        Some(given) => Some(given),
+        None => current,
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MetadataResource {
+    external_id: Option<String>,
+    note: Option<String>,
+}
+
+impl From<SwapMetadata> for MetadataResource {
+    fn from(metadata: SwapMetadata) -> Self {
+        MetadataResource {
+            external_id: metadata.external_id,
+            note: metadata.note,
+        }
+    }
+}
+
+pub fn patch_metadata<D: Retrieve + MetadataStore>(
+    dependencies: D,
+    id: SwapId,
+    body: MetadataBody,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        // Answers with 404 for swaps that do not exist.
+        Retrieve::get(&dependencies, &id).await?;
+
+        let metadata = MetadataStore::swap_metadata(&dependencies, &id).await?;
+        let metadata = body.apply(metadata)?;
+        MetadataStore::save_metadata(&dependencies, &id, metadata.clone()).await?;
+
+        Ok(MetadataResource::from(metadata))
+    }
+    .boxed()
+    .compat()
+    .map(|metadata| warp::reply::json(&metadata))
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn given_fields_replace_the_current_ones_and_empty_ones_remove_them() {
+        let current = SwapMetadata {
+            external_id: Some(String::from("order-42")),
+            note: Some(String::from("first fill")),
+        };
+        let body = MetadataBody {
+            external_id: None,
+            note: Some(String::new()),
+        };
+
+        assert_that(&body.apply(current)).is_ok_containing(SwapMetadata {
+            external_id: Some(String::from("order-42")),
+            note: None,
+        });
+    }
+
+    #[test]
+    fn too_long_notes_are_rejected() {
+        let body = MetadataBody {
+            external_id: None,
+            note: Some("x".repeat(MAX_NOTE_LENGTH + 1)),
+        };
+
+        assert_that(&body.apply(SwapMetadata::default()))
+            .is_err_containing(InvalidMetadata::NoteTooLong);
+    }
+}
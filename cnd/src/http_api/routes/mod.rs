@@ -1,9 +1,19 @@
 use http_api_problem::HttpApiProblem;
 use warp::Rejection;
 
+pub mod expiry_recommendation;
+pub mod export;
 pub mod index;
+pub mod internal;
+pub mod messages;
+pub mod metadata;
+pub mod payout_accounts;
 pub mod peers;
+pub mod reports;
 pub mod rfc003;
+pub mod stats;
+pub mod ui;
+pub mod watch;
 
 pub fn into_rejection(problem: HttpApiProblem) -> Rejection {
     warp::reject::custom(problem)
@@ -0,0 +1,111 @@
+use crate::{
+    db::{PayoutAccountUsage, PayoutAccountUsages},
+    ethereum::{Address, ChecksumAddress},
+    http_api::{problem, routes::into_rejection, PayoutAccounts},
+};
+use chrono::NaiveDateTime;
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use warp::{Rejection, Reply};
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PayoutAccountResource {
+    name: String,
+    address: ChecksumAddress,
+    swaps: u64,
+    last_used_at: Option<NaiveDateTime>,
+}
+
+fn payout_account_resources<'a>(
+    accounts: impl Iterator<Item = (&'a String, &'a Address)>,
+    usages: &BTreeMap<String, PayoutAccountUsage>,
+) -> Vec<PayoutAccountResource> {
+    accounts
+        .map(|(name, address)| {
+            let usage = usages.get(name);
+
+            PayoutAccountResource {
+                name: name.clone(),
+                address: ChecksumAddress(*address),
+                swaps: usage.map_or(0, |usage| usage.swaps),
+                last_used_at: usage.map(|usage| usage.last_used_at),
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_payout_accounts<D: PayoutAccountUsages>(
+    dependencies: D,
+    payout_accounts: PayoutAccounts,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        let usages = PayoutAccountUsages::payout_account_usages(&dependencies).await?;
+
+        Ok(payout_account_resources(
+            payout_accounts.accounts(),
+            &usages,
+        ))
+    }
+    .boxed()
+    .compat()
+    .map(|accounts| warp::reply::json(&accounts))
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn payout_accounts_serialize_correctly_to_json() {
+        let payout_accounts = PayoutAccounts::new(&[
+            config::PayoutAccount {
+                name: String::from("cold-wallet"),
+                address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+                    .parse()
+                    .unwrap(),
+            },
+            config::PayoutAccount {
+                name: String::from("exchange"),
+                address: "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"
+                    .parse()
+                    .unwrap(),
+            },
+        ]);
+        let mut usages = BTreeMap::new();
+        usages.insert(String::from("cold-wallet"), PayoutAccountUsage {
+            swaps: 2,
+            last_used_at: NaiveDate::from_ymd(2020, 2, 10).and_hms(12, 0, 0),
+        });
+
+        let json = serde_json::to_value(payout_account_resources(
+            payout_accounts.accounts(),
+            &usages,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "name": "cold-wallet",
+                    "address": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+                    "swaps": 2,
+                    "last_used_at": "2020-02-10T12:00:00"
+                },
+                {
+                    "name": "exchange",
+                    "address": "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+                    "swaps": 0,
+                    "last_used_at": null
+                }
+            ])
+        );
+    }
+}
@@ -1,6 +1,12 @@
-use crate::{http_api::Http, network::Network};
+use crate::{
+    http_api::{problem, routes::into_rejection, Http},
+    network::Network,
+};
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
 use libp2p::{Multiaddr, PeerId};
 use serde::Serialize;
+use std::collections::HashMap;
 use warp::{Rejection, Reply};
 
 #[derive(Serialize, Debug)]
@@ -12,16 +18,57 @@ pub struct PeersResource {
 pub struct Peer {
     id: Http<PeerId>,
     endpoints: Vec<Multiaddr>,
+    /// Round-trip time of the last ping, absent until the peer answered one.
+    latency_ms: Option<u64>,
+    /// Swaps the peer requested from us that are not over yet.
+    active_swaps: usize,
+    /// The version of the peer's software, absent until it identified itself.
+    agent_version: Option<String>,
+    /// The addresses the peer listens on according to itself.
+    listen_addresses: Vec<Multiaddr>,
 }
 
 #[allow(clippy::needless_pass_by_value)]
-pub fn get_peers<D: Network>(dependencies: D) -> Result<impl Reply, Rejection> {
-    let peers = Network::comit_peers(&dependencies)
-        .map(|(peer, addresses)| Peer {
-            id: Http(peer),
-            endpoints: addresses,
-        })
-        .collect();
+pub fn get_peers<D: Network>(dependencies: D) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        let peers = Network::comit_peers(&dependencies).await?;
+        let latencies = Network::latencies(&dependencies).await?;
+        let active_swaps = Network::active_swaps(&dependencies).await?;
+        let peer_infos = Network::peer_infos(&dependencies).await?;
 
-    Ok(warp::reply::json(&PeersResource { peers }))
+        Ok::<_, anyhow::Error>((
+            peers,
+            latencies.into_iter().collect::<HashMap<_, _>>(),
+            active_swaps,
+            peer_infos,
+        ))
+    }
+    .boxed()
+    .compat()
+    .map(|(peers, latencies, active_swaps, mut peer_infos)| {
+        let peers = peers
+            .into_iter()
+            .map(|(peer, addresses)| {
+                let (agent_version, listen_addresses) = match peer_infos.remove(&peer) {
+                    Some(info) => (Some(info.agent_version), info.listen_addresses),
+                    None => (None, Vec::new()),
+                };
+
+                Peer {
+                    latency_ms: latencies
+                        .get(&peer)
+                        .map(|latency| latency.as_millis() as u64),
+                    active_swaps: active_swaps.get(&peer).cloned().unwrap_or(0),
+                    agent_version,
+                    listen_addresses,
+                    id: Http(peer),
+                    endpoints: addresses,
+                }
+            })
+            .collect();
+
+        warp::reply::json(&PeersResource { peers })
+    })
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
 }
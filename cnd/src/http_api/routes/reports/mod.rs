@@ -0,0 +1,190 @@
+use crate::{
+    db::{LedgerKind, RecordedFee, SwapFees},
+    http_api::{problem, routes::into_rejection},
+    swap_protocols::SwapId,
+};
+use chrono::{DateTime, Utc};
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use serde::{Deserialize, Serialize};
+use warp::{Rejection, Reply};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub struct FeesReportQuery {
+    /// Only include fees recorded at or after this time.
+    from: Option<DateTime<Utc>>,
+    /// Only include fees recorded before this time.
+    to: Option<DateTime<Utc>>,
+}
+
+/// Fees are given in the smallest unit of the native asset of the ledger
+/// (satoshi, wei) and serialized as strings because they may not fit into a
+/// JSON number.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct FeeTotals {
+    bitcoin: String,
+    ethereum: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SwapFeesEntry {
+    swap_id: SwapId,
+    fees: FeeTotals,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FeesReport {
+    total: FeeTotals,
+    swaps: Vec<SwapFeesEntry>,
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_fees<D: SwapFees>(
+    dependencies: D,
+    query: FeesReportQuery,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        let fees = SwapFees::swap_fees(
+            &dependencies,
+            query.from.map(|from| from.naive_utc()),
+            query.to.map(|to| to.naive_utc()),
+        )
+        .await?;
+
+        Ok(fees_report(fees))
+    }
+    .boxed()
+    .compat()
+    .map(|report| warp::reply::json(&report))
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
+
+#[derive(Clone, Copy, Default)]
+struct Sums {
+    bitcoin: u128,
+    ethereum: u128,
+}
+
+impl Sums {
+    fn add(&mut self, ledger: LedgerKind, amount: u128) {
+        let sum = match ledger {
+            LedgerKind::Bitcoin => &mut self.bitcoin,
+            LedgerKind::Ethereum => &mut self.ethereum,
+        };
+        *sum = sum.saturating_add(amount);
+    }
+}
+
+impl From<Sums> for FeeTotals {
+    fn from(sums: Sums) -> Self {
+        FeeTotals {
+            bitcoin: sums.bitcoin.to_string(),
+            ethereum: sums.ethereum.to_string(),
+        }
+    }
+}
+
+/// Sum up the fees per ledger, overall and per swap. Swaps are listed in the
+/// order their first fee was recorded.
+fn fees_report(fees: Vec<RecordedFee>) -> FeesReport {
+    let mut total = Sums::default();
+    let mut swaps: Vec<(SwapId, Sums)> = Vec::new();
+
+    for recorded in fees {
+        let ledger = recorded.fee.ledger;
+        let amount = recorded.fee.amount;
+
+        total.add(ledger, amount);
+
+        match swaps
+            .iter_mut()
+            .find(|(swap_id, _)| *swap_id == recorded.swap_id)
+        {
+            Some((_, sums)) => sums.add(ledger, amount),
+            None => {
+                let mut sums = Sums::default();
+                sums.add(ledger, amount);
+                swaps.push((recorded.swap_id, sums));
+            }
+        }
+    }
+
+    FeesReport {
+        total: total.into(),
+        swaps: swaps
+            .into_iter()
+            .map(|(swap_id, sums)| SwapFeesEntry {
+                swap_id,
+                fees: sums.into(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::PaidFee;
+    use chrono::NaiveDateTime;
+    use spectral::prelude::*;
+
+    fn recorded(swap_id: SwapId, ledger: LedgerKind, amount: u128) -> RecordedFee {
+        RecordedFee {
+            swap_id,
+            fee: PaidFee {
+                ledger,
+                transaction_id: String::new(),
+                amount,
+            },
+            recorded_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    #[test]
+    fn fees_are_summed_per_ledger_and_per_swap() {
+        let first = SwapId::default();
+        let second = SwapId::default();
+
+        let report = fees_report(vec![
+            recorded(first, LedgerKind::Bitcoin, 1_000),
+            recorded(second, LedgerKind::Ethereum, 21_000_000_000_000),
+            recorded(first, LedgerKind::Ethereum, 42_000_000_000_000),
+            recorded(second, LedgerKind::Bitcoin, 500),
+        ]);
+
+        assert_that(&report).is_equal_to(FeesReport {
+            total: FeeTotals {
+                bitcoin: "1500".to_string(),
+                ethereum: "63000000000000".to_string(),
+            },
+            swaps: vec![
+                SwapFeesEntry {
+                    swap_id: first,
+                    fees: FeeTotals {
+                        bitcoin: "1000".to_string(),
+                        ethereum: "42000000000000".to_string(),
+                    },
+                },
+                SwapFeesEntry {
+                    swap_id: second,
+                    fees: FeeTotals {
+                        bitcoin: "500".to_string(),
+                        ethereum: "21000000000000".to_string(),
+                    },
+                },
+            ],
+        });
+    }
+
+    #[test]
+    fn period_is_parsed_from_the_query_string() {
+        let query = serde_urlencoded::from_str::<FeesReportQuery>(
+            "from=2020-01-01T00:00:00Z&to=2020-02-01T00:00:00Z",
+        )
+        .unwrap();
+
+        assert_that(&query.from.map(|from| from.timestamp())).is_equal_to(Some(1_577_836_800));
+        assert_that(&query.to.map(|to| to.timestamp())).is_equal_to(Some(1_580_515_200));
+    }
+}
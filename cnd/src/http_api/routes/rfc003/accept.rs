@@ -1,5 +1,8 @@
 use crate::{
-    http_api::action::ListRequiredFields,
+    http_api::{
+        action::ListRequiredFields,
+        routes::rfc003::identities::{self, IdentityKind},
+    },
     swap_protocols::{
         ledger::{Bitcoin, Ethereum},
         rfc003::{
@@ -12,9 +15,34 @@ use crate::{
 };
 use serde::Deserialize;
 
+/// The identities Bob supplies when accepting, those that are left out are
+/// derived from the seed of the swap.
 #[derive(Deserialize, Clone, Debug)]
-pub struct OnlyRedeem<L: Ledger> {
-    pub alpha_ledger_redeem_identity: L::Identity,
+pub struct AcceptIdentities<AL: Ledger, BL: Ledger> {
+    pub alpha_ledger_redeem_identity: Option<AL::Identity>,
+    pub beta_ledger_refund_identity: Option<BL::Identity>,
+}
+
+impl<AL: Ledger, BL: Ledger> IntoAcceptMessage<AL, BL> for AcceptIdentities<AL, BL> {
+    fn into_accept_message(
+        self,
+        id: SwapId,
+        secret_source: &dyn SecretSource,
+    ) -> anyhow::Result<messages::Accept<AL, BL>> {
+        Ok(messages::Accept {
+            swap_id: id,
+            alpha_ledger_redeem_identity: identities::redeem_identity::<AL>(
+                self.alpha_ledger_redeem_identity,
+                IdentityKind::AlphaLedgerRedeemIdentity,
+                secret_source,
+            )?,
+            beta_ledger_refund_identity: identities::refund_identity::<BL>(
+                self.beta_ledger_refund_identity,
+                IdentityKind::BetaLedgerRefundIdentity,
+                secret_source,
+            )?,
+        })
+    }
 }
 
 impl ListRequiredFields for Accept<Ethereum, Bitcoin> {
@@ -29,29 +57,6 @@ impl ListRequiredFields for Accept<Ethereum, Bitcoin> {
     }
 }
 
-impl IntoAcceptMessage<Ethereum, Bitcoin> for OnlyRedeem<Ethereum> {
-    fn into_accept_message(
-        self,
-        id: SwapId,
-        secret_source: &dyn SecretSource,
-    ) -> messages::Accept<Ethereum, Bitcoin> {
-        let beta_ledger_refund_identity = crate::bitcoin::PublicKey::from_secret_key(
-            &*crate::SECP,
-            &secret_source.secp256k1_refund(),
-        );
-        messages::Accept {
-            swap_id: id,
-            alpha_ledger_redeem_identity: self.alpha_ledger_redeem_identity,
-            beta_ledger_refund_identity,
-        }
-    }
-}
-
-#[derive(Deserialize, Clone, Debug)]
-pub struct OnlyRefund<L: Ledger> {
-    pub beta_ledger_refund_identity: L::Identity,
-}
-
 impl ListRequiredFields for Accept<Bitcoin, Ethereum> {
     fn list_required_fields() -> Vec<siren::Field> {
         vec![siren::Field {
@@ -63,21 +68,3 @@ impl ListRequiredFields for Accept<Bitcoin, Ethereum> {
         }]
     }
 }
-
-impl IntoAcceptMessage<Bitcoin, Ethereum> for OnlyRefund<Ethereum> {
-    fn into_accept_message(
-        self,
-        id: SwapId,
-        secret_source: &dyn SecretSource,
-    ) -> messages::Accept<Bitcoin, Ethereum> {
-        let alpha_ledger_redeem_identity = crate::bitcoin::PublicKey::from_secret_key(
-            &*crate::SECP,
-            &secret_source.secp256k1_redeem(),
-        );
-        messages::Accept {
-            swap_id: id,
-            beta_ledger_refund_identity: self.beta_ledger_refund_identity,
-            alpha_ledger_redeem_identity,
-        }
-    }
-}
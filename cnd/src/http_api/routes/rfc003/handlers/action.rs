@@ -1,42 +1,68 @@
 use crate::{
-    db::{DetermineTypes, Save, Saver},
+    db::{
+        self, ActionHistory, ConstructedTransactions, DetermineTypes, LedgerKind,
+        PayoutAccountUsages, Retention, Save, Saver, SwapFailures, SwapMessages, SwapTypes,
+    },
+    ethereum::{GasOracle, GasPricing},
     http_api::{
         action::{
+            bump_fee_payload, rebroadcast_payload, rebroadcast_required_fields,
             ActionExecutionParameters, ActionResponseBody, IntoResponsePayload, ListRequiredFields,
             ToSirenAction,
         },
         route_factory::new_action_link,
         routes::rfc003::decline::{to_swap_decline_reason, DeclineBody},
+        HttpAsset, PayoutAccounts, SwapSubResource,
     },
-    libp2p_comit_ext::ToHeader,
-    network::Network,
+    identity_reuse::{derived_identity, IssueIdentities},
+    network::{self, Network},
+    price_feed::{IndicativeFiatValue, PriceFeed},
+    redeem_destinations::RedeemDestination,
     seed::SwapSeed,
     swap_protocols::{
         self,
-        actions::Actions,
+        actions::{bitcoin::BumpFee, Actions},
         rfc003::{
             self,
             actions::{Action, ActionKind},
             bob::State,
-            messages::{Decision, IntoAcceptMessage},
+            messages::IntoAcceptMessage,
             state_store::StateStore,
+            Ledger,
         },
-        LedgerEventsCreator, SwapId,
+        FeeAccounting, LedgerEventsCreator, Role, SwapEvent, SwapEvents, SwapId, SwapTasks,
     },
 };
 use anyhow::Context;
-use libp2p_comit::frame::Response;
+use libp2p_comit::IntoFrame;
 use std::fmt::Debug;
 use tokio::executor::Executor;
 use warp::http;
 
-#[allow(clippy::unit_arg, clippy::let_unit_value, clippy::cognitive_complexity)]
+#[allow(
+    clippy::unit_arg,
+    clippy::let_unit_value,
+    clippy::cognitive_complexity,
+    clippy::too_many_arguments
+)]
 pub async fn handle_action<
     D: StateStore
         + Network
         + SwapSeed
         + Saver
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + ActionHistory
+        + GasOracle
+        + SwapTasks
+        + SwapEvents
+        + RedeemDestination
+        + PayoutAccountUsages
+        + SwapMessages
+        + IssueIdentities
         + DetermineTypes
+        + ConstructedTransactions
         + LedgerEventsCreator
         + Executor
         + Clone,
@@ -47,7 +73,10 @@ pub async fn handle_action<
     body: serde_json::Value,
     query_params: ActionExecutionParameters,
     dependencies: D,
-) -> anyhow::Result<ActionResponseBody> {
+    bitcoin_memo: Option<String>,
+    payout_accounts: PayoutAccounts,
+    price_feed: PriceFeed,
+) -> anyhow::Result<serde_json::Value> {
     let types = dependencies.determine_types(&swap_id).await?;
 
     with_swap_types!(types, {
@@ -59,10 +88,68 @@ pub async fn handle_action<
         let action = state
             .actions()
             .into_iter()
-            .select_action(action_kind, method)?;
+            .select_action(action_kind, method.clone())?;
+
+        let parameters = match method {
+            http::Method::GET => serde_json::to_value(&query_params)?,
+            _ => body.clone(),
+        };
+
+        let asset = match SwapSubResource::of_action(action_kind, types) {
+            SwapSubResource::Alpha => Some(HttpAsset::from(state.request().alpha_asset)),
+            SwapSubResource::Beta => Some(HttpAsset::from(state.request().beta_asset)),
+            SwapSubResource::Communication => None,
+        };
+        let indicative_fiat_value = match asset {
+            Some(asset) => price_feed
+                .quotes()
+                .await
+                .and_then(|quotes| asset.indicative_fiat_value(&quotes)),
+            None => None,
+        };
+
+        // Ledger actions are built from what the ledger looks like right now,
+        // hand out the same payload again rather than a conflicting one.
+        if method == http::Method::GET {
+            if let Some(invocation) = ActionHistory::previous_action_invocation(
+                &dependencies,
+                &swap_id,
+                action_kind,
+                &parameters,
+            )
+            .await?
+            {
+                log::debug!(
+                    "Replaying {} action of swap {} invoked at {}",
+                    action_kind,
+                    swap_id,
+                    invocation.invoked_at
+                );
+                return with_indicative_fiat_value(invocation.payload, indicative_fiat_value);
+            }
+        }
 
-        match action {
+        let query_params = match query_params {
+            ActionExecutionParameters::BitcoinFee { fee_per_wu, format }
+                if spends_bitcoin_htlc(types, action_kind) =>
+            {
+                match RedeemDestination::redeem_destination(&dependencies, &swap_id).await? {
+                    Some(address) => ActionExecutionParameters::BitcoinAddressAndFee {
+                        address,
+                        fee_per_wu,
+                        format,
+                    },
+                    None => ActionExecutionParameters::BitcoinFee { fee_per_wu, format },
+                }
+            }
+            query_params => query_params,
+        };
+
+        let payload = match action {
             Action::Accept(_) => {
+                let mut body = body;
+                let payout_account =
+                    payout_accounts.resolve(&mut body, "alpha_ledger_redeem_identity")?;
                 let body = serde_json::from_value::<AcceptBody>(body)
                     .context("failed to deserialize accept body")?;
 
@@ -71,18 +158,44 @@ pub async fn handle_action<
                         format!("unable to find response channel for swap {}", swap_id)
                     })?;
 
-                let accept_message =
-                    body.into_accept_message(swap_id, &SwapSeed::swap_seed(&dependencies, swap_id));
+                let seed = SwapSeed::swap_secrets(&dependencies, swap_id, Role::Bob);
+                let accept_message = body.into_accept_message(swap_id, &seed)?;
+
+                let issued = vec![
+                    derived_identity(
+                        &accept_message.alpha_ledger_redeem_identity,
+                        AL::derive_redeem_identity(&seed),
+                    ),
+                    derived_identity(
+                        &accept_message.beta_ledger_refund_identity,
+                        BL::derive_refund_identity(&seed),
+                    ),
+                ];
+                let issued = issued.into_iter().flatten().collect::<Vec<_>>();
+                IssueIdentities::issue_identities(&dependencies, &swap_id, &issued).await?;
 
                 Save::save(&dependencies, accept_message).await?;
+                if let Some(account) = payout_account {
+                    PayoutAccountUsages::record_payout_account_usage(
+                        &dependencies,
+                        &swap_id,
+                        &account,
+                    )
+                    .await?;
+                }
 
-                let response = rfc003_accept_response(accept_message);
+                let response = network::accept_response(accept_message);
+                let sent = response.clone().into_frame();
                 channel.send(response).map_err(|_| {
                     anyhow::anyhow!(
                         "failed to send response through channel for swap {}",
                         swap_id
                     )
                 })?;
+                network::record_message(&dependencies, swap_id, db::Direction::Outbound, &sent)
+                    .await;
+
+                SwapEvents::publish(&dependencies, SwapEvent::Accepted { swap_id });
 
                 let swap_request = state.request();
                 swap_protocols::init_accepted_swap(
@@ -92,7 +205,7 @@ pub async fn handle_action<
                     types.role,
                 )?;
 
-                Ok(ActionResponseBody::None)
+                ActionResponseBody::None
             }
             Action::Decline(_) => {
                 let body = serde_json::from_value::<DeclineBody>(body)?;
@@ -109,29 +222,127 @@ pub async fn handle_action<
 
                 Save::save(&dependencies, decline_message.clone()).await?;
 
-                let response = rfc003_decline_response(decline_message.clone());
+                let response = network::decline_swap_response(decline_message.clone());
+                let sent = response.clone().into_frame();
                 channel.send(response).map_err(|_| {
                     anyhow::anyhow!(
                         "failed to send response through channel for swap {}",
                         swap_id
                     )
                 })?;
+                network::record_message(&dependencies, swap_id, db::Direction::Outbound, &sent)
+                    .await;
 
                 let swap_request = state.request();
-                let seed = dependencies.swap_seed(swap_id);
+                let seed = dependencies.swap_secrets(swap_id, Role::Bob);
                 let state = State::declined(swap_request.clone(), decline_message.clone(), seed);
                 StateStore::insert(&dependencies, swap_id, state);
+                SwapTasks::cancel_swap_tasks(&dependencies, &swap_id);
+                SwapEvents::publish(&dependencies, SwapEvent::Declined { swap_id });
 
-                Ok(ActionResponseBody::None)
+                ActionResponseBody::None
+            }
+            Action::Deploy(action) => action.into_response_payload(query_params)?,
+            Action::Fund(action) => {
+                let payload = action.into_response_payload(query_params)?;
+
+                match bitcoin_memo {
+                    Some(memo) => payload.with_op_return(format!("{}{}", memo, swap_id).as_bytes()),
+                    None => payload,
+                }
             }
-            Action::Deploy(action) => action.into_response_payload(query_params),
-            Action::Fund(action) => action.into_response_payload(query_params),
-            Action::Redeem(action) => action.into_response_payload(query_params),
-            Action::Refund(action) => action.into_response_payload(query_params),
+            Action::Redeem(action) => action.into_response_payload(query_params)?,
+            Action::Refund(action) => action.into_response_payload(query_params)?,
+            Action::BumpFee(action) => {
+                let parent =
+                    ConstructedTransactions::constructed_transactions(&dependencies, &swap_id)
+                        .await?
+                        .into_iter()
+                        .find(|transaction| action.is_parent(transaction))
+                        .ok_or(NothingToBump)?;
+
+                bump_fee_payload(&action, &parent, query_params)?
+            }
+            Action::Rebroadcast(action) => {
+                let previous =
+                    ConstructedTransactions::constructed_transactions(&dependencies, &swap_id)
+                        .await?
+                        .into_iter()
+                        .find(|transaction| action.is_spent_by(transaction))
+                        .ok_or(NothingToReplace)?;
+
+                rebroadcast_payload(action, &previous, query_params)?
+            }
+        };
+        let payload = if payload.is_ethereum_transaction() {
+            payload.with_gas_pricing(gas_pricing(&dependencies).await)
+        } else {
+            payload
+        };
+        if let Some(transaction) = payload.signed_bitcoin_transaction() {
+            ConstructedTransactions::record_constructed_transaction(
+                &dependencies,
+                &swap_id,
+                &transaction,
+            )
+            .await?;
         }
+        let payload = serde_json::to_value(&payload)?;
+
+        ActionHistory::record_action_invocation(
+            &dependencies,
+            &swap_id,
+            action_kind,
+            &parameters,
+            &payload,
+        )
+        .await?;
+
+        with_indicative_fiat_value(payload, indicative_fiat_value)
     })
 }
 
+/// The value is kept out of the recorded payload, such that replayed payloads
+/// are valued at the current price.
+fn with_indicative_fiat_value(
+    mut response: serde_json::Value,
+    indicative_fiat_value: Option<IndicativeFiatValue>,
+) -> anyhow::Result<serde_json::Value> {
+    if let (Some(value), Some(object)) = (indicative_fiat_value, response.as_object_mut()) {
+        object.insert(
+            String::from("indicative_fiat_value"),
+            serde_json::to_value(value)?,
+        );
+    }
+
+    Ok(response)
+}
+
+/// Alice redeems on beta and refunds on alpha, Bob the other way round.
+fn spends_bitcoin_htlc(types: SwapTypes, action_kind: ActionKind) -> bool {
+    let ledger = match (action_kind, types.role) {
+        (ActionKind::Redeem, Role::Alice) | (ActionKind::Refund, Role::Bob) => types.beta_ledger,
+        (ActionKind::Redeem, Role::Bob) | (ActionKind::Refund, Role::Alice) => types.alpha_ledger,
+        _ => return false,
+    };
+
+    ledger == LedgerKind::Bitcoin
+}
+
+/// Legacy transactions are valid on every chain, the user just misses out on
+/// the base fee mechanism if we cannot figure out the current fees.
+async fn gas_pricing<D: GasOracle>(dependencies: &D) -> GasPricing {
+    GasOracle::gas_pricing(dependencies)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!(
+                "failed to determine gas pricing, falling back to legacy transactions: {:?}",
+                e
+            );
+            GasPricing::Legacy
+        })
+}
+
 #[derive(Debug, thiserror::Error, PartialEq)]
 #[error("attempt to invoke {action_kind} action with http method {method}, which is an invalid combination")]
 pub struct InvalidActionInvocation {
@@ -145,6 +356,18 @@ pub struct InvalidAction {
     action_kind: ActionKind,
 }
 
+/// Only fees of transactions cnd handed out signed can be bumped, it does not
+/// know about transactions completed from a PSBT.
+#[derive(Clone, Copy, Debug, thiserror::Error, PartialEq)]
+#[error("no redeem transaction constructed by cnd is known for this swap")]
+pub struct NothingToBump;
+
+/// Like [`NothingToBump`], cnd only replaces transactions it handed out
+/// signed.
+#[derive(Clone, Copy, Debug, thiserror::Error, PartialEq)]
+#[error("no redeem or refund transaction constructed by cnd is known for this swap")]
+pub struct NothingToReplace;
+
 trait SelectAction<Accept, Decline, Deploy, Fund, Redeem, Refund>:
     Iterator<Item = Action<Accept, Decline, Deploy, Fund, Redeem, Refund>>
 {
@@ -171,41 +394,6 @@ trait SelectAction<Accept, Decline, Deploy, Fund, Redeem, Refund>:
     }
 }
 
-fn rfc003_accept_response<AL: rfc003::Ledger, BL: rfc003::Ledger>(
-    message: rfc003::messages::Accept<AL, BL>,
-) -> Response {
-    Response::empty()
-        .with_header(
-            "decision",
-            Decision::Accepted
-                .to_header()
-                .expect("Decision should not fail to serialize"),
-        )
-        .with_body(
-            serde_json::to_value(rfc003::messages::AcceptResponseBody::<AL, BL> {
-                beta_ledger_refund_identity: message.beta_ledger_refund_identity,
-                alpha_ledger_redeem_identity: message.alpha_ledger_redeem_identity,
-            })
-            .expect("body should always serialize into serde_json::Value"),
-        )
-}
-
-fn rfc003_decline_response(message: rfc003::messages::Decline) -> Response {
-    Response::empty()
-        .with_header(
-            "decision",
-            Decision::Declined
-                .to_header()
-                .expect("Decision shouldn't fail to serialize"),
-        )
-        .with_body(
-            serde_json::to_value(rfc003::messages::DeclineResponseBody {
-                reason: message.reason,
-            })
-            .expect("decline body should always serialize into serde_json::Value"),
-        )
-}
-
 impl<Accept, Decline, Deploy, Fund, Redeem, Refund, I>
     SelectAction<Accept, Decline, Deploy, Fund, Redeem, Refund> for I
 where
@@ -357,6 +545,8 @@ impl From<ActionKind> for http::Method {
             ActionKind::Fund => http::Method::GET,
             ActionKind::Refund => http::Method::GET,
             ActionKind::Redeem => http::Method::GET,
+            ActionKind::BumpFee => http::Method::GET,
+            ActionKind::Rebroadcast => http::Method::GET,
         }
     }
 }
@@ -381,6 +571,10 @@ where
             Action::Accept(_) | Action::Decline(_) => Err(anyhow::anyhow!(
                 "IntoResponsePayload is not available for Accept/Decline"
             )),
+            Action::BumpFee(_) | Action::Rebroadcast(_) => Err(anyhow::anyhow!(
+                "IntoResponsePayload is not available for BumpFee/Rebroadcast, they need the \
+                 transaction constructed before"
+            )),
         }
     }
 }
@@ -413,6 +607,8 @@ where
             Action::Fund(_) => Fund::list_required_fields(),
             Action::Redeem(_) => Redeem::list_required_fields(),
             Action::Refund(_) => Refund::list_required_fields(),
+            Action::BumpFee(_) => BumpFee::list_required_fields(),
+            Action::Rebroadcast(_) => rebroadcast_required_fields(),
         };
 
         log::debug!(target: "http-api", "Creating siren::Action from {:?} with HTTP method: {}, Media-Type: {:?}, Name: {}, Fields: {:?}", self, method, media_type, name, fields);
@@ -0,0 +1,45 @@
+use crate::{
+    db::{ActionHistory, ActionInvocation, Retrieve},
+    swap_protocols::SwapId,
+};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ActionHistoryResource {
+    actions: Vec<ActionInvocationResource>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActionInvocationResource {
+    action: String,
+    parameters: serde_json::Value,
+    payload_hash: String,
+    invoked_at: NaiveDateTime,
+}
+
+impl From<ActionInvocation> for ActionInvocationResource {
+    fn from(invocation: ActionInvocation) -> Self {
+        ActionInvocationResource {
+            action: invocation.action.to_string(),
+            parameters: invocation.parameters,
+            payload_hash: invocation.payload_hash,
+            invoked_at: invocation.invoked_at,
+        }
+    }
+}
+
+pub async fn handle_get_action_history<D: Retrieve + ActionHistory>(
+    dependencies: D,
+    id: SwapId,
+) -> anyhow::Result<ActionHistoryResource> {
+    // Fails with `SwapNotFound` for unknown swaps instead of an empty history.
+    let _ = Retrieve::get(&dependencies, &id).await?;
+    let actions = ActionHistory::action_history(&dependencies, &id)
+        .await?
+        .into_iter()
+        .map(ActionInvocationResource::from)
+        .collect();
+
+    Ok(ActionHistoryResource { actions })
+}
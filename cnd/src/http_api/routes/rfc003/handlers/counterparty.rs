@@ -0,0 +1,49 @@
+use crate::{
+    db::Retrieve,
+    http_api::Http,
+    network::{probe, Network},
+    swap_protocols::SwapId,
+};
+use chrono::{DateTime, Utc};
+use libp2p::{Multiaddr, PeerId};
+use serde::Serialize;
+use std::time::Duration;
+
+/// How long to wait for a connection to one of the counterparty's addresses.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize)]
+pub struct CounterpartyResource {
+    peer_id: Http<PeerId>,
+    connected: bool,
+    last_seen: Option<DateTime<Utc>>,
+    addresses: Vec<AddressProbe>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AddressProbe {
+    address: Multiaddr,
+    dialable: bool,
+}
+
+pub async fn handle_get_counterparty<D: Retrieve + Network>(
+    dependencies: D,
+    id: SwapId,
+) -> anyhow::Result<CounterpartyResource> {
+    let swap = Retrieve::get(&dependencies, &id).await?;
+    let status = Network::peer_status(&dependencies, swap.counterparty.clone()).await?;
+
+    let probes = status.addresses.into_iter().map(|address| async move {
+        let dialable = probe::is_dialable(&address, PROBE_TIMEOUT).await;
+
+        AddressProbe { address, dialable }
+    });
+    let addresses = futures_core::future::join_all(probes).await;
+
+    Ok(CounterpartyResource {
+        peer_id: Http(swap.counterparty),
+        connected: status.connected,
+        last_seen: status.last_seen.map(DateTime::<Utc>::from),
+        addresses,
+    })
+}
@@ -1,15 +1,61 @@
 use crate::{
-    db::{DetermineTypes, Retrieve},
-    http_api::swap_resource::{build_rfc003_siren_entity, IncludeState},
-    swap_protocols::{rfc003::state_store::StateStore, SwapId},
+    block_lag::BlockLagMonitor,
+    clock_skew::ClockMonitor,
+    db::{DetermineTypes, IssuedIdentities, MetadataStore, RequestedRates, Retrieve, SwapFailures},
+    http_api::swap_resource::{
+        build_rfc003_siren_entity, build_rfc003_sub_resource_entity, IncludeState, SwapSubResource,
+    },
+    price_feed::PriceFeed,
+    swap_protocols::{rfc003::state_store::StateStore, SwapId, SwapTasks},
 };
 
-pub async fn handle_get_swap<D: Retrieve + StateStore + DetermineTypes>(
+pub async fn handle_get_swap<
+    D: Retrieve
+        + StateStore
+        + DetermineTypes
+        + SwapFailures
+        + MetadataStore
+        + RequestedRates
+        + IssuedIdentities
+        + SwapTasks,
+>(
     dependencies: D,
+    clock_monitor: ClockMonitor,
+    block_lag_monitor: BlockLagMonitor,
+    price_feed: PriceFeed,
     id: SwapId,
 ) -> anyhow::Result<siren::Entity> {
     let swap = Retrieve::get(&dependencies, &id).await?;
     let types = dependencies.determine_types(&id).await?;
+    let failure = SwapFailures::swap_failure(&dependencies, &id).await?;
+    let metadata = MetadataStore::swap_metadata(&dependencies, &id).await?;
+    let rate = RequestedRates::requested_rate(&dependencies, &id).await?;
+    let reused_identities = IssuedIdentities::reused_identities(&dependencies, &id).await?;
+    let quotes = price_feed.quotes().await;
 
-    build_rfc003_siren_entity(&dependencies, swap, types, IncludeState::Yes)
+    build_rfc003_siren_entity(
+        &dependencies,
+        swap,
+        types,
+        failure,
+        metadata,
+        rate,
+        reused_identities,
+        clock_monitor.skews_of(&id),
+        block_lag_monitor.stale_ledgers(&[types.alpha_ledger, types.beta_ledger]),
+        SwapTasks::degraded(&dependencies, &id),
+        quotes.as_ref(),
+        IncludeState::Yes,
+    )
+}
+
+pub async fn handle_get_swap_sub_resource<D: Retrieve + StateStore + DetermineTypes>(
+    dependencies: D,
+    id: SwapId,
+    sub_resource: SwapSubResource,
+) -> anyhow::Result<siren::Entity> {
+    let swap = Retrieve::get(&dependencies, &id).await?;
+    let types = dependencies.determine_types(&id).await?;
+
+    build_rfc003_sub_resource_entity(&dependencies, swap, types, sub_resource)
 }
@@ -1,9 +1,21 @@
 mod action;
+mod action_history;
+mod counterparty;
 mod get_swap;
 pub mod post_swap;
+mod proof;
+mod receipt;
+mod verify_transaction;
 
 pub use self::{
-    action::{handle_action, InvalidAction, InvalidActionInvocation},
-    get_swap::handle_get_swap,
+    action::{
+        handle_action, InvalidAction, InvalidActionInvocation, NothingToBump, NothingToReplace,
+    },
+    action_history::handle_get_action_history,
+    counterparty::handle_get_counterparty,
+    get_swap::{handle_get_swap, handle_get_swap_sub_resource},
     post_swap::handle_post_swap,
+    proof::{handle_get_proof, ProofUnavailable},
+    receipt::{handle_get_receipt, ReceiptUnavailable},
+    verify_transaction::{handle_verify_transaction, UnverifiedTransaction},
 };
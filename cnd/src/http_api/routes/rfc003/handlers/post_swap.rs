@@ -1,62 +1,95 @@
 use crate::{
-    db::{Save, Saver, Swap},
-    ethereum,
-    http_api::{HttpAsset, HttpLedger},
-    network::{DialInformation, SendRequest},
+    db::{
+        AutoRefund, AutoRefunds, Enqueue, Enqueuer, MetadataStore, PayoutAccountUsages,
+        RequestedRates, Swap, SwapMetadata,
+    },
+    expiries::{Chain, ExpiryCalculator},
+    http_api::{
+        routes::{
+            metadata::MetadataBody,
+            rfc003::identities::{self, IdentityKind},
+        },
+        HttpAsset, HttpLedger, PayoutAccounts, TokenRegistry,
+    },
+    identity_reuse::{derived_identity, IssueIdentities},
+    network::DialInformation,
     seed::SwapSeed,
     swap_protocols::{
-        self,
         asset::Asset,
-        ledger,
         rfc003::{
-            self, alice::State, state_store::StateStore, Accept, Decline, Ledger, Request,
-            SecretHash, SecretSource,
+            self, alice::State, state_store::StateStore, Ledger, Rate, Request, SecretHash,
+            SecretSource,
         },
-        HashFunction, LedgerEventsCreator, Role, SwapId,
+        HashFunction, Role, SwapEvent, SwapEvents, SwapId,
     },
+    swap_tracing::{SwapTracing, TraceContext},
     timestamp::Timestamp,
-    CreateLedgerEvents,
-};
-use anyhow::Context;
-use futures::Future;
-use futures_core::{
-    compat::Future01CompatExt,
-    future::{FutureExt, TryFutureExt},
 };
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
-use tokio::executor::Executor;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_post_swap<
     D: Clone
-        + Executor
         + StateStore
-        + Save<Swap>
-        + SendRequest
         + SwapSeed
-        + Saver
-        + Clone
-        + LedgerEventsCreator,
+        + Enqueuer
+        + SwapEvents
+        + AutoRefunds
+        + PayoutAccountUsages
+        + MetadataStore
+        + RequestedRates
+        + IssueIdentities
+        + SwapTracing,
 >(
     dependencies: D,
-    body: serde_json::Value,
+    token_registry: TokenRegistry,
+    payout_accounts: PayoutAccounts,
+    expiry_calculator: ExpiryCalculator,
+    auto_refund_enabled: bool,
+    mut body: serde_json::Value,
+    trace_parent: Option<TraceContext>,
 ) -> anyhow::Result<SwapCreated> {
     let id = SwapId::default();
-    let seed = dependencies.swap_seed(id);
+    let seed = dependencies.swap_secrets(id, Role::Alice);
     let secret_hash = seed.secret().hash();
 
-    let body = serde_json::from_value(body)?;
+    token_registry.resolve_aliases(&mut body)?;
+    let payout_account = payout_accounts.resolve(&mut body, "beta_ledger_redeem_identity")?;
+    let body: SwapRequestBody = serde_json::from_value(body)?;
+
+    if let Some(auto_refund) = &body.auto_refund {
+        auto_refund.validate(&body, auto_refund_enabled)?;
+    }
 
-    match body {
+    let metadata = MetadataBody {
+        external_id: body.external_id.clone(),
+        note: body.note.clone(),
+    }
+    .apply(SwapMetadata::default())?;
+
+    let (alpha_expiry, beta_expiry) = match (body.alpha_expiry, body.beta_expiry) {
+        (Some(alpha_expiry), Some(beta_expiry)) => (alpha_expiry, beta_expiry),
+        (alpha_expiry, beta_expiry) => {
+            let recommended = expiry_calculator
+                .recommend(chain(&body.alpha_ledger), chain(&body.beta_ledger))
+                .await;
+
+            (
+                alpha_expiry.unwrap_or(recommended.alpha_expiry),
+                beta_expiry.unwrap_or(recommended.beta_expiry),
+            )
+        }
+    };
+
+    match body.clone() {
         SwapRequestBody {
             alpha_ledger: HttpLedger::Bitcoin(alpha_ledger),
             beta_ledger: HttpLedger::Ethereum(beta_ledger),
             alpha_asset: HttpAsset::Bitcoin(alpha_asset),
             beta_asset: HttpAsset::Ether(beta_asset),
-            alpha_expiry,
-            beta_expiry,
             identities,
             peer,
+            ..
         } => {
             let identities = identities.into_identities(&seed)?;
             let request = new_request(
@@ -70,17 +103,24 @@ pub async fn handle_post_swap<
                 identities,
                 secret_hash,
             );
-            initiate_request(dependencies, id, peer, request).await?;
+            initiate_request(
+                dependencies.clone(),
+                id,
+                peer,
+                request,
+                body.rate.clone(),
+                trace_parent,
+            )
+            .await?;
         }
         SwapRequestBody {
             alpha_ledger: HttpLedger::Ethereum(alpha_ledger),
             beta_ledger: HttpLedger::Bitcoin(beta_ledger),
             alpha_asset: HttpAsset::Ether(alpha_asset),
             beta_asset: HttpAsset::Bitcoin(beta_asset),
-            alpha_expiry,
-            beta_expiry,
             identities,
             peer,
+            ..
         } => {
             let identities = identities.into_identities(&seed)?;
             let request = new_request(
@@ -94,17 +134,24 @@ pub async fn handle_post_swap<
                 identities,
                 secret_hash,
             );
-            initiate_request(dependencies, id, peer, request).await?;
+            initiate_request(
+                dependencies.clone(),
+                id,
+                peer,
+                request,
+                body.rate.clone(),
+                trace_parent,
+            )
+            .await?;
         }
         SwapRequestBody {
             alpha_ledger: HttpLedger::Bitcoin(alpha_ledger),
             beta_ledger: HttpLedger::Ethereum(beta_ledger),
             alpha_asset: HttpAsset::Bitcoin(alpha_asset),
             beta_asset: HttpAsset::Erc20(beta_asset),
-            alpha_expiry,
-            beta_expiry,
             identities,
             peer,
+            ..
         } => {
             let identities = identities.into_identities(&seed)?;
             let request = new_request(
@@ -118,17 +165,24 @@ pub async fn handle_post_swap<
                 identities,
                 secret_hash,
             );
-            initiate_request(dependencies, id, peer, request).await?;
+            initiate_request(
+                dependencies.clone(),
+                id,
+                peer,
+                request,
+                body.rate.clone(),
+                trace_parent,
+            )
+            .await?;
         }
         SwapRequestBody {
             alpha_ledger: HttpLedger::Ethereum(alpha_ledger),
             beta_ledger: HttpLedger::Bitcoin(beta_ledger),
             alpha_asset: HttpAsset::Erc20(alpha_asset),
             beta_asset: HttpAsset::Bitcoin(beta_asset),
-            alpha_expiry,
-            beta_expiry,
             identities,
             peer,
+            ..
         } => {
             let identities = identities.into_identities(&seed)?;
             let request = new_request(
@@ -142,7 +196,15 @@ pub async fn handle_post_swap<
                 identities,
                 secret_hash,
             );
-            initiate_request(dependencies, id, peer, request).await?;
+            initiate_request(
+                dependencies.clone(),
+                id,
+                peer,
+                request,
+                body.rate.clone(),
+                trace_parent,
+            )
+            .await?;
         }
         _ => {
             return Err(anyhow::Error::from(UnsupportedSwap {
@@ -154,6 +216,22 @@ pub async fn handle_post_swap<
         }
     }
 
+    if let Some(auto_refund) = body.auto_refund {
+        AutoRefunds::enable_auto_refund(&dependencies, &id, AutoRefund {
+            address: auto_refund.address,
+            fee_per_wu: auto_refund.fee_per_wu,
+        })
+        .await?;
+    }
+
+    if let Some(account) = payout_account {
+        PayoutAccountUsages::record_payout_account_usage(&dependencies, &id, &account).await?;
+    }
+
+    if metadata != SwapMetadata::default() {
+        MetadataStore::save_metadata(&dependencies, &id, metadata).await?;
+    }
+
     Ok(SwapCreated { id })
 }
 
@@ -164,8 +242,8 @@ fn new_request<AL, BL, AA, BA>(
     beta_ledger: BL,
     alpha_asset: AA,
     beta_asset: BA,
-    alpha_expiry: Option<Timestamp>,
-    beta_expiry: Option<Timestamp>,
+    alpha_expiry: Timestamp,
+    beta_expiry: Timestamp,
     identities: Identities<AL, BL>,
     secret_hash: SecretHash,
 ) -> rfc003::Request<AL, BL, AA, BA>
@@ -184,21 +262,29 @@ where
         hash_function: HashFunction::Sha256,
         alpha_ledger_refund_identity: identities.alpha_ledger_refund_identity,
         beta_ledger_redeem_identity: identities.beta_ledger_redeem_identity,
-        alpha_expiry: alpha_expiry.unwrap_or_else(default_alpha_expiry),
-        beta_expiry: beta_expiry.unwrap_or_else(default_beta_expiry),
+        alpha_expiry,
+        beta_expiry,
         secret_hash,
     }
 }
 
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+pub enum InvalidAutoRefund {
+    #[error("only the bitcoin of swaps from bitcoin to ether or ERC20 tokens can be refunded automatically")]
+    UnsupportedSwap,
+    #[error("refunds can only be broadcast if bitcoin.rpc_credentials are configured")]
+    BroadcastNotConfigured,
+}
+
 /// An error type for describing that a particular combination of assets and
 /// ledgers is not supported.
 #[derive(Debug, thiserror::Error)]
 #[error("swapping {alpha_asset:?} for {beta_asset:?} from {alpha_ledger:?} to {beta_ledger:?} is not supported")]
 pub struct UnsupportedSwap {
-    alpha_asset: HttpAsset,
-    beta_asset: HttpAsset,
-    alpha_ledger: HttpLedger,
-    beta_ledger: HttpLedger,
+    pub alpha_asset: HttpAsset,
+    pub beta_asset: HttpAsset,
+    pub alpha_ledger: HttpLedger,
+    pub beta_ledger: HttpLedger,
 }
 
 async fn initiate_request<D, AL, BL, AA, BA>(
@@ -206,66 +292,68 @@ async fn initiate_request<D, AL, BL, AA, BA>(
     id: SwapId,
     peer: DialInformation,
     swap_request: rfc003::Request<AL, BL, AA, BA>,
+    rate: Option<Rate>,
+    trace_parent: Option<TraceContext>,
 ) -> anyhow::Result<()>
 where
     D: StateStore
-        + Executor
-        + SendRequest
         + SwapSeed
-        + Save<Request<AL, BL, AA, BA>>
-        + Save<Accept<AL, BL>>
-        + Save<Swap>
-        + Save<Decline>
-        + LedgerEventsCreator
-        + CreateLedgerEvents<AL, AA>
-        + CreateLedgerEvents<BL, BA>
-        + Clone,
+        + SwapEvents
+        + RequestedRates
+        + IssueIdentities
+        + SwapTracing
+        + Enqueue<Request<AL, BL, AA, BA>>,
     AL: Ledger,
     BL: Ledger,
     AA: Asset,
     BA: Asset,
 {
     let counterparty = peer.peer_id.clone();
-    let seed = dependencies.swap_seed(id);
-
-    Save::save(&dependencies, Swap::new(id, Role::Alice, counterparty)).await?;
-    Save::save(&dependencies, swap_request.clone()).await?;
-
+    let seed = dependencies.swap_secrets(id, Role::Alice);
+
+    let issued = vec![
+        derived_identity(
+            &swap_request.alpha_ledger_refund_identity,
+            AL::derive_refund_identity(&seed),
+        ),
+        derived_identity(
+            &swap_request.beta_ledger_redeem_identity,
+            BL::derive_redeem_identity(&seed),
+        ),
+    ];
+    let issued = issued.into_iter().flatten().collect::<Vec<_>>();
+    IssueIdentities::issue_identities(&dependencies, &id, &issued).await?;
+
+    // The state has to be there before the outbox dispatcher can pick up the
+    // request and replace it with the response.
     let state = State::proposed(swap_request.clone(), seed);
     StateStore::insert(&dependencies, id, state);
 
-    let future = {
-        async move {
-            let response = dependencies
-                .send_request(peer.clone(), swap_request.clone())
-                .compat()
-                .await
-                .with_context(|| format!("Failed to send swap request to {}", peer.clone()))?;
-
-            match response {
-                Ok(accept) => {
-                    Save::save(&dependencies, accept).await?;
-
-                    swap_protocols::init_accepted_swap(
-                        &dependencies,
-                        swap_request,
-                        accept,
-                        Role::Alice,
-                    )?;
-                }
-                Err(decline) => {
-                    log::info!("Swap declined: {:?}", decline);
-                    let state = State::declined(swap_request.clone(), decline.clone(), seed);
-                    StateStore::insert(&dependencies, id, state.clone());
-                    Save::save(&dependencies, decline.clone()).await?;
-                }
-            };
-            Ok(())
+    // The outbox dispatcher may send the request as soon as it is enqueued.
+    if let Some(rate) = rate {
+        if let Err(e) = RequestedRates::save_requested_rate(&dependencies, &id, &rate).await {
+            StateStore::remove(&dependencies, &id);
+            return Err(e);
         }
-    };
-    tokio::spawn(future.boxed().compat().map_err(|e: anyhow::Error| {
-        log::error!("{:?}", e);
-    }));
+    }
+
+    if let Err(e) = Enqueue::save_and_enqueue(
+        &dependencies,
+        Swap::new(id, Role::Alice, counterparty),
+        swap_request,
+        peer,
+    )
+    .await
+    {
+        StateStore::remove(&dependencies, &id);
+        return Err(e);
+    }
+
+    if let Some(trace_parent) = trace_parent {
+        SwapTracing::continue_trace(&dependencies, id, trace_parent);
+    }
+    SwapEvents::publish(&dependencies, SwapEvent::Created { swap_id: id });
+
     Ok(())
 }
 
@@ -291,16 +379,68 @@ struct SwapRequestBody {
     #[serde(flatten)]
     identities: HttpIdentities,
     peer: DialInformation,
+    /// Refund the alpha asset automatically once it expired without the
+    /// counterparty redeeming it.
+    auto_refund: Option<HttpAutoRefund>,
+    /// Opaque to cnd, returned with the swap and usable to filter the list
+    /// of swaps.
+    external_id: Option<String>,
+    note: Option<String>,
+    /// The units of the beta asset Alice expects per unit of the alpha asset,
+    /// sent along with the request for Bob to judge it by.
+    rate: Option<Rate>,
 }
 
-/// The identities a user may have to provide for a given swap.
-///
-/// To make the implementation easier, this is hardcoded to Ethereum addresses
-/// for now because those are always provided upfront.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+struct HttpAutoRefund {
+    address: bitcoin::Address,
+    fee_per_wu: usize,
+}
+
+impl HttpAutoRefund {
+    /// Only accept swaps the periodic refund knows how to sign for, such that
+    /// they are rejected once here rather than failing on every check.
+    fn validate(
+        &self,
+        body: &SwapRequestBody,
+        auto_refund_enabled: bool,
+    ) -> Result<(), InvalidAutoRefund> {
+        let refundable = match (
+            &body.alpha_ledger,
+            &body.beta_ledger,
+            &body.alpha_asset,
+            &body.beta_asset,
+        ) {
+            (
+                HttpLedger::Bitcoin(_),
+                HttpLedger::Ethereum(_),
+                HttpAsset::Bitcoin(_),
+                HttpAsset::Ether(_),
+            )
+            | (
+                HttpLedger::Bitcoin(_),
+                HttpLedger::Ethereum(_),
+                HttpAsset::Bitcoin(_),
+                HttpAsset::Erc20(_),
+            ) => true,
+            _ => false,
+        };
+
+        match (refundable, auto_refund_enabled) {
+            (true, true) => Ok(()),
+            (true, false) => Err(InvalidAutoRefund::BroadcastNotConfigured),
+            (false, _) => Err(InvalidAutoRefund::UnsupportedSwap),
+        }
+    }
+}
+
+/// The identities a user may provide for a given swap, in the format of the
+/// respective ledger. Those that are left out are derived from the seed of the
+/// swap.
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 struct HttpIdentities {
-    alpha_ledger_refund_identity: Option<ethereum::Address>,
-    beta_ledger_redeem_identity: Option<ethereum::Address>,
+    alpha_ledger_refund_identity: Option<serde_json::Value>,
+    beta_ledger_redeem_identity: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone)]
@@ -309,125 +449,50 @@ struct Identities<AL: Ledger, BL: Ledger> {
     pub beta_ledger_redeem_identity: BL::Identity,
 }
 
-trait IntoIdentities<AL: Ledger, BL: Ledger> {
-    fn into_identities(
-        self,
-        secret_source: &dyn SecretSource,
-    ) -> anyhow::Result<Identities<AL, BL>>;
-}
-
-#[derive(Debug, thiserror::Error)]
-#[error("{kind} identity was not expected")]
-pub struct UnexpectedIdentity {
-    kind: IdentityKind,
-}
-
-#[derive(Debug, thiserror::Error)]
-#[error("{kind} identity was missing")]
-pub struct MissingIdentity {
-    kind: IdentityKind,
-}
-
-#[derive(Debug, thiserror::Error)]
-#[error("{kind} was not a valid ethereum address")]
-pub struct InvalidEthereumAddress {
-    kind: IdentityKind,
-    source: <ethereum::Address as FromStr>::Err,
-}
-
-#[derive(strum_macros::Display, Debug)]
-#[strum(serialize_all = "snake_case")]
-pub enum IdentityKind {
-    AlphaLedgerRefundIdentity,
-    BetaLedgerRedeemIdentity,
-}
-
-impl IntoIdentities<ledger::Bitcoin, ledger::Ethereum> for HttpIdentities {
-    fn into_identities(
+impl HttpIdentities {
+    fn into_identities<AL: Ledger, BL: Ledger>(
         self,
         secret_source: &dyn SecretSource,
-    ) -> anyhow::Result<Identities<ledger::Bitcoin, ledger::Ethereum>> {
-        let HttpIdentities {
-            alpha_ledger_refund_identity,
-            beta_ledger_redeem_identity,
-        } = self;
-
-        let beta_ledger_redeem_identity =
-            match (alpha_ledger_refund_identity, beta_ledger_redeem_identity) {
-                (None, Some(beta_ledger_redeem_identity)) => beta_ledger_redeem_identity,
-                (_, None) => {
-                    return Err(anyhow::Error::from(MissingIdentity {
-                        kind: IdentityKind::BetaLedgerRedeemIdentity,
-                    }))
-                }
-                (Some(_), _) => {
-                    return Err(anyhow::Error::from(UnexpectedIdentity {
-                        kind: IdentityKind::AlphaLedgerRefundIdentity,
-                    }))
-                }
-            };
-
-        let alpha_ledger_refund_identity = crate::bitcoin::PublicKey::from_secret_key(
-            &*crate::SECP,
-            &secret_source.secp256k1_refund(),
-        );
+    ) -> anyhow::Result<Identities<AL, BL>> {
+        let alpha_ledger_refund_identity = identities::parse_identity::<AL>(
+            self.alpha_ledger_refund_identity,
+            IdentityKind::AlphaLedgerRefundIdentity,
+        )?;
+        let beta_ledger_redeem_identity = identities::parse_identity::<BL>(
+            self.beta_ledger_redeem_identity,
+            IdentityKind::BetaLedgerRedeemIdentity,
+        )?;
 
         Ok(Identities {
-            alpha_ledger_refund_identity,
-            beta_ledger_redeem_identity,
+            alpha_ledger_refund_identity: identities::refund_identity::<AL>(
+                alpha_ledger_refund_identity,
+                IdentityKind::AlphaLedgerRefundIdentity,
+                secret_source,
+            )?,
+            beta_ledger_redeem_identity: identities::redeem_identity::<BL>(
+                beta_ledger_redeem_identity,
+                IdentityKind::BetaLedgerRedeemIdentity,
+                secret_source,
+            )?,
         })
     }
 }
 
-impl IntoIdentities<ledger::Ethereum, ledger::Bitcoin> for HttpIdentities {
-    fn into_identities(
-        self,
-        secret_source: &dyn SecretSource,
-    ) -> anyhow::Result<Identities<ledger::Ethereum, ledger::Bitcoin>> {
-        let HttpIdentities {
-            alpha_ledger_refund_identity,
-            beta_ledger_redeem_identity,
-        } = self;
-
-        let alpha_ledger_refund_identity =
-            match (alpha_ledger_refund_identity, beta_ledger_redeem_identity) {
-                (Some(alpha_ledger_refund_identity), None) => alpha_ledger_refund_identity,
-                (_, Some(_)) => {
-                    return Err(anyhow::Error::from(UnexpectedIdentity {
-                        kind: IdentityKind::BetaLedgerRedeemIdentity,
-                    }))
-                }
-                (None, _) => {
-                    return Err(anyhow::Error::from(MissingIdentity {
-                        kind: IdentityKind::AlphaLedgerRefundIdentity,
-                    }))
-                }
-            };
-
-        let beta_ledger_redeem_identity = crate::bitcoin::PublicKey::from_secret_key(
-            &*crate::SECP,
-            &secret_source.secp256k1_redeem(),
-        );
-
-        Ok(Identities {
-            alpha_ledger_refund_identity,
-            beta_ledger_redeem_identity,
-        })
+fn chain(ledger: &HttpLedger) -> Chain {
+    match ledger {
+        HttpLedger::Bitcoin(_) => Chain::Bitcoin,
+        HttpLedger::Ethereum(_) => Chain::Ethereum,
     }
 }
 
-fn default_alpha_expiry() -> Timestamp {
-    Timestamp::now().plus(60 * 60 * 24)
-}
-
-fn default_beta_expiry() -> Timestamp {
-    Timestamp::now().plus(60 * 60 * 12)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{network::DialInformation, swap_protocols::ledger::ethereum::ChainId};
+    use crate::{
+        network::DialInformation,
+        seed::Seed,
+        swap_protocols::ledger::{self, ethereum::ChainId},
+    };
     use spectral::prelude::*;
 
     #[test]
@@ -532,4 +597,76 @@ mod tests {
                 chain_id: ChainId::new(3),
             }));
     }
+
+    #[test]
+    fn auto_refund_is_only_valid_for_bitcoin_if_broadcast_is_configured() {
+        let body = r#"{
+                "alpha_ledger": {
+                    "name": "bitcoin",
+                    "network": "regtest"
+                },
+                "beta_ledger": {
+                    "name": "ethereum",
+                    "network": "regtest"
+                },
+                "alpha_asset": {
+                    "name": "bitcoin",
+                    "quantity": "100000000"
+                },
+                "beta_asset": {
+                    "name": "ether",
+                    "quantity": "10000000000000000000"
+                },
+                "beta_ledger_redeem_identity": "0x00a329c0648769a73afac7f9381e08fb43dbea72",
+                "peer": "Qma9T5YraSnpRDZqRR4krcSJabThc8nwZuJV3LercPHufi",
+                "auto_refund": {
+                    "address": "2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9",
+                    "fee_per_wu": 10
+                }
+            }"#;
+
+        let body = serde_json::from_str::<SwapRequestBody>(body).unwrap();
+        let auto_refund = body.auto_refund.clone().unwrap();
+        let reversed = SwapRequestBody {
+            alpha_ledger: body.beta_ledger.clone(),
+            beta_ledger: body.alpha_ledger.clone(),
+            alpha_asset: body.beta_asset.clone(),
+            beta_asset: body.alpha_asset.clone(),
+            ..body.clone()
+        };
+
+        assert_that(&auto_refund.validate(&body, true)).is_ok();
+        assert_that(&auto_refund.validate(&body, false)).is_err();
+        assert_that(&auto_refund.validate(&reversed, true)).is_err();
+    }
+
+    #[test]
+    fn identities_that_are_not_supplied_are_derived_if_the_ledger_allows() {
+        let seed = Seed::from(*b"hello world, you are beautiful!!");
+        let refund_identity =
+            crate::bitcoin::PublicKey::from_secret_key(&*crate::SECP, &seed.secp256k1_redeem());
+        let redeem_identity = serde_json::json!("0x00a329c0648769a73afac7f9381e08fb43dbea72");
+
+        let supplied = HttpIdentities {
+            alpha_ledger_refund_identity: Some(serde_json::to_value(refund_identity).unwrap()),
+            beta_ledger_redeem_identity: Some(redeem_identity.clone()),
+        }
+        .into_identities::<ledger::Bitcoin, ledger::Ethereum>(&seed)
+        .unwrap();
+        let derived = HttpIdentities {
+            alpha_ledger_refund_identity: None,
+            beta_ledger_redeem_identity: Some(redeem_identity),
+        }
+        .into_identities::<ledger::Bitcoin, ledger::Ethereum>(&seed)
+        .unwrap();
+        let missing = HttpIdentities {
+            alpha_ledger_refund_identity: None,
+            beta_ledger_redeem_identity: None,
+        }
+        .into_identities::<ledger::Ethereum, ledger::Bitcoin>(&seed);
+
+        assert_that(&supplied.alpha_ledger_refund_identity).is_equal_to(refund_identity);
+        assert_that(&derived.alpha_ledger_refund_identity).is_not_equal_to(refund_identity);
+        assert_that(&missing).is_err();
+    }
 }
@@ -0,0 +1,174 @@
+use crate::{
+    db::{DetermineTypes, Retention, Retrieve},
+    http_api::{
+        routes::{
+            internal::HtlcVectors,
+            rfc003::{handlers::receipt::TransactionId, LedgerState},
+        },
+        Http,
+    },
+    swap_protocols::{
+        asset::Asset,
+        rfc003::{self, state_machine::HtlcParams, state_store::StateStore, HtlcState, Ledger},
+        InclusionProver, ProveInclusion, SwapId,
+    },
+};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("proofs are only available for accepted swaps that finished")]
+pub struct ProofUnavailable;
+
+/// Everything a third party needs to check the outcome of a swap without
+/// trusting either of its parties, e.g. to resolve a dispute.
+///
+/// The HTLCs follow from the parameters both parties agreed on, the proofs tie
+/// the transactions of the HTLCs to blocks of their ledger and the secret, if
+/// it was revealed, hashes to the secret hash of the HTLCs.
+#[derive(Debug, Serialize)]
+pub struct ProofBundle {
+    swap_id: Http<SwapId>,
+    role: String,
+    secret_hash: rfc003::SecretHash,
+    /// Hex encoded, `None` if no HTLC was redeemed.
+    secret: Option<String>,
+    alpha_ledger: LedgerProofs,
+    beta_ledger: LedgerProofs,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LedgerProofs {
+    status: HtlcState,
+    htlc: HtlcVectors,
+    htlc_location: Option<serde_json::Value>,
+    transactions: Vec<ProvenTransaction>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProvenTransaction {
+    /// A Bitcoin HTLC is deployed and funded by the same transaction.
+    kinds: Vec<TransactionKind>,
+    id: String,
+    transaction: serde_json::Value,
+    /// `None` if the transaction is not in a block, e.g. because it was
+    /// reorganized out of the chain.
+    proof: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Deploy,
+    Fund,
+    TopUp,
+    Redeem,
+    Refund,
+}
+
+pub async fn handle_get_proof<D>(dependencies: D, id: SwapId) -> anyhow::Result<ProofBundle>
+where
+    D: Retrieve + DetermineTypes + StateStore + Retention + InclusionProver,
+{
+    let swap = Retrieve::get(&dependencies, &id).await?;
+    Retention::finished_at(&dependencies, &id)
+        .await?
+        .ok_or(ProofUnavailable)?;
+    let types = DetermineTypes::determine_types(&dependencies, &id).await?;
+
+    with_swap_types!(types, {
+        let state = StateStore::get::<ROLE>(&dependencies, &id)?
+            .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", id))?;
+        let (request, response) = match state.swap_communication {
+            rfc003::SwapCommunication::Accepted { request, response } => (request, response),
+            _ => return Err(ProofUnavailable.into()),
+        };
+
+        let alpha_ledger = ledger_proofs(
+            &dependencies,
+            HtlcParams::new_alpha_params(&request, &response),
+            state.alpha_ledger_state,
+        )
+        .await?;
+        let beta_ledger = ledger_proofs(
+            &dependencies,
+            HtlcParams::new_beta_params(&request, &response),
+            state.beta_ledger_state,
+        )
+        .await?;
+
+        Ok(ProofBundle {
+            swap_id: Http(swap.swap_id),
+            role: swap.role.to_string(),
+            secret_hash: request.secret_hash,
+            secret: state.secret.map(|secret| format!("{:x}", secret)),
+            alpha_ledger,
+            beta_ledger,
+        })
+    })
+}
+
+async fn ledger_proofs<D, L, A>(
+    dependencies: &D,
+    htlc_params: HtlcParams<L, A>,
+    ledger_state: rfc003::LedgerState<L>,
+) -> anyhow::Result<LedgerProofs>
+where
+    D: ProveInclusion<L>,
+    L: Ledger,
+    A: Asset,
+    L::Transaction: TransactionId,
+    HtlcVectors: From<HtlcParams<L, A>>,
+    Http<L::HtlcLocation>: Serialize,
+    Http<L::Transaction>: Serialize,
+{
+    let htlc = HtlcVectors::from(htlc_params);
+    let ledger_state = LedgerState::<L::HtlcLocation, L::Transaction>::from(ledger_state);
+
+    let mut transactions = Vec::<(Vec<TransactionKind>, L::Transaction)>::new();
+    {
+        let mut add = |kind, transaction: Option<Http<L::Transaction>>| {
+            if let Some(Http(transaction)) = transaction {
+                match transactions
+                    .iter_mut()
+                    .find(|(_, known)| *known == transaction)
+                {
+                    Some((kinds, _)) => kinds.push(kind),
+                    None => transactions.push((vec![kind], transaction)),
+                }
+            }
+        };
+        add(TransactionKind::Deploy, ledger_state.deploy_tx);
+        add(TransactionKind::Fund, ledger_state.fund_tx);
+        for top_up in ledger_state.top_ups {
+            add(TransactionKind::TopUp, Some(top_up.fund_tx));
+        }
+        add(TransactionKind::Redeem, ledger_state.redeem_tx);
+        add(TransactionKind::Refund, ledger_state.refund_tx);
+    }
+
+    let mut proven = Vec::with_capacity(transactions.len());
+    for (kinds, transaction) in transactions {
+        let proof = dependencies
+            .prove_inclusion(&transaction)
+            .await?
+            .map(serde_json::to_value)
+            .transpose()?;
+
+        proven.push(ProvenTransaction {
+            kinds,
+            id: transaction.transaction_id(),
+            transaction: serde_json::to_value(Http(transaction))?,
+            proof,
+        });
+    }
+
+    Ok(LedgerProofs {
+        status: ledger_state.status,
+        htlc,
+        htlc_location: ledger_state
+            .htlc_location
+            .map(serde_json::to_value)
+            .transpose()?,
+        transactions: proven,
+    })
+}
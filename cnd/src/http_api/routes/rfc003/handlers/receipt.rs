@@ -0,0 +1,250 @@
+use crate::{
+    db::{DetermineTypes, LoadAcceptedSwap, Retention, Retrieve, Swap},
+    ethereum::{self, Erc20Token, EtherQuantity},
+    http_api::{routes::rfc003::LedgerState, Http, SwapParameters},
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{self, state_store::StateStore, HtlcState, Ledger, SecretHash},
+        SwapId,
+    },
+    timestamp::Timestamp,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use libp2p::{identity, PeerId};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("receipts are only issued for accepted swaps that finished")]
+pub struct ReceiptUnavailable;
+
+/// Proof of the terms and the outcome of a swap, signed with the key the
+/// node is identified with on the network.
+///
+/// `signature` is made over the JSON serialization of `receipt` exactly as
+/// it appears in the response, i.e. with its fields in the given order and
+/// without any whitespace.
+#[derive(Debug, Serialize)]
+pub struct SignedReceipt {
+    receipt: Receipt,
+    /// Hex encoded libp2p public key in its protobuf encoding, the peer id of
+    /// the signer is derived from it.
+    public_key: String,
+    /// Hex encoded signature.
+    signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Receipt {
+    swap_id: Http<SwapId>,
+    role: String,
+    signer: Http<PeerId>,
+    counterparty: Http<PeerId>,
+    parameters: SwapParameters,
+    alpha_expiry: Timestamp,
+    beta_expiry: Timestamp,
+    secret_hash: SecretHash,
+    alpha_ledger: LedgerReceipt,
+    beta_ledger: LedgerReceipt,
+    accepted_at: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LedgerReceipt {
+    status: HtlcState,
+    deploy_txid: Option<String>,
+    fund_txid: Option<String>,
+    redeem_txid: Option<String>,
+    refund_txid: Option<String>,
+}
+
+/// The id a transaction can be looked up with on its ledger.
+pub trait TransactionId {
+    fn transaction_id(&self) -> String;
+}
+
+impl TransactionId for bitcoin::Transaction {
+    fn transaction_id(&self) -> String {
+        self.txid().to_string()
+    }
+}
+
+impl TransactionId for ethereum::Transaction {
+    fn transaction_id(&self) -> String {
+        format!("{:#x}", self.hash)
+    }
+}
+
+impl<L: Ledger> From<rfc003::LedgerState<L>> for LedgerReceipt
+where
+    L::Transaction: TransactionId,
+{
+    fn from(ledger_state: rfc003::LedgerState<L>) -> Self {
+        let ledger_state = LedgerState::<L::HtlcLocation, L::Transaction>::from(ledger_state);
+        let txid = |transaction: Option<Http<L::Transaction>>| {
+            transaction.map(|transaction| transaction.transaction_id())
+        };
+
+        LedgerReceipt {
+            status: ledger_state.status,
+            deploy_txid: txid(ledger_state.deploy_tx),
+            fund_txid: txid(ledger_state.fund_tx),
+            redeem_txid: txid(ledger_state.redeem_tx),
+            refund_txid: txid(ledger_state.refund_tx),
+        }
+    }
+}
+
+pub async fn handle_get_receipt<D>(
+    dependencies: D,
+    key_pair: identity::Keypair,
+    id: SwapId,
+) -> anyhow::Result<SignedReceipt>
+where
+    D: Retrieve
+        + DetermineTypes
+        + StateStore
+        + Retention
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let swap = Retrieve::get(&dependencies, &id).await?;
+    let finished_at = Retention::finished_at(&dependencies, &id)
+        .await?
+        .ok_or(ReceiptUnavailable)?;
+    let types = DetermineTypes::determine_types(&dependencies, &id).await?;
+
+    let receipt = with_swap_types!(types, {
+        let state = StateStore::get::<ROLE>(&dependencies, &id)?
+            .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", id))?;
+        match state.swap_communication {
+            rfc003::SwapCommunication::Accepted { .. } => {}
+            _ => return Err(ReceiptUnavailable.into()),
+        }
+
+        let (request, _, accepted_at) =
+            LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(&dependencies, &id).await?;
+
+        receipt(
+            swap,
+            PeerId::from(key_pair.public()),
+            request,
+            state.alpha_ledger_state,
+            state.beta_ledger_state,
+            accepted_at,
+            finished_at,
+        )
+    });
+
+    sign(receipt, &key_pair)
+}
+
+fn receipt<AL, BL, AA, BA>(
+    swap: Swap,
+    signer: PeerId,
+    request: rfc003::Request<AL, BL, AA, BA>,
+    alpha_ledger_state: rfc003::LedgerState<AL>,
+    beta_ledger_state: rfc003::LedgerState<BL>,
+    accepted_at: NaiveDateTime,
+    finished_at: NaiveDateTime,
+) -> Receipt
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+    AL::Transaction: TransactionId,
+    BL::Transaction: TransactionId,
+    SwapParameters: From<rfc003::Request<AL, BL, AA, BA>>,
+{
+    Receipt {
+        swap_id: Http(swap.swap_id),
+        role: swap.role.to_string(),
+        signer: Http(signer),
+        counterparty: Http(swap.counterparty),
+        alpha_expiry: request.alpha_expiry,
+        beta_expiry: request.beta_expiry,
+        secret_hash: request.secret_hash,
+        parameters: SwapParameters::from(request),
+        alpha_ledger: LedgerReceipt::from(alpha_ledger_state),
+        beta_ledger: LedgerReceipt::from(beta_ledger_state),
+        accepted_at: DateTime::from_utc(accepted_at, Utc),
+        finished_at: DateTime::from_utc(finished_at, Utc),
+    }
+}
+
+fn sign(receipt: Receipt, key_pair: &identity::Keypair) -> anyhow::Result<SignedReceipt> {
+    let signature = key_pair.sign(&serde_json::to_vec(&receipt)?)?;
+
+    Ok(SignedReceipt {
+        receipt,
+        public_key: hex::encode(key_pair.public().into_protobuf_encoding()),
+        signature: hex::encode(signature),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ethereum::Address,
+        swap_protocols::{
+            rfc003::{messages::Request, Secret},
+            HashFunction, Role,
+        },
+    };
+    use bitcoin::Amount;
+    use spectral::prelude::*;
+
+    fn receipt() -> Receipt {
+        let counterparty = PeerId::random();
+        let request = Request {
+            swap_id: SwapId::default(),
+            alpha_ledger: Bitcoin::default(),
+            beta_ledger: Ethereum::default(),
+            alpha_asset: Amount::from_btc(1.0).unwrap(),
+            beta_asset: EtherQuantity::from_eth(10.0),
+            hash_function: HashFunction::Sha256,
+            alpha_ledger_refund_identity: crate::bitcoin::PublicKey::new(
+                "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+                    .parse()
+                    .unwrap(),
+            ),
+            beta_ledger_redeem_identity: "8457037fcd80a8650c4692d7fcfc1d0a96b92867"
+                .parse::<Address>()
+                .unwrap(),
+            alpha_expiry: Timestamp::from(2_000_000_000),
+            beta_expiry: Timestamp::from(2_000_000_000),
+            secret_hash: Secret::from(*b"hello world, you are beautiful!!").hash(),
+        };
+
+        super::receipt(
+            Swap::new(request.swap_id, Role::Alice, counterparty.clone()),
+            counterparty,
+            request,
+            rfc003::LedgerState::NotDeployed,
+            rfc003::LedgerState::NotDeployed,
+            NaiveDateTime::from_timestamp(1_580_000_000, 0),
+            NaiveDateTime::from_timestamp(1_580_003_600, 0),
+        )
+    }
+
+    #[test]
+    fn signature_can_be_verified_with_the_public_key_of_the_receipt() {
+        let key_pair = identity::Keypair::generate_ed25519();
+
+        let signed = sign(receipt(), &key_pair).unwrap();
+
+        let public_key =
+            identity::PublicKey::from_protobuf_encoding(&hex::decode(&signed.public_key).unwrap())
+                .unwrap();
+        let message = serde_json::to_vec(&signed.receipt).unwrap();
+        let signature = hex::decode(&signed.signature).unwrap();
+
+        assert_that(&public_key.verify(&message, &signature)).is_true();
+        assert_that(&public_key.verify(b"some other receipt", &signature)).is_false();
+    }
+}
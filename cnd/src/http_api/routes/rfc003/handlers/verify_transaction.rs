@@ -0,0 +1,126 @@
+use crate::{
+    db::DetermineTypes,
+    ethereum::{GasOracle, GasPricing},
+    http_api::transaction_verification::{FeeLimits, VerifyTransaction},
+    swap_protocols::{
+        actions::Actions,
+        rfc003::{
+            actions::{Action, ActionKind},
+            state_store::StateStore,
+        },
+        SwapId,
+    },
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VerifyTransactionBody {
+    /// The signed transaction as hex, Bitcoin transactions in their consensus
+    /// encoding and Ethereum transactions RLP encoded.
+    #[serde(deserialize_with = "hex_bytes")]
+    transaction: Vec<u8>,
+}
+
+fn hex_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+
+    hex::decode(hex.trim_start_matches("0x")).map_err(D::Error::custom)
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifiedTransaction {
+    /// The action the transaction performs.
+    action: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("the transaction does not match any action that is available for the swap")]
+pub struct UnverifiedTransaction {
+    /// Why the transaction does not match, by action.
+    pub mismatches: BTreeMap<String, String>,
+}
+
+/// Checks a transaction the client signed against the ledger actions that are
+/// available in the current state of the swap, the client should only
+/// broadcast it if one of them matches.
+#[allow(clippy::cognitive_complexity)]
+pub async fn handle_verify_transaction<D: DetermineTypes + StateStore + GasOracle>(
+    dependencies: D,
+    swap_id: SwapId,
+    body: serde_json::Value,
+) -> anyhow::Result<VerifiedTransaction> {
+    let body = serde_json::from_value::<VerifyTransactionBody>(body)?;
+    let types = dependencies.determine_types(&swap_id).await?;
+
+    let fee_limits = match GasOracle::gas_pricing(&dependencies).await {
+        Ok(GasPricing::Eip1559 {
+            max_fee_per_gas, ..
+        }) => FeeLimits {
+            max_fee_per_gas: Some(max_fee_per_gas),
+        },
+        Ok(GasPricing::Legacy) => FeeLimits::default(),
+        Err(e) => {
+            log::warn!(
+                "failed to determine gas pricing, not limiting fees: {:?}",
+                e
+            );
+            FeeLimits::default()
+        }
+    };
+
+    with_swap_types!(types, {
+        let state = StateStore::get::<ROLE>(&dependencies, &swap_id)?.ok_or_else(|| {
+            anyhow::anyhow!("state store did not contain an entry for {}", swap_id)
+        })?;
+
+        let mut mismatches = BTreeMap::new();
+        for action in state.actions() {
+            let action_kind = ActionKind::from(&action);
+            let verified = match &action {
+                Action::Deploy(action) => action.verify_transaction(&body.transaction, fee_limits),
+                Action::Fund(action) => action.verify_transaction(&body.transaction, fee_limits),
+                Action::Redeem(action) => action.verify_transaction(&body.transaction, fee_limits),
+                Action::Refund(action) => action.verify_transaction(&body.transaction, fee_limits),
+                Action::Accept(_)
+                | Action::Decline(_)
+                | Action::BumpFee(_)
+                | Action::Rebroadcast(_) => continue,
+            };
+
+            match verified {
+                Ok(()) => {
+                    return Ok(VerifiedTransaction {
+                        action: action_kind.to_string(),
+                    })
+                }
+                Err(mismatch) => {
+                    mismatches.insert(action_kind.to_string(), mismatch.to_string());
+                }
+            }
+        }
+
+        Err(anyhow::Error::from(UnverifiedTransaction { mismatches }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_is_accepted_with_and_without_0x_prefix() {
+        let with_prefix = serde_json::from_value::<VerifyTransactionBody>(serde_json::json!({
+            "transaction": "0x02f8"
+        }))
+        .unwrap();
+        let without_prefix = serde_json::from_value::<VerifyTransactionBody>(serde_json::json!({
+            "transaction": "02f8"
+        }))
+        .unwrap();
+
+        assert_eq!(with_prefix.transaction, vec![0x02, 0xf8]);
+        assert_eq!(without_prefix.transaction, vec![0x02, 0xf8]);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::swap_protocols::rfc003::{Ledger, SecretSource};
+
+#[derive(Debug, thiserror::Error)]
+#[error("{kind} identity was missing")]
+pub struct MissingIdentity {
+    kind: IdentityKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{kind} was not a valid identity of the ledger: {source}")]
+pub struct InvalidIdentity {
+    kind: IdentityKind,
+    source: serde_json::Error,
+}
+
+#[derive(Clone, Copy, strum_macros::Display, Debug, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+pub enum IdentityKind {
+    AlphaLedgerRedeemIdentity,
+    AlphaLedgerRefundIdentity,
+    BetaLedgerRedeemIdentity,
+    BetaLedgerRefundIdentity,
+}
+
+/// The identity the user supplied or, if they did not, the one cnd derives
+/// from the seed of the swap. Ledgers cnd cannot derive identities for need
+/// them to be supplied.
+pub fn redeem_identity<L: Ledger>(
+    supplied: Option<L::Identity>,
+    kind: IdentityKind,
+    secret_source: &dyn SecretSource,
+) -> Result<L::Identity, MissingIdentity> {
+    supplied
+        .or_else(|| L::derive_redeem_identity(secret_source))
+        .ok_or(MissingIdentity { kind })
+}
+
+/// See `redeem_identity`.
+pub fn refund_identity<L: Ledger>(
+    supplied: Option<L::Identity>,
+    kind: IdentityKind,
+    secret_source: &dyn SecretSource,
+) -> Result<L::Identity, MissingIdentity> {
+    supplied
+        .or_else(|| L::derive_refund_identity(secret_source))
+        .ok_or(MissingIdentity { kind })
+}
+
+/// Reads an identity that was given in whatever format `L` uses.
+pub fn parse_identity<L: Ledger>(
+    value: Option<serde_json::Value>,
+    kind: IdentityKind,
+) -> Result<Option<L::Identity>, InvalidIdentity> {
+    value
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|source| InvalidIdentity { kind, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ethereum::Address,
+        seed::Seed,
+        swap_protocols::ledger::{Bitcoin, Ethereum},
+    };
+    use spectral::prelude::*;
+
+    #[test]
+    fn supplied_identities_are_used_over_derived_ones() {
+        let seed = Seed::from(*b"hello world, you are beautiful!!");
+        let supplied =
+            crate::bitcoin::PublicKey::from_secret_key(&*crate::SECP, &seed.secp256k1_refund());
+
+        assert_that(&redeem_identity::<Bitcoin>(
+            Some(supplied),
+            IdentityKind::BetaLedgerRedeemIdentity,
+            &seed,
+        ))
+        .is_ok_containing(supplied);
+        assert_that(&redeem_identity::<Bitcoin>(
+            None,
+            IdentityKind::BetaLedgerRedeemIdentity,
+            &seed,
+        ))
+        .is_ok()
+        .is_not_equal_to(supplied);
+    }
+
+    #[test]
+    fn ethereum_identities_have_to_be_supplied() {
+        let seed = Seed::from(*b"hello world, you are beautiful!!");
+        let address = parse_identity::<Ethereum>(
+            Some(serde_json::json!(
+                "0x00a329c0648769a73afac7f9381e08fb43dbea72"
+            )),
+            IdentityKind::AlphaLedgerRefundIdentity,
+        )
+        .unwrap();
+
+        assert_that(&address).is_equal_to(Some(
+            "00a329c0648769a73afac7f9381e08fb43dbea72"
+                .parse::<Address>()
+                .unwrap(),
+        ));
+        assert_that(&refund_identity::<Ethereum>(
+            None,
+            IdentityKind::AlphaLedgerRefundIdentity,
+            &seed,
+        ))
+        .is_err();
+        assert_that(&parse_identity::<Ethereum>(
+            Some(serde_json::json!("not an address")),
+            IdentityKind::AlphaLedgerRefundIdentity,
+        ))
+        .is_err();
+    }
+}
@@ -1,75 +1,259 @@
 pub mod accept;
 pub mod decline;
 pub mod handlers;
+pub mod identities;
 mod swap_state;
 
 use crate::{
-    db::{DetermineTypes, Retrieve, Save, Swap},
+    block_lag::BlockLagMonitor,
+    clock_skew::ClockMonitor,
+    db::{DetermineTypes, LoadAcceptedSwap, Retrieve},
+    ethereum::{Erc20Token, EtherQuantity, GasOracle},
     http_api::{
         action::ActionExecutionParameters,
         route_factory::swap_path,
         routes::{
             into_rejection,
-            rfc003::handlers::{handle_action, handle_get_swap, handle_post_swap},
+            rfc003::handlers::{
+                handle_action, handle_get_action_history, handle_get_counterparty,
+                handle_get_proof, handle_get_receipt, handle_get_swap,
+                handle_get_swap_sub_resource, handle_post_swap, handle_verify_transaction,
+            },
         },
+        swap_resource::SwapSubResource,
+        PayoutAccounts, TokenRegistry,
     },
-    network::{Network, SendRequest},
+    maintenance::Maintenance,
+    network::Network,
+    price_feed::PriceFeed,
+    recovery,
+    redeem_destinations::RedeemDestination,
     seed::SwapSeed,
     swap_protocols::{
+        ledger::{Bitcoin, Ethereum},
         rfc003::{actions::ActionKind, state_store::StateStore},
-        LedgerEventsCreator, SwapId,
+        FeeAccounting, HtlcFinder, InclusionProver, LedgerEventsCreator, SwapEvents, SwapId,
+        SwapTasks,
     },
+    swap_tracing::{SwapTracing, TraceContext},
 };
 use futures::Future;
 use futures_core::future::{FutureExt, TryFutureExt};
 use hyper::header;
+use libp2p::identity;
 use warp::{http, Rejection, Reply};
 
 pub use self::swap_state::{LedgerState, SwapCommunication, SwapCommunicationState, SwapState};
-use crate::{db::Saver, http_api::problem};
+use crate::{
+    db::{
+        ActionHistory, AutoRefunds, ConstructedTransactions, Enqueuer, IssuedIdentities,
+        MetadataStore, PayoutAccountUsages, RequestedRates, Retention, Saver, SwapFailures,
+        SwapMessages,
+    },
+    expiries::ExpiryCalculator,
+    http_api::problem,
+    identity_reuse::IssueIdentities,
+};
 use tokio::executor::Executor;
 
-#[allow(clippy::needless_pass_by_value)]
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
 pub fn post_swap<
     D: Clone
         + StateStore
-        + Executor
-        + Save<Swap>
-        + SendRequest
         + SwapSeed
-        + Saver
-        + LedgerEventsCreator,
+        + Enqueuer
+        + SwapEvents
+        + AutoRefunds
+        + PayoutAccountUsages
+        + MetadataStore
+        + RequestedRates
+        + IssueIdentities
+        + SwapTracing,
 >(
     dependencies: D,
+    token_registry: TokenRegistry,
+    payout_accounts: PayoutAccounts,
+    expiry_calculator: ExpiryCalculator,
+    auto_refund_enabled: bool,
+    maintenance: Maintenance,
     body: serde_json::Value,
+    traceparent: Option<String>,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move {
+        maintenance.ensure_off()?;
+
+        // An invalid header must not fail the request, the swap just starts
+        // a new trace then.
+        let trace_parent = traceparent.and_then(|header| header.parse::<TraceContext>().ok());
+
+        handle_post_swap(
+            dependencies,
+            token_registry,
+            payout_accounts,
+            expiry_calculator,
+            auto_refund_enabled,
+            body,
+            trace_parent,
+        )
+        .await
+    }
+    .boxed()
+    .compat()
+    .map(|swap_created| {
+        let body = warp::reply::json(&swap_created);
+        let response = warp::reply::with_header(body, header::LOCATION, swap_path(swap_created.id));
+        warp::reply::with_status(response, warp::http::StatusCode::CREATED)
+    })
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_swap<
+    D: DetermineTypes
+        + Retrieve
+        + StateStore
+        + SwapFailures
+        + MetadataStore
+        + RequestedRates
+        + IssuedIdentities
+        + SwapTasks,
+>(
+    dependencies: D,
+    clock_monitor: ClockMonitor,
+    block_lag_monitor: BlockLagMonitor,
+    price_feed: PriceFeed,
+    id: SwapId,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    handle_get_swap(
+        dependencies,
+        clock_monitor,
+        block_lag_monitor,
+        price_feed,
+        id,
+    )
+    .boxed()
+    .compat()
+    .map(|swap_resource| warp::reply::json(&swap_resource))
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_swap_sub_resource<D: DetermineTypes + Retrieve + StateStore>(
+    dependencies: D,
+    id: SwapId,
+    sub_resource: SwapSubResource,
 ) -> impl Future<Item = impl Reply, Error = Rejection> {
-    handle_post_swap(dependencies, body)
+    handle_get_swap_sub_resource(dependencies, id, sub_resource)
         .boxed()
         .compat()
-        .map(|swap_created| {
-            let body = warp::reply::json(&swap_created);
-            let response =
-                warp::reply::with_header(body, header::LOCATION, swap_path(swap_created.id));
-            warp::reply::with_status(response, warp::http::StatusCode::CREATED)
-        })
+        .map(|entity| warp::reply::json(&entity))
         .map_err(problem::from_anyhow)
         .map_err(into_rejection)
 }
 
 #[allow(clippy::needless_pass_by_value)]
-pub fn get_swap<D: DetermineTypes + Retrieve + StateStore>(
+pub fn get_action_history<D: Retrieve + ActionHistory>(
     dependencies: D,
     id: SwapId,
 ) -> impl Future<Item = impl Reply, Error = Rejection> {
-    handle_get_swap(dependencies, id)
+    handle_get_action_history(dependencies, id)
         .boxed()
         .compat()
-        .map(|swap_resource| warp::reply::json(&swap_resource))
+        .map(|history| warp::reply::json(&history))
         .map_err(problem::from_anyhow)
         .map_err(into_rejection)
 }
 
 #[allow(clippy::needless_pass_by_value)]
+pub fn get_counterparty<D: Retrieve + Network>(
+    dependencies: D,
+    id: SwapId,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    handle_get_counterparty(dependencies, id)
+        .boxed()
+        .compat()
+        .map(|counterparty| warp::reply::json(&counterparty))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value, clippy::type_repetition_in_bounds)]
+pub fn get_receipt<D>(
+    dependencies: D,
+    key_pair: identity::Keypair,
+    id: SwapId,
+) -> impl Future<Item = impl Reply, Error = Rejection>
+where
+    D: Retrieve
+        + DetermineTypes
+        + StateStore
+        + Retention
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    handle_get_receipt(dependencies, key_pair, id)
+        .boxed()
+        .compat()
+        .map(|receipt| warp::reply::json(&receipt))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_proof<D: Retrieve + DetermineTypes + StateStore + Retention + InclusionProver>(
+    dependencies: D,
+    id: SwapId,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    handle_get_proof(dependencies, id)
+        .boxed()
+        .compat()
+        .map(|proof| warp::reply::json(&proof))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn verify_transaction<D: DetermineTypes + StateStore + GasOracle>(
+    dependencies: D,
+    id: SwapId,
+    body: serde_json::Value,
+) -> impl Future<Item = impl Reply, Error = Rejection> {
+    handle_verify_transaction(dependencies, id, body)
+        .boxed()
+        .compat()
+        .map(|verified| warp::reply::json(&verified))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value, clippy::type_repetition_in_bounds)]
+pub fn get_recovery<D>(
+    dependencies: D,
+    id: SwapId,
+    query_params: ActionExecutionParameters,
+) -> impl Future<Item = impl Reply, Error = Rejection>
+where
+    D: SwapSeed
+        + DetermineTypes
+        + HtlcFinder
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    async move { recovery::refund_action(&dependencies, id, query_params).await }
+        .boxed()
+        .compat()
+        .map(|body| warp::reply::json(&body))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
 pub fn action<
     D: DetermineTypes
         + Retrieve
@@ -79,6 +263,18 @@ pub fn action<
         + Network
         + SwapSeed
         + Saver
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + ActionHistory
+        + GasOracle
+        + SwapTasks
+        + SwapEvents
+        + RedeemDestination
+        + PayoutAccountUsages
+        + SwapMessages
+        + ConstructedTransactions
+        + IssueIdentities
         + LedgerEventsCreator,
 >(
     method: http::Method,
@@ -86,12 +282,25 @@ pub fn action<
     action_kind: ActionKind,
     query_params: ActionExecutionParameters,
     dependencies: D,
+    bitcoin_memo: Option<String>,
+    payout_accounts: PayoutAccounts,
+    price_feed: PriceFeed,
     body: serde_json::Value,
 ) -> impl Future<Item = impl Reply, Error = Rejection> {
-    handle_action(method, id, action_kind, body, query_params, dependencies)
-        .boxed()
-        .compat()
-        .map(|body| warp::reply::json(&body))
-        .map_err(problem::from_anyhow)
-        .map_err(into_rejection)
+    handle_action(
+        method,
+        id,
+        action_kind,
+        body,
+        query_params,
+        dependencies,
+        bitcoin_memo,
+        payout_accounts,
+        price_feed,
+    )
+    .boxed()
+    .compat()
+    .map(|body| warp::reply::json(&body))
+    .map_err(problem::from_anyhow)
+    .map_err(into_rejection)
 }
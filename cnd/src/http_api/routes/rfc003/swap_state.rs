@@ -3,7 +3,7 @@ use crate::{
     http_api::{Http, SwapStatus},
     swap_protocols::{
         asset::Asset,
-        rfc003::{self, alice, bob, Ledger, SecretHash},
+        rfc003::{self, Ledger, SecretHash},
     },
     timestamp::Timestamp,
 };
@@ -45,6 +45,28 @@ pub struct LedgerState<H, T> {
     pub fund_tx: Option<Http<T>>,
     pub redeem_tx: Option<Http<T>>,
     pub refund_tx: Option<Http<T>>,
+    /// Only present while the fund transaction is unconfirmed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u32>,
+    /// Further transactions that funded the HTLC after `fund_tx`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top_ups: Vec<TopUp<H, T>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(bound = "Http<T>: Serialize, Http<H>: Serialize")]
+pub struct TopUp<H, T> {
+    pub htlc_location: Http<H>,
+    pub fund_tx: Http<T>,
+}
+
+impl<L: Ledger> From<rfc003::events::TopUp<L>> for TopUp<L::HtlcLocation, L::Transaction> {
+    fn from(top_up: rfc003::events::TopUp<L>) -> Self {
+        Self {
+            htlc_location: Http(top_up.location),
+            fund_tx: Http(top_up.transaction),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Copy, Serialize)]
@@ -53,13 +75,14 @@ pub enum SwapCommunicationState {
     Sent,
     Accepted,
     Declined,
+    Failed,
 }
 
-impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> From<alice::SwapCommunication<AL, BL, AA, BA>>
+impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> From<rfc003::SwapCommunication<AL, BL, AA, BA>>
     for SwapCommunication<AL::Identity, BL::Identity>
 {
-    fn from(communication: alice::SwapCommunication<AL, BL, AA, BA>) -> Self {
-        use self::alice::SwapCommunication::*;
+    fn from(communication: rfc003::SwapCommunication<AL, BL, AA, BA>) -> Self {
+        use self::rfc003::SwapCommunication::*;
         match communication {
             Proposed { request } => Self {
                 status: SwapCommunicationState::Sent,
@@ -91,38 +114,8 @@ impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> From<alice::SwapCommunication
                 beta_refund_identity: None,
                 secret_hash: request.secret_hash,
             },
-        }
-    }
-}
-
-impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> From<bob::SwapCommunication<AL, BL, AA, BA>>
-    for SwapCommunication<AL::Identity, BL::Identity>
-{
-    fn from(communication: bob::SwapCommunication<AL, BL, AA, BA>) -> Self {
-        use self::bob::SwapCommunication::*;
-        match communication {
-            Proposed { request, .. } => Self {
-                status: SwapCommunicationState::Sent,
-                alpha_expiry: request.alpha_expiry,
-                beta_expiry: request.beta_expiry,
-                alpha_redeem_identity: None,
-                beta_redeem_identity: Http(request.beta_ledger_redeem_identity),
-                alpha_refund_identity: Http(request.alpha_ledger_refund_identity),
-                beta_refund_identity: None,
-                secret_hash: request.secret_hash,
-            },
-            Accepted { request, response } => Self {
-                status: SwapCommunicationState::Accepted,
-                alpha_expiry: request.alpha_expiry,
-                beta_expiry: request.beta_expiry,
-                alpha_redeem_identity: Some(Http(response.alpha_ledger_redeem_identity)),
-                beta_redeem_identity: Http(request.beta_ledger_redeem_identity),
-                alpha_refund_identity: Http(request.alpha_ledger_refund_identity),
-                beta_refund_identity: Some(Http(response.beta_ledger_refund_identity)),
-                secret_hash: request.secret_hash,
-            },
-            Declined { request, .. } => Self {
-                status: SwapCommunicationState::Declined,
+            Failed { request } => Self {
+                status: SwapCommunicationState::Failed,
                 alpha_expiry: request.alpha_expiry,
                 beta_expiry: request.beta_expiry,
                 alpha_redeem_identity: None,
@@ -151,11 +144,29 @@ impl<L: Ledger> From<rfc003::LedgerState<L>> for LedgerState<L::HtlcLocation, L:
                 fund_tx: None,
                 refund_tx: None,
                 redeem_tx: None,
+                confirmations: None,
+                top_ups: vec![],
+            },
+            FundedUnconfirmed {
+                htlc_location,
+                deploy_transaction,
+                fund_transaction,
+                confirmations,
+            } => Self {
+                status,
+                htlc_location: Some(Http(htlc_location)),
+                deploy_tx: Some(Http(deploy_transaction)),
+                fund_tx: Some(Http(fund_transaction)),
+                redeem_tx: None,
+                refund_tx: None,
+                confirmations: Some(confirmations),
+                top_ups: vec![],
             },
             IncorrectlyFunded {
                 htlc_location,
                 deploy_transaction,
                 fund_transaction,
+                top_ups,
             } => Self {
                 status,
                 htlc_location: Some(Http(htlc_location)),
@@ -163,11 +174,14 @@ impl<L: Ledger> From<rfc003::LedgerState<L>> for LedgerState<L::HtlcLocation, L:
                 fund_tx: Some(Http(fund_transaction)),
                 redeem_tx: None,
                 refund_tx: None,
+                confirmations: None,
+                top_ups: top_ups.into_iter().map(TopUp::from).collect(),
             },
             Funded {
                 htlc_location,
                 deploy_transaction,
                 fund_transaction,
+                top_ups,
             } => Self {
                 status,
                 htlc_location: Some(Http(htlc_location)),
@@ -175,11 +189,14 @@ impl<L: Ledger> From<rfc003::LedgerState<L>> for LedgerState<L::HtlcLocation, L:
                 fund_tx: Some(Http(fund_transaction)),
                 refund_tx: None,
                 redeem_tx: None,
+                confirmations: None,
+                top_ups: top_ups.into_iter().map(TopUp::from).collect(),
             },
             Redeemed {
                 htlc_location,
                 deploy_transaction,
                 fund_transaction,
+                top_ups,
                 redeem_transaction,
             } => Self {
                 status,
@@ -188,11 +205,14 @@ impl<L: Ledger> From<rfc003::LedgerState<L>> for LedgerState<L::HtlcLocation, L:
                 fund_tx: Some(Http(fund_transaction)),
                 redeem_tx: Some(Http(redeem_transaction)),
                 refund_tx: None,
+                confirmations: None,
+                top_ups: top_ups.into_iter().map(TopUp::from).collect(),
             },
             Refunded {
                 htlc_location,
                 deploy_transaction,
                 fund_transaction,
+                top_ups,
                 refund_transaction,
             } => Self {
                 status,
@@ -201,6 +221,8 @@ impl<L: Ledger> From<rfc003::LedgerState<L>> for LedgerState<L::HtlcLocation, L:
                 fund_tx: Some(Http(fund_transaction)),
                 refund_tx: Some(Http(refund_transaction)),
                 redeem_tx: None,
+                confirmations: None,
+                top_ups: top_ups.into_iter().map(TopUp::from).collect(),
             },
         }
     }
@@ -216,6 +238,16 @@ impl SwapStatus {
         use self::SwapCommunicationState::*;
         use crate::swap_protocols::rfc003::HtlcState::*;
 
+        // A failed request is not an internal failure, the error only tells why
+        // the swap never started.
+        if swap_communication_state == Failed {
+            return SwapStatus::NotSwapped;
+        }
+
+        if let Some(rfc003::Error::TimedOut) = error {
+            return SwapStatus::TimedOut;
+        }
+
         if let Some(e) = error {
             log::debug!(target: "http-api", "derived SwapStatus is InternalFailure because: {:?}", e);
             return SwapStatus::InternalFailure;
@@ -237,10 +269,11 @@ impl SwapStatus {
 #[cfg(test)]
 impl quickcheck::Arbitrary for SwapCommunicationState {
     fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
-        match g.next_u32() % 3 {
+        match g.next_u32() % 4 {
             0 => SwapCommunicationState::Declined,
             1 => SwapCommunicationState::Accepted,
             2 => SwapCommunicationState::Sent,
+            3 => SwapCommunicationState::Failed,
             _ => unreachable!(),
         }
     }
@@ -323,6 +356,34 @@ mod tests {
         )
     }
 
+    #[test]
+    fn given_timed_out_should_be_timed_out() {
+        assert_eq!(
+            SwapStatus::new(
+                Accepted,
+                NotDeployed,
+                NotDeployed,
+                &Some(rfc003::Error::TimedOut)
+            ),
+            SwapStatus::TimedOut
+        )
+    }
+
+    #[test]
+    fn given_failed_request_should_not_be_swapped() {
+        assert_eq!(
+            SwapStatus::new(
+                Failed,
+                NotDeployed,
+                NotDeployed,
+                &Some(rfc003::Error::Network(
+                    crate::network::Error::InvalidResponse
+                ))
+            ),
+            SwapStatus::NotSwapped
+        )
+    }
+
     quickcheck::quickcheck! {
         fn test(
             swap_communication_state: SwapCommunicationState,
@@ -0,0 +1,125 @@
+use crate::{
+    db::{Stats, SwapStats},
+    http_api::{problem, routes::into_rejection},
+    swap_protocols::rfc003::messages::SwapDeclineReason,
+};
+use chrono::NaiveDate;
+use futures::Future;
+use futures_core::future::{FutureExt, TryFutureExt};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use warp::{Rejection, Reply};
+
+/// Quantities are given in the smallest unit of the asset and serialized as
+/// strings because they may not fit into a JSON number.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StatsResource {
+    swaps_per_day: Vec<SwapsPerDay>,
+    volume: Volume,
+    average_time_to_redeem_secs: Option<i64>,
+    decline_reasons: Vec<DeclineReason>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SwapsPerDay {
+    day: NaiveDate,
+    swaps: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Volume {
+    bitcoin: String,
+    ether: String,
+    erc20: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DeclineReason {
+    reason: Option<SwapDeclineReason>,
+    declines: u64,
+}
+
+impl From<SwapStats> for StatsResource {
+    fn from(stats: SwapStats) -> Self {
+        StatsResource {
+            swaps_per_day: stats
+                .swaps_per_day
+                .into_iter()
+                .map(|(day, swaps)| SwapsPerDay { day, swaps })
+                .collect(),
+            volume: Volume {
+                bitcoin: stats.volume.bitcoin.to_string(),
+                ether: stats.volume.ether.to_string(),
+                erc20: stats
+                    .volume
+                    .erc20
+                    .into_iter()
+                    .map(|(contract, quantity)| (format!("{:#x}", contract), quantity.to_string()))
+                    .collect(),
+            },
+            average_time_to_redeem_secs: stats
+                .average_time_to_redeem
+                .map(|duration| duration.num_seconds()),
+            decline_reasons: stats
+                .decline_reasons
+                .into_iter()
+                .map(|(reason, declines)| DeclineReason { reason, declines })
+                .collect(),
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_stats<D: Stats>(dependencies: D) -> impl Future<Item = impl Reply, Error = Rejection> {
+    async move { Stats::stats(&dependencies).await }
+        .boxed()
+        .compat()
+        .map(|stats| warp::reply::json(&StatsResource::from(stats)))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::Volume as SwapVolume, ethereum::U256};
+    use chrono::Duration;
+
+    #[test]
+    fn stats_serialize_correctly_to_json() {
+        let mut erc20 = BTreeMap::new();
+        erc20.insert(
+            "b97048628db6b661d4c2aa833e95dbe1a905b280".parse().unwrap(),
+            U256::from(10_000),
+        );
+        let stats = SwapStats {
+            swaps_per_day: vec![(NaiveDate::from_ymd(2020, 1, 20), 3)],
+            volume: SwapVolume {
+                bitcoin: 100_000_000,
+                ether: U256::from(1_000_000_000_000_000_000u64),
+                erc20,
+            },
+            average_time_to_redeem: Some(Duration::seconds(90)),
+            decline_reasons: vec![(Some(SwapDeclineReason::UnsatisfactoryRate), 2), (None, 1)],
+        };
+
+        let json = serde_json::to_value(StatsResource::from(stats)).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "swaps_per_day": [{ "day": "2020-01-20", "swaps": 3 }],
+                "volume": {
+                    "bitcoin": "100000000",
+                    "ether": "1000000000000000000",
+                    "erc20": { "0xb97048628db6b661d4c2aa833e95dbe1a905b280": "10000" }
+                },
+                "average_time_to_redeem_secs": 90,
+                "decline_reasons": [
+                    { "reason": "unsatisfactory-rate", "declines": 2 },
+                    { "reason": null, "declines": 1 }
+                ]
+            })
+        );
+    }
+}
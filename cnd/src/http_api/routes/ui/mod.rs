@@ -0,0 +1,16 @@
+use warp::{Rejection, Reply};
+
+#[cfg(feature = "admin-ui")]
+const INDEX_HTML: &str = include_str!("../../../../ui/index.html");
+
+/// A single page that manages swaps through the REST API, only served if cnd
+/// was built with the `admin-ui` feature.
+#[cfg(feature = "admin-ui")]
+pub fn get_ui() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::html(INDEX_HTML))
+}
+
+#[cfg(not(feature = "admin-ui"))]
+pub fn get_ui() -> Result<impl Reply, Rejection> {
+    Err::<&'static str, _>(warp::reject::not_found())
+}
@@ -0,0 +1,173 @@
+use crate::{
+    db,
+    http_api::{
+        problem,
+        routes::{
+            internal::{htlc_params, UnsupportedHtlc},
+            into_rejection,
+        },
+        HttpAsset, HttpLedger,
+    },
+    swap_protocols::{
+        rfc003::{self, SecretHash},
+        SwapId,
+    },
+    timestamp::Timestamp,
+    watch_only::{watch_htlc, Side, WatchOnly, WatchedSwap},
+};
+use futures_core::future::{BoxFuture, FutureExt, TryFutureExt};
+use serde::Deserialize;
+use warp::{http::StatusCode, Rejection, Reply};
+
+#[derive(Debug, Deserialize)]
+struct WatchBody {
+    alpha: HtlcBody,
+    beta: HtlcBody,
+    secret_hash: SecretHash,
+}
+
+#[derive(Debug, Deserialize)]
+struct HtlcBody {
+    ledger: HttpLedger,
+    asset: HttpAsset,
+    /// The identities are ledger specific, hence they can only be
+    /// deserialized once the ledger is known.
+    redeem_identity: serde_json::Value,
+    refund_identity: serde_json::Value,
+    expiry: Timestamp,
+}
+
+pub fn post_watch(watch_only: WatchOnly, body: serde_json::Value) -> Result<impl Reply, Rejection> {
+    handle_post_watch(watch_only, body)
+        .map(|swap| warp::reply::with_status(warp::reply::json(&swap), StatusCode::CREATED))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_watched_swaps(watch_only: WatchOnly) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&watch_only.watched.all()))
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_watched_swap(id: SwapId, watch_only: WatchOnly) -> Result<impl Reply, Rejection> {
+    watch_only
+        .watched
+        .get(&id)
+        .map(|swap| warp::reply::json(&swap))
+        .ok_or_else(|| anyhow::Error::from(db::Error::SwapNotFound))
+        .map_err(problem::from_anyhow)
+        .map_err(into_rejection)
+}
+
+fn handle_post_watch(
+    watch_only: WatchOnly,
+    body: serde_json::Value,
+) -> anyhow::Result<WatchedSwap> {
+    let WatchBody {
+        alpha,
+        beta,
+        secret_hash,
+    } = serde_json::from_value(body)?;
+    let id = SwapId::default();
+
+    // Both HTLCs are checked before anything is watched, such that a swap is
+    // either watched on both ledgers or not at all.
+    let alpha = watch(&watch_only, id, Side::Alpha, alpha, secret_hash)?;
+    let beta = watch(&watch_only, id, Side::Beta, beta, secret_hash)?;
+
+    let swap = watch_only.watched.insert(id);
+    for (side, watch) in vec![(Side::Alpha, alpha), (Side::Beta, beta)] {
+        tokio::spawn(
+            async move {
+                if let Err(e) = watch.await {
+                    log::warn!("stopped watching {:?} HTLC of swap {}: {:?}", side, id, e);
+                }
+
+                Ok::<(), ()>(())
+            }
+            .boxed()
+            .compat(),
+        );
+    }
+
+    Ok(swap)
+}
+
+fn watch(
+    watch_only: &WatchOnly,
+    id: SwapId,
+    side: Side,
+    htlc: HtlcBody,
+    secret_hash: SecretHash,
+) -> anyhow::Result<BoxFuture<'static, Result<(), rfc003::Error>>> {
+    let HtlcBody {
+        ledger,
+        asset,
+        redeem_identity,
+        refund_identity,
+        expiry,
+    } = htlc;
+    let watched = watch_only.watched.clone();
+
+    match (ledger, asset) {
+        (HttpLedger::Bitcoin(ledger), HttpAsset::Bitcoin(asset)) => {
+            let htlc_params = htlc_params(
+                ledger,
+                asset,
+                redeem_identity,
+                refund_identity,
+                secret_hash,
+                expiry,
+            )?;
+
+            Ok(watch_htlc(
+                watch_only.bitcoin_connector.clone(),
+                htlc_params,
+                id,
+                side,
+                watched,
+            )
+            .boxed())
+        }
+        (HttpLedger::Ethereum(ledger), HttpAsset::Ether(asset)) => {
+            let htlc_params = htlc_params(
+                ledger,
+                asset,
+                redeem_identity,
+                refund_identity,
+                secret_hash,
+                expiry,
+            )?;
+
+            Ok(watch_htlc(
+                watch_only.ethereum_connector.clone(),
+                htlc_params,
+                id,
+                side,
+                watched,
+            )
+            .boxed())
+        }
+        (HttpLedger::Ethereum(ledger), HttpAsset::Erc20(asset)) => {
+            let htlc_params = htlc_params(
+                ledger,
+                asset,
+                redeem_identity,
+                refund_identity,
+                secret_hash,
+                expiry,
+            )?;
+
+            Ok(watch_htlc(
+                watch_only.ethereum_connector.clone(),
+                htlc_params,
+                id,
+                side,
+                watched,
+            )
+            .boxed())
+        }
+        _ => Err(anyhow::Error::from(UnsupportedHtlc)),
+    }
+}
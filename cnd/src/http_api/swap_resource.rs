@@ -1,25 +1,31 @@
 #![allow(clippy::type_repetition_in_bounds)]
 
 use crate::{
-    db::{Swap, SwapTypes},
+    block_lag::LedgerLag,
+    clock_skew::ClockSkew,
+    db::{LedgerKind, Swap, SwapFailure, SwapMetadata, SwapTypes},
     ethereum,
     http_api::{
         action::ToSirenAction,
+        problem,
         route_factory::swap_path,
         routes::rfc003::{LedgerState, SwapCommunication, SwapState},
         Http, HttpAsset, HttpLedger,
     },
+    price_feed::{IndicativeFiatValue, Quotes},
     swap_protocols::{
         actions::Actions,
+        asset::Asset,
         ledger,
-        rfc003::{self, state_store::StateStore},
-        HashFunction, SwapId, SwapProtocol,
+        rfc003::{
+            self, actions::ActionKind, state::Actor, state_store::StateStore, FailureCategory, Rate,
+        },
+        HashFunction, Role, SwapId, SwapProtocol,
     },
 };
-use http_api_problem::HttpApiProblem;
+use chrono::NaiveDateTime;
 use libp2p::PeerId;
-use serde::Serialize;
-use warp::http::StatusCode;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize)]
 pub struct SwapResource<S> {
@@ -29,10 +35,72 @@ pub struct SwapResource<S> {
     pub protocol: Http<SwapProtocol>,
     pub status: SwapStatus,
     pub parameters: SwapParameters,
+    /// Why the swap failed, if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The recorded failure of the swap, unlike `error` this survives restarts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<SwapFailureResource>,
+    /// Given by the client, e.g. the id of its order the swap belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// The units of the beta asset Alice expects per unit of the alpha asset,
+    /// if she attached it to her request. Not checked against the assets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Rate>,
+    /// Identities cnd derived for this swap that were issued for other swaps
+    /// as well, counterparties can link those swaps.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reused_identities: Vec<String>,
+    /// The clocks that are off by more than the configured threshold, the
+    /// swap may then expire earlier or later than cnd expects.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub clock_skews: Vec<ClockSkew>,
+    /// The ledgers of the swap whose latest block did not advance for longer
+    /// than the configured threshold, the swap does not progress on them.
+    /// Only shown while the swap is in progress.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stale_ledgers: Vec<LedgerLag>,
+    /// Why cnd gave up restarting the watcher of the swap, it then no longer
+    /// follows what happens on the ledgers until cnd is restarted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub degraded: Option<String>,
+    /// Only present if a price feed is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indicative_fiat_values: Option<IndicativeFiatValues>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<S>,
 }
 
+/// What the assets of the swap are worth according to the price feed, for
+/// operators to sanity-check the magnitude of a swap.
+#[derive(Debug, Serialize)]
+pub struct IndicativeFiatValues {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alpha_asset: Option<IndicativeFiatValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beta_asset: Option<IndicativeFiatValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapFailureResource {
+    category: FailureCategory,
+    message: String,
+    failed_at: NaiveDateTime,
+}
+
+impl From<SwapFailure> for SwapFailureResource {
+    fn from(failure: SwapFailure) -> Self {
+        SwapFailureResource {
+            category: failure.category,
+            message: failure.message,
+            failed_at: failure.failed_at,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct SwapParameters {
     alpha_ledger: HttpLedger,
@@ -41,12 +109,15 @@ pub struct SwapParameters {
     beta_asset: HttpAsset,
 }
 
-#[derive(Debug, Serialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SwapStatus {
     InProgress,
     Swapped,
     NotSwapped,
+    /// Neither HTLC was deployed before both expired, cnd stopped watching the
+    /// ledgers for them.
+    TimedOut,
     InternalFailure,
 }
 
@@ -99,10 +170,140 @@ pub enum IncludeState {
     No,
 }
 
+/// The parts of a swap that can be requested on their own.
+#[derive(Clone, Copy, Debug, PartialEq, strum_macros::Display, strum_macros::EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum SwapSubResource {
+    Communication,
+    Alpha,
+    Beta,
+}
+
+impl SwapSubResource {
+    /// The sub-resource whose state an action of the given role changes.
+    pub fn of_action(action_kind: ActionKind, types: SwapTypes) -> Self {
+        match (action_kind, types.role) {
+            (ActionKind::Accept, _) | (ActionKind::Decline, _) => SwapSubResource::Communication,
+            // Only Bitcoin transactions can be replaced.
+            (ActionKind::Rebroadcast, _) if types.alpha_ledger == LedgerKind::Bitcoin => {
+                SwapSubResource::Alpha
+            }
+            (ActionKind::Rebroadcast, _) => SwapSubResource::Beta,
+            (ActionKind::Deploy, Role::Alice)
+            | (ActionKind::Fund, Role::Alice)
+            | (ActionKind::Refund, Role::Alice)
+            | (ActionKind::Redeem, Role::Bob)
+            | (ActionKind::BumpFee, Role::Bob) => SwapSubResource::Alpha,
+            (ActionKind::Deploy, Role::Bob)
+            | (ActionKind::Fund, Role::Bob)
+            | (ActionKind::Refund, Role::Bob)
+            | (ActionKind::Redeem, Role::Alice)
+            | (ActionKind::BumpFee, Role::Alice) => SwapSubResource::Beta,
+        }
+    }
+}
+
+pub fn build_rfc003_sub_resource_entity<S: StateStore>(
+    state_store: &S,
+    swap: Swap,
+    types: SwapTypes,
+    sub_resource: SwapSubResource,
+) -> anyhow::Result<siren::Entity> {
+    let id = swap.swap_id;
+
+    with_swap_types!(types, {
+        let state = state_store
+            .get::<ROLE>(&id)?
+            .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", id))?;
+
+        let entity = siren::Entity::default().with_class_member(sub_resource.to_string());
+        let entity = match sub_resource {
+            SwapSubResource::Communication => {
+                entity.with_properties(SwapCommunication::from(state.swap_communication.clone()))
+            }
+            SwapSubResource::Alpha => {
+                entity.with_properties(LedgerState::from(state.alpha_ledger_state.clone()))
+            }
+            SwapSubResource::Beta => {
+                entity.with_properties(LedgerState::from(state.beta_ledger_state.clone()))
+            }
+        }
+        .map_err(|e| {
+            log::error!("failed to set properties of entity: {:?}", e);
+            problem::internal_error()
+        })?
+        .with_link(siren::NavigationalLink::new(
+            &["self"],
+            format!("{}/{}", swap_path(id), sub_resource),
+        ))
+        .with_link(siren::NavigationalLink::new(&["up"], swap_path(id)));
+
+        let entity = state
+            .actions()
+            .into_iter()
+            .filter(|action| {
+                SwapSubResource::of_action(ActionKind::from(action), types) == sub_resource
+            })
+            .fold(entity, |acc, action| {
+                let action = action.to_siren_action(&id);
+                acc.with_action(action)
+            });
+
+        Ok(entity)
+    })
+}
+
+pub fn swap_status<AL: rfc003::Ledger, BL: rfc003::Ledger, AA: Asset, BA: Asset, R: Actor>(
+    state: &rfc003::State<AL, BL, AA, BA, R>,
+    failure: &Option<SwapFailure>,
+) -> SwapStatus {
+    // The error in the state is lost on restart, the recorded failure is not.
+    match failure {
+        Some(failure) if failure.category == FailureCategory::Expiry => {
+            return SwapStatus::TimedOut
+        }
+        Some(_) => return SwapStatus::InternalFailure,
+        None => {}
+    }
+
+    SwapStatus::new(
+        SwapCommunication::from(state.swap_communication.clone()).status,
+        LedgerState::from(state.alpha_ledger_state.clone()).status,
+        LedgerState::from(state.beta_ledger_state.clone()).status,
+        &state.error,
+    )
+}
+
+pub fn rfc003_swap_status<S: StateStore>(
+    state_store: &S,
+    swap: &Swap,
+    types: SwapTypes,
+    failure: &Option<SwapFailure>,
+) -> anyhow::Result<SwapStatus> {
+    let id = swap.swap_id;
+
+    with_swap_types!(types, {
+        let state = state_store
+            .get::<ROLE>(&id)?
+            .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", id))?;
+
+        Ok(swap_status(&state, failure))
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn build_rfc003_siren_entity<S: StateStore>(
     state_store: &S,
     swap: Swap,
     types: SwapTypes,
+    failure: Option<SwapFailure>,
+    metadata: SwapMetadata,
+    rate: Option<Rate>,
+    reused_identities: Vec<String>,
+    clock_skews: Vec<ClockSkew>,
+    stale_ledgers: Vec<LedgerLag>,
+    degraded: Option<String>,
+    quotes: Option<&Quotes>,
     include_state: IncludeState,
 ) -> anyhow::Result<siren::Entity> {
     let id = swap.swap_id;
@@ -112,19 +313,18 @@ pub fn build_rfc003_siren_entity<S: StateStore>(
             .get::<ROLE>(&id)?
             .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", id))?;
 
+        let status = swap_status(&state, &failure);
         let communication = SwapCommunication::from(state.swap_communication.clone());
         let alpha_ledger = LedgerState::from(state.alpha_ledger_state.clone());
         let beta_ledger = LedgerState::from(state.beta_ledger_state.clone());
         let parameters = SwapParameters::from(state.clone().request());
+        let indicative_fiat_values = quotes.map(|quotes| IndicativeFiatValues {
+            alpha_asset: parameters.alpha_asset.indicative_fiat_value(quotes),
+            beta_asset: parameters.beta_asset.indicative_fiat_value(quotes),
+        });
         let actions = state.clone().actions();
 
         let error = state.error;
-        let status = SwapStatus::new(
-            communication.status,
-            alpha_ledger.status,
-            beta_ledger.status,
-            &error,
-        );
 
         let swap = SwapResource {
             id: Http(id),
@@ -133,6 +333,19 @@ pub fn build_rfc003_siren_entity<S: StateStore>(
             parameters,
             role: swap.role.to_string(),
             counterparty: Http(swap.counterparty),
+            error: error.map(|e| e.to_string()),
+            failure: failure.map(SwapFailureResource::from),
+            external_id: metadata.external_id,
+            note: metadata.note,
+            rate,
+            reused_identities,
+            clock_skews,
+            stale_ledgers: match status {
+                SwapStatus::InProgress => stale_ledgers,
+                _ => Vec::new(),
+            },
+            degraded,
+            indicative_fiat_values,
             state: match include_state {
                 IncludeState::Yes => Some(SwapState::<AL, BL> {
                     communication,
@@ -148,7 +361,7 @@ pub fn build_rfc003_siren_entity<S: StateStore>(
             .with_properties(swap)
             .map_err(|e| {
                 log::error!("failed to set properties of entity: {:?}", e);
-                HttpApiProblem::with_title_and_type_from_status(StatusCode::INTERNAL_SERVER_ERROR)
+                problem::internal_error()
             })?
             .with_link(siren::NavigationalLink::new(&["self"], swap_path(id)))
             .with_link(siren::NavigationalLink::new(
@@ -164,3 +377,56 @@ pub fn build_rfc003_siren_entity<S: StateStore>(
         Ok(entity)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn funding_changes_the_ledger_the_role_is_funding() {
+        let alice = swap_types(Role::Alice);
+        let bob = swap_types(Role::Bob);
+
+        assert_that(&SwapSubResource::of_action(ActionKind::Fund, alice))
+            .is_equal_to(SwapSubResource::Alpha);
+        assert_that(&SwapSubResource::of_action(ActionKind::Fund, bob))
+            .is_equal_to(SwapSubResource::Beta);
+        assert_that(&SwapSubResource::of_action(ActionKind::Redeem, alice))
+            .is_equal_to(SwapSubResource::Beta);
+        assert_that(&SwapSubResource::of_action(ActionKind::Accept, bob))
+            .is_equal_to(SwapSubResource::Communication);
+    }
+
+    #[test]
+    fn rebroadcasting_changes_the_bitcoin_ledger() {
+        assert_that(&SwapSubResource::of_action(
+            ActionKind::Rebroadcast,
+            swap_types(Role::Alice),
+        ))
+        .is_equal_to(SwapSubResource::Alpha);
+        assert_that(&SwapSubResource::of_action(
+            ActionKind::Rebroadcast,
+            swap_types(Role::Bob),
+        ))
+        .is_equal_to(SwapSubResource::Alpha);
+    }
+
+    fn swap_types(role: Role) -> SwapTypes {
+        SwapTypes {
+            alpha_ledger: LedgerKind::Bitcoin,
+            beta_ledger: LedgerKind::Ethereum,
+            alpha_asset: crate::db::AssetKind::Bitcoin,
+            beta_asset: crate::db::AssetKind::Ether,
+            role,
+        }
+    }
+
+    #[test]
+    fn sub_resources_are_parsed_from_their_path_segment() {
+        assert_that(&"alpha".parse::<SwapSubResource>()).is_ok_containing(SwapSubResource::Alpha);
+        assert_that(&"communication".parse::<SwapSubResource>())
+            .is_ok_containing(SwapSubResource::Communication);
+        assert_that(&"fund".parse::<SwapSubResource>()).is_err();
+    }
+}
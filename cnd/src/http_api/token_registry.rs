@@ -0,0 +1,184 @@
+use crate::{
+    config, ethereum::Address, http_api::HttpLedger, swap_protocols::ledger::ethereum::ChainId,
+};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// ERC20 tokens that assets can refer to by their symbol instead of by their
+/// contract address, e.g. `{"name":"dai","quantity":"..."}`.
+///
+/// The contract depends on the chain of the ledger the asset is on, the
+/// built-in tokens are therefore only known on mainnet unless their contracts
+/// on other chains are configured.
+#[derive(Clone, Debug)]
+pub struct TokenRegistry {
+    tokens: HashMap<(String, ChainId), Token>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Token {
+    token_contract: Address,
+    decimals: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum UnresolvableToken {
+    #[error("{symbol} is an ERC20 token and can only be swapped on the ethereum ledger")]
+    NotOnEthereum { symbol: String },
+    #[error("the contract of {symbol} on chain {chain_id} is unknown")]
+    UnknownContract { symbol: String, chain_id: u32 },
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl TokenRegistry {
+    /// Configured tokens take precedence over the built-in ones.
+    pub fn new(configured: &[config::Token]) -> Self {
+        let built_in = vec![
+            (
+                "dai",
+                ChainId::mainnet(),
+                "6b175474e89094c44da98b954eedeac495271d0f",
+                18,
+            ),
+            (
+                "usdc",
+                ChainId::mainnet(),
+                "a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                6,
+            ),
+        ];
+
+        let mut tokens = HashMap::new();
+        for (symbol, chain_id, token_contract, decimals) in built_in {
+            tokens.insert((symbol.to_owned(), chain_id), Token {
+                token_contract: token_contract
+                    .parse()
+                    .expect("static string to be a valid address"),
+                decimals,
+            });
+        }
+        for token in configured {
+            tokens.insert((token.symbol.to_lowercase(), token.chain_id), Token {
+                token_contract: token.token_contract,
+                decimals: token.decimals,
+            });
+        }
+
+        Self { tokens }
+    }
+
+    fn knows(&self, symbol: &str) -> bool {
+        self.tokens.keys().any(|(known, _)| known == symbol)
+    }
+
+    /// Replace assets of a swap request body that refer to a token by its
+    /// symbol with the `erc20` asset of the token's contract on the chain of
+    /// the corresponding ledger.
+    pub fn resolve_aliases(&self, body: &mut JsonValue) -> Result<(), UnresolvableToken> {
+        for &(ledger_key, asset_key) in &[
+            ("alpha_ledger", "alpha_asset"),
+            ("beta_ledger", "beta_asset"),
+        ] {
+            let symbol = match body
+                .get(asset_key)
+                .and_then(|asset| asset.get("name"))
+                .and_then(JsonValue::as_str)
+                .map(str::to_lowercase)
+            {
+                Some(symbol) if self.knows(&symbol) => symbol,
+                _ => continue,
+            };
+
+            let chain_id = match body
+                .get(ledger_key)
+                .cloned()
+                .map(serde_json::from_value::<HttpLedger>)
+            {
+                Some(Ok(HttpLedger::Ethereum(ethereum))) => ethereum.chain_id,
+                _ => return Err(UnresolvableToken::NotOnEthereum { symbol }),
+            };
+
+            let token = match self.tokens.get(&(symbol.clone(), chain_id)) {
+                Some(token) => *token,
+                None => {
+                    return Err(UnresolvableToken::UnknownContract {
+                        symbol,
+                        chain_id: chain_id.into(),
+                    })
+                }
+            };
+
+            let asset = &mut body[asset_key];
+            asset["name"] = JsonValue::from("erc20");
+            asset["token_contract"] = JsonValue::from(format!("{:#x}", token.token_contract));
+            asset["decimals"] = JsonValue::from(token.decimals);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ethereum::{Erc20Quantity, Erc20Token, U256},
+        http_api::HttpAsset,
+    };
+    use spectral::prelude::*;
+
+    #[test]
+    fn dai_resolves_to_its_mainnet_contract() {
+        let registry = TokenRegistry::default();
+        let mut body = serde_json::json!({
+            "alpha_ledger": { "name": "bitcoin", "network": "mainnet" },
+            "alpha_asset": { "name": "bitcoin", "quantity": "100000000" },
+            "beta_ledger": { "name": "ethereum", "network": "mainnet" },
+            "beta_asset": { "name": "dai", "human_quantity": "1.5" },
+        });
+
+        registry.resolve_aliases(&mut body).unwrap();
+
+        assert_that(&serde_json::from_value::<HttpAsset>(body["beta_asset"].clone()).unwrap())
+            .is_equal_to(HttpAsset::from(Erc20Token::new(
+                "6b175474e89094c44da98b954eedeac495271d0f".parse().unwrap(),
+                Erc20Quantity(U256::from(1_500_000_000_000_000_000u64)),
+            )));
+        assert_that(&body["alpha_asset"]["name"]).is_equal_to(&JsonValue::from("bitcoin"));
+    }
+
+    #[test]
+    fn configured_token_resolves_on_its_chain_only() {
+        let registry = TokenRegistry::new(&[config::Token {
+            symbol: String::from("DAI"),
+            chain_id: ChainId::regtest(),
+            token_contract: "b97048628db6b661d4c2aa833e95dbe1a905b280".parse().unwrap(),
+            decimals: 18,
+        }]);
+        let body = |network: &str| {
+            serde_json::json!({
+                "alpha_ledger": { "name": "ethereum", "network": network },
+                "alpha_asset": { "name": "dai", "quantity": "1000" },
+            })
+        };
+
+        let mut regtest = body("regtest");
+        let mut ropsten = body("ropsten");
+
+        assert_that(&registry.resolve_aliases(&mut regtest)).is_ok();
+        assert_that(&regtest["alpha_asset"]["token_contract"]).is_equal_to(&JsonValue::from(
+            "0xb97048628db6b661d4c2aa833e95dbe1a905b280",
+        ));
+        assert_that(&registry.resolve_aliases(&mut ropsten)).is_err_containing(
+            UnresolvableToken::UnknownContract {
+                symbol: String::from("dai"),
+                chain_id: 3,
+            },
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{
+    ethereum::{Address, SignedTransaction, U256},
+    swap_protocols::actions::{
+        bitcoin::{SendToAddress, SpendOutput},
+        ethereum::{CallContract, DeployContract},
+    },
+};
+use bitcoin::{consensus::deserialize, Amount, OutPoint, Transaction};
+use std::convert::Infallible;
+
+/// A transaction that spends an HTLC must not give away more than this share
+/// of the HTLC's value in fees.
+const MAX_BITCOIN_FEE_DIVISOR: u64 = 10;
+/// A transaction may offer up to this multiple of the fee per gas the node
+/// currently recommends.
+const MAX_FEE_PER_GAS_MULTIPLIER: u64 = 2;
+
+/// What fees are currently reasonable on the ledgers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FeeLimits {
+    /// `None` if the Ethereum node cannot tell.
+    pub max_fee_per_gas: Option<U256>,
+}
+
+/// Checks that a client signed transaction does exactly what an action asks
+/// for before it is broadcast.
+pub trait VerifyTransaction {
+    fn verify_transaction(
+        &self,
+        raw_transaction: &[u8],
+        fee_limits: FeeLimits,
+    ) -> Result<(), TransactionMismatch>;
+}
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum TransactionMismatch {
+    #[error("the transaction cannot be decoded: {0}")]
+    Undecodable(String),
+    #[error("not all inputs of the transaction are signed")]
+    Unsigned,
+    #[error("the transaction does not pay {amount} to {address}")]
+    MissingOutput {
+        address: bitcoin::Address,
+        amount: Amount,
+    },
+    #[error("the transaction does not spend the HTLC output {0}")]
+    HtlcNotSpent(OutPoint),
+    #[error("the transaction spends outputs that do not belong to the HTLC")]
+    ForeignInputs,
+    #[error("the transaction spends more than the {0} locked in the HTLC")]
+    HtlcOverspent(Amount),
+    #[error("the transaction pays a fee of {fee}, at most {max} are reasonable")]
+    BitcoinFeeTooHigh { fee: Amount, max: Amount },
+    #[error("the transaction is for chain {actual:?}, expected {expected}")]
+    WrongChain { expected: u64, actual: Option<u64> },
+    #[error("the transaction is sent to {actual:?}, expected {expected:?}")]
+    WrongRecipient {
+        expected: Option<Address>,
+        actual: Option<Address>,
+    },
+    #[error("the transaction transfers {actual} wei, expected {expected}")]
+    WrongValue { expected: U256, actual: U256 },
+    #[error("the data of the transaction does not match the action")]
+    WrongData,
+    #[error("the gas limit of {actual} is below the required {expected}")]
+    InsufficientGas { expected: U256, actual: U256 },
+    #[error("the transaction can cost up to {actual} wei in fees, at most {max} are reasonable")]
+    EthereumFeeTooHigh { max: U256, actual: U256 },
+}
+
+impl VerifyTransaction for SendToAddress {
+    /// The wallet adds its own inputs and change, so the fee is not known
+    /// here.
+    fn verify_transaction(
+        &self,
+        raw_transaction: &[u8],
+        _: FeeLimits,
+    ) -> Result<(), TransactionMismatch> {
+        let transaction = decode_bitcoin_transaction(raw_transaction)?;
+
+        let script_pubkey = self.to.script_pubkey();
+        let pays_htlc = transaction.output.iter().any(|output| {
+            output.script_pubkey == script_pubkey && output.value == self.amount.as_sat()
+        });
+        if !pays_htlc {
+            return Err(TransactionMismatch::MissingOutput {
+                address: self.to.clone(),
+                amount: self.amount,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl VerifyTransaction for SpendOutput {
+    fn verify_transaction(
+        &self,
+        raw_transaction: &[u8],
+        _: FeeLimits,
+    ) -> Result<(), TransactionMismatch> {
+        let transaction = decode_bitcoin_transaction(raw_transaction)?;
+
+        // All outputs of the HTLC have to be spent and nothing else, the
+        // fee is only known from the value of the inputs that way.
+        if let Some(output) = self.outputs.iter().find(|output| {
+            !transaction
+                .input
+                .iter()
+                .any(|input| input.previous_output == output.outpoint)
+        }) {
+            return Err(TransactionMismatch::HtlcNotSpent(output.outpoint));
+        }
+        if transaction.input.len() != self.outputs.len() {
+            return Err(TransactionMismatch::ForeignInputs);
+        }
+
+        let value = self.value();
+        let spent = transaction
+            .output
+            .iter()
+            .fold(0u64, |sum, output| sum.saturating_add(output.value));
+        let fee = value
+            .as_sat()
+            .checked_sub(spent)
+            .map(Amount::from_sat)
+            .ok_or(TransactionMismatch::HtlcOverspent(value))?;
+
+        let max = Amount::from_sat(value.as_sat() / MAX_BITCOIN_FEE_DIVISOR);
+        if fee > max {
+            return Err(TransactionMismatch::BitcoinFeeTooHigh { fee, max });
+        }
+
+        Ok(())
+    }
+}
+
+impl VerifyTransaction for DeployContract {
+    fn verify_transaction(
+        &self,
+        raw_transaction: &[u8],
+        fee_limits: FeeLimits,
+    ) -> Result<(), TransactionMismatch> {
+        let transaction = decode_ethereum_transaction(raw_transaction)?;
+
+        verify_ethereum_transaction(&transaction, EthereumExpectations {
+            to: None,
+            value: self.amount.wei(),
+            data: &self.data.0,
+            gas_limit: self.gas_limit,
+            chain_id: u64::from(u32::from(self.chain_id)),
+            fee_limits,
+        })
+    }
+}
+
+impl VerifyTransaction for CallContract {
+    fn verify_transaction(
+        &self,
+        raw_transaction: &[u8],
+        fee_limits: FeeLimits,
+    ) -> Result<(), TransactionMismatch> {
+        let transaction = decode_ethereum_transaction(raw_transaction)?;
+
+        verify_ethereum_transaction(&transaction, EthereumExpectations {
+            to: Some(self.to),
+            value: U256::zero(),
+            data: self.data.as_ref().map_or(&[][..], |data| data.0.as_slice()),
+            gas_limit: self.gas_limit,
+            chain_id: u64::from(u32::from(self.chain_id)),
+            fee_limits,
+        })
+    }
+}
+
+impl VerifyTransaction for Infallible {
+    fn verify_transaction(&self, _: &[u8], _: FeeLimits) -> Result<(), TransactionMismatch> {
+        unreachable!("how did you manage to construct Infallible?")
+    }
+}
+
+fn decode_bitcoin_transaction(raw_transaction: &[u8]) -> Result<Transaction, TransactionMismatch> {
+    let transaction = deserialize::<Transaction>(raw_transaction)
+        .map_err(|e| TransactionMismatch::Undecodable(e.to_string()))?;
+
+    let signed = !transaction.input.is_empty()
+        && transaction
+            .input
+            .iter()
+            .all(|input| !input.script_sig.is_empty() || !input.witness.is_empty());
+    if !signed {
+        return Err(TransactionMismatch::Unsigned);
+    }
+
+    Ok(transaction)
+}
+
+fn decode_ethereum_transaction(
+    raw_transaction: &[u8],
+) -> Result<SignedTransaction, TransactionMismatch> {
+    SignedTransaction::decode(raw_transaction)
+        .map_err(|e| TransactionMismatch::Undecodable(e.to_string()))
+}
+
+#[derive(Debug)]
+struct EthereumExpectations<'a> {
+    to: Option<Address>,
+    value: U256,
+    data: &'a [u8],
+    gas_limit: U256,
+    chain_id: u64,
+    fee_limits: FeeLimits,
+}
+
+fn verify_ethereum_transaction(
+    transaction: &SignedTransaction,
+    expected: EthereumExpectations<'_>,
+) -> Result<(), TransactionMismatch> {
+    if transaction.chain_id != Some(expected.chain_id) {
+        return Err(TransactionMismatch::WrongChain {
+            expected: expected.chain_id,
+            actual: transaction.chain_id,
+        });
+    }
+    if transaction.to != expected.to {
+        return Err(TransactionMismatch::WrongRecipient {
+            expected: expected.to,
+            actual: transaction.to,
+        });
+    }
+    if transaction.value != expected.value {
+        return Err(TransactionMismatch::WrongValue {
+            expected: expected.value,
+            actual: transaction.value,
+        });
+    }
+    if transaction.data.as_slice() != expected.data {
+        return Err(TransactionMismatch::WrongData);
+    }
+    if transaction.gas_limit < expected.gas_limit {
+        return Err(TransactionMismatch::InsufficientGas {
+            expected: expected.gas_limit,
+            actual: transaction.gas_limit,
+        });
+    }
+
+    // A larger gas limit than required is fine as long as the transaction
+    // cannot cost more than the required gas at a reasonable price.
+    if let Some(max_fee_per_gas) = expected.fee_limits.max_fee_per_gas {
+        let max = expected
+            .gas_limit
+            .saturating_mul(max_fee_per_gas)
+            .saturating_mul(U256::from(MAX_FEE_PER_GAS_MULTIPLIER));
+        if transaction.max_fee() > max {
+            return Err(TransactionMismatch::EthereumFeeTooHigh {
+                max,
+                actual: transaction.max_fee(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{consensus::serialize, Script, TxIn, TxOut};
+    use spectral::prelude::*;
+    use std::str::FromStr;
+
+    fn send_to_address() -> SendToAddress {
+        SendToAddress {
+            to: bitcoin::Address::from_str(
+                "bcrt1qs2aderg3whgu0m8uadn6dwxjf7j3wx97kk2qqtrum89pmfcxknhsf89pj0",
+            )
+            .unwrap(),
+            amount: Amount::from_sat(100_000),
+            network: bitcoin::Network::Regtest,
+        }
+    }
+
+    fn signed_transaction(outputs: Vec<TxOut>) -> Vec<u8> {
+        serialize(&Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::default(),
+                script_sig: Script::new(),
+                sequence: 0xFFFF_FFFF,
+                witness: vec![vec![0x01]],
+            }],
+            output: outputs,
+        })
+    }
+
+    #[test]
+    fn fund_transaction_has_to_pay_the_htlc_the_exact_amount() {
+        let action = send_to_address();
+        let htlc_output = |value| TxOut {
+            value,
+            script_pubkey: action.to.script_pubkey(),
+        };
+        let change = TxOut {
+            value: 42_000,
+            script_pubkey: Script::new(),
+        };
+
+        let funds_htlc = signed_transaction(vec![change.clone(), htlc_output(100_000)]);
+        let funds_too_little = signed_transaction(vec![change, htlc_output(99_999)]);
+
+        assert_that(&action.verify_transaction(&funds_htlc, FeeLimits::default())).is_ok();
+        assert_that(&action.verify_transaction(&funds_too_little, FeeLimits::default()))
+            .is_err_containing(TransactionMismatch::MissingOutput {
+                address: action.to.clone(),
+                amount: action.amount,
+            });
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{db::IssuedIdentities, swap_protocols::SwapId};
+use async_trait::async_trait;
+use serde::Serialize;
+
+/// An identity cnd derived for a swap was issued for another swap before.
+#[derive(Debug, thiserror::Error)]
+#[error("identity {identity} was already issued for swap {other_swap_id}")]
+pub struct IdentityReused {
+    pub identity: String,
+    pub other_swap_id: SwapId,
+}
+
+/// Keep track of the identities cnd derives for swaps, they are meant to be
+/// used once. Derived identities repeat if the seed is restored on another
+/// node or two swaps map to the same index of the derivation path.
+#[async_trait]
+pub trait IssueIdentities: Send + Sync + 'static {
+    /// Warns about every identity that was issued for another swap before,
+    /// refuses to issue them at all if `identity_reuse.refuse` is configured.
+    async fn issue_identities(&self, swap_id: &SwapId, identities: &[String])
+        -> anyhow::Result<()>;
+}
+
+/// `identity` as it appears in the swap resource if cnd derived it rather than
+/// the user supplying it.
+pub fn derived_identity<I: PartialEq + Serialize>(
+    identity: &I,
+    derived: Option<I>,
+) -> Option<String> {
+    if derived.as_ref() != Some(identity) {
+        return None;
+    }
+
+    match serde_json::to_value(identity) {
+        Ok(serde_json::Value::String(identity)) => Some(identity),
+        Ok(identity) => Some(identity.to_string()),
+        Err(_) => None,
+    }
+}
+
+/// Look for every identity in the swaps it was issued for before, the ones
+/// that are found are logged and, if `refuse` is set, the first is returned as
+/// an error. Otherwise the identities are recorded for `swap_id`.
+pub async fn issue<D: IssuedIdentities>(
+    db: &D,
+    swap_id: &SwapId,
+    identities: &[String],
+    refuse: bool,
+) -> anyhow::Result<()> {
+    for identity in identities {
+        let others = db.swaps_issued(identity).await?;
+
+        if let Some(other_swap_id) = others.into_iter().find(|other| other != swap_id) {
+            log::warn!(
+                "identity {} of swap {} was already issued for swap {}, counterparties can link both swaps",
+                identity,
+                swap_id,
+                other_swap_id
+            );
+
+            if refuse {
+                return Err(anyhow::Error::from(IdentityReused {
+                    identity: identity.clone(),
+                    other_swap_id,
+                }));
+            }
+        }
+    }
+
+    db.record_issued_identities(swap_id, identities).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Sqlite;
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    #[test]
+    fn only_derived_identities_are_issued() {
+        let address = "00a329c0648769a73afac7f9381e08fb43dbea72"
+            .parse::<crate::ethereum::Address>()
+            .unwrap();
+
+        assert_that(&derived_identity(&address, Some(address))).is_equal_to(Some(String::from(
+            "0x00a329c0648769a73afac7f9381e08fb43dbea72",
+        )));
+        assert_that(&derived_identity(&address, None)).is_none();
+    }
+
+    #[test]
+    fn reused_identities_are_only_refused_if_configured() {
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let identities = [String::from("0x00a329c0648769a73afac7f9381e08fb43dbea72")];
+        let (first, second, third) = (SwapId::default(), SwapId::default(), SwapId::default());
+
+        let (warned, refused) = crate::executor::block_on(async {
+            issue(&db, &first, &identities, true).await.unwrap();
+            let warned = issue(&db, &second, &identities, false).await;
+            let refused = issue(&db, &third, &identities, true).await;
+
+            (warned, refused)
+        });
+
+        assert_that(&warned).is_ok();
+        assert_that(&refused).is_err();
+        assert_that(&crate::executor::block_on(db.reused_identities(&third)).unwrap()).is_empty();
+    }
+}
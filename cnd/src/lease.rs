@@ -0,0 +1,101 @@
+use crate::db::Lease;
+use futures_core::compat::Future01CompatExt;
+use std::{
+    process,
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+/// How long the lease is given to a node each time it renews it.
+const LEASE_DURATION: Duration = Duration::from_secs(30);
+
+const RENEWAL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A node stops once it could not renew its lease for this long. This is
+/// shorter than `LEASE_DURATION` to leave room for the clock of the node that
+/// takes over to be ahead.
+const MAX_TIME_WITHOUT_LEASE: Duration = Duration::from_secs(20);
+
+/// Identifies this node as holder of the lease.
+///
+/// Nodes are told apart by the host they run on, such that a restarted node
+/// gets its lease back right away while a standby on another host waits for
+/// it to expire. A standby on the same host is not fenced off.
+pub fn holder() -> anyhow::Result<String> {
+    hostname::get_hostname()
+        .ok_or_else(|| anyhow::anyhow!("failed to read the hostname this node holds the lease as"))
+}
+
+/// Take the lease of the database for `holder` before any swap is executed.
+///
+/// A lease that is held by another node is waited for, e.g. if a standby is
+/// promoted right after the node it stands in for went down. If the lease is
+/// still not available once it could have expired, the other node keeps
+/// renewing it and hence is still running.
+pub async fn acquire<D: Lease>(db: &D, holder: &str) -> anyhow::Result<()> {
+    let give_up_at = Instant::now() + LEASE_DURATION + RENEWAL_INTERVAL;
+
+    loop {
+        if renew(db, holder).await? {
+            log::info!("Acquired the lease of the database");
+            return Ok(());
+        }
+
+        let expires_at = db.lease_expires_at().await?;
+        if Instant::now() > give_up_at {
+            anyhow::bail!(
+                "the database is leased to another node until {}, is it still running?",
+                expires_at
+            );
+        }
+
+        log::info!(
+            "Waiting for the lease of another node to expire at {}",
+            expires_at
+        );
+        wait(RENEWAL_INTERVAL).await;
+    }
+}
+
+/// Renew the lease of `holder` for as long as the node runs.
+///
+/// The process exits once the lease is lost, the swaps may be executed by
+/// another node with the same PeerId from then on.
+pub async fn renew_periodically<D: Lease>(db: D, holder: String) {
+    let mut renewed_at = Instant::now();
+
+    loop {
+        wait(RENEWAL_INTERVAL).await;
+
+        match renew(&db, &holder).await {
+            Ok(true) => renewed_at = Instant::now(),
+            Ok(false) => {
+                log::error!("Another node took over the lease of the database, stopping");
+                process::exit(1);
+            }
+            Err(e) => log::warn!("failed to renew the lease of the database: {:?}", e),
+        }
+
+        if renewed_at.elapsed() > MAX_TIME_WITHOUT_LEASE {
+            log::error!(
+                "Could not renew the lease of the database for {:?}, stopping",
+                MAX_TIME_WITHOUT_LEASE
+            );
+            process::exit(1);
+        }
+    }
+}
+
+async fn renew<D: Lease>(db: &D, holder: &str) -> anyhow::Result<bool> {
+    let now = chrono::Utc::now().naive_utc();
+    let expires_at = now + chrono::Duration::from_std(LEASE_DURATION)?;
+
+    db.acquire_lease(holder, now, expires_at).await
+}
+
+async fn wait(duration: Duration) {
+    Delay::new(Instant::now() + duration)
+        .compat()
+        .await
+        .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+}
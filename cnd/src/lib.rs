@@ -17,23 +17,48 @@ pub mod libp2p_comit_ext;
 #[macro_use]
 pub mod db;
 
+pub mod abandon_swaps;
+pub mod auto_refund;
+pub mod backup;
 pub mod bitcoin;
+pub mod bitcoin_mempool;
+pub mod bitcoind_rpc;
+pub mod block_lag;
 pub mod btsieve;
+pub mod chaos;
+pub mod clock_skew;
 pub mod comit_api;
 pub mod config;
+pub mod derivation;
 pub mod ethereum;
-pub mod first_or_else;
+pub mod executor;
+pub mod expiries;
 pub mod http_api;
+pub mod identity_reuse;
+pub mod lease;
 pub mod load_swaps;
 pub mod logging;
+pub mod maintenance;
 pub mod network;
+pub mod notification;
+pub mod outbox;
+pub mod price_feed;
+pub mod prune_swaps;
 #[cfg(test)]
 pub mod quickcheck;
+pub mod recovery;
+pub mod redeem_destinations;
 pub mod seed;
+pub mod sharded_map;
 #[cfg(test)]
 pub mod spectral_ext;
+pub mod standby;
+pub mod supervisor;
 pub mod swap_protocols;
+pub mod swap_tracing;
 pub mod timestamp;
+pub mod watch_only;
+pub mod webhook;
 
 use crate::swap_protocols::{
     asset::Asset,
@@ -11,24 +11,27 @@ pub trait ToHeader {
     fn to_header(&self) -> Result<Header, serde_json::Error>;
 }
 
+/// A header of a message we send that could not be serialized.
+#[derive(Debug, thiserror::Error)]
+#[error("failed to serialize the {name} header")]
+pub struct HeaderSerializationError {
+    pub name: &'static str,
+    source: serde_json::Error,
+}
+
+impl HeaderSerializationError {
+    pub fn new(name: &'static str) -> impl FnOnce(serde_json::Error) -> Self {
+        move |source| Self { name, source }
+    }
+}
+
 #[macro_export(local_inner_macros)]
 macro_rules! header {
     ($e:expr) => {
         header_internal!($e, {
             log::info!("Header was not present, early returning with decline response!");
-            let decline_body = DeclineResponseBody {
-                reason: Some(SwapDeclineReason::MissingMandatoryHeader),
-            };
 
-            return Err(Response::empty().with_header(
-                "decision",
-                Decision::Declined
-                    .to_header()
-                    .expect("Decision should not fail to serialize"),
-            )
-            .with_body(serde_json::to_value(decline_body).expect(
-                "decline body should always serialize into serde_json::Value",
-            )));
+            return Err(decline(Some(SwapDeclineReason::MissingMandatoryHeader)));
         })
     };
 }
@@ -40,19 +43,8 @@ macro_rules! body {
             Ok(body) => body,
             Err(e) => {
                 log::error!("Failed to deserialize body because of unexpected field: {:?}", e);
-                let decline_body = DeclineResponseBody {
-                    reason: Some(SwapDeclineReason::BadJsonField),
-                };
 
-                return Err(Response::empty().with_header(
-                    "decision",
-                    Decision::Declined
-                        .to_header()
-                        .expect("Decision should not fail to serialize"),
-                )
-                .with_body(serde_json::to_value(decline_body).expect(
-                    "decline body should always serialize into serde_json::Value",
-                )));
+                return Err(decline(Some(SwapDeclineReason::BadJsonField)));
             }
         }
     };
@@ -66,20 +58,7 @@ macro_rules! header_internal {
             Some(Err(e)) => {
                 log::error!("Failed to deserialize header because of unexpected field: {:?}", e);
 
-                let decline_body = DeclineResponseBody {
-                    reason: Some(SwapDeclineReason::BadJsonField),
-                };
-
-                return Err(Response::empty().with_header(
-                    "decision",
-                    Decision::Declined
-                        .to_header()
-                        .expect("Decision should not fail to serialize"),
-                )
-                .with_body(serde_json::to_value(decline_body).expect(
-                    "decline body should always serialize into serde_json::Value",
-                )));
-
+                return Err(decline(Some(SwapDeclineReason::BadJsonField)));
             },
             None => $none,
         }
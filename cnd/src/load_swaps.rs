@@ -1,19 +1,61 @@
 #![allow(clippy::type_repetition_in_bounds)]
 use crate::{
-    db::{DetermineTypes, LoadAcceptedSwap, Retrieve},
+    db::{DetermineTypes, LoadAcceptedSwap, Retention, Retrieve, SwapFailures},
     ethereum::{Erc20Token, EtherQuantity},
     seed::SwapSeed,
     swap_protocols::{
         self,
+        asset::Asset,
         ledger::{Bitcoin, Ethereum},
-        rfc003::state_store::StateStore,
-        LedgerEventsCreator,
+        rfc003::{
+            self,
+            events::{HtlcHistory, ScanHtlc},
+            state_machine::HtlcParams,
+            state_store::StateStore,
+            FailureCategory, Ledger,
+        },
+        FeeAccounting, HtlcScanner, LedgerEventsCreator, SwapEvent, SwapEvents, SwapId, SwapTasks,
     },
+    timestamp::Timestamp,
 };
-use tokio::executor::Executor;
+use futures_core::{
+    compat::Future01CompatExt,
+    future,
+    stream::{self, StreamExt},
+    TryFutureExt,
+};
+use std::{cmp, convert::TryFrom, time::Duration};
+use tokio::{executor::Executor, timer::Timeout};
+
+/// How many swaps are loaded from the database at the same time, which also
+/// bounds how many ledger scans run at once.
+const CONCURRENCY_LIMIT: usize = 16;
+
+/// How long to look for what happened to an HTLC while cnd was not running.
+///
+/// If the scan does not finish in time the swap is resumed anyway and only
+/// watches the blocks mined from then on.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+/// Used if `scan_window.expiry_margin_minutes` is not configured.
+pub const DEFAULT_EXPIRY_MARGIN_MINUTES: u32 = 60;
+
+/// A swap that was loaded from the database but is not yet executing.
+struct PendingSwap<D> {
+    swap_id: SwapId,
+    resume: Box<dyn FnOnce(&D) -> anyhow::Result<()> + Send>,
+}
 
+/// Resume all swaps that are stored in the database.
+///
+/// Swaps are loaded concurrently and resumed in order of their earliest
+/// expiry as soon as they are loaded, hence this is meant to be run in the
+/// background while the node is already serving requests.
+///
+/// Swaps whose HTLCs were not deployed by the time `expiry_margin` seconds
+/// passed after both expiries time out instead of being resumed.
 #[allow(clippy::cognitive_complexity)]
-pub async fn load_swaps_from_database<D>(dependencies: D) -> anyhow::Result<()>
+pub async fn load_swaps_from_database<D>(dependencies: D, expiry_margin: u32) -> anyhow::Result<()>
 where
     D: StateStore
         + Executor
@@ -21,7 +63,13 @@ where
         + SwapSeed
         + LedgerEventsCreator
         + Retrieve
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
         + DetermineTypes
+        + HtlcScanner
         + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
         + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
         + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
@@ -29,24 +77,320 @@ where
 {
     log::debug!("loading swaps from database ...");
 
-    for swap in Retrieve::all(&dependencies).await?.iter() {
-        let swap_id = swap.swap_id;
-        log::debug!("got swap from database: {}", swap_id);
+    let swaps = Retrieve::all(&dependencies).await?;
 
-        let types = DetermineTypes::determine_types(&dependencies, &swap_id).await?;
+    let mut expiries = stream::iter(swaps)
+        .map(|swap| {
+            let dependencies = dependencies.clone();
+            async move {
+                earliest_expiry(&dependencies, &swap.swap_id)
+                    .await
+                    .map(|expiry| (swap.swap_id, expiry))
+                    .map_err(|e| (swap.swap_id, e))
+            }
+        })
+        .buffer_unordered(CONCURRENCY_LIMIT)
+        .filter_map(|result| async move {
+            match result {
+                Ok(expiry) => Some(expiry),
+                Err((swap_id, e)) => {
+                    log::error!("failed to load swap {}: {}, continuing ...", swap_id, e);
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .await;
 
-        with_swap_types!(types, {
-            let accepted =
-                LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(&dependencies, &swap_id)
-                    .await;
+    expiries.sort_by_key(|(_, expiry)| *expiry);
 
-            match accepted {
-                Ok((request, accept, _at)) => {
-                    swap_protocols::init_accepted_swap(&dependencies, request, accept, types.role)?;
+    // `buffered` yields the swaps in the order they were sorted in, hence every
+    // swap is resumed as soon as it and all swaps expiring before it are
+    // loaded.
+    stream::iter(expiries)
+        .map(|(swap_id, _)| {
+            let dependencies = dependencies.clone();
+            async move {
+                load_swap(&dependencies, swap_id, expiry_margin)
+                    .await
+                    .map_err(|e| (swap_id, e))
+            }
+        })
+        .buffered(CONCURRENCY_LIMIT)
+        .for_each(|result| {
+            match result {
+                Ok(PendingSwap { swap_id, resume }) => match resume(&dependencies) {
+                    Ok(()) => log::debug!("resumed swap: {}", swap_id),
+                    Err(e) => {
+                        log::error!("failed to resume swap {}: {}, continuing ...", swap_id, e)
+                    }
+                },
+                Err((swap_id, e)) => {
+                    log::error!("failed to load swap {}: {}, continuing ...", swap_id, e)
                 }
-                Err(e) => log::error!("failed to load swap: {}, continuing ...", e),
-            };
-        });
-    }
+            }
+
+            future::ready(())
+        })
+        .await;
+
+    log::debug!("finished resuming swaps from database");
+
     Ok(())
 }
+
+/// Cancel the tasks of a swap and resume it from the database, the way it
+/// would be after cnd was restarted.
+pub async fn restart_swap<D>(
+    dependencies: &D,
+    swap_id: SwapId,
+    expiry_margin: u32,
+) -> anyhow::Result<()>
+where
+    D: StateStore
+        + Executor
+        + Clone
+        + SwapSeed
+        + LedgerEventsCreator
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + HtlcScanner
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    SwapTasks::cancel_swap_tasks(dependencies, &swap_id);
+
+    let pending_swap = load_swap(dependencies, swap_id, expiry_margin).await?;
+
+    (pending_swap.resume)(dependencies)
+}
+
+#[allow(clippy::cognitive_complexity)]
+async fn load_swap<D>(
+    dependencies: &D,
+    swap_id: SwapId,
+    expiry_margin: u32,
+) -> anyhow::Result<PendingSwap<D>>
+where
+    D: StateStore
+        + Executor
+        + Clone
+        + SwapSeed
+        + LedgerEventsCreator
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + HtlcScanner
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    log::debug!("got swap from database: {}", swap_id);
+
+    let types = DetermineTypes::determine_types(dependencies, &swap_id).await?;
+
+    with_swap_types!(types, {
+        let (request, accept, accepted_at) =
+            LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(dependencies, &swap_id).await?;
+        let role = types.role;
+
+        // There is no point in scanning the ledgers again for a swap that cnd
+        // gave up on already.
+        if timed_out_before(dependencies, &swap_id).await? {
+            return Ok(PendingSwap {
+                swap_id,
+                resume: Box::new(move |dependencies: &D| {
+                    swap_protocols::time_out_accepted_swap(dependencies, request, accept, role);
+                    Ok(())
+                }),
+            });
+        }
+
+        // Nothing can have happened to the HTLCs before the swap was accepted.
+        let since = Timestamp::from(u32::try_from(accepted_at.timestamp())?);
+        let alpha_history = scan_htlc(
+            dependencies,
+            swap_id,
+            HtlcParams::new_alpha_params(&request, &accept),
+            since,
+        )
+        .await;
+        let beta_history = scan_htlc(
+            dependencies,
+            swap_id,
+            HtlcParams::new_beta_params(&request, &accept),
+            since,
+        )
+        .await;
+
+        let gives_up_at = cmp::max(request.alpha_expiry, request.beta_expiry).plus(expiry_margin);
+        if is_timed_out(&alpha_history, &beta_history, gives_up_at, Timestamp::now()) {
+            log::info!(
+                "timing out swap {} because neither HTLC was deployed before both expired",
+                swap_id
+            );
+
+            SwapFailures::record_swap_failure(dependencies, &swap_id, &rfc003::Error::TimedOut)
+                .await?;
+            Retention::mark_finished(dependencies, &swap_id).await?;
+
+            return Ok(PendingSwap {
+                swap_id,
+                resume: Box::new(move |dependencies: &D| {
+                    swap_protocols::time_out_accepted_swap(dependencies, request, accept, role);
+                    SwapEvents::publish(dependencies, SwapEvent::Failed {
+                        swap_id,
+                        reason: rfc003::Error::TimedOut.to_string(),
+                    });
+                    Ok(())
+                }),
+            });
+        }
+
+        Ok(PendingSwap {
+            swap_id,
+            resume: Box::new(move |dependencies: &D| {
+                swap_protocols::resume_accepted_swap(
+                    dependencies,
+                    request,
+                    accept,
+                    role,
+                    alpha_history.unwrap_or_default(),
+                    beta_history.unwrap_or_default(),
+                )
+            }),
+        })
+    })
+}
+
+async fn earliest_expiry<D>(dependencies: &D, swap_id: &SwapId) -> anyhow::Result<Timestamp>
+where
+    D: DetermineTypes
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let types = DetermineTypes::determine_types(dependencies, swap_id).await?;
+
+    with_swap_types!(types, {
+        let (request, ..) =
+            LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(dependencies, swap_id).await?;
+
+        Ok(cmp::min(request.alpha_expiry, request.beta_expiry))
+    })
+}
+
+async fn timed_out_before<D: SwapFailures>(
+    dependencies: &D,
+    swap_id: &SwapId,
+) -> anyhow::Result<bool> {
+    let failure = SwapFailures::swap_failure(dependencies, swap_id).await?;
+
+    Ok(failure.map_or(false, |failure| failure.category == FailureCategory::Expiry))
+}
+
+/// A swap times out once `gives_up_at` passed without either HTLC being
+/// deployed.
+///
+/// If a scan failed, it is not known whether an HTLC was deployed, hence the
+/// swap does not time out.
+fn is_timed_out<AL, BL, AA, BA>(
+    alpha_history: &Option<HtlcHistory<AL, AA>>,
+    beta_history: &Option<HtlcHistory<BL, BA>>,
+    gives_up_at: Timestamp,
+    now: Timestamp,
+) -> bool
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+{
+    match (alpha_history, beta_history) {
+        (Some(alpha_history), Some(beta_history)) => {
+            now > gives_up_at && alpha_history.deployed.is_none() && beta_history.deployed.is_none()
+        }
+        _ => false,
+    }
+}
+
+/// Find out how far the HTLC got while cnd was not running, such that the
+/// swap does not wait for events that happened already.
+///
+/// `None` if the ledger could not be scanned.
+async fn scan_htlc<D, L, A>(
+    dependencies: &D,
+    swap_id: SwapId,
+    htlc_params: HtlcParams<L, A>,
+    since: Timestamp,
+) -> Option<HtlcHistory<L, A>>
+where
+    D: ScanHtlc<L, A>,
+    L: Ledger,
+    A: Asset,
+{
+    let scan = dependencies.scan_htlc(htlc_params, since).compat();
+
+    match Timeout::new(scan, SCAN_TIMEOUT).compat().await {
+        Ok(history) => {
+            if history.deployed.is_some() {
+                log::debug!(
+                    "fast-forwarding swap {} to what happened while cnd was not running",
+                    swap_id
+                );
+            }
+            Some(history)
+        }
+        Err(e) => {
+            log::warn!(
+                "failed to scan the ledger for the HTLC of swap {}, only watching new blocks: {:?}",
+                swap_id,
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swaps_without_deployed_htlcs_time_out_only_if_both_scans_succeeded() {
+        let nothing_happened = || Some(HtlcHistory::<Bitcoin, bitcoin::Amount>::default());
+        let scan_failed = None::<HtlcHistory<Ethereum, EtherQuantity>>;
+        let gives_up_at = Timestamp::from(1_000);
+        let now = Timestamp::from(1_001);
+
+        assert!(is_timed_out(
+            &nothing_happened(),
+            &nothing_happened(),
+            gives_up_at,
+            now
+        ));
+        assert!(!is_timed_out(
+            &nothing_happened(),
+            &nothing_happened(),
+            gives_up_at,
+            gives_up_at
+        ));
+        assert!(!is_timed_out(
+            &nothing_happened(),
+            &scan_failed,
+            gives_up_at,
+            now
+        ));
+    }
+}
@@ -1,45 +1,67 @@
+use crate::{
+    config::LogFile,
+    logging::{LogLevels, RotatingFile, DEFAULT_MAX_FILES, DEFAULT_MAX_FILE_SIZE},
+};
 use fern::{Dispatch, FormatCallback};
-use log::{LevelFilter, Record};
-use std::{fmt::Arguments, io::stdout};
+use log::Record;
+use std::{
+    fmt::Arguments,
+    io::{stdout, Write},
+    time::Duration,
+};
 
 pub fn initialize(
-    base_log_level: LevelFilter,
+    levels: LogLevels,
     structured: bool,
-) -> Result<(), log::SetLoggerError> {
+    file: Option<&LogFile>,
+) -> anyhow::Result<()> {
     #![allow(clippy::print_stdout)] // We cannot use `log` before we have the config file
-    println!("Initializing logging with base level {}", base_log_level);
+    println!(
+        "Initializing logging with base level {}",
+        levels.get().level
+    );
+
+    let mut outputs = Dispatch::new().chain(stdout());
+    if let Some(file) = file {
+        println!("Logging to {}", file.path.display());
+
+        let file: Box<dyn Write + Send> = Box::new(RotatingFile::open(
+            &file.path,
+            file.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+            file.max_files.unwrap_or(DEFAULT_MAX_FILES),
+            file.max_age_hours
+                .map(|hours| Duration::from_secs(hours * 60 * 60)),
+        )?);
+        outputs = outputs.chain(file);
+    }
 
-    let (max_level, log) = create_logger(base_log_level, structured, stdout());
+    let log = create_logger(levels.clone(), structured, outputs);
 
     log::set_boxed_logger(log)?;
-    log::set_max_level(max_level);
+    log::set_max_level(levels.max_level());
 
     Ok(())
 }
 
+/// Filtering is left to `levels` so that they can be changed at runtime.
 fn create_logger<T: Into<fern::Output>>(
-    base_log_level: LevelFilter,
+    levels: LogLevels,
     structured: bool,
     target: T,
-) -> (LevelFilter, Box<dyn log::Log>) {
+) -> Box<dyn log::Log> {
     let formatter = if structured {
         json_formatter
     } else {
         line_formatter
     };
 
-    Dispatch::new()
+    let (_, log) = Dispatch::new()
         .format(formatter)
-        .level(base_log_level)
-        .level_for("tokio_core::reactor", LevelFilter::Info)
-        .level_for("tokio_reactor", LevelFilter::Info)
-        .level_for("hyper", LevelFilter::Info)
-        .level_for("warp", LevelFilter::Info)
-        .level_for("libp2p", LevelFilter::Debug) // the libp2p library
-        .level_for("sub-libp2p", LevelFilter::Debug) // the libp2p subsystem in our application
-        .level_for("http-api", LevelFilter::Debug) // the http-api of our application
+        .filter(move |metadata| levels.enabled(metadata))
         .chain(target)
-        .into_log()
+        .into_log();
+
+    log
 }
 
 fn line_formatter(out: FormatCallback<'_>, message: &Arguments<'_>, record: &Record<'_>) {
@@ -88,14 +110,19 @@ fn json_formatter(out: FormatCallback<'_>, message: &Arguments<'_>, record: &Rec
 mod tests {
 
     use super::*;
+    use crate::config::ModuleLevels;
     use log::{Level, LevelFilter, Record};
     use spectral::prelude::*;
     use std::sync::mpsc::channel;
 
+    fn trace() -> LogLevels {
+        LogLevels::new(LevelFilter::Trace, ModuleLevels::default())
+    }
+
     #[test]
     fn line_formatter_should_return_a_single_line() {
         let (sender, receiver) = channel();
-        let (_, log) = create_logger(LevelFilter::Trace, false, sender);
+        let log = create_logger(trace(), false, sender);
 
         log.log(
             &Record::builder()
@@ -140,7 +167,7 @@ mod tests {
     #[test]
     fn json_formatter_should_return_a_json_object() {
         let (sender, receiver) = channel();
-        let (_, log) = create_logger(LevelFilter::Trace, true, sender);
+        let log = create_logger(trace(), true, sender);
 
         log.log(
             &Record::builder()
@@ -181,7 +208,7 @@ mod tests {
     #[test]
     fn json_formatter_can_handle_missing_values_on_record() {
         let (sender, receiver) = channel();
-        let (_, log) = create_logger(LevelFilter::Trace, true, sender);
+        let log = create_logger(trace(), true, sender);
 
         log.log(&Record::builder().level(Level::Debug).build());
 
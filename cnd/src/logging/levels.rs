@@ -0,0 +1,125 @@
+use crate::config::ModuleLevels;
+use log::{LevelFilter, Metadata};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// Dependencies that are too chatty at the base level and subsystems that
+/// are always interesting, unless overridden per module.
+const DEFAULT_LEVELS: &[(&str, LevelFilter)] = &[
+    ("tokio_core::reactor", LevelFilter::Info),
+    ("tokio_reactor", LevelFilter::Info),
+    ("hyper", LevelFilter::Info),
+    ("warp", LevelFilter::Info),
+    ("libp2p", LevelFilter::Debug),     // the libp2p library
+    ("sub-libp2p", LevelFilter::Debug), // the libp2p subsystem in our application
+    ("http-api", LevelFilter::Debug),   // the http-api of our application
+];
+
+const NETWORK_TARGETS: &[&str] = &["cnd::network", "sub-libp2p"];
+const BTSIEVE_TARGETS: &[&str] = &["cnd::btsieve"];
+const HTTP_API_TARGETS: &[&str] = &["cnd::http_api", "http-api", "http"];
+
+/// The levels records are filtered with, as configured under `[logging]` or
+/// last set with `PUT /internal/log-level`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Levels {
+    pub level: LevelFilter,
+    #[serde(default)]
+    pub modules: ModuleLevels,
+}
+
+impl Levels {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let modules = vec![
+            (NETWORK_TARGETS, self.modules.network),
+            (BTSIEVE_TARGETS, self.modules.btsieve),
+            (HTTP_API_TARGETS, self.modules.http_api),
+        ];
+
+        for (targets, level) in modules {
+            if let Some(level) = level {
+                if targets.iter().any(|prefix| is_within(target, prefix)) {
+                    return level;
+                }
+            }
+        }
+
+        DEFAULT_LEVELS
+            .iter()
+            .find(|(prefix, _)| is_within(target, prefix))
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        let modules = vec![
+            self.modules.network,
+            self.modules.btsieve,
+            self.modules.http_api,
+        ];
+
+        modules
+            .into_iter()
+            .flatten()
+            .chain(DEFAULT_LEVELS.iter().map(|(_, level)| *level))
+            .fold(self.level, Ord::max)
+    }
+}
+
+/// Whether `target` is `prefix` or one of its sub-modules.
+fn is_within(target: &str, prefix: &str) -> bool {
+    target == prefix || (target.starts_with(prefix) && target[prefix.len()..].starts_with("::"))
+}
+
+/// The levels of the logger, shared such that they can be changed while cnd
+/// is running.
+#[derive(Clone, Debug)]
+pub struct LogLevels(Arc<RwLock<Levels>>);
+
+impl LogLevels {
+    pub fn new(level: LevelFilter, modules: ModuleLevels) -> Self {
+        LogLevels(Arc::new(RwLock::new(Levels { level, modules })))
+    }
+
+    pub fn get(&self) -> Levels {
+        *self.0.read().expect("poisoned")
+    }
+
+    /// Takes effect for all records logged from then on.
+    pub fn set(&self, levels: Levels) {
+        *self.0.write().expect("poisoned") = levels;
+        log::set_max_level(levels.max_level());
+    }
+
+    pub fn max_level(&self) -> LevelFilter {
+        self.get().max_level()
+    }
+
+    pub fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.get().level_for(metadata.target())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn module_levels_take_precedence_over_defaults_and_base_level() {
+        let levels = Levels {
+            level: LevelFilter::Info,
+            modules: ModuleLevels {
+                network: Some(LevelFilter::Warn),
+                btsieve: Some(LevelFilter::Trace),
+                http_api: None,
+            },
+        };
+
+        assert_that(&levels.level_for("cnd::btsieve::bitcoin")).is_equal_to(LevelFilter::Trace);
+        assert_that(&levels.level_for("sub-libp2p")).is_equal_to(LevelFilter::Warn);
+        assert_that(&levels.level_for("http-api")).is_equal_to(LevelFilter::Debug);
+        assert_that(&levels.level_for("hyper::client")).is_equal_to(LevelFilter::Info);
+        assert_that(&levels.level_for("cnd::btsieve_ext")).is_equal_to(LevelFilter::Info);
+        assert_that(&levels.max_level()).is_equal_to(LevelFilter::Trace);
+    }
+}
@@ -1,3 +1,9 @@
 mod initialize;
+mod levels;
+mod rotating_file;
 
-pub use self::initialize::initialize;
+pub use self::{
+    initialize::initialize,
+    levels::{Levels, LogLevels},
+    rotating_file::{rotated_path, RotatingFile, DEFAULT_MAX_FILES, DEFAULT_MAX_FILE_SIZE},
+};
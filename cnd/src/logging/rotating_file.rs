@@ -0,0 +1,157 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+/// A file that is appended to and rotated once it grows beyond its maximum
+/// size or, if given, once it was written to for longer than its maximum age.
+///
+/// Files are only rotated at the start of a line, such that a line is never
+/// split across two files.
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    max_age: Option<Duration>,
+    file: File,
+    size: u64,
+    opened_at: Instant,
+    at_line_start: bool,
+}
+
+impl RotatingFile {
+    pub fn open(
+        path: &Path,
+        max_file_size: u64,
+        max_files: usize,
+        max_age: Option<Duration>,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_file_size,
+            max_files,
+            max_age,
+            file,
+            size,
+            opened_at: Instant::now(),
+            at_line_start: true,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn is_due(&self, len: usize) -> bool {
+        if !self.at_line_start || self.size == 0 {
+            return false;
+        }
+
+        let is_full = self.size + len as u64 > self.max_file_size;
+        let is_old = self
+            .max_age
+            .map_or(false, |max_age| self.opened_at.elapsed() >= max_age);
+
+        is_full || is_old
+    }
+
+    /// Shift `<path>.<n>` to `<path>.<n + 1>` and `<path>` to `<path>.1`, the
+    /// oldest file is overwritten once `max_files` are kept.
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.max_files).rev() {
+                let rotated = rotated_path(&self.path, n);
+                if rotated.exists() {
+                    fs::rename(rotated, rotated_path(&self.path, n + 1))?;
+                }
+            }
+
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+
+        *self = Self::open(&self.path, self.max_file_size, self.max_files, self.max_age)?;
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.is_due(buf.len()) {
+            self.rotate()?;
+        }
+
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        self.at_line_start = buf.ends_with(b"\n");
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+pub fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", n));
+
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn lines_are_not_split_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cnd.log");
+        let mut file = RotatingFile::open(&path, 1, 1, None).unwrap();
+
+        file.write_all(b"first ").unwrap();
+        file.write_all(b"line\n").unwrap();
+        file.write_all(b"second line\n").unwrap();
+
+        assert_that(&fs::read_to_string(rotated_path(&path, 1)).unwrap())
+            .is_equal_to("first line\n".to_string());
+        assert_that(&fs::read_to_string(&path).unwrap()).is_equal_to("second line\n".to_string());
+    }
+
+    #[test]
+    fn old_files_are_rotated_even_if_not_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cnd.log");
+        let mut file = RotatingFile::open(
+            &path,
+            DEFAULT_MAX_FILE_SIZE,
+            1,
+            Some(Duration::from_secs(0)),
+        )
+        .unwrap();
+
+        file.write_all(b"first line\n").unwrap();
+        file.write_all(b"second line\n").unwrap();
+
+        assert_that(&fs::read_to_string(rotated_path(&path, 1)).unwrap())
+            .is_equal_to("first line\n".to_string());
+        assert_that(&fs::read_to_string(&path).unwrap()).is_equal_to("second line\n".to_string());
+    }
+}
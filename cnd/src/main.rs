@@ -1,38 +1,84 @@
 #![warn(unused_extern_crates, missing_debug_implementations, rust_2018_idioms)]
 #![forbid(unsafe_code)]
-use crate::cli::Options;
+use crate::cli::{Command, Options};
 use anyhow::Context;
+#[cfg(feature = "bitcoind")]
+use cnd::btsieve::bitcoin::BitcoindConnector;
 use cnd::{
-    btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
+    abandon_swaps, auto_refund,
+    backup::{Archive, Backup},
+    bitcoin_mempool,
+    bitcoind_rpc::BitcoindRpc,
+    block_lag::{self, BlockLagMonitor},
+    btsieve::{
+        bitcoin::{BitcoinConnector, EsploraConnector, FailoverConnector, SpvConnector},
+        ethereum::{EventLoopHandle, QuorumTransport, Web3Connector, Web3Transport},
+        ConcurrencyLimit, DEFAULT_MAX_CONCURRENT_REQUESTS,
+    },
+    chaos::{self, Chaos},
+    clock_skew::{self, ClockMonitor},
     config::{self, Settings},
-    db::{DetermineTypes, Retrieve, Saver, Sqlite},
-    http_api::route_factory,
-    load_swaps,
-    network::{self, transport, Network, SendRequest},
-    seed::{Seed, SwapSeed},
+    db::{
+        ActionHistory, AutoRefunds, ConstructedTransactions, Database, DetermineTypes, Enqueuer,
+        ImportedSwapSecrets, Importer, IssuedIdentities, LoadAcceptedSwap, MetadataStore, Outbox,
+        PayoutAccountUsages, RequestedRates, Retention, RetentionPolicy, Retrieve, Saver, Sqlite,
+        Stats, SwapFailures, SwapMessages,
+    },
+    derivation::Derivation,
+    ethereum::{Erc20Token, EtherQuantity, GasOracle},
+    expiries::ExpiryCalculator,
+    http_api::{
+        action::{ActionExecutionParameters, BitcoinTransactionFormat},
+        route_factory,
+        routes::internal::Faucet,
+        ApiKeys, Listener, PayoutAccounts, TokenRegistry,
+    },
+    identity_reuse::IssueIdentities,
+    lease, load_swaps,
+    logging::{self, LogLevels},
+    maintenance::Maintenance,
+    network::{
+        self, compliance::ComplianceNode, transport, ConnectionLimits, Network, SwarmWorker,
+        WireLog,
+    },
+    notification::{self, Notifications},
+    outbox,
+    price_feed::PriceFeed,
+    prune_swaps, recovery,
+    redeem_destinations::{self, ReceiveAddresses, RedeemDestination},
+    seed::{ImportSwapSecrets, Seed, SwapSeed},
+    sharded_map::ShardedMap,
+    standby::{self, Standby},
+    supervisor,
     swap_protocols::{
-        rfc003::state_store::{InMemoryStateStore, StateStore},
-        Facade, LedgerEventsCreator,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{
+            ethereum::htlc_templates::HtlcTemplates,
+            state_store::{InMemoryStateStore, StateStore},
+        },
+        EventBus, Facade, FeeAccounting, HtlcFinder, HtlcScanner, InclusionProver,
+        LedgerEventsCreator, SwapEvents, SwapId, SwapTasks, TaskRegistry,
     },
+    swap_tracing::{OtlpExporter, SwapTracing, Tracer},
+    watch_only::{WatchOnly, WatchedSwaps},
+    webhook::Webhook,
 };
-use futures::{stream, Future, Stream};
+use futures::{Future, Stream};
 use futures_core::{FutureExt, TryFutureExt};
 use libp2p::{
     identity::{self, ed25519},
     PeerId, Swarm,
 };
 use rand::rngs::OsRng;
-use std::{
-    net::SocketAddr,
-    process,
-    sync::{Arc, Mutex},
-};
+use std::{path::Path, process, sync::Arc, time::Duration};
 use structopt::StructOpt;
 use tokio::executor::Executor;
 
 mod cli;
-mod logging;
 
+const PROMOTION_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+#[allow(clippy::cognitive_complexity)]
 fn main() -> anyhow::Result<()> {
     let options = cli::Options::from_args();
 
@@ -43,37 +89,221 @@ fn main() -> anyhow::Result<()> {
         process::exit(0);
     }
 
-    let base_log_level = settings.logging.level;
-    logging::initialize(base_log_level, settings.logging.structured)?;
+    if let Some(Command::Restore { file, force }) = &options.cmd {
+        restore(&settings, file, *force)?;
+        process::exit(0);
+    }
+
+    let log_levels = LogLevels::new(settings.logging.level, settings.logging.modules);
+    logging::initialize(
+        log_levels.clone(),
+        settings.logging.structured,
+        settings.logging.file.as_ref(),
+    )?;
+
+    let rebuilt_state_store = if options.standby {
+        Some(wait_for_promotion(&settings)?)
+    } else {
+        None
+    };
+
+    if options.watch_only {
+        return watch_only(&settings);
+    }
 
     let seed = Seed::from_dir_or_generate(&settings.data.dir, OsRng)?;
 
-    let mut runtime = tokio::runtime::Runtime::new()?;
+    if options.compliance_server {
+        return compliance_server(&settings, &seed);
+    }
 
-    let bitcoin_connector = {
-        let config::Bitcoin { node_url, network } = settings.clone().bitcoin;
-        BitcoindConnector::new(node_url, network)?
+    let derivation = match &settings.derivation {
+        Some(derivation) => Some(Derivation::new(
+            &seed,
+            settings.bitcoin.network,
+            derivation,
+        )?),
+        None => None,
+    };
+    let receive_addresses = match &settings.bitcoin.redeem_destinations {
+        Some(redeem_destinations) => Some(
+            ReceiveAddresses::new(redeem_destinations, settings.bitcoin.network)
+                .context("invalid redeem destinations in the [bitcoin] section")?,
+        ),
+        None => None,
     };
+    let refuse_identity_reuse = settings
+        .identity_reuse
+        .and_then(|identity_reuse| identity_reuse.refuse)
+        .unwrap_or(false);
+    let chaos = Chaos::new(settings.chaos)?;
+    if let Some(htlc_templates) = &settings.htlc_templates {
+        HtlcTemplates::load(htlc_templates)
+            .context("invalid HTLC templates in the [htlc_templates] section")?
+            .install();
+    }
+
+    let mut runtime = tokio::runtime::Runtime::new()?;
 
+    let bitcoin_connector = bitcoin_connector(&settings, chaos)?;
     let (ethereum_connector, _event_loop_handle) =
-        { Web3Connector::new(settings.clone().ethereum.node_url, runtime.executor())? };
+        ethereum_connector(&settings, chaos, runtime.executor())?;
 
-    let state_store = Arc::new(InMemoryStateStore::default());
+    let state_store = Arc::new(rebuilt_state_store.unwrap_or_default());
 
     let database = Sqlite::new_in_dir(&settings.data.dir)?;
+    let swaps = Database::new(settings.database.as_ref(), &database)?;
+    let imported_secrets = Arc::new(ShardedMap::default());
+    for (swap_id, secrets) in runtime.block_on({
+        let database = database.clone();
+        async move { database.imported_swap_secrets(&seed).await }
+            .boxed()
+            .compat()
+    })? {
+        imported_secrets.insert(swap_id, secrets);
+    }
+
+    if let Some(Command::Recover {
+        swap_id,
+        address,
+        fee_per_wu,
+    }) = &options.cmd
+    {
+        let parameters = match (address, fee_per_wu) {
+            (Some(address), Some(fee_per_wu)) => ActionExecutionParameters::BitcoinAddressAndFee {
+                address: address.clone(),
+                fee_per_wu: fee_per_wu.clone(),
+                format: BitcoinTransactionFormat::default(),
+            },
+            _ => ActionExecutionParameters::None {},
+        };
+        let deps = Facade {
+            bitcoin_connector,
+            ethereum_connector,
+            state_store,
+            seed,
+            derivation,
+            imported_secrets,
+            receive_addresses,
+            refuse_identity_reuse,
+            swarm: Arc::new(()),
+            db: database,
+            swaps,
+            task_executor: runtime.executor(),
+            swap_tasks: Arc::new(TaskRegistry::default()),
+            event_bus: EventBus::default(),
+            tracer: None,
+        };
+
+        recover(&mut runtime, deps, *swap_id, parameters)?;
+        process::exit(0);
+    }
+
+    // Fences off other nodes with the same seed, e.g. a promoted standby, from
+    // executing the same swaps.
+    let lease_holder = lease::holder()?;
+    runtime.block_on({
+        let swaps = swaps.clone();
+        let lease_holder = lease_holder.clone();
+        async move { lease::acquire(&swaps, &lease_holder).await }
+            .boxed()
+            .compat()
+    })?;
+    runtime.spawn(
+        lease::renew_periodically(swaps.clone(), lease_holder)
+            .unit_error()
+            .boxed()
+            .compat(),
+    );
 
     let local_key_pair = derive_key_pair(&seed);
     let local_peer_id = PeerId::from(local_key_pair.clone().public());
     log::info!("Starting with peer_id: {}", local_peer_id);
 
-    let transport = transport::build_comit_transport(local_key_pair);
+    let transport = transport::build_comit_transport(
+        local_key_pair.clone(),
+        ConnectionLimits {
+            max_connections: settings.network.max_connections,
+            max_connections_per_peer: settings.network.max_connections_per_peer,
+        },
+        settings.network.socks5_proxy,
+    );
+    let wire_log = match &settings.logging.wire_log {
+        Some(wire_log) => {
+            log::info!("Logging COMIT frames to {}", wire_log.path.display());
+            Some(WireLog::open(wire_log)?)
+        }
+        None => None,
+    };
+    let event_bus = EventBus::default();
+    let tracer = match &settings.tracing {
+        Some(tracing) => {
+            log::info!(
+                "Exporting swap traces to {}",
+                tracing.otlp_endpoint.as_str()
+            );
+            let (tracer, spans) = Tracer::new();
+            runtime.spawn(
+                OtlpExporter::new(tracing)?
+                    .export_batches(spans)
+                    .unit_error()
+                    .boxed()
+                    .compat(),
+            );
+            runtime.spawn(
+                tracer
+                    .clone()
+                    .trace_swaps(event_bus.subscribe())
+                    .unit_error()
+                    .boxed()
+                    .compat(),
+            );
+
+            Some(tracer)
+        }
+        None => None,
+    };
+    let maintenance = Maintenance::default();
+    let clock_monitor = ClockMonitor::new(
+        settings
+            .clock_skew
+            .and_then(|clock_skew| clock_skew.max_skew_seconds)
+            .unwrap_or(clock_skew::DEFAULT_MAX_SKEW_SECONDS),
+    );
+    let block_lag_monitor = BlockLagMonitor::new(
+        settings
+            .block_lag
+            .and_then(|block_lag| block_lag.bitcoin_max_lag_seconds)
+            .unwrap_or(block_lag::DEFAULT_BITCOIN_MAX_LAG_SECONDS),
+        settings
+            .block_lag
+            .and_then(|block_lag| block_lag.ethereum_max_lag_seconds)
+            .unwrap_or(block_lag::DEFAULT_ETHEREUM_MAX_LAG_SECONDS),
+    );
+    let known_headers = match &settings.network.known_headers {
+        Some(configured) => network::KnownHeaders::builtin().with_configured(configured),
+        None => network::KnownHeaders::builtin(),
+    };
     let behaviour = network::ComitNode::new(
         bitcoin_connector.clone(),
         ethereum_connector.clone(),
         Arc::clone(&state_store),
         seed,
+        derivation,
+        swaps.clone(),
         database.clone(),
         runtime.executor(),
+        event_bus.clone(),
+        known_headers,
+        settings.network.max_pending_inbound_substreams,
+        settings.network.max_frame_size,
+        settings.network.max_swaps_per_peer,
+        wire_log,
+        maintenance.clone(),
+        clock_monitor.clone(),
+        chaos,
+        tracer.clone(),
+        local_key_pair.public(),
     )?;
 
     let mut swarm = Swarm::new(transport, behaviour, local_peer_id.clone());
@@ -82,78 +312,482 @@ fn main() -> anyhow::Result<()> {
         Swarm::listen_on(&mut swarm, addr).expect("Could not listen on specified address");
     }
 
-    let swarm = Arc::new(Mutex::new(swarm));
+    let external_addresses = settings.network.external_addresses.clone();
+    for addr in external_addresses.unwrap_or_default() {
+        Swarm::add_external_address(&mut swarm, addr);
+    }
+
+    let (swarm_worker, swarm) = SwarmWorker::new(swarm);
+    runtime.spawn(swarm_worker);
+
+    let (swap_tasks, watcher_terminations) = TaskRegistry::supervised();
 
     let deps = Facade {
         bitcoin_connector,
         ethereum_connector,
         state_store: Arc::clone(&state_store),
         seed,
-        swarm: Arc::clone(&swarm),
+        derivation,
+        imported_secrets,
+        receive_addresses,
+        refuse_identity_reuse,
+        swarm: Arc::new(swarm),
         db: database.clone(),
+        swaps,
         task_executor: runtime.executor(),
+        swap_tasks: Arc::new(swap_tasks),
+        event_bus: event_bus.clone(),
+        tracer: tracer.clone(),
     };
 
-    runtime.block_on(
-        load_swaps::load_swaps_from_database(deps.clone())
+    if let Some(webhook) = settings.webhook.clone() {
+        runtime.spawn(
+            Webhook::new(webhook.url)
+                .forward_swap_events(event_bus.subscribe())
+                .unit_error()
+                .boxed()
+                .compat(),
+        );
+    }
+
+    if receive_addresses.is_some() {
+        runtime.spawn(
+            redeem_destinations::track_used_destinations(database.clone(), event_bus.subscribe())
+                .unit_error()
+                .boxed()
+                .compat(),
+        );
+    }
+
+    runtime.spawn(
+        clock_skew::watch_ledgers_periodically(
+            deps.bitcoin_connector.clone(),
+            deps.ethereum_connector.clone(),
+            clock_monitor.clone(),
+        )
+        .unit_error()
+        .boxed()
+        .compat(),
+    );
+
+    runtime.spawn(
+        block_lag::watch_ledgers_periodically(
+            deps.bitcoin_connector.clone(),
+            deps.ethereum_connector.clone(),
+            block_lag_monitor.clone(),
+        )
+        .unit_error()
+        .boxed()
+        .compat(),
+    );
+
+    runtime.spawn(
+        outbox::dispatch_requests(deps.clone(), event_bus.subscribe())
+            .unit_error()
+            .boxed()
+            .compat(),
+    );
+
+    let expiry_margin = settings
+        .scan_window
+        .and_then(|scan_window| scan_window.expiry_margin_minutes)
+        .unwrap_or(load_swaps::DEFAULT_EXPIRY_MARGIN_MINUTES)
+        .saturating_mul(60);
+
+    runtime.spawn(
+        supervisor::supervise_watchers(deps.clone(), expiry_margin, watcher_terminations)
+            .unit_error()
+            .boxed()
+            .compat(),
+    );
+
+    // Swaps are resumed in the background so that the node is ready to serve
+    // requests right away.
+    runtime.spawn(
+        load_swaps::load_swaps_from_database(deps.clone(), expiry_margin)
+            .map(|result| {
+                if let Err(e) = result {
+                    log::error!("failed to load swaps from database: {:?}", e);
+                }
+            })
+            .unit_error()
             .boxed()
             .compat(),
+    );
+
+    if let Some(interval) = chaos.task_restart_interval() {
+        runtime.spawn(
+            chaos::restart_swaps_periodically(deps.clone(), interval, expiry_margin)
+                .unit_error()
+                .boxed()
+                .compat(),
+        );
+    }
+
+    if let Some(retention) = settings.retention {
+        let policy = RetentionPolicy {
+            max_age: retention
+                .max_age_days
+                .map(|days| chrono::Duration::days(i64::from(days))),
+            max_finished_swaps: retention.max_finished_swaps.map(|max| max as usize),
+        };
+
+        runtime.spawn(
+            prune_swaps::prune_swaps_periodically(deps.clone(), policy)
+                .unit_error()
+                .boxed()
+                .compat(),
+        );
+    }
+
+    if let Some(funding_window) = settings.funding_window {
+        let funding_window = chrono::Duration::minutes(i64::from(funding_window.minutes));
+
+        runtime.spawn(
+            abandon_swaps::abandon_unfunded_swaps_periodically(deps.clone(), funding_window)
+                .unit_error()
+                .boxed()
+                .compat(),
+        );
+    }
+
+    let bitcoind = match settings.bitcoin.rpc_credentials.clone() {
+        Some(credentials) => {
+            let bitcoind = BitcoindRpc::new(settings.bitcoin.node_url.clone(), credentials);
+            match settings.network.socks5_proxy {
+                Some(proxy) => Some(bitcoind.with_socks5_proxy(proxy)?),
+                None => Some(bitcoind),
+            }
+        }
+        None => None,
+    };
+
+    let notifications = Notifications::new(
+        settings
+            .webhook
+            .clone()
+            .map(|webhook| Webhook::new(webhook.url)),
+        settings.notifications.as_ref(),
+    );
+
+    if settings.webhook.is_some() || settings.notifications.is_some() {
+        let expiry_warning_minutes = settings
+            .notifications
+            .as_ref()
+            .and_then(|notifications| notifications.expiry_warning_minutes)
+            .unwrap_or(notification::DEFAULT_EXPIRY_WARNING_MINUTES);
+
+        runtime.spawn(
+            notification::watch_swaps_periodically(
+                deps.clone(),
+                notifications.clone(),
+                expiry_warning_minutes * 60,
+            )
+            .unit_error()
+            .boxed()
+            .compat(),
+        );
+    }
+
+    if let (Some(bitcoind), Some(mempool)) = (bitcoind.clone(), settings.bitcoin.mempool) {
+        let required_confirmations = mempool
+            .confirmations
+            .unwrap_or(config::DEFAULT_BITCOIN_CONFIRMATIONS);
+
+        runtime.spawn(
+            bitcoin_mempool::watch_mempool_periodically(
+                deps.clone(),
+                bitcoind,
+                required_confirmations,
+                event_bus.subscribe(),
+            )
+            .unit_error()
+            .boxed()
+            .compat(),
+        );
+    }
+
+    if let Some(bitcoind) = bitcoind.clone() {
+        runtime.spawn(
+            auto_refund::refund_expired_swaps_periodically(deps.clone(), bitcoind, notifications)
+                .unit_error()
+                .boxed()
+                .compat(),
+        );
+    }
+
+    let faucet = Faucet::new(
+        bitcoind,
+        settings.bitcoin.network,
+        deps.ethereum_connector.clone(),
+    );
+
+    let expiry_calculator = ExpiryCalculator::new(
+        deps.bitcoin_connector.clone(),
+        deps.ethereum_connector.clone(),
+        settings.expiries,
+    );
+
+    let price_feed = match &settings.price_feed {
+        Some(config) => {
+            let price_feed = PriceFeed::new(config)?;
+            match settings.network.socks5_proxy {
+                Some(proxy) => price_feed.with_socks5_proxy(proxy)?,
+                None => price_feed,
+            }
+        }
+        None => PriceFeed::default(),
+    };
+
+    spawn_warp_instance(
+        &settings,
+        local_key_pair,
+        &mut runtime,
+        deps,
+        faucet,
+        expiry_calculator,
+        maintenance,
+        clock_monitor,
+        block_lag_monitor,
+        price_feed,
+        expiry_margin,
+        log_levels,
     )?;
 
-    spawn_warp_instance(&settings, local_peer_id, &mut runtime, deps);
+    // Block the current thread.
+    ::std::thread::park();
+    Ok(())
+}
 
-    let swarm_worker = stream::poll_fn(move || swarm.lock().unwrap().poll())
-        .for_each(|_| Ok(()))
-        .map_err(|e| {
-            log::error!("failed with {:?}", e);
-        });
+fn compliance_server(settings: &Settings, seed: &Seed) -> anyhow::Result<()> {
+    let mut runtime = tokio::runtime::Runtime::new()?;
 
-    runtime.spawn(swarm_worker);
+    let local_key_pair = derive_key_pair(seed);
+    let local_peer_id = PeerId::from(local_key_pair.public());
+
+    let transport = transport::build_comit_transport(
+        local_key_pair,
+        ConnectionLimits::default(),
+        settings.network.socks5_proxy,
+    );
+    let behaviour = ComplianceNode::new(runtime.executor());
+    let mut swarm = Swarm::new(transport, behaviour, local_peer_id.clone());
+
+    for addr in settings.network.listen.clone() {
+        Swarm::listen_on(&mut swarm, addr).expect("Could not listen on specified address");
+    }
+
+    log::info!(
+        "Starting compliance server with peer_id: {}, waiting for peers to connect",
+        local_peer_id
+    );
+
+    runtime
+        .block_on(swarm.for_each(|_| Ok(())))
+        .context("compliance server failed")
+}
+
+fn watch_only(settings: &Settings) -> anyhow::Result<()> {
+    let chaos = Chaos::new(settings.chaos)?;
+    let mut runtime = tokio::runtime::Runtime::new()?;
+
+    let bitcoin_connector = bitcoin_connector(settings, chaos)?;
+    let (ethereum_connector, _event_loop_handle) =
+        ethereum_connector(settings, chaos, runtime.executor())?;
+
+    let routes = route_factory::create_watch_only(
+        WatchOnly {
+            watched: WatchedSwaps::default(),
+            bitcoin_connector,
+            ethereum_connector,
+        },
+        &settings.http_api.cors.allowed_origins,
+        ApiKeys::new(&settings.http_api.api_keys),
+    );
+    for listener in Listener::from_settings(&settings.http_api) {
+        let server = listener.serve(routes.clone())?;
+
+        runtime.spawn(server);
+    }
+
+    log::info!("Starting watch-only, import swaps via POST /watch");
 
     // Block the current thread.
     ::std::thread::park();
     Ok(())
 }
 
+/// Run as standby until promoted, the caller then starts the node as usual
+/// with the returned state store.
+fn wait_for_promotion(settings: &Settings) -> anyhow::Result<InMemoryStateStore> {
+    let seed = Seed::from_dir(&settings.data.dir)
+        .context("a standby needs the seed of the node it stands in for")?;
+    let peer_id = PeerId::from(derive_key_pair(&seed).public());
+    let secrets = standby::Secrets {
+        seed,
+        derivation: match &settings.derivation {
+            Some(derivation) => Some(Derivation::new(
+                &seed,
+                settings.bitcoin.network,
+                derivation,
+            )?),
+            None => None,
+        },
+    };
+
+    let mut runtime = tokio::runtime::Runtime::new()?;
+
+    let database = Database::new_read_only(settings.database.as_ref(), &settings.data.dir)?;
+    let local = Sqlite::new_read_only_in_dir(&settings.data.dir)
+        .context("a standby needs the data directory of the node it stands in for")?;
+    let (standby, promoted) = Standby::new();
+
+    runtime.spawn(
+        standby::replay_periodically(database.clone(), local.clone(), secrets, standby.clone())
+            .unit_error()
+            .boxed()
+            .compat(),
+    );
+
+    let routes = route_factory::create_standby(
+        standby,
+        &settings.http_api.cors.allowed_origins,
+        ApiKeys::new(&settings.http_api.api_keys),
+    );
+    for listener in Listener::from_settings(&settings.http_api) {
+        let server = listener.serve(routes.clone())?;
+
+        runtime.spawn(server);
+    }
+
+    log::info!(
+        "Standing by for peer_id: {}, waiting to be promoted",
+        peer_id
+    );
+
+    runtime
+        .block_on(promoted)
+        .context("standby stopped without being promoted")?;
+
+    // The swaps saved since the last replay are part of the state store too.
+    let (replica, state_store) = runtime
+        .block_on(
+            async move { standby::replay(&database, &local, secrets).await }
+                .boxed()
+                .compat(),
+        )
+        .context("failed to rebuild the state store from the database")?;
+    log::info!(
+        "Rebuilt the state of {} swaps, {} of them are active",
+        replica.swaps,
+        replica.active_swaps.len()
+    );
+
+    // Leave time to answer the promotion request before the listeners are
+    // released for the HTTP API of the node.
+    std::thread::sleep(PROMOTION_GRACE_PERIOD);
+    runtime
+        .shutdown_now()
+        .wait()
+        .map_err(|_| anyhow::anyhow!("failed to shut down standby"))?;
+
+    Ok(state_store)
+}
+
 fn derive_key_pair(seed: &Seed) -> identity::Keypair {
     let bytes = seed.sha256_with_seed(&[b"NODE_ID"]);
     let key = ed25519::SecretKey::from_bytes(bytes).expect("we always pass 32 bytes");
     identity::Keypair::Ed25519(key.into())
 }
 
+#[allow(clippy::type_repetition_in_bounds, clippy::too_many_arguments)]
 fn spawn_warp_instance<
     D: Clone
         + StateStore
         + Executor
         + Network
-        + SendRequest
         + SwapSeed
         + DetermineTypes
         + Retrieve
         + LedgerEventsCreator
-        + Saver,
+        + Saver
+        + Enqueuer
+        + Outbox
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + Stats
+        + ActionHistory
+        + GasOracle
+        + HtlcFinder
+        + SwapTasks
+        + SwapEvents
+        + RedeemDestination
+        + PayoutAccountUsages
+        + AutoRefunds
+        + MetadataStore
+        + SwapMessages
+        + ConstructedTransactions
+        + RequestedRates
+        + IssuedIdentities
+        + IssueIdentities
+        + HtlcScanner
+        + InclusionProver
+        + Importer
+        + ImportSwapSecrets
+        + SwapTracing
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>
+        + Backup,
 >(
     settings: &Settings,
-    peer_id: PeerId,
+    key_pair: identity::Keypair,
     runtime: &mut tokio::runtime::Runtime,
     dependencies: D,
-) {
+    faucet: Faucet,
+    expiry_calculator: ExpiryCalculator,
+    maintenance: Maintenance,
+    clock_monitor: ClockMonitor,
+    block_lag_monitor: BlockLagMonitor,
+    price_feed: PriceFeed,
+    expiry_margin: u32,
+    log_levels: LogLevels,
+) -> anyhow::Result<()> {
     let routes = route_factory::create(
-        peer_id,
+        key_pair,
         dependencies,
         &settings.http_api.cors.allowed_origins,
+        ApiKeys::new(&settings.http_api.api_keys),
+        settings.backup.clone().map(|backup| backup.passphrase),
+        settings.bitcoin.memo.clone(),
+        TokenRegistry::new(&settings.ethereum.tokens.clone().unwrap_or_default()),
+        PayoutAccounts::new(
+            &settings
+                .ethereum
+                .payout_accounts
+                .clone()
+                .unwrap_or_default(),
+        ),
+        expiry_calculator,
+        settings.bitcoin.rpc_credentials.is_some(),
+        faucet,
+        maintenance,
+        clock_monitor,
+        block_lag_monitor,
+        price_feed,
+        expiry_margin,
+        log_levels,
     );
 
-    let listen_addr = SocketAddr::new(
-        settings.http_api.socket.address,
-        settings.http_api.socket.port,
-    );
-
-    log::info!("Starting HTTP server on {:?}", listen_addr);
+    for listener in Listener::from_settings(&settings.http_api) {
+        let server = listener.serve(routes.clone())?;
 
-    let server = warp::serve(routes).bind(listen_addr);
+        runtime.spawn(server);
+    }
 
-    runtime.spawn(server);
+    Ok(())
 }
 
 #[allow(clippy::print_stdout)] // We cannot use `log` before we have the config file
@@ -189,3 +823,199 @@ fn dump_config(settings: Settings) -> anyhow::Result<()> {
     println!("{}", serialized);
     Ok(())
 }
+
+#[allow(clippy::print_stdout)] // We don't initialize the logger for one-off commands
+fn restore(settings: &Settings, file: &Path, force: bool) -> anyhow::Result<()> {
+    let passphrase = settings
+        .backup
+        .as_ref()
+        .map(|backup| backup.passphrase.as_str())
+        .context("no passphrase configured in the [backup] section of the config file")?;
+
+    let encrypted = std::fs::read(file)
+        .with_context(|| format!("failed to read backup file {}", file.display()))?;
+    let archive = Archive::decrypt(&encrypted, passphrase)?;
+    archive.restore_in_dir(&settings.data.dir, force)?;
+
+    println!(
+        "Restored seed and database from {} into {}",
+        file.display(),
+        settings.data.dir.display()
+    );
+    Ok(())
+}
+
+fn recover(
+    runtime: &mut tokio::runtime::Runtime,
+    dependencies: Facade<()>,
+    swap_id: SwapId,
+    parameters: ActionExecutionParameters,
+) -> anyhow::Result<()> {
+    let refund = runtime.block_on(
+        async move { recovery::refund_action(&dependencies, swap_id, parameters).await }
+            .boxed()
+            .compat(),
+    )?;
+
+    println!("{}", serde_json::to_string_pretty(&refund)?);
+    Ok(())
+}
+
+fn bitcoin_connector(settings: &Settings, chaos: Chaos) -> anyhow::Result<BitcoinConnector> {
+    let config::Bitcoin {
+        network,
+        max_concurrent_requests,
+        esplora,
+        fallback,
+        spv,
+        ..
+    } = settings.clone().bitcoin;
+
+    let connector = match esplora.clone() {
+        Some(config::Esplora { url }) => {
+            log::info!(
+                "Fetching Bitcoin blocks from the Esplora instance at {}",
+                url
+            );
+            BitcoinConnector::from(esplora_connector(
+                settings,
+                url,
+                max_concurrent_requests,
+                chaos,
+            )?)
+        }
+        #[cfg(feature = "bitcoind")]
+        None => {
+            let connector = BitcoindConnector::new(
+                settings.bitcoin.node_url.clone(),
+                settings.bitcoin.network,
+            )?
+            .with_concurrency_limit(concurrency_limit(max_concurrent_requests))
+            .with_verbose_blocks(settings.bitcoin.verbose_blocks.unwrap_or(false))
+            .with_chaos(chaos);
+
+            BitcoinConnector::from(match settings.network.socks5_proxy {
+                Some(proxy) => connector.with_socks5_proxy(proxy)?,
+                None => connector,
+            })
+        }
+        #[cfg(not(feature = "bitcoind"))]
+        None => anyhow::bail!(
+            "cnd was built without the bitcoind feature, configure an Esplora instance in the \
+             [bitcoin.esplora] section"
+        ),
+    };
+
+    let connector = match fallback.clone() {
+        Some(config::Esplora { url }) => {
+            log::info!(
+                "Falling back to the Esplora instance at {} for Bitcoin blocks",
+                url
+            );
+            let fallback = esplora_connector(settings, url, max_concurrent_requests, chaos)?;
+
+            BitcoinConnector::from(FailoverConnector::new(connector, fallback))
+        }
+        None => connector,
+    };
+
+    let connector = match spv {
+        Some(config::Spv { merkle_proofs }) => {
+            log::info!("Verifying the proof of work and merkle roots of Bitcoin blocks");
+            let merkle_proofs = match merkle_proofs.or(esplora).or(fallback) {
+                Some(config::Esplora { url }) => Some(esplora_connector(
+                    settings,
+                    url,
+                    max_concurrent_requests,
+                    chaos,
+                )?),
+                None => None,
+            };
+
+            BitcoinConnector::from(SpvConnector::new(connector, merkle_proofs, network))
+        }
+        None => connector,
+    };
+
+    Ok(connector)
+}
+
+fn esplora_connector(
+    settings: &Settings,
+    url: reqwest::Url,
+    max_concurrent_requests: Option<usize>,
+    chaos: Chaos,
+) -> anyhow::Result<EsploraConnector> {
+    let connector = EsploraConnector::new(url)?
+        .with_concurrency_limit(concurrency_limit(max_concurrent_requests))
+        .with_chaos(chaos);
+
+    Ok(match settings.network.socks5_proxy {
+        Some(proxy) => connector.with_socks5_proxy(proxy)?,
+        None => connector,
+    })
+}
+
+fn ethereum_connector(
+    settings: &Settings,
+    chaos: Chaos,
+    executor: tokio::runtime::TaskExecutor,
+) -> anyhow::Result<(Web3Connector, Vec<EventLoopHandle>)> {
+    let config::Ethereum {
+        node_url,
+        max_concurrent_requests,
+        watch_pending_transactions,
+        fallback_nodes,
+        quorum,
+        verify_receipts,
+        ..
+    } = settings.clone().ethereum;
+
+    let node_urls = std::iter::once(node_url)
+        .chain(
+            fallback_nodes
+                .into_iter()
+                .flatten()
+                .map(|node| node.node_url),
+        )
+        .collect::<Vec<_>>();
+
+    let mut transports = Vec::new();
+    let mut event_loop_handles = Vec::new();
+    for node_url in node_urls {
+        match settings.network.socks5_proxy {
+            Some(proxy) => transports.push(Web3Transport::new_with_socks5_proxy(node_url, proxy)?),
+            None => {
+                let (transport, event_loop_handle) = Web3Transport::new(node_url)?;
+                transports.push(transport);
+                event_loop_handles.push(event_loop_handle);
+            }
+        }
+    }
+
+    let transport = if transports.len() == 1 {
+        transports.remove(0)
+    } else {
+        let quorum = quorum.unwrap_or(transports.len() / 2 + 1);
+        log::info!(
+            "Sending Ethereum requests to {} nodes, {} of which have to agree on blocks and receipts",
+            transports.len(),
+            quorum
+        );
+
+        Web3Transport::Quorum(QuorumTransport::new(transports, quorum))
+    };
+
+    Ok((
+        Web3Connector::with_transport(transport, executor)
+            .with_concurrency_limit(concurrency_limit(max_concurrent_requests))
+            .with_pending_transactions(watch_pending_transactions.unwrap_or(false))
+            .with_receipt_verification(verify_receipts.unwrap_or(false))
+            .with_chaos(chaos),
+        event_loop_handles,
+    ))
+}
+
+fn concurrency_limit(max_concurrent_requests: Option<usize>) -> ConcurrencyLimit {
+    ConcurrencyLimit::new(max_concurrent_requests.unwrap_or(DEFAULT_MAX_CONCURRENT_REQUESTS))
+}
@@ -0,0 +1,33 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+#[derive(Debug, thiserror::Error)]
+#[error("cnd is in maintenance mode and does not take new swaps")]
+pub struct UnderMaintenance;
+
+/// While on, cnd declines new swaps but keeps executing the ones in flight,
+/// such that it can be upgraded once they are finished.
+///
+/// Not persisted, cnd always starts with maintenance mode off.
+#[derive(Clone, Debug, Default)]
+pub struct Maintenance(Arc<AtomicBool>);
+
+impl Maintenance {
+    pub fn is_on(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn set(&self, on: bool) {
+        self.0.store(on, Ordering::SeqCst);
+    }
+
+    pub fn ensure_off(&self) -> Result<(), UnderMaintenance> {
+        if self.is_on() {
+            return Err(UnderMaintenance);
+        }
+
+        Ok(())
+    }
+}
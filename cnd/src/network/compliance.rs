@@ -0,0 +1,418 @@
+use crate::{
+    ethereum::EtherQuantity,
+    libp2p_comit_ext::ToHeader,
+    network::{
+        send_request::{build_swap_request, decode_response},
+        KnownHeaders,
+    },
+    swap_protocols::{
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{
+            self,
+            messages::{Decision, SwapDeclineReason},
+            Secret,
+        },
+        HashFunction, SwapId,
+    },
+    timestamp::Timestamp,
+};
+use futures::{future, Future};
+use libp2p::{
+    ping::{Ping, PingEvent},
+    swarm::NetworkBehaviourEventProcess,
+    NetworkBehaviour, PeerId,
+};
+use libp2p_comit::{
+    frame::{Header, OutboundRequest, Response},
+    BehaviourOutEvent, Comit, PendingInboundRequest, RequestError,
+};
+use serde_json::Value as JsonValue;
+use std::{collections::HashSet, fmt, time::Duration};
+use tokio::{runtime::TaskExecutor, timer::Timeout};
+
+/// How long a peer has to respond to one of our requests.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Exercises the RFC003 messaging layer of the implementations that connect
+/// to it instead of taking part in swaps.
+///
+/// Once a peer answered the first ping it is sent a well-formed SWAP request
+/// as well as deliberately malformed ones, the verdict on how the peer handled
+/// each of them is logged. The SWAP requests of peers are answered with well-
+/// formed and malformed responses in turn, whether the peer rejected the
+/// malformed ones has to be checked on its side.
+#[derive(NetworkBehaviour)]
+#[allow(missing_debug_implementations)]
+pub struct ComplianceNode<TSubstream> {
+    comit: Comit<TSubstream>,
+    ping: Ping<TSubstream>,
+
+    #[behaviour(ignore)]
+    task_executor: TaskExecutor,
+    #[behaviour(ignore)]
+    exercised_peers: HashSet<PeerId>,
+    #[behaviour(ignore)]
+    answered_requests: usize,
+}
+
+impl<TSubstream> ComplianceNode<TSubstream> {
+    pub fn new(task_executor: TaskExecutor) -> Self {
+        Self {
+            comit: Comit::new(KnownHeaders::builtin().into_inner()),
+            ping: Ping::default(),
+            task_executor,
+            exercised_peers: HashSet::new(),
+            answered_requests: 0,
+        }
+    }
+
+    fn exercise(&mut self, peer: PeerId) {
+        let cases = request_cases();
+        log::info!("Sending {} SWAP requests to {}", cases.len(), peer);
+
+        let checks = cases
+            .into_iter()
+            .map(|case| {
+                let swap_id = SwapId::default();
+                let response = self
+                    .comit
+                    .send_request((peer.clone(), None), case.request(swap_id));
+
+                Timeout::new(response, RESPONSE_TIMEOUT).then(move |result| {
+                    let outcome = match result {
+                        Ok(response) => Outcome::Response(response),
+                        Err(e) => match e.into_inner() {
+                            Some(e) => Outcome::Closed(e),
+                            None => Outcome::TimedOut,
+                        },
+                    };
+
+                    Ok::<_, ()>((case.name, check(swap_id, &case.expectation, outcome)))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.task_executor
+            .spawn(future::join_all(checks).map(move |verdicts| report(&peer, &verdicts)));
+    }
+}
+
+impl<TSubstream> NetworkBehaviourEventProcess<BehaviourOutEvent> for ComplianceNode<TSubstream> {
+    fn inject_event(&mut self, event: BehaviourOutEvent) {
+        match event {
+            BehaviourOutEvent::PendingInboundRequest { request, peer_id } => {
+                let PendingInboundRequest { channel, .. } = request;
+
+                let cases = response_cases();
+                let case = &cases[self.answered_requests % cases.len()];
+                self.answered_requests += 1;
+
+                log::info!(
+                    "Answering the request of {} with {}, the peer has to treat it as {}",
+                    peer_id,
+                    case.name,
+                    if case.valid { "valid" } else { "invalid" }
+                );
+
+                channel
+                    .send((case.response)())
+                    .unwrap_or_else(|_| log::debug!("failed to send response through channel"));
+            }
+        }
+    }
+}
+
+impl<TSubstream> NetworkBehaviourEventProcess<PingEvent> for ComplianceNode<TSubstream> {
+    fn inject_event(&mut self, event: PingEvent) {
+        if event.result.is_ok() && self.exercised_peers.insert(event.peer.clone()) {
+            self.exercise(event.peer);
+        }
+    }
+}
+
+/// How a spec compliant peer reacts to a request.
+#[derive(Clone, Debug, PartialEq)]
+enum Expectation {
+    /// The request is either accepted or declined.
+    Decision,
+    Declined(SwapDeclineReason),
+    /// The request is rejected on the messaging layer, i.e. the substream is
+    /// closed without a response.
+    NoResponse,
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Response(Response),
+    Closed(RequestError),
+    TimedOut,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Verdict {
+    Passed,
+    Failed(String),
+    Inconclusive(String),
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Passed => write!(f, "passed"),
+            Verdict::Failed(reason) => write!(f, "FAILED, {}", reason),
+            Verdict::Inconclusive(reason) => write!(f, "inconclusive, {}", reason),
+        }
+    }
+}
+
+fn check(swap_id: SwapId, expectation: &Expectation, outcome: Outcome) -> Verdict {
+    match (expectation.clone(), outcome) {
+        (_, Outcome::Closed(RequestError::PeerUnreachable)) => {
+            Verdict::Inconclusive("the peer could not be reached".to_string())
+        }
+        (Expectation::NoResponse, Outcome::Response(response)) => Verdict::Failed(format!(
+            "expected the request to be rejected but got {:?}",
+            response
+        )),
+        (Expectation::NoResponse, _) => Verdict::Passed,
+        (Expectation::Decision, Outcome::TimedOut) => Verdict::Inconclusive(
+            "no decision was made in time, it may be pending with the user of the peer".to_string(),
+        ),
+        (_, Outcome::TimedOut) => Verdict::Failed("the peer did not respond".to_string()),
+        (_, Outcome::Closed(e)) => Verdict::Failed(format!("the peer did not respond: {}", e)),
+        (Expectation::Decision, Outcome::Response(response)) => {
+            match decode_response::<Bitcoin, Ethereum>(swap_id, response) {
+                Ok(_) => Verdict::Passed,
+                Err(e) => Verdict::Failed(format!("invalid response: {}", e)),
+            }
+        }
+        (Expectation::Declined(expected), Outcome::Response(response)) => {
+            match decode_response::<Bitcoin, Ethereum>(swap_id, response) {
+                Ok(Err(rfc003::Decline {
+                    reason: Some(ref reason),
+                    ..
+                })) if *reason == expected => Verdict::Passed,
+                Ok(Err(decline)) => Verdict::Failed(format!(
+                    "declined with {:?} instead of {}",
+                    decline.reason, expected
+                )),
+                Ok(Ok(_)) => {
+                    Verdict::Failed(format!("accepted instead of declined with {}", expected))
+                }
+                Err(e) => Verdict::Failed(format!("invalid response: {}", e)),
+            }
+        }
+    }
+}
+
+fn report(peer: &PeerId, verdicts: &[(&'static str, Verdict)]) {
+    for (name, verdict) in verdicts {
+        log::info!("{}: {}: {}", peer, name, verdict);
+    }
+
+    let passed = verdicts
+        .iter()
+        .filter(|(_, verdict)| *verdict == Verdict::Passed)
+        .count();
+    log::info!("{} passed {} of {} checks", peer, passed, verdicts.len());
+}
+
+struct RequestCase {
+    name: &'static str,
+    expectation: Expectation,
+    /// Breaks the JSON of the well-formed request.
+    mutation: fn(&mut JsonValue),
+}
+
+impl RequestCase {
+    fn request(&self, swap_id: SwapId) -> OutboundRequest {
+        let mut request = serde_json::to_value(well_formed_request(swap_id))
+            .expect("requests always serialize to JSON");
+        (self.mutation)(&mut request);
+
+        serde_json::from_value(request).expect("mutations keep the shape of a request")
+    }
+}
+
+fn request_cases() -> Vec<RequestCase> {
+    vec![
+        RequestCase {
+            name: "well-formed request",
+            expectation: Expectation::Decision,
+            mutation: |_| {},
+        },
+        RequestCase {
+            name: "missing mandatory header",
+            expectation: Expectation::Declined(SwapDeclineReason::MissingMandatoryHeader),
+            mutation: |request| {
+                if let Some(headers) = request["headers"].as_object_mut() {
+                    headers.remove("alpha_asset");
+                }
+            },
+        },
+        RequestCase {
+            name: "malformed header",
+            expectation: Expectation::Declined(SwapDeclineReason::BadJsonField),
+            mutation: |request| request["headers"]["alpha_ledger"] = serde_json::json!(42),
+        },
+        RequestCase {
+            name: "malformed body",
+            expectation: Expectation::Declined(SwapDeclineReason::BadJsonField),
+            mutation: |request| request["body"]["alpha_expiry"] = serde_json::json!("tomorrow"),
+        },
+        RequestCase {
+            name: "unknown swap protocol",
+            expectation: Expectation::Declined(SwapDeclineReason::UnsupportedProtocol),
+            mutation: |request| request["headers"]["protocol"] = serde_json::json!("comit-rfc-999"),
+        },
+        RequestCase {
+            name: "unsupported swap",
+            expectation: Expectation::Declined(SwapDeclineReason::UnsupportedSwap),
+            mutation: |request| {
+                request["headers"]["beta_ledger"] = request["headers"]["alpha_ledger"].clone();
+                request["headers"]["beta_asset"] = request["headers"]["alpha_asset"].clone();
+            },
+        },
+        RequestCase {
+            name: "unknown mandatory header",
+            expectation: Expectation::NoResponse,
+            mutation: |request| request["headers"]["fee"] = serde_json::json!("1000"),
+        },
+        RequestCase {
+            name: "unknown request type",
+            expectation: Expectation::NoResponse,
+            mutation: |request| request["type"] = serde_json::json!("TRADE"),
+        },
+    ]
+}
+
+pub(super) fn well_formed_request(swap_id: SwapId) -> OutboundRequest {
+    build_swap_request(
+        rfc003::Request {
+            swap_id,
+            alpha_ledger: Bitcoin::default(),
+            beta_ledger: Ethereum::default(),
+            alpha_asset: bitcoin::Amount::from_sat(100_000_000),
+            beta_asset: EtherQuantity::from_eth(10.0),
+            hash_function: HashFunction::Sha256,
+            alpha_ledger_refund_identity: crate::bitcoin::PublicKey::new(
+                "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+                    .parse()
+                    .expect("static string to be a valid public key"),
+            ),
+            beta_ledger_redeem_identity: "8457037fcd80a8650c4692d7fcfc1d0a96b92867"
+                .parse()
+                .expect("static string to be a valid address"),
+            alpha_expiry: Timestamp::from(2_000_000_000),
+            beta_expiry: Timestamp::from(1_900_000_000),
+            secret_hash: Secret::from(*b"hello world, you are beautiful!!").hash(),
+        },
+        None,
+    )
+    .expect("the well-formed request always serializes")
+}
+
+struct ResponseCase {
+    name: &'static str,
+    valid: bool,
+    response: fn() -> Response,
+}
+
+fn response_cases() -> Vec<ResponseCase> {
+    vec![
+        ResponseCase {
+            name: "a decline",
+            valid: true,
+            response: || {
+                Response::empty()
+                    .with_header("decision", decision_header(Decision::Declined))
+                    .with_body(serde_json::json!({ "reason": "unsatisfactory-rate" }))
+            },
+        },
+        ResponseCase {
+            name: "a response without decision",
+            valid: false,
+            response: || Response::empty().with_body(serde_json::json!({})),
+        },
+        ResponseCase {
+            name: "an unknown decision",
+            valid: false,
+            response: || Response::empty().with_header("decision", Header::with_str_value("maybe")),
+        },
+        ResponseCase {
+            name: "an accept without identities",
+            valid: false,
+            response: || {
+                Response::empty()
+                    .with_header("decision", decision_header(Decision::Accepted))
+                    .with_body(serde_json::json!({}))
+            },
+        },
+    ]
+}
+
+fn decision_header(decision: Decision) -> Header {
+    decision
+        .to_header()
+        .expect("Decision should not fail to serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn decline(reason: SwapDeclineReason) -> Response {
+        Response::empty()
+            .with_header("decision", decision_header(Decision::Declined))
+            .with_body(serde_json::json!({ "reason": reason }))
+    }
+
+    #[test]
+    fn all_request_cases_are_comit_requests() {
+        for case in request_cases() {
+            let request = serde_json::to_value(case.request(SwapId::default())).unwrap();
+
+            assert_that(&request["type"].is_string()).is_true();
+        }
+    }
+
+    #[test]
+    fn decline_with_expected_reason_passes() {
+        let expectation = Expectation::Declined(SwapDeclineReason::BadJsonField);
+        let outcome = Outcome::Response(decline(SwapDeclineReason::BadJsonField));
+
+        assert_that(&check(SwapId::default(), &expectation, outcome)).is_equal_to(Verdict::Passed);
+    }
+
+    #[test]
+    fn decline_with_other_reason_fails() {
+        let expectation = Expectation::Declined(SwapDeclineReason::BadJsonField);
+        let outcome = Outcome::Response(decline(SwapDeclineReason::UnsatisfactoryRate));
+
+        assert!(match check(SwapId::default(), &expectation, outcome) {
+            Verdict::Failed(_) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn request_rejected_on_the_messaging_layer_must_not_be_answered() {
+        let expectation = Expectation::NoResponse;
+
+        assert_that(&check(
+            SwapId::default(),
+            &expectation,
+            Outcome::Closed(RequestError::ConnectionClosed),
+        ))
+        .is_equal_to(Verdict::Passed);
+        assert!(match check(
+            SwapId::default(),
+            &expectation,
+            Outcome::Response(decline(SwapDeclineReason::BadJsonField)),
+        ) {
+            Verdict::Failed(_) => true,
+            _ => false,
+        });
+    }
+}
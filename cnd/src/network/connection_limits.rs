@@ -0,0 +1,270 @@
+use futures::Poll;
+use libp2p::{core::muxing::StreamMuxer, PeerId};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Upper bounds for the connections of the swarm. A limit that is not set is
+/// not enforced.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConnectionLimits {
+    pub max_connections: Option<usize>,
+    pub max_connections_per_peer: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("refusing connection because the connection limit is reached")]
+pub struct ConnectionLimitReached;
+
+#[derive(Debug, Default)]
+struct Connections {
+    total: usize,
+    per_peer: HashMap<PeerId, usize>,
+}
+
+/// Keeps track of the established connections across all clones.
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionCounter {
+    limits: ConnectionLimits,
+    connections: Arc<Mutex<Connections>>,
+}
+
+impl ConnectionCounter {
+    pub fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            limits,
+            connections: Arc::default(),
+        }
+    }
+
+    /// Reserve a slot for a connection that was just accepted or dialed, the
+    /// slot is freed once it is dropped.
+    ///
+    /// This happens before the handshake, such that connections whose
+    /// handshake is still in flight count against the limit as well.
+    pub fn acquire(&self) -> Result<ConnectionSlot, ConnectionLimitReached> {
+        let mut connections = self.connections.lock().unwrap();
+
+        if self
+            .limits
+            .max_connections
+            .map_or(false, |max| connections.total >= max)
+        {
+            return Err(ConnectionLimitReached);
+        }
+
+        connections.total += 1;
+
+        Ok(ConnectionSlot {
+            connections: Arc::clone(&self.connections),
+        })
+    }
+
+    /// Reserve a slot for a connection to `peer` once the handshake told who
+    /// is on the other end, the slot is freed once it is dropped.
+    pub fn acquire_for_peer(&self, peer: &PeerId) -> Result<PeerSlot, ConnectionLimitReached> {
+        let mut connections = self.connections.lock().unwrap();
+        let to_peer = connections.per_peer.get(peer).cloned().unwrap_or(0);
+
+        if self
+            .limits
+            .max_connections_per_peer
+            .map_or(false, |max| to_peer >= max)
+        {
+            return Err(ConnectionLimitReached);
+        }
+
+        connections.per_peer.insert(peer.clone(), to_peer + 1);
+
+        Ok(PeerSlot {
+            peer: peer.clone(),
+            connections: Arc::clone(&self.connections),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ConnectionSlot {
+    connections: Arc<Mutex<Connections>>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        let mut connections = self.connections.lock().unwrap();
+        connections.total -= 1;
+    }
+}
+
+#[derive(Debug)]
+pub struct PeerSlot {
+    peer: PeerId,
+    connections: Arc<Mutex<Connections>>,
+}
+
+impl Drop for PeerSlot {
+    fn drop(&mut self) {
+        let mut connections = self.connections.lock().unwrap();
+
+        if let Some(to_peer) = connections.per_peer.get_mut(&self.peer) {
+            *to_peer -= 1;
+            if *to_peer == 0 {
+                connections.per_peer.remove(&self.peer);
+            }
+        }
+    }
+}
+
+/// A connection that holds on to its slot for as long as it is open.
+#[derive(Debug)]
+pub struct LimitedStream<S> {
+    inner: S,
+    _slot: ConnectionSlot,
+}
+
+impl<S> LimitedStream<S> {
+    pub fn new(inner: S, slot: ConnectionSlot) -> Self {
+        Self { inner, _slot: slot }
+    }
+}
+
+impl<S: io::Read> io::Read for LimitedStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<S: io::Write> io::Write for LimitedStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for LimitedStream<S> {}
+
+impl<S: AsyncWrite> AsyncWrite for LimitedStream<S> {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
+
+/// A muxer that holds on to the slot of its peer for as long as the
+/// connection is alive.
+#[derive(Debug)]
+pub struct LimitedMuxer<M> {
+    inner: M,
+    _slot: PeerSlot,
+}
+
+impl<M> LimitedMuxer<M> {
+    pub fn new(inner: M, slot: PeerSlot) -> Self {
+        Self { inner, _slot: slot }
+    }
+}
+
+impl<M: StreamMuxer> StreamMuxer for LimitedMuxer<M> {
+    type Substream = M::Substream;
+    type OutboundSubstream = M::OutboundSubstream;
+    type Error = M::Error;
+
+    fn poll_inbound(&self) -> Poll<Self::Substream, Self::Error> {
+        self.inner.poll_inbound()
+    }
+
+    fn open_outbound(&self) -> Self::OutboundSubstream {
+        self.inner.open_outbound()
+    }
+
+    fn poll_outbound(
+        &self,
+        substream: &mut Self::OutboundSubstream,
+    ) -> Poll<Self::Substream, Self::Error> {
+        self.inner.poll_outbound(substream)
+    }
+
+    fn destroy_outbound(&self, substream: Self::OutboundSubstream) {
+        self.inner.destroy_outbound(substream)
+    }
+
+    fn read_substream(
+        &self,
+        substream: &mut Self::Substream,
+        buf: &mut [u8],
+    ) -> Poll<usize, Self::Error> {
+        self.inner.read_substream(substream, buf)
+    }
+
+    fn write_substream(
+        &self,
+        substream: &mut Self::Substream,
+        buf: &[u8],
+    ) -> Poll<usize, Self::Error> {
+        self.inner.write_substream(substream, buf)
+    }
+
+    fn flush_substream(&self, substream: &mut Self::Substream) -> Poll<(), Self::Error> {
+        self.inner.flush_substream(substream)
+    }
+
+    fn shutdown_substream(&self, substream: &mut Self::Substream) -> Poll<(), Self::Error> {
+        self.inner.shutdown_substream(substream)
+    }
+
+    fn destroy_substream(&self, substream: Self::Substream) {
+        self.inner.destroy_substream(substream)
+    }
+
+    fn is_remote_acknowledged(&self) -> bool {
+        self.inner.is_remote_acknowledged()
+    }
+
+    fn close(&self) -> Poll<(), Self::Error> {
+        self.inner.close()
+    }
+
+    fn flush_all(&self) -> Poll<(), Self::Error> {
+        self.inner.flush_all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn slots_are_freed_when_dropped() {
+        let counter = ConnectionCounter::new(ConnectionLimits {
+            max_connections: Some(1),
+            max_connections_per_peer: None,
+        });
+
+        let slot = counter.acquire();
+        assert_that(&slot).is_ok();
+        assert_that(&counter.acquire()).is_err();
+
+        drop(slot);
+        assert_that(&counter.acquire()).is_ok();
+    }
+
+    #[test]
+    fn connections_per_peer_are_limited_independently() {
+        let counter = ConnectionCounter::new(ConnectionLimits {
+            max_connections: None,
+            max_connections_per_peer: Some(1),
+        });
+        let peer = PeerId::random();
+
+        let _slot = counter.acquire_for_peer(&peer).unwrap();
+
+        assert_that(&counter.acquire_for_peer(&peer)).is_err();
+        assert_that(&counter.acquire_for_peer(&PeerId::random())).is_ok();
+        assert_that(&counter.acquire()).is_ok();
+    }
+}
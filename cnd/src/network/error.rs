@@ -0,0 +1,24 @@
+use libp2p_comit::RequestError;
+
+/// Why a request to a peer did not yield a usable response.
+#[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error("failed to connect to the peer")]
+    PeerUnreachable,
+    #[error("the connection to the peer closed before it responded")]
+    ConnectionClosed,
+    #[error("the peer responded with an invalid response")]
+    InvalidResponse,
+    #[error("the peer did not respond in time")]
+    Timeout,
+}
+
+impl From<RequestError> for Error {
+    fn from(e: RequestError) -> Self {
+        match e {
+            RequestError::PeerUnreachable => Error::PeerUnreachable,
+            RequestError::ConnectionClosed => Error::ConnectionClosed,
+            RequestError::InvalidResponse => Error::InvalidResponse,
+        }
+    }
+}
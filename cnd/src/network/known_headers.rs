@@ -0,0 +1,72 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The request types we accept from peers and the mandatory headers we
+/// understand for each of them.
+///
+/// Requests of other types and requests with other mandatory headers are
+/// rejected. New protocol messages register themselves here instead of in the
+/// network behaviour.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KnownHeaders(HashMap<String, HashSet<String>>);
+
+impl KnownHeaders {
+    /// The request types cnd implements.
+    pub fn builtin() -> Self {
+        KnownHeaders::default().with_request_type("SWAP", &[
+            "id",
+            "alpha_ledger",
+            "beta_ledger",
+            "alpha_asset",
+            "beta_asset",
+            "protocol",
+        ])
+    }
+
+    /// Adds `headers` to the ones known for `request_type`, the request type
+    /// is known from then on even without any headers.
+    pub fn with_request_type(mut self, request_type: &str, headers: &[&str]) -> Self {
+        self.0
+            .entry(request_type.to_owned())
+            .or_default()
+            .extend(headers.iter().map(|header| (*header).to_owned()));
+
+        self
+    }
+
+    /// Adds the request types and headers of the `[network.known_headers]`
+    /// section.
+    pub fn with_configured(mut self, configured: &BTreeMap<String, Vec<String>>) -> Self {
+        for (request_type, headers) in configured {
+            self.0
+                .entry(request_type.clone())
+                .or_default()
+                .extend(headers.iter().cloned());
+        }
+
+        self
+    }
+
+    pub fn into_inner(self) -> HashMap<String, HashSet<String>> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_headers_extend_the_builtin_ones() {
+        let mut configured = BTreeMap::new();
+        configured.insert(String::from("SWAP"), vec![String::from("fee_rate")]);
+        configured.insert(String::from("PING"), vec![]);
+
+        let known_headers = KnownHeaders::builtin()
+            .with_configured(&configured)
+            .into_inner();
+
+        assert_eq!(known_headers.get("PING"), Some(&HashSet::new()));
+        assert!(known_headers["SWAP"].contains("fee_rate"));
+        assert!(known_headers["SWAP"].contains("alpha_ledger"));
+    }
+}
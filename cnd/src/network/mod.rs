@@ -1,55 +1,107 @@
+pub mod compliance;
+mod connection_limits;
+mod error;
+mod known_headers;
+mod onion;
+pub mod probe;
 pub mod send_request;
+mod socks5;
+mod swap_limits;
 pub mod transport;
+mod wire_log;
+mod worker;
 
-pub use send_request::*;
+pub use self::{
+    connection_limits::ConnectionLimits,
+    error::Error,
+    known_headers::KnownHeaders,
+    onion::{format_address_hint, parse_address_hint, InvalidAddressHint},
+    send_request::*,
+    swap_limits::{release_finished_swaps, SwapCounter, SwapLimitReached, SwapSlot},
+    wire_log::WireLog,
+    worker::{SwarmHandle, SwarmWorker, WorkerGone},
+};
 
 use crate::{
-    btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
-    db::{Save, Saver, Sqlite, Swap},
-    libp2p_comit_ext::{FromHeader, ToHeader},
+    btsieve::{bitcoin::BitcoinConnector, ethereum::Web3Connector},
+    chaos::Chaos,
+    clock_skew::{self, ClockMonitor, ClockSkew},
+    db::{self, Database, RequestedRates, Save, Saver, Sqlite, Swap, SwapMessages},
+    derivation::Derivation,
+    libp2p_comit_ext::{FromHeader, HeaderSerializationError, ToHeader},
+    maintenance::Maintenance,
     seed::Seed,
+    sharded_map::ShardedMap,
     swap_protocols::{
         asset::{Asset, AssetKind},
         rfc003::{
             self, bob,
-            messages::{Decision, DeclineResponseBody, Request, SwapDeclineReason},
+            messages::{
+                AcceptResponseBody, Decision, DeclineResponseBody, Request, SwapDeclineReason,
+            },
             state_store::{InMemoryStateStore, StateStore},
-            Ledger,
+            Ledger, Rate, SwapCommunication, SwapSecrets,
         },
-        HashFunction, LedgerKind, Role, SwapId, SwapProtocol,
+        EventBus, HashFunction, LedgerKind, Role, SwapEvent, SwapEvents, SwapId, SwapProtocol,
     },
+    swap_tracing::Tracer,
+    timestamp::Timestamp,
 };
-use futures::{
-    future::Future,
-    sync::oneshot::{self, Sender},
-};
+use async_trait::async_trait;
+use futures::{future::Future, sync::oneshot};
 use futures_core::{FutureExt, TryFutureExt};
+#[cfg(feature = "mdns")]
+use libp2p::mdns::{Mdns, MdnsEvent};
 use libp2p::{
-    core::muxing::{StreamMuxer, SubstreamRef},
-    mdns::{Mdns, MdnsEvent},
+    identify::{Identify, IdentifyEvent},
+    identity::PublicKey,
+    ping::{Ping, PingEvent, PingSuccess},
     swarm::NetworkBehaviourEventProcess,
-    Multiaddr, NetworkBehaviour, PeerId, Swarm, Transport,
+    Multiaddr, NetworkBehaviour, PeerId,
 };
 use libp2p_comit::{
-    frame::{OutboundRequest, Response, ValidatedInboundRequest},
-    BehaviourOutEvent, Comit, PendingInboundRequest,
+    frame::{Header, OutboundRequest, Response, ValidatedInboundRequest},
+    BehaviourOutEvent, Comit, Frame, FrameType, PendingInboundRequest, RequestKind, RequestMetrics,
+    Unrecognized,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
     io,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
 };
 use tokio::runtime::TaskExecutor;
 
+/// Frames are rejected if they are larger than this and nothing else is
+/// configured, no message of the COMIT protocol comes close.
+const DEFAULT_MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// Announced to peers via the identify protocol.
+const PROTOCOL_VERSION: &str = "comit/1.0.0";
+
+/// The version of cnd and the commit it was built from, as announced to peers
+/// via the identify protocol.
+pub fn agent_version() -> String {
+    format!("cnd/{} ({})", env!("CARGO_PKG_VERSION"), env!("GIT_HASH"))
+}
+
 #[derive(NetworkBehaviour)]
 #[allow(missing_debug_implementations)]
 pub struct ComitNode<TSubstream> {
     comit: Comit<TSubstream>,
+    /// Only if cnd was built with the `mdns` feature, otherwise peers have to
+    /// be dialed with an address.
+    #[cfg(feature = "mdns")]
     mdns: Mdns<TSubstream>,
+    ping: Ping<TSubstream>,
+    identify: Identify<TSubstream>,
 
     #[behaviour(ignore)]
-    pub bitcoin_connector: BitcoindConnector,
+    pub bitcoin_connector: BitcoinConnector,
     #[behaviour(ignore)]
     pub ethereum_connector: Web3Connector,
     #[behaviour(ignore)]
@@ -57,11 +109,57 @@ pub struct ComitNode<TSubstream> {
     #[behaviour(ignore)]
     pub seed: Seed,
     #[behaviour(ignore)]
-    pub db: Sqlite,
+    pub derivation: Option<Derivation>,
     #[behaviour(ignore)]
-    response_channels: Arc<Mutex<HashMap<SwapId, oneshot::Sender<Response>>>>,
+    pub db: Database,
+    /// Keeps what is only recorded alongside the swaps, whichever backend
+    /// they are saved in.
+    #[behaviour(ignore)]
+    sqlite: Sqlite,
+    #[behaviour(ignore)]
+    response_channels: Arc<ShardedMap<SwapId, oneshot::Sender<Response>>>,
     #[behaviour(ignore)]
     task_executor: TaskExecutor,
+    #[behaviour(ignore)]
+    event_bus: EventBus,
+    #[behaviour(ignore)]
+    discovered_addresses: HashMap<PeerId, HashSet<Multiaddr>>,
+    #[behaviour(ignore)]
+    latencies: HashMap<PeerId, Duration>,
+    #[behaviour(ignore)]
+    peer_infos: HashMap<PeerId, PeerInfo>,
+    #[behaviour(ignore)]
+    maintenance: Maintenance,
+    #[behaviour(ignore)]
+    clock_monitor: ClockMonitor,
+    #[behaviour(ignore)]
+    unsaved_requests: Arc<AtomicU64>,
+    #[behaviour(ignore)]
+    swap_counter: SwapCounter,
+    #[behaviour(ignore)]
+    chaos: Chaos,
+    #[behaviour(ignore)]
+    tracer: Option<Tracer>,
+}
+
+/// What we know about our connection to a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerStatus {
+    pub connected: bool,
+    pub last_seen: Option<SystemTime>,
+    /// The addresses the peer announced via mDNS and the ones we are
+    /// currently connected to it on.
+    pub addresses: Vec<Multiaddr>,
+    /// The round-trip time of the last successful ping while connected.
+    pub latency: Option<Duration>,
+}
+
+/// What a peer told us about itself via the identify protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerInfo {
+    pub agent_version: String,
+    pub protocol_version: String,
+    pub listen_addresses: Vec<Multiaddr>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -74,62 +172,211 @@ impl Display for DialInformation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match &self.address_hint {
             None => write!(f, "{}", self.peer_id),
-            Some(address_hint) => write!(f, "{}@{}", self.peer_id, address_hint),
+            Some(address_hint) => {
+                write!(f, "{}@{}", self.peer_id, format_address_hint(address_hint))
+            }
         }
     }
 }
 
 impl<TSubstream> ComitNode<TSubstream> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        bitcoin_connector: BitcoindConnector,
+        bitcoin_connector: BitcoinConnector,
         ethereum_connector: Web3Connector,
         state_store: Arc<InMemoryStateStore>,
         seed: Seed,
-        db: Sqlite,
+        derivation: Option<Derivation>,
+        db: Database,
+        sqlite: Sqlite,
         task_executor: TaskExecutor,
+        event_bus: EventBus,
+        known_headers: KnownHeaders,
+        max_pending_inbound_substreams: Option<usize>,
+        max_frame_size: Option<usize>,
+        max_swaps_per_peer: Option<usize>,
+        wire_log: Option<WireLog>,
+        maintenance: Maintenance,
+        clock_monitor: ClockMonitor,
+        chaos: Chaos,
+        tracer: Option<Tracer>,
+        local_public_key: PublicKey,
     ) -> Result<Self, io::Error> {
-        let mut swap_headers = HashSet::new();
-        swap_headers.insert("id".into());
-        swap_headers.insert("alpha_ledger".into());
-        swap_headers.insert("beta_ledger".into());
-        swap_headers.insert("alpha_asset".into());
-        swap_headers.insert("beta_asset".into());
-        swap_headers.insert("protocol".into());
+        let comit = Comit::new(known_headers.into_inner())
+            .with_max_frame_size(max_frame_size.unwrap_or(DEFAULT_MAX_FRAME_SIZE));
+        let comit = match max_pending_inbound_substreams {
+            Some(max) => comit.with_max_inbound_substreams(max),
+            None => comit,
+        };
+        let comit = match wire_log {
+            Some(wire_log) => comit.with_frame_observer(Arc::new(wire_log)),
+            None => comit,
+        };
 
-        let mut known_headers = HashMap::new();
-        known_headers.insert("SWAP".into(), swap_headers);
+        let swap_counter = SwapCounter::new(max_swaps_per_peer);
+        task_executor.spawn(release_finished_swaps(
+            swap_counter.clone(),
+            event_bus.subscribe(),
+        ));
 
         Ok(Self {
-            comit: Comit::new(known_headers),
+            comit,
+            #[cfg(feature = "mdns")]
             mdns: Mdns::new()?,
+            ping: Ping::default(),
+            identify: Identify::new(
+                PROTOCOL_VERSION.to_owned(),
+                agent_version(),
+                local_public_key,
+            ),
             bitcoin_connector,
             ethereum_connector,
             state_store,
             seed,
+            derivation,
             db,
-            response_channels: Arc::new(Mutex::new(HashMap::new())),
+            sqlite,
+            response_channels: Arc::new(ShardedMap::default()),
             task_executor,
+            event_bus,
+            discovered_addresses: HashMap::new(),
+            latencies: HashMap::new(),
+            peer_infos: HashMap::new(),
+            maintenance,
+            clock_monitor,
+            unsaved_requests: Arc::new(AtomicU64::new(0)),
+            swap_counter,
+            chaos,
+            tracer,
         })
     }
 
+    pub fn peer_status(&mut self, peer_id: &PeerId) -> PeerStatus {
+        let mut addresses = self
+            .discovered_addresses
+            .get(peer_id)
+            .cloned()
+            .unwrap_or_default();
+        if let Some((_, connected_addresses)) = self
+            .comit
+            .connected_peers()
+            .find(|(candidate, _)| candidate == peer_id)
+        {
+            addresses.extend(connected_addresses);
+        }
+
+        let connected = self.comit.is_connected(peer_id);
+
+        PeerStatus {
+            connected,
+            last_seen: self.comit.last_seen(peer_id),
+            addresses: addresses.into_iter().collect(),
+            latency: self.latency(peer_id).filter(|_| connected),
+        }
+    }
+
+    pub fn latency(&self, peer_id: &PeerId) -> Option<Duration> {
+        self.latencies.get(peer_id).cloned()
+    }
+
+    /// The latency of all peers we are currently connected to and have
+    /// successfully pinged.
+    pub fn latencies(&mut self) -> Vec<(PeerId, Duration)> {
+        let connected_peers = self
+            .comit
+            .connected_peers()
+            .map(|(peer, _)| peer)
+            .collect::<Vec<_>>();
+
+        connected_peers
+            .into_iter()
+            .filter_map(|peer| self.latency(&peer).map(|latency| (peer, latency)))
+            .collect()
+    }
+
+    /// What the peers we are currently connected to reported about
+    /// themselves.
+    pub fn peer_infos(&mut self) -> HashMap<PeerId, PeerInfo> {
+        let connected_peers = self
+            .comit
+            .connected_peers()
+            .map(|(peer, _)| peer)
+            .collect::<Vec<_>>();
+
+        connected_peers
+            .into_iter()
+            .filter_map(|peer| self.peer_infos.get(&peer).cloned().map(|info| (peer, info)))
+            .collect()
+    }
+
+    /// The request types and mandatory headers peers sent us that are not in
+    /// our known headers, with how often they did.
+    pub fn unrecognized(&self) -> BTreeMap<Unrecognized, u64> {
+        self.comit
+            .unrecognized()
+            .iter()
+            .map(|(unrecognized, count)| (unrecognized.clone(), *count))
+            .collect()
+    }
+
+    /// How many requests of each type we sent and received, and how long
+    /// peers took to answer ours.
+    pub fn request_metrics(&self) -> BTreeMap<RequestKind, RequestMetrics> {
+        self.comit.request_metrics().into_iter().collect()
+    }
+
+    /// How many swap requests we declined because we could not save them.
+    pub fn unsaved_requests(&self) -> u64 {
+        self.unsaved_requests.load(Ordering::SeqCst)
+    }
+
+    /// How many swaps each peer requested from us that are not over yet.
+    pub fn active_swaps(&self) -> HashMap<PeerId, usize> {
+        self.swap_counter.active_swaps()
+    }
+
     pub fn send_request(
         &mut self,
         peer_id: DialInformation,
         request: OutboundRequest,
-    ) -> Box<dyn Future<Item = Response, Error = ()> + Send> {
-        self.comit
-            .send_request((peer_id.peer_id, peer_id.address_hint), request)
+    ) -> Box<dyn Future<Item = Response, Error = Error> + Send> {
+        Box::new(
+            self.comit
+                .send_request((peer_id.peer_id, peer_id.address_hint), request)
+                .map_err(Error::from),
+        )
     }
 }
 
-async fn handle_request(
-    db: Sqlite,
+/// Everything besides the request itself that is needed to take a swap
+/// request from a peer.
+#[derive(Clone, Debug)]
+struct RequestContext<DB, R> {
+    db: DB,
+    rates: R,
     seed: Seed,
+    derivation: Option<Derivation>,
     state_store: Arc<InMemoryStateStore>,
+    maintenance: Maintenance,
+    clock_monitor: ClockMonitor,
+    unsaved_requests: Arc<AtomicU64>,
+    swap_counter: SwapCounter,
+}
+
+async fn handle_request<DB: Saver + Clone, R: RequestedRates>(
+    context: RequestContext<DB, R>,
     counterparty: PeerId,
     mut request: ValidatedInboundRequest,
-) -> Result<SwapId, Response> {
+) -> Result<InboundRequest, Response> {
     match request.request_type() {
+        "SWAP" if context.maintenance.is_on() => {
+            log::info!(
+                "declining swap request from {} because of maintenance mode",
+                counterparty
+            );
+
+            Err(decline(Some(SwapDeclineReason::TemporarilyUnavailable)))
+        }
         "SWAP" => {
             let protocol: SwapProtocol = header!(request
                 .take_header("protocol")
@@ -137,6 +384,30 @@ async fn handle_request(
             match protocol {
                 SwapProtocol::Rfc003(hash_function) => {
                     let swap_id = header!(request.take_header("id").map(SwapId::from_header));
+                    if let Some(sent_at) = request
+                        .take_header("sent_at")
+                        .and_then(|header| Timestamp::from_header(header).ok())
+                    {
+                        context.clock_monitor.record_counterparty(
+                            swap_id,
+                            ClockSkew::measure(
+                                clock_skew::Source::Counterparty,
+                                Timestamp::now(),
+                                sent_at,
+                            ),
+                        );
+                    }
+                    let rate = request.take_header("rate").and_then(|header| {
+                        Rate::from_header(header)
+                            .map_err(|e| {
+                                log::warn!("ignoring invalid rate of swap {}: {}", swap_id, e)
+                            })
+                            .ok()
+                    });
+                    let slot = context
+                        .swap_counter
+                        .acquire(&counterparty, swap_id)
+                        .map_err(|e| swap_limit_reached(swap_id, &counterparty, e))?;
                     let alpha_ledger = header!(request
                         .take_header("alpha_ledger")
                         .map(LedgerKind::from_header));
@@ -150,32 +421,27 @@ async fn handle_request(
                         .take_header("beta_asset")
                         .map(AssetKind::from_header));
 
-                    match (alpha_ledger, beta_ledger, alpha_asset, beta_asset) {
+                    let inbound = match (alpha_ledger, beta_ledger, alpha_asset, beta_asset) {
                         (
                             LedgerKind::Bitcoin(alpha_ledger),
                             LedgerKind::Ethereum(beta_ledger),
                             AssetKind::Bitcoin(alpha_asset),
                             AssetKind::Ether(beta_asset),
                         ) => {
-                            let request = rfc003_swap_request(
-                                swap_id,
-                                alpha_ledger,
-                                beta_ledger,
-                                alpha_asset,
-                                beta_asset,
-                                hash_function,
-                                body!(request.take_body_as()),
-                            );
-                            insert_state_for_bob(
-                                db.clone(),
-                                seed,
-                                state_store.clone(),
+                            take_swap_request(
+                                &context,
                                 counterparty,
-                                request,
+                                rfc003_swap_request(
+                                    swap_id,
+                                    alpha_ledger,
+                                    beta_ledger,
+                                    alpha_asset,
+                                    beta_asset,
+                                    hash_function,
+                                    body!(request.take_body_as()),
+                                ),
                             )
-                            .await
-                            .expect("Could not save state to db");
-                            Ok(swap_id)
+                            .await?
                         }
                         (
                             LedgerKind::Ethereum(alpha_ledger),
@@ -183,25 +449,20 @@ async fn handle_request(
                             AssetKind::Ether(alpha_asset),
                             AssetKind::Bitcoin(beta_asset),
                         ) => {
-                            let request = rfc003_swap_request(
-                                swap_id,
-                                alpha_ledger,
-                                beta_ledger,
-                                alpha_asset,
-                                beta_asset,
-                                hash_function,
-                                body!(request.take_body_as()),
-                            );
-                            insert_state_for_bob(
-                                db.clone(),
-                                seed,
-                                state_store.clone(),
+                            take_swap_request(
+                                &context,
                                 counterparty,
-                                request,
+                                rfc003_swap_request(
+                                    swap_id,
+                                    alpha_ledger,
+                                    beta_ledger,
+                                    alpha_asset,
+                                    beta_asset,
+                                    hash_function,
+                                    body!(request.take_body_as()),
+                                ),
                             )
-                            .await
-                            .expect("Could not save state to db");
-                            Ok(swap_id)
+                            .await?
                         }
                         (
                             LedgerKind::Bitcoin(alpha_ledger),
@@ -209,26 +470,20 @@ async fn handle_request(
                             AssetKind::Bitcoin(alpha_asset),
                             AssetKind::Erc20(beta_asset),
                         ) => {
-                            let request = rfc003_swap_request(
-                                swap_id,
-                                alpha_ledger,
-                                beta_ledger,
-                                alpha_asset,
-                                beta_asset,
-                                hash_function,
-                                body!(request.take_body_as()),
-                            );
-                            insert_state_for_bob(
-                                db.clone(),
-                                seed,
-                                state_store.clone(),
+                            take_swap_request(
+                                &context,
                                 counterparty,
-                                request,
+                                rfc003_swap_request(
+                                    swap_id,
+                                    alpha_ledger,
+                                    beta_ledger,
+                                    alpha_asset,
+                                    beta_asset,
+                                    hash_function,
+                                    body!(request.take_body_as()),
+                                ),
                             )
-                            .await
-                            .expect("Could not save state to db");
-
-                            Ok(swap_id)
+                            .await?
                         }
                         (
                             LedgerKind::Ethereum(alpha_ledger),
@@ -236,66 +491,44 @@ async fn handle_request(
                             AssetKind::Erc20(alpha_asset),
                             AssetKind::Bitcoin(beta_asset),
                         ) => {
-                            let request = rfc003_swap_request(
-                                swap_id,
-                                alpha_ledger,
-                                beta_ledger,
-                                alpha_asset,
-                                beta_asset,
-                                hash_function,
-                                body!(request.take_body_as()),
-                            );
-                            insert_state_for_bob(
-                                db.clone(),
-                                seed,
-                                state_store.clone(),
+                            take_swap_request(
+                                &context,
                                 counterparty,
-                                request,
+                                rfc003_swap_request(
+                                    swap_id,
+                                    alpha_ledger,
+                                    beta_ledger,
+                                    alpha_asset,
+                                    beta_asset,
+                                    hash_function,
+                                    body!(request.take_body_as()),
+                                ),
                             )
-                            .await
-                            .expect("Could not save state to db");
-                            Ok(swap_id)
+                            .await?
                         }
                         (alpha_ledger, beta_ledger, alpha_asset, beta_asset) => {
                             log::warn!(
-                                    "swapping {:?} to {:?} from {:?} to {:?} is currently not supported", alpha_asset, beta_asset, alpha_ledger, beta_ledger
-                                );
-
-                            let decline_body = DeclineResponseBody {
-                                reason: Some(SwapDeclineReason::UnsupportedSwap),
-                            };
-
-                            Err(Response::empty()
-                                .with_header(
-                                    "decision",
-                                    Decision::Declined
-                                        .to_header()
-                                        .expect("Decision should not fail to serialize"),
-                                )
-                                .with_body(serde_json::to_value(decline_body).expect(
-                                    "decline body should always serialize into serde_json::Value",
-                                )))
+                                "swapping {:?} to {:?} from {:?} to {:?} is currently not supported",
+                                alpha_asset,
+                                beta_asset,
+                                alpha_ledger,
+                                beta_ledger
+                            );
+
+                            return Err(decline(Some(SwapDeclineReason::UnsupportedSwap)));
                         }
+                    };
+
+                    slot.keep();
+                    if let InboundRequest::New(swap_id) = inbound {
+                        save_requested_rate(&context.rates, swap_id, rate).await;
                     }
+                    Ok(inbound)
                 }
                 SwapProtocol::Unknown(protocol) => {
                     log::warn!("the swap protocol {} is currently not supported", protocol);
 
-                    let decline_body = DeclineResponseBody {
-                        reason: Some(SwapDeclineReason::UnsupportedProtocol),
-                    };
-                    Err(Response::empty()
-                        .with_header(
-                            "decision",
-                            Decision::Declined
-                                .to_header()
-                                .expect("Decision should not fail to serialize"),
-                        )
-                        .with_body(
-                            serde_json::to_value(decline_body).expect(
-                                "decline body should always serialize into serde_json::Value",
-                            ),
-                        ))
+                    Err(decline(Some(SwapDeclineReason::UnsupportedProtocol)))
                 }
             }
         }
@@ -307,32 +540,227 @@ async fn handle_request(
         request_type => {
             log::warn!("request type '{}' is unknown", request_type);
 
-            Err(Response::empty().with_header(
-                "decision",
-                Decision::Declined
-                    .to_header()
-                    .expect("Decision should not fail to serialize"),
-            ))
+            Err(decline(None))
+        }
+    }
+}
+
+/// A swap request that waits for Bob to decide on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InboundRequest {
+    New(SwapId),
+    /// Alice sent the request again because she did not get a response yet,
+    /// the response Bob is going to give is sent to this request instead.
+    Resent(SwapId),
+}
+
+/// Saves the swap request and hands it to Bob, declining it if that fails.
+///
+/// Alice sends a request again if she did not get the response to it, hence a
+/// request for a swap Bob knows already is answered the way he answered it
+/// before.
+async fn take_swap_request<AL, BL, AA, BA, DB, R>(
+    context: &RequestContext<DB, R>,
+    counterparty: PeerId,
+    swap_request: Request<AL, BL, AA, BA>,
+) -> Result<InboundRequest, Response>
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+    DB: Save<(Swap, Request<AL, BL, AA, BA>)> + Clone,
+{
+    let swap_id = swap_request.swap_id;
+
+    match context
+        .state_store
+        .get::<bob::State<AL, BL, AA, BA>>(&swap_id)
+    {
+        Ok(None) => {}
+        Ok(Some(state)) => return resent_request(state, swap_request),
+        Err(e) => {
+            log::warn!(
+                "declining swap {} because its id belongs to a different swap: {:?}",
+                swap_id,
+                e
+            );
+
+            return Err(decline(None));
+        }
+    }
+
+    insert_state_for_bob(
+        context.db.clone(),
+        context.seed,
+        context.derivation,
+        context.state_store.clone(),
+        counterparty,
+        swap_request,
+    )
+    .await
+    .map_err(|e| unsaved_request(swap_id, e, &context.unsaved_requests))?;
+
+    Ok(InboundRequest::New(swap_id))
+}
+
+fn resent_request<AL, BL, AA, BA>(
+    state: bob::State<AL, BL, AA, BA>,
+    swap_request: Request<AL, BL, AA, BA>,
+) -> Result<InboundRequest, Response>
+where
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+{
+    let swap_id = swap_request.swap_id;
+
+    if state.request() != swap_request {
+        log::warn!(
+            "declining swap {} because its id belongs to a different swap",
+            swap_id
+        );
+
+        return Err(decline(None));
+    }
+
+    match state.swap_communication {
+        SwapCommunication::Proposed { .. } => Ok(InboundRequest::Resent(swap_id)),
+        SwapCommunication::Accepted { response, .. } => Err(accept_response(response)),
+        SwapCommunication::Declined { response, .. } => Err(decline_swap_response(response)),
+        SwapCommunication::Failed { .. } => Err(decline(Some(SwapDeclineReason::InternalError))),
+    }
+}
+
+/// Declines the swap such that Alice does not wait for a response until her
+/// request times out, she may send it again later.
+fn unsaved_request(swap_id: SwapId, e: anyhow::Error, unsaved_requests: &AtomicU64) -> Response {
+    log::error!(
+        "declining swap {} because it could not be saved: {:?}",
+        swap_id,
+        e
+    );
+    unsaved_requests.fetch_add(1, Ordering::SeqCst);
+
+    decline(Some(SwapDeclineReason::InternalError))
+}
+
+/// Declines the swap until some of the swaps of the peer are over.
+fn swap_limit_reached(swap_id: SwapId, counterparty: &PeerId, e: SwapLimitReached) -> Response {
+    log::warn!(
+        "declining swap {} from {} because {}",
+        swap_id,
+        counterparty,
+        e
+    );
+
+    decline(Some(SwapDeclineReason::TemporarilyUnavailable))
+}
+
+/// Declines the request, without a reason if the response fails to serialize,
+/// so that a response is sent in any case.
+fn decline(reason: Option<SwapDeclineReason>) -> Response {
+    decline_with(&Decision::Declined, reason)
+}
+
+fn decline_with<D: ToHeader>(decision: &D, reason: Option<SwapDeclineReason>) -> Response {
+    decline_response(decision, reason).unwrap_or_else(|e| {
+        log::error!("declining without a reason because {:?}", e);
+
+        Response::empty()
+            .with_header("decision", Header::with_str_value("declined"))
+            .with_body(serde_json::json!({}))
+    })
+}
+
+fn decline_response<D: ToHeader>(
+    decision: &D,
+    reason: Option<SwapDeclineReason>,
+) -> Result<Response, HeaderSerializationError> {
+    let decision = decision
+        .to_header()
+        .map_err(HeaderSerializationError::new("decision"))?;
+    let body = serde_json::to_value(DeclineResponseBody { reason }).unwrap_or_else(|e| {
+        log::error!("dropping the reason of a decline because {:?}", e);
+        serde_json::json!({})
+    });
+
+    Ok(Response::empty()
+        .with_header("decision", decision)
+        .with_body(body))
+}
+
+/// Accepts the swap with the identities of Bob's accept message.
+pub fn accept_response<AL: Ledger, BL: Ledger>(message: rfc003::Accept<AL, BL>) -> Response {
+    Response::empty()
+        .with_header(
+            "decision",
+            Decision::Accepted
+                .to_header()
+                .expect("Decision should not fail to serialize"),
+        )
+        .with_body(
+            serde_json::to_value(AcceptResponseBody::<AL, BL> {
+                beta_ledger_refund_identity: message.beta_ledger_refund_identity,
+                alpha_ledger_redeem_identity: message.alpha_ledger_redeem_identity,
+            })
+            .expect("body should always serialize into serde_json::Value"),
+        )
+}
+
+/// Declines the swap for the reason of Bob's decline message.
+pub fn decline_swap_response(message: rfc003::Decline) -> Response {
+    decline(message.reason)
+}
+
+/// Keep the rate Alice attached to her request, the swap goes on if this fails.
+async fn save_requested_rate<R: RequestedRates>(rates: &R, swap_id: SwapId, rate: Option<Rate>) {
+    if let Some(rate) = rate {
+        if let Err(e) = rates.save_requested_rate(&swap_id, &rate).await {
+            log::warn!("failed to save the rate of swap {}: {:?}", swap_id, e);
         }
     }
 }
 
+/// Record a request or response of the swap, the swap goes on if this fails.
+pub async fn record_message<M: SwapMessages>(
+    messages: &M,
+    swap_id: SwapId,
+    direction: db::Direction,
+    frame: &Frame,
+) {
+    if let Err(e) = messages.record_message(&swap_id, direction, frame).await {
+        log::warn!(
+            "failed to record {} message of swap {}: {:?}",
+            direction,
+            swap_id,
+            e
+        );
+    }
+}
+
 #[allow(clippy::type_complexity)]
 async fn insert_state_for_bob<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset, DB>(
     db: DB,
     seed: Seed,
+    derivation: Option<Derivation>,
     state_store: Arc<InMemoryStateStore>,
     counterparty: PeerId,
     swap_request: Request<AL, BL, AA, BA>,
 ) -> anyhow::Result<()>
 where
-    DB: Save<Request<AL, BL, AA, BA>> + Saver,
+    DB: Save<(Swap, Request<AL, BL, AA, BA>)>,
 {
     let id = swap_request.swap_id;
-    let seed = seed.swap_seed(id);
+    let identities = derivation.map(|derivation| derivation.identities(id, Role::Bob));
+    let seed = SwapSecrets::new(seed.swap_seed(id), identities);
 
-    Save::save(&db, Swap::new(id, Role::Bob, counterparty)).await?;
-    Save::save(&db, swap_request.clone()).await?;
+    Save::save(
+        &db,
+        (Swap::new(id, Role::Bob, counterparty), swap_request.clone()),
+    )
+    .await?;
 
     let state = bob::State::proposed(swap_request.clone(), seed);
     state_store.insert(id, state);
@@ -340,59 +768,52 @@ where
     Ok(())
 }
 
+#[async_trait]
 pub trait Network: Send + Sync + 'static {
-    fn comit_peers(&self) -> Box<dyn Iterator<Item = (PeerId, Vec<Multiaddr>)> + Send + 'static>;
-    fn listen_addresses(&self) -> Vec<Multiaddr>;
+    async fn comit_peers(&self) -> anyhow::Result<Vec<(PeerId, Vec<Multiaddr>)>>;
+    async fn listen_addresses(&self) -> anyhow::Result<Vec<Multiaddr>>;
+    async fn peer_status(&self, peer_id: PeerId) -> anyhow::Result<PeerStatus>;
+    async fn latencies(&self) -> anyhow::Result<Vec<(PeerId, Duration)>>;
+    async fn unrecognized(&self) -> anyhow::Result<BTreeMap<Unrecognized, u64>>;
+    async fn request_metrics(&self) -> anyhow::Result<BTreeMap<RequestKind, RequestMetrics>>;
+    async fn unsaved_requests(&self) -> anyhow::Result<u64>;
+    async fn active_swaps(&self) -> anyhow::Result<HashMap<PeerId, usize>>;
+    async fn peer_infos(&self) -> anyhow::Result<HashMap<PeerId, PeerInfo>>;
     fn pending_request_for(&self, swap: SwapId) -> Option<oneshot::Sender<Response>>;
 }
 
-impl<
-        TTransport: Transport + Send + Sync + 'static,
-        TMuxer: StreamMuxer + Send + Sync + 'static,
-    > Network for Mutex<Swarm<TTransport, ComitNode<SubstreamRef<Arc<TMuxer>>>>>
-where
-    <TMuxer as StreamMuxer>::OutboundSubstream: Send + 'static,
-    <TMuxer as StreamMuxer>::Substream: Send + Sync + 'static,
-    <TTransport as Transport>::Dial: Send,
-    <TTransport as Transport>::Error: Send,
-    <TTransport as Transport>::Listener: Send,
-    <TTransport as Transport>::ListenerUpgrade: Send,
-    TTransport: Transport<Output = (PeerId, TMuxer)> + Clone,
-{
-    fn comit_peers(&self) -> Box<dyn Iterator<Item = (PeerId, Vec<Multiaddr>)> + Send + 'static> {
-        let mut swarm = self.lock().unwrap();
-
-        Box::new(swarm.comit.connected_peers())
-    }
-
-    fn listen_addresses(&self) -> Vec<Multiaddr> {
-        let swarm = self.lock().unwrap();
-
-        Swarm::listeners(&swarm)
-            .chain(Swarm::external_addresses(&swarm))
-            .cloned()
-            .collect()
-    }
-
-    fn pending_request_for(&self, swap: SwapId) -> Option<Sender<Response>> {
-        let swarm = self.lock().unwrap();
-        let mut response_channels = swarm.response_channels.lock().unwrap();
-
-        response_channels.remove(&swap)
-    }
-}
-
 impl<TSubstream> NetworkBehaviourEventProcess<BehaviourOutEvent> for ComitNode<TSubstream> {
     fn inject_event(&mut self, event: BehaviourOutEvent) {
         match event {
             BehaviourOutEvent::PendingInboundRequest { request, peer_id } => {
                 let PendingInboundRequest { request, channel } = request;
+                // The requester never hears back, as if the response got lost
+                // on the way.
+                let channel = if self.chaos.drop_response() {
+                    log::warn!("dropping the response to {} on purpose", peer_id);
+                    oneshot::channel().0
+                } else {
+                    channel
+                };
+                let received = Frame::new(
+                    FrameType::Request,
+                    serde_json::to_value(&request).expect("requests always serialize"),
+                );
+                let received_at = SystemTime::now();
 
                 self.task_executor.spawn(
                     handle_request(
-                        self.db.clone(),
-                        self.seed,
-                        self.state_store.clone(),
+                        RequestContext {
+                            db: self.db.clone(),
+                            rates: self.sqlite.clone(),
+                            seed: self.seed,
+                            derivation: self.derivation,
+                            state_store: self.state_store.clone(),
+                            maintenance: self.maintenance.clone(),
+                            clock_monitor: self.clock_monitor.clone(),
+                            unsaved_requests: self.unsaved_requests.clone(),
+                            swap_counter: self.swap_counter.clone(),
+                        },
                         peer_id,
                         request,
                     )
@@ -400,13 +821,42 @@ impl<TSubstream> NetworkBehaviourEventProcess<BehaviourOutEvent> for ComitNode<T
                     .compat()
                     .then({
                         let response_channels = self.response_channels.clone();
+                        let event_bus = self.event_bus.clone();
+                        let messages = self.sqlite.clone();
+                        let task_executor = self.task_executor.clone();
+                        let tracer = self.tracer.clone();
 
                         move |result| {
                             match result {
-                                Ok(id) => {
-                                    let mut response_channels = response_channels.lock().unwrap();
+                                Ok(InboundRequest::Resent(id)) => {
+                                    log::info!("swap request {} was sent again", id);
                                     response_channels.insert(id, channel);
                                 }
+                                Ok(InboundRequest::New(id)) => {
+                                    if let Some(tracer) = tracer {
+                                        tracer.record_span(
+                                            id,
+                                            "network.handle-request",
+                                            received_at,
+                                        );
+                                    }
+                                    response_channels.insert(id, channel);
+                                    event_bus.publish(SwapEvent::Created { swap_id: id });
+                                    task_executor.spawn(
+                                        async move {
+                                            record_message(
+                                                &messages,
+                                                id,
+                                                db::Direction::Inbound,
+                                                &received,
+                                            )
+                                            .await
+                                        }
+                                        .unit_error()
+                                        .boxed()
+                                        .compat(),
+                                    );
+                                }
                                 Err(response) => channel.send(response).unwrap_or_else(|_| {
                                     log::debug!("failed to send response through channel")
                                 }),
@@ -420,23 +870,68 @@ impl<TSubstream> NetworkBehaviourEventProcess<BehaviourOutEvent> for ComitNode<T
     }
 }
 
+#[cfg(feature = "mdns")]
 impl<TSubstream> NetworkBehaviourEventProcess<libp2p::mdns::MdnsEvent> for ComitNode<TSubstream> {
     fn inject_event(&mut self, event: libp2p::mdns::MdnsEvent) {
         match event {
             MdnsEvent::Discovered(addresses) => {
                 for (peer, address) in addresses {
-                    log::trace!("discovered {} at {}", peer, address)
+                    log::trace!("discovered {} at {}", peer, address);
+                    self.discovered_addresses
+                        .entry(peer)
+                        .or_default()
+                        .insert(address);
                 }
             }
             MdnsEvent::Expired(addresses) => {
                 for (peer, address) in addresses {
-                    log::trace!("address {} of peer {} expired", address, peer)
+                    log::trace!("address {} of peer {} expired", address, peer);
+                    if let Some(discovered) = self.discovered_addresses.get_mut(&peer) {
+                        discovered.remove(&address);
+                    }
                 }
             }
         }
     }
 }
 
+impl<TSubstream> NetworkBehaviourEventProcess<PingEvent> for ComitNode<TSubstream> {
+    fn inject_event(&mut self, event: PingEvent) {
+        match event.result {
+            Ok(PingSuccess::Ping { rtt }) => {
+                log::trace!("ping to {} took {:?}", event.peer, rtt);
+                self.latencies.insert(event.peer, rtt);
+            }
+            Ok(PingSuccess::Pong) => {}
+            Err(e) => {
+                // A failing ping is usually the first sign of a dying
+                // connection, hence we don't report a stale latency.
+                log::debug!("ping to {} failed: {:?}", event.peer, e);
+                self.latencies.remove(&event.peer);
+            }
+        }
+    }
+}
+
+impl<TSubstream> NetworkBehaviourEventProcess<IdentifyEvent> for ComitNode<TSubstream> {
+    fn inject_event(&mut self, event: IdentifyEvent) {
+        match event {
+            IdentifyEvent::Received { peer_id, info, .. } => {
+                log::debug!("{} runs {}", peer_id, info.agent_version);
+                self.peer_infos.insert(peer_id, PeerInfo {
+                    agent_version: info.agent_version,
+                    protocol_version: info.protocol_version,
+                    listen_addresses: info.listen_addrs,
+                });
+            }
+            IdentifyEvent::Sent { .. } => {}
+            IdentifyEvent::Error { peer_id, error } => {
+                log::debug!("failed to identify {}: {:?}", peer_id, error);
+            }
+        }
+    }
+}
+
 fn rfc003_swap_request<AL: rfc003::Ledger, BL: rfc003::Ledger, AA: Asset, BA: Asset>(
     id: SwapId,
     alpha_ledger: AL,
@@ -460,3 +955,278 @@ fn rfc003_swap_request<AL: rfc003::Ledger, BL: rfc003::Ledger, AA: Asset, BA: As
         secret_hash: body.secret_hash,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::LoadRequest,
+        ethereum::EtherQuantity,
+        network::send_request::decode_response,
+        swap_protocols::ledger::{Bitcoin, Ethereum},
+    };
+    use futures_core::executor::block_on;
+    use spectral::prelude::*;
+    use std::{path::Path, str::FromStr};
+
+    #[derive(Clone, Copy, Debug)]
+    struct FailingDatabase;
+
+    #[async_trait]
+    impl<T: Send + 'static> Save<T> for FailingDatabase {
+        async fn save(&self, _: T) -> anyhow::Result<()> {
+            Err(anyhow::anyhow!("database is locked"))
+        }
+    }
+
+    impl Saver for FailingDatabase {}
+
+    fn context<DB>(db: DB, rates: Sqlite) -> RequestContext<DB, Sqlite> {
+        RequestContext {
+            db,
+            rates,
+            seed: Seed::from(*b"hello world, you are beautiful!!"),
+            derivation: None,
+            state_store: Arc::new(InMemoryStateStore::default()),
+            maintenance: Maintenance::default(),
+            clock_monitor: ClockMonitor::new(clock_skew::DEFAULT_MAX_SKEW_SECONDS),
+            unsaved_requests: Arc::new(AtomicU64::new(0)),
+            swap_counter: SwapCounter::default(),
+        }
+    }
+
+    #[test]
+    fn swap_is_declined_if_it_cannot_be_saved() {
+        let swap_id = SwapId::default();
+        let request = || {
+            serde_json::to_value(compliance::well_formed_request(swap_id))
+                .and_then(serde_json::from_value)
+                .unwrap()
+        };
+        let counterparty = PeerId::random();
+        let state_store = Arc::new(InMemoryStateStore::default());
+        let unsaved_requests = Arc::new(AtomicU64::new(0));
+
+        let response = block_on(handle_request(
+            RequestContext {
+                state_store: state_store.clone(),
+                unsaved_requests: unsaved_requests.clone(),
+                ..context(
+                    FailingDatabase,
+                    Sqlite::new(&Path::new(":memory:")).unwrap(),
+                )
+            },
+            counterparty.clone(),
+            request(),
+        ))
+        .unwrap_err();
+
+        let decline = decode_response::<Bitcoin, Ethereum>(swap_id, response)
+            .unwrap()
+            .unwrap_err();
+        assert_that(&decline.reason).is_equal_to(Some(SwapDeclineReason::InternalError));
+        assert_that(&unsaved_requests.load(Ordering::SeqCst)).is_equal_to(1);
+        assert_that(
+            &state_store
+                .get::<bob::State<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>(&swap_id)
+                .unwrap(),
+        )
+        .is_none();
+
+        // The peer sends the request again once the database is back.
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let retried = block_on(handle_request(
+            RequestContext {
+                state_store: state_store.clone(),
+                unsaved_requests: unsaved_requests.clone(),
+                ..context(db.clone(), db)
+            },
+            counterparty,
+            request(),
+        ));
+
+        assert_that(&retried).is_ok_containing(InboundRequest::New(swap_id));
+        assert_that(
+            &state_store
+                .get::<bob::State<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>(&swap_id)
+                .unwrap(),
+        )
+        .is_some();
+    }
+
+    #[test]
+    fn swap_is_not_saved_if_its_request_cannot_be() {
+        let swap_id = SwapId::default();
+        let request = || {
+            serde_json::to_value(compliance::well_formed_request(swap_id))
+                .and_then(serde_json::from_value)
+                .unwrap()
+        };
+        let handle = |db: Sqlite| {
+            block_on(handle_request(
+                context(db.clone(), db),
+                PeerId::random(),
+                request(),
+            ))
+        };
+        let other = Sqlite::new(&Path::new(":memory:")).unwrap();
+        handle(other.clone()).unwrap();
+        let saved: rfc003::Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity> =
+            block_on(other.load_request(&swap_id)).unwrap();
+
+        // Saving the request fails because there already is one for the swap.
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        block_on(Save::save(&db, saved)).unwrap();
+        handle(db.clone()).unwrap_err();
+
+        assert_that(&block_on(db::Retrieve::get(&db, &swap_id))).is_err();
+    }
+
+    #[test]
+    fn swap_is_declined_if_the_peer_reached_the_swap_limit() {
+        let swap_id = SwapId::default();
+        let request = serde_json::to_value(compliance::well_formed_request(swap_id))
+            .and_then(serde_json::from_value)
+            .unwrap();
+        let counterparty = PeerId::random();
+        let swap_counter = SwapCounter::new(Some(1));
+        swap_counter
+            .acquire(&counterparty, SwapId::default())
+            .unwrap()
+            .keep();
+
+        let response = block_on(handle_request(
+            RequestContext {
+                swap_counter: swap_counter.clone(),
+                ..context(
+                    FailingDatabase,
+                    Sqlite::new(&Path::new(":memory:")).unwrap(),
+                )
+            },
+            counterparty.clone(),
+            request,
+        ))
+        .unwrap_err();
+
+        let decline = decode_response::<Bitcoin, Ethereum>(swap_id, response)
+            .unwrap()
+            .unwrap_err();
+        assert_that(&decline.reason).is_equal_to(Some(SwapDeclineReason::TemporarilyUnavailable));
+        assert_that(&swap_counter.active_swaps().get(&counterparty)).is_equal_to(Some(&1));
+    }
+
+    #[test]
+    fn resent_request_waits_for_the_decision_on_the_original() {
+        let swap_id = SwapId::default();
+        let request = || {
+            serde_json::to_value(compliance::well_formed_request(swap_id))
+                .and_then(serde_json::from_value)
+                .unwrap()
+        };
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let context = context(db.clone(), db);
+        let counterparty = PeerId::random();
+
+        let original = block_on(handle_request(
+            context.clone(),
+            counterparty.clone(),
+            request(),
+        ));
+        let resent = block_on(handle_request(context, counterparty, request()));
+
+        assert_that(&original).is_ok_containing(InboundRequest::New(swap_id));
+        assert_that(&resent).is_ok_containing(InboundRequest::Resent(swap_id));
+    }
+
+    #[test]
+    fn resent_request_is_answered_with_the_original_response() {
+        let swap_id = SwapId::default();
+        let request = || {
+            serde_json::to_value(compliance::well_formed_request(swap_id))
+                .and_then(serde_json::from_value)
+                .unwrap()
+        };
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+        let context = context(db.clone(), db);
+        let counterparty = PeerId::random();
+
+        block_on(handle_request(
+            context.clone(),
+            counterparty.clone(),
+            request(),
+        ))
+        .unwrap();
+        let state = context
+            .state_store
+            .get::<bob::State<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>(&swap_id)
+            .unwrap()
+            .unwrap();
+        let decline = rfc003::Decline {
+            swap_id,
+            reason: Some(SwapDeclineReason::UnsatisfactoryRate),
+        };
+        let seed = SwapSecrets::new(context.seed.swap_seed(swap_id), None);
+        context.state_store.insert(
+            swap_id,
+            bob::State::declined(state.request(), decline, seed),
+        );
+
+        let response = block_on(handle_request(context, counterparty, request())).unwrap_err();
+
+        let decline = decode_response::<Bitcoin, Ethereum>(swap_id, response)
+            .unwrap()
+            .unwrap_err();
+        assert_that(&decline.reason).is_equal_to(Some(SwapDeclineReason::UnsatisfactoryRate));
+    }
+
+    struct Unserializable;
+
+    impl ToHeader for Unserializable {
+        fn to_header(&self) -> Result<Header, serde_json::Error> {
+            Err(serde::ser::Error::custom("cannot be a header"))
+        }
+    }
+
+    #[test]
+    fn unserializable_header_declines_without_panicking() {
+        let error = decline_response(
+            &Unserializable,
+            Some(SwapDeclineReason::TemporarilyUnavailable),
+        )
+        .unwrap_err();
+        let response = decline_with(
+            &Unserializable,
+            Some(SwapDeclineReason::TemporarilyUnavailable),
+        );
+
+        let decline = decode_response::<Bitcoin, Ethereum>(SwapId::default(), response)
+            .unwrap()
+            .unwrap_err();
+        assert_that(&error.name).is_equal_to("decision");
+        assert_that(&decline.reason).is_none();
+    }
+
+    #[test]
+    fn rate_of_the_request_is_saved_with_the_swap() {
+        let swap_id = SwapId::default();
+        let rate = Rate::from_str("0.025").unwrap();
+        // Validating the request strips the underscore of the optional header.
+        let request = compliance::well_formed_request(swap_id)
+            .with_header("rate", rate.to_header().unwrap());
+        let request = serde_json::to_value(request)
+            .and_then(serde_json::from_value)
+            .unwrap();
+        let db = Sqlite::new(&Path::new(":memory:")).unwrap();
+
+        let id = block_on(handle_request(
+            context(db.clone(), db.clone()),
+            PeerId::random(),
+            request,
+        ))
+        .unwrap();
+
+        let saved = block_on(db.requested_rate(&swap_id)).unwrap();
+        assert_that(&id).is_equal_to(InboundRequest::New(swap_id));
+        assert_that(&saved).is_equal_to(Some(rate));
+    }
+}
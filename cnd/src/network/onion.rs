@@ -0,0 +1,118 @@
+use libp2p::{core::multiaddr::Protocol, Multiaddr};
+
+const ONION3_PREFIX: &str = "/onion3/";
+const ONION3_ADDRESS_LENGTH: usize = 56;
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum InvalidAddressHint {
+    #[error("{0} is not an onion3 address of the form /onion3/<address>:<port>")]
+    Onion3(String),
+    #[error("invalid multiaddr: {0}")]
+    Multiaddr(String),
+}
+
+/// Parse an address hint, which may also start with `/onion3/<address>:<port>`.
+///
+/// The multiaddrs of our libp2p version do not know about version 3 onion
+/// services yet, such addresses are therefore turned into a DNS name that is
+/// resolved by the SOCKS5 proxy we dial through.
+pub fn parse_address_hint(address_hint: &str) -> Result<Multiaddr, InvalidAddressHint> {
+    if !address_hint.starts_with(ONION3_PREFIX) {
+        return address_hint
+            .parse()
+            .map_err(|e| InvalidAddressHint::Multiaddr(format!("{}", e)));
+    }
+
+    let onion3 = &address_hint[ONION3_PREFIX.len()..];
+    let (onion3, rest) = match onion3.find('/') {
+        Some(index) => onion3.split_at(index),
+        None => (onion3, ""),
+    };
+
+    let invalid = || InvalidAddressHint::Onion3(onion3.to_string());
+
+    let mut parts = onion3.splitn(2, ':');
+    let address = parts.next().ok_or_else(invalid)?.to_lowercase();
+    let port = parts
+        .next()
+        .and_then(|port| port.parse::<u16>().ok())
+        .ok_or_else(invalid)?;
+
+    if !is_onion3_address(&address) {
+        return Err(invalid());
+    }
+
+    format!("/dns4/{}.onion/tcp/{}{}", address, port, rest)
+        .parse()
+        .map_err(|e| InvalidAddressHint::Multiaddr(format!("{}", e)))
+}
+
+/// Format an address hint the way it was given to `parse_address_hint`.
+pub fn format_address_hint(address_hint: &Multiaddr) -> String {
+    let mut protocols = address_hint.iter();
+
+    let onion3 = match (protocols.next(), protocols.next()) {
+        (Some(Protocol::Dns4(name)), Some(Protocol::Tcp(port))) => match onion3_address(&name) {
+            Some(address) => format!("{}{}:{}", ONION3_PREFIX, address, port),
+            None => return address_hint.to_string(),
+        },
+        _ => return address_hint.to_string(),
+    };
+    let rest = protocols.collect::<Multiaddr>();
+
+    format!("{}{}", onion3, rest)
+}
+
+fn onion3_address(name: &str) -> Option<&str> {
+    if !name.ends_with(".onion") {
+        return None;
+    }
+    let address = &name[..name.len() - ".onion".len()];
+
+    if is_onion3_address(address) {
+        Some(address)
+    } else {
+        None
+    }
+}
+
+fn is_onion3_address(address: &str) -> bool {
+    address.len() == ONION3_ADDRESS_LENGTH
+        && address
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    const ONION3: &str = "vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd";
+
+    #[test]
+    fn onion3_address_hint_roundtrips_through_dns_name() {
+        let address_hint = format!("/onion3/{}:9939", ONION3);
+
+        let multiaddr = parse_address_hint(&address_hint).unwrap();
+
+        assert_that(&multiaddr.to_string()).is_equal_to(format!("/dns4/{}.onion/tcp/9939", ONION3));
+        assert_that(&format_address_hint(&multiaddr)).is_equal_to(address_hint);
+    }
+
+    #[test]
+    fn onion3_address_hint_without_port_is_invalid() {
+        let address_hint = format!("/onion3/{}", ONION3);
+
+        assert_that(&parse_address_hint(&address_hint)).is_err();
+    }
+
+    #[test]
+    fn other_address_hints_are_plain_multiaddrs() {
+        let address_hint = "/ip4/8.9.0.1/tcp/9999";
+
+        let multiaddr = parse_address_hint(address_hint).unwrap();
+
+        assert_that(&format_address_hint(&multiaddr)).is_equal_to(address_hint.to_string());
+    }
+}
@@ -0,0 +1,62 @@
+use futures_core::compat::Future01CompatExt;
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::TcpStream, timer::Timeout};
+
+/// Check whether a TCP connection to `address` can be established within
+/// `timeout`.
+///
+/// Only `/ip4/../tcp/..` and `/ip6/../tcp/..` addresses can be probed, all
+/// other addresses are reported as not dialable.
+pub async fn is_dialable(address: &Multiaddr, timeout: Duration) -> bool {
+    let socket_address = match socket_address(address) {
+        Some(socket_address) => socket_address,
+        None => return false,
+    };
+
+    Timeout::new(TcpStream::connect(&socket_address), timeout)
+        .compat()
+        .await
+        .is_ok()
+}
+
+fn socket_address(address: &Multiaddr) -> Option<SocketAddr> {
+    let mut protocols = address.iter();
+
+    let ip = match protocols.next()? {
+        Protocol::Ip4(ip) => IpAddr::V4(ip),
+        Protocol::Ip6(ip) => IpAddr::V6(ip),
+        _ => return None,
+    };
+    let port = match protocols.next()? {
+        Protocol::Tcp(port) => port,
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn tcp_addresses_can_be_probed() {
+        let address = "/ip4/127.0.0.1/tcp/9939".parse().unwrap();
+
+        assert_that(&socket_address(&address))
+            .is_some()
+            .is_equal_to(&"127.0.0.1:9939".parse::<SocketAddr>().unwrap());
+    }
+
+    #[test]
+    fn non_tcp_addresses_cannot_be_probed() {
+        let address = "/ip4/127.0.0.1/udp/9939".parse().unwrap();
+
+        assert_that(&socket_address(&address)).is_none();
+    }
+}
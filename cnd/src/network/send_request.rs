@@ -1,24 +1,29 @@
 use crate::{
+    db,
     libp2p_comit_ext::{FromHeader, ToHeader},
-    network::{ComitNode, DialInformation},
+    network::{record_message, DialInformation, Error, SwarmHandle},
     swap_protocols::{
         self,
         asset::Asset,
         rfc003::{
             self,
             messages::{Decision, SwapDeclineReason},
+            Rate,
         },
-        SwapProtocol,
+        SwapId, SwapProtocol,
     },
+    timestamp::Timestamp,
 };
 use futures::Future;
-use libp2p::{Swarm, Transport};
-use libp2p_comit::frame;
+use futures_core::{FutureExt, TryFutureExt};
+use libp2p_comit::{
+    frame::{self, Response},
+    Frame, FrameType, IntoFrame,
+};
 use serde::Deserialize;
-use std::{io, sync::Mutex};
-use tokio::{io::AsyncRead, prelude::AsyncWrite};
 
-/// Sends an RFC003 swap request to the peer node.
+/// Sends an RFC003 swap request to the peer node, along with the rate Alice
+/// expects if she gave one.
 pub trait SendRequest: Send + Sync + 'static {
     fn send_request<
         AL: swap_protocols::rfc003::Ledger,
@@ -29,19 +34,8 @@ pub trait SendRequest: Send + Sync + 'static {
         &self,
         peer_identity: DialInformation,
         request: swap_protocols::rfc003::messages::Request<AL, BL, AA, BA>,
-    ) -> Box<dyn Future<Item = rfc003::Response<AL, BL>, Error = RequestError> + Send>;
-}
-
-#[derive(Debug, thiserror::Error)]
-pub enum RequestError {
-    #[error("peer node had an internal error while processing the request")]
-    InternalError,
-    #[error("peer node produced an invalid response")]
-    InvalidResponse,
-    #[error("failed to establish a new connection to make the request")]
-    Connecting(io::ErrorKind),
-    #[error("unable to send the data on the existing connection")]
-    Connection,
+        rate: Option<Rate>,
+    ) -> Box<dyn Future<Item = rfc003::Response<AL, BL>, Error = Error> + Send>;
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,14 +43,7 @@ pub struct Reason {
     pub value: SwapDeclineReason,
 }
 
-impl<
-        TTransport: Transport + Send + 'static,
-        TSubstream: AsyncRead + AsyncWrite + Send + 'static,
-    > SendRequest for Mutex<Swarm<TTransport, ComitNode<TSubstream>>>
-where
-    <TTransport as Transport>::Listener: Send,
-    <TTransport as Transport>::Error: Send,
-{
+impl SendRequest for SwarmHandle {
     fn send_request<
         AL: swap_protocols::rfc003::Ledger,
         BL: swap_protocols::rfc003::Ledger,
@@ -66,84 +53,110 @@ where
         &self,
         dial_information: DialInformation,
         request: rfc003::Request<AL, BL, AA, BA>,
-    ) -> Box<dyn Future<Item = rfc003::Response<AL, BL>, Error = RequestError> + Send> {
+        rate: Option<Rate>,
+    ) -> Box<dyn Future<Item = rfc003::Response<AL, BL>, Error = Error> + Send> {
         let id = request.swap_id;
-        let request = build_swap_request(request)
+        let request = build_swap_request(request, rate)
             .expect("constructing a frame::OutoingRequest should never fail!");
+        let sent = Frame::new(
+            FrameType::Request,
+            serde_json::to_value(&request).expect("requests always serialize"),
+        );
+        let messages = self.messages.clone();
 
-        let response = {
-            let mut swarm = self.lock().unwrap();
-            log::debug!(
-                "Making swap request to {}: {:?}",
-                dial_information.clone(),
-                request
-            );
+        let response =
+            self.send_frame(dial_information.clone(), request)
+                .and_then(move |response| {
+                    let received = response.clone().into_frame();
 
-            swarm.send_request(dial_information.clone(), request)
-        };
+                    async move {
+                        record_message(&messages, id, db::Direction::Outbound, &sent).await;
+                        record_message(&messages, id, db::Direction::Inbound, &received).await;
 
-        let response =
-            response.then(move |result| match result {
-                Ok(mut response) => {
-                    let decision = response
-                        .take_header("decision")
-                        .map(Decision::from_header)
-                        .map_or(Ok(None), |x| x.map(Some))
-                        .map_err(|e| {
-                            log::error!(
-                                "Could not deserialize header in response {:?}: {}",
-                                response,
-                                e,
-                            );
-                            RequestError::InvalidResponse
-                        })?;
-
-                    match decision {
-                        Some(Decision::Accepted) => {
-                            match serde_json::from_value::<
-                                rfc003::messages::AcceptResponseBody<AL, BL>,
-                            >(response.body().clone())
-                            {
-                                Ok(body) => Ok(Ok(rfc003::Accept {
-                                    swap_id: id,
-                                    beta_ledger_refund_identity: body.beta_ledger_refund_identity,
-                                    alpha_ledger_redeem_identity: body.alpha_ledger_redeem_identity,
-                                })),
-                                Err(_e) => Err(RequestError::InvalidResponse),
-                            }
-                        }
-
-                        Some(Decision::Declined) => {
-                            match serde_json::from_value::<rfc003::messages::DeclineResponseBody>(
-                                response.body().clone(),
-                            ) {
-                                Ok(body) => Ok(Err(rfc003::Decline {
-                                    swap_id: id,
-                                    reason: body.reason,
-                                })),
-                                Err(_e) => Err(RequestError::InvalidResponse),
-                            }
-                        }
-
-                        None => Err(RequestError::InvalidResponse),
+                        Ok::<_, Error>(response)
                     }
-                }
-                Err(e) => {
-                    log::error!(
-                        "Unable to request over connection {:?}:{:?}",
-                        dial_information.clone(),
-                        e
-                    );
-                    Err(RequestError::Connection)
-                }
-            });
+                    .boxed()
+                    .compat()
+                });
+
+        let response = response.then(move |result| match result {
+            Ok(response) => decode_response(id, response.clone()).map_err(|e| {
+                log::warn!(
+                    "Received invalid response to swap request {}: {}, response was {:?}",
+                    id,
+                    e,
+                    response
+                );
+                Error::InvalidResponse
+            }),
+            Err(e) => {
+                log::error!(
+                    "Unable to request over connection {:?}:{:?}",
+                    dial_information.clone(),
+                    e
+                );
+                Err(e)
+            }
+        });
 
         Box::new(response)
     }
 }
 
-fn build_swap_request<AL: rfc003::Ledger, BL: rfc003::Ledger, AA: Asset, BA: Asset>(
+/// Why a response to a swap request is not acceptable.
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidResponse {
+    #[error("the decision header is missing")]
+    MissingDecision,
+    #[error("the decision header is malformed: {0}")]
+    MalformedDecision(serde_json::Error),
+    #[error("the body does not match the decision: {0}")]
+    MalformedBody(serde_json::Error),
+}
+
+/// Turn the response of the peer into either an accept or a decline, the
+/// decision header is required and the body has to match the decision.
+pub(crate) fn decode_response<AL: rfc003::Ledger, BL: rfc003::Ledger>(
+    swap_id: SwapId,
+    mut response: Response,
+) -> Result<rfc003::Response<AL, BL>, InvalidResponse> {
+    let decision = response
+        .take_header("decision")
+        .ok_or(InvalidResponse::MissingDecision)
+        .and_then(|header| {
+            Decision::from_header(header).map_err(InvalidResponse::MalformedDecision)
+        })?;
+
+    match decision {
+        Decision::Accepted => {
+            let body = serde_json::from_value::<rfc003::messages::AcceptResponseBody<AL, BL>>(
+                response.body().clone(),
+            )
+            .map_err(InvalidResponse::MalformedBody)?;
+
+            Ok(Ok(rfc003::Accept {
+                swap_id,
+                beta_ledger_refund_identity: body.beta_ledger_refund_identity,
+                alpha_ledger_redeem_identity: body.alpha_ledger_redeem_identity,
+            }))
+        }
+        Decision::Declined => {
+            let body = serde_json::from_value::<rfc003::messages::DeclineResponseBody>(
+                response.body().clone(),
+            )
+            .map_err(InvalidResponse::MalformedBody)?;
+
+            Ok(Err(rfc003::Decline {
+                swap_id,
+                reason: body.reason,
+            }))
+        }
+    }
+}
+
+pub(crate) fn build_swap_request<AL: rfc003::Ledger, BL: rfc003::Ledger, AA: Asset, BA: Asset>(
     request: rfc003::Request<AL, BL, AA, BA>,
+    rate: Option<Rate>,
 ) -> Result<frame::OutboundRequest, serde_json::Error> {
     let alpha_ledger_refund_identity = request.alpha_ledger_refund_identity;
     let beta_ledger_redeem_identity = request.beta_ledger_redeem_identity;
@@ -152,21 +165,85 @@ fn build_swap_request<AL: rfc003::Ledger, BL: rfc003::Ledger, AA: Asset, BA: Ass
     let secret_hash = request.secret_hash;
     let protocol = SwapProtocol::Rfc003(request.hash_function);
 
-    Ok(frame::OutboundRequest::new("SWAP")
+    let mut swap_request = frame::OutboundRequest::new("SWAP")
         .with_header("id", request.swap_id.to_header()?)
         .with_header("alpha_ledger", request.alpha_ledger.into().to_header()?)
         .with_header("beta_ledger", request.beta_ledger.into().to_header()?)
         .with_header("alpha_asset", request.alpha_asset.into().to_header()?)
         .with_header("beta_asset", request.beta_asset.into().to_header()?)
         .with_header("protocol", protocol.to_header()?)
-        .with_body(serde_json::to_value(rfc003::messages::RequestBody::<
-            AL,
-            BL,
-        > {
-            alpha_ledger_refund_identity,
-            beta_ledger_redeem_identity,
-            alpha_expiry,
-            beta_expiry,
-            secret_hash,
-        })?))
+        // Not mandatory, peers that predate it just do not learn our clock.
+        .with_header("_sent_at", Timestamp::now().to_header()?);
+
+    // Informative only, the assets are what Bob accepts or declines.
+    if let Some(rate) = rate {
+        swap_request = swap_request.with_header("_rate", rate.to_header()?);
+    }
+
+    let body = rfc003::messages::RequestBody::<AL, BL> {
+        alpha_ledger_refund_identity,
+        beta_ledger_redeem_identity,
+        alpha_expiry,
+        beta_expiry,
+        secret_hash,
+    };
+
+    Ok(swap_request.with_body(serde_json::to_value(body)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swap_protocols::ledger::{Bitcoin, Ethereum};
+    use libp2p_comit::frame::Header;
+    use spectral::prelude::*;
+
+    fn decode(response: Response) -> Result<rfc003::Response<Bitcoin, Ethereum>, InvalidResponse> {
+        decode_response(SwapId::default(), response)
+    }
+
+    #[test]
+    fn response_without_decision_is_invalid() {
+        let response = Response::empty().with_body(serde_json::json!({}));
+
+        assert_that(&decode(response)).is_err();
+    }
+
+    #[test]
+    fn response_with_unknown_decision_is_invalid() {
+        let response = Response::empty().with_header("decision", Header::with_str_value("maybe"));
+
+        assert_that(&decode(response)).is_err();
+    }
+
+    #[test]
+    fn accept_with_decline_body_is_invalid() {
+        let response = Response::empty()
+            .with_header("decision", Decision::Accepted.to_header().unwrap())
+            .with_body(serde_json::json!({ "reason": "unsatisfactory-rate" }));
+
+        assert_that(&decode(response)).is_err();
+    }
+
+    #[test]
+    fn decline_with_corrupted_reason_is_invalid() {
+        let response = Response::empty()
+            .with_header("decision", Decision::Declined.to_header().unwrap())
+            .with_body(serde_json::json!({ "reason": 42 }));
+
+        assert_that(&decode(response)).is_err();
+    }
+
+    #[test]
+    fn decline_without_reason_is_valid() {
+        let response = Response::empty()
+            .with_header("decision", Decision::Declined.to_header().unwrap())
+            .with_body(serde_json::json!({}));
+
+        assert_that(&decode(response))
+            .is_ok()
+            .is_err()
+            .map(|decline| &decline.reason)
+            .is_none();
+    }
 }
@@ -0,0 +1,250 @@
+use futures::{future, stream, Future};
+use futures_core::{
+    compat::Future01CompatExt,
+    future::{FutureExt, TryFutureExt},
+};
+use libp2p::{
+    core::{
+        multiaddr::Protocol,
+        transport::{ListenerEvent, TransportError},
+    },
+    Multiaddr, Transport,
+};
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+};
+use tokio::{
+    io::{read_exact, write_all},
+    net::TcpStream,
+};
+
+const VERSION: u8 = 0x05;
+const NO_AUTHENTICATION: u8 = 0x00;
+const CONNECT: u8 = 0x01;
+const SUCCEEDED: u8 = 0x00;
+
+const IPV4: u8 = 0x01;
+const DOMAIN_NAME: u8 = 0x03;
+const IPV6: u8 = 0x04;
+
+/// Dials TCP connections through a SOCKS5 proxy, e.g. Tor.
+///
+/// Host names are passed on to the proxy instead of being resolved locally,
+/// hence peers can be dialed on their onion address as
+/// `/dns4/<address>.onion/tcp/<port>`, which is what `/onion3` address hints
+/// are turned into. This transport never listens and does not support any
+/// address if no proxy is configured, combine it with a plain TCP transport to
+/// listen.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Socks5Dialer {
+    proxy: Option<SocketAddr>,
+}
+
+impl Socks5Dialer {
+    pub fn new(proxy: Option<SocketAddr>) -> Self {
+        Self { proxy }
+    }
+}
+
+impl Transport for Socks5Dialer {
+    type Output = TcpStream;
+    type Error = io::Error;
+    type Listener = stream::Empty<ListenerEvent<Self::ListenerUpgrade>, io::Error>;
+    type ListenerUpgrade = future::Empty<TcpStream, io::Error>;
+    type Dial = Box<dyn Future<Item = TcpStream, Error = io::Error> + Send>;
+
+    fn listen_on(self, addr: Multiaddr) -> Result<Self::Listener, TransportError<Self::Error>> {
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let destination = destination(&addr);
+
+        // Resolving an onion address locally would only fail after leaking it
+        // to the DNS server.
+        if is_onion(&addr) && (self.proxy.is_none() || destination.is_none()) {
+            return Err(TransportError::Other(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} can only be dialed as /dns4/<address>.onion/tcp/<port> through a SOCKS5 proxy",
+                    addr
+                ),
+            )));
+        }
+
+        let (proxy, (host, port)) = match (self.proxy, destination) {
+            (Some(proxy), Some(destination)) => (proxy, destination),
+            _ => return Err(TransportError::MultiaddrNotSupported(addr)),
+        };
+        let request = connect_request(&host, port).map_err(TransportError::Other)?;
+
+        log::debug!("Dialing {} through SOCKS5 proxy {}", addr, proxy);
+
+        Ok(Box::new(connect(proxy, request).boxed().compat()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Host {
+    Ip(IpAddr),
+    Domain(String),
+}
+
+fn destination(addr: &Multiaddr) -> Option<(Host, u16)> {
+    let mut protocols = addr.iter();
+
+    let host = match protocols.next()? {
+        Protocol::Ip4(ip) => Host::Ip(ip.into()),
+        Protocol::Ip6(ip) => Host::Ip(ip.into()),
+        Protocol::Dns4(name) | Protocol::Dns6(name) => Host::Domain(name.into_owned()),
+        _ => return None,
+    };
+    let port = match protocols.next()? {
+        Protocol::Tcp(port) => port,
+        _ => return None,
+    };
+
+    match protocols.next() {
+        Some(_) => None,
+        None => Some((host, port)),
+    }
+}
+
+fn is_onion(addr: &Multiaddr) -> bool {
+    match addr.iter().next() {
+        Some(Protocol::Dns4(name)) | Some(Protocol::Dns6(name)) => name.ends_with(".onion"),
+        _ => false,
+    }
+}
+
+fn connect_request(host: &Host, port: u16) -> io::Result<Vec<u8>> {
+    let mut request = vec![VERSION, CONNECT, 0x00];
+
+    match host {
+        Host::Ip(IpAddr::V4(ip)) => {
+            request.push(IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Host::Ip(IpAddr::V6(ip)) => {
+            request.push(IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Host::Domain(name) => {
+            if name.len() > 255 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("domain name {} is too long for SOCKS5", name),
+                ));
+            }
+            request.push(DOMAIN_NAME);
+            request.push(name.len() as u8);
+            request.extend_from_slice(name.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+
+    Ok(request)
+}
+
+async fn connect(proxy: SocketAddr, request: Vec<u8>) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect(&proxy).compat().await?;
+    stream.set_nodelay(true)?;
+
+    let (stream, _) = write_all(stream, [VERSION, 1, NO_AUTHENTICATION])
+        .compat()
+        .await?;
+    let (stream, method) = read_exact(stream, [0u8; 2]).compat().await?;
+    if method != [VERSION, NO_AUTHENTICATION] {
+        return Err(protocol_error(
+            "proxy requires an unsupported authentication method",
+        ));
+    }
+
+    let (stream, _) = write_all(stream, request).compat().await?;
+    let (stream, reply) = read_exact(stream, [0u8; 4]).compat().await?;
+    if reply[0] != VERSION {
+        return Err(protocol_error("proxy replied with an unexpected version"));
+    }
+    if reply[1] != SUCCEEDED {
+        return Err(protocol_error(&format!(
+            "proxy failed to connect with reply code {}",
+            reply[1]
+        )));
+    }
+
+    // The address the proxy bound is of no use to us but still needs to be
+    // read before the stream carries the data of the connection.
+    let (stream, bound_address_length) = match reply[3] {
+        IPV4 => (stream, 4),
+        IPV6 => (stream, 16),
+        DOMAIN_NAME => {
+            let (stream, length) = read_exact(stream, [0u8; 1]).compat().await?;
+            (stream, usize::from(length[0]))
+        }
+        _ => return Err(protocol_error("proxy replied with an unknown address type")),
+    };
+    let (stream, _) = read_exact(stream, vec![0u8; bound_address_length + 2])
+        .compat()
+        .await?;
+
+    Ok(stream)
+}
+
+fn protocol_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("SOCKS5: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn onion_addresses_are_resolved_by_the_proxy() {
+        let addr = "/dns4/3g2upl4pq6kufc4m.onion/tcp/9939".parse().unwrap();
+
+        let (host, port) = destination(&addr).unwrap();
+        let request = connect_request(&host, port).unwrap();
+
+        assert_that(&host).is_equal_to(Host::Domain("3g2upl4pq6kufc4m.onion".to_string()));
+        assert_eq!(&request[..5], &[VERSION, CONNECT, 0x00, DOMAIN_NAME, 22]);
+        assert_eq!(&request[5..27], &b"3g2upl4pq6kufc4m.onion"[..]);
+        assert_eq!(&request[27..], &9939u16.to_be_bytes());
+    }
+
+    #[test]
+    fn only_tcp_addresses_are_dialed() {
+        let udp = "/ip4/127.0.0.1/udp/9939".parse().unwrap();
+        let ip = "/ip6/::1/tcp/9939".parse().unwrap();
+
+        assert_that(&destination(&udp)).is_none();
+        assert_that(&destination(&ip))
+            .is_some()
+            .is_equal_to((Host::Ip("::1".parse().unwrap()), 9939));
+    }
+
+    #[test]
+    fn nothing_is_dialed_without_proxy() {
+        let addr = "/ip4/127.0.0.1/tcp/9939".parse().unwrap();
+
+        let dial = Socks5Dialer::new(None).dial(addr);
+
+        assert!(match dial {
+            Err(TransportError::MultiaddrNotSupported(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn onion_addresses_are_never_resolved_locally() {
+        let addr = "/dns4/3g2upl4pq6kufc4m.onion/tcp/9939".parse().unwrap();
+
+        let dial = Socks5Dialer::new(None).dial(addr);
+
+        assert!(match dial {
+            Err(TransportError::Other(_)) => true,
+            _ => false,
+        });
+    }
+}
@@ -0,0 +1,175 @@
+use crate::swap_protocols::{SwapEvent, SwapId};
+use futures::{sync::mpsc::UnboundedReceiver, Future, Stream};
+use libp2p::PeerId;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("the peer reached the limit of concurrent swaps")]
+pub struct SwapLimitReached;
+
+/// Keeps track of the swaps peers requested from us that are not over yet
+/// across all clones, such that a single peer cannot make us keep the state of
+/// arbitrarily many swaps. A limit that is not set is not enforced.
+///
+/// Not persisted, swaps loaded from the database after a restart do not count.
+#[derive(Clone, Debug, Default)]
+pub struct SwapCounter {
+    max_swaps_per_peer: Option<usize>,
+    active: Arc<Mutex<HashMap<PeerId, HashSet<SwapId>>>>,
+}
+
+impl SwapCounter {
+    pub fn new(max_swaps_per_peer: Option<usize>) -> Self {
+        Self {
+            max_swaps_per_peer,
+            active: Arc::default(),
+        }
+    }
+
+    /// Count `swap_id` towards the swaps of `peer` until it is over. The slot
+    /// is freed right away if it is dropped without being kept.
+    pub fn acquire(&self, peer: &PeerId, swap_id: SwapId) -> Result<SwapSlot, SwapLimitReached> {
+        let mut active = self.active.lock().unwrap();
+        let swaps = active.get(peer);
+
+        if swaps.map_or(false, |swaps| swaps.contains(&swap_id)) {
+            // Someone else holds the slot of this swap already.
+            return Ok(SwapSlot {
+                counter: self.clone(),
+                swap_id,
+                kept: true,
+            });
+        }
+
+        if self
+            .max_swaps_per_peer
+            .map_or(false, |max| swaps.map_or(0, HashSet::len) >= max)
+        {
+            return Err(SwapLimitReached);
+        }
+
+        active.entry(peer.clone()).or_default().insert(swap_id);
+
+        Ok(SwapSlot {
+            counter: self.clone(),
+            swap_id,
+            kept: false,
+        })
+    }
+
+    /// Stop counting `swap_id`, whichever peer it was requested by.
+    pub fn release(&self, swap_id: &SwapId) {
+        let mut active = self.active.lock().unwrap();
+
+        for swaps in active.values_mut() {
+            swaps.remove(swap_id);
+        }
+        active.retain(|_, swaps| !swaps.is_empty());
+    }
+
+    /// The number of swaps that are not over yet per peer, peers without any
+    /// are left out.
+    pub fn active_swaps(&self) -> HashMap<PeerId, usize> {
+        self.active
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(peer, swaps)| (peer.clone(), swaps.len()))
+            .collect()
+    }
+}
+
+#[must_use]
+#[derive(Debug)]
+pub struct SwapSlot {
+    counter: SwapCounter,
+    swap_id: SwapId,
+    kept: bool,
+}
+
+impl SwapSlot {
+    /// Keep counting the swap until `release_finished_swaps` sees it end.
+    pub fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for SwapSlot {
+    fn drop(&mut self) {
+        if !self.kept {
+            self.counter.release(&self.swap_id);
+        }
+    }
+}
+
+/// Release the slots of swaps once they were declined, finished or failed.
+pub fn release_finished_swaps(
+    counter: SwapCounter,
+    events: UnboundedReceiver<SwapEvent>,
+) -> impl Future<Item = (), Error = ()> {
+    events.for_each(move |event| {
+        match event {
+            SwapEvent::Declined { swap_id }
+            | SwapEvent::Finished { swap_id }
+            | SwapEvent::Failed { swap_id, .. } => counter.release(&swap_id),
+            _ => {}
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swap_protocols::{EventBus, SwapEvents};
+    use spectral::prelude::*;
+
+    #[test]
+    fn swaps_per_peer_are_limited() {
+        let counter = SwapCounter::new(Some(1));
+        let peer = PeerId::random();
+
+        counter.acquire(&peer, SwapId::default()).unwrap().keep();
+
+        assert_that(&counter.acquire(&peer, SwapId::default())).is_err();
+        assert_that(&counter.acquire(&PeerId::random(), SwapId::default())).is_ok();
+    }
+
+    #[test]
+    fn slots_that_are_not_kept_are_freed_when_dropped() {
+        let counter = SwapCounter::new(Some(1));
+        let peer = PeerId::random();
+
+        drop(counter.acquire(&peer, SwapId::default()).unwrap());
+
+        assert_that(&counter.active_swaps()).is_empty();
+        assert_that(&counter.acquire(&peer, SwapId::default())).is_ok();
+    }
+
+    #[test]
+    fn kept_slots_are_freed_once_the_swap_is_over() {
+        let counter = SwapCounter::new(None);
+        let event_bus = EventBus::default();
+        let events = event_bus.subscribe();
+        let peer = PeerId::random();
+        let (finished, declined) = (SwapId::default(), SwapId::default());
+
+        counter.acquire(&peer, finished).unwrap().keep();
+        counter.acquire(&peer, declined).unwrap().keep();
+        counter.acquire(&peer, SwapId::default()).unwrap().keep();
+        event_bus.publish(SwapEvent::Accepted { swap_id: finished });
+        event_bus.publish(SwapEvent::Finished { swap_id: finished });
+        event_bus.publish(SwapEvent::Declined { swap_id: declined });
+        drop(event_bus);
+
+        release_finished_swaps(counter.clone(), events)
+            .wait()
+            .unwrap();
+
+        assert_that(&counter.active_swaps().get(&peer)).is_equal_to(Some(&1));
+    }
+}
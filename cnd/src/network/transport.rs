@@ -1,3 +1,7 @@
+use crate::network::{
+    connection_limits::{ConnectionCounter, ConnectionLimits, LimitedMuxer, LimitedStream},
+    socks5::Socks5Dialer,
+};
 use libp2p::{
     core::{
         muxing::{StreamMuxer, StreamMuxerBox},
@@ -10,15 +14,19 @@ use libp2p::{
     tcp::TcpConfig,
     yamux, PeerId, Transport,
 };
-use std::{error, io, time::Duration};
+use std::{error, io, net::SocketAddr, time::Duration};
 
 /// Builds a libp2p transport with the following features:
 /// - TcpConnection
 /// - DNS name resolution
+/// - dialing through a SOCKS5 proxy if one is given
 /// - authentication via secio
 /// - multiplexing via yamux or mplex
+/// - refusing connections beyond the given limits, before their handshake
 pub fn build_comit_transport(
     keypair: identity::Keypair,
+    limits: ConnectionLimits,
+    socks5_proxy: Option<SocketAddr>,
 ) -> impl Transport<
     Output = (
         PeerId,
@@ -36,14 +44,30 @@ pub fn build_comit_transport(
 > + Clone {
     let transport = TcpConfig::new().nodelay(true);
     let transport = DnsConfig::new(transport);
+    // With a proxy, TCP addresses are never dialed directly and the plain
+    // transport is only used to listen.
+    let transport = Socks5Dialer::new(socks5_proxy).or_transport(transport);
+    let connections = ConnectionCounter::new(limits);
 
     transport
+        .and_then({
+            let connections = connections.clone();
+            move |stream, _| {
+                connections
+                    .acquire()
+                    .map(|slot| LimitedStream::new(stream, slot))
+            }
+        })
         .upgrade(Version::V1)
         .authenticate(SecioConfig::new(keypair))
         .multiplex(SelectUpgrade::new(
             yamux::Config::default(),
             MplexConfig::new(),
         ))
-        .map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer)))
+        .and_then(move |(peer, muxer), _| {
+            connections
+                .acquire_for_peer(&peer)
+                .map(|slot| (peer, StreamMuxerBox::new(LimitedMuxer::new(muxer, slot))))
+        })
         .timeout(Duration::from_secs(20))
 }
@@ -0,0 +1,146 @@
+use crate::{
+    config,
+    logging::{RotatingFile, DEFAULT_MAX_FILES, DEFAULT_MAX_FILE_SIZE},
+};
+use chrono::Utc;
+use libp2p_comit::{
+    frame::{Direction, FrameObserver},
+    Frame,
+};
+use serde_json::Value as JsonValue;
+use std::{
+    io::{self, Write},
+    sync::Mutex,
+};
+
+const REDACTED: &str = "<redacted>";
+
+/// Appends every COMIT frame that is sent or received as a line of JSON to a
+/// file, which is rotated once it grows beyond its maximum size.
+///
+/// Secrets and identities are redacted, everything else is kept as it was on
+/// the wire so that the log can be compared with the one of the peer.
+#[derive(Debug)]
+pub struct WireLog {
+    file: Mutex<RotatingFile>,
+}
+
+impl WireLog {
+    pub fn open(config: &config::WireLog) -> io::Result<Self> {
+        let file = RotatingFile::open(
+            &config.path,
+            config.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+            config.max_files.unwrap_or(DEFAULT_MAX_FILES),
+            None,
+        )?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl FrameObserver for WireLog {
+    fn observe(&self, direction: Direction, frame: &Frame) {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "direction": direction.to_string(),
+            "frame": {
+                "type": frame.frame_type,
+                "payload": redact(frame.payload.clone()),
+            },
+        });
+
+        let mut line = entry.to_string().into_bytes();
+        line.push(b'\n');
+
+        let mut file = self.file.lock().expect("no other thread panicked");
+        if let Err(e) = file.write_all(&line) {
+            log::warn!("failed to write frame to {}: {}", file.path().display(), e)
+        }
+    }
+}
+
+fn redact(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(object) => JsonValue::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    if is_sensitive(&key) {
+                        (key, JsonValue::String(REDACTED.to_string()))
+                    } else {
+                        (key, redact(value))
+                    }
+                })
+                .collect(),
+        ),
+        JsonValue::Array(values) => JsonValue::Array(values.into_iter().map(redact).collect()),
+        value => value,
+    }
+}
+
+/// The secret hash is public once the HTLCs are deployed but links the log to
+/// the swap on both ledgers, just like the identities do.
+fn is_sensitive(key: &str) -> bool {
+    key == "secret" || key == "secret_hash" || key.ends_with("_identity")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::rotated_path;
+    use libp2p_comit::FrameType;
+    use spectral::prelude::*;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn secrets_and_identities_are_redacted() {
+        let payload = serde_json::json!({
+            "type": "SWAP",
+            "headers": { "alpha_ledger": { "value": "bitcoin" } },
+            "body": {
+                "alpha_ledger_refund_identity": "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275",
+                "beta_ledger_redeem_identity": "0x8457037fcd80a8650c4692d7fcfc1d0a96b92867",
+                "alpha_expiry": 2_000_000_000,
+                "secret_hash": "68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4cec",
+            },
+        });
+
+        assert_that(&redact(payload)).is_equal_to(serde_json::json!({
+            "type": "SWAP",
+            "headers": { "alpha_ledger": { "value": "bitcoin" } },
+            "body": {
+                "alpha_ledger_refund_identity": REDACTED,
+                "beta_ledger_redeem_identity": REDACTED,
+                "alpha_expiry": 2_000_000_000,
+                "secret_hash": REDACTED,
+            },
+        }));
+    }
+
+    #[test]
+    fn log_file_is_rotated_once_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wire.log");
+        let wire_log = WireLog::open(&config::WireLog {
+            path: path.clone(),
+            max_file_size: Some(1),
+            max_files: Some(2),
+        })
+        .unwrap();
+
+        for _ in 0..4 {
+            wire_log.observe(
+                Direction::Inbound,
+                &Frame::new(FrameType::Request, JsonValue::Null),
+            );
+        }
+
+        let lines = |path: &Path| fs::read_to_string(path).unwrap().lines().count();
+        assert_that(&lines(&path)).is_equal_to(1);
+        assert_that(&lines(&rotated_path(&path, 1))).is_equal_to(1);
+        assert_that(&lines(&rotated_path(&path, 2))).is_equal_to(1);
+        assert_that(&rotated_path(&path, 3).exists()).is_false();
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    db::Sqlite,
+    network::{ComitNode, DialInformation, Error, Network, PeerInfo, PeerStatus},
+    sharded_map::ShardedMap,
+    swap_protocols::SwapId,
+};
+use async_trait::async_trait;
+use futures::{
+    future,
+    sync::{mpsc, oneshot},
+    Async, Future, Poll, Stream,
+};
+use futures_core::compat::Future01CompatExt;
+use libp2p::{
+    core::muxing::{StreamMuxer, SubstreamRef},
+    Multiaddr, PeerId, Swarm, Transport,
+};
+use libp2p_comit::{
+    frame::{OutboundRequest, Response},
+    RequestKind, RequestMetrics, Unrecognized,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::timer::Timeout;
+
+/// How long to wait for a peer to respond to a request, including the time
+/// needed to connect to it.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The commands the `SwarmWorker` executes on behalf of a `SwarmHandle`.
+#[derive(Debug)]
+enum Command {
+    GetPeers(oneshot::Sender<Vec<(PeerId, Vec<Multiaddr>)>>),
+    GetListenAddresses(oneshot::Sender<Vec<Multiaddr>>),
+    GetPeerStatus(PeerId, oneshot::Sender<PeerStatus>),
+    GetLatencies(oneshot::Sender<Vec<(PeerId, Duration)>>),
+    GetUnrecognized(oneshot::Sender<BTreeMap<Unrecognized, u64>>),
+    GetRequestMetrics(oneshot::Sender<BTreeMap<RequestKind, RequestMetrics>>),
+    GetUnsavedRequests(oneshot::Sender<u64>),
+    GetActiveSwaps(oneshot::Sender<HashMap<PeerId, usize>>),
+    GetPeerInfos(oneshot::Sender<HashMap<PeerId, PeerInfo>>),
+    SendRequest {
+        dial_information: DialInformation,
+        request: OutboundRequest,
+        response: oneshot::Sender<Result<Response, Error>>,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("the swarm worker is not running anymore")]
+pub struct WorkerGone;
+
+/// Drives the libp2p `Swarm` and is the only one that has access to it.
+///
+/// Everybody else interacts with the swarm by sending commands through a
+/// `SwarmHandle`, hence nobody has to lock the swarm.
+#[allow(missing_debug_implementations)]
+pub struct SwarmWorker<TTransport: Transport, TMuxer: StreamMuxer + Send + Sync + 'static> {
+    swarm: Swarm<TTransport, ComitNode<SubstreamRef<Arc<TMuxer>>>>,
+    commands: mpsc::UnboundedReceiver<Command>,
+}
+
+/// A cheaply cloneable handle to the `SwarmWorker`.
+#[derive(Clone, Debug)]
+pub struct SwarmHandle {
+    commands: mpsc::UnboundedSender<Command>,
+    response_channels: Arc<ShardedMap<SwapId, oneshot::Sender<Response>>>,
+    pub(super) messages: Sqlite,
+}
+
+impl<TTransport, TMuxer> SwarmWorker<TTransport, TMuxer>
+where
+    TTransport: Transport,
+    TMuxer: StreamMuxer + Send + Sync + 'static,
+{
+    pub fn new(
+        swarm: Swarm<TTransport, ComitNode<SubstreamRef<Arc<TMuxer>>>>,
+    ) -> (Self, SwarmHandle) {
+        let (sender, receiver) = mpsc::unbounded();
+        let handle = SwarmHandle {
+            commands: sender,
+            response_channels: Arc::clone(&swarm.response_channels),
+            messages: swarm.sqlite.clone(),
+        };
+
+        (
+            Self {
+                swarm,
+                commands: receiver,
+            },
+            handle,
+        )
+    }
+}
+
+impl<TTransport, TMuxer> SwarmWorker<TTransport, TMuxer>
+where
+    TTransport: Transport<Output = (PeerId, TMuxer)> + Clone + Send + Sync + 'static,
+    TMuxer: StreamMuxer + Send + Sync + 'static,
+    <TMuxer as StreamMuxer>::OutboundSubstream: Send + 'static,
+    <TMuxer as StreamMuxer>::Substream: Send + Sync + 'static,
+    <TTransport as Transport>::Dial: Send,
+    <TTransport as Transport>::Error: Send,
+    <TTransport as Transport>::Listener: Send,
+    <TTransport as Transport>::ListenerUpgrade: Send,
+{
+    fn handle_command(&mut self, command: Command) {
+        // The requester may have given up already, in which case there is no one
+        // to reply to.
+        match command {
+            Command::GetPeers(reply) => {
+                let _ = reply.send(self.swarm.comit.connected_peers().collect());
+            }
+            Command::GetListenAddresses(reply) => {
+                let _ = reply.send(
+                    Swarm::listeners(&self.swarm)
+                        .chain(Swarm::external_addresses(&self.swarm))
+                        .cloned()
+                        .collect(),
+                );
+            }
+            Command::GetPeerStatus(peer_id, reply) => {
+                let _ = reply.send(self.swarm.peer_status(&peer_id));
+            }
+            Command::GetLatencies(reply) => {
+                let _ = reply.send(self.swarm.latencies());
+            }
+            Command::GetUnrecognized(reply) => {
+                let _ = reply.send(self.swarm.unrecognized());
+            }
+            Command::GetRequestMetrics(reply) => {
+                let _ = reply.send(self.swarm.request_metrics());
+            }
+            Command::GetUnsavedRequests(reply) => {
+                let _ = reply.send(self.swarm.unsaved_requests());
+            }
+            Command::GetActiveSwaps(reply) => {
+                let _ = reply.send(self.swarm.active_swaps());
+            }
+            Command::GetPeerInfos(reply) => {
+                let _ = reply.send(self.swarm.peer_infos());
+            }
+            Command::SendRequest {
+                dial_information,
+                request,
+                response,
+            } => {
+                log::debug!(
+                    "Making swap request to {}: {:?}",
+                    dial_information.clone(),
+                    request
+                );
+
+                let response_future =
+                    self.swarm
+                        .send_request(dial_information, request)
+                        .then(move |result| {
+                            let _ = response.send(result);
+                            Ok(())
+                        });
+
+                self.swarm.task_executor.spawn(response_future);
+            }
+        }
+    }
+}
+
+impl<TTransport, TMuxer> Future for SwarmWorker<TTransport, TMuxer>
+where
+    TTransport: Transport<Output = (PeerId, TMuxer)> + Clone + Send + Sync + 'static,
+    TMuxer: StreamMuxer + Send + Sync + 'static,
+    <TMuxer as StreamMuxer>::OutboundSubstream: Send + 'static,
+    <TMuxer as StreamMuxer>::Substream: Send + Sync + 'static,
+    <TTransport as Transport>::Dial: Send,
+    <TTransport as Transport>::Error: Send,
+    <TTransport as Transport>::Listener: Send,
+    <TTransport as Transport>::ListenerUpgrade: Send,
+{
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        // Once all handles are dropped there are no more commands to process,
+        // but the swarm still needs to be driven to answer inbound requests.
+        while let Ok(Async::Ready(Some(command))) = self.commands.poll() {
+            self.handle_command(command);
+        }
+
+        loop {
+            match self.swarm.poll() {
+                Ok(Async::Ready(Some(_))) => continue,
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(())),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(e) => {
+                    log::error!("failed with {:?}", e);
+                    return Err(());
+                }
+            }
+        }
+    }
+}
+
+impl SwarmHandle {
+    pub(crate) fn send_frame(
+        &self,
+        dial_information: DialInformation,
+        request: OutboundRequest,
+    ) -> impl Future<Item = Response, Error = Error> + Send {
+        let (sender, receiver) = oneshot::channel();
+
+        let command = Command::SendRequest {
+            dial_information,
+            request,
+            response: sender,
+        };
+
+        // Without a worker there is no connection the response could arrive on.
+        let response = future::result(self.commands.unbounded_send(command))
+            .map_err(|_| Error::ConnectionClosed)
+            .and_then(|()| receiver.map_err(|_| Error::ConnectionClosed))
+            .and_then(future::result);
+
+        Timeout::new(response, REQUEST_TIMEOUT)
+            .map_err(|e| e.into_inner().unwrap_or(Error::Timeout))
+    }
+
+    async fn query<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> Command,
+    ) -> anyhow::Result<T> {
+        let (sender, receiver) = oneshot::channel();
+
+        self.commands
+            .unbounded_send(command(sender))
+            .map_err(|_| WorkerGone)?;
+
+        let reply = receiver.compat().await.map_err(|_| WorkerGone)?;
+
+        Ok(reply)
+    }
+}
+
+#[async_trait]
+impl Network for SwarmHandle {
+    async fn comit_peers(&self) -> anyhow::Result<Vec<(PeerId, Vec<Multiaddr>)>> {
+        self.query(Command::GetPeers).await
+    }
+
+    async fn listen_addresses(&self) -> anyhow::Result<Vec<Multiaddr>> {
+        self.query(Command::GetListenAddresses).await
+    }
+
+    async fn peer_status(&self, peer_id: PeerId) -> anyhow::Result<PeerStatus> {
+        self.query(|reply| Command::GetPeerStatus(peer_id, reply))
+            .await
+    }
+
+    async fn latencies(&self) -> anyhow::Result<Vec<(PeerId, Duration)>> {
+        self.query(Command::GetLatencies).await
+    }
+
+    async fn unrecognized(&self) -> anyhow::Result<BTreeMap<Unrecognized, u64>> {
+        self.query(Command::GetUnrecognized).await
+    }
+
+    async fn request_metrics(&self) -> anyhow::Result<BTreeMap<RequestKind, RequestMetrics>> {
+        self.query(Command::GetRequestMetrics).await
+    }
+
+    async fn unsaved_requests(&self) -> anyhow::Result<u64> {
+        self.query(Command::GetUnsavedRequests).await
+    }
+
+    async fn active_swaps(&self) -> anyhow::Result<HashMap<PeerId, usize>> {
+        self.query(Command::GetActiveSwaps).await
+    }
+
+    async fn peer_infos(&self) -> anyhow::Result<HashMap<PeerId, PeerInfo>> {
+        self.query(Command::GetPeerInfos).await
+    }
+
+    fn pending_request_for(&self, swap: SwapId) -> Option<oneshot::Sender<Response>> {
+        self.response_channels.remove(&swap)
+    }
+}
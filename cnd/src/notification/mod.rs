@@ -0,0 +1,120 @@
+mod smtp;
+mod telegram;
+mod watch;
+
+pub use self::{smtp::Smtp, telegram::Telegram, watch::watch_swaps_periodically};
+
+use crate::{
+    config,
+    webhook::{Event, EventKind, Webhook},
+};
+use async_trait::async_trait;
+use std::{fmt::Debug, sync::Arc};
+
+/// Used if `notifications.expiry_warning_minutes` is not configured.
+pub const DEFAULT_EXPIRY_WARNING_MINUTES: u32 = 60;
+
+/// A channel that events are sent to in a form readable by humans.
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync + 'static {
+    async fn notify(&self, event: &Event) -> anyhow::Result<()>;
+}
+
+#[derive(Debug)]
+struct Subscription {
+    notifier: Box<dyn Notifier>,
+    /// All events if `None`.
+    events: Option<Vec<EventKind>>,
+}
+
+impl Subscription {
+    fn wants(&self, kind: EventKind) -> bool {
+        self.events
+            .as_ref()
+            .map_or(true, |events| events.contains(&kind))
+    }
+}
+
+/// Hands events to the webhook and to every notifier that subscribed to their
+/// kind.
+///
+/// Like for the webhook, delivery is best effort and failures are only
+/// logged.
+#[derive(Clone, Debug, Default)]
+pub struct Notifications {
+    webhook: Option<Webhook>,
+    subscriptions: Arc<Vec<Subscription>>,
+}
+
+impl Notifications {
+    pub fn new(webhook: Option<Webhook>, config: Option<&config::Notifications>) -> Self {
+        let mut subscriptions = Vec::new();
+
+        if let Some(config) = config {
+            if let Some(smtp) = &config.smtp {
+                subscriptions.push(Subscription {
+                    notifier: Box::new(Smtp::new(smtp.clone())),
+                    events: smtp.events.clone(),
+                });
+            }
+            if let Some(telegram) = &config.telegram {
+                subscriptions.push(Subscription {
+                    notifier: Box::new(Telegram::new(
+                        telegram.bot_token.clone(),
+                        telegram.chat_id.clone(),
+                    )),
+                    events: telegram.events.clone(),
+                });
+            }
+        }
+
+        Self {
+            webhook,
+            subscriptions: Arc::new(subscriptions),
+        }
+    }
+
+    pub async fn send(&self, event: Event) {
+        for subscription in self.subscriptions.iter() {
+            if !subscription.wants(event.kind()) {
+                continue;
+            }
+
+            if let Err(e) = subscription.notifier.notify(&event).await {
+                log::warn!(
+                    "failed to send {:?} to {:?}: {:?}",
+                    event,
+                    subscription.notifier,
+                    e
+                );
+            }
+        }
+
+        if let Some(webhook) = &self.webhook {
+            webhook.send(event).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn subscription_without_events_wants_all_of_them() {
+        let telegram = || Box::new(Telegram::new(String::from("token"), String::from("42")));
+        let all = Subscription {
+            notifier: telegram(),
+            events: None,
+        };
+        let failures = Subscription {
+            notifier: telegram(),
+            events: Some(vec![EventKind::SwapFailed]),
+        };
+
+        assert_that(&all.wants(EventKind::SwapCompleted)).is_true();
+        assert_that(&failures.wants(EventKind::SwapFailed)).is_true();
+        assert_that(&failures.wants(EventKind::SwapCompleted)).is_false();
+    }
+}
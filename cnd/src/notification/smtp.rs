@@ -0,0 +1,52 @@
+use crate::{config, notification::Notifier, webhook::Event};
+use async_trait::async_trait;
+use lettre::{smtp::authentication::Credentials, SmtpClient, Transport};
+use lettre_email::EmailBuilder;
+use std::fmt;
+
+/// Sends events by email, one email per event.
+#[derive(Clone)]
+pub struct Smtp {
+    config: config::Smtp,
+}
+
+impl Smtp {
+    pub fn new(config: config::Smtp) -> Self {
+        Self { config }
+    }
+}
+
+// Does not print the password.
+impl fmt::Debug for Smtp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Smtp")
+            .field("server", &self.config.server)
+            .field("to", &self.config.to)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Notifier for Smtp {
+    async fn notify(&self, event: &Event) -> anyhow::Result<()> {
+        let email = EmailBuilder::new()
+            .from(self.config.from.as_str())
+            .to(self.config.to.as_str())
+            .subject(format!("cnd: {}", event))
+            .text(serde_json::to_string_pretty(event)?)
+            .build()
+            .map_err(|e| anyhow::anyhow!("failed to build email: {:?}", e))?;
+        let config = self.config.clone();
+
+        // lettre only comes with a blocking transport.
+        crate::executor::spawn_blocking(move || -> anyhow::Result<()> {
+            let mut transport = SmtpClient::new_simple(&config.server)?
+                .credentials(Credentials::new(config.username, config.password))
+                .transport();
+            transport.send(email.into())?;
+
+            Ok(())
+        })
+        .await
+    }
+}
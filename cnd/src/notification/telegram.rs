@@ -0,0 +1,57 @@
+use crate::{notification::Notifier, webhook::Event};
+use async_trait::async_trait;
+use futures::Future;
+use futures_core::compat::Future01CompatExt;
+use reqwest::r#async::Client;
+use std::fmt;
+
+/// Sends events as messages of a Telegram bot to a chat.
+#[derive(Clone)]
+pub struct Telegram {
+    bot_token: String,
+    chat_id: String,
+    client: Client,
+}
+
+impl Telegram {
+    pub fn new(bot_token: String, chat_id: String) -> Self {
+        Self {
+            bot_token,
+            chat_id,
+            client: Client::new(),
+        }
+    }
+}
+
+// Does not print the bot token, it grants full control over the bot.
+impl fmt::Debug for Telegram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Telegram")
+            .field("chat_id", &self.chat_id)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Notifier for Telegram {
+    async fn notify(&self, event: &Event) -> anyhow::Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+
+        self.client
+            .post(&url)
+            .json(&serde_json::json!({
+                "chat_id": self.chat_id,
+                "text": event.to_string(),
+            }))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .compat()
+            .await
+            // The error would contain the URL and with it the bot token.
+            .map_err(|e| {
+                anyhow::anyhow!("request to Telegram failed with status {:?}", e.status())
+            })?;
+
+        Ok(())
+    }
+}
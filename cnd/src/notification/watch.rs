@@ -0,0 +1,265 @@
+use crate::{
+    db::{DetermineTypes, Retrieve, Swap, SwapFailures},
+    http_api::{swap_status, SwapStatus},
+    notification::Notifications,
+    swap_protocols::{
+        actions::Actions,
+        rfc003::{actions::ActionKind, state_store::StateStore, SwapCommunication},
+        SwapId,
+    },
+    timestamp::Timestamp,
+    webhook::Event,
+};
+use futures_core::compat::Future01CompatExt;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What the user needs to know about a swap at one point in time.
+#[derive(Clone, Debug, PartialEq)]
+struct Milestones {
+    /// The counterparty requested the swap and it was neither accepted nor
+    /// declined yet.
+    pending_request: bool,
+    actions: Vec<String>,
+    status: SwapStatus,
+    error: Option<String>,
+    alpha_expiry: Timestamp,
+    beta_expiry: Timestamp,
+}
+
+/// What the user was already notified about.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Notified {
+    request: bool,
+    actions: Vec<String>,
+    alpha_expiry: bool,
+    beta_expiry: bool,
+    outcome: bool,
+}
+
+/// Compare the swaps to what the user was already notified about once per
+/// `CHECK_INTERVAL` and send an event for every new milestone.
+///
+/// Requests and outcomes that predate the start of cnd are not notified
+/// about again, actions that are still required and expiries that are still
+/// approaching are.
+pub async fn watch_swaps_periodically<D>(
+    dependencies: D,
+    notifications: Notifications,
+    expiry_warning_secs: u32,
+) where
+    D: Retrieve + DetermineTypes + StateStore + SwapFailures,
+{
+    let mut notified = HashMap::<SwapId, Notified>::new();
+    let mut first_check = true;
+
+    loop {
+        match Retrieve::all(&dependencies).await {
+            Ok(swaps) => {
+                notified.retain(|swap_id, _| swaps.iter().any(|swap| swap.swap_id == *swap_id));
+
+                for swap in swaps {
+                    let milestones = match milestones(&dependencies, &swap).await {
+                        Ok(milestones) => milestones,
+                        Err(e) => {
+                            log::error!("failed to load swap {}: {:?}", swap.swap_id, e);
+                            continue;
+                        }
+                    };
+
+                    let notified = notified.entry(swap.swap_id).or_insert_with(|| Notified {
+                        request: first_check,
+                        outcome: first_check && milestones.status != SwapStatus::InProgress,
+                        ..Notified::default()
+                    });
+
+                    let now = Timestamp::now();
+                    for event in new_events(
+                        swap.swap_id,
+                        &milestones,
+                        notified,
+                        now,
+                        expiry_warning_secs,
+                    ) {
+                        notifications.send(event).await
+                    }
+                }
+            }
+            Err(e) => log::error!("failed to load swaps: {:?}", e),
+        }
+        first_check = false;
+
+        Delay::new(Instant::now() + CHECK_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
+
+async fn milestones<D>(dependencies: &D, swap: &Swap) -> anyhow::Result<Milestones>
+where
+    D: DetermineTypes + StateStore + SwapFailures,
+{
+    let id = swap.swap_id;
+    let types = DetermineTypes::determine_types(dependencies, &id).await?;
+    let failure = SwapFailures::swap_failure(dependencies, &id).await?;
+
+    with_swap_types!(types, {
+        let state = StateStore::get::<ROLE>(dependencies, &id)?
+            .ok_or_else(|| anyhow::anyhow!("state store did not contain an entry for {}", id))?;
+        let request = state.request();
+
+        let pending_request = match state.swap_communication {
+            SwapCommunication::Proposed { .. } => swap.role == Role::Bob,
+            _ => false,
+        };
+        let status = swap_status(&state, &failure);
+        let actions = state
+            .actions()
+            .iter()
+            .map(|action| ActionKind::from(action).to_string())
+            .collect();
+        let error = failure
+            .map(|failure| failure.message)
+            .or_else(|| state.error.as_ref().map(ToString::to_string));
+
+        Ok(Milestones {
+            pending_request,
+            actions,
+            status,
+            error,
+            alpha_expiry: request.alpha_expiry,
+            beta_expiry: request.beta_expiry,
+        })
+    })
+}
+
+fn new_events(
+    swap_id: SwapId,
+    milestones: &Milestones,
+    notified: &mut Notified,
+    now: Timestamp,
+    expiry_warning_secs: u32,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    if milestones.pending_request && !notified.request {
+        notified.request = true;
+        events.push(Event::SwapRequestReceived { swap_id });
+    }
+
+    // Accepting and declining are covered by the request notification.
+    let actions = if milestones.pending_request {
+        Vec::new()
+    } else {
+        milestones.actions.clone()
+    };
+    if actions != notified.actions {
+        notified.actions = actions.clone();
+        if !actions.is_empty() {
+            events.push(Event::ActionRequired { swap_id, actions });
+        }
+    }
+
+    if milestones.status == SwapStatus::InProgress {
+        let approaching =
+            |expiry: Timestamp| now < expiry && now.plus(expiry_warning_secs) >= expiry;
+
+        if approaching(milestones.alpha_expiry) && !notified.alpha_expiry {
+            notified.alpha_expiry = true;
+            events.push(Event::ExpiryApproaching {
+                swap_id,
+                ledger: String::from("alpha"),
+                expiry: milestones.alpha_expiry,
+            });
+        }
+        if approaching(milestones.beta_expiry) && !notified.beta_expiry {
+            notified.beta_expiry = true;
+            events.push(Event::ExpiryApproaching {
+                swap_id,
+                ledger: String::from("beta"),
+                expiry: milestones.beta_expiry,
+            });
+        }
+    } else if !notified.outcome {
+        notified.outcome = true;
+        events.push(match milestones.status {
+            SwapStatus::Swapped => Event::SwapCompleted { swap_id },
+            _ => Event::SwapFailed {
+                swap_id,
+                reason: milestones
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| String::from("the swap did not happen")),
+            },
+        });
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    fn in_progress(now: Timestamp) -> Milestones {
+        Milestones {
+            pending_request: false,
+            actions: vec![String::from("fund")],
+            status: SwapStatus::InProgress,
+            error: None,
+            alpha_expiry: now.plus(48 * 60 * 60),
+            beta_expiry: now.plus(30 * 60),
+        }
+    }
+
+    #[test]
+    fn milestones_are_notified_once() {
+        let swap_id = SwapId::default();
+        let now = Timestamp::now();
+        let mut notified = Notified::default();
+        let milestones = in_progress(now);
+
+        let first = new_events(swap_id, &milestones, &mut notified, now, 60 * 60);
+        let second = new_events(swap_id, &milestones, &mut notified, now, 60 * 60);
+
+        assert_that(&first).is_equal_to(vec![
+            Event::ActionRequired {
+                swap_id,
+                actions: vec![String::from("fund")],
+            },
+            Event::ExpiryApproaching {
+                swap_id,
+                ledger: String::from("beta"),
+                expiry: milestones.beta_expiry,
+            },
+        ]);
+        assert_that(&second).is_empty();
+    }
+
+    #[test]
+    fn finished_swap_is_notified_with_its_error() {
+        let swap_id = SwapId::default();
+        let now = Timestamp::now();
+        let mut notified = Notified::default();
+        let milestones = Milestones {
+            actions: Vec::new(),
+            status: SwapStatus::InternalFailure,
+            error: Some(String::from("btsieve is down")),
+            ..in_progress(now)
+        };
+
+        let events = new_events(swap_id, &milestones, &mut notified, now, 60 * 60);
+
+        assert_that(&events).is_equal_to(vec![Event::SwapFailed {
+            swap_id,
+            reason: String::from("btsieve is down"),
+        }]);
+    }
+}
@@ -0,0 +1,296 @@
+#![allow(clippy::type_repetition_in_bounds)]
+use crate::{
+    db::{
+        DetermineTypes, LoadRequest, Outbox, PendingRequest, RequestedRates, Retention, Save,
+        SwapFailures,
+    },
+    ethereum::{Erc20Token, EtherQuantity},
+    network::{self, DialInformation, SendRequest},
+    seed::SwapSeed,
+    swap_protocols::{
+        self,
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{
+            self, alice::State, fees::PaidFees, state_store::StateStore, Accept, Decline, Ledger,
+            Rate, Request,
+        },
+        FeeAccounting, LedgerEventsCreator, Role, SwapEvent, SwapEvents, SwapTasks,
+    },
+    timestamp::Timestamp,
+    CreateLedgerEvents,
+};
+use futures::{sync::mpsc, Future};
+use futures_core::{
+    compat::{Future01CompatExt, Stream01CompatExt},
+    future::{FutureExt, TryFutureExt},
+    stream::StreamExt,
+};
+use std::{
+    cmp,
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+use tokio::{executor::Executor, timer::Delay};
+
+/// How long to wait before sending a request again that did not reach the
+/// peer, doubled after every attempt up to `MAX_RETRY_DELAY`.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Send the swap requests in the outbox to their peers.
+///
+/// The outbox is drained when cnd starts, which picks up requests that were
+/// saved right before a crash, and whenever a swap is created. A request that
+/// does not reach the peer is sent again with an increasing delay until the
+/// peer answers it or the swap expires. The peer answers a request it got
+/// before the way it did the first time, hence sending it again is safe.
+pub async fn dispatch_requests<D>(dependencies: D, events: mpsc::UnboundedReceiver<SwapEvent>)
+where
+    D: StateStore
+        + Executor
+        + Clone
+        + SendRequest
+        + SwapSeed
+        + LedgerEventsCreator
+        + Outbox
+        + RequestedRates
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + Save<Accept<Bitcoin, Ethereum>>
+        + Save<Accept<Ethereum, Bitcoin>>
+        + Save<Decline>
+        + LoadRequest<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadRequest<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadRequest<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadRequest<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let mut in_flight = HashSet::new();
+    let mut events = events.compat();
+
+    loop {
+        match Outbox::pending_requests(&dependencies).await {
+            Ok(pending) => {
+                in_flight
+                    .retain(|swap_id| pending.iter().any(|request| request.swap_id == *swap_id));
+
+                for request in pending {
+                    let swap_id = request.swap_id;
+                    if !in_flight.insert(swap_id) {
+                        continue;
+                    }
+
+                    if let Err(e) = dispatch(&dependencies, request).await {
+                        log::error!("failed to send swap request {}: {:?}", swap_id, e);
+                    }
+                }
+            }
+            Err(e) => log::error!("failed to load the outbox: {:?}", e),
+        }
+
+        loop {
+            match events.next().await {
+                Some(Ok(SwapEvent::Created { .. })) => break,
+                Some(Ok(_)) => continue,
+                Some(Err(_)) | None => return,
+            }
+        }
+    }
+}
+
+async fn dispatch<D>(dependencies: &D, request: PendingRequest) -> anyhow::Result<()>
+where
+    D: StateStore
+        + Executor
+        + Clone
+        + SendRequest
+        + SwapSeed
+        + LedgerEventsCreator
+        + Outbox
+        + RequestedRates
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + Save<Accept<Bitcoin, Ethereum>>
+        + Save<Accept<Ethereum, Bitcoin>>
+        + Save<Decline>
+        + LoadRequest<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadRequest<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadRequest<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadRequest<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let PendingRequest { swap_id, peer } = request;
+    let types = DetermineTypes::determine_types(dependencies, &swap_id).await?;
+    let rate = RequestedRates::requested_rate(dependencies, &swap_id).await?;
+
+    with_swap_types!(types, {
+        let request = LoadRequest::<AL, BL, AA, BA>::load_request(dependencies, &swap_id).await?;
+
+        send_request(dependencies.clone(), peer, request, rate)
+    })
+}
+
+fn send_request<D, AL, BL, AA, BA>(
+    dependencies: D,
+    peer: DialInformation,
+    swap_request: Request<AL, BL, AA, BA>,
+    rate: Option<Rate>,
+) -> anyhow::Result<()>
+where
+    D: StateStore
+        + Executor
+        + SendRequest
+        + SwapSeed
+        + Outbox
+        + Save<Accept<AL, BL>>
+        + Save<Decline>
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + PaidFees<AL>
+        + PaidFees<BL>
+        + SwapTasks
+        + SwapEvents
+        + CreateLedgerEvents<AL, AA>
+        + CreateLedgerEvents<BL, BA>
+        + Clone,
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+{
+    let id = swap_request.swap_id;
+    let seed = dependencies.swap_secrets(id, Role::Alice);
+
+    // The swap is not in the state store if cnd restarted since it was
+    // created.
+    let state = State::proposed(swap_request.clone(), seed);
+    StateStore::insert(&dependencies, id, state);
+
+    let future = {
+        let dependencies = dependencies.clone();
+
+        async move {
+            let response =
+                match send_until_answered(&dependencies, &peer, &swap_request, rate).await {
+                    Ok(response) => response,
+                    Err(e) => {
+                        log::error!("Failed to send swap request to {}: {}", peer, e);
+
+                        // The peer did answer, sending it again yields the same.
+                        if e == network::Error::InvalidResponse {
+                            Outbox::mark_sent(&dependencies, &id).await?;
+                        }
+
+                        let error = rfc003::Error::from(e);
+                        let reason = error.to_string();
+                        let state = State::failed(swap_request, error, seed);
+                        StateStore::insert(&dependencies, id, state);
+                        SwapEvents::publish(&dependencies, SwapEvent::Failed {
+                            swap_id: id,
+                            reason,
+                        });
+
+                        return Ok(());
+                    }
+                };
+            Outbox::mark_sent(&dependencies, &id).await?;
+
+            match response {
+                Ok(accept) => {
+                    Save::save(&dependencies, accept).await?;
+                    SwapEvents::publish(&dependencies, SwapEvent::Accepted { swap_id: id });
+
+                    swap_protocols::init_accepted_swap(
+                        &dependencies,
+                        swap_request,
+                        accept,
+                        Role::Alice,
+                    )?;
+                }
+                Err(decline) => {
+                    log::info!("Swap declined: {:?}", decline);
+                    let state = State::declined(swap_request.clone(), decline.clone(), seed);
+                    StateStore::insert(&dependencies, id, state);
+                    Save::save(&dependencies, decline.clone()).await?;
+                    SwapEvents::publish(&dependencies, SwapEvent::Declined { swap_id: id });
+                }
+            };
+            Ok(())
+        }
+    };
+    dependencies.spawn_swap_task(
+        id,
+        future.boxed().compat().map_err(|e: anyhow::Error| {
+            log::error!("{:?}", e);
+        }),
+    )?;
+
+    Ok(())
+}
+
+/// Send the request until the peer answers it, giving up if the answer is
+/// invalid or the request could not be answered before the swap expires.
+async fn send_until_answered<D, AL, BL, AA, BA>(
+    dependencies: &D,
+    peer: &DialInformation,
+    swap_request: &Request<AL, BL, AA, BA>,
+    rate: Option<Rate>,
+) -> Result<rfc003::Response<AL, BL>, network::Error>
+where
+    D: SendRequest + Outbox,
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+{
+    let id = swap_request.swap_id;
+    let gives_up_at = cmp::min(swap_request.alpha_expiry, swap_request.beta_expiry);
+    let mut retry_delay = INITIAL_RETRY_DELAY;
+
+    loop {
+        if let Err(e) = Outbox::record_attempt(dependencies, &id).await {
+            log::warn!(
+                "failed to record attempt to send swap request {}: {:?}",
+                id,
+                e
+            );
+        }
+
+        let error = match dependencies
+            .send_request(peer.clone(), swap_request.clone(), rate.clone())
+            .compat()
+            .await
+        {
+            Ok(response) => return Ok(response),
+            Err(network::Error::InvalidResponse) => return Err(network::Error::InvalidResponse),
+            Err(e) => e,
+        };
+
+        let retry_at = Timestamp::now().plus(retry_delay.as_secs() as u32);
+        if retry_at >= gives_up_at {
+            return Err(error);
+        }
+
+        log::warn!(
+            "failed to send swap request {} to {}, retrying in {:?}: {}",
+            id,
+            peer,
+            retry_delay,
+            error
+        );
+
+        Delay::new(Instant::now() + retry_delay)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+        retry_delay = cmp::min(retry_delay * 2, MAX_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{btsieve::socks5_client, config, timestamp::Timestamp};
+use futures::Future;
+use futures_core::compat::Future01CompatExt;
+use reqwest::{r#async::Client, Url};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Used if `price_feed.currency` is not configured.
+pub const DEFAULT_CURRENCY: &str = "usd";
+
+/// How long fetched prices are used before they are fetched again.
+const MAX_QUOTE_AGE: Duration = Duration::from_secs(5 * 60);
+
+const BITCOIN_ID: &str = "bitcoin";
+const ETHER_ID: &str = "ethereum";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Coin {
+    Bitcoin,
+    Ether,
+}
+
+/// What an amount was worth according to the price feed when it was quoted.
+///
+/// Only meant to let operators sanity-check the magnitude of a swap, neither
+/// cnd nor the counterparty are bound by it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct IndicativeFiatValue {
+    /// Rounded to two decimals.
+    pub amount: String,
+    pub currency: String,
+    pub quoted_at: Timestamp,
+}
+
+/// The prices of the coins in the configured currency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quotes {
+    currency: String,
+    bitcoin: Option<f64>,
+    ether: Option<f64>,
+    quoted_at: Timestamp,
+}
+
+impl Quotes {
+    /// Reads the response of a CoinGecko compatible `simple/price` endpoint,
+    /// e.g. `{"bitcoin":{"usd":9000.0},"ethereum":{"usd":200.0}}`.
+    fn from_response(
+        currency: &str,
+        response: &HashMap<String, HashMap<String, f64>>,
+        quoted_at: Timestamp,
+    ) -> Self {
+        let price = |id: &str| {
+            response
+                .get(id)
+                .and_then(|prices| prices.get(currency))
+                .copied()
+        };
+
+        Self {
+            currency: currency.to_owned(),
+            bitcoin: price(BITCOIN_ID),
+            ether: price(ETHER_ID),
+            quoted_at,
+        }
+    }
+
+    /// `amount` is given in whole coins, `None` if the feed has no price for
+    /// `coin`.
+    pub fn value_of(&self, coin: Coin, amount: f64) -> Option<IndicativeFiatValue> {
+        let price = match coin {
+            Coin::Bitcoin => self.bitcoin,
+            Coin::Ether => self.ether,
+        }?;
+
+        Some(IndicativeFiatValue {
+            amount: format!("{:.2}", amount * price),
+            currency: self.currency.clone(),
+            quoted_at: self.quoted_at,
+        })
+    }
+}
+
+/// Fetches the prices of Bitcoin and Ether from the `[price_feed]`, does
+/// nothing if none is configured.
+#[derive(Clone, Debug, Default)]
+pub struct PriceFeed {
+    source: Option<Source>,
+    cached: Arc<Mutex<Option<(Instant, Quotes)>>>,
+}
+
+#[derive(Clone, Debug)]
+struct Source {
+    url: Url,
+    currency: String,
+    client: Client,
+}
+
+impl PriceFeed {
+    pub fn new(config: &config::PriceFeed) -> Result<Self, reqwest::UrlError> {
+        let mut base_url = config.url.clone();
+        // The API is usually served under a path, e.g. `/api/v3`, which would
+        // be replaced when joining if it does not end with a slash.
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+
+        let currency = config
+            .currency
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CURRENCY.to_owned())
+            .to_lowercase();
+        let mut url = base_url.join("simple/price")?;
+        url.query_pairs_mut()
+            .append_pair("ids", &format!("{},{}", BITCOIN_ID, ETHER_ID))
+            .append_pair("vs_currencies", &currency);
+
+        Ok(Self {
+            source: Some(Source {
+                url,
+                currency,
+                client: Client::new(),
+            }),
+            cached: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Send all requests to the price feed through the given SOCKS5 proxy.
+    pub fn with_socks5_proxy(self, proxy: SocketAddr) -> Result<Self, reqwest::Error> {
+        let client = socks5_client(proxy)?;
+
+        Ok(Self {
+            source: self.source.map(|source| Source { client, ..source }),
+            ..self
+        })
+    }
+
+    /// `None` if no price feed is configured or the prices cannot be fetched,
+    /// the values are left out then.
+    pub async fn quotes(&self) -> Option<Quotes> {
+        let source = self.source.as_ref()?;
+
+        if let Some((fetched_at, quotes)) = self.cached.lock().unwrap().as_ref() {
+            if fetched_at.elapsed() < MAX_QUOTE_AGE {
+                return Some(quotes.clone());
+            }
+        }
+
+        match fetch(source).await {
+            Ok(quotes) => {
+                *self.cached.lock().unwrap() = Some((Instant::now(), quotes.clone()));
+                Some(quotes)
+            }
+            Err(e) => {
+                log::warn!("failed to fetch prices from {}: {:?}", source.url, e);
+                None
+            }
+        }
+    }
+}
+
+async fn fetch(source: &Source) -> anyhow::Result<Quotes> {
+    let response = source
+        .client
+        .get(source.url.clone())
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.json::<HashMap<String, HashMap<String, f64>>>())
+        .compat()
+        .await?;
+
+    Ok(Quotes::from_response(
+        &source.currency,
+        &response,
+        Timestamp::now(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn values_are_quoted_in_the_configured_currency() {
+        let response = serde_json::from_str::<HashMap<String, HashMap<String, f64>>>(
+            r#"{"bitcoin":{"eur":8000.5},"ethereum":{"eur":150.25}}"#,
+        )
+        .unwrap();
+        let quotes = Quotes::from_response("eur", &response, Timestamp::from(1_000_000));
+
+        assert_that(&quotes.value_of(Coin::Bitcoin, 0.5)).is_equal_to(Some(IndicativeFiatValue {
+            amount: String::from("4000.25"),
+            currency: String::from("eur"),
+            quoted_at: Timestamp::from(1_000_000),
+        }));
+        assert_that(&quotes.value_of(Coin::Ether, 2.0).map(|value| value.amount))
+            .is_equal_to(Some(String::from("300.50")));
+    }
+
+    #[test]
+    fn coins_without_a_price_have_no_value() {
+        let response = serde_json::from_str::<HashMap<String, HashMap<String, f64>>>(
+            r#"{"bitcoin":{"usd":9000.0}}"#,
+        )
+        .unwrap();
+        let quotes = Quotes::from_response("usd", &response, Timestamp::from(1_000_000));
+
+        assert_that(&quotes.value_of(Coin::Ether, 1.0)).is_none();
+    }
+
+    #[test]
+    fn prices_are_requested_in_the_configured_currency() {
+        let price_feed = PriceFeed::new(&config::PriceFeed {
+            url: "https://api.coingecko.com/api/v3".parse().unwrap(),
+            currency: Some(String::from("EUR")),
+        })
+        .unwrap();
+
+        assert_that(&price_feed.source.unwrap().url.as_str()).is_equal_to(
+            "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin%2Cethereum&vs_currencies=eur",
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{
+    db::{Retention, RetentionPolicy},
+    swap_protocols::{rfc003::state_store::StateStore, SwapTasks},
+};
+use futures_core::compat::Future01CompatExt;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Archive finished swaps that are no longer covered by `policy`, cancel their
+/// remaining tasks and drop them from the state store, once per
+/// `PRUNE_INTERVAL`.
+pub async fn prune_swaps_periodically<D>(dependencies: D, policy: RetentionPolicy)
+where
+    D: Retention + StateStore + SwapTasks,
+{
+    loop {
+        let now = chrono::Utc::now().naive_utc();
+
+        match Retention::archive_finished_swaps(&dependencies, policy, now).await {
+            Ok(archived) => {
+                for swap_id in archived.iter() {
+                    SwapTasks::cancel_swap_tasks(&dependencies, swap_id);
+                    StateStore::remove(&dependencies, swap_id);
+                }
+                log::debug!("archived {} finished swaps", archived.len());
+            }
+            Err(e) => log::error!("failed to archive finished swaps: {:?}", e),
+        }
+
+        Delay::new(Instant::now() + PRUNE_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
@@ -0,0 +1,112 @@
+#![allow(clippy::type_repetition_in_bounds)]
+use crate::{
+    db::{DetermineTypes, LoadAcceptedSwap},
+    ethereum::{Erc20Token, EtherQuantity},
+    http_api::action::{ActionExecutionParameters, ActionResponseBody, IntoResponsePayload},
+    seed::SwapSeed,
+    swap_protocols::{
+        asset::Asset,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{
+            actions::RefundAction, events::FindHtlc, state_machine::HtlcParams, Ledger,
+            SecretSource,
+        },
+        HtlcFinder, SwapId,
+    },
+    timestamp::Timestamp,
+};
+use futures_core::compat::Future01CompatExt;
+use std::{convert::TryFrom, time::Duration};
+use tokio::timer::Timeout;
+
+/// How long to look for our HTLC on the ledger before giving up.
+///
+/// The scan walks back block by block to the time the swap was accepted,
+/// which can take a while for swaps that were accepted long ago.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Reconstruct the refund action of our HTLC.
+///
+/// Only the persisted request and accept messages are used, the HTLC itself is
+/// looked up on the ledger. Hence this works even if the node lost track of
+/// the swap, e.g. because it crashed before it saw the funding transaction.
+#[allow(clippy::cognitive_complexity)]
+pub async fn refund_action<D>(
+    dependencies: &D,
+    swap_id: SwapId,
+    parameters: ActionExecutionParameters,
+) -> anyhow::Result<ActionResponseBody>
+where
+    D: SwapSeed
+        + DetermineTypes
+        + HtlcFinder
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let types = DetermineTypes::determine_types(dependencies, &swap_id).await?;
+    let secret_source = SwapSeed::swap_secrets(dependencies, swap_id, types.role);
+
+    with_swap_types!(types, {
+        let (request, accept, accepted_at) =
+            LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(dependencies, &swap_id).await?;
+        let since = Timestamp::from(u32::try_from(accepted_at.timestamp())?);
+
+        match types.role {
+            Role::Alice => {
+                refund_htlc(
+                    dependencies,
+                    HtlcParams::new_alpha_params(&request, &accept),
+                    since,
+                    &secret_source,
+                    parameters,
+                )
+                .await
+            }
+            Role::Bob => {
+                refund_htlc(
+                    dependencies,
+                    HtlcParams::new_beta_params(&request, &accept),
+                    since,
+                    &secret_source,
+                    parameters,
+                )
+                .await
+            }
+        }
+    })
+}
+
+async fn refund_htlc<D, L, A>(
+    dependencies: &D,
+    htlc_params: HtlcParams<L, A>,
+    since: Timestamp,
+    secret_source: &dyn SecretSource,
+    parameters: ActionExecutionParameters,
+) -> anyhow::Result<ActionResponseBody>
+where
+    D: FindHtlc<L, A>,
+    L: Ledger,
+    A: Asset,
+    (L, A): RefundAction<L, A>,
+    <(L, A) as RefundAction<L, A>>::RefundActionOutput: IntoResponsePayload,
+{
+    let (deployed, funded) = Timeout::new(
+        dependencies.find_funded_htlc(htlc_params.clone(), since),
+        SCAN_TIMEOUT,
+    )
+    .compat()
+    .await
+    .map_err(|e| anyhow::anyhow!("failed to find the funded HTLC: {:?}", e))?;
+
+    let action = <(L, A)>::refund_action(
+        htlc_params,
+        deployed.location,
+        secret_source,
+        &funded.transaction,
+        &funded.top_ups,
+    );
+
+    action.into_response_payload(parameters)
+}
@@ -0,0 +1,126 @@
+use crate::{
+    config,
+    db::RedeemDestinations,
+    swap_protocols::{SwapEvent, SwapId},
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use bitcoin::util::bip32::{ChildNumber, ExtendedPubKey};
+use futures::sync::mpsc;
+use futures_core::{compat::Stream01CompatExt, stream::StreamExt};
+use std::str::FromStr;
+
+/// Used if `bitcoin.redeem_destinations.gap_limit` is not configured.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Where the bitcoin of a swap are sent to if no address is given when
+/// redeeming or refunding.
+#[async_trait]
+pub trait RedeemDestination: Send + Sync + 'static {
+    /// `None` if no redeem destinations are configured.
+    async fn redeem_destination(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Option<bitcoin::Address>>;
+}
+
+/// The P2WPKH receive addresses `<xpub>/0/<index>` of a wallet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReceiveAddresses {
+    xpub: ExtendedPubKey,
+    network: bitcoin::Network,
+    pub gap_limit: u32,
+}
+
+impl ReceiveAddresses {
+    pub fn new(
+        config: &config::RedeemDestinations,
+        network: bitcoin::Network,
+    ) -> anyhow::Result<Self> {
+        let xpub = ExtendedPubKey::from_str(&config.xpub).context("invalid xpub")?;
+
+        // Extended keys only tell mainnet and test networks apart.
+        let expected_network = match network {
+            bitcoin::Network::Bitcoin => bitcoin::Network::Bitcoin,
+            _ => bitcoin::Network::Testnet,
+        };
+        if xpub.network != expected_network {
+            anyhow::bail!("xpub is for {} but cnd runs on {}", xpub.network, network);
+        }
+
+        Ok(Self {
+            xpub,
+            network,
+            gap_limit: config.gap_limit.unwrap_or(DEFAULT_GAP_LIMIT),
+        })
+    }
+
+    pub fn address(&self, index: u32) -> anyhow::Result<bitcoin::Address> {
+        let path = [
+            ChildNumber::from_normal_idx(0)?,
+            ChildNumber::from_normal_idx(index)?,
+        ];
+        let key = self.xpub.derive_pub(&*crate::SECP, &path)?;
+
+        Ok(bitcoin::Address::p2wpkh(&key.public_key, self.network))
+    }
+}
+
+/// Mark the destination of a swap as used once one of its HTLCs was redeemed
+/// or refunded, which moves the gap limit on.
+pub async fn track_used_destinations<D>(dependencies: D, events: mpsc::UnboundedReceiver<SwapEvent>)
+where
+    D: RedeemDestinations,
+{
+    let mut events = events.compat();
+
+    while let Some(Ok(event)) = events.next().await {
+        let swap_id = match event {
+            SwapEvent::AlphaRedeemed { swap_id }
+            | SwapEvent::AlphaRefunded { swap_id }
+            | SwapEvent::BetaRedeemed { swap_id }
+            | SwapEvent::BetaRefunded { swap_id } => swap_id,
+            _ => continue,
+        };
+
+        if let Err(e) =
+            RedeemDestinations::mark_redeem_destination_used(&dependencies, &swap_id).await
+        {
+            log::error!(
+                "failed to mark redeem destination of swap {} as used: {:?}",
+                swap_id,
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    const XPUB: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+
+    fn config() -> config::RedeemDestinations {
+        config::RedeemDestinations {
+            xpub: String::from(XPUB),
+            gap_limit: None,
+        }
+    }
+
+    #[test]
+    fn every_index_is_a_different_address() {
+        let addresses = ReceiveAddresses::new(&config(), bitcoin::Network::Bitcoin).unwrap();
+
+        assert_that(&addresses.gap_limit).is_equal_to(DEFAULT_GAP_LIMIT);
+        assert_that(&addresses.address(0).unwrap()).is_not_equal_to(addresses.address(1).unwrap());
+    }
+
+    #[test]
+    fn mainnet_xpub_is_rejected_on_regtest() {
+        let addresses = ReceiveAddresses::new(&config(), bitcoin::Network::Regtest);
+
+        assert_that(&addresses).is_err();
+    }
+}
@@ -1,4 +1,5 @@
-use crate::swap_protocols::SwapId;
+use crate::swap_protocols::{rfc003::SwapSecrets, Role, SwapId};
+use async_trait::async_trait;
 use crypto::{digest::Digest, sha2::Sha256};
 use pem::{encode, Pem};
 use rand::Rng;
@@ -77,6 +78,22 @@ impl Seed {
         Ok(random_seed)
     }
 
+    /// Read the seed from the directory, fails if there is none.
+    pub fn from_dir<D: AsRef<OsStr>>(data_dir: D) -> Result<Seed, Error> {
+        let dir = Path::new(&data_dir);
+        Self::from_file(seed_path_from_dir(dir))
+    }
+
+    /// Write the seed to the default location within the given directory.
+    pub fn write_to_dir<D: AsRef<OsStr>>(&self, data_dir: D) -> Result<(), Error> {
+        let dir = Path::new(&data_dir);
+        self.write_to(seed_path_from_dir(dir))
+    }
+
+    pub fn bytes(&self) -> [u8; SEED_LENGTH] {
+        self.0
+    }
+
     fn from_file<D: AsRef<OsStr>>(seed_file: D) -> Result<Seed, Error> {
         let file = Path::new(&seed_file);
         let contents = fs::read_to_string(file)?;
@@ -122,13 +139,14 @@ impl Seed {
 }
 
 pub trait SwapSeed {
-    fn swap_seed(&self, id: SwapId) -> Seed;
+    fn swap_secrets(&self, id: SwapId, role: Role) -> SwapSecrets;
 }
 
-impl SwapSeed for Seed {
-    fn swap_seed(&self, id: SwapId) -> Seed {
-        self.swap_seed(id)
-    }
+/// Make the secrets of a swap that was imported from a node with another seed
+/// available through `SwapSeed`.
+#[async_trait]
+pub trait ImportSwapSecrets: Send + Sync + 'static {
+    async fn import_swap_secrets(&self, id: SwapId, secrets: SwapSecrets) -> anyhow::Result<()>;
 }
 
 fn ensure_directory_exists(file: PathBuf) -> Result<(), Error> {
@@ -149,7 +167,7 @@ fn default_seed_path() -> Result<PathBuf, Error> {
     Ok(seed_path_from_dir(&default_path))
 }
 
-fn seed_path_from_dir(dir: &Path) -> PathBuf {
+pub(crate) fn seed_path_from_dir(dir: &Path) -> PathBuf {
     let path = dir.to_path_buf();
     path.join("seed.pem")
 }
@@ -0,0 +1,133 @@
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::RwLock,
+};
+
+const DEFAULT_SHARD_COUNT: usize = 16;
+
+/// A concurrent map that spreads its entries over several independently
+/// locked shards.
+///
+/// Accessing an entry only locks the shard the entry lives in, hence readers
+/// and writers only contend if they happen to access the same shard.
+pub struct ShardedMap<K, V> {
+    shards: Vec<RwLock<HashMap<K, V>>>,
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug> fmt::Debug for ShardedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedMap")
+            .field("shards", &self.shards)
+            .finish()
+    }
+}
+
+impl<K: Eq + Hash, V> Default for ShardedMap<K, V> {
+    fn default() -> Self {
+        Self::with_shard_count(DEFAULT_SHARD_COUNT)
+    }
+}
+
+impl<K: Eq + Hash, V> ShardedMap<K, V> {
+    pub fn with_shard_count(shard_count: usize) -> Self {
+        assert!(shard_count > 0, "a sharded map needs at least one shard");
+
+        Self {
+            shards: (0..shard_count)
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let mut shard = self.shard(&key).write().unwrap();
+        shard.insert(key, value)
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mut shard = self.shard(key).write().unwrap();
+        shard.remove(key)
+    }
+
+    /// Call `f` with a shared reference to the value stored under `key`.
+    pub fn with<Q, R>(&self, key: &Q, f: impl FnOnce(Option<&V>) -> R) -> R
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let shard = self.shard(key).read().unwrap();
+        f(shard.get(key))
+    }
+
+    /// Call `f` with an exclusive reference to the value stored under `key`.
+    ///
+    /// Other entries in the same shard cannot be accessed while `f` runs,
+    /// hence `f` should not block.
+    pub fn with_mut<Q, R>(&self, key: &Q, f: impl FnOnce(Option<&mut V>) -> R) -> R
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mut shard = self.shard(key).write().unwrap();
+        f(shard.get_mut(key))
+    }
+
+    /// Call `f` with an exclusive reference to the value stored under `key`,
+    /// inserting the value returned by `default` first if there is none.
+    pub fn with_entry<R>(
+        &self,
+        key: K,
+        default: impl FnOnce() -> V,
+        f: impl FnOnce(&mut V) -> R,
+    ) -> R {
+        let mut shard = self.shard(&key).write().unwrap();
+        f(shard.entry(key).or_insert_with(default))
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> &RwLock<HashMap<K, V>> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let index = hasher.finish() as usize % self.shards.len();
+
+        &self.shards[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn entries_can_be_inserted_updated_and_removed() {
+        let map = ShardedMap::with_shard_count(4);
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        map.with_mut(&42, |value| *value.unwrap() = 0);
+
+        assert_that(&map.len()).is_equal_to(100);
+        assert_that(&map.with(&42, |value| value.cloned())).contains_value(0);
+        assert_that(&map.remove(&41)).contains_value(41);
+        assert_that(&map.with(&41, |value| value.cloned())).is_none();
+    }
+}
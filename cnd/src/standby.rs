@@ -0,0 +1,314 @@
+use crate::{
+    db::{
+        DetermineTypes, ImportedSwapSecrets, Lease, LoadAcceptedSwap, Retention, Retrieve, Sqlite,
+    },
+    derivation::Derivation,
+    ethereum::{Erc20Token, EtherQuantity},
+    seed::{Seed, SwapSeed},
+    swap_protocols::{
+        self,
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{state_store::InMemoryStateStore, SwapSecrets},
+        Role, SwapId,
+    },
+};
+use chrono::NaiveDateTime;
+use futures::sync::oneshot;
+use futures_core::compat::Future01CompatExt;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+const REPLAY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What a standby would take over if it was promoted right now.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Replica {
+    pub swaps: usize,
+    pub active_swaps: Vec<SwapId>,
+    /// `None` until the database was read for the first time.
+    pub replayed_at: Option<NaiveDateTime>,
+    /// Until when the node the standby stands in for holds the lease of the
+    /// database, a promoted standby waits for it to expire.
+    pub lease_expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("the standby was already promoted")]
+pub struct AlreadyPromoted;
+
+/// Derives the secrets of swaps the way the promoted node will.
+#[derive(Clone, Copy, Debug)]
+pub struct Secrets {
+    pub seed: Seed,
+    pub derivation: Option<Derivation>,
+}
+
+/// The secrets of all swaps as of one replay.
+struct ReplayedSecrets {
+    secrets: Secrets,
+    imported: HashMap<SwapId, SwapSecrets>,
+}
+
+impl SwapSeed for ReplayedSecrets {
+    fn swap_secrets(&self, id: SwapId, role: Role) -> SwapSecrets {
+        if let Some(secrets) = self.imported.get(&id) {
+            return *secrets;
+        }
+
+        let identities = self
+            .secrets
+            .derivation
+            .map(|derivation| derivation.identities(id, role));
+
+        SwapSecrets::new(self.secrets.seed.swap_seed(id), identities)
+    }
+}
+
+/// A cnd instance that follows the database of an active node read-only and
+/// takes its place once it is promoted.
+///
+/// Promotion is a one-way street: it hands control back to `main` which then
+/// starts the node as usual with the same seed, i.e. the same PeerId, and the
+/// state store rebuilt by the standby, and resumes all swaps from the
+/// database once it got the lease of the database.
+#[derive(Clone, Debug)]
+pub struct Standby {
+    replica: Arc<Mutex<Replica>>,
+    promote: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+impl Standby {
+    /// The returned receiver resolves once the standby is promoted.
+    pub fn new() -> (Self, oneshot::Receiver<()>) {
+        let (sender, receiver) = oneshot::channel();
+
+        let standby = Self {
+            replica: Arc::new(Mutex::new(Replica::default())),
+            promote: Arc::new(Mutex::new(Some(sender))),
+        };
+
+        (standby, receiver)
+    }
+
+    pub fn replica(&self) -> Replica {
+        self.replica.lock().expect("poisoned").clone()
+    }
+
+    pub fn promote(&self) -> Result<Replica, AlreadyPromoted> {
+        let sender = self
+            .promote
+            .lock()
+            .expect("poisoned")
+            .take()
+            .ok_or(AlreadyPromoted)?;
+        let _ = sender.send(());
+
+        Ok(self.replica())
+    }
+}
+
+/// Replay the swaps from the database once per `REPLAY_INTERVAL` until the
+/// standby is promoted.
+///
+/// This does not execute the swaps, it rebuilds the state store from the
+/// database and notices early if that fails, e.g. because replication broke.
+/// `local` is the SQLite database in the data directory, it holds the secrets
+/// of imported swaps even if swaps are stored in Postgres.
+pub async fn replay_periodically<D>(
+    dependencies: D,
+    local: Sqlite,
+    secrets: Secrets,
+    standby: Standby,
+) where
+    D: Retrieve
+        + Retention
+        + DetermineTypes
+        + Lease
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    loop {
+        match replay(&dependencies, &local, secrets).await {
+            Ok((replica, _)) => {
+                log::debug!(
+                    "replayed {} swaps, {} of them are active",
+                    replica.swaps,
+                    replica.active_swaps.len()
+                );
+                *standby.replica.lock().expect("poisoned") = replica;
+            }
+            Err(e) => log::error!("failed to replay swaps from the database: {:?}", e),
+        }
+
+        Delay::new(Instant::now() + REPLAY_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
+}
+
+/// Read all swaps from the database and put the state they were accepted in
+/// into a new state store.
+///
+/// The state store is what the promoted node starts with, the states are
+/// brought up to date with the ledgers once the swaps are resumed. Swaps that
+/// were not accepted yet have no state to rebuild.
+#[allow(clippy::cognitive_complexity)]
+pub async fn replay<D>(
+    dependencies: &D,
+    local: &Sqlite,
+    secrets: Secrets,
+) -> anyhow::Result<(Replica, InMemoryStateStore)>
+where
+    D: Retrieve
+        + Retention
+        + DetermineTypes
+        + Lease
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let swaps = Retrieve::all(dependencies).await?;
+    let imported = local.imported_swap_secrets(&secrets.seed).await?;
+    let secrets = ReplayedSecrets {
+        secrets,
+        imported: imported.into_iter().collect(),
+    };
+    let state_store = InMemoryStateStore::default();
+
+    let mut active_swaps = Vec::new();
+    for swap in swaps.iter() {
+        let swap_id = swap.swap_id;
+        // Fails if the swap cannot be resumed after promotion.
+        let types = DetermineTypes::determine_types(dependencies, &swap_id).await?;
+        let role = types.role;
+
+        with_swap_types!(types, {
+            match LoadAcceptedSwap::<AL, BL, AA, BA>::load_accepted_swap(dependencies, &swap_id)
+                .await
+            {
+                Ok((request, accept, _)) => swap_protocols::insert_accepted_swap(
+                    &state_store,
+                    request,
+                    accept,
+                    role,
+                    secrets.swap_secrets(swap_id, role),
+                ),
+                Err(e) => log::debug!("not rebuilding the state of swap {}: {}", swap_id, e),
+            }
+        });
+
+        if Retention::finished_at(dependencies, &swap_id)
+            .await?
+            .is_none()
+        {
+            active_swaps.push(swap_id);
+        }
+    }
+
+    let replica = Replica {
+        swaps: swaps.len(),
+        active_swaps,
+        replayed_at: Some(chrono::Utc::now().naive_utc()),
+        lease_expires_at: Some(Lease::lease_expires_at(dependencies).await?),
+    };
+
+    Ok((replica, state_store))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{Save, Swap},
+        quickcheck::Quickcheck,
+        swap_protocols::rfc003::{alice, bob, state_store::StateStore, Accept, Request},
+    };
+    use spectral::prelude::*;
+    use std::path::Path;
+
+    type BitcoinEtherRequest = Request<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>;
+
+    fn is_rebuilt(state_store: &InMemoryStateStore, swap_id: &SwapId) -> bool {
+        let alice = state_store
+            .get::<alice::State<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>(swap_id);
+        let bob = state_store
+            .get::<bob::State<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>>(swap_id);
+
+        match (alice, bob) {
+            (Ok(Some(_)), _) | (_, Ok(Some(_))) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn replay_rebuilds_the_state_of_accepted_swaps() {
+        fn prop(
+            accepted: Quickcheck<Swap>,
+            requested: Quickcheck<Swap>,
+            request: Quickcheck<BitcoinEtherRequest>,
+            accept: Quickcheck<Accept<Bitcoin, Ethereum>>,
+        ) -> anyhow::Result<bool> {
+            let (accepted, requested) = (accepted.0, requested.0);
+            let db = Sqlite::new(&Path::new(":memory:"))?;
+            let secrets = Secrets {
+                seed: Seed::from(*b"this string is exactly 32 bytes!"),
+                derivation: None,
+            };
+
+            let (replica, state_store) =
+                crate::executor::block_on::<_, anyhow::Result<_>>(async {
+                    db.save(accepted.clone()).await?;
+                    db.save(Request {
+                        swap_id: accepted.swap_id,
+                        ..request.0.clone()
+                    })
+                    .await?;
+                    db.save(Accept {
+                        swap_id: accepted.swap_id,
+                        ..*accept
+                    })
+                    .await?;
+                    db.save(requested.clone()).await?;
+                    db.save(Request {
+                        swap_id: requested.swap_id,
+                        ..*request
+                    })
+                    .await?;
+
+                    replay(&db, &db, secrets).await
+                })?;
+
+            Ok(replica.swaps == 2
+                && replica.active_swaps.len() == 2
+                && is_rebuilt(&state_store, &accepted.swap_id)
+                && !is_rebuilt(&state_store, &requested.swap_id))
+        }
+
+        quickcheck::quickcheck(
+            prop as fn(
+                Quickcheck<Swap>,
+                Quickcheck<Swap>,
+                Quickcheck<BitcoinEtherRequest>,
+                Quickcheck<Accept<Bitcoin, Ethereum>>,
+            ) -> anyhow::Result<bool>,
+        );
+    }
+
+    #[test]
+    fn standby_can_only_be_promoted_once() {
+        let (standby, promoted) = Standby::new();
+
+        assert_that(&standby.promote()).is_ok();
+        assert_that(&standby.promote()).is_err();
+        assert_that(&futures::Future::wait(promoted)).is_ok();
+    }
+}
@@ -0,0 +1,137 @@
+#![allow(clippy::type_repetition_in_bounds)]
+use crate::{
+    db::{DetermineTypes, LoadAcceptedSwap, Retention, SwapFailures},
+    ethereum::{Erc20Token, EtherQuantity},
+    load_swaps,
+    seed::SwapSeed,
+    swap_protocols::{
+        ledger::{Bitcoin, Ethereum},
+        rfc003::state_store::StateStore,
+        FeeAccounting, HtlcScanner, LedgerEventsCreator, SwapEvents, SwapId, SwapTasks,
+        Termination,
+    },
+};
+use futures::sync::mpsc;
+use futures_core::{
+    compat::{Future01CompatExt, Stream01CompatExt},
+    stream::StreamExt,
+    FutureExt, TryFutureExt,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::{executor::Executor, timer::Delay};
+
+/// How often the watcher of a swap is restarted before the swap is marked as
+/// degraded.
+const MAX_RESTARTS: u32 = 5;
+
+/// Doubled with every restart of the same swap, such that a watcher that keeps
+/// stopping e.g. because a ledger is down does not restart in a tight loop.
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(10);
+
+/// Restart the watchers of swaps that stopped before the swap was over.
+///
+/// A swap is restarted the way it would be after cnd was restarted, i.e. it
+/// is resumed from what is stored in the database and the ledgers are scanned
+/// for what happened in the meantime. Once `MAX_RESTARTS` did not keep the
+/// watcher of a swap running, the swap is marked as degraded instead.
+pub async fn supervise_watchers<D>(
+    dependencies: D,
+    expiry_margin: u32,
+    terminations: mpsc::UnboundedReceiver<Termination>,
+) where
+    D: StateStore
+        + Executor
+        + Clone
+        + SwapSeed
+        + LedgerEventsCreator
+        + Retention
+        + SwapFailures
+        + FeeAccounting
+        + SwapTasks
+        + SwapEvents
+        + DetermineTypes
+        + HtlcScanner
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, EtherQuantity>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, EtherQuantity, bitcoin::Amount>
+        + LoadAcceptedSwap<Bitcoin, Ethereum, bitcoin::Amount, Erc20Token>
+        + LoadAcceptedSwap<Ethereum, Bitcoin, Erc20Token, bitcoin::Amount>,
+{
+    let mut restarts = HashMap::<SwapId, u32>::new();
+    let mut terminations = terminations.compat();
+
+    while let Some(Ok(Termination { swap_id, reason })) = terminations.next().await {
+        let restart = restarts.entry(swap_id).or_insert(0);
+
+        let delay = match restart_delay(*restart) {
+            Some(delay) => delay,
+            None => {
+                log::error!(
+                    "giving up on restarting the watcher of swap {} after {} restarts",
+                    swap_id,
+                    restart
+                );
+                SwapTasks::mark_degraded(&dependencies, swap_id, reason);
+                continue;
+            }
+        };
+        *restart += 1;
+
+        log::info!(
+            "restarting the watcher of swap {} in {} seconds",
+            swap_id,
+            delay.as_secs()
+        );
+
+        let dependencies = dependencies.clone();
+        tokio::spawn(
+            async move {
+                Delay::new(Instant::now() + delay)
+                    .compat()
+                    .await
+                    .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+
+                // A restart that fails counts as the watcher stopping again.
+                if let Err(e) =
+                    load_swaps::restart_swap(&dependencies, swap_id, expiry_margin).await
+                {
+                    SwapTasks::watcher_terminated(&dependencies, Termination {
+                        swap_id,
+                        reason: format!("failed to restart: {:#}", e),
+                    });
+                }
+
+                Ok::<(), ()>(())
+            }
+            .boxed()
+            .compat(),
+        );
+    }
+}
+
+/// How long to wait before restarting a watcher that was restarted `restarts`
+/// times already, `None` once it is not restarted anymore.
+fn restart_delay(restarts: u32) -> Option<Duration> {
+    if restarts >= MAX_RESTARTS {
+        return None;
+    }
+
+    Some(INITIAL_RESTART_DELAY * 2u32.pow(restarts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn restarts_back_off_until_giving_up() {
+        let delays = (0..=MAX_RESTARTS).map(restart_delay).collect::<Vec<_>>();
+
+        assert_that(&delays[0]).is_equal_to(Some(INITIAL_RESTART_DELAY));
+        assert_that(&delays[1]).is_equal_to(Some(INITIAL_RESTART_DELAY * 2));
+        assert_that(&delays[MAX_RESTARTS as usize]).is_equal_to(None);
+    }
+}
@@ -11,9 +11,16 @@ pub trait Actions {
 }
 
 pub mod bitcoin {
-    use bitcoin::{Address, Amount};
+    use bitcoin::{Address, Amount, OutPoint, Transaction, TxIn, TxOut};
     use blockchain_contracts::bitcoin::witness::{PrimedInput, PrimedTransaction};
 
+    /// Outputs below this value are not relayed by Bitcoin Core.
+    const DUST_LIMIT: u64 = 546;
+
+    /// The weight of the witness of a P2WPKH input plus the segwit marker and
+    /// flag, with a signature of maximal length.
+    const P2WPKH_WITNESS_WEIGHT: u64 = 2 + 1 + 1 + 72 + 1 + 33;
+
     #[derive(Debug, Clone, PartialEq)]
     pub struct SendToAddress {
         pub to: Address,
@@ -21,21 +28,172 @@ pub mod bitcoin {
         pub network: bitcoin::Network,
     }
 
+    impl SendToAddress {
+        /// A transaction paying `amount` to `to`, the wallet is expected to add
+        /// inputs and change.
+        pub fn unsigned_transaction(&self) -> Transaction {
+            Transaction {
+                version: 2,
+                lock_time: 0,
+                input: vec![],
+                output: vec![TxOut {
+                    value: self.amount.as_sat(),
+                    script_pubkey: self.to.script_pubkey(),
+                }],
+            }
+        }
+    }
+
     #[derive(Debug, Clone, PartialEq)]
     pub struct SpendOutput {
+        /// Usually one, more if the HTLC was funded several times.
+        pub outputs: Vec<HtlcOutput>,
+        pub network: bitcoin::Network,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct HtlcOutput {
         // Remember: One man's input is another man's output!
         pub output: PrimedInput,
-        pub network: bitcoin::Network,
+        /// The output that is spent and its value, the primed input does not
+        /// expose them.
+        pub outpoint: OutPoint,
+        pub value: Amount,
     }
 
     impl SpendOutput {
+        /// What all spent outputs add up to.
+        pub fn value(&self) -> Amount {
+            self.outputs
+                .iter()
+                .fold(Amount::from_sat(0), |sum, output| sum + output.value)
+        }
+
+        /// Whether `transaction` spends exactly the outputs of this action,
+        /// e.g. because it was constructed from it before.
+        pub fn is_spent_by(&self, transaction: &Transaction) -> bool {
+            spends_exactly(
+                transaction,
+                &self
+                    .outputs
+                    .iter()
+                    .map(|output| output.outpoint)
+                    .collect::<Vec<_>>(),
+            )
+        }
+
         pub fn spend_to(self, to_address: Address) -> PrimedTransaction {
             PrimedTransaction {
-                inputs: vec![self.output],
+                inputs: self
+                    .outputs
+                    .into_iter()
+                    .map(|output| output.output)
+                    .collect(),
                 output_address: to_address,
             }
         }
     }
+
+    /// Speeds up the confirmation of a transaction spending an HTLC by
+    /// spending its output before it is confirmed, with a fee high enough for
+    /// both transactions (child pays for parent).
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct BumpFee {
+        /// The HTLC outputs the transaction to speed up spends.
+        pub htlc_outputs: Vec<(OutPoint, Amount)>,
+        pub network: bitcoin::Network,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+    pub enum CannotBumpFee {
+        #[error("the fee would leave less than the dust limit of the spent output")]
+        FeeHigherThanOutputValue,
+        #[error("the fee overflows")]
+        OverflowingFee,
+    }
+
+    impl BumpFee {
+        /// Whether `transaction` spends exactly the HTLC outputs to a single
+        /// output, like the redeem transactions cnd constructs.
+        pub fn is_parent(&self, transaction: &Transaction) -> bool {
+            transaction.output.len() == 1
+                && spends_exactly(
+                    transaction,
+                    &self
+                        .htlc_outputs
+                        .iter()
+                        .map(|(outpoint, _)| *outpoint)
+                        .collect::<Vec<_>>(),
+                )
+        }
+
+        /// An unsigned transaction spending the output of `parent` to the
+        /// same script, such that both together pay `fee_per_wu`.
+        ///
+        /// The weight of the child is estimated assuming the output is
+        /// spent with a P2WPKH witness, as wallets usually do.
+        pub fn child(
+            &self,
+            parent: &Transaction,
+            fee_per_wu: u64,
+        ) -> Result<Transaction, CannotBumpFee> {
+            let parent_output = &parent.output[0];
+            let spent = self
+                .htlc_outputs
+                .iter()
+                .map(|(_, value)| value.as_sat())
+                .sum::<u64>();
+            let parent_fee = spent.saturating_sub(parent_output.value);
+
+            let mut child = Transaction {
+                version: 2,
+                lock_time: 0,
+                input: vec![TxIn {
+                    previous_output: OutPoint {
+                        txid: parent.txid(),
+                        vout: 0,
+                    },
+                    script_sig: bitcoin::Script::new(),
+                    // Signal replaceability, such that the child can be
+                    // bumped again.
+                    sequence: 0xFFFF_FFFD,
+                    witness: Vec::new(),
+                }],
+                output: vec![TxOut {
+                    value: 0,
+                    script_pubkey: parent_output.script_pubkey.clone(),
+                }],
+            };
+            let child_weight = child.get_weight() as u64 + P2WPKH_WITNESS_WEIGHT;
+
+            let package_fee = (parent.get_weight() as u64 + child_weight)
+                .checked_mul(fee_per_wu)
+                .ok_or(CannotBumpFee::OverflowingFee)?;
+            // The child pays for its own weight even if the parent already
+            // pays enough.
+            let child_fee = package_fee
+                .saturating_sub(parent_fee)
+                .max(child_weight * fee_per_wu);
+
+            child.output[0].value = parent_output
+                .value
+                .checked_sub(child_fee)
+                .filter(|value| *value >= DUST_LIMIT)
+                .ok_or(CannotBumpFee::FeeHigherThanOutputValue)?;
+
+            Ok(child)
+        }
+    }
+
+    fn spends_exactly(transaction: &Transaction, outpoints: &[OutPoint]) -> bool {
+        transaction.input.len() == outpoints.len()
+            && outpoints.iter().all(|outpoint| {
+                transaction
+                    .input
+                    .iter()
+                    .any(|input| input.previous_output == *outpoint)
+            })
+    }
 }
 
 pub mod ethereum {
@@ -1,32 +1,59 @@
 use crate::{
-    btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
+    backup::{Archive, Backup},
+    btsieve::{
+        bitcoin::{BitcoinConnector, TransactionProof},
+        ethereum::{ReceiptProof, Web3Connector},
+        InclusionProof,
+    },
     db::{
-        AcceptedSwap, DetermineTypes, LoadAcceptedSwap, Retrieve, Save, Saver, Sqlite, Swap,
-        SwapTypes,
+        self, AcceptedSwap, ActionHistory, ActionInvocation, AutoRefund, AutoRefunds,
+        ConstructedTransactions, Database, DetermineTypes, Enqueue, Enqueuer, Import,
+        ImportedSwapSecrets, Importer, IssuedIdentities, LoadAcceptedSwap, LoadRequest,
+        MetadataStore, Outbox, PaidFee, PayoutAccountUsage, PayoutAccountUsages, PendingRequest,
+        RecordedFee, RedeemDestinations, RequestedRates, Retention, RetentionPolicy, Retrieve,
+        Save, Saver, Sqlite, Stats, Swap, SwapFailure, SwapFailures, SwapFees, SwapMessage,
+        SwapMessages, SwapMetadata, SwapStats, SwapTypes, UnansweredRequest,
     },
-    ethereum::{Erc20Token, EtherQuantity},
-    network::{DialInformation, Network, RequestError, SendRequest},
-    seed::{Seed, SwapSeed},
+    derivation::Derivation,
+    ethereum::{Erc20Token, EtherQuantity, GasOracle, GasPricing},
+    identity_reuse::{self, IssueIdentities},
+    network::{self, DialInformation, Network, PeerInfo, PeerStatus, SendRequest},
+    redeem_destinations::{ReceiveAddresses, RedeemDestination},
+    seed::{ImportSwapSecrets, Seed, SwapSeed},
+    sharded_map::ShardedMap,
     swap_protocols::{
         asset::Asset,
         ledger::{Bitcoin, Ethereum},
         rfc003::{
             self,
-            events::{HtlcEvents, LedgerEventFutures, LedgerEvents},
-            state_machine::SwapStates,
+            actions::ActionKind,
+            events::{
+                FindHtlc, FundedHtlcFuture, HtlcEvents, HtlcHistory, LedgerEventFutures,
+                LedgerEvents, ScanHtlc,
+            },
+            fees::PaidFees,
+            state_machine::{HtlcParams, SwapStates},
             state_store::{self, InMemoryStateStore, StateStore},
-            ActorState, Ledger,
+            ActorState, Ledger, Rate, SwapSecrets,
         },
-        SwapId,
+        EventBus, Role, SwapEvent, SwapEvents, SwapId, SwapTasks, TaskRegistry, Termination,
     },
+    swap_tracing::{InScope, SwapTracing, TraceContext, Tracer},
+    timestamp::Timestamp,
     CreateLedgerEvents,
 };
 use async_trait::async_trait;
-use bitcoin::Amount;
+use bitcoin::{hashes::sha256d, Amount};
+use chrono::NaiveDateTime;
 use futures::{sync::oneshot::Sender, Future};
 use libp2p::PeerId;
-use libp2p_comit::frame::Response;
-use std::sync::Arc;
+use libp2p_comit::{frame::Response, Frame, RequestKind, RequestMetrics, Unrecognized};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use tokio::{executor, runtime::TaskExecutor};
 
 /// This is a facade that implements all the required traits and forwards them
@@ -34,13 +61,26 @@ use tokio::{executor, runtime::TaskExecutor};
 /// HTTP API controllers small and still access all the functionality we need.
 #[allow(missing_debug_implementations)]
 pub struct Facade<S> {
-    pub bitcoin_connector: BitcoindConnector,
+    pub bitcoin_connector: BitcoinConnector,
     pub ethereum_connector: Web3Connector,
     pub state_store: Arc<InMemoryStateStore>,
     pub seed: Seed,
-    pub swarm: Arc<S>, // S is the libp2p Swarm within a mutex.
+    pub derivation: Option<Derivation>,
+    /// The secrets of swaps imported from a node with another seed, they
+    /// take precedence over the ones derived from `seed`.
+    pub imported_secrets: Arc<ShardedMap<SwapId, SwapSecrets>>,
+    pub receive_addresses: Option<ReceiveAddresses>,
+    /// Whether swaps that would re-use a derived identity are refused.
+    pub refuse_identity_reuse: bool,
+    pub swarm: Arc<S>, // S is a handle to the task driving the libp2p Swarm.
     pub db: Sqlite,
+    /// Where the swaps themselves are stored, may be `db`.
+    pub swaps: Database,
     pub task_executor: TaskExecutor,
+    pub swap_tasks: Arc<TaskRegistry>,
+    pub event_bus: EventBus,
+    /// `None` unless `[tracing]` is configured.
+    pub tracer: Option<Tracer>,
 }
 
 impl<S> Clone for Facade<S> {
@@ -50,9 +90,17 @@ impl<S> Clone for Facade<S> {
             ethereum_connector: self.ethereum_connector.clone(),
             state_store: Arc::clone(&self.state_store),
             seed: self.seed,
+            derivation: self.derivation,
+            imported_secrets: Arc::clone(&self.imported_secrets),
+            receive_addresses: self.receive_addresses,
+            refuse_identity_reuse: self.refuse_identity_reuse,
             swarm: Arc::clone(&self.swarm),
             db: self.db.clone(),
+            swaps: self.swaps.clone(),
             task_executor: self.task_executor.clone(),
+            swap_tasks: Arc::clone(&self.swap_tasks),
+            event_bus: self.event_bus.clone(),
+            tracer: self.tracer.clone(),
         }
     }
 }
@@ -72,20 +120,55 @@ where
     fn update<A: ActorState>(&self, key: &SwapId, update: SwapStates<A::AL, A::BL, A::AA, A::BA>) {
         self.state_store.update::<A>(key, update)
     }
+
+    fn modify<A: ActorState, F: FnOnce(&mut A)>(&self, key: &SwapId, modify: F) {
+        self.state_store.modify::<A, F>(key, modify)
+    }
+
+    fn remove(&self, key: &SwapId) {
+        self.state_store.remove(key)
+    }
 }
 
+#[async_trait]
 impl<S: Network> Network for Facade<S>
 where
     S: Send + Sync + 'static,
 {
-    fn comit_peers(
-        &self,
-    ) -> Box<dyn Iterator<Item = (PeerId, Vec<libp2p::Multiaddr>)> + Send + 'static> {
-        self.swarm.comit_peers()
+    async fn comit_peers(&self) -> anyhow::Result<Vec<(PeerId, Vec<libp2p::Multiaddr>)>> {
+        self.swarm.comit_peers().await
+    }
+
+    async fn listen_addresses(&self) -> anyhow::Result<Vec<libp2p::Multiaddr>> {
+        self.swarm.listen_addresses().await
+    }
+
+    async fn peer_status(&self, peer_id: PeerId) -> anyhow::Result<PeerStatus> {
+        self.swarm.peer_status(peer_id).await
     }
 
-    fn listen_addresses(&self) -> Vec<libp2p::Multiaddr> {
-        self.swarm.listen_addresses()
+    async fn latencies(&self) -> anyhow::Result<Vec<(PeerId, Duration)>> {
+        self.swarm.latencies().await
+    }
+
+    async fn unrecognized(&self) -> anyhow::Result<BTreeMap<Unrecognized, u64>> {
+        self.swarm.unrecognized().await
+    }
+
+    async fn request_metrics(&self) -> anyhow::Result<BTreeMap<RequestKind, RequestMetrics>> {
+        self.swarm.request_metrics().await
+    }
+
+    async fn unsaved_requests(&self) -> anyhow::Result<u64> {
+        self.swarm.unsaved_requests().await
+    }
+
+    async fn active_swaps(&self) -> anyhow::Result<HashMap<PeerId, usize>> {
+        self.swarm.active_swaps().await
+    }
+
+    async fn peer_infos(&self) -> anyhow::Result<HashMap<PeerId, PeerInfo>> {
+        self.swarm.peer_infos().await
     }
 
     fn pending_request_for(&self, swap: SwapId) -> Option<Sender<Response>> {
@@ -101,8 +184,22 @@ where
         &self,
         dial_info: DialInformation,
         request: rfc003::Request<AL, BL, AA, BA>,
-    ) -> Box<dyn Future<Item = rfc003::Response<AL, BL>, Error = RequestError> + Send> {
-        self.swarm.send_request(dial_info, request)
+        rate: Option<Rate>,
+    ) -> Box<dyn Future<Item = rfc003::Response<AL, BL>, Error = network::Error> + Send> {
+        let swap_id = request.swap_id;
+        let response = self.swarm.send_request(dial_info, request, rate);
+
+        match self.tracer.clone() {
+            Some(tracer) => {
+                let start = SystemTime::now();
+
+                Box::new(response.then(move |result| {
+                    tracer.record_span(swap_id, "network.send-request", start);
+                    result
+                }))
+            }
+            None => response,
+        }
     }
 }
 
@@ -110,8 +207,16 @@ impl<S> SwapSeed for Facade<S>
 where
     S: Send + Sync + 'static,
 {
-    fn swap_seed(&self, id: SwapId) -> Seed {
-        self.seed.swap_seed(id)
+    fn swap_secrets(&self, id: SwapId, role: Role) -> SwapSecrets {
+        if let Some(secrets) = self.imported_secrets.with(&id, |secrets| secrets.copied()) {
+            return secrets;
+        }
+
+        let identities = self
+            .derivation
+            .map(|derivation| derivation.identities(id, role));
+
+        SwapSecrets::new(self.seed.swap_seed(id), identities)
     }
 }
 
@@ -121,11 +226,11 @@ where
     S: Send + Sync + 'static,
 {
     async fn get(&self, key: &SwapId) -> anyhow::Result<Swap> {
-        self.db.get(key).await
+        self.swaps.get(key).await
     }
 
     async fn all(&self) -> anyhow::Result<Vec<Swap>> {
-        self.db.all().await
+        self.swaps.all().await
     }
 }
 
@@ -137,13 +242,70 @@ where
     BL: Ledger + Send + 'static,
     AA: Asset + Send + 'static,
     BA: Asset + Send + 'static,
-    Sqlite: LoadAcceptedSwap<AL, BL, AA, BA>,
+    Database: LoadAcceptedSwap<AL, BL, AA, BA>,
 {
     async fn load_accepted_swap(
         &self,
         swap_id: &SwapId,
     ) -> anyhow::Result<AcceptedSwap<AL, BL, AA, BA>> {
-        self.db.load_accepted_swap(swap_id).await
+        self.swaps.load_accepted_swap(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S, AL, BL, AA, BA> LoadRequest<AL, BL, AA, BA> for Facade<S>
+where
+    S: Send + Sync + 'static,
+    AL: Ledger + Send + 'static,
+    BL: Ledger + Send + 'static,
+    AA: Asset + Send + 'static,
+    BA: Asset + Send + 'static,
+    Database: LoadRequest<AL, BL, AA, BA>,
+{
+    async fn load_request(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<rfc003::Request<AL, BL, AA, BA>> {
+        self.swaps.load_request(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S, T> Enqueue<T> for Facade<S>
+where
+    S: Send + Sync + 'static,
+    T: Send + 'static,
+    Database: Enqueue<T>,
+{
+    async fn save_and_enqueue(
+        &self,
+        swap: Swap,
+        request: T,
+        peer: DialInformation,
+    ) -> anyhow::Result<()> {
+        self.swaps.save_and_enqueue(swap, request, peer).await
+    }
+}
+
+#[async_trait]
+impl<S> Outbox for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn pending_requests(&self) -> anyhow::Result<Vec<PendingRequest>> {
+        self.swaps.pending_requests().await
+    }
+
+    async fn mark_sent(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        self.swaps.mark_sent(swap_id).await
+    }
+
+    async fn record_attempt(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        self.swaps.record_attempt(swap_id).await
+    }
+
+    async fn unanswered_requests(&self) -> anyhow::Result<Vec<UnansweredRequest>> {
+        self.swaps.unanswered_requests().await
     }
 }
 
@@ -153,22 +315,394 @@ where
     S: Send + Sync + 'static,
 {
     async fn determine_types(&self, key: &SwapId) -> anyhow::Result<SwapTypes> {
-        self.db.determine_types(key).await
+        self.swaps.determine_types(key).await
+    }
+}
+
+#[async_trait]
+impl<S> Retention for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn mark_finished(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        self.swaps.mark_finished(swap_id).await
+    }
+
+    async fn finished_at(&self, swap_id: &SwapId) -> anyhow::Result<Option<NaiveDateTime>> {
+        self.swaps.finished_at(swap_id).await
+    }
+
+    async fn archive_finished_swaps(
+        &self,
+        policy: RetentionPolicy,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Vec<SwapId>> {
+        self.swaps.archive_finished_swaps(policy, now).await
+    }
+}
+
+#[async_trait]
+impl<S> ActionHistory for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn record_action_invocation(
+        &self,
+        swap_id: &SwapId,
+        action: ActionKind,
+        parameters: &serde_json::Value,
+        payload: &serde_json::Value,
+    ) -> anyhow::Result<()> {
+        self.db
+            .record_action_invocation(swap_id, action, parameters, payload)
+            .await
+    }
+
+    async fn previous_action_invocation(
+        &self,
+        swap_id: &SwapId,
+        action: ActionKind,
+        parameters: &serde_json::Value,
+    ) -> anyhow::Result<Option<ActionInvocation>> {
+        self.db
+            .previous_action_invocation(swap_id, action, parameters)
+            .await
+    }
+
+    async fn action_history(&self, swap_id: &SwapId) -> anyhow::Result<Vec<ActionInvocation>> {
+        self.db.action_history(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> SwapFailures for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn record_swap_failure(
+        &self,
+        swap_id: &SwapId,
+        error: &rfc003::Error,
+    ) -> anyhow::Result<()> {
+        self.db.record_swap_failure(swap_id, error).await
+    }
+
+    async fn swap_failure(&self, swap_id: &SwapId) -> anyhow::Result<Option<SwapFailure>> {
+        self.db.swap_failure(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> SwapFees for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn record_swap_fees(&self, swap_id: &SwapId, fees: &[PaidFee]) -> anyhow::Result<()> {
+        self.db.record_swap_fees(swap_id, fees).await
+    }
+
+    async fn swap_fees(
+        &self,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> anyhow::Result<Vec<RecordedFee>> {
+        self.db.swap_fees(from, to).await
+    }
+}
+
+#[async_trait]
+impl<S> AutoRefunds for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn enable_auto_refund(&self, swap_id: &SwapId, refund: AutoRefund) -> anyhow::Result<()> {
+        self.db.enable_auto_refund(swap_id, refund).await
+    }
+
+    async fn pending_auto_refunds(&self) -> anyhow::Result<Vec<(SwapId, AutoRefund)>> {
+        self.db.pending_auto_refunds().await
+    }
+
+    async fn record_auto_refund_broadcast(
+        &self,
+        swap_id: &SwapId,
+        transaction_id: &sha256d::Hash,
+        broadcast_at: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        self.db
+            .record_auto_refund_broadcast(swap_id, transaction_id, broadcast_at)
+            .await
+    }
+
+    async fn cancel_auto_refund(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        self.db.cancel_auto_refund(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> ConstructedTransactions for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn record_constructed_transaction(
+        &self,
+        swap_id: &SwapId,
+        transaction: &bitcoin::Transaction,
+    ) -> anyhow::Result<()> {
+        self.db
+            .record_constructed_transaction(swap_id, transaction)
+            .await
+    }
+
+    async fn constructed_transactions(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Vec<bitcoin::Transaction>> {
+        self.db.constructed_transactions(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> RedeemDestinations for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn redeem_destination_index(
+        &self,
+        swap_id: &SwapId,
+        gap_limit: u32,
+    ) -> anyhow::Result<u32> {
+        self.db.redeem_destination_index(swap_id, gap_limit).await
+    }
+
+    async fn mark_redeem_destination_used(&self, swap_id: &SwapId) -> anyhow::Result<()> {
+        self.db.mark_redeem_destination_used(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> PayoutAccountUsages for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn record_payout_account_usage(
+        &self,
+        swap_id: &SwapId,
+        account: &str,
+    ) -> anyhow::Result<()> {
+        self.db.record_payout_account_usage(swap_id, account).await
+    }
+
+    async fn payout_account_usages(&self) -> anyhow::Result<BTreeMap<String, PayoutAccountUsage>> {
+        self.db.payout_account_usages().await
+    }
+}
+
+#[async_trait]
+impl<S> MetadataStore for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn save_metadata(&self, swap_id: &SwapId, metadata: SwapMetadata) -> anyhow::Result<()> {
+        self.db.save_metadata(swap_id, metadata).await
+    }
+
+    async fn swap_metadata(&self, swap_id: &SwapId) -> anyhow::Result<SwapMetadata> {
+        self.db.swap_metadata(swap_id).await
+    }
+
+    async fn swaps_with_external_id(&self, external_id: &str) -> anyhow::Result<Vec<SwapId>> {
+        self.db.swaps_with_external_id(external_id).await
+    }
+}
+
+#[async_trait]
+impl<S> SwapMessages for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn record_message(
+        &self,
+        swap_id: &SwapId,
+        direction: db::Direction,
+        frame: &Frame,
+    ) -> anyhow::Result<()> {
+        self.db.record_message(swap_id, direction, frame).await
+    }
+
+    async fn swap_messages(&self, swap_id: &SwapId) -> anyhow::Result<Vec<SwapMessage>> {
+        self.db.swap_messages(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> RequestedRates for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn save_requested_rate(&self, swap_id: &SwapId, rate: &Rate) -> anyhow::Result<()> {
+        self.db.save_requested_rate(swap_id, rate).await
+    }
+
+    async fn requested_rate(&self, swap_id: &SwapId) -> anyhow::Result<Option<Rate>> {
+        self.db.requested_rate(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> IssuedIdentities for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn record_issued_identities(
+        &self,
+        swap_id: &SwapId,
+        identities: &[String],
+    ) -> anyhow::Result<()> {
+        self.db.record_issued_identities(swap_id, identities).await
+    }
+
+    async fn swaps_issued(&self, identity: &str) -> anyhow::Result<Vec<SwapId>> {
+        self.db.swaps_issued(identity).await
+    }
+
+    async fn reused_identities(&self, swap_id: &SwapId) -> anyhow::Result<Vec<String>> {
+        self.db.reused_identities(swap_id).await
+    }
+}
+
+#[async_trait]
+impl<S> IssueIdentities for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn issue_identities(
+        &self,
+        swap_id: &SwapId,
+        identities: &[String],
+    ) -> anyhow::Result<()> {
+        identity_reuse::issue(&self.db, swap_id, identities, self.refuse_identity_reuse).await
+    }
+}
+
+#[async_trait]
+impl<S> RedeemDestination for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn redeem_destination(
+        &self,
+        swap_id: &SwapId,
+    ) -> anyhow::Result<Option<bitcoin::Address>> {
+        let addresses = match self.receive_addresses {
+            Some(addresses) => addresses,
+            None => return Ok(None),
+        };
+        let index = self
+            .db
+            .redeem_destination_index(swap_id, addresses.gap_limit)
+            .await?;
+
+        Ok(Some(addresses.address(index)?))
+    }
+}
+
+#[async_trait]
+impl<S> Stats for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn stats(&self) -> anyhow::Result<SwapStats> {
+        self.db.stats().await
+    }
+}
+
+#[async_trait]
+impl<S> GasOracle for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn gas_pricing(&self) -> anyhow::Result<GasPricing> {
+        self.ethereum_connector.gas_pricing().await
     }
 }
 
 #[async_trait]
 impl<S> Saver for Facade<S> where S: Send + Sync + 'static {}
 
+impl<S> Enqueuer for Facade<S> where S: Send + Sync + 'static {}
+
 #[async_trait]
 impl<S, T> Save<T> for Facade<S>
 where
     S: Send + Sync + 'static,
     T: Send + 'static,
-    Sqlite: Save<T>,
+    Database: Save<T>,
 {
     async fn save(&self, data: T) -> anyhow::Result<()> {
-        self.db.save(data).await
+        self.swaps.save(data).await
+    }
+}
+
+impl<S> Importer for Facade<S> where S: Send + Sync + 'static {}
+
+#[async_trait]
+impl<S, AL, BL, AA, BA> Import<AL, BL, AA, BA> for Facade<S>
+where
+    S: Send + Sync + 'static,
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+    Database: Import<AL, BL, AA, BA>,
+{
+    async fn import(
+        &self,
+        swap: Swap,
+        request: rfc003::Request<AL, BL, AA, BA>,
+        accept: rfc003::Accept<AL, BL>,
+        accepted_at: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        self.swaps.import(swap, request, accept, accepted_at).await
+    }
+}
+
+impl<S> SwapTracing for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn continue_trace(&self, swap_id: SwapId, parent: TraceContext) {
+        if let Some(tracer) = &self.tracer {
+            tracer.start_swap(swap_id, parent);
+        }
+    }
+}
+
+#[async_trait]
+impl<S> ImportSwapSecrets for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn import_swap_secrets(&self, id: SwapId, secrets: SwapSecrets) -> anyhow::Result<()> {
+        self.db
+            .save_imported_swap_secrets(&id, secrets, &self.seed)
+            .await?;
+        self.imported_secrets.insert(id, secrets);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> Backup for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn backup(&self) -> anyhow::Result<Archive> {
+        // `db` only holds the swaps if they are not stored in Postgres.
+        let database = self.swaps.snapshot().await?;
+
+        Ok(Archive::new(self.seed, database))
     }
 }
 
@@ -205,6 +739,229 @@ where
     }
 }
 
+pub trait HtlcFinder:
+    FindHtlc<Bitcoin, Amount> + FindHtlc<Ethereum, EtherQuantity> + FindHtlc<Ethereum, Erc20Token>
+{
+}
+
+impl<S> HtlcFinder for Facade<S> where S: Send + Sync + 'static {}
+
+impl<S> FindHtlc<Bitcoin, Amount> for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn find_funded_htlc(
+        &self,
+        htlc_params: HtlcParams<Bitcoin, Amount>,
+        since: Timestamp,
+    ) -> Box<FundedHtlcFuture<Bitcoin, Amount>> {
+        self.bitcoin_connector.find_funded_htlc(htlc_params, since)
+    }
+}
+
+impl<S, A> FindHtlc<Ethereum, A> for Facade<S>
+where
+    S: Send + Sync + 'static,
+    A: Asset + Send + Sync + 'static,
+    Web3Connector: FindHtlc<Ethereum, A>,
+{
+    fn find_funded_htlc(
+        &self,
+        htlc_params: HtlcParams<Ethereum, A>,
+        since: Timestamp,
+    ) -> Box<FundedHtlcFuture<Ethereum, A>> {
+        self.ethereum_connector.find_funded_htlc(htlc_params, since)
+    }
+}
+
+pub trait HtlcScanner:
+    ScanHtlc<Bitcoin, Amount> + ScanHtlc<Ethereum, EtherQuantity> + ScanHtlc<Ethereum, Erc20Token>
+{
+}
+
+impl<S> HtlcScanner for Facade<S> where S: Send + Sync + 'static {}
+
+#[async_trait]
+impl<S> ScanHtlc<Bitcoin, Amount> for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn scan_htlc(
+        &self,
+        htlc_params: HtlcParams<Bitcoin, Amount>,
+        since: Timestamp,
+    ) -> anyhow::Result<HtlcHistory<Bitcoin, Amount>> {
+        self.bitcoin_connector.scan_htlc(htlc_params, since).await
+    }
+}
+
+#[async_trait]
+impl<S, A> ScanHtlc<Ethereum, A> for Facade<S>
+where
+    S: Send + Sync + 'static,
+    A: Asset + Send + Sync + 'static,
+    Web3Connector: ScanHtlc<Ethereum, A>,
+{
+    async fn scan_htlc(
+        &self,
+        htlc_params: HtlcParams<Ethereum, A>,
+        since: Timestamp,
+    ) -> anyhow::Result<HtlcHistory<Ethereum, A>> {
+        self.ethereum_connector.scan_htlc(htlc_params, since).await
+    }
+}
+
+/// Everything needed to account for the fees we pay in swaps.
+pub trait FeeAccounting: SwapFees + PaidFees<Bitcoin> + PaidFees<Ethereum> {}
+
+impl<S> FeeAccounting for Facade<S> where S: Send + Sync + 'static {}
+
+#[async_trait]
+impl<S> PaidFees<Bitcoin> for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn paid_fee(
+        &self,
+        transaction: &bitcoin::Transaction,
+        fund_transaction: &bitcoin::Transaction,
+    ) -> anyhow::Result<Option<PaidFee>> {
+        self.bitcoin_connector
+            .paid_fee(transaction, fund_transaction)
+            .await
+    }
+}
+
+#[async_trait]
+impl<S> PaidFees<Ethereum> for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    async fn paid_fee(
+        &self,
+        transaction: &crate::ethereum::Transaction,
+        fund_transaction: &crate::ethereum::Transaction,
+    ) -> anyhow::Result<Option<PaidFee>> {
+        self.ethereum_connector
+            .paid_fee(transaction, fund_transaction)
+            .await
+    }
+}
+
+/// Prove that a transaction of a swap is in its ledger, see
+/// `InclusionProof`.
+#[async_trait]
+pub trait ProveInclusion<L: Ledger>: Send + Sync + 'static {
+    type Proof: Serialize + Send;
+
+    /// `None` if the transaction is not in a block yet.
+    async fn prove_inclusion(
+        &self,
+        transaction: &L::Transaction,
+    ) -> anyhow::Result<Option<Self::Proof>>;
+}
+
+pub trait InclusionProver: ProveInclusion<Bitcoin> + ProveInclusion<Ethereum> {}
+
+impl<S> InclusionProver for Facade<S> where S: Send + Sync + 'static {}
+
+#[async_trait]
+impl<S> ProveInclusion<Bitcoin> for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    type Proof = TransactionProof;
+
+    async fn prove_inclusion(
+        &self,
+        transaction: &bitcoin::Transaction,
+    ) -> anyhow::Result<Option<TransactionProof>> {
+        self.bitcoin_connector
+            .inclusion_proof(transaction.txid())
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "could not prove transaction {}: {:?}",
+                    transaction.txid(),
+                    e
+                )
+            })
+    }
+}
+
+#[async_trait]
+impl<S> ProveInclusion<Ethereum> for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    type Proof = ReceiptProof;
+
+    async fn prove_inclusion(
+        &self,
+        transaction: &crate::ethereum::Transaction,
+    ) -> anyhow::Result<Option<ReceiptProof>> {
+        self.ethereum_connector
+            .inclusion_proof(transaction.hash)
+            .await
+    }
+}
+
+impl<S> SwapTasks for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn spawn_swap_task(
+        &self,
+        swap_id: SwapId,
+        task: impl Future<Item = (), Error = ()> + Send + 'static,
+    ) -> anyhow::Result<()> {
+        let task = self.swap_tasks.register(swap_id, task);
+        let scope = self
+            .tracer
+            .as_ref()
+            .and_then(|tracer| tracer.scope(swap_id));
+        executor::Executor::spawn(
+            &mut self.task_executor.clone(),
+            Box::new(InScope::new(scope, task)),
+        )?;
+
+        Ok(())
+    }
+
+    fn cancel_swap_tasks(&self, swap_id: &SwapId) {
+        self.swap_tasks.cancel(swap_id)
+    }
+
+    fn active_watchers(&self) -> usize {
+        self.swap_tasks.active()
+    }
+
+    fn watcher_terminated(&self, termination: Termination) {
+        self.swap_tasks.report_termination(termination)
+    }
+
+    fn mark_degraded(&self, swap_id: SwapId, reason: String) {
+        self.swap_tasks.mark_degraded(swap_id, reason)
+    }
+
+    fn degraded(&self, swap_id: &SwapId) -> Option<String> {
+        self.swap_tasks.degraded(swap_id)
+    }
+}
+
+impl<S> SwapEvents for Facade<S>
+where
+    S: Send + Sync + 'static,
+{
+    fn publish(&self, event: SwapEvent) {
+        self.event_bus.publish(event)
+    }
+
+    fn published_events(&self) -> BTreeMap<&'static str, u64> {
+        self.event_bus.published_events()
+    }
+}
+
 impl<S> executor::Executor for Facade<S>
 where
     S: Send + Sync + 'static,
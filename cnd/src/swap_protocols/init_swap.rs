@@ -1,21 +1,25 @@
 use crate::{
+    db::{Retention, SwapFailures, SwapFees},
     seed::SwapSeed,
     swap_protocols::{
         asset::Asset,
         rfc003::{
-            alice, bob,
-            state_machine::{self, SwapStates},
+            self, alice, bob,
+            events::{FastForwarded, HtlcHistory},
+            fees::{self, PaidFees},
+            state::Actor,
+            state_machine::{self, Error as ErrorState, SwapStates},
             state_store::StateStore,
-            Accept, Ledger, Request,
+            Accept, Ledger, LedgerState, Request, SwapSecrets,
         },
-        Role, SwapId,
+        Role, SwapEvent, SwapEvents, SwapId, SwapTasks, Termination,
     },
     CreateLedgerEvents,
 };
 use futures::{Future, Stream};
-use tokio::executor::Executor;
+use futures_core::TryFutureExt;
+use std::{any::Any, panic::AssertUnwindSafe};
 
-#[allow(clippy::cognitive_complexity)]
 pub fn init_accepted_swap<D, AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
     dependencies: &D,
     request: Request<AL, BL, AA, BA>,
@@ -26,58 +30,280 @@ where
     D: StateStore
         + Clone
         + SwapSeed
-        + Executor
+        + Retention
+        + SwapFailures
+        + SwapFees
+        + PaidFees<AL>
+        + PaidFees<BL>
+        + SwapTasks
+        + SwapEvents
         + CreateLedgerEvents<AL, AA>
         + CreateLedgerEvents<BL, BA>,
+{
+    resume_accepted_swap(
+        dependencies,
+        request,
+        accept,
+        role,
+        HtlcHistory::default(),
+        HtlcHistory::default(),
+    )
+}
+
+/// Like `init_accepted_swap` but the swap starts off with what is known to
+/// have happened to its HTLCs already, e.g. while cnd was not running.
+#[allow(clippy::cognitive_complexity)]
+pub fn resume_accepted_swap<D, AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
+    dependencies: &D,
+    request: Request<AL, BL, AA, BA>,
+    accept: Accept<AL, BL>,
+    role: Role,
+    alpha_history: HtlcHistory<AL, AA>,
+    beta_history: HtlcHistory<BL, BA>,
+) -> anyhow::Result<()>
+where
+    D: StateStore
+        + Clone
+        + SwapSeed
+        + Retention
+        + SwapFailures
+        + SwapFees
+        + PaidFees<AL>
+        + PaidFees<BL>
+        + SwapTasks
+        + SwapEvents
+        + CreateLedgerEvents<AL, AA>
+        + CreateLedgerEvents<BL, BA>,
+{
+    let id = request.swap_id;
+    let seed = SwapSeed::swap_secrets(dependencies, id, role);
+    insert_accepted_swap(dependencies, request.clone(), accept, role, seed);
+
+    let alpha = Box::new(FastForwarded::new(
+        dependencies.create_ledger_events(),
+        alpha_history,
+    ));
+    let beta = Box::new(FastForwarded::new(
+        dependencies.create_ledger_events(),
+        beta_history,
+    ));
+    let (swap_execution, receiver) = state_machine::create_swap(alpha, beta, request, accept);
+
+    spawn(dependencies, id, swap_execution, receiver, role)
+}
+
+/// Put a swap whose HTLCs were not deployed before both of them expired into
+/// its terminal state without watching the ledgers for it.
+pub fn time_out_accepted_swap<D, AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
+    dependencies: &D,
+    request: Request<AL, BL, AA, BA>,
+    accept: Accept<AL, BL>,
+    role: Role,
+) where
+    D: StateStore + SwapSeed,
 {
     let id = request.swap_id;
-    let seed = SwapSeed::swap_seed(dependencies, id);
+    let seed = SwapSeed::swap_secrets(dependencies, id, role);
+    let timed_out = SwapStates::Error(ErrorState(rfc003::Error::TimedOut));
 
+    insert_accepted_swap(dependencies, request, accept, role, seed);
     match role {
         Role::Alice => {
-            let state = alice::State::accepted(request.clone(), accept, seed);
-            StateStore::insert(dependencies, id, state.clone());
-        }
-        Role::Bob => {
-            let state = bob::State::accepted(request.clone(), accept, seed);
-            StateStore::insert(dependencies, id, state.clone());
+            StateStore::update::<alice::State<AL, BL, AA, BA>>(dependencies, &id, timed_out)
         }
-    };
+        Role::Bob => StateStore::update::<bob::State<AL, BL, AA, BA>>(dependencies, &id, timed_out),
+    }
+}
 
-    let alpha = dependencies.create_ledger_events();
-    let beta = dependencies.create_ledger_events();
-    let (swap_execution, receiver) = state_machine::create_swap(alpha, beta, request, accept);
+/// Put the state a swap is in right after it was accepted into the state
+/// store, without watching the ledgers for it.
+pub fn insert_accepted_swap<S, AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
+    state_store: &S,
+    request: Request<AL, BL, AA, BA>,
+    accept: Accept<AL, BL>,
+    role: Role,
+    seed: SwapSecrets,
+) where
+    S: StateStore,
+{
+    let id = request.swap_id;
 
-    spawn(dependencies, id, swap_execution, receiver, role)
+    match role {
+        Role::Alice => StateStore::insert(
+            state_store,
+            id,
+            alice::State::accepted(request, accept, seed),
+        ),
+        Role::Bob => {
+            StateStore::insert(state_store, id, bob::State::accepted(request, accept, seed))
+        }
+    }
 }
 
 fn spawn<D, AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
     dependencies: &D,
     id: SwapId,
-    swap_execution: impl Future<Item = (), Error = ()> + Send + 'static,
+    swap_execution: impl Future<Item = (), Error = rfc003::Error> + Send + 'static,
     receiver: impl Stream<Item = SwapStates<AL, BL, AA, BA>, Error = ()> + Send + 'static,
     role: Role,
 ) -> anyhow::Result<()>
 where
-    D: Executor + StateStore + Clone,
+    D: StateStore
+        + Retention
+        + SwapFailures
+        + SwapFees
+        + PaidFees<AL>
+        + PaidFees<BL>
+        + SwapTasks
+        + SwapEvents
+        + Clone,
 {
-    let mut dependencies = dependencies.clone();
+    let dependencies = dependencies.clone();
 
-    dependencies.spawn(Box::new(swap_execution))?;
-
-    dependencies.spawn(Box::new(receiver.for_each({
+    // The swap execution only resolves successfully once a final state was
+    // reached, which is when the swap becomes subject to the retention policy.
+    //
+    // If it panicked or its watcher stopped instead, the swap is handed to the
+    // supervisor rather than silently no longer being watched.
+    let swap_execution = AssertUnwindSafe(swap_execution).catch_unwind().then({
         let dependencies = dependencies.clone();
-        move |update| {
-            match role {
-                Role::Alice => {
-                    StateStore::update::<alice::State<AL, BL, AA, BA>>(&dependencies, &id, update)
-                }
-                Role::Bob => {
-                    StateStore::update::<bob::State<AL, BL, AA, BA>>(&dependencies, &id, update)
+        move |result| {
+            Box::pin(async move {
+                let reason = match result {
+                    Ok(Ok(())) => {
+                        if let Err(e) = Retention::mark_finished(&dependencies, &id).await {
+                            log::error!("failed to mark swap {} as finished: {:?}", id, e);
+                        }
+                        return Ok(());
+                    }
+                    Ok(Err(rfc003::Error::Btsieve)) => {
+                        String::from("stopped watching the ledgers before the swap was over")
+                    }
+                    // The failure is in the state of the swap already.
+                    Ok(Err(_)) => return Ok(()),
+                    Err(panic) => panic_message(panic),
+                };
+
+                SwapTasks::watcher_terminated(&dependencies, Termination {
+                    swap_id: id,
+                    reason,
+                });
+                Ok::<(), ()>(())
+            })
+            .compat()
+        }
+    });
+
+    dependencies.spawn_swap_task(id, swap_execution)?;
+
+    let state_updates = receiver
+        .for_each({
+            let dependencies = dependencies.clone();
+            move |update| {
+                let event = SwapEvent::from_update(id, &update);
+                let failure = match &update {
+                    SwapStates::Error(ErrorState(e)) => Some(e.clone()),
+                    _ => None,
+                };
+
+                let final_ledger_states = match role {
+                    Role::Alice => {
+                        apply_update::<_, alice::Alice, _, _, _, _>(&dependencies, &id, update)
+                    }
+                    Role::Bob => {
+                        apply_update::<_, bob::Bob, _, _, _, _>(&dependencies, &id, update)
+                    }
+                };
+
+                // Published once the state store reflects the event.
+                if let Some(event) = event {
+                    SwapEvents::publish(&dependencies, event);
                 }
+
+                // The state store only keeps the failure in memory, persist it
+                // so it can still be looked at after a restart.
+                let dependencies = dependencies.clone();
+                Box::pin(async move {
+                    if let Some(e) = failure {
+                        if let Err(db_error) =
+                            SwapFailures::record_swap_failure(&dependencies, &id, &e).await
+                        {
+                            log::error!("failed to record failure of swap {}: {:?}", id, db_error);
+                        }
+                    }
+                    if let Some((alpha_ledger_state, beta_ledger_state)) = final_ledger_states {
+                        if let Err(e) = fees::record_fees(
+                            &dependencies,
+                            id,
+                            role,
+                            alpha_ledger_state,
+                            beta_ledger_state,
+                        )
+                        .await
+                        {
+                            log::error!("failed to record fees of swap {}: {:?}", id, e);
+                        }
+                    }
+                    Ok(())
+                })
+                .compat()
             }
-            Ok(())
-        }
-    })))?;
+        })
+        // The updates end once the swap execution is over, at which point the
+        // remaining tasks of the swap, e.g. its watchers, can be torn down.
+        .then({
+            let dependencies = dependencies.clone();
+            move |_| {
+                dependencies.cancel_swap_tasks(&id);
+                Ok(())
+            }
+        });
+
+    dependencies.spawn_swap_task(id, state_updates)?;
+
     Ok(())
 }
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => format!("panicked: {}", message),
+        Err(panic) => match panic.downcast_ref::<&str>() {
+            Some(message) => format!("panicked: {}", message),
+            None => String::from("panicked"),
+        },
+    }
+}
+
+/// Apply `update` to the state of the swap.
+///
+/// Once the swap is over, the ledger states are returned as they then contain
+/// all transactions of the swap.
+fn apply_update<D, R, AL, BL, AA, BA>(
+    dependencies: &D,
+    id: &SwapId,
+    update: SwapStates<AL, BL, AA, BA>,
+) -> Option<(LedgerState<AL>, LedgerState<BL>)>
+where
+    D: StateStore,
+    R: Actor,
+    AL: Ledger,
+    BL: Ledger,
+    AA: Asset,
+    BA: Asset,
+{
+    let is_final = match update {
+        SwapStates::Final(_) => true,
+        _ => false,
+    };
+
+    StateStore::update::<rfc003::State<AL, BL, AA, BA, R>>(dependencies, id, update);
+
+    if !is_final {
+        return None;
+    }
+
+    match StateStore::get::<rfc003::State<AL, BL, AA, BA, R>>(dependencies, id) {
+        Ok(Some(state)) => Some((state.alpha_ledger_state, state.beta_ledger_state)),
+        _ => None,
+    }
+}
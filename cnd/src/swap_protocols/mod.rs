@@ -4,13 +4,17 @@ mod facade;
 mod init_swap;
 pub mod ledger;
 pub mod rfc003;
+mod swap_events;
 mod swap_id;
+mod swap_tasks;
 
 pub use self::{
     facade::*,
     init_swap::*,
     ledger::{Ledger, LedgerKind},
+    swap_events::*,
     swap_id::*,
+    swap_tasks::*,
 };
 use serde::{Deserialize, Serialize};
 
@@ -1,15 +1,23 @@
 use crate::swap_protocols::{
-    actions::bitcoin::{SendToAddress, SpendOutput},
+    actions::bitcoin::{BumpFee, HtlcOutput, SendToAddress, SpendOutput},
     ledger::Bitcoin,
     rfc003::{
         actions::{FundAction, RedeemAction, RefundAction},
+        events::TopUp,
         secret_source::SecretSource,
         state_machine::HtlcParams,
         Secret,
     },
 };
 use bitcoin::{Amount, OutPoint, Transaction};
-use blockchain_contracts::bitcoin::{rfc003::bitcoin_htlc::BitcoinHtlc, witness::PrimedInput};
+use blockchain_contracts::bitcoin::{
+    rfc003::bitcoin_htlc::BitcoinHtlc,
+    witness::{PrimedInput, UnlockParameters},
+};
+
+/// Signals replaceability (BIP 125) and still enables the lock time of refund
+/// transactions.
+const SEQUENCE_ALLOW_NTIMELOCK_SIGNAL_RBF: u32 = 0xFFFF_FFFD;
 
 impl FundAction<Bitcoin, Amount> for (Bitcoin, Amount) {
     type FundActionOutput = SendToAddress;
@@ -33,18 +41,27 @@ impl RefundAction<Bitcoin, Amount> for (Bitcoin, Amount) {
         htlc_location: OutPoint,
         secret_source: &dyn SecretSource,
         fund_transaction: &Transaction,
+        top_ups: &[TopUp<Bitcoin>],
     ) -> Self::RefundActionOutput {
-        let htlc = BitcoinHtlc::from(htlc_params.clone());
+        let unlock = || {
+            BitcoinHtlc::from(htlc_params.clone())
+                .unlock_after_timeout(&*crate::SECP, secret_source.secp256k1_refund())
+        };
+
+        let outputs = funded_outputs(htlc_location, fund_transaction, top_ups)
+            .into_iter()
+            .map(|(outpoint, value)| htlc_output(outpoint, value, unlock()))
+            .collect();
 
         SpendOutput {
-            output: PrimedInput::new(
-                htlc_location,
-                Amount::from_sat(fund_transaction.output[htlc_location.vout as usize].value),
-                htlc.unlock_after_timeout(&*crate::SECP, secret_source.secp256k1_refund()),
-            ),
+            outputs,
             network: htlc_params.ledger.network,
         }
     }
+
+    fn rebroadcast_refund_action(action: &SpendOutput) -> Option<SpendOutput> {
+        Some(action.clone())
+    }
 }
 
 impl RedeemAction<Bitcoin, Amount> for (Bitcoin, Amount) {
@@ -55,20 +72,189 @@ impl RedeemAction<Bitcoin, Amount> for (Bitcoin, Amount) {
         htlc_location: OutPoint,
         secret_source: &dyn SecretSource,
         secret: Secret,
+        fund_transaction: &Transaction,
+        top_ups: &[TopUp<Bitcoin>],
     ) -> Self::RedeemActionOutput {
-        let htlc = BitcoinHtlc::from(htlc_params.clone());
+        let secret = secret.into_raw_secret();
+        let unlock = || {
+            BitcoinHtlc::from(htlc_params.clone()).unlock_with_secret(
+                &*crate::SECP,
+                secret_source.secp256k1_redeem(),
+                secret,
+            )
+        };
+
+        let outputs = funded_outputs(htlc_location, fund_transaction, top_ups)
+            .into_iter()
+            .map(|(outpoint, value)| htlc_output(outpoint, value, unlock()))
+            .collect();
 
         SpendOutput {
-            output: PrimedInput::new(
-                htlc_location,
-                htlc_params.asset,
-                htlc.unlock_with_secret(
-                    &*crate::SECP,
-                    secret_source.secp256k1_redeem(),
-                    secret.into_raw_secret(),
-                ),
-            ),
+            outputs,
+            network: htlc_params.ledger.network,
+        }
+    }
+
+    fn rebroadcast_redeem_action(action: &SpendOutput) -> Option<SpendOutput> {
+        Some(action.clone())
+    }
+
+    fn bump_fee_action(
+        htlc_params: HtlcParams<Bitcoin, Amount>,
+        htlc_location: OutPoint,
+        fund_transaction: &Transaction,
+        top_ups: &[TopUp<Bitcoin>],
+    ) -> Option<BumpFee> {
+        Some(BumpFee {
+            htlc_outputs: funded_outputs(htlc_location, fund_transaction, top_ups),
             network: htlc_params.ledger.network,
+        })
+    }
+}
+
+/// All outputs that fund the HTLC with the values they have on the chain.
+fn funded_outputs(
+    htlc_location: OutPoint,
+    fund_transaction: &Transaction,
+    top_ups: &[TopUp<Bitcoin>],
+) -> Vec<(OutPoint, Amount)> {
+    std::iter::once((htlc_location, output_value(fund_transaction, htlc_location)))
+        .chain(top_ups.iter().map(top_up_output))
+        .collect()
+}
+
+fn output_value(transaction: &Transaction, outpoint: OutPoint) -> Amount {
+    Amount::from_sat(transaction.output[outpoint.vout as usize].value)
+}
+
+fn top_up_output(top_up: &TopUp<Bitcoin>) -> (OutPoint, Amount) {
+    (
+        top_up.location,
+        output_value(&top_up.transaction, top_up.location),
+    )
+}
+
+fn htlc_output(outpoint: OutPoint, value: Amount, unlock: UnlockParameters) -> HtlcOutput {
+    let unlock = UnlockParameters {
+        sequence: SEQUENCE_ALLOW_NTIMELOCK_SIGNAL_RBF,
+        ..unlock
+    };
+
+    HtlcOutput {
+        output: PrimedInput::new(outpoint, value, unlock),
+        outpoint,
+        value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{seed::Seed, timestamp::Timestamp};
+    use bitcoin::{Network, TxOut};
+    use spectral::prelude::*;
+
+    fn seed() -> Seed {
+        Seed::from(*b"hello world, you are beautiful!!")
+    }
+
+    fn htlc_params() -> HtlcParams<Bitcoin, Amount> {
+        let identity =
+            crate::bitcoin::PublicKey::from_secret_key(&*crate::SECP, &seed().secp256k1_refund());
+
+        HtlcParams {
+            asset: Amount::from_sat(100_000),
+            ledger: Bitcoin::new(Network::Regtest),
+            redeem_identity: identity,
+            refund_identity: identity,
+            expiry: Timestamp::from(2_000_000_000),
+            secret_hash: Secret::from(*b"This is our favourite passphrase").hash(),
+        }
+    }
+
+    fn transaction_to_htlc(htlc_params: &HtlcParams<Bitcoin, Amount>, value: u64) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![TxOut {
+                value,
+                script_pubkey: htlc_params.compute_address().script_pubkey(),
+            }],
         }
     }
+
+    fn first_output(transaction: &Transaction) -> OutPoint {
+        OutPoint {
+            txid: transaction.txid(),
+            vout: 0,
+        }
+    }
+
+    #[test]
+    fn refunds_signal_replaceability_and_keep_their_lock_time() {
+        let seed = seed();
+        let htlc_params = htlc_params();
+        let fund_transaction = transaction_to_htlc(&htlc_params, 100_000);
+        let htlc_location = first_output(&fund_transaction);
+
+        let action = <(Bitcoin, Amount)>::refund_action(
+            htlc_params.clone(),
+            htlc_location,
+            &seed,
+            &fund_transaction,
+            &[],
+        );
+        assert_that(&<(Bitcoin, Amount)>::rebroadcast_refund_action(&action))
+            .is_equal_to(Some(action.clone()));
+
+        let refund = action
+            .spend_to(htlc_params.compute_address())
+            .sign_with_rate(&*crate::SECP, 10)
+            .unwrap();
+
+        assert_that(&refund.input[0].sequence).is_equal_to(0xFFFF_FFFD);
+        assert_that(&refund.lock_time).is_equal_to(2_000_000_000);
+    }
+
+    #[test]
+    fn redeem_spends_the_values_the_htlc_was_funded_with() {
+        let htlc_params = htlc_params();
+        // Together they are more than the agreed 100_000 sats.
+        let fund_transaction = transaction_to_htlc(&htlc_params, 70_000);
+        let top_up = transaction_to_htlc(&htlc_params, 40_000);
+        let htlc_location = first_output(&fund_transaction);
+        let top_ups = vec![TopUp {
+            location: first_output(&top_up),
+            transaction: top_up.clone(),
+        }];
+
+        let redeem = <(Bitcoin, Amount)>::redeem_action(
+            htlc_params.clone(),
+            htlc_location,
+            &seed(),
+            Secret::from(*b"This is our favourite passphrase"),
+            &fund_transaction,
+            &top_ups,
+        );
+        let bump_fee = <(Bitcoin, Amount)>::bump_fee_action(
+            htlc_params,
+            htlc_location,
+            &fund_transaction,
+            &top_ups,
+        )
+        .unwrap();
+
+        let spent = redeem
+            .outputs
+            .iter()
+            .map(|output| (output.outpoint, output.value))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (htlc_location, Amount::from_sat(70_000)),
+            (first_output(&top_up), Amount::from_sat(40_000)),
+        ];
+        assert_that(&spent).is_equal_to(&expected);
+        assert_that(&bump_fee.htlc_outputs).is_equal_to(&expected);
+    }
 }
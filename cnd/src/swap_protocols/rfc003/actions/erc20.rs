@@ -1,9 +1,12 @@
 use crate::{
-    ethereum::{Bytes, Erc20Token},
+    ethereum::{Address as EthereumAddress, Bytes, Erc20Token, Transaction},
     swap_protocols::{
         actions::ethereum::{CallContract, DeployContract},
         ledger::{ethereum::ChainId, Ethereum},
-        rfc003::{state_machine::HtlcParams, Secret},
+        rfc003::{
+            actions::RefundAction, events::TopUp, secret_source::SecretSource,
+            state_machine::HtlcParams, Secret,
+        },
     },
     timestamp::Timestamp,
 };
@@ -50,6 +53,26 @@ pub fn refund_action(
     }
 }
 
+/// Refunding an ERC20 HTLC only needs what is known once the HTLC is funded,
+/// hence it can be expressed in terms of the generic `RefundAction`.
+impl RefundAction<Ethereum, Erc20Token> for (Ethereum, Erc20Token) {
+    type RefundActionOutput = CallContract;
+
+    fn refund_action(
+        htlc_params: HtlcParams<Ethereum, Erc20Token>,
+        htlc_location: EthereumAddress,
+        _secret_source: &dyn SecretSource,
+        _fund_transaction: &Transaction,
+        _top_ups: &[TopUp<Ethereum>],
+    ) -> Self::RefundActionOutput {
+        refund_action(
+            htlc_params.ledger.chain_id,
+            htlc_params.expiry,
+            htlc_location,
+        )
+    }
+}
+
 pub fn redeem_action(
     alpha_htlc_location: crate::ethereum::Address,
     secret: Secret,
@@ -5,6 +5,7 @@ use crate::{
         ledger::Ethereum,
         rfc003::{
             actions::{FundAction, RedeemAction, RefundAction},
+            events::TopUp,
             secret_source::SecretSource,
             state_machine::HtlcParams,
             Secret,
@@ -28,6 +29,7 @@ impl RefundAction<Ethereum, EtherQuantity> for (Ethereum, EtherQuantity) {
         htlc_location: EthereumAddress,
         _secret_source: &dyn SecretSource,
         _fund_transaction: &Transaction,
+        _top_ups: &[TopUp<Ethereum>],
     ) -> Self::RefundActionOutput {
         let gas_limit = EtherHtlc::tx_gas_limit();
 
@@ -48,6 +50,8 @@ impl RedeemAction<Ethereum, EtherQuantity> for (Ethereum, EtherQuantity) {
         htlc_location: EthereumAddress,
         _secret_source: &dyn SecretSource,
         secret: Secret,
+        _fund_transaction: &Transaction,
+        _top_ups: &[TopUp<Ethereum>],
     ) -> Self::RedeemActionOutput {
         let data = Bytes::from(secret.as_raw_secret().to_vec());
         let gas_limit = EtherHtlc::tx_gas_limit();
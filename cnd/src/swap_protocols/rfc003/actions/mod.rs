@@ -3,8 +3,11 @@ pub mod erc20;
 pub mod ether;
 
 use crate::swap_protocols::{
+    actions::bitcoin::{BumpFee, SpendOutput},
     asset::Asset,
-    rfc003::{secret_source::SecretSource, state_machine::HtlcParams, Ledger, Secret},
+    rfc003::{
+        events::TopUp, secret_source::SecretSource, state_machine::HtlcParams, Ledger, Secret,
+    },
 };
 use std::marker::PhantomData;
 
@@ -22,6 +25,11 @@ pub enum Action<Accept, Decline, Deploy, Fund, Redeem, Refund> {
     Fund(Fund),
     Redeem(Redeem),
     Refund(Refund),
+    /// Only available next to redeeming bitcoin.
+    BumpFee(BumpFee),
+    /// Replaces the redeem or refund transaction cnd handed out before with
+    /// one paying a higher fee.
+    Rebroadcast(SpendOutput),
 }
 
 pub trait FundAction<L: Ledger, A: Asset> {
@@ -38,7 +46,14 @@ pub trait RefundAction<L: Ledger, A: Asset> {
         htlc_location: L::HtlcLocation,
         secret_source: &dyn SecretSource,
         fund_transaction: &L::Transaction,
+        top_ups: &[TopUp<L>],
     ) -> Self::RefundActionOutput;
+
+    /// The refund to sign again with a higher fee, only possible on ledgers
+    /// that allow replacing unconfirmed transactions.
+    fn rebroadcast_refund_action(_action: &Self::RefundActionOutput) -> Option<SpendOutput> {
+        None
+    }
 }
 
 pub trait RedeemAction<L: Ledger, A: Asset> {
@@ -49,7 +64,26 @@ pub trait RedeemAction<L: Ledger, A: Asset> {
         htlc_location: L::HtlcLocation,
         secret_source: &dyn SecretSource,
         secret: Secret,
+        fund_transaction: &L::Transaction,
+        top_ups: &[TopUp<L>],
     ) -> Self::RedeemActionOutput;
+
+    /// The redeem to sign again with a higher fee, only possible on ledgers
+    /// that allow replacing unconfirmed transactions.
+    fn rebroadcast_redeem_action(_action: &Self::RedeemActionOutput) -> Option<SpendOutput> {
+        None
+    }
+
+    /// Speeds up a redeem transaction that is stuck because of its fee, only
+    /// possible on ledgers that allow spending unconfirmed outputs.
+    fn bump_fee_action(
+        _htlc_params: HtlcParams<L, A>,
+        _htlc_location: L::HtlcLocation,
+        _fund_transaction: &L::Transaction,
+        _top_ups: &[TopUp<L>],
+    ) -> Option<BumpFee> {
+        None
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -91,5 +125,10 @@ mod tests {
         assert_eq!(ActionKind::Refund.to_string(), "refund".to_string());
         assert_eq!(ActionKind::Redeem.to_string(), "redeem".to_string());
         assert_eq!(ActionKind::Deploy.to_string(), "deploy".to_string());
+        assert_eq!(ActionKind::BumpFee.to_string(), "bump_fee".to_string());
+        assert_eq!(
+            ActionKind::Rebroadcast.to_string(),
+            "rebroadcast".to_string()
+        );
     }
 }
@@ -3,9 +3,9 @@ use crate::swap_protocols::{
     asset::Asset,
     rfc003::{
         actions::{Accept, Action, Decline, FundAction, RedeemAction, RefundAction},
-        alice::{self, SwapCommunication},
+        alice,
         state_machine::HtlcParams,
-        Ledger, LedgerState,
+        Ledger, LedgerState, SwapCommunication,
     },
 };
 use std::convert::Infallible;
@@ -48,34 +48,63 @@ where
             IncorrectlyFunded {
                 htlc_location,
                 fund_transaction,
+                top_ups,
                 ..
             } => vec![Action::Refund(<(AL, AA)>::refund_action(
                 HtlcParams::new_alpha_params(request, response),
                 htlc_location.clone(),
                 &*self.secret_source,
                 fund_transaction,
+                top_ups,
             ))],
             Funded {
                 htlc_location,
                 fund_transaction,
+                top_ups,
                 ..
             } => vec![Action::Refund(<(AL, AA)>::refund_action(
                 HtlcParams::new_alpha_params(request, response),
                 htlc_location.clone(),
                 &*self.secret_source,
                 fund_transaction,
+                top_ups,
             ))],
             _ => vec![],
         };
 
-        if let Funded { htlc_location, .. } = beta_state {
+        if let Funded {
+            htlc_location,
+            fund_transaction,
+            top_ups,
+            ..
+        } = beta_state
+        {
             actions.push(Action::Redeem(<(BL, BA)>::redeem_action(
                 HtlcParams::new_beta_params(request, response),
                 htlc_location.clone(),
                 &*self.secret_source,
                 self.secret_source.secret(),
+                fund_transaction,
+                top_ups,
             )));
+            actions.extend(
+                <(BL, BA)>::bump_fee_action(
+                    HtlcParams::new_beta_params(request, response),
+                    htlc_location.clone(),
+                    fund_transaction,
+                    top_ups,
+                )
+                .map(Action::BumpFee),
+            );
         }
+
+        let rebroadcast = actions.iter().find_map(|action| match action {
+            Action::Redeem(redeem) => <(BL, BA)>::rebroadcast_redeem_action(redeem),
+            Action::Refund(refund) => <(AL, AA)>::rebroadcast_refund_action(refund),
+            _ => None,
+        });
+        actions.extend(rebroadcast.map(Action::Rebroadcast));
+
         actions
     }
 }
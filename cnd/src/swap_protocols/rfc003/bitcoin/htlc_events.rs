@@ -1,79 +1,45 @@
 use crate::{
     btsieve::{
-        bitcoin::{BitcoindConnector, TransactionExt, TransactionPattern},
-        MatchingTransactions,
+        bitcoin::{BitcoinConnector, Spend, TransactionExt, TransactionPattern},
+        MatchingTransactions, PastTransactions,
     },
-    first_or_else::StreamExt,
     swap_protocols::{
         ledger::Bitcoin,
         rfc003::{
             self,
-            bitcoin::extract_secret::extract_secret,
             events::{
-                Deployed, DeployedFuture, Funded, FundedFuture, HtlcEvents, Redeemed,
-                RedeemedOrRefundedFuture, Refunded,
+                first_matching_transaction, Deployed, DeployedFuture, FindHtlc, Funded,
+                FundedFuture, FundedHtlcFuture, HtlcEvents, HtlcHistory, Redeemed,
+                RedeemedOrRefundedFuture, Refunded, ScanHtlc, TopUp,
             },
             state_machine::HtlcParams,
         },
     },
+    timestamp::Timestamp,
 };
-use bitcoin::{Amount, OutPoint};
-use futures::{
-    future::{self, Either},
-    Future, Stream,
-};
+use async_trait::async_trait;
+use bitcoin::{hashes::sha256d, Amount, OutPoint};
+use futures::future::{self, Either};
+use futures_core::{future::ready, FutureExt as _, StreamExt as _, TryFutureExt as _};
 
-impl HtlcEvents<Bitcoin, Amount> for BitcoindConnector {
+impl HtlcEvents<Bitcoin, Amount> for BitcoinConnector {
     fn htlc_deployed(
         &self,
         htlc_params: HtlcParams<Bitcoin, Amount>,
     ) -> Box<DeployedFuture<Bitcoin>> {
-        let future = self
-            .matching_transactions(TransactionPattern {
-                to_address: Some(htlc_params.compute_address()),
-                from_outpoint: None,
-                unlock_script: None,
-            }, None)
-            .map_err(|_| rfc003::Error::Btsieve)
-            .first_or_else(|| {
-                log::warn!("stream of matching transactions ended before yielding a value");
-                rfc003::Error::Btsieve
-            })
-            .and_then({
-                let htlc_params = htlc_params.clone();
-
-                move |tx| {
-                    let (vout, _txout) = tx.find_output(&htlc_params.compute_address())
-                        .ok_or_else(|| {
-                            rfc003::Error::Internal(
-                                "Query returned Bitcoin transaction that didn't match the requested address".into(),
-                            )
-                        })?;
-
-                    Ok(Deployed {
-                        location: OutPoint {
-                            txid: tx.txid(),
-                            vout,
-                        },
-                        transaction: tx,
-                    })
-                }
-            });
-
-        Box::new(future)
+        Box::new(
+            htlc_deployed(self.clone(), htlc_params, None)
+                .boxed()
+                .compat(),
+        )
     }
 
     fn htlc_funded(
         &self,
-        _htlc_params: HtlcParams<Bitcoin, Amount>,
+        htlc_params: HtlcParams<Bitcoin, Amount>,
         htlc_deployment: &Deployed<Bitcoin>,
     ) -> Box<FundedFuture<Bitcoin, Amount>> {
-        let tx = &htlc_deployment.transaction;
-        let asset = Amount::from_sat(tx.output[htlc_deployment.location.vout as usize].value);
-        Box::new(future::ok(Funded {
-            transaction: tx.clone(),
-            asset,
-        }))
+        Box::new(future::ok(htlc_funded(&htlc_params, htlc_deployment)))
     }
 
     fn htlc_redeemed_or_refunded(
@@ -82,68 +48,353 @@ impl HtlcEvents<Bitcoin, Amount> for BitcoindConnector {
         htlc_deployment: &Deployed<Bitcoin>,
         _htlc_funding: &Funded<Bitcoin, Amount>,
     ) -> Box<RedeemedOrRefundedFuture<Bitcoin>> {
-        let refunded_future = {
-            self.matching_transactions(
-                TransactionPattern {
-                    to_address: None,
-                    from_outpoint: Some(htlc_deployment.location),
-                    unlock_script: Some(vec![vec![]]),
-                },
-                None,
+        Box::new(
+            htlc_redeemed_or_refunded(self.clone(), htlc_params, spent_pattern(htlc_deployment))
+                .boxed()
+                .compat(),
+        )
+    }
+
+    fn htlc_topped_up(
+        &self,
+        htlc_params: HtlcParams<Bitcoin, Amount>,
+        deployment: &Deployed<Bitcoin>,
+        htlc_funding: &Funded<Bitcoin, Amount>,
+    ) -> Box<FundedFuture<Bitcoin, Amount>> {
+        let known_transactions = std::iter::once(&deployment.transaction)
+            .chain(
+                htlc_funding
+                    .top_ups
+                    .iter()
+                    .map(|top_up| &top_up.transaction),
             )
-            .map_err(|_| rfc003::Error::Btsieve)
-            .first_or_else(|| {
-                log::warn!("stream of matching transactions ended before yielding a value");
-                rfc003::Error::Btsieve
-            })
-            .and_then(|transaction| Ok(Refunded { transaction }))
-        };
+            .map(|transaction| transaction.txid())
+            .collect::<Vec<_>>();
 
-        let redeemed_future = {
-            self.matching_transactions(
-                TransactionPattern {
-                    to_address: None,
-                    from_outpoint: Some(htlc_deployment.location),
-                    unlock_script: Some(vec![vec![1u8]]),
-                },
-                None,
+        Box::new(
+            next_top_up(
+                self.clone(),
+                htlc_params,
+                known_transactions,
+                htlc_funding.clone(),
             )
-            .map_err(|_| rfc003::Error::Btsieve)
-            .first_or_else(|| {
-                log::warn!("stream of matching transactions ended before yielding a value");
-                rfc003::Error::Btsieve
-            })
-            .and_then({
-                let htlc_params = htlc_params.clone();
-
-                move |tx| {
-                    let secret =
-                        extract_secret(&tx, &htlc_params.secret_hash).ok_or_else(|| {
-                            log::error!("Redeem transaction didn't have secret it in: {:?}", tx);
-                            rfc003::Error::Internal(
-                                "Redeem transaction didn't have the secret in it".into(),
-                            )
-                        })?;
-
-                    Ok(Redeemed {
-                        transaction: tx,
-                        secret,
-                    })
-                }
-            })
-        };
+            .boxed()
+            .compat(),
+        )
+    }
+}
+
+impl FindHtlc<Bitcoin, Amount> for BitcoinConnector {
+    fn find_funded_htlc(
+        &self,
+        htlc_params: HtlcParams<Bitcoin, Amount>,
+        since: Timestamp,
+    ) -> Box<FundedHtlcFuture<Bitcoin, Amount>> {
+        let connector = self.clone();
 
         Box::new(
-            redeemed_future
-                .select2(refunded_future)
-                .map(|tx| match tx {
-                    Either::A((tx, _)) => Either::A(tx),
-                    Either::B((tx, _)) => Either::B(tx),
-                })
-                .map_err(|either| match either {
-                    Either::A((error, _)) => error,
-                    Either::B((error, _)) => error,
-                }),
+            async move {
+                let deployed =
+                    htlc_deployed(connector, htlc_params.clone(), Some(since.into())).await?;
+                let funded = htlc_funded(&htlc_params, &deployed);
+
+                Ok((deployed, funded))
+            }
+            .boxed()
+            .compat(),
         )
     }
 }
+
+#[async_trait]
+impl ScanHtlc<Bitcoin, Amount> for BitcoinConnector {
+    async fn scan_htlc(
+        &self,
+        htlc_params: HtlcParams<Bitcoin, Amount>,
+        since: Timestamp,
+    ) -> anyhow::Result<HtlcHistory<Bitcoin, Amount>> {
+        let since = u32::from(since);
+
+        let deployed = match self
+            .past_transaction(deployed_pattern(&htlc_params), since)
+            .await?
+        {
+            Some(transaction) => htlc_deployment(&htlc_params, transaction)?,
+            None => return Ok(HtlcHistory::default()),
+        };
+        let funded = htlc_funded(&htlc_params, &deployed);
+
+        let pattern = spent_pattern(&deployed);
+        let redeemed_or_refunded = match self.past_transaction(pattern.clone(), since).await? {
+            Some(transaction) => Some(htlc_spent(&pattern, &htlc_params, transaction)?),
+            None => None,
+        };
+
+        Ok(HtlcHistory {
+            deployed: Some(deployed),
+            funded: Some(funded),
+            redeemed_or_refunded,
+        })
+    }
+}
+
+fn deployed_pattern(htlc_params: &HtlcParams<Bitcoin, Amount>) -> TransactionPattern {
+    TransactionPattern {
+        to_address: Some(htlc_params.compute_address()),
+        from_outpoint: None,
+        unlock_script: None,
+    }
+}
+
+/// Matches both the redeem and the refund transaction, they are told apart by
+/// `TransactionPattern::spend`.
+fn spent_pattern(htlc_deployment: &Deployed<Bitcoin>) -> TransactionPattern {
+    TransactionPattern {
+        to_address: None,
+        from_outpoint: Some(htlc_deployment.location),
+        unlock_script: None,
+    }
+}
+
+/// Every output of `tx` that pays to the HTLC, a single transaction may pay to
+/// it several times.
+fn htlc_outputs(
+    htlc_params: &HtlcParams<Bitcoin, Amount>,
+    tx: &bitcoin::Transaction,
+) -> Vec<(OutPoint, Amount)> {
+    let txid = tx.txid();
+
+    tx.find_outputs(&htlc_params.compute_address())
+        .into_iter()
+        .map(|(vout, txout)| (OutPoint { txid, vout }, Amount::from_sat(txout.value)))
+        .collect()
+}
+
+fn htlc_deployment(
+    htlc_params: &HtlcParams<Bitcoin, Amount>,
+    tx: bitcoin::Transaction,
+) -> Result<Deployed<Bitcoin>, rfc003::Error> {
+    let (location, _value) = htlc_outputs(htlc_params, &tx)
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            rfc003::Error::Internal(
+                "Query returned Bitcoin transaction that didn't match the requested address".into(),
+            )
+        })?;
+
+    Ok(Deployed {
+        location,
+        transaction: tx,
+    })
+}
+
+/// The transaction that deploys the HTLC also funds it, its outputs to the
+/// HTLC other than the deployed location are top ups.
+fn htlc_funded(
+    htlc_params: &HtlcParams<Bitcoin, Amount>,
+    htlc_deployment: &Deployed<Bitcoin>,
+) -> Funded<Bitcoin, Amount> {
+    let tx = &htlc_deployment.transaction;
+    let funding = Funded {
+        transaction: tx.clone(),
+        asset: Amount::from_sat(0),
+        top_ups: vec![],
+    };
+
+    htlc_outputs(htlc_params, tx)
+        .into_iter()
+        .fold(funding, |mut funding, (location, value)| {
+            if location != htlc_deployment.location {
+                funding.top_ups.push(TopUp {
+                    transaction: tx.clone(),
+                    location,
+                });
+            }
+            funding.asset += value;
+
+            funding
+        })
+}
+
+/// Adds every output of `top_up` that pays to the HTLC to what funded the
+/// HTLC.
+fn htlc_topped_up(
+    htlc_params: &HtlcParams<Bitcoin, Amount>,
+    htlc_funding: Funded<Bitcoin, Amount>,
+    top_up: bitcoin::Transaction,
+) -> Funded<Bitcoin, Amount> {
+    htlc_outputs(htlc_params, &top_up).into_iter().fold(
+        htlc_funding,
+        |mut funding, (location, value)| {
+            funding.top_ups.push(TopUp {
+                transaction: top_up.clone(),
+                location,
+            });
+            funding.asset += value;
+
+            funding
+        },
+    )
+}
+
+fn htlc_spent(
+    pattern: &TransactionPattern,
+    htlc_params: &HtlcParams<Bitcoin, Amount>,
+    tx: bitcoin::Transaction,
+) -> Result<Either<Redeemed<Bitcoin>, Refunded<Bitcoin>>, rfc003::Error> {
+    match pattern.spend(&tx, &htlc_params.secret_hash) {
+        Some(Spend::Redeem { secret }) => Ok(Either::A(Redeemed {
+            transaction: tx,
+            secret,
+        })),
+        Some(Spend::Refund) => Ok(Either::B(Refunded { transaction: tx })),
+        None => {
+            log::error!(
+                "HTLC was spent neither by redeeming nor refunding: {:?}",
+                tx
+            );
+            Err(rfc003::Error::Internal(
+                "HTLC was spent neither by redeeming nor refunding".into(),
+            ))
+        }
+    }
+}
+
+async fn htlc_deployed(
+    connector: BitcoinConnector,
+    htlc_params: HtlcParams<Bitcoin, Amount>,
+    reference_timestamp: Option<u32>,
+) -> Result<Deployed<Bitcoin>, rfc003::Error> {
+    let transactions =
+        connector.matching_transactions(deployed_pattern(&htlc_params), reference_timestamp);
+    let transaction = first_matching_transaction(transactions).await?;
+
+    htlc_deployment(&htlc_params, transaction)
+}
+
+async fn htlc_redeemed_or_refunded(
+    connector: BitcoinConnector,
+    htlc_params: HtlcParams<Bitcoin, Amount>,
+    pattern: TransactionPattern,
+) -> Result<Either<Redeemed<Bitcoin>, Refunded<Bitcoin>>, rfc003::Error> {
+    let transactions = connector.matching_transactions(pattern.clone(), None);
+    let transaction = first_matching_transaction(transactions).await?;
+
+    htlc_spent(&pattern, &htlc_params, transaction)
+}
+
+/// Resolves with `htlc_funding` plus the first transaction to the HTLC address
+/// that is none of `known_transactions`.
+async fn next_top_up(
+    connector: BitcoinConnector,
+    htlc_params: HtlcParams<Bitcoin, Amount>,
+    known_transactions: Vec<sha256d::Hash>,
+    htlc_funding: Funded<Bitcoin, Amount>,
+) -> Result<Funded<Bitcoin, Amount>, rfc003::Error> {
+    let transactions = connector
+        .matching_transactions(deployed_pattern(&htlc_params), None)
+        .filter(move |transaction| ready(!known_transactions.contains(&transaction.txid())))
+        .boxed();
+    let transaction = first_matching_transaction(transactions).await?;
+
+    Ok(htlc_topped_up(&htlc_params, htlc_funding, transaction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        seed::Seed,
+        swap_protocols::rfc003::{secret_source::SecretSource, Secret},
+    };
+    use bitcoin::{Network, Script, TxOut};
+    use spectral::prelude::*;
+
+    fn htlc_params() -> HtlcParams<Bitcoin, Amount> {
+        let seed = Seed::from(*b"hello world, you are beautiful!!");
+        let identity =
+            crate::bitcoin::PublicKey::from_secret_key(&*crate::SECP, &seed.secp256k1_refund());
+
+        HtlcParams {
+            asset: Amount::from_sat(100_000),
+            ledger: Bitcoin::new(Network::Regtest),
+            redeem_identity: identity,
+            refund_identity: identity,
+            expiry: Timestamp::from(2_000_000_000),
+            secret_hash: Secret::from(*b"This is our favourite passphrase").hash(),
+        }
+    }
+
+    /// A transaction with an output for each value, the ones flagged with
+    /// `true` pay to the HTLC.
+    fn transaction(outputs: &[(u64, bool)]) -> bitcoin::Transaction {
+        let htlc = htlc_params().compute_address().script_pubkey();
+
+        bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: Vec::new(),
+            output: outputs
+                .iter()
+                .map(|(value, to_htlc)| TxOut {
+                    value: *value,
+                    script_pubkey: if *to_htlc {
+                        htlc.clone()
+                    } else {
+                        Script::new()
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    fn location(transaction: &bitcoin::Transaction, vout: u32) -> OutPoint {
+        OutPoint {
+            txid: transaction.txid(),
+            vout,
+        }
+    }
+
+    #[test]
+    fn top_ups_add_to_the_funded_amount() {
+        let htlc_params = htlc_params();
+        let deployment = transaction(&[(70_000, true)]);
+        let top_up = transaction(&[(1_000, false), (30_000, true)]);
+
+        let deployed = htlc_deployment(&htlc_params, deployment.clone()).unwrap();
+        let funded = htlc_topped_up(
+            &htlc_params,
+            htlc_funded(&htlc_params, &deployed),
+            top_up.clone(),
+        );
+
+        assert_that(&funded).is_equal_to(Funded {
+            transaction: deployment,
+            asset: Amount::from_sat(100_000),
+            top_ups: vec![TopUp {
+                location: location(&top_up, 1),
+                transaction: top_up,
+            }],
+        });
+    }
+
+    #[test]
+    fn every_output_to_the_htlc_funds_it() {
+        let htlc_params = htlc_params();
+        let deployment = transaction(&[(40_000, true), (5_000, false), (60_000, true)]);
+
+        let deployed = htlc_deployment(&htlc_params, deployment.clone()).unwrap();
+        let funded = htlc_funded(&htlc_params, &deployed);
+
+        assert_that(&deployed.location).is_equal_to(location(&deployment, 0));
+        assert_that(&funded).is_equal_to(Funded {
+            transaction: deployment.clone(),
+            asset: Amount::from_sat(100_000),
+            top_ups: vec![TopUp {
+                location: location(&deployment, 2),
+                transaction: deployment,
+            }],
+        });
+    }
+}
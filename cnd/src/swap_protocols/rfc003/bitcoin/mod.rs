@@ -1,29 +1,38 @@
-mod extract_secret;
 mod htlc_events;
 
 use crate::swap_protocols::{
     ledger::Bitcoin,
-    rfc003::{state_machine::HtlcParams, Ledger},
+    rfc003::{state_machine::HtlcParams, Ledger, SecretSource},
 };
 use bitcoin::{
     hashes::{hash160, Hash},
-    Address, Amount, OutPoint,
+    Address, Amount, OutPoint, Script,
 };
-use blockchain_contracts::bitcoin::rfc003::bitcoin_htlc::BitcoinHtlc;
+use blockchain_contracts::bitcoin::rfc003::bitcoin_htlc::{BitcoinHtlc, CONTRACT_TEMPLATE};
 
 pub use self::htlc_events::*;
 
 impl Ledger for Bitcoin {
     type HtlcLocation = OutPoint;
+
+    fn derive_redeem_identity(secret_source: &dyn SecretSource) -> Option<Self::Identity> {
+        Some(crate::bitcoin::PublicKey::from_secret_key(
+            &*crate::SECP,
+            &secret_source.secp256k1_redeem(),
+        ))
+    }
+
+    fn derive_refund_identity(secret_source: &dyn SecretSource) -> Option<Self::Identity> {
+        Some(crate::bitcoin::PublicKey::from_secret_key(
+            &*crate::SECP,
+            &secret_source.secp256k1_refund(),
+        ))
+    }
 }
 
 impl From<HtlcParams<Bitcoin, Amount>> for BitcoinHtlc {
     fn from(htlc_params: HtlcParams<Bitcoin, Amount>) -> Self {
-        let refund_public_key = htlc_params.refund_identity.into_inner();
-        let redeem_public_key = htlc_params.redeem_identity.into_inner();
-
-        let refund_identity = hash160::Hash::hash(&refund_public_key.key.serialize());
-        let redeem_identity = hash160::Hash::hash(&redeem_public_key.key.serialize());
+        let (refund_identity, redeem_identity) = htlc_params.identity_hashes();
 
         BitcoinHtlc::new(
             htlc_params.expiry.into(),
@@ -38,4 +47,31 @@ impl HtlcParams<Bitcoin, Amount> {
     pub fn compute_address(&self) -> Address {
         BitcoinHtlc::from(self.clone()).compute_address(self.ledger.network)
     }
+
+    /// The script the HTLC address commits to.
+    ///
+    /// `BitcoinHtlc` keeps its script to itself, hence the contract template
+    /// is filled in the same way here.
+    pub fn script(&self) -> Script {
+        let (refund_identity, redeem_identity) = self.identity_hashes();
+        let expiry: u32 = self.expiry.into();
+
+        let mut script = CONTRACT_TEMPLATE.to_vec();
+        script[7..39].copy_from_slice(&self.secret_hash.into_raw());
+        script[43..63].copy_from_slice(&redeem_identity[..]);
+        script[65..69].copy_from_slice(&expiry.to_le_bytes());
+        script[74..94].copy_from_slice(&refund_identity[..]);
+
+        Script::from(script)
+    }
+
+    fn identity_hashes(&self) -> (hash160::Hash, hash160::Hash) {
+        let refund_public_key = self.refund_identity.into_inner();
+        let redeem_public_key = self.redeem_identity.into_inner();
+
+        (
+            hash160::Hash::hash(&refund_public_key.key.serialize()),
+            hash160::Hash::hash(&redeem_public_key.key.serialize()),
+        )
+    }
 }
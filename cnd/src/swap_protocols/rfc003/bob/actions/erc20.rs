@@ -6,9 +6,9 @@ use crate::{
         ledger::Ethereum,
         rfc003::{
             actions::{erc20, Accept, Action, Decline, FundAction, RedeemAction, RefundAction},
-            bob::{self, SwapCommunication},
+            bob,
             state_machine::HtlcParams,
-            Ledger, LedgerState,
+            Ledger, LedgerState, SwapCommunication,
         },
     },
 };
@@ -51,12 +51,23 @@ where
         use self::LedgerState::*;
 
         let mut actions = match (alpha_state, beta_state, self.secret) {
-            (Funded { htlc_location, .. }, _, Some(secret)) => {
+            (
+                Funded {
+                    htlc_location,
+                    fund_transaction,
+                    top_ups,
+                    ..
+                },
+                _,
+                Some(secret),
+            ) => {
                 vec![Action::Redeem(<(AL, AA)>::redeem_action(
                     HtlcParams::new_alpha_params(request, response),
                     htlc_location.clone(),
                     &*self.secret_source,
                     secret,
+                    fund_transaction,
+                    top_ups,
                 ))]
             }
             (Funded { .. }, NotDeployed, _) => vec![Action::Deploy(erc20::deploy_action(
@@ -72,13 +83,41 @@ where
             _ => vec![],
         };
 
-        if let Funded { htlc_location, .. } = beta_state {
+        if let (
+            Funded {
+                htlc_location,
+                fund_transaction,
+                top_ups,
+                ..
+            },
+            Some(_),
+        ) = (alpha_state, self.secret)
+        {
+            actions.extend(
+                <(AL, AA)>::bump_fee_action(
+                    HtlcParams::new_alpha_params(request, response),
+                    htlc_location.clone(),
+                    fund_transaction,
+                    top_ups,
+                )
+                .map(Action::BumpFee),
+            );
+        }
+
+        if let Funded { htlc_location, .. } | IncorrectlyFunded { htlc_location, .. } = beta_state {
             actions.push(Action::Refund(erc20::refund_action(
                 request.beta_ledger.chain_id,
                 request.beta_expiry,
                 *htlc_location,
             )));
         }
+
+        let rebroadcast = actions.iter().find_map(|action| match action {
+            Action::Redeem(redeem) => <(AL, AA)>::rebroadcast_redeem_action(redeem),
+            _ => None,
+        });
+        actions.extend(rebroadcast.map(Action::Rebroadcast));
+
         actions
     }
 }
@@ -131,6 +170,13 @@ where
         if let Funded {
             htlc_location,
             fund_transaction,
+            top_ups,
+            ..
+        }
+        | IncorrectlyFunded {
+            htlc_location,
+            fund_transaction,
+            top_ups,
             ..
         } = beta_state
         {
@@ -139,8 +185,16 @@ where
                 htlc_location.clone(),
                 &*self.secret_source,
                 fund_transaction,
+                top_ups,
             )))
         }
+
+        let rebroadcast = actions.iter().find_map(|action| match action {
+            Action::Refund(refund) => <(BL, BA)>::rebroadcast_refund_action(refund),
+            _ => None,
+        });
+        actions.extend(rebroadcast.map(Action::Rebroadcast));
+
         actions
     }
 }
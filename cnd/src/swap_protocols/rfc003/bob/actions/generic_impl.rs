@@ -3,9 +3,9 @@ use crate::swap_protocols::{
     asset::Asset,
     rfc003::{
         actions::{Accept, Action, Decline, FundAction, RedeemAction, RefundAction},
-        bob::{self, SwapCommunication},
+        bob,
         state_machine::HtlcParams,
-        Ledger, LedgerState,
+        Ledger, LedgerState, SwapCommunication,
     },
 };
 use std::convert::Infallible;
@@ -49,12 +49,23 @@ where
 
         use self::LedgerState::*;
         let mut actions = match (alpha_state, beta_state, self.secret) {
-            (Funded { htlc_location, .. }, _, Some(secret)) => {
+            (
+                Funded {
+                    htlc_location,
+                    fund_transaction,
+                    top_ups,
+                    ..
+                },
+                _,
+                Some(secret),
+            ) => {
                 vec![Action::Redeem(<(AL, AA)>::redeem_action(
                     HtlcParams::new_alpha_params(request, response),
                     htlc_location.clone(),
                     &*self.secret_source,
                     secret,
+                    fund_transaction,
+                    top_ups,
                 ))]
             }
             (Funded { .. }, NotDeployed, _) => vec![Action::Fund(<(BL, BA)>::fund_action(
@@ -63,9 +74,37 @@ where
             _ => vec![],
         };
 
+        if let (
+            Funded {
+                htlc_location,
+                fund_transaction,
+                top_ups,
+                ..
+            },
+            Some(_),
+        ) = (alpha_state, self.secret)
+        {
+            actions.extend(
+                <(AL, AA)>::bump_fee_action(
+                    HtlcParams::new_alpha_params(request, response),
+                    htlc_location.clone(),
+                    fund_transaction,
+                    top_ups,
+                )
+                .map(Action::BumpFee),
+            );
+        }
+
         if let Funded {
             htlc_location,
             fund_transaction,
+            top_ups,
+            ..
+        }
+        | IncorrectlyFunded {
+            htlc_location,
+            fund_transaction,
+            top_ups,
             ..
         } = beta_state
         {
@@ -74,9 +113,17 @@ where
                 htlc_location.clone(),
                 &*self.secret_source,
                 fund_transaction,
+                top_ups,
             )))
         }
 
+        let rebroadcast = actions.iter().find_map(|action| match action {
+            Action::Redeem(redeem) => <(AL, AA)>::rebroadcast_redeem_action(redeem),
+            Action::Refund(refund) => <(BL, BA)>::rebroadcast_refund_action(refund),
+            _ => None,
+        });
+        actions.extend(rebroadcast.map(Action::Rebroadcast));
+
         actions
     }
 }
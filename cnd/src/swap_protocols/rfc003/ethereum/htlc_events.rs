@@ -1,37 +1,44 @@
 use crate::{
     btsieve::{
-        ethereum::{Event, Topic, TransactionPattern, Web3Connector},
-        MatchingTransactions,
+        ethereum::{
+            Event, Spend, Topic, TransactionPattern, Web3Connector, REDEEM_LOG_MSG, REFUND_LOG_MSG,
+        },
+        MatchingTransactions, PastTransactions,
     },
     ethereum::{
-        Address, CalculateContractAddress, Erc20Token, EtherQuantity, Transaction,
+        Address, Bytes, CalculateContractAddress, Erc20Token, EtherQuantity, Transaction,
         TransactionAndReceipt, H256,
     },
-    first_or_else::StreamExt,
     swap_protocols::{
         asset::Asset,
         ledger::Ethereum,
         rfc003::{
             self,
             events::{
-                Deployed, DeployedFuture, Funded, FundedFuture, HtlcEvents, Redeemed,
-                RedeemedOrRefundedFuture, Refunded,
+                first_matching_transaction, Deployed, DeployedFuture, FindHtlc, Funded,
+                FundedFuture, FundedHtlcFuture, HtlcEvents, HtlcHistory, Redeemed,
+                RedeemedOrRefundedFuture, Refunded, ScanHtlc,
             },
             state_machine::HtlcParams,
-            Secret,
+            SecretHash,
         },
     },
+    timestamp::Timestamp,
 };
-use futures::{
-    future::{self, Either},
-    Future, Stream,
+use async_trait::async_trait;
+use futures::future::{self, Either};
+use futures_core::{
+    compat::Future01CompatExt,
+    future::{select, BoxFuture},
+    FutureExt as _, TryFutureExt as _,
 };
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+
+/// How often the pending transactions are looked at for a redeem of the HTLC.
+const PENDING_TRANSACTIONS_INTERVAL: Duration = Duration::from_secs(1);
 
 lazy_static::lazy_static! {
-    /// keccak256(Redeemed())
-    pub static ref REDEEM_LOG_MSG: H256 = "B8CAC300E37F03AD332E581DEA21B2F0B84EAAADC184A295FEF71E81F44A7413".parse().expect("to be valid hex");
-    /// keccak256(Refunded())
-    pub static ref REFUND_LOG_MSG: H256 = "5D26862916391BF49478B2F5103B0720A842B45EF145A268F2CD1FB2AED55178".parse().expect("to be valid hex");
     /// keccak('Transfer(address,address,uint256)')
     pub static ref TRANSFER_LOG_MSG: H256 = "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef".parse().expect("to be valid hex");
 }
@@ -41,29 +48,11 @@ impl HtlcEvents<Ethereum, EtherQuantity> for Web3Connector {
         &self,
         htlc_params: HtlcParams<Ethereum, EtherQuantity>,
     ) -> Box<DeployedFuture<Ethereum>> {
-        let future = self
-            .matching_transactions(
-                TransactionPattern {
-                    from_address: None,
-                    to_address: None,
-                    is_contract_creation: Some(true),
-                    transaction_data: Some(htlc_params.bytecode()),
-                    transaction_data_length: None,
-                    events: None,
-                },
-                None,
-            )
-            .map_err(|_| rfc003::Error::Btsieve)
-            .first_or_else(|| {
-                log::warn!("stream of matching transactions ended before yielding a value");
-                rfc003::Error::Btsieve
-            })
-            .map(|txr| Deployed {
-                location: calcualte_contract_address_from_deployment_transaction(&txr.transaction),
-                transaction: txr.transaction,
-            });
-
-        Box::new(future)
+        Box::new(
+            htlc_deployed(self.clone(), htlc_params.bytecode(), None)
+                .boxed()
+                .compat(),
+        )
     }
 
     fn htlc_funded(
@@ -71,109 +60,280 @@ impl HtlcEvents<Ethereum, EtherQuantity> for Web3Connector {
         _htlc_params: HtlcParams<Ethereum, EtherQuantity>,
         deploy_transaction: &Deployed<Ethereum>,
     ) -> Box<FundedFuture<Ethereum, EtherQuantity>> {
-        Box::new(future::ok(Funded {
-            transaction: deploy_transaction.transaction.clone(),
-            asset: EtherQuantity::from_wei(deploy_transaction.transaction.value),
-        }))
+        Box::new(future::ok(ether_funded(deploy_transaction)))
     }
 
     fn htlc_redeemed_or_refunded(
         &self,
         htlc_params: HtlcParams<Ethereum, EtherQuantity>,
         htlc_deployment: &Deployed<Ethereum>,
-        htlc_funding: &Funded<Ethereum, EtherQuantity>,
+        _htlc_funding: &Funded<Ethereum, EtherQuantity>,
     ) -> Box<RedeemedOrRefundedFuture<Ethereum>> {
-        htlc_redeemed_or_refunded(self.clone(), htlc_params, htlc_deployment, htlc_funding)
+        Box::new(
+            htlc_redeemed_or_refunded(
+                self.clone(),
+                htlc_params.secret_hash,
+                htlc_deployment.location,
+            )
+            .boxed()
+            .compat(),
+        )
+    }
+}
+
+impl FindHtlc<Ethereum, EtherQuantity> for Web3Connector {
+    fn find_funded_htlc(
+        &self,
+        htlc_params: HtlcParams<Ethereum, EtherQuantity>,
+        since: Timestamp,
+    ) -> Box<FundedHtlcFuture<Ethereum, EtherQuantity>> {
+        let connector = self.clone();
+
+        Box::new(
+            async move {
+                let deployed =
+                    htlc_deployed(connector, htlc_params.bytecode(), Some(since.into())).await?;
+                let funded = ether_funded(&deployed);
+
+                Ok((deployed, funded))
+            }
+            .boxed()
+            .compat(),
+        )
+    }
+}
+
+#[async_trait]
+impl ScanHtlc<Ethereum, EtherQuantity> for Web3Connector {
+    async fn scan_htlc(
+        &self,
+        htlc_params: HtlcParams<Ethereum, EtherQuantity>,
+        since: Timestamp,
+    ) -> anyhow::Result<HtlcHistory<Ethereum, EtherQuantity>> {
+        let deployed = match scan_deployed(self, htlc_params.bytecode(), since).await? {
+            Some(deployed) => deployed,
+            None => return Ok(HtlcHistory::default()),
+        };
+        let funded = ether_funded(&deployed);
+        let redeemed_or_refunded =
+            scan_redeemed_or_refunded(self, &htlc_params, &deployed, since).await?;
+
+        Ok(HtlcHistory {
+            deployed: Some(deployed),
+            funded: Some(funded),
+            redeemed_or_refunded,
+        })
+    }
+}
+
+/// The transaction that deploys the HTLC also funds it.
+fn ether_funded(htlc_deployment: &Deployed<Ethereum>) -> Funded<Ethereum, EtherQuantity> {
+    Funded {
+        transaction: htlc_deployment.transaction.clone(),
+        asset: EtherQuantity::from_wei(htlc_deployment.transaction.value),
+        top_ups: vec![],
+    }
+}
+
+async fn scan_deployed(
+    connector: &Web3Connector,
+    bytecode: Bytes,
+    since: Timestamp,
+) -> anyhow::Result<Option<Deployed<Ethereum>>> {
+    let deployed = connector
+        .past_transaction(deployed_pattern(bytecode), since.into())
+        .await?
+        .map(htlc_deployment);
+
+    Ok(deployed)
+}
+
+async fn scan_redeemed_or_refunded<A: Asset>(
+    connector: &Web3Connector,
+    htlc_params: &HtlcParams<Ethereum, A>,
+    htlc_deployment: &Deployed<Ethereum>,
+    since: Timestamp,
+) -> anyhow::Result<Option<Either<Redeemed<Ethereum>, Refunded<Ethereum>>>> {
+    for pattern in vec![
+        redeemed_pattern(htlc_deployment.location),
+        refunded_pattern(htlc_deployment.location),
+    ] {
+        if let Some(transaction) = connector
+            .past_transaction(pattern.clone(), since.into())
+            .await?
+        {
+            return Ok(Some(htlc_spent(
+                &pattern,
+                &htlc_params.secret_hash,
+                transaction,
+            )?));
+        }
+    }
+
+    Ok(None)
+}
+
+fn deployed_pattern(bytecode: Bytes) -> TransactionPattern {
+    TransactionPattern {
+        from_address: None,
+        to_address: None,
+        is_contract_creation: Some(true),
+        transaction_data: Some(bytecode),
+        transaction_data_length: None,
+        events: None,
     }
 }
 
+fn redeemed_pattern(htlc_location: Address) -> TransactionPattern {
+    htlc_event_pattern(htlc_location, *REDEEM_LOG_MSG)
+}
+
+fn refunded_pattern(htlc_location: Address) -> TransactionPattern {
+    htlc_event_pattern(htlc_location, *REFUND_LOG_MSG)
+}
+
+fn htlc_event_pattern(htlc_location: Address, topic: H256) -> TransactionPattern {
+    TransactionPattern {
+        from_address: None,
+        to_address: None,
+        is_contract_creation: None,
+        transaction_data: None,
+        transaction_data_length: None,
+        events: Some(vec![Event {
+            address: Some(htlc_location),
+            data: None,
+            topics: vec![Some(Topic(topic))],
+        }]),
+    }
+}
+
+fn htlc_deployment(transaction: TransactionAndReceipt) -> Deployed<Ethereum> {
+    Deployed {
+        location: calcualte_contract_address_from_deployment_transaction(&transaction.transaction),
+        transaction: transaction.transaction,
+    }
+}
+
+fn htlc_spent(
+    pattern: &TransactionPattern,
+    secret_hash: &SecretHash,
+    TransactionAndReceipt {
+        transaction,
+        receipt,
+    }: TransactionAndReceipt,
+) -> Result<Either<Redeemed<Ethereum>, Refunded<Ethereum>>, rfc003::Error> {
+    match pattern.spend(&transaction, Some(&receipt), secret_hash) {
+        Some(Spend::Redeem { secret }) => Ok(Either::A(Redeemed {
+            transaction,
+            secret,
+        })),
+        Some(Spend::Refund) => Ok(Either::B(Refunded { transaction })),
+        None => Err(rfc003::Error::Internal(format!(
+            "transaction receipt {:?} contained neither a REDEEM log with the secret nor a REFUND log",
+            transaction.hash
+        ))),
+    }
+}
+
+async fn htlc_deployed(
+    connector: Web3Connector,
+    bytecode: Bytes,
+    reference_timestamp: Option<u32>,
+) -> Result<Deployed<Ethereum>, rfc003::Error> {
+    let transactions =
+        connector.matching_transactions(deployed_pattern(bytecode), reference_timestamp);
+    let transaction = first_matching_transaction(transactions).await?;
+
+    Ok(htlc_deployment(transaction))
+}
+
 fn calcualte_contract_address_from_deployment_transaction(tx: &Transaction) -> Address {
     tx.from.calculate_contract_address(&tx.nonce)
 }
 
-fn htlc_redeemed_or_refunded<A: Asset>(
+async fn htlc_redeemed_or_refunded(
     ethereum_connector: Web3Connector,
-    _htlc_params: HtlcParams<Ethereum, A>,
-    htlc_deployment: &Deployed<Ethereum>,
-    _: &Funded<Ethereum, A>,
-) -> Box<RedeemedOrRefundedFuture<Ethereum>> {
-    let refunded_future = {
-        ethereum_connector
-            .matching_transactions(
-                TransactionPattern {
-                    from_address: None,
-                    to_address: None,
-                    is_contract_creation: None,
-                    transaction_data: None,
-                    transaction_data_length: None,
-                    events: Some(vec![Event {
-                        address: Some(htlc_deployment.location),
-                        data: None,
-                        topics: vec![Some(Topic(*REFUND_LOG_MSG))],
-                    }]),
-                },
-                None,
-            )
-            .map_err(|_| rfc003::Error::Btsieve)
-            .first_or_else(|| {
-                log::warn!("stream of matching transactions ended before yielding a value");
-                rfc003::Error::Btsieve
-            })
-            .map(|transaction| Refunded {
-                transaction: transaction.transaction,
-            })
-    };
+    secret_hash: SecretHash,
+    htlc_location: Address,
+) -> Result<Either<Redeemed<Ethereum>, Refunded<Ethereum>>, rfc003::Error> {
+    let spent_future = |pattern: TransactionPattern| {
+        let transactions = ethereum_connector.matching_transactions(pattern.clone(), None);
 
-    let redeemed_future = {
-        ethereum_connector.matching_transactions(TransactionPattern {
-            from_address: None,
-            to_address: None,
-            is_contract_creation: None,
-            transaction_data: None,
-            transaction_data_length: None,
-            events: Some(vec![Event {
-                address: Some(htlc_deployment.location),
-                data: None,
-                topics: vec![Some(Topic(*REDEEM_LOG_MSG))],
-            }])
-        }, None)
-            .map_err(|_| rfc003::Error::Btsieve)
-            .first_or_else(|| {
-                log::warn!("stream of matching transactions ended before yielding a value");
-                rfc003::Error::Btsieve
-            })
-            .and_then(|TransactionAndReceipt { transaction, receipt }| {
-                receipt
-                    .logs
-                    .into_iter()
-                    .find(|log| log.topics.contains(&*REDEEM_LOG_MSG))
-                    .ok_or_else(|| {
-                        rfc003::Error::Internal(format!("transaction receipt {:?} did not contain a REDEEM log", transaction.hash))
-                    }).and_then(|log| {
-                    let log_data = log.data.0.as_ref();
-                    let secret = Secret::from_vec(log_data)
-                        .map_err(|e| rfc003::Error::Internal(format!("failed to construct secret from data in transaction receipt {:?}: {:?}", transaction.hash, e)))?;
-
-                    Ok(Redeemed {
-                        transaction,
-                        secret,
-                    })
-                })
-            })
+        async move {
+            let transaction = first_matching_transaction(transactions).await?;
+
+            htlc_spent(&pattern, &secret_hash, transaction)
+        }
+        .boxed()
     };
 
-    Box::new(
+    let refunded_future = spent_future(refunded_pattern(htlc_location));
+    let redeemed_future = spent_future(redeemed_pattern(htlc_location));
+
+    // The secret is known as soon as the redeem transaction is broadcast,
+    // there is no need to wait for it to be mined.
+    let redeemed_future = if ethereum_connector.watches_pending_transactions() {
+        let pending_redeem_future =
+            pending_redeem(ethereum_connector.clone(), htlc_location, secret_hash)
+                .map(|redeemed| Ok(Either::A(redeemed)))
+                .boxed();
+
+        first_of(redeemed_future, pending_redeem_future).boxed()
+    } else {
         redeemed_future
-            .select2(refunded_future)
-            .map(|tx| match tx {
-                Either::A((tx, _)) => Either::A(tx),
-                Either::B((tx, _)) => Either::B(tx),
-            })
-            .map_err(|either| match either {
-                Either::A((error, _)) => error,
-                Either::B((error, _)) => error,
-            }),
-    )
+    };
+
+    first_of(redeemed_future, refunded_future).await
+}
+
+/// Resolves with the output of whichever future completes first.
+async fn first_of<T>(a: BoxFuture<'static, T>, b: BoxFuture<'static, T>) -> T {
+    match select(a, b).await {
+        futures_core::future::Either::Left((output, _))
+        | futures_core::future::Either::Right((output, _)) => output,
+    }
+}
+
+/// Resolves with the first transaction in the pending block that redeems the
+/// HTLC, it is not known yet whether it will be mined.
+async fn pending_redeem(
+    ethereum_connector: Web3Connector,
+    htlc_location: Address,
+    secret_hash: SecretHash,
+) -> Redeemed<Ethereum> {
+    let pattern = redeemed_pattern(htlc_location);
+
+    loop {
+        match ethereum_connector.pending_transactions().await {
+            Ok(transactions) => {
+                let redeemed: Option<Redeemed<Ethereum>> =
+                    transactions.into_iter().find_map(|transaction| {
+                        match pattern.spend(&transaction, None, &secret_hash) {
+                            Some(Spend::Redeem { secret }) => Some(Redeemed {
+                                transaction,
+                                secret,
+                            }),
+                            _ => None,
+                        }
+                    });
+
+                if let Some(redeemed) = redeemed {
+                    log::info!(
+                        "found pending redeem transaction {:?} of HTLC {:?}",
+                        redeemed.transaction.hash,
+                        htlc_location
+                    );
+                    return redeemed;
+                }
+            }
+            Err(e) => log::warn!("Could not get pending transactions: {:?}", e),
+        }
+
+        Delay::new(Instant::now() + PENDING_TRANSACTIONS_INTERVAL)
+            .compat()
+            .await
+            .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+    }
 }
 
 mod erc20 {
@@ -185,31 +345,11 @@ mod erc20 {
             &self,
             htlc_params: HtlcParams<Ethereum, Erc20Token>,
         ) -> Box<DeployedFuture<Ethereum>> {
-            let future = self
-                .matching_transactions(
-                    TransactionPattern {
-                        from_address: None,
-                        to_address: None,
-                        is_contract_creation: Some(true),
-                        transaction_data: Some(htlc_params.bytecode()),
-                        transaction_data_length: None,
-                        events: None,
-                    },
-                    None,
-                )
-                .map_err(|_| rfc003::Error::Btsieve)
-                .first_or_else(|| {
-                    log::warn!("stream of matching transactions ended before yielding a value");
-                    rfc003::Error::Btsieve
-                })
-                .map(|txr| Deployed {
-                    location: calcualte_contract_address_from_deployment_transaction(
-                        &txr.transaction,
-                    ),
-                    transaction: txr.transaction,
-                });
-
-            Box::new(future)
+            Box::new(
+                super::htlc_deployed(self.clone(), htlc_params.bytecode(), None)
+                    .boxed()
+                    .compat(),
+            )
         }
 
         fn htlc_funded(
@@ -217,67 +357,171 @@ mod erc20 {
             htlc_params: HtlcParams<Ethereum, Erc20Token>,
             htlc_deployment: &Deployed<Ethereum>,
         ) -> Box<FundedFuture<Ethereum, Erc20Token>> {
-            let future = self
-                .matching_transactions(
-                    TransactionPattern {
-                        from_address: None,
-                        to_address: None,
-                        is_contract_creation: None,
-                        transaction_data: None,
-                        transaction_data_length: None,
-                        events: Some(vec![Event {
-                            address: Some(htlc_params.asset.token_contract),
-                            data: None,
-                            topics: vec![
-                                Some(Topic(*super::TRANSFER_LOG_MSG)),
-                                None,
-                                Some(Topic(htlc_deployment.location.into())),
-                            ],
-                        }]),
-                    },
-                    None,
-                )
-                .map_err(|_| rfc003::Error::Btsieve)
-                .first_or_else(|| {
-                    log::warn!("stream of matching transactions ended before yielding a value");
-                    rfc003::Error::Btsieve
-                })
-                .and_then(
-                    |TransactionAndReceipt {
-                         transaction,
-                         receipt,
-                     }| {
-                        receipt
-                            .logs
-                            .into_iter()
-                            .find(|log| log.topics.contains(&*super::TRANSFER_LOG_MSG))
-                            .ok_or_else(|| {
-                                log::warn!(
-                                "receipt for transaction {:?} did not contain any Transfer events",
-                                transaction.hash
-                            );
-                                rfc003::Error::IncorrectFunding
-                            })
-                            .map(|log| {
-                                let quantity =
-                                    Erc20Quantity(U256::from_big_endian(log.data.0.as_ref()));
-                                let asset = Erc20Token::new(log.address, quantity);
-
-                                Funded { transaction, asset }
-                            })
-                    },
-                );
-
-            Box::new(future)
+            Box::new(
+                htlc_funded(self.clone(), htlc_params, htlc_deployment.clone(), None)
+                    .boxed()
+                    .compat(),
+            )
         }
 
         fn htlc_redeemed_or_refunded(
             &self,
             htlc_params: HtlcParams<Ethereum, Erc20Token>,
             htlc_deployment: &Deployed<Ethereum>,
-            htlc_funding: &Funded<Ethereum, Erc20Token>,
+            _htlc_funding: &Funded<Ethereum, Erc20Token>,
         ) -> Box<RedeemedOrRefundedFuture<Ethereum>> {
-            htlc_redeemed_or_refunded(self.clone(), htlc_params, htlc_deployment, htlc_funding)
+            Box::new(
+                htlc_redeemed_or_refunded(
+                    self.clone(),
+                    htlc_params.secret_hash,
+                    htlc_deployment.location,
+                )
+                .boxed()
+                .compat(),
+            )
+        }
+    }
+
+    #[async_trait]
+    impl ScanHtlc<Ethereum, Erc20Token> for Web3Connector {
+        async fn scan_htlc(
+            &self,
+            htlc_params: HtlcParams<Ethereum, Erc20Token>,
+            since: Timestamp,
+        ) -> anyhow::Result<HtlcHistory<Ethereum, Erc20Token>> {
+            let deployed = match scan_deployed(self, htlc_params.bytecode(), since).await? {
+                Some(deployed) => deployed,
+                None => return Ok(HtlcHistory::default()),
+            };
+
+            let funded = match self
+                .past_transaction(funded_pattern(&htlc_params, &deployed), since.into())
+                .await?
+            {
+                Some(transaction) => htlc_funding(&htlc_params, &deployed, transaction)?,
+                None => {
+                    return Ok(HtlcHistory {
+                        deployed: Some(deployed),
+                        ..HtlcHistory::default()
+                    })
+                }
+            };
+            let redeemed_or_refunded =
+                scan_redeemed_or_refunded(self, &htlc_params, &deployed, since).await?;
+
+            Ok(HtlcHistory {
+                deployed: Some(deployed),
+                funded: Some(funded),
+                redeemed_or_refunded,
+            })
+        }
+    }
+
+    impl FindHtlc<Ethereum, Erc20Token> for Web3Connector {
+        fn find_funded_htlc(
+            &self,
+            htlc_params: HtlcParams<Ethereum, Erc20Token>,
+            since: Timestamp,
+        ) -> Box<FundedHtlcFuture<Ethereum, Erc20Token>> {
+            let connector = self.clone();
+
+            Box::new(
+                async move {
+                    let deployed = super::htlc_deployed(
+                        connector.clone(),
+                        htlc_params.bytecode(),
+                        Some(since.into()),
+                    )
+                    .await?;
+                    let funded =
+                        htlc_funded(connector, htlc_params, deployed.clone(), Some(since.into()))
+                            .await?;
+
+                    Ok((deployed, funded))
+                }
+                .boxed()
+                .compat(),
+            )
         }
     }
+
+    async fn htlc_funded(
+        connector: Web3Connector,
+        htlc_params: HtlcParams<Ethereum, Erc20Token>,
+        htlc_deployment: Deployed<Ethereum>,
+        reference_timestamp: Option<u32>,
+    ) -> Result<Funded<Ethereum, Erc20Token>, rfc003::Error> {
+        let transactions = connector.matching_transactions(
+            funded_pattern(&htlc_params, &htlc_deployment),
+            reference_timestamp,
+        );
+        let transaction = first_matching_transaction(transactions).await?;
+
+        htlc_funding(&htlc_params, &htlc_deployment, transaction)
+    }
+
+    fn funded_pattern(
+        htlc_params: &HtlcParams<Ethereum, Erc20Token>,
+        htlc_deployment: &Deployed<Ethereum>,
+    ) -> TransactionPattern {
+        TransactionPattern {
+            from_address: None,
+            to_address: None,
+            is_contract_creation: None,
+            transaction_data: None,
+            transaction_data_length: None,
+            events: Some(vec![Event {
+                address: Some(htlc_params.asset.token_contract),
+                data: None,
+                topics: vec![
+                    Some(Topic(*super::TRANSFER_LOG_MSG)),
+                    None,
+                    Some(Topic(htlc_deployment.location.into())),
+                ],
+            }]),
+        }
+    }
+
+    /// Adds up all transfers of the token to the HTLC in the transaction, other
+    /// transfers it makes do not fund the HTLC.
+    fn htlc_funding(
+        htlc_params: &HtlcParams<Ethereum, Erc20Token>,
+        htlc_deployment: &Deployed<Ethereum>,
+        TransactionAndReceipt {
+            transaction,
+            receipt,
+        }: TransactionAndReceipt,
+    ) -> Result<Funded<Ethereum, Erc20Token>, rfc003::Error> {
+        let token_contract = htlc_params.asset.token_contract;
+        let htlc: H256 = htlc_deployment.location.into();
+
+        let quantities = receipt
+            .logs
+            .into_iter()
+            .filter(|log| {
+                log.address == token_contract
+                    && log.topics.get(0) == Some(&*super::TRANSFER_LOG_MSG)
+                    && log.topics.get(2) == Some(&htlc)
+            })
+            .map(|log| U256::from_big_endian(log.data.0.as_ref()))
+            .collect::<Vec<_>>();
+
+        if quantities.is_empty() {
+            log::warn!(
+                "receipt for transaction {:?} did not contain any Transfer events to the HTLC",
+                transaction.hash
+            );
+            return Err(rfc003::Error::IncorrectFunding);
+        }
+
+        let quantity = quantities
+            .into_iter()
+            .fold(U256::zero(), |sum, quantity| sum.saturating_add(quantity));
+
+        Ok(Funded {
+            transaction,
+            asset: Erc20Token::new(token_contract, Erc20Quantity(quantity)),
+            top_ups: vec![],
+        })
+    }
 }
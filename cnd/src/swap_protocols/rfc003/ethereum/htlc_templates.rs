@@ -0,0 +1,265 @@
+use crate::{
+    config,
+    ethereum::{Address, Bytes, Erc20Token, EtherQuantity, U256},
+    swap_protocols::{ledger::Ethereum, rfc003::state_machine::HtlcParams},
+};
+use anyhow::Context;
+use std::{fs, path::Path, sync::RwLock};
+
+const EXPIRY: Placeholder = Placeholder {
+    name: "expiry",
+    length: 4,
+};
+const REFUND_IDENTITY: Placeholder = Placeholder {
+    name: "refund_identity",
+    length: 20,
+};
+const REDEEM_IDENTITY: Placeholder = Placeholder {
+    name: "redeem_identity",
+    length: 20,
+};
+const SECRET_HASH: Placeholder = Placeholder {
+    name: "secret_hash",
+    length: 32,
+};
+const TOKEN_CONTRACT: Placeholder = Placeholder {
+    name: "token_contract",
+    length: 20,
+};
+const TOKEN_QUANTITY: Placeholder = Placeholder {
+    name: "token_quantity",
+    length: 32,
+};
+
+/// The placeholders an Ether HTLC template has to contain.
+pub const ETHER_PLACEHOLDERS: &[Placeholder] =
+    &[EXPIRY, REFUND_IDENTITY, REDEEM_IDENTITY, SECRET_HASH];
+
+/// The placeholders an ERC20 HTLC template has to contain.
+pub const ERC20_PLACEHOLDERS: &[Placeholder] = &[
+    EXPIRY,
+    REFUND_IDENTITY,
+    REDEEM_IDENTITY,
+    SECRET_HASH,
+    TOKEN_CONTRACT,
+    TOKEN_QUANTITY,
+];
+
+lazy_static::lazy_static! {
+    static ref INSTALLED: RwLock<HtlcTemplates> = RwLock::new(HtlcTemplates::default());
+}
+
+/// A parameter of the HTLC, written as `{name}` in a template and replaced by
+/// the big-endian encoding of the value in `length` bytes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placeholder {
+    pub name: &'static str,
+    pub length: usize,
+}
+
+#[derive(Clone, Copy, Debug, thiserror::Error, PartialEq)]
+pub enum Error {
+    #[error("the placeholder {{{0}}} is missing")]
+    MissingPlaceholder(&'static str),
+    #[error("a placeholder is not closed or not one of the supported ones")]
+    UnknownPlaceholder,
+    #[error("the template is not hex encoded bytecode: {0}")]
+    Hex(#[from] hex::FromHexError),
+}
+
+/// Hex encoded EVM bytecode that deploys an HTLC, with placeholders for its
+/// parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template(String);
+
+impl Template {
+    /// Checks that every one of `placeholders` is used and that the template
+    /// is valid bytecode once they are filled in.
+    pub fn parse(template: &str, placeholders: &[Placeholder]) -> Result<Self, Error> {
+        let template = template
+            .trim()
+            .trim_start_matches("0x")
+            .split_whitespace()
+            .collect::<String>();
+
+        let mut filled_in = template.clone();
+        for placeholder in placeholders {
+            let pattern = placeholder.pattern();
+            if !filled_in.contains(&pattern) {
+                return Err(Error::MissingPlaceholder(placeholder.name));
+            }
+            filled_in = filled_in.replace(&pattern, &"00".repeat(placeholder.length));
+        }
+
+        if filled_in.contains('{') || filled_in.contains('}') {
+            return Err(Error::UnknownPlaceholder);
+        }
+        hex::decode(filled_in)?;
+
+        Ok(Template(template))
+    }
+
+    fn render(&self, values: &[(Placeholder, String)]) -> Bytes {
+        let bytecode = values
+            .iter()
+            .fold(self.0.clone(), |bytecode, (placeholder, value)| {
+                bytecode.replace(&placeholder.pattern(), value)
+            });
+
+        Bytes(hex::decode(bytecode).expect("templates are validated when they are parsed"))
+    }
+}
+
+impl Placeholder {
+    fn pattern(self) -> String {
+        format!("{{{}}}", self.name)
+    }
+}
+
+/// HTLC templates that replace the ones cnd ships with, for protocol research
+/// deployments that want to try modified contracts.
+///
+/// The templates are neither audited nor checked for their semantics by cnd.
+/// Both parties of a swap have to use the same ones, cnd only recognises HTLCs
+/// deployed from the templates it uses itself.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HtlcTemplates {
+    ether: Option<Template>,
+    erc20: Option<Template>,
+}
+
+impl HtlcTemplates {
+    /// Reads the templates from the configured files.
+    pub fn load(config: &config::HtlcTemplates) -> anyhow::Result<Self> {
+        let load = |path: &Path, placeholders: &[Placeholder]| -> anyhow::Result<Template> {
+            let template = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+
+            Template::parse(&template, placeholders)
+                .with_context(|| format!("invalid HTLC template {}", path.display()))
+        };
+
+        Ok(Self {
+            ether: match &config.ether {
+                Some(path) => Some(load(path, ETHER_PLACEHOLDERS)?),
+                None => None,
+            },
+            erc20: match &config.erc20 {
+                Some(path) => Some(load(path, ERC20_PLACEHOLDERS)?),
+                None => None,
+            },
+        })
+    }
+
+    /// Use these templates for all HTLCs deployed or looked for from now on.
+    pub fn install(self) {
+        if self.ether.is_some() {
+            log::warn!("deploying Ether HTLCs from a custom template");
+        }
+        if self.erc20.is_some() {
+            log::warn!("deploying ERC20 HTLCs from a custom template");
+        }
+
+        *INSTALLED.write().unwrap() = self;
+    }
+}
+
+/// The bytecode of the HTLC if a custom Ether template is installed.
+pub fn ether_bytecode(htlc_params: &HtlcParams<Ethereum, EtherQuantity>) -> Option<Bytes> {
+    let templates = INSTALLED.read().unwrap();
+    let template = templates.ether.as_ref()?;
+
+    Some(template.render(&common_values(
+        htlc_params.expiry.into(),
+        &htlc_params.refund_identity,
+        &htlc_params.redeem_identity,
+        htlc_params.secret_hash.as_raw(),
+    )))
+}
+
+/// The bytecode of the HTLC if a custom ERC20 template is installed.
+pub fn erc20_bytecode(htlc_params: &HtlcParams<Ethereum, Erc20Token>) -> Option<Bytes> {
+    let templates = INSTALLED.read().unwrap();
+    let template = templates.erc20.as_ref()?;
+
+    let mut values = common_values(
+        htlc_params.expiry.into(),
+        &htlc_params.refund_identity,
+        &htlc_params.redeem_identity,
+        htlc_params.secret_hash.as_raw(),
+    );
+    values.push((
+        TOKEN_CONTRACT,
+        format!("{:x}", htlc_params.asset.token_contract),
+    ));
+    values.push((TOKEN_QUANTITY, u256_hex(htlc_params.asset.quantity.0)));
+
+    Some(template.render(&values))
+}
+
+fn common_values(
+    expiry: u32,
+    refund_identity: &Address,
+    redeem_identity: &Address,
+    secret_hash: &[u8; 32],
+) -> Vec<(Placeholder, String)> {
+    vec![
+        (EXPIRY, hex::encode(expiry.to_be_bytes())),
+        (REFUND_IDENTITY, format!("{:x}", refund_identity)),
+        (REDEEM_IDENTITY, format!("{:x}", redeem_identity)),
+        (SECRET_HASH, hex::encode(secret_hash)),
+    ]
+}
+
+fn u256_hex(value: U256) -> String {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn templates_must_contain_every_placeholder() {
+        let template = "6020{expiry}{refund_identity}{redeem_identity}";
+
+        assert_that(&Template::parse(template, ETHER_PLACEHOLDERS))
+            .is_err_containing(Error::MissingPlaceholder("secret_hash"));
+    }
+
+    #[test]
+    fn templates_must_not_contain_unknown_placeholders() {
+        let template = "{expiry}{refund_identity}{redeem_identity}{secret_hash}{amount}";
+
+        assert_that(&Template::parse(template, ETHER_PLACEHOLDERS))
+            .is_err_containing(Error::UnknownPlaceholder);
+    }
+
+    #[test]
+    fn placeholders_are_replaced_by_the_encoded_values() {
+        let template = Template::parse(
+            "0x6004 {expiry}\n{refund_identity}{redeem_identity}{secret_hash}",
+            ETHER_PLACEHOLDERS,
+        )
+        .unwrap();
+
+        let bytecode = template.render(&common_values(
+            0x5d_b2_c1_00,
+            &"0000000000000000000000000000000000000001".parse().unwrap(),
+            &"0000000000000000000000000000000000000002".parse().unwrap(),
+            &[0xaa; 32],
+        ));
+
+        let mut expected = vec![0x60, 0x04, 0x5d, 0xb2, 0xc1, 0x00];
+        expected.extend_from_slice(&[0; 19]);
+        expected.push(0x01);
+        expected.extend_from_slice(&[0; 19]);
+        expected.push(0x02);
+        expected.extend_from_slice(&[0xaa; 32]);
+        assert_that(&bytecode.0).is_equal_to(expected);
+    }
+}
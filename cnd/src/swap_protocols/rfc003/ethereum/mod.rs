@@ -1,11 +1,12 @@
 pub mod htlc_events;
+pub mod htlc_templates;
 
 use crate::{
     ethereum::{Address, Bytes, Erc20Token, EtherQuantity},
     swap_protocols::{
         actions::ethereum::DeployContract,
         ledger::Ethereum,
-        rfc003::{state_machine::HtlcParams, Ledger},
+        rfc003::{state_machine::HtlcParams, Ledger, SecretSource},
     },
 };
 use blockchain_contracts::ethereum::rfc003::{erc20_htlc::Erc20Htlc, ether_htlc::EtherHtlc};
@@ -42,6 +43,16 @@ impl From<Seconds> for Duration {
 
 impl Ledger for Ethereum {
     type HtlcLocation = Address;
+
+    // The user sends the Ethereum transactions, the identities have to be
+    // addresses of their wallet.
+    fn derive_redeem_identity(_: &dyn SecretSource) -> Option<Self::Identity> {
+        None
+    }
+
+    fn derive_refund_identity(_: &dyn SecretSource) -> Option<Self::Identity> {
+        None
+    }
 }
 
 impl From<HtlcParams<Ethereum, EtherQuantity>> for EtherHtlc {
@@ -57,7 +68,7 @@ impl From<HtlcParams<Ethereum, EtherQuantity>> for EtherHtlc {
 
 impl HtlcParams<Ethereum, EtherQuantity> {
     pub fn bytecode(&self) -> Bytes {
-        EtherHtlc::from(self.clone()).into()
+        htlc_templates::ether_bytecode(self).unwrap_or_else(|| EtherHtlc::from(self.clone()).into())
     }
 }
 
@@ -76,7 +87,7 @@ impl From<HtlcParams<Ethereum, Erc20Token>> for Erc20Htlc {
 
 impl HtlcParams<Ethereum, Erc20Token> {
     pub fn bytecode(&self) -> Bytes {
-        Erc20Htlc::from(self.clone()).into()
+        htlc_templates::erc20_bytecode(self).unwrap_or_else(|| Erc20Htlc::from(self.clone()).into())
     }
 }
 
@@ -86,7 +97,7 @@ impl From<HtlcParams<Ethereum, EtherQuantity>> for DeployContract {
         let gas_limit = htlc.deployment_gas_limit();
 
         DeployContract {
-            data: htlc.into(),
+            data: htlc_params.bytecode(),
             amount: htlc_params.asset,
             gas_limit,
             chain_id: htlc_params.ledger.chain_id,
@@ -100,7 +111,7 @@ impl From<HtlcParams<Ethereum, Erc20Token>> for DeployContract {
         let gas_limit = htlc.deployment_gas_limit();
 
         DeployContract {
-            data: htlc.into(),
+            data: htlc_params.bytecode(),
             amount: EtherQuantity::zero(),
             gas_limit,
             chain_id: htlc_params.ledger.chain_id,
@@ -0,0 +1,91 @@
+use crate::swap_protocols::{
+    asset::Asset,
+    rfc003::{
+        self,
+        events::{
+            Deployed, DeployedFuture, Funded, FundedFuture, HtlcHistory, LedgerEvents,
+            RedeemedOrRefundedFuture,
+        },
+        state_machine::HtlcParams,
+        Ledger,
+    },
+};
+use futures::future;
+
+/// Ledger events that resolve right away with what is already known to have
+/// happened to the HTLC, only the remaining ones are watched for.
+///
+/// Used to fast-forward a resumed swap to the state its HTLCs are in on the
+/// ledger.
+#[allow(missing_debug_implementations)]
+pub struct FastForwarded<L: Ledger, A: Asset> {
+    inner: Box<dyn LedgerEvents<L, A>>,
+    htlc_deployed: Option<Box<DeployedFuture<L>>>,
+    htlc_funded: Option<Box<FundedFuture<L, A>>>,
+    htlc_redeemed_or_refunded: Option<Box<RedeemedOrRefundedFuture<L>>>,
+}
+
+impl<L: Ledger, A: Asset> FastForwarded<L, A> {
+    pub fn new(inner: Box<dyn LedgerEvents<L, A>>, history: HtlcHistory<L, A>) -> Self {
+        Self {
+            inner,
+            htlc_deployed: history.deployed.map(|deployed| {
+                Box::new(future::ok::<_, rfc003::Error>(deployed)) as Box<DeployedFuture<L>>
+            }),
+            htlc_funded: history.funded.map(|funded| {
+                Box::new(future::ok::<_, rfc003::Error>(funded)) as Box<FundedFuture<L, A>>
+            }),
+            htlc_redeemed_or_refunded: history.redeemed_or_refunded.map(|redeemed_or_refunded| {
+                Box::new(future::ok::<_, rfc003::Error>(redeemed_or_refunded))
+                    as Box<RedeemedOrRefundedFuture<L>>
+            }),
+        }
+    }
+}
+
+impl<L: Ledger, A: Asset> LedgerEvents<L, A> for FastForwarded<L, A> {
+    fn htlc_deployed(&mut self, htlc_params: HtlcParams<L, A>) -> &mut DeployedFuture<L> {
+        match self.htlc_deployed.as_mut() {
+            Some(htlc_deployed) => &mut **htlc_deployed,
+            None => self.inner.htlc_deployed(htlc_params),
+        }
+    }
+
+    fn htlc_funded(
+        &mut self,
+        htlc_params: HtlcParams<L, A>,
+        htlc_deployment: &Deployed<L>,
+    ) -> &mut FundedFuture<L, A> {
+        match self.htlc_funded.as_mut() {
+            Some(htlc_funded) => &mut **htlc_funded,
+            None => self.inner.htlc_funded(htlc_params, htlc_deployment),
+        }
+    }
+
+    fn htlc_redeemed_or_refunded(
+        &mut self,
+        htlc_params: HtlcParams<L, A>,
+        htlc_deployment: &Deployed<L>,
+        htlc_funding: &Funded<L, A>,
+    ) -> &mut RedeemedOrRefundedFuture<L> {
+        match self.htlc_redeemed_or_refunded.as_mut() {
+            Some(htlc_redeemed_or_refunded) => &mut **htlc_redeemed_or_refunded,
+            None => {
+                self.inner
+                    .htlc_redeemed_or_refunded(htlc_params, htlc_deployment, htlc_funding)
+            }
+        }
+    }
+
+    /// Top ups are not fast forwarded, only those that happen from now on are
+    /// seen.
+    fn htlc_topped_up(
+        &mut self,
+        htlc_params: HtlcParams<L, A>,
+        htlc_deployment: &Deployed<L>,
+        htlc_funding: &Funded<L, A>,
+    ) -> &mut FundedFuture<L, A> {
+        self.inner
+            .htlc_topped_up(htlc_params, htlc_deployment, htlc_funding)
+    }
+}
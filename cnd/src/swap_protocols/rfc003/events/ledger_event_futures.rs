@@ -21,6 +21,9 @@ pub struct LedgerEventFutures<L: Ledger, A: Asset> {
     htlc_deployed: Option<Box<DeployedFuture<L>>>,
     htlc_funded: Option<Box<FundedFuture<L, A>>>,
     htlc_redeemed_or_refunded: Option<Box<RedeemedOrRefundedFuture<L>>>,
+    /// Keyed by the number of top ups it was created for, a resolved future is
+    /// replaced by one waiting for the next top up.
+    htlc_topped_up: Option<(usize, Box<FundedFuture<L, A>>)>,
 }
 
 impl<L: Ledger, A: Asset> LedgerEventFutures<L, A> {
@@ -30,6 +33,7 @@ impl<L: Ledger, A: Asset> LedgerEventFutures<L, A> {
             htlc_deployed: None,
             htlc_funded: None,
             htlc_redeemed_or_refunded: None,
+            htlc_topped_up: None,
         }
     }
 }
@@ -62,4 +66,31 @@ impl<L: Ledger, A: Asset> LedgerEvents<L, A> for LedgerEventFutures<L, A> {
             htlc_events.htlc_redeemed_or_refunded(htlc_params, htlc_deployment, htlc_funding)
         })
     }
+
+    fn htlc_topped_up(
+        &mut self,
+        htlc_params: HtlcParams<L, A>,
+        htlc_deployment: &Deployed<L>,
+        htlc_funding: &Funded<L, A>,
+    ) -> &mut FundedFuture<L, A> {
+        let top_ups = htlc_funding.top_ups.len();
+        let is_current = self
+            .htlc_topped_up
+            .as_ref()
+            .map_or(false, |(known_top_ups, _)| *known_top_ups == top_ups);
+
+        if !is_current {
+            self.htlc_topped_up = Some((
+                top_ups,
+                self.htlc_events
+                    .htlc_topped_up(htlc_params, htlc_deployment, htlc_funding),
+            ));
+        }
+
+        &mut *self
+            .htlc_topped_up
+            .as_mut()
+            .expect("future was just inserted")
+            .1
+    }
 }
@@ -2,16 +2,25 @@
 // see: https://github.com/rust-lang/rust/issues/21903
 #![allow(type_alias_bounds)]
 
+mod fast_forwarded;
 mod ledger_event_futures;
 
-pub use self::ledger_event_futures::*;
+pub use self::{fast_forwarded::*, ledger_event_futures::*};
 
-use crate::swap_protocols::{
-    asset::Asset,
-    rfc003::{self, ledger::Ledger, state_machine::HtlcParams, Secret},
+use crate::{
+    swap_protocols::{
+        asset::Asset,
+        rfc003::{self, ledger::Ledger, state_machine::HtlcParams, Secret},
+    },
+    timestamp::Timestamp,
 };
+use async_trait::async_trait;
+use futures_core::stream::{BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
-use tokio::{self, prelude::future::Either};
+use tokio::{
+    self,
+    prelude::future::{self, Either},
+};
 
 type Future<I> = dyn tokio::prelude::Future<Item = I, Error = rfc003::Error> + Send;
 
@@ -20,8 +29,20 @@ pub type ResponseFuture<AL, BL> = Future<rfc003::Response<AL, BL>>;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Funded<L: Ledger, A: Asset> {
+    /// The first transaction that funded the HTLC.
     pub transaction: L::Transaction,
+    /// What all transactions that funded the HTLC added up to.
     pub asset: A,
+    pub top_ups: Vec<TopUp<L>>,
+}
+
+/// Another output that funded an HTLC, e.g. because the first transaction
+/// funded the wrong amount, and the transaction it is in. A transaction that
+/// pays to the HTLC several times adds one for each further output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopUp<L: Ledger> {
+    pub transaction: L::Transaction,
+    pub location: L::HtlcLocation,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -50,6 +71,7 @@ impl<L: Ledger> Refunded<L> {
 pub type DeployedFuture<L: Ledger> = Future<Deployed<L>>;
 pub type FundedFuture<L: Ledger, A: Asset> = Future<Funded<L, A>>;
 pub type RedeemedOrRefundedFuture<L: Ledger> = Future<Either<Redeemed<L>, Refunded<L>>>;
+pub type FundedHtlcFuture<L: Ledger, A: Asset> = Future<(Deployed<L>, Funded<L, A>)>;
 
 pub trait LedgerEvents<L: Ledger, A: Asset>: Send {
     fn htlc_deployed(&mut self, htlc_params: HtlcParams<L, A>) -> &mut DeployedFuture<L>;
@@ -66,6 +88,13 @@ pub trait LedgerEvents<L: Ledger, A: Asset>: Send {
         htlc_deployment: &Deployed<L>,
         htlc_funding: &Funded<L, A>,
     ) -> &mut RedeemedOrRefundedFuture<L>;
+
+    fn htlc_topped_up(
+        &mut self,
+        htlc_params: HtlcParams<L, A>,
+        htlc_deployment: &Deployed<L>,
+        htlc_funding: &Funded<L, A>,
+    ) -> &mut FundedFuture<L, A>;
 }
 
 pub trait HtlcEvents<L: Ledger, A: Asset>: Send + Sync + 'static {
@@ -81,4 +110,77 @@ pub trait HtlcEvents<L: Ledger, A: Asset>: Send + Sync + 'static {
         htlc_deployment: &Deployed<L>,
         htlc_funding: &Funded<L, A>,
     ) -> Box<RedeemedOrRefundedFuture<L>>;
+
+    /// Resolves with `htlc_funding` plus the next transaction that funds the
+    /// HTLC.
+    ///
+    /// Only HTLCs whose funds can sit at several locations, i.e. Bitcoin ones,
+    /// can be topped up. An Ethereum HTLC is a contract of its own for every
+    /// deployment, hence this never resolves by default.
+    fn htlc_topped_up(
+        &self,
+        _htlc_params: HtlcParams<L, A>,
+        _htlc_deployment: &Deployed<L>,
+        _htlc_funding: &Funded<L, A>,
+    ) -> Box<FundedFuture<L, A>> {
+        Box::new(future::empty())
+    }
+}
+
+/// Look for an HTLC that was deployed and funded after `since`.
+///
+/// Unlike `HtlcEvents`, which only looks at blocks mined from now on, this
+/// also goes through past blocks. It allows us to recover a swap without
+/// relying on its in-memory state.
+pub trait FindHtlc<L: Ledger, A: Asset>: Send + Sync + 'static {
+    fn find_funded_htlc(
+        &self,
+        htlc_params: HtlcParams<L, A>,
+        since: Timestamp,
+    ) -> Box<FundedHtlcFuture<L, A>>;
+}
+
+/// Resolves with the first of the transactions btsieve matched.
+///
+/// These streams never end on their own, if one does btsieve gave up on the
+/// ledger.
+pub async fn first_matching_transaction<T>(
+    mut transactions: BoxStream<'static, T>,
+) -> Result<T, rfc003::Error> {
+    transactions.next().await.ok_or_else(|| {
+        log::warn!("stream of matching transactions ended before yielding a value");
+        rfc003::Error::Btsieve
+    })
+}
+
+/// What happened to an HTLC while nobody was watching it, `None` for the
+/// events that did not happen (yet).
+#[derive(Debug)]
+pub struct HtlcHistory<L: Ledger, A: Asset> {
+    pub deployed: Option<Deployed<L>>,
+    pub funded: Option<Funded<L, A>>,
+    pub redeemed_or_refunded: Option<Either<Redeemed<L>, Refunded<L>>>,
+}
+
+impl<L: Ledger, A: Asset> Default for HtlcHistory<L, A> {
+    fn default() -> Self {
+        Self {
+            deployed: None,
+            funded: None,
+            redeemed_or_refunded: None,
+        }
+    }
+}
+
+/// Look through the blocks mined after `since` for what happened to an HTLC.
+///
+/// Unlike `FindHtlc` this does not wait for events that did not happen yet,
+/// it tells how far the HTLC got so far.
+#[async_trait]
+pub trait ScanHtlc<L: Ledger, A: Asset>: Send + Sync + 'static {
+    async fn scan_htlc(
+        &self,
+        htlc_params: HtlcParams<L, A>,
+        since: Timestamp,
+    ) -> anyhow::Result<HtlcHistory<L, A>>;
 }
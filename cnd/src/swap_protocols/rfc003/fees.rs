@@ -0,0 +1,245 @@
+use crate::{
+    btsieve::{bitcoin::BitcoinConnector, ethereum::Web3Connector, ReceiptByHash},
+    db::{LedgerKind, PaidFee, SwapFees},
+    ethereum::{Transaction as EthereumTransaction, U256},
+    swap_protocols::{
+        ledger::{Bitcoin, Ethereum},
+        rfc003::{Ledger, LedgerState},
+        Role, SwapId,
+    },
+};
+use async_trait::async_trait;
+use bitcoin::Transaction as BitcoinTransaction;
+
+/// Determine the on-chain fee of a transaction we sent as part of a swap.
+#[async_trait]
+pub trait PaidFees<L: Ledger>: Send + Sync + 'static {
+    /// `fund_transaction` is the transaction that funded the HTLC, `None` is
+    /// returned if the fee cannot be determined.
+    async fn paid_fee(
+        &self,
+        transaction: &L::Transaction,
+        fund_transaction: &L::Transaction,
+    ) -> anyhow::Result<Option<PaidFee>>;
+}
+
+#[async_trait]
+impl PaidFees<Bitcoin> for BitcoinConnector {
+    /// Without asking the node we only know the value of the HTLC output.
+    /// Hence we only know the fees of the redeem and refund transactions,
+    /// which are also the only ones cnd constructs itself.
+    async fn paid_fee(
+        &self,
+        transaction: &BitcoinTransaction,
+        fund_transaction: &BitcoinTransaction,
+    ) -> anyhow::Result<Option<PaidFee>> {
+        Ok(htlc_spend_fee(transaction, fund_transaction))
+    }
+}
+
+#[async_trait]
+impl PaidFees<Ethereum> for Web3Connector {
+    async fn paid_fee(
+        &self,
+        transaction: &EthereumTransaction,
+        _fund_transaction: &EthereumTransaction,
+    ) -> anyhow::Result<Option<PaidFee>> {
+        let gas_used = match self.receipt_by_hash(transaction.hash).await? {
+            Some(receipt) => receipt.gas_used,
+            None => None,
+        };
+        let amount = match gas_used.and_then(|gas_used| gas_used.checked_mul(transaction.gas_price))
+        {
+            Some(amount) if amount <= U256::from(u128::max_value()) => amount.low_u128(),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(PaidFee {
+            ledger: LedgerKind::Ethereum,
+            transaction_id: format!("{:#x}", transaction.hash),
+            amount,
+        }))
+    }
+}
+
+fn htlc_spend_fee(
+    transaction: &BitcoinTransaction,
+    fund_transaction: &BitcoinTransaction,
+) -> Option<PaidFee> {
+    let fund_txid = fund_transaction.txid();
+
+    let spent = transaction
+        .input
+        .iter()
+        .map(|input| {
+            if input.previous_output.txid != fund_txid {
+                return None;
+            }
+            fund_transaction
+                .output
+                .get(input.previous_output.vout as usize)
+                .map(|output| output.value)
+        })
+        .sum::<Option<u64>>()?;
+    let sent = transaction
+        .output
+        .iter()
+        .map(|output| output.value)
+        .sum::<u64>();
+
+    Some(PaidFee {
+        ledger: LedgerKind::Bitcoin,
+        transaction_id: transaction.txid().to_string(),
+        amount: u128::from(spent.checked_sub(sent)?),
+    })
+}
+
+/// Record the fees of all transactions we sent during the swap.
+///
+/// We deploy, fund and possibly refund the HTLC on the ledger we sell on and
+/// redeem the HTLC on the ledger we buy on.
+pub async fn record_fees<D, AL, BL>(
+    dependencies: &D,
+    swap_id: SwapId,
+    role: Role,
+    alpha_ledger_state: LedgerState<AL>,
+    beta_ledger_state: LedgerState<BL>,
+) -> anyhow::Result<()>
+where
+    D: SwapFees + PaidFees<AL> + PaidFees<BL>,
+    AL: Ledger,
+    BL: Ledger,
+{
+    let (alpha_funded_by_us, beta_funded_by_us) = match role {
+        Role::Alice => (true, false),
+        Role::Bob => (false, true),
+    };
+
+    let mut fees = paid_fees(dependencies, &alpha_ledger_state, alpha_funded_by_us).await?;
+    fees.extend(paid_fees(dependencies, &beta_ledger_state, beta_funded_by_us).await?);
+
+    SwapFees::record_swap_fees(dependencies, &swap_id, &fees).await
+}
+
+async fn paid_fees<D, L>(
+    dependencies: &D,
+    ledger_state: &LedgerState<L>,
+    funded_by_us: bool,
+) -> anyhow::Result<Vec<PaidFee>>
+where
+    D: PaidFees<L>,
+    L: Ledger,
+{
+    use self::LedgerState::*;
+
+    let (fund_transaction, mut transactions) = match ledger_state {
+        // Only HTLCs that need a separate fund transaction are ever in this
+        // state, for the fee of the deployment the fund transaction does not
+        // matter.
+        Deployed {
+            deploy_transaction, ..
+        } if funded_by_us => (deploy_transaction, vec![deploy_transaction]),
+        FundedUnconfirmed {
+            deploy_transaction,
+            fund_transaction,
+            ..
+        }
+        | Funded {
+            deploy_transaction,
+            fund_transaction,
+            ..
+        }
+        | IncorrectlyFunded {
+            deploy_transaction,
+            fund_transaction,
+            ..
+        } if funded_by_us => (fund_transaction, vec![deploy_transaction, fund_transaction]),
+        Redeemed {
+            deploy_transaction,
+            fund_transaction,
+            ..
+        } if funded_by_us => (fund_transaction, vec![deploy_transaction, fund_transaction]),
+        Redeemed {
+            fund_transaction,
+            redeem_transaction,
+            ..
+        } => (fund_transaction, vec![redeem_transaction]),
+        Refunded {
+            deploy_transaction,
+            fund_transaction,
+            refund_transaction,
+            ..
+        } if funded_by_us => (fund_transaction, vec![
+            deploy_transaction,
+            fund_transaction,
+            refund_transaction,
+        ]),
+        _ => return Ok(Vec::new()),
+    };
+    // Some HTLCs are deployed and funded by the same transaction.
+    transactions.dedup();
+
+    let mut fees = Vec::new();
+    for transaction in transactions {
+        if let Some(fee) = dependencies.paid_fee(transaction, fund_transaction).await? {
+            fees.push(fee);
+        }
+    }
+
+    Ok(fees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{OutPoint, Script, TxIn, TxOut};
+    use spectral::prelude::*;
+
+    fn transaction(input: Vec<TxIn>, output: Vec<u64>) -> BitcoinTransaction {
+        BitcoinTransaction {
+            version: 2,
+            lock_time: 0,
+            input,
+            output: output
+                .into_iter()
+                .map(|value| TxOut {
+                    value,
+                    script_pubkey: Script::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn spending(transaction: &BitcoinTransaction, vout: u32) -> TxIn {
+        TxIn {
+            previous_output: OutPoint::new(transaction.txid(), vout),
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: vec![],
+        }
+    }
+
+    #[test]
+    fn fee_of_htlc_spend_is_htlc_value_minus_outputs() {
+        let fund_transaction = transaction(vec![], vec![50_000, 100_000]);
+        let redeem_transaction = transaction(vec![spending(&fund_transaction, 1)], vec![99_000]);
+
+        let fee = htlc_spend_fee(&redeem_transaction, &fund_transaction);
+
+        assert_that(&fee)
+            .is_some()
+            .map(|fee| &fee.amount)
+            .is_equal_to(1_000);
+    }
+
+    #[test]
+    fn fee_of_transaction_not_spending_the_htlc_is_unknown() {
+        let fund_transaction = transaction(vec![], vec![100_000]);
+        let other_transaction = transaction(vec![], vec![200_000]);
+        let unrelated_spend = transaction(vec![spending(&other_transaction, 0)], vec![99_000]);
+
+        let fee = htlc_spend_fee(&unrelated_spend, &fund_transaction);
+
+        assert_that(&fee).is_none();
+    }
+}
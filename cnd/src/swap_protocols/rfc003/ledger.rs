@@ -1,7 +1,15 @@
-use crate::swap_protocols;
+use crate::swap_protocols::{self, rfc003::SecretSource};
 use serde::{de::DeserializeOwned, Serialize};
 use std::fmt::Debug;
 
 pub trait Ledger: swap_protocols::Ledger {
     type HtlcLocation: PartialEq + Debug + Clone + DeserializeOwned + Serialize + Send + Sync;
+
+    /// The identity cnd redeems with on this ledger if the user does not
+    /// supply one, `None` if it cannot act on the ledger by itself.
+    fn derive_redeem_identity(secret_source: &dyn SecretSource) -> Option<Self::Identity>;
+
+    /// The identity cnd refunds with on this ledger if the user does not
+    /// supply one, `None` if it cannot act on the ledger by itself.
+    fn derive_refund_identity(secret_source: &dyn SecretSource) -> Option<Self::Identity>;
 }
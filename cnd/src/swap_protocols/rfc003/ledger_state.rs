@@ -1,4 +1,4 @@
-use crate::swap_protocols::rfc003::ledger::Ledger;
+use crate::swap_protocols::rfc003::{events::TopUp, ledger::Ledger};
 use serde::Serialize;
 use strum_macros::EnumDiscriminants;
 
@@ -14,27 +14,41 @@ pub enum LedgerState<L: Ledger> {
         htlc_location: L::HtlcLocation,
         deploy_transaction: L::Transaction,
     },
+    /// The fund transaction was seen in the mempool but does not have the
+    /// required number of confirmations yet.
+    FundedUnconfirmed {
+        htlc_location: L::HtlcLocation,
+        deploy_transaction: L::Transaction,
+        fund_transaction: L::Transaction,
+        confirmations: u32,
+    },
     Funded {
         htlc_location: L::HtlcLocation,
         deploy_transaction: L::Transaction,
         fund_transaction: L::Transaction,
+        /// Further transactions that funded the HTLC, only possible for
+        /// Bitcoin.
+        top_ups: Vec<TopUp<L>>,
     },
     Redeemed {
         htlc_location: L::HtlcLocation,
         deploy_transaction: L::Transaction,
         fund_transaction: L::Transaction,
+        top_ups: Vec<TopUp<L>>,
         redeem_transaction: L::Transaction,
     },
     Refunded {
         htlc_location: L::HtlcLocation,
         deploy_transaction: L::Transaction,
         fund_transaction: L::Transaction,
+        top_ups: Vec<TopUp<L>>,
         refund_transaction: L::Transaction,
     },
     IncorrectlyFunded {
         htlc_location: L::HtlcLocation,
         deploy_transaction: L::Transaction,
         fund_transaction: L::Transaction,
+        top_ups: Vec<TopUp<L>>,
     },
 }
 
@@ -47,13 +61,14 @@ impl Default for HtlcState {
 #[cfg(test)]
 impl quickcheck::Arbitrary for HtlcState {
     fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
-        match g.next_u32() % 6 {
+        match g.next_u32() % 7 {
             0 => HtlcState::NotDeployed,
             1 => HtlcState::Deployed,
             2 => HtlcState::Funded,
             3 => HtlcState::Redeemed,
             4 => HtlcState::Refunded,
             5 => HtlcState::IncorrectlyFunded,
+            6 => HtlcState::FundedUnconfirmed,
             _ => unreachable!(),
         }
     }
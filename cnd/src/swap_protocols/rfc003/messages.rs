@@ -78,14 +78,30 @@ pub struct DeclineResponseBody {
     pub reason: Option<SwapDeclineReason>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
 #[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab_case")]
 pub enum SwapDeclineReason {
     UnsatisfactoryRate,
     UnsupportedProtocol,
     UnsupportedSwap,
     MissingMandatoryHeader,
     BadJsonField,
+    /// The peer is in maintenance mode, the request may be sent again later.
+    TemporarilyUnavailable,
+    /// The peer failed to process the request, e.g. because it could not save
+    /// it. The request may be sent again later.
+    InternalError,
 }
 
 pub trait ToRequest<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> {
@@ -93,7 +109,11 @@ pub trait ToRequest<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> {
 }
 
 pub trait IntoAcceptMessage<AL: Ledger, BL: Ledger> {
-    fn into_accept_message(self, id: SwapId, secret_source: &dyn SecretSource) -> Accept<AL, BL>;
+    fn into_accept_message(
+        self,
+        id: SwapId,
+        secret_source: &dyn SecretSource,
+    ) -> anyhow::Result<Accept<AL, BL>>;
 }
 
 #[cfg(test)]
@@ -134,6 +154,18 @@ mod tests {
         assert_eq!(response, expected_response);
     }
 
+    #[test]
+    fn serialize_decline_body_temporarily_unavailable() {
+        let decline_response_body = DeclineResponseBody {
+            reason: Some(SwapDeclineReason::TemporarilyUnavailable),
+        };
+
+        let response = serde_json::to_string(&decline_response_body).unwrap();
+        let expected_response = r#"{"reason":"temporarily-unavailable"}"#;
+
+        assert_eq!(response, expected_response);
+    }
+
     #[test]
     fn serialize_decline_body_missing_mandatory_header() {
         let decline_response_body = DeclineResponseBody {
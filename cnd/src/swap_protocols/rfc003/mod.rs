@@ -6,8 +6,11 @@ pub mod bitcoin;
 pub mod bob;
 pub mod ethereum;
 pub mod events;
+pub mod fees;
 pub mod ledger_state;
 pub mod messages;
+pub mod rate;
+pub mod state;
 pub mod state_machine;
 pub mod state_store;
 
@@ -17,14 +20,18 @@ mod ledger;
 mod save_state;
 mod secret;
 mod secret_source;
+#[cfg(test)]
+mod simulation_tests;
 
 pub use self::{
     actor_state::ActorState,
     ledger::Ledger,
     ledger_state::{HtlcState, LedgerState},
+    rate::{InvalidRate, Rate},
     save_state::SaveState,
     secret::{FromErr, Secret, SecretHash},
     secret_source::*,
+    state::{State, SwapCommunication},
 };
 
 pub use self::messages::{Accept, Decline, Request};
@@ -39,6 +46,41 @@ pub enum Error {
     TimerError,
     #[error("incorrect funding")]
     IncorrectFunding,
+    #[error("alpha ledger was not funded within the funding window")]
+    Abandoned,
+    #[error("neither HTLC was deployed before both expired")]
+    TimedOut,
     #[error("internal error: {0}")]
     Internal(String),
+    #[error("swap request failed: {0}")]
+    Network(#[from] crate::network::Error),
+}
+
+/// Coarse classification of `Error`, stable enough to be persisted and
+/// filtered on.
+#[derive(
+    Clone, Copy, Debug, PartialEq, serde::Serialize, strum_macros::Display, strum_macros::EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum FailureCategory {
+    Blockchain,
+    Timer,
+    Funding,
+    Expiry,
+    Internal,
+    Network,
+}
+
+impl Error {
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            Error::Btsieve => FailureCategory::Blockchain,
+            Error::TimerError => FailureCategory::Timer,
+            Error::IncorrectFunding | Error::Abandoned => FailureCategory::Funding,
+            Error::TimedOut => FailureCategory::Expiry,
+            Error::Internal(_) => FailureCategory::Internal,
+            Error::Network(_) => FailureCategory::Network,
+        }
+    }
 }
@@ -0,0 +1,64 @@
+use bigdecimal::{BigDecimal, Zero};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[error("the rate must be a positive decimal number, e.g. 40.5")]
+pub struct InvalidRate;
+
+/// The price Alice expects for a swap in units of the beta asset per unit of
+/// the alpha asset, e.g. `40.5` Ether per Bitcoin.
+///
+/// Only meant to spare Bob working it out from the assets, the assets of the
+/// request are what is swapped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rate(BigDecimal);
+
+impl FromStr for Rate {
+    type Err = InvalidRate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rate = BigDecimal::from_str(s).map_err(|_| InvalidRate)?;
+
+        if rate <= BigDecimal::zero() {
+            return Err(InvalidRate);
+        }
+
+        Ok(Rate(rate))
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rate = String::deserialize(deserializer)?;
+
+        Rate::from_str(&rate).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn rates_must_be_positive() {
+        assert_that(&Rate::from_str("40.5").map(|rate| rate.to_string()))
+            .is_ok_containing(String::from("40.5"));
+        assert_that(&Rate::from_str("0")).is_err();
+        assert_that(&Rate::from_str("-1")).is_err();
+        assert_that(&Rate::from_str("forty")).is_err();
+    }
+}
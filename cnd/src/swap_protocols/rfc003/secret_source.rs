@@ -1,4 +1,4 @@
-use crate::{seed::Seed, swap_protocols::rfc003::Secret};
+use crate::{derivation::Identities, seed::Seed, swap_protocols::rfc003::Secret};
 use bitcoin::secp256k1::SecretKey;
 
 pub trait SecretSource: Send + Sync + 'static {
@@ -22,3 +22,45 @@ impl SecretSource for Seed {
             .expect("The probability of this happening is < 1 in 2^120")
     }
 }
+
+/// The secret source of a swap, the identities are derived from the BIP32
+/// tree of the seed if a derivation is configured.
+#[derive(Clone, Copy, Debug)]
+pub struct SwapSecrets {
+    seed: Seed,
+    identities: Option<Identities>,
+}
+
+impl SwapSecrets {
+    pub fn new(seed: Seed, identities: Option<Identities>) -> Self {
+        Self { seed, identities }
+    }
+
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    pub fn identities(&self) -> Option<Identities> {
+        self.identities
+    }
+}
+
+impl SecretSource for SwapSecrets {
+    fn secret(&self) -> Secret {
+        self.seed.secret()
+    }
+
+    fn secp256k1_redeem(&self) -> SecretKey {
+        match self.identities {
+            Some(identities) => identities.redeem,
+            None => self.seed.secp256k1_redeem(),
+        }
+    }
+
+    fn secp256k1_refund(&self) -> SecretKey {
+        match self.identities {
+            Some(identities) => identities.refund,
+            None => self.seed.secp256k1_refund(),
+        }
+    }
+}
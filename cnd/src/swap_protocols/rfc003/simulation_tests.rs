@@ -0,0 +1,602 @@
+//! Drives the state machines of Alice's and Bob's cnd in-process against two
+//! simulated ledgers.
+//!
+//! Every simulation step one of the ledgers mines a block, which ledger that
+//! is follows a schedule. For each scenario all fair schedules are simulated,
+//! i.e. those in which no ledger goes more than `MAX_BLOCK_INTERVAL` steps
+//! without a block. The parties act honestly on what their state machine
+//! reports, each run has to end with both swaps in a terminal state that
+//! agrees with the ledgers and in which no party lost its asset.
+
+use crate::{
+    seed::Seed,
+    swap_protocols::{
+        ledger::Bitcoin,
+        rfc003::{
+            self,
+            alice::{self, Alice},
+            bob::{self, Bob},
+            events::{
+                Deployed, DeployedFuture, Funded, FundedFuture, HtlcEvents, LedgerEventFutures,
+                LedgerEvents, Redeemed, RedeemedOrRefundedFuture, Refunded,
+            },
+            state::Actor,
+            state_machine::{self, HtlcParams, SwapStates},
+            state_store::{InMemoryStateStore, StateStore},
+            Accept, HtlcState, LedgerState, Request, Secret, SecretSource, State,
+        },
+        HashFunction, SwapId,
+    },
+    timestamp::Timestamp,
+};
+use bitcoin::{Amount, OutPoint};
+use futures::{
+    executor::{self, Notify, NotifyHandle, Spawn},
+    future::{self, Either},
+    Async, Future, Stream,
+};
+use std::{
+    collections::VecDeque,
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Transactions are reported to the state machines once they are this deep,
+/// shallower reorgs go unnoticed.
+const CONFIRMATIONS: usize = 2;
+const MAX_BLOCK_INTERVAL: usize = 4;
+const SCHEDULE_PERIOD: usize = 12;
+const MAX_STEPS: u32 = 120;
+
+/// A transaction that was mined once may be mined again after a reorg, hence
+/// it can take up to three block intervals until it stays in the chain.
+const INCLUSION_TIME: u32 = 3 * MAX_BLOCK_INTERVAL as u32;
+const BETA_EXPIRY: u32 = 48;
+const ALPHA_EXPIRY: u32 = BETA_EXPIRY + 2 * INCLUSION_TIME;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    Alpha,
+    Beta,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Fund,
+    Redeem,
+    Refund,
+}
+
+/// A ledger that holds a single HTLC.
+#[derive(Debug)]
+struct Chain {
+    asset: Amount,
+    expiry: u32,
+    secret: Secret,
+    blocks: Vec<Vec<Kind>>,
+    mempool: VecDeque<Kind>,
+    /// Once a block with this kind of transaction is the tip, the next block
+    /// replaces it and the transaction goes back to the mempool.
+    reorg_once: Option<Kind>,
+}
+
+impl Chain {
+    fn submit(&mut self, kind: Kind) {
+        let known =
+            self.mempool.contains(&kind) || self.blocks.iter().flatten().any(|k| *k == kind);
+
+        if !known {
+            self.mempool.push_back(kind);
+        }
+    }
+
+    fn mine(&mut self, now: u32) {
+        let reorg = self
+            .reorg_once
+            .filter(|kind| self.blocks.last().map_or(false, |tip| tip.contains(kind)));
+        if reorg.is_some() {
+            let stale = self.blocks.pop().expect("tip contains a transaction");
+            for kind in stale.into_iter().rev() {
+                self.mempool.push_front(kind);
+            }
+            self.reorg_once = None;
+            self.blocks.push(Vec::new());
+
+            return;
+        }
+
+        let mut block = Vec::new();
+        while let Some(kind) = self.mempool.pop_front() {
+            if self.is_valid(&block, kind, now) {
+                block.push(kind);
+            }
+        }
+        self.blocks.push(block);
+    }
+
+    fn is_valid(&self, block: &[Kind], kind: Kind, now: u32) -> bool {
+        let mined = || self.blocks.iter().flatten().chain(block.iter());
+        let funded = mined().any(|k| *k == Kind::Fund);
+        let spent = mined().any(|k| *k != Kind::Fund);
+
+        match kind {
+            Kind::Fund => !funded,
+            Kind::Redeem => funded && !spent,
+            Kind::Refund => funded && !spent && now >= self.expiry,
+        }
+    }
+
+    fn confirmed(&self, kind: Kind) -> Option<bitcoin::Transaction> {
+        let confirmed = self.blocks.len().saturating_sub(CONFIRMATIONS - 1);
+
+        self.blocks[..confirmed]
+            .iter()
+            .flatten()
+            .find(|k| **k == kind)
+            .map(|kind| transaction(*kind))
+    }
+
+    fn htlc_state(&self) -> HtlcState {
+        let mined = |kind| self.blocks.iter().flatten().any(|k| *k == kind);
+
+        if mined(Kind::Redeem) {
+            HtlcState::Redeemed
+        } else if mined(Kind::Refund) {
+            HtlcState::Refunded
+        } else if mined(Kind::Fund) {
+            HtlcState::Funded
+        } else {
+            HtlcState::NotDeployed
+        }
+    }
+}
+
+fn transaction(kind: Kind) -> bitcoin::Transaction {
+    bitcoin::Transaction {
+        version: 2,
+        lock_time: kind as u32,
+        input: Vec::new(),
+        output: Vec::new(),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SimulatedLedger(Arc<Mutex<Chain>>);
+
+impl SimulatedLedger {
+    fn new(asset: Amount, expiry: u32, secret: Secret, reorg_once: Option<Kind>) -> Self {
+        SimulatedLedger(Arc::new(Mutex::new(Chain {
+            asset,
+            expiry,
+            secret,
+            blocks: Vec::new(),
+            mempool: VecDeque::new(),
+            reorg_once,
+        })))
+    }
+
+    fn chain(&self) -> MutexGuard<'_, Chain> {
+        self.0.lock().expect("no other thread panicked")
+    }
+
+    fn ledger_events(&self) -> Box<dyn LedgerEvents<Bitcoin, Amount>> {
+        Box::new(LedgerEventFutures::new(Box::new(self.clone())))
+    }
+
+    /// The simulation polls the state machines after every block, hence the
+    /// future does not need to be woken up.
+    fn wait_for<T>(
+        &self,
+        event: impl Fn(&Chain) -> Option<T> + Send + 'static,
+    ) -> impl Future<Item = T, Error = rfc003::Error> + Send {
+        let ledger = self.clone();
+
+        future::poll_fn(move || {
+            Ok(match event(&*ledger.chain()) {
+                Some(event) => Async::Ready(event),
+                None => Async::NotReady,
+            })
+        })
+    }
+}
+
+impl HtlcEvents<Bitcoin, Amount> for SimulatedLedger {
+    fn htlc_deployed(
+        &self,
+        _htlc_params: HtlcParams<Bitcoin, Amount>,
+    ) -> Box<DeployedFuture<Bitcoin>> {
+        Box::new(self.wait_for(|chain| {
+            chain.confirmed(Kind::Fund).map(|transaction| Deployed {
+                location: OutPoint {
+                    txid: transaction.txid(),
+                    vout: 0,
+                },
+                transaction,
+            })
+        }))
+    }
+
+    fn htlc_funded(
+        &self,
+        _htlc_params: HtlcParams<Bitcoin, Amount>,
+        _htlc_deployment: &Deployed<Bitcoin>,
+    ) -> Box<FundedFuture<Bitcoin, Amount>> {
+        Box::new(self.wait_for(|chain| {
+            chain.confirmed(Kind::Fund).map(|transaction| Funded {
+                transaction,
+                asset: chain.asset,
+                top_ups: Vec::new(),
+            })
+        }))
+    }
+
+    fn htlc_redeemed_or_refunded(
+        &self,
+        _htlc_params: HtlcParams<Bitcoin, Amount>,
+        _htlc_deployment: &Deployed<Bitcoin>,
+        _htlc_funding: &Funded<Bitcoin, Amount>,
+    ) -> Box<RedeemedOrRefundedFuture<Bitcoin>> {
+        Box::new(self.wait_for(|chain| {
+            if let Some(transaction) = chain.confirmed(Kind::Redeem) {
+                return Some(Either::A(Redeemed {
+                    transaction,
+                    secret: chain.secret,
+                }));
+            }
+
+            chain
+                .confirmed(Kind::Refund)
+                .map(|transaction| Either::B(Refunded::new(transaction)))
+        }))
+    }
+}
+
+type SimulatedState<R> = State<Bitcoin, Bitcoin, Amount, Amount, R>;
+type Execution = Box<dyn Future<Item = (), Error = rfc003::Error> + Send>;
+type Updates =
+    Box<dyn Stream<Item = SwapStates<Bitcoin, Bitcoin, Amount, Amount>, Error = ()> + Send>;
+
+struct NoopNotify;
+
+impl Notify for NoopNotify {
+    fn notify(&self, _id: usize) {}
+}
+
+/// The cnd of one party, it watches the ledgers with a state machine of its
+/// own.
+struct Node<R: Actor> {
+    swap_id: SwapId,
+    state_store: InMemoryStateStore,
+    execution: Spawn<Execution>,
+    updates: Spawn<Updates>,
+    finished: bool,
+    role: PhantomData<R>,
+}
+
+impl<R: Actor> Node<R> {
+    fn new(
+        state: SimulatedState<R>,
+        accept: Accept<Bitcoin, Bitcoin>,
+        alpha: &SimulatedLedger,
+        beta: &SimulatedLedger,
+    ) -> Self {
+        let request = state.request();
+        let swap_id = request.swap_id;
+        let state_store = InMemoryStateStore::default();
+        state_store.insert(swap_id, state);
+
+        let (execution, updates) = state_machine::create_swap(
+            alpha.ledger_events(),
+            beta.ledger_events(),
+            request,
+            accept,
+        );
+
+        Self {
+            swap_id,
+            state_store,
+            execution: executor::spawn(Box::new(execution) as Execution),
+            updates: executor::spawn(Box::new(updates) as Updates),
+            finished: false,
+            role: PhantomData,
+        }
+    }
+
+    fn poll(&mut self) {
+        let notify = NotifyHandle::from(Arc::new(NoopNotify));
+
+        if !self.finished {
+            self.finished = match self.execution.poll_future_notify(&notify, 0) {
+                Ok(Async::NotReady) => false,
+                Ok(Async::Ready(())) | Err(_) => true,
+            };
+        }
+
+        while let Ok(Async::Ready(Some(update))) = self.updates.poll_stream_notify(&notify, 0) {
+            StateStore::update::<SimulatedState<R>>(&self.state_store, &self.swap_id, update);
+        }
+    }
+
+    fn state(&self) -> SimulatedState<R> {
+        StateStore::get(&self.state_store, &self.swap_id)
+            .expect("state has the type it was inserted with")
+            .expect("state was inserted")
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Scenario {
+    alice_funds_at: u32,
+    bob_funds_at: u32,
+    alice_redeems: bool,
+    alpha_reorg: Option<Kind>,
+    beta_reorg: Option<Kind>,
+    /// What the HTLCs on alpha and beta end up as.
+    expected: (HtlcState, HtlcState),
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            alice_funds_at: 0,
+            bob_funds_at: 0,
+            alice_redeems: true,
+            alpha_reorg: None,
+            beta_reorg: None,
+            expected: (HtlcState::Redeemed, HtlcState::Redeemed),
+        }
+    }
+}
+
+struct Simulation {
+    scenario: Scenario,
+    alpha: SimulatedLedger,
+    beta: SimulatedLedger,
+    alice: Node<Alice>,
+    bob: Node<Bob>,
+}
+
+impl Simulation {
+    fn new(scenario: Scenario) -> Self {
+        let swap_id = SwapId::default();
+        let alice_seed = Seed::from(*b"hello world, you are beautiful!!").swap_seed(swap_id);
+        let bob_seed = Seed::from(*b"simulated bob, watching ledgers!").swap_seed(swap_id);
+        let identity = crate::bitcoin::PublicKey::new(
+            "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"
+                .parse()
+                .unwrap(),
+        );
+
+        let request = Request {
+            swap_id,
+            alpha_ledger: Bitcoin::default(),
+            beta_ledger: Bitcoin::default(),
+            alpha_asset: Amount::from_sat(100_000_000),
+            beta_asset: Amount::from_sat(50_000_000),
+            hash_function: HashFunction::Sha256,
+            alpha_ledger_refund_identity: identity,
+            beta_ledger_redeem_identity: identity,
+            alpha_expiry: Timestamp::from(ALPHA_EXPIRY),
+            beta_expiry: Timestamp::from(BETA_EXPIRY),
+            secret_hash: alice_seed.secret().hash(),
+        };
+        let accept = Accept {
+            swap_id,
+            beta_ledger_refund_identity: identity,
+            alpha_ledger_redeem_identity: identity,
+        };
+
+        let secret = alice_seed.secret();
+        let alpha = SimulatedLedger::new(
+            request.alpha_asset,
+            ALPHA_EXPIRY,
+            secret,
+            scenario.alpha_reorg,
+        );
+        let beta =
+            SimulatedLedger::new(request.beta_asset, BETA_EXPIRY, secret, scenario.beta_reorg);
+
+        let alice = Node::new(
+            alice::State::accepted(request.clone(), accept, alice_seed),
+            accept,
+            &alpha,
+            &beta,
+        );
+        let bob = Node::new(
+            bob::State::accepted(request, accept, bob_seed),
+            accept,
+            &alpha,
+            &beta,
+        );
+
+        Self {
+            scenario,
+            alpha,
+            beta,
+            alice,
+            bob,
+        }
+    }
+
+    fn run(&mut self, schedule: &[Side]) {
+        for (now, side) in (0..MAX_STEPS).zip(schedule.iter().cycle()) {
+            self.alice_acts(now);
+            self.bob_acts(now);
+
+            match side {
+                Side::Alpha => self.alpha.chain().mine(now),
+                Side::Beta => self.beta.chain().mine(now),
+            }
+
+            self.alice.poll();
+            self.bob.poll();
+
+            if self.alice.finished && self.bob.finished {
+                return;
+            }
+        }
+    }
+
+    fn alice_acts(&self, now: u32) {
+        let state = self.alice.state();
+
+        match state.alpha_ledger_state {
+            LedgerState::NotDeployed if now >= self.scenario.alice_funds_at => {
+                self.alpha.chain().submit(Kind::Fund)
+            }
+            LedgerState::Funded { .. } if now >= ALPHA_EXPIRY => {
+                self.alpha.chain().submit(Kind::Refund)
+            }
+            _ => {}
+        }
+
+        // Redeeming late would let Bob refund first and still redeem alpha.
+        if let LedgerState::Funded { .. } = state.beta_ledger_state {
+            if self.scenario.alice_redeems && now + INCLUSION_TIME < BETA_EXPIRY {
+                self.beta.chain().submit(Kind::Redeem)
+            }
+        }
+    }
+
+    fn bob_acts(&self, now: u32) {
+        let state = self.bob.state();
+
+        match (&state.alpha_ledger_state, &state.beta_ledger_state) {
+            (LedgerState::Funded { .. }, LedgerState::NotDeployed)
+                if now >= self.scenario.bob_funds_at && now + 2 * INCLUSION_TIME < BETA_EXPIRY =>
+            {
+                self.beta.chain().submit(Kind::Fund)
+            }
+            (_, LedgerState::Funded { .. }) if now >= BETA_EXPIRY => {
+                self.beta.chain().submit(Kind::Refund)
+            }
+            _ => {}
+        }
+
+        if let (LedgerState::Funded { .. }, Some(_)) = (&state.alpha_ledger_state, state.secret) {
+            self.alpha.chain().submit(Kind::Redeem)
+        }
+    }
+
+    fn assert_ended_safely(&self, schedule: &[Side]) {
+        let alpha = self.alpha.chain().htlc_state();
+        let beta = self.beta.chain().htlc_state();
+        let alice = self.alice.state();
+        let bob = self.bob.state();
+
+        let context = format!("{:?} with schedule {:?}", self.scenario, schedule);
+
+        assert!(
+            self.alice.finished && self.bob.finished,
+            "swap did not end: {}",
+            context
+        );
+        assert_eq!(alice.error, None, "{}", context);
+        assert_eq!(bob.error, None, "{}", context);
+
+        for state in vec![alice.alpha_ledger_state, bob.alpha_ledger_state] {
+            assert_eq!(
+                HtlcState::from(state),
+                alpha,
+                "view of alpha is off: {}",
+                context
+            );
+        }
+        for state in vec![alice.beta_ledger_state, bob.beta_ledger_state] {
+            assert_eq!(
+                HtlcState::from(state),
+                beta,
+                "view of beta is off: {}",
+                context
+            );
+        }
+
+        assert_ne!(
+            (alpha, beta),
+            (HtlcState::Redeemed, HtlcState::Refunded),
+            "Alice lost alpha: {}",
+            context
+        );
+        assert_ne!(
+            (alpha, beta),
+            (HtlcState::Refunded, HtlcState::Redeemed),
+            "Bob lost beta: {}",
+            context
+        );
+        assert_eq!((alpha, beta), self.scenario.expected, "{}", context);
+    }
+}
+
+/// All schedules of `SCHEDULE_PERIOD` steps, repeated, in which neither
+/// ledger waits `MAX_BLOCK_INTERVAL` steps or more for its next block.
+fn fair_schedules() -> Vec<Vec<Side>> {
+    (0u32..1 << SCHEDULE_PERIOD)
+        .map(|bits| {
+            (0..SCHEDULE_PERIOD)
+                .map(|i| {
+                    if (bits >> i) & 1 == 1 {
+                        Side::Alpha
+                    } else {
+                        Side::Beta
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|schedule| {
+            (0..schedule.len()).all(|start| {
+                (0..MAX_BLOCK_INTERVAL)
+                    .any(|offset| schedule[(start + offset) % schedule.len()] != schedule[start])
+            })
+        })
+        .collect()
+}
+
+fn every_interleaving_ends_safely(scenario: Scenario) {
+    for schedule in fair_schedules() {
+        let mut simulation = Simulation::new(scenario);
+        simulation.run(&schedule);
+        simulation.assert_ended_safely(&schedule);
+    }
+}
+
+#[test]
+fn both_redeem() {
+    every_interleaving_ends_safely(Scenario::default())
+}
+
+#[test]
+fn both_redeem_with_delayed_funding() {
+    every_interleaving_ends_safely(Scenario {
+        alice_funds_at: 5,
+        bob_funds_at: 15,
+        ..Scenario::default()
+    })
+}
+
+#[test]
+fn both_redeem_if_funding_and_redeeming_are_reorged() {
+    every_interleaving_ends_safely(Scenario {
+        alpha_reorg: Some(Kind::Fund),
+        beta_reorg: Some(Kind::Redeem),
+        ..Scenario::default()
+    })
+}
+
+#[test]
+fn alice_refunds_if_bob_would_fund_too_late() {
+    every_interleaving_ends_safely(Scenario {
+        bob_funds_at: BETA_EXPIRY - INCLUSION_TIME,
+        expected: (HtlcState::Refunded, HtlcState::NotDeployed),
+        ..Scenario::default()
+    })
+}
+
+#[test]
+fn both_refund_if_alice_does_not_redeem() {
+    every_interleaving_ends_safely(Scenario {
+        alice_redeems: false,
+        beta_reorg: Some(Kind::Fund),
+        expected: (HtlcState::Refunded, HtlcState::Refunded),
+        ..Scenario::default()
+    })
+}
@@ -0,0 +1,153 @@
+use crate::swap_protocols::{
+    asset::Asset,
+    rfc003::{
+        self, ledger::Ledger, ledger_state::LedgerState, messages, secret_source::SecretSource,
+        ActorState, Secret,
+    },
+    Role,
+};
+use derivative::Derivative;
+use std::{fmt::Debug, marker::PhantomData, sync::Arc};
+
+/// The role we play in a swap, lifted to the type level.
+///
+/// The state of a swap looks the same for both roles, only the actions that
+/// can be derived from it differ. These are implemented per role on
+/// `State<AL, BL, AA, BA, R>`.
+pub trait Actor: Clone + Copy + Debug + PartialEq + Send + Sync + 'static {
+    const ROLE: Role;
+}
+
+#[derive(Clone, Derivative)]
+#[derivative(Debug, PartialEq)]
+pub struct State<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset, R: Actor> {
+    pub swap_communication: SwapCommunication<AL, BL, AA, BA>,
+    pub alpha_ledger_state: LedgerState<AL>,
+    pub beta_ledger_state: LedgerState<BL>,
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub secret_source: Arc<dyn SecretSource>,
+    /// The secret as learned from the ledger, Alice knows it upfront through
+    /// `secret_source`.
+    pub secret: Option<Secret>,
+    pub error: Option<rfc003::Error>,
+    #[derivative(Debug = "ignore")]
+    role: PhantomData<R>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SwapCommunication<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> {
+    Proposed {
+        request: messages::Request<AL, BL, AA, BA>,
+    },
+    Accepted {
+        request: messages::Request<AL, BL, AA, BA>,
+        response: messages::Accept<AL, BL>,
+    },
+    Declined {
+        request: messages::Request<AL, BL, AA, BA>,
+        response: messages::Decline,
+    },
+    /// The request did not yield a valid response, `State::error` says why.
+    Failed {
+        request: messages::Request<AL, BL, AA, BA>,
+    },
+}
+
+impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset, R: Actor> State<AL, BL, AA, BA, R> {
+    fn new(
+        swap_communication: SwapCommunication<AL, BL, AA, BA>,
+        secret_source: impl SecretSource,
+        error: Option<rfc003::Error>,
+    ) -> Self {
+        Self {
+            swap_communication,
+            alpha_ledger_state: LedgerState::NotDeployed,
+            beta_ledger_state: LedgerState::NotDeployed,
+            secret_source: Arc::new(secret_source),
+            secret: None,
+            error,
+            role: PhantomData,
+        }
+    }
+
+    pub fn proposed(
+        request: messages::Request<AL, BL, AA, BA>,
+        secret_source: impl SecretSource,
+    ) -> Self {
+        Self::new(SwapCommunication::Proposed { request }, secret_source, None)
+    }
+
+    pub fn accepted(
+        request: messages::Request<AL, BL, AA, BA>,
+        response: messages::Accept<AL, BL>,
+        secret_source: impl SecretSource,
+    ) -> Self {
+        Self::new(
+            SwapCommunication::Accepted { request, response },
+            secret_source,
+            None,
+        )
+    }
+
+    pub fn declined(
+        request: messages::Request<AL, BL, AA, BA>,
+        response: messages::Decline,
+        secret_source: impl SecretSource,
+    ) -> Self {
+        Self::new(
+            SwapCommunication::Declined { request, response },
+            secret_source,
+            None,
+        )
+    }
+
+    pub fn failed(
+        request: messages::Request<AL, BL, AA, BA>,
+        error: rfc003::Error,
+        secret_source: impl SecretSource,
+    ) -> Self {
+        Self::new(
+            SwapCommunication::Failed { request },
+            secret_source,
+            Some(error),
+        )
+    }
+
+    pub fn role(&self) -> Role {
+        R::ROLE
+    }
+
+    pub fn request(&self) -> messages::Request<AL, BL, AA, BA> {
+        match &self.swap_communication {
+            SwapCommunication::Accepted { request, .. }
+            | SwapCommunication::Proposed { request }
+            | SwapCommunication::Declined { request, .. }
+            | SwapCommunication::Failed { request } => request.clone(),
+        }
+    }
+}
+
+impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset, R: Actor> ActorState
+    for State<AL, BL, AA, BA, R>
+{
+    type AL = AL;
+    type BL = BL;
+    type AA = AA;
+    type BA = BA;
+
+    fn set_secret(&mut self, secret: Secret) {
+        self.secret = Some(secret)
+    }
+
+    fn set_error(&mut self, error: rfc003::Error) {
+        self.error = Some(error)
+    }
+
+    fn alpha_ledger_mut(&mut self) -> &mut LedgerState<AL> {
+        &mut self.alpha_ledger_state
+    }
+
+    fn beta_ledger_mut(&mut self) -> &mut LedgerState<BL> {
+        &mut self.beta_ledger_state
+    }
+}
@@ -195,7 +195,7 @@ pub enum Swap<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> {
         alpha_funded: Funded<AL, AA>,
     },
 
-    #[state_machine_future(transitions(BothFunded, Final))]
+    #[state_machine_future(transitions(BothFunded, AlphaFundedBetaIncorrectlyFunded, Final))]
     AlphaFundedBetaDeployed {
         swap: OngoingSwap<AL, BL, AA, BA>,
         alpha_deployed: Deployed<AL>,
@@ -257,13 +257,28 @@ pub enum Swap<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> {
         beta_redeem_transaction: Redeemed<BL>,
     },
 
-    #[state_machine_future(transitions(Final))]
+    #[state_machine_future(transitions(AlphaFunded, AlphaIncorrectlyFunded, Final))]
     AlphaIncorrectlyFunded {
         swap: OngoingSwap<AL, BL, AA, BA>,
         alpha_deployed: Deployed<AL>,
         alpha_funded: Funded<AL, AA>,
     },
 
+    #[state_machine_future(transitions(
+        BothFunded,
+        AlphaFundedBetaIncorrectlyFunded,
+        AlphaFundedBetaRedeemed,
+        AlphaFundedBetaRefunded,
+        Final,
+    ))]
+    AlphaFundedBetaIncorrectlyFunded {
+        swap: OngoingSwap<AL, BL, AA, BA>,
+        alpha_deployed: Deployed<AL>,
+        alpha_funded: Funded<AL, AA>,
+        beta_deployed: Deployed<BL>,
+        beta_funded: Funded<BL, BA>,
+    },
+
     #[state_machine_future(ready)]
     Final(SwapOutcome<AL, BL, AA, BA>),
 
@@ -271,18 +286,22 @@ pub enum Swap<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> {
     Error(rfc003::Error),
 }
 
+/// Builds the state machine of a swap that follows the HTLCs through the given
+/// ledger events, anything implementing `LedgerEvents` can drive it, e.g. the
+/// simulated ledgers in `simulation_tests`.
 pub fn create_swap<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
     alpha_ledger_events: Box<dyn LedgerEvents<AL, AA>>,
     beta_ledger_events: Box<dyn LedgerEvents<BL, BA>>,
     request: Request<AL, BL, AA, BA>,
     accept: Accept<AL, BL>,
 ) -> (
-    impl Future<Item = (), Error = ()> + Send + 'static,
+    impl Future<Item = (), Error = rfc003::Error> + Send + 'static,
     impl Stream<Item = SwapStates<AL, BL, AA, BA>, Error = ()> + Send + 'static,
 ) {
     let id = request.swap_id;
 
     let (sender, receiver) = mpsc::unbounded();
+    let error_repo = sender.clone();
 
     let context = Context {
         alpha_ledger_events,
@@ -297,7 +316,20 @@ pub fn create_swap<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
         context,
     )
     .map(move |outcome| log::info!("Swap {} finished with {:?}", id, outcome))
-    .map_err(move |e| log::error!("Swap {} failed with {:?}", id, e));
+    .map_err(move |e| {
+        // The swap did not fail if only its watcher stopped, it is restarted
+        // from what was persisted instead.
+        if e == rfc003::Error::Btsieve {
+            return e;
+        }
+
+        log::error!("Swap {} failed with {:?}", id, e);
+        // Errors do not go through `transition_save!`, hand them to the
+        // subscriber explicitly so the failure is not only in the log.
+        error_repo.save(SwapStates::Error(Error(e.clone())));
+
+        e
+    });
 
     (swap_execution, receiver)
 }
@@ -397,42 +429,63 @@ impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> PollSwap<AL, BL, AA, BA>
         state: &'s mut RentToOwn<'s, AlphaIncorrectlyFunded<AL, BL, AA, BA>>,
         context: &'c mut RentToOwn<'c, Context<AL, BL, AA, BA>>,
     ) -> Result<Async<AfterAlphaIncorrectlyFunded<AL, BL, AA, BA>>, rfc003::Error> {
-        let alpha_redeemed_or_refunded = try_ready!(context
+        if let Async::Ready(alpha_redeemed_or_refunded) = context
             .alpha_ledger_events
             .htlc_redeemed_or_refunded(
                 state.swap.alpha_htlc_params(),
                 &state.alpha_deployed,
                 &state.alpha_funded,
             )
+            .poll()?
+        {
+            let state = state.take();
+            match alpha_redeemed_or_refunded {
+                future::Either::A(redeem_transaction) => transition_save!(
+                    context.state_repo,
+                    Final(SwapOutcome::AlphaRedeemed {
+                        swap: state.swap,
+                        alpha_deployed: state.alpha_deployed,
+                        alpha_funded: state.alpha_funded,
+                        alpha_redeemed: redeem_transaction
+                    })
+                ),
+                future::Either::B(refund_transaction) => transition_save!(
+                    context.state_repo,
+                    Final(SwapOutcome::AlphaRefunded {
+                        swap: state.swap,
+                        alpha_deployed: state.alpha_deployed,
+                        alpha_funded: state.alpha_funded,
+                        alpha_refunded: refund_transaction
+                    })
+                ),
+            }
+        }
+
+        // Funding the HTLC again can make up for a wrong amount.
+        let alpha_funded = try_ready!(context
+            .alpha_ledger_events
+            .htlc_topped_up(
+                state.swap.alpha_htlc_params(),
+                &state.alpha_deployed,
+                &state.alpha_funded,
+            )
             .poll());
         let state = state.take();
-        match alpha_redeemed_or_refunded {
-            future::Either::A(redeem_transaction) => transition_save!(
-                context.state_repo,
-                Final(SwapOutcome::AlphaRedeemed {
-                    swap: state.swap,
-                    alpha_deployed: state.alpha_deployed,
-                    alpha_funded: state.alpha_funded,
-                    alpha_redeemed: redeem_transaction
-                })
-            ),
-            future::Either::B(refund_transaction) => transition_save!(
-                context.state_repo,
-                Final(SwapOutcome::AlphaRefunded {
-                    swap: state.swap,
-                    alpha_deployed: state.alpha_deployed,
-                    alpha_funded: state.alpha_funded,
-                    alpha_refunded: refund_transaction
-                })
-            ),
+
+        match alpha_funded.asset.cmp(&state.swap.alpha_asset) {
+            Equal => transition_save!(context.state_repo, AlphaFunded {
+                swap: state.swap,
+                alpha_funded,
+                alpha_deployed: state.alpha_deployed,
+            }),
+            _ => transition_save!(context.state_repo, AlphaIncorrectlyFunded {
+                swap: state.swap,
+                alpha_deployed: state.alpha_deployed,
+                alpha_funded,
+            }),
         }
     }
 
-    /// This function returns an error if beta was incorrectly funded (either
-    /// too much or not enough) We will need to cover this case in the
-    /// future, however, with the current design our state machine would
-    /// explode and we would need to add too many extra states to cover that
-    /// case. See issue #1155
     fn poll_alpha_funded_beta_deployed<'s, 'c>(
         state: &'s mut RentToOwn<'s, AlphaFundedBetaDeployed<AL, BL, AA, BA>>,
         context: &'c mut RentToOwn<'c, Context<AL, BL, AA, BA>>,
@@ -483,7 +536,112 @@ impl<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset> PollSwap<AL, BL, AA, BA>
                 beta_deployed: state.beta_deployed,
                 beta_funded
             }),
-            _ => Err(rfc003::Error::IncorrectFunding),
+            _ => transition_save!(context.state_repo, AlphaFundedBetaIncorrectlyFunded {
+                swap: state.swap,
+                alpha_funded: state.alpha_funded,
+                alpha_deployed: state.alpha_deployed,
+                beta_deployed: state.beta_deployed,
+                beta_funded
+            }),
+        }
+    }
+
+    fn poll_alpha_funded_beta_incorrectly_funded<'s, 'c>(
+        state: &'s mut RentToOwn<'s, AlphaFundedBetaIncorrectlyFunded<AL, BL, AA, BA>>,
+        context: &'c mut RentToOwn<'c, Context<AL, BL, AA, BA>>,
+    ) -> Result<Async<AfterAlphaFundedBetaIncorrectlyFunded<AL, BL, AA, BA>>, rfc003::Error> {
+        if let Async::Ready(beta_redeemed_or_refunded) = context
+            .beta_ledger_events
+            .htlc_redeemed_or_refunded(
+                state.swap.beta_htlc_params(),
+                &state.beta_deployed,
+                &state.beta_funded,
+            )
+            .poll()?
+        {
+            let state = state.take();
+            match beta_redeemed_or_refunded {
+                future::Either::A(beta_redeem_transaction) => {
+                    transition_save!(context.state_repo, AlphaFundedBetaRedeemed {
+                        swap: state.swap,
+                        alpha_deployed: state.alpha_deployed,
+                        alpha_funded: state.alpha_funded,
+                        beta_deployed: state.beta_deployed,
+                        beta_funded: state.beta_funded,
+                        beta_redeem_transaction,
+                    })
+                }
+                future::Either::B(beta_refund_transaction) => {
+                    transition_save!(context.state_repo, AlphaFundedBetaRefunded {
+                        swap: state.swap,
+                        alpha_deployed: state.alpha_deployed,
+                        alpha_funded: state.alpha_funded,
+                        beta_deployed: state.beta_deployed,
+                        beta_funded: state.beta_funded,
+                        beta_refund_transaction,
+                    })
+                }
+            }
+        }
+
+        if let Async::Ready(alpha_redeemed_or_refunded) = context
+            .alpha_ledger_events
+            .htlc_redeemed_or_refunded(
+                state.swap.alpha_htlc_params(),
+                &state.alpha_deployed,
+                &state.alpha_funded,
+            )
+            .poll()?
+        {
+            let state = state.take();
+            match alpha_redeemed_or_refunded {
+                future::Either::A(redeem_transaction) => transition_save!(
+                    context.state_repo,
+                    Final(SwapOutcome::AlphaRedeemed {
+                        swap: state.swap,
+                        alpha_deployed: state.alpha_deployed,
+                        alpha_funded: state.alpha_funded,
+                        alpha_redeemed: redeem_transaction
+                    })
+                ),
+                future::Either::B(refund_transaction) => transition_save!(
+                    context.state_repo,
+                    Final(SwapOutcome::AlphaRefunded {
+                        swap: state.swap,
+                        alpha_deployed: state.alpha_deployed,
+                        alpha_funded: state.alpha_funded,
+                        alpha_refunded: refund_transaction
+                    })
+                ),
+            }
+        }
+
+        // Funding the HTLC again can make up for a wrong amount.
+        let beta_funded = try_ready!(context
+            .beta_ledger_events
+            .htlc_topped_up(
+                state.swap.beta_htlc_params(),
+                &state.beta_deployed,
+                &state.beta_funded,
+            )
+            .poll());
+        let state = state.take();
+
+        match beta_funded.asset.cmp(&state.swap.beta_asset) {
+            Equal => transition_save!(context.state_repo, BothFunded {
+                swap: state.swap,
+                alpha_funded: state.alpha_funded,
+                alpha_deployed: state.alpha_deployed,
+                beta_deployed: state.beta_deployed,
+                beta_funded
+            }),
+            _ => transition_save!(context.state_repo, AlphaFundedBetaIncorrectlyFunded {
+                swap: state.swap,
+                alpha_funded: state.alpha_funded,
+                alpha_deployed: state.alpha_deployed,
+                beta_deployed: state.beta_deployed,
+                beta_funded
+            }),
         }
     }
 
@@ -758,6 +916,7 @@ impl_display!(AlphaDeployed);
 impl_display!(AlphaFunded);
 impl_display!(AlphaIncorrectlyFunded);
 impl_display!(AlphaFundedBetaDeployed);
+impl_display!(AlphaFundedBetaIncorrectlyFunded);
 impl_display!(BothFunded);
 impl_display!(AlphaFundedBetaRefunded);
 impl_display!(AlphaRefundedBetaFunded);
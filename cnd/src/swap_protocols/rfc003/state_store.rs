@@ -1,18 +1,21 @@
-use crate::swap_protocols::{
-    rfc003::{
-        ledger_state::LedgerState,
-        state_machine::{
-            AlphaDeployed, AlphaFunded, AlphaFundedBetaDeployed, AlphaFundedBetaRedeemed,
-            AlphaFundedBetaRefunded, AlphaIncorrectlyFunded, AlphaRedeemedBetaFunded,
-            AlphaRefundedBetaFunded, BothFunded, Error as ErrorState, Final, SwapOutcome,
-            SwapStates,
+use crate::{
+    sharded_map::ShardedMap,
+    swap_protocols::{
+        rfc003::{
+            ledger_state::LedgerState,
+            state_machine::{
+                AlphaDeployed, AlphaFunded, AlphaFundedBetaDeployed,
+                AlphaFundedBetaIncorrectlyFunded, AlphaFundedBetaRedeemed, AlphaFundedBetaRefunded,
+                AlphaIncorrectlyFunded, AlphaRedeemedBetaFunded, AlphaRefundedBetaFunded,
+                BothFunded, Error as ErrorState, Final, SwapOutcome, SwapStates,
+            },
+            ActorState, Ledger,
         },
-        ActorState,
+        swap_id::SwapId,
     },
-    swap_id::SwapId,
 };
 use either::Either;
-use std::{any::Any, collections::HashMap, sync::Mutex};
+use std::{any::Any, cmp};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -24,234 +27,333 @@ pub trait StateStore: Send + Sync + 'static {
     fn insert<A: ActorState>(&self, key: SwapId, value: A);
     fn get<A: ActorState>(&self, key: &SwapId) -> Result<Option<A>, Error>;
     fn update<A: ActorState>(&self, key: &SwapId, update: SwapStates<A::AL, A::BL, A::AA, A::BA>);
+    /// Change the state of a swap in place with what was learned outside of
+    /// its state machine, e.g. from the mempool.
+    fn modify<A: ActorState, F: FnOnce(&mut A)>(&self, key: &SwapId, modify: F);
+    fn remove(&self, key: &SwapId);
 }
 
+/// Keeps the state of all swaps in memory.
+///
+/// The states are sharded by swap id so that reading the state of one swap
+/// (e.g. from the HTTP API) does not block updates to other swaps.
+///
+/// Updates are applied by the caller rather than sent to a task owning the
+/// states, such that a state is visible as soon as `insert` or `update`
+/// returns.
 #[derive(Default, Debug)]
 pub struct InMemoryStateStore {
-    states: Mutex<HashMap<SwapId, Box<dyn Any + Send + Sync>>>,
+    states: ShardedMap<SwapId, Box<dyn Any + Send + Sync>>,
 }
 
 impl StateStore for InMemoryStateStore {
     fn insert<A: ActorState>(&self, key: SwapId, value: A) {
-        let mut states = self.states.lock().unwrap();
-        states.insert(key, Box::new(value));
+        self.states.insert(key, Box::new(value));
     }
 
     fn get<A: ActorState>(&self, key: &SwapId) -> Result<Option<A>, Error> {
-        let states = self.states.lock().unwrap();
-        match states.get(key) {
+        self.states.with(key, |state| match state {
             Some(state) => match state.downcast_ref::<A>() {
                 Some(state) => Ok(Some(state.clone())),
                 None => Err(Error::InvalidType),
             },
             None => Ok(None),
-        }
+        })
     }
 
     fn update<A: ActorState>(&self, key: &SwapId, update: SwapStates<A::AL, A::BL, A::AA, A::BA>) {
-        use self::{LedgerState::*, SwapStates as SS};
-
-        let mut actor_state = match self.get::<A>(key) {
-            Ok(Some(actor_state)) => actor_state,
-            Ok(None) => {
-                log::warn!("Value not found for key {}", key);
-                return;
-            }
-            Err(_invalid_type) => {
-                log::warn!("Attempted to get state with wrong type for key {}", key);
-                return;
-            }
-        };
-
-        match update {
-            SS::Start(_) => {
-                log::warn!("Attempted to update Start state for key {}", key);
-                return;
-            }
-            SS::AlphaDeployed(AlphaDeployed { alpha_deployed, .. }) => {
-                *actor_state.alpha_ledger_mut() = Deployed {
-                    htlc_location: alpha_deployed.location,
-                    deploy_transaction: alpha_deployed.transaction,
+        // The state is updated in place so that concurrent updates of the same
+        // swap cannot overwrite each other.
+        self.states.with_mut(key, |state| {
+            match state.map(|state| state.downcast_mut::<A>()) {
+                Some(Some(actor_state)) => apply_update(key, actor_state, update),
+                Some(None) => {
+                    log::warn!("Attempted to get state with wrong type for key {}", key)
                 }
+                None => log::warn!("Value not found for key {}", key),
             }
+        })
+    }
 
-            SS::AlphaIncorrectlyFunded(AlphaIncorrectlyFunded {
-                alpha_deployed,
-                alpha_funded,
-                ..
-            }) => {
-                *actor_state.alpha_ledger_mut() = IncorrectlyFunded {
-                    htlc_location: alpha_deployed.location,
-                    deploy_transaction: alpha_deployed.transaction,
-                    fund_transaction: alpha_funded.transaction,
-                }
-            }
-            SS::AlphaFunded(AlphaFunded {
-                alpha_deployed,
-                alpha_funded,
-                ..
-            }) => {
-                *actor_state.alpha_ledger_mut() = Funded {
-                    htlc_location: alpha_deployed.location,
-                    deploy_transaction: alpha_deployed.transaction,
-                    fund_transaction: alpha_funded.transaction,
+    fn modify<A: ActorState, F: FnOnce(&mut A)>(&self, key: &SwapId, modify: F) {
+        self.states.with_mut(key, |state| {
+            match state.map(|state| state.downcast_mut::<A>()) {
+                Some(Some(actor_state)) => modify(actor_state),
+                Some(None) => {
+                    log::warn!("Attempted to get state with wrong type for key {}", key)
                 }
+                None => log::warn!("Value not found for key {}", key),
             }
-            SS::AlphaFundedBetaDeployed(AlphaFundedBetaDeployed {
-                alpha_deployed,
-                alpha_funded,
-                beta_deployed,
-                ..
-            }) => {
-                *actor_state.alpha_ledger_mut() = Funded {
-                    htlc_location: alpha_deployed.location,
-                    deploy_transaction: alpha_deployed.transaction,
-                    fund_transaction: alpha_funded.transaction,
-                };
-                *actor_state.beta_ledger_mut() = Deployed {
-                    htlc_location: beta_deployed.location,
-                    deploy_transaction: beta_deployed.transaction,
-                };
-            }
-            SS::BothFunded(BothFunded {
-                alpha_deployed,
-                alpha_funded,
-                beta_deployed,
-                beta_funded,
-                ..
-            }) => {
-                *actor_state.alpha_ledger_mut() = Funded {
-                    htlc_location: alpha_deployed.location,
-                    deploy_transaction: alpha_deployed.transaction,
-                    fund_transaction: alpha_funded.transaction,
-                };
-                *actor_state.beta_ledger_mut() = Funded {
-                    htlc_location: beta_deployed.location,
-                    deploy_transaction: beta_deployed.transaction,
-                    fund_transaction: beta_funded.transaction,
-                };
-            }
-            SS::AlphaFundedBetaRefunded(AlphaFundedBetaRefunded {
-                beta_deployed,
-                beta_funded,
-                beta_refund_transaction,
-                ..
-            })
-            | SS::Final(Final(SwapOutcome::BothRefunded {
-                beta_deployed,
-                beta_funded,
-                alpha_or_beta_refunded: Either::Right(beta_refund_transaction),
-                ..
-            }))
-            | SS::Final(Final(SwapOutcome::AlphaRedeemedBetaRefunded {
-                beta_deployed,
-                beta_funded,
-                alpha_redeemed_or_beta_refunded: Either::Right(beta_refund_transaction),
-                ..
-            })) => {
-                *actor_state.beta_ledger_mut() = Refunded {
-                    htlc_location: beta_deployed.location,
-                    deploy_transaction: beta_deployed.transaction,
-                    fund_transaction: beta_funded.transaction,
-                    refund_transaction: beta_refund_transaction.transaction,
-                }
-            }
-            SS::AlphaRefundedBetaFunded(AlphaRefundedBetaFunded {
-                alpha_deployed,
-                alpha_funded,
-                alpha_refunded,
-                ..
-            })
-            | SS::Final(Final(SwapOutcome::AlphaRefunded {
-                alpha_deployed,
-                alpha_funded,
-                alpha_refunded,
-                ..
-            }))
-            | SS::Final(Final(SwapOutcome::BothRefunded {
-                alpha_deployed,
-                alpha_funded,
-                alpha_or_beta_refunded: Either::Left(alpha_refunded),
-                ..
-            }))
-            | SS::Final(Final(SwapOutcome::AlphaRefundedBetaRedeemed {
-                alpha_deployed,
-                alpha_funded,
-                alpha_refunded_or_beta_redeemed: Either::Left(alpha_refunded),
-                ..
-            })) => {
-                *actor_state.alpha_ledger_mut() = Refunded {
-                    htlc_location: alpha_deployed.location,
-                    deploy_transaction: alpha_deployed.transaction,
-                    fund_transaction: alpha_funded.transaction,
-                    refund_transaction: alpha_refunded.transaction,
-                }
+        })
+    }
+
+    fn remove(&self, key: &SwapId) {
+        self.states.remove(key);
+    }
+}
+
+/// A fund transaction that was reported as unconfirmed by
+/// `crate::bitcoin_mempool` stays so until it has the required number of
+/// confirmations, it has at least one once the swap sees it in a block.
+fn deploy_or_fund<L: Ledger>(ledger: &mut LedgerState<L>, update: LedgerState<L>) {
+    let (fund_transaction, confirmations) = match ledger {
+        LedgerState::FundedUnconfirmed {
+            fund_transaction,
+            confirmations,
+            ..
+        } => (fund_transaction.clone(), cmp::max(*confirmations, 1)),
+        _ => {
+            *ledger = update;
+            return;
+        }
+    };
+
+    *ledger = match update {
+        LedgerState::Deployed {
+            htlc_location,
+            deploy_transaction,
+        } => LedgerState::FundedUnconfirmed {
+            htlc_location,
+            deploy_transaction,
+            fund_transaction,
+            confirmations,
+        },
+        LedgerState::Funded {
+            htlc_location,
+            deploy_transaction,
+            fund_transaction,
+            ..
+        } => LedgerState::FundedUnconfirmed {
+            htlc_location,
+            deploy_transaction,
+            fund_transaction,
+            confirmations,
+        },
+        update => update,
+    };
+}
+
+fn apply_update<A: ActorState>(
+    key: &SwapId,
+    actor_state: &mut A,
+    update: SwapStates<A::AL, A::BL, A::AA, A::BA>,
+) {
+    use self::{LedgerState::*, SwapStates as SS};
+
+    match update {
+        SS::Start(_) => {
+            log::warn!("Attempted to update Start state for key {}", key);
+        }
+        SS::AlphaDeployed(AlphaDeployed { alpha_deployed, .. }) => {
+            deploy_or_fund(actor_state.alpha_ledger_mut(), Deployed {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+            });
+        }
+
+        SS::AlphaIncorrectlyFunded(AlphaIncorrectlyFunded {
+            alpha_deployed,
+            alpha_funded,
+            ..
+        }) => {
+            *actor_state.alpha_ledger_mut() = IncorrectlyFunded {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+                fund_transaction: alpha_funded.transaction,
+                top_ups: alpha_funded.top_ups,
             }
-            SS::AlphaFundedBetaRedeemed(AlphaFundedBetaRedeemed {
-                beta_deployed,
-                beta_funded,
-                beta_redeem_transaction,
-                ..
-            })
-            | SS::Final(Final(SwapOutcome::BothRedeemed {
-                beta_deployed,
-                beta_funded,
-                alpha_or_beta_redeemed: Either::Right(beta_redeem_transaction),
-                ..
-            }))
-            | SS::Final(Final(SwapOutcome::AlphaRefundedBetaRedeemed {
-                beta_deployed,
-                beta_funded,
-                alpha_refunded_or_beta_redeemed: Either::Right(beta_redeem_transaction),
-                ..
-            })) => {
-                *actor_state.beta_ledger_mut() = Redeemed {
-                    htlc_location: beta_deployed.location,
-                    deploy_transaction: beta_deployed.transaction,
-                    fund_transaction: beta_funded.transaction,
-                    redeem_transaction: beta_redeem_transaction.transaction,
-                };
-                actor_state.set_secret(beta_redeem_transaction.secret);
+        }
+        SS::AlphaFunded(AlphaFunded {
+            alpha_deployed,
+            alpha_funded,
+            ..
+        }) => {
+            deploy_or_fund(actor_state.alpha_ledger_mut(), Funded {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+                fund_transaction: alpha_funded.transaction,
+                top_ups: alpha_funded.top_ups,
+            });
+        }
+        SS::AlphaFundedBetaDeployed(AlphaFundedBetaDeployed {
+            alpha_deployed,
+            alpha_funded,
+            beta_deployed,
+            ..
+        }) => {
+            deploy_or_fund(actor_state.alpha_ledger_mut(), Funded {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+                fund_transaction: alpha_funded.transaction,
+                top_ups: alpha_funded.top_ups,
+            });
+            deploy_or_fund(actor_state.beta_ledger_mut(), Deployed {
+                htlc_location: beta_deployed.location,
+                deploy_transaction: beta_deployed.transaction,
+            });
+        }
+        SS::AlphaFundedBetaIncorrectlyFunded(AlphaFundedBetaIncorrectlyFunded {
+            alpha_deployed,
+            alpha_funded,
+            beta_deployed,
+            beta_funded,
+            ..
+        }) => {
+            deploy_or_fund(actor_state.alpha_ledger_mut(), Funded {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+                fund_transaction: alpha_funded.transaction,
+                top_ups: alpha_funded.top_ups,
+            });
+            *actor_state.beta_ledger_mut() = IncorrectlyFunded {
+                htlc_location: beta_deployed.location,
+                deploy_transaction: beta_deployed.transaction,
+                fund_transaction: beta_funded.transaction,
+                top_ups: beta_funded.top_ups,
             }
-            SS::AlphaRedeemedBetaFunded(AlphaRedeemedBetaFunded {
-                alpha_deployed,
-                alpha_funded,
-                alpha_redeemed,
-                ..
-            })
-            | SS::Final(Final(SwapOutcome::AlphaRedeemed {
-                alpha_deployed,
-                alpha_funded,
-                alpha_redeemed,
-                ..
-            }))
-            | SS::Final(Final(SwapOutcome::BothRedeemed {
-                alpha_deployed,
-                alpha_funded,
-                alpha_or_beta_redeemed: Either::Left(alpha_redeemed),
-                ..
-            }))
-            | SS::Final(Final(SwapOutcome::AlphaRedeemedBetaRefunded {
-                alpha_deployed,
-                alpha_funded,
-                alpha_redeemed_or_beta_refunded: Either::Left(alpha_redeemed),
-                ..
-            })) => {
-                *actor_state.alpha_ledger_mut() = Redeemed {
-                    htlc_location: alpha_deployed.location,
-                    deploy_transaction: alpha_deployed.transaction,
-                    fund_transaction: alpha_funded.transaction,
-                    redeem_transaction: alpha_redeemed.transaction,
-                };
-                actor_state.set_secret(alpha_redeemed.secret);
+        }
+        SS::BothFunded(BothFunded {
+            alpha_deployed,
+            alpha_funded,
+            beta_deployed,
+            beta_funded,
+            ..
+        }) => {
+            deploy_or_fund(actor_state.alpha_ledger_mut(), Funded {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+                fund_transaction: alpha_funded.transaction,
+                top_ups: alpha_funded.top_ups,
+            });
+            deploy_or_fund(actor_state.beta_ledger_mut(), Funded {
+                htlc_location: beta_deployed.location,
+                deploy_transaction: beta_deployed.transaction,
+                fund_transaction: beta_funded.transaction,
+                top_ups: beta_funded.top_ups,
+            });
+        }
+        SS::AlphaFundedBetaRefunded(AlphaFundedBetaRefunded {
+            beta_deployed,
+            beta_funded,
+            beta_refund_transaction,
+            ..
+        })
+        | SS::Final(Final(SwapOutcome::BothRefunded {
+            beta_deployed,
+            beta_funded,
+            alpha_or_beta_refunded: Either::Right(beta_refund_transaction),
+            ..
+        }))
+        | SS::Final(Final(SwapOutcome::AlphaRedeemedBetaRefunded {
+            beta_deployed,
+            beta_funded,
+            alpha_redeemed_or_beta_refunded: Either::Right(beta_refund_transaction),
+            ..
+        })) => {
+            *actor_state.beta_ledger_mut() = Refunded {
+                htlc_location: beta_deployed.location,
+                deploy_transaction: beta_deployed.transaction,
+                fund_transaction: beta_funded.transaction,
+                top_ups: beta_funded.top_ups,
+                refund_transaction: beta_refund_transaction.transaction,
             }
-            SS::Error(ErrorState(e)) => {
-                log::error!("Internal failure: {:?}", e);
-                return;
+        }
+        SS::AlphaRefundedBetaFunded(AlphaRefundedBetaFunded {
+            alpha_deployed,
+            alpha_funded,
+            alpha_refunded,
+            ..
+        })
+        | SS::Final(Final(SwapOutcome::AlphaRefunded {
+            alpha_deployed,
+            alpha_funded,
+            alpha_refunded,
+            ..
+        }))
+        | SS::Final(Final(SwapOutcome::BothRefunded {
+            alpha_deployed,
+            alpha_funded,
+            alpha_or_beta_refunded: Either::Left(alpha_refunded),
+            ..
+        }))
+        | SS::Final(Final(SwapOutcome::AlphaRefundedBetaRedeemed {
+            alpha_deployed,
+            alpha_funded,
+            alpha_refunded_or_beta_redeemed: Either::Left(alpha_refunded),
+            ..
+        })) => {
+            *actor_state.alpha_ledger_mut() = Refunded {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+                fund_transaction: alpha_funded.transaction,
+                top_ups: alpha_funded.top_ups,
+                refund_transaction: alpha_refunded.transaction,
             }
         }
-
-        self.insert(key.clone(), actor_state)
+        SS::AlphaFundedBetaRedeemed(AlphaFundedBetaRedeemed {
+            beta_deployed,
+            beta_funded,
+            beta_redeem_transaction,
+            ..
+        })
+        | SS::Final(Final(SwapOutcome::BothRedeemed {
+            beta_deployed,
+            beta_funded,
+            alpha_or_beta_redeemed: Either::Right(beta_redeem_transaction),
+            ..
+        }))
+        | SS::Final(Final(SwapOutcome::AlphaRefundedBetaRedeemed {
+            beta_deployed,
+            beta_funded,
+            alpha_refunded_or_beta_redeemed: Either::Right(beta_redeem_transaction),
+            ..
+        })) => {
+            *actor_state.beta_ledger_mut() = Redeemed {
+                htlc_location: beta_deployed.location,
+                deploy_transaction: beta_deployed.transaction,
+                fund_transaction: beta_funded.transaction,
+                top_ups: beta_funded.top_ups,
+                redeem_transaction: beta_redeem_transaction.transaction,
+            };
+            actor_state.set_secret(beta_redeem_transaction.secret);
+        }
+        SS::AlphaRedeemedBetaFunded(AlphaRedeemedBetaFunded {
+            alpha_deployed,
+            alpha_funded,
+            alpha_redeemed,
+            ..
+        })
+        | SS::Final(Final(SwapOutcome::AlphaRedeemed {
+            alpha_deployed,
+            alpha_funded,
+            alpha_redeemed,
+            ..
+        }))
+        | SS::Final(Final(SwapOutcome::BothRedeemed {
+            alpha_deployed,
+            alpha_funded,
+            alpha_or_beta_redeemed: Either::Left(alpha_redeemed),
+            ..
+        }))
+        | SS::Final(Final(SwapOutcome::AlphaRedeemedBetaRefunded {
+            alpha_deployed,
+            alpha_funded,
+            alpha_redeemed_or_beta_refunded: Either::Left(alpha_redeemed),
+            ..
+        })) => {
+            *actor_state.alpha_ledger_mut() = Redeemed {
+                htlc_location: alpha_deployed.location,
+                deploy_transaction: alpha_deployed.transaction,
+                fund_transaction: alpha_funded.transaction,
+                top_ups: alpha_funded.top_ups,
+                redeem_transaction: alpha_redeemed.transaction,
+            };
+            actor_state.set_secret(alpha_redeemed.secret);
+        }
+        SS::Error(ErrorState(e)) => {
+            log::error!("Internal failure: {:?}", e);
+            actor_state.set_error(e);
+        }
     }
 }
 
@@ -314,4 +416,34 @@ mod tests {
             .unwrap();
         assert_that(&res).contains_value(state);
     }
+
+    #[test]
+    fn unconfirmed_funding_stays_unconfirmed_once_seen_in_a_block() {
+        let transaction = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: Vec::new(),
+            output: Vec::new(),
+        };
+        let mut ledger = LedgerState::<Bitcoin>::FundedUnconfirmed {
+            htlc_location: bitcoin::OutPoint::default(),
+            deploy_transaction: transaction.clone(),
+            fund_transaction: transaction.clone(),
+            confirmations: 0,
+        };
+
+        deploy_or_fund(&mut ledger, LedgerState::Funded {
+            htlc_location: bitcoin::OutPoint::default(),
+            deploy_transaction: transaction.clone(),
+            fund_transaction: transaction.clone(),
+            top_ups: vec![],
+        });
+
+        assert_that(&ledger).is_equal_to(LedgerState::FundedUnconfirmed {
+            htlc_location: bitcoin::OutPoint::default(),
+            deploy_transaction: transaction.clone(),
+            fund_transaction: transaction,
+            confirmations: 1,
+        });
+    }
 }
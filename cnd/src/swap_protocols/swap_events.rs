@@ -0,0 +1,184 @@
+use crate::swap_protocols::{
+    asset::Asset,
+    rfc003::{
+        state_machine::{Error as ErrorState, SwapStates},
+        Ledger,
+    },
+    SwapId,
+};
+use futures::sync::mpsc;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Something that happened to a swap.
+#[derive(Clone, Debug, PartialEq, Serialize, strum_macros::IntoStaticStr)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab_case")]
+pub enum SwapEvent {
+    Created { swap_id: SwapId },
+    Accepted { swap_id: SwapId },
+    Declined { swap_id: SwapId },
+    AlphaDeployed { swap_id: SwapId },
+    AlphaFunded { swap_id: SwapId },
+    AlphaIncorrectlyFunded { swap_id: SwapId },
+    AlphaRedeemed { swap_id: SwapId },
+    AlphaRefunded { swap_id: SwapId },
+    BetaDeployed { swap_id: SwapId },
+    BetaFunded { swap_id: SwapId },
+    BetaIncorrectlyFunded { swap_id: SwapId },
+    BetaRedeemed { swap_id: SwapId },
+    BetaRefunded { swap_id: SwapId },
+    Finished { swap_id: SwapId },
+    Failed { swap_id: SwapId, reason: String },
+}
+
+impl SwapEvent {
+    /// The event a state transition of the swap execution stands for, `None`
+    /// for the initial state.
+    pub fn from_update<AL: Ledger, BL: Ledger, AA: Asset, BA: Asset>(
+        swap_id: SwapId,
+        update: &SwapStates<AL, BL, AA, BA>,
+    ) -> Option<Self> {
+        use self::SwapStates as SS;
+
+        let event = match update {
+            SS::Start(_) => return None,
+            SS::AlphaDeployed(_) => SwapEvent::AlphaDeployed { swap_id },
+            SS::AlphaFunded(_) => SwapEvent::AlphaFunded { swap_id },
+            SS::AlphaIncorrectlyFunded(_) => SwapEvent::AlphaIncorrectlyFunded { swap_id },
+            SS::AlphaFundedBetaDeployed(_) => SwapEvent::BetaDeployed { swap_id },
+            SS::AlphaFundedBetaIncorrectlyFunded(_) => SwapEvent::BetaIncorrectlyFunded { swap_id },
+            SS::BothFunded(_) => SwapEvent::BetaFunded { swap_id },
+            SS::AlphaFundedBetaRedeemed(_) => SwapEvent::BetaRedeemed { swap_id },
+            SS::AlphaFundedBetaRefunded(_) => SwapEvent::BetaRefunded { swap_id },
+            SS::AlphaRedeemedBetaFunded(_) => SwapEvent::AlphaRedeemed { swap_id },
+            SS::AlphaRefundedBetaFunded(_) => SwapEvent::AlphaRefunded { swap_id },
+            SS::Final(_) => SwapEvent::Finished { swap_id },
+            SS::Error(ErrorState(e)) => SwapEvent::Failed {
+                swap_id,
+                reason: e.to_string(),
+            },
+        };
+
+        Some(event)
+    }
+
+    /// The `type` the event is serialized with.
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+
+    pub fn swap_id(&self) -> SwapId {
+        match self {
+            SwapEvent::Created { swap_id }
+            | SwapEvent::Accepted { swap_id }
+            | SwapEvent::Declined { swap_id }
+            | SwapEvent::AlphaDeployed { swap_id }
+            | SwapEvent::AlphaFunded { swap_id }
+            | SwapEvent::AlphaIncorrectlyFunded { swap_id }
+            | SwapEvent::AlphaRedeemed { swap_id }
+            | SwapEvent::AlphaRefunded { swap_id }
+            | SwapEvent::BetaDeployed { swap_id }
+            | SwapEvent::BetaFunded { swap_id }
+            | SwapEvent::BetaIncorrectlyFunded { swap_id }
+            | SwapEvent::BetaRedeemed { swap_id }
+            | SwapEvent::BetaRefunded { swap_id }
+            | SwapEvent::Finished { swap_id }
+            | SwapEvent::Failed { swap_id, .. } => *swap_id,
+        }
+    }
+}
+
+/// Publish what happens to swaps so that side effects, e.g. calling the
+/// webhook, can be added without touching the swap execution.
+pub trait SwapEvents: Send + Sync + 'static {
+    fn publish(&self, event: SwapEvent);
+
+    /// How many events of each type were published since the start of cnd.
+    fn published_events(&self) -> BTreeMap<&'static str, u64>;
+}
+
+/// Hands every published event to all subscribers.
+///
+/// Subscribers that dropped their receiver are removed on the next publish.
+#[derive(Clone, Debug, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<SwapEvent>>>>,
+    published: Arc<Mutex<BTreeMap<&'static str, u64>>>,
+}
+
+impl EventBus {
+    /// Receive all events that are published from now on.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<SwapEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.subscribers
+            .lock()
+            .expect("lock is not poisoned")
+            .push(sender);
+
+        receiver
+    }
+}
+
+impl SwapEvents for EventBus {
+    fn publish(&self, event: SwapEvent) {
+        log::debug!("publishing {:?}", event);
+
+        *self
+            .published
+            .lock()
+            .expect("lock is not poisoned")
+            .entry(event.name())
+            .or_default() += 1;
+
+        self.subscribers
+            .lock()
+            .expect("lock is not poisoned")
+            .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+
+    fn published_events(&self) -> BTreeMap<&'static str, u64> {
+        self.published.lock().expect("lock is not poisoned").clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{Future, Stream};
+    use spectral::prelude::*;
+
+    #[test]
+    fn published_events_reach_remaining_subscribers() {
+        let bus = EventBus::default();
+        let swap_id = SwapId::default();
+        let subscriber = bus.subscribe();
+        drop(bus.subscribe());
+
+        bus.publish(SwapEvent::Created { swap_id });
+        bus.publish(SwapEvent::AlphaFunded { swap_id });
+        drop(bus);
+
+        let received = subscriber.collect().wait();
+
+        assert_that(&received).is_ok_containing(vec![
+            SwapEvent::Created { swap_id },
+            SwapEvent::AlphaFunded { swap_id },
+        ]);
+    }
+
+    #[test]
+    fn events_are_counted_by_their_type() {
+        let bus = EventBus::default();
+        let swap_id = SwapId::default();
+
+        bus.publish(SwapEvent::BetaRedeemed { swap_id });
+        bus.publish(SwapEvent::BetaRedeemed { swap_id });
+
+        assert_that(&bus.published_events().get("beta-redeemed")).is_equal_to(Some(&2));
+        assert_that(&SwapEvent::BetaRedeemed { swap_id }.name()).is_equal_to("beta-redeemed");
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{sharded_map::ShardedMap, swap_protocols::SwapId};
+use futures::{sync::mpsc, Future};
+use futures_core::{
+    compat::Future01CompatExt,
+    future::{self, AbortHandle},
+    FutureExt, TryFutureExt,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Spawn tasks that belong to a swap and tear them down once the swap reached
+/// a terminal state.
+pub trait SwapTasks: Send + Sync + 'static {
+    /// Spawn `task` and register it under `swap_id` so that it can be
+    /// cancelled through `cancel_swap_tasks`.
+    fn spawn_swap_task(
+        &self,
+        swap_id: SwapId,
+        task: impl Future<Item = (), Error = ()> + Send + 'static,
+    ) -> anyhow::Result<()>;
+
+    /// Cancel all tasks of the given swap that are still running.
+    fn cancel_swap_tasks(&self, swap_id: &SwapId);
+
+    /// The number of swap tasks that are currently running.
+    fn active_watchers(&self) -> usize;
+
+    /// Hand a swap whose watcher stopped before the swap was over to the
+    /// supervisor, which restarts it.
+    fn watcher_terminated(&self, termination: Termination);
+
+    /// Stop restarting the watcher of a swap, `reason` is shown with the swap.
+    fn mark_degraded(&self, swap_id: SwapId, reason: String);
+
+    /// Why the watcher of the swap is no longer restarted, if it is not.
+    fn degraded(&self, swap_id: &SwapId) -> Option<String>;
+}
+
+/// The watcher of a swap, i.e. its execution, stopped without the swap
+/// reaching a final state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Termination {
+    pub swap_id: SwapId,
+    pub reason: String,
+}
+
+/// Keeps track of the abort handles of all running swap tasks.
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    handles: ShardedMap<SwapId, Vec<AbortHandle>>,
+    active: Arc<AtomicUsize>,
+    /// `None` if nothing supervises the watchers.
+    terminations: Option<mpsc::UnboundedSender<Termination>>,
+    degraded: ShardedMap<SwapId, String>,
+}
+
+impl TaskRegistry {
+    /// A registry that hands terminated watchers to the receiver.
+    pub fn supervised() -> (Self, mpsc::UnboundedReceiver<Termination>) {
+        let (sender, receiver) = mpsc::unbounded();
+
+        let registry = Self {
+            terminations: Some(sender),
+            ..Self::default()
+        };
+
+        (registry, receiver)
+    }
+
+    /// Make `task` cancellable through `cancel` and count it as active until it
+    /// either finished or got cancelled.
+    pub fn register(
+        &self,
+        swap_id: SwapId,
+        task: impl Future<Item = (), Error = ()> + Send + 'static,
+    ) -> impl Future<Item = (), Error = ()> + Send + 'static {
+        let (task, handle) = future::abortable(task.compat());
+
+        self.handles
+            .with_entry(swap_id, Vec::new, |handles| handles.push(handle));
+
+        let guard = ActiveGuard::new(Arc::clone(&self.active));
+
+        task.map(move |result| {
+            drop(guard);
+
+            if result.is_err() {
+                log::debug!("cancelled task of swap {}", swap_id);
+            }
+
+            Ok::<(), ()>(())
+        })
+        .boxed()
+        .compat()
+    }
+
+    pub fn cancel(&self, swap_id: &SwapId) {
+        if let Some(handles) = self.handles.remove(swap_id) {
+            for handle in handles {
+                handle.abort();
+            }
+        }
+    }
+
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    pub fn report_termination(&self, termination: Termination) {
+        log::error!(
+            "watcher of swap {} terminated: {}",
+            termination.swap_id,
+            termination.reason
+        );
+
+        let swap_id = termination.swap_id;
+        let is_supervised = self.terminations.as_ref().map_or(false, |terminations| {
+            terminations.unbounded_send(termination).is_ok()
+        });
+
+        if !is_supervised {
+            self.mark_degraded(swap_id, String::from("watcher terminated"));
+        }
+    }
+
+    pub fn mark_degraded(&self, swap_id: SwapId, reason: String) {
+        self.degraded.insert(swap_id, reason);
+    }
+
+    pub fn degraded(&self, swap_id: &SwapId) -> Option<String> {
+        self.degraded.with(swap_id, |reason| reason.cloned())
+    }
+}
+
+/// Counts a task as active for as long as it is alive.
+#[derive(Debug)]
+struct ActiveGuard(Arc<AtomicUsize>);
+
+impl ActiveGuard {
+    fn new(active: Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        Self(active)
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Stream;
+    use spectral::prelude::*;
+
+    #[test]
+    fn cancelled_tasks_are_no_longer_active() {
+        let registry = TaskRegistry::default();
+        let swap_id = SwapId::default();
+
+        let task = registry.register(swap_id, futures::future::empty::<(), ()>());
+        assert_that(&registry.active()).is_equal_to(1);
+
+        registry.cancel(&swap_id);
+
+        assert_that(&task.wait()).is_ok();
+        assert_that(&registry.active()).is_equal_to(0);
+    }
+
+    #[test]
+    fn terminations_are_handed_to_the_supervisor_if_there_is_one() {
+        let (registry, terminations) = TaskRegistry::supervised();
+        let termination = Termination {
+            swap_id: SwapId::default(),
+            reason: String::from("panicked"),
+        };
+
+        registry.report_termination(termination.clone());
+        drop(registry);
+
+        let received = terminations.collect().wait().unwrap();
+        assert_that(&received).is_equal_to(vec![termination.clone()]);
+
+        let unsupervised = TaskRegistry::default();
+        unsupervised.report_termination(termination.clone());
+        assert_that(&unsupervised.degraded(&termination.swap_id)).is_some();
+    }
+}
@@ -0,0 +1,697 @@
+use crate::{
+    config,
+    sharded_map::ShardedMap,
+    swap_protocols::{SwapEvent, SwapId},
+};
+use futures::{Future, Poll};
+use futures_core::{
+    channel::mpsc,
+    compat::{Future01CompatExt, Stream01CompatExt},
+    stream::StreamExt,
+};
+use rand::{rngs::OsRng, RngCore};
+use reqwest::{r#async::Client, Url};
+use std::{
+    cell::RefCell,
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::timer::Delay;
+
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+const DEFAULT_SERVICE_NAME: &str = "cnd";
+const SAMPLED: u8 = 0x01;
+/// `SPAN_KIND_INTERNAL` in OTLP.
+const SPAN_KIND_INTERNAL: u8 = 1;
+const SPAN_QUEUE_CAPACITY: usize = 2048;
+const MAX_BATCH_SIZE: usize = 512;
+const BATCH_DELAY: Duration = Duration::from_secs(5);
+
+/// The position of a span within a trace, as carried by the W3C `traceparent`
+/// header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub flags: u8,
+}
+
+impl TraceContext {
+    /// The context of a span that starts a new trace.
+    pub fn root() -> Self {
+        let mut trace_id = [0u8; 16];
+        OsRng.fill_bytes(&mut trace_id);
+
+        TraceContext {
+            trace_id,
+            span_id: random_span_id(),
+            flags: SAMPLED,
+        }
+    }
+
+    pub fn is_sampled(&self) -> bool {
+        self.flags & SAMPLED != 0
+    }
+
+    /// The context of a span in the same trace, with this one as its parent.
+    pub fn child(&self) -> Self {
+        TraceContext {
+            span_id: random_span_id(),
+            ..*self
+        }
+    }
+}
+
+fn random_span_id() -> [u8; 8] {
+    let mut span_id = [0u8; 8];
+    OsRng.fill_bytes(&mut span_id);
+
+    span_id
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+#[error("invalid traceparent")]
+pub struct InvalidTraceparent;
+
+impl FromStr for TraceContext {
+    type Err = InvalidTraceparent;
+
+    /// Later versions of the header may append fields, they are ignored.
+    fn from_str(header: &str) -> Result<Self, Self::Err> {
+        let fields = header.trim().split('-').collect::<Vec<_>>();
+
+        let version = fields.first().ok_or(InvalidTraceparent)?;
+        let is_supported = match *version {
+            "00" => fields.len() == 4,
+            "ff" => false,
+            version => version.len() == 2 && fields.len() >= 4,
+        };
+        if !is_supported {
+            return Err(InvalidTraceparent);
+        }
+
+        let mut context = TraceContext {
+            trace_id: [0u8; 16],
+            span_id: [0u8; 8],
+            flags: 0,
+        };
+        decode_id(fields[1], &mut context.trace_id)?;
+        decode_id(fields[2], &mut context.span_id)?;
+        if fields[3].len() != 2 {
+            return Err(InvalidTraceparent);
+        }
+        context.flags = u8::from_str_radix(fields[3], 16).map_err(|_| InvalidTraceparent)?;
+
+        Ok(context)
+    }
+}
+
+/// Ids that are all zeros are invalid.
+fn decode_id(hex: &str, id: &mut [u8]) -> Result<(), InvalidTraceparent> {
+    hex::decode_to_slice(hex, id).map_err(|_| InvalidTraceparent)?;
+
+    if id.iter().all(|byte| *byte == 0) {
+        return Err(InvalidTraceparent);
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{}-{}-{:02x}",
+            hex::encode(self.trace_id),
+            hex::encode(self.span_id),
+            self.flags
+        )
+    }
+}
+
+/// Continue the trace of the HTTP request a swap was created with, instead of
+/// starting a new one.
+pub trait SwapTracing: Send + Sync + 'static {
+    fn continue_trace(&self, swap_id: SwapId, parent: TraceContext);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub context: TraceContext,
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: &'static str,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Vec<(&'static str, String)>,
+}
+
+/// What is known about the trace of a swap that did not finish yet.
+#[derive(Clone, Copy, Debug)]
+struct SwapTrace {
+    context: TraceContext,
+    parent_span_id: Option<[u8; 8]>,
+    started_at: SystemTime,
+    last_event_at: SystemTime,
+}
+
+impl SwapTrace {
+    fn new(parent: Option<TraceContext>, now: SystemTime) -> Self {
+        SwapTrace {
+            context: parent.map_or_else(TraceContext::root, |parent| parent.child()),
+            parent_span_id: parent.map(|parent| parent.span_id),
+            started_at: now,
+            last_event_at: now,
+        }
+    }
+}
+
+/// Where finished spans wait to be exported.
+///
+/// The queue is bounded, spans that do not fit are dropped and counted rather
+/// than slowing down the swaps they belong to.
+#[derive(Clone, Debug)]
+pub struct SpanSink {
+    queue: Arc<Mutex<mpsc::Sender<Span>>>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl SpanSink {
+    fn send(&self, span: Span) {
+        let mut queue = self.queue.lock().unwrap();
+
+        if queue.try_send(span).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The receiving end of a `SpanSink`.
+#[derive(Debug)]
+pub struct SpanQueue {
+    spans: mpsc::Receiver<Span>,
+    dropped: Arc<AtomicUsize>,
+}
+
+/// Turns the events of swaps into spans, one trace per swap.
+///
+/// Each event ends a span that started with the previous event of the swap,
+/// hence the time spent e.g. sending the request to the peer or waiting for
+/// an HTLC to be funded shows up as the span of the event that ended it. The
+/// span of the swap itself ends once the swap finished, failed or was
+/// declined.
+///
+/// Nothing is recorded for swaps whose trace is not sampled.
+#[derive(Clone, Debug)]
+pub struct Tracer {
+    traces: Arc<ShardedMap<SwapId, SwapTrace>>,
+    spans: SpanSink,
+}
+
+impl Tracer {
+    pub fn new() -> (Self, SpanQueue) {
+        let (sender, receiver) = mpsc::channel(SPAN_QUEUE_CAPACITY);
+        let dropped = Arc::new(AtomicUsize::new(0));
+
+        let tracer = Tracer {
+            traces: Arc::new(ShardedMap::default()),
+            spans: SpanSink {
+                queue: Arc::new(Mutex::new(sender)),
+                dropped: Arc::clone(&dropped),
+            },
+        };
+        let queue = SpanQueue {
+            spans: receiver,
+            dropped,
+        };
+
+        (tracer, queue)
+    }
+
+    /// Make the span of the swap a child of `parent`, has to happen before
+    /// the swap is created.
+    pub fn start_swap(&self, swap_id: SwapId, parent: TraceContext) {
+        self.traces
+            .insert(swap_id, SwapTrace::new(Some(parent), SystemTime::now()));
+    }
+
+    /// The scope the tasks of a swap run in, `None` if the trace of the swap
+    /// is not sampled.
+    ///
+    /// Swaps that were resumed after a restart start a new trace.
+    pub fn scope(&self, swap_id: SwapId) -> Option<Scope> {
+        let context = self.context(swap_id, SystemTime::now());
+
+        if !context.is_sampled() {
+            return None;
+        }
+
+        Some(Scope {
+            context,
+            spans: self.spans.clone(),
+        })
+    }
+
+    /// Record a span of the swap that started at `start` and ends now.
+    pub fn record_span(&self, swap_id: SwapId, name: &'static str, start: SystemTime) {
+        let context = self.context(swap_id, start);
+
+        if context.is_sampled() {
+            self.spans.send(Span {
+                context: context.child(),
+                parent_span_id: Some(context.span_id),
+                name,
+                start,
+                end: SystemTime::now(),
+                attributes: vec![("swap.id", swap_id.to_string())],
+            });
+        }
+    }
+
+    fn context(&self, swap_id: SwapId, now: SystemTime) -> TraceContext {
+        self.traces
+            .with_entry(swap_id, || SwapTrace::new(None, now), |trace| trace.context)
+    }
+
+    pub fn record(&self, event: &SwapEvent, now: SystemTime) {
+        let swap_id = event.swap_id();
+
+        let spans = self.traces.with_entry(
+            swap_id,
+            || SwapTrace::new(None, now),
+            |trace| {
+                if let SwapEvent::Created { .. } = event {
+                    return Vec::new();
+                }
+
+                let mut attributes = vec![("swap.id", swap_id.to_string())];
+                if let SwapEvent::Failed { reason, .. } = event {
+                    attributes.push(("swap.failure", reason.clone()));
+                }
+
+                let mut spans = vec![Span {
+                    context: trace.context.child(),
+                    parent_span_id: Some(trace.context.span_id),
+                    name: event.name(),
+                    start: trace.last_event_at,
+                    end: now,
+                    attributes,
+                }];
+                trace.last_event_at = now;
+
+                if ends_swap(event) {
+                    spans.push(Span {
+                        context: trace.context,
+                        parent_span_id: trace.parent_span_id,
+                        name: "swap",
+                        start: trace.started_at,
+                        end: now,
+                        attributes: vec![("swap.id", swap_id.to_string())],
+                    });
+                }
+
+                if trace.context.is_sampled() {
+                    spans
+                } else {
+                    Vec::new()
+                }
+            },
+        );
+
+        if ends_swap(event) {
+            self.traces.remove(&swap_id);
+        }
+
+        for span in spans {
+            self.spans.send(span)
+        }
+    }
+
+    /// Record the spans of every swap event the subscription receives until
+    /// cnd shuts down.
+    pub async fn trace_swaps(self, events: futures::sync::mpsc::UnboundedReceiver<SwapEvent>) {
+        let mut events = events.compat();
+
+        while let Some(Ok(event)) = events.next().await {
+            self.record(&event, SystemTime::now())
+        }
+    }
+}
+
+fn ends_swap(event: &SwapEvent) -> bool {
+    match event {
+        SwapEvent::Finished { .. } | SwapEvent::Failed { .. } | SwapEvent::Declined { .. } => true,
+        _ => false,
+    }
+}
+
+/// The span that tasks, and the spans they start, are children of.
+#[derive(Clone, Debug)]
+pub struct Scope {
+    context: TraceContext,
+    spans: SpanSink,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Scope>> = RefCell::new(None);
+}
+
+/// The scope of the task that is being polled on this thread.
+pub fn current_scope() -> Option<Scope> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Restores the scope that was current before it was entered once dropped.
+struct Entered(Option<Scope>);
+
+impl Entered {
+    fn enter(scope: Option<Scope>) -> Self {
+        Entered(CURRENT.with(|current| current.replace(scope)))
+    }
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+
+        CURRENT.with(|current| *current.borrow_mut() = previous)
+    }
+}
+
+/// Polls a task in a scope, which carries the trace into whatever the task
+/// spawns or calls.
+#[derive(Debug)]
+pub struct InScope<F> {
+    scope: Option<Scope>,
+    inner: F,
+}
+
+impl<F> InScope<F> {
+    pub fn new(scope: Option<Scope>, inner: F) -> Self {
+        InScope { scope, inner }
+    }
+}
+
+impl<F: Future> Future for InScope<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let _entered = Entered::enter(self.scope.clone());
+
+        self.inner.poll()
+    }
+}
+
+/// A span that ends once dropped, hence also if the future it covers is
+/// cancelled.
+struct ActiveSpan {
+    scope: Scope,
+    name: &'static str,
+    start: SystemTime,
+}
+
+impl Drop for ActiveSpan {
+    fn drop(&mut self) {
+        self.scope.spans.send(Span {
+            context: self.scope.context.child(),
+            parent_span_id: Some(self.scope.context.span_id),
+            name: self.name,
+            start: self.start,
+            end: SystemTime::now(),
+            attributes: Vec::new(),
+        })
+    }
+}
+
+/// Record a span for the time `future` takes, as a child of the current
+/// scope.
+///
+/// Spans do not nest, the ones started by `future` are siblings of this one.
+pub async fn in_span<F: std::future::Future>(name: &'static str, future: F) -> F::Output {
+    let _span = current_scope().map(|scope| ActiveSpan {
+        scope,
+        name,
+        start: SystemTime::now(),
+    });
+
+    future.await
+}
+
+/// POSTs spans to an OTLP/HTTP receiver in its JSON encoding.
+///
+/// Delivery is best effort, failures are only logged.
+#[derive(Clone, Debug)]
+pub struct OtlpExporter {
+    url: Url,
+    service_name: String,
+    client: Client,
+}
+
+impl OtlpExporter {
+    pub fn new(config: &config::Tracing) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: config.otlp_endpoint.join("v1/traces")?,
+            service_name: config
+                .service_name
+                .clone()
+                .unwrap_or_else(|| String::from(DEFAULT_SERVICE_NAME)),
+            client: Client::new(),
+        })
+    }
+
+    pub async fn export(&self, spans: Vec<Span>) {
+        if spans.is_empty() {
+            return;
+        }
+
+        let result = self
+            .client
+            .post(self.url.clone())
+            .json(&otlp_json(&self.service_name, &spans))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .compat()
+            .await;
+
+        if let Err(e) = result {
+            log::warn!(
+                "failed to export {} spans to {}: {}",
+                spans.len(),
+                self.url,
+                e
+            );
+        }
+    }
+
+    /// Export the spans of the queue in batches until cnd shuts down.
+    ///
+    /// A batch is sent once it is full or `BATCH_DELAY` after its first span
+    /// arrived.
+    pub async fn export_batches(self, queue: SpanQueue) {
+        let SpanQueue { mut spans, dropped } = queue;
+
+        while let Some(span) = spans.next().await {
+            let mut batch = vec![span];
+            drain(&mut spans, &mut batch);
+
+            if batch.len() < MAX_BATCH_SIZE {
+                Delay::new(Instant::now() + BATCH_DELAY)
+                    .compat()
+                    .await
+                    .unwrap_or_else(|e| log::warn!("Failed to wait for delay: {:?}", e));
+                drain(&mut spans, &mut batch);
+            }
+
+            let dropped = dropped.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                log::warn!("dropped {} spans, the export queue was full", dropped);
+            }
+
+            self.export(batch).await
+        }
+    }
+}
+
+fn drain(spans: &mut mpsc::Receiver<Span>, batch: &mut Vec<Span>) {
+    while batch.len() < MAX_BATCH_SIZE {
+        match spans.try_next() {
+            Ok(Some(span)) => batch.push(span),
+            _ => return,
+        }
+    }
+}
+
+fn otlp_json(service_name: &str, spans: &[Span]) -> serde_json::Value {
+    let spans = spans
+        .iter()
+        .map(|span| {
+            let mut json = serde_json::json!({
+                "traceId": hex::encode(span.context.trace_id),
+                "spanId": hex::encode(span.context.span_id),
+                "name": span.name,
+                "kind": SPAN_KIND_INTERNAL,
+                "startTimeUnixNano": unix_nanos(span.start),
+                "endTimeUnixNano": unix_nanos(span.end),
+                "attributes": span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| attribute(key, value))
+                    .collect::<Vec<_>>(),
+            });
+            if let Some(parent_span_id) = span.parent_span_id {
+                json["parentSpanId"] = serde_json::json!(hex::encode(parent_span_id));
+            }
+
+            json
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", service_name)],
+            },
+            "scopeSpans": [{
+                "scope": { "name": DEFAULT_SERVICE_NAME },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn attribute(key: &str, value: &str) -> serde_json::Value {
+    serde_json::json!({
+        "key": key,
+        "value": { "stringValue": value },
+    })
+}
+
+/// A string because JSON numbers cannot hold 64 bit integers precisely.
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_core::{FutureExt, TryFutureExt};
+    use spectral::prelude::*;
+    use std::time::Duration;
+
+    const TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    #[test]
+    fn traceparent_roundtrips() {
+        let context = TRACEPARENT.parse::<TraceContext>().unwrap();
+
+        assert_that(&context.flags).is_equal_to(SAMPLED);
+        assert_that(&context.to_string()).is_equal_to(TRACEPARENT.to_string());
+    }
+
+    #[test]
+    fn invalid_traceparents_are_rejected() {
+        let invalid = vec![
+            "",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331",
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-00",
+            "00-0af7651916cd43dd-b7ad6b7169203331-01",
+        ];
+
+        for header in invalid {
+            assert_that(&header.parse::<TraceContext>()).is_err();
+        }
+    }
+
+    fn queued(queue: &mut SpanQueue) -> Vec<Span> {
+        let mut spans = Vec::new();
+        drain(&mut queue.spans, &mut spans);
+
+        spans
+    }
+
+    #[test]
+    fn steps_are_children_of_the_swap_span_which_continues_the_http_trace() {
+        let (tracer, mut queue) = Tracer::new();
+        let swap_id = SwapId::default();
+        let parent = TRACEPARENT.parse::<TraceContext>().unwrap();
+        let created_at = UNIX_EPOCH + Duration::from_secs(100);
+        let funded_at = created_at + Duration::from_secs(10);
+        let finished_at = funded_at + Duration::from_secs(10);
+
+        tracer
+            .traces
+            .insert(swap_id, SwapTrace::new(Some(parent), created_at));
+        tracer.record(&SwapEvent::Created { swap_id }, created_at);
+        let created = queued(&mut queue);
+        tracer.record(&SwapEvent::AlphaFunded { swap_id }, funded_at);
+        let funded = queued(&mut queue);
+        tracer.record(&SwapEvent::Finished { swap_id }, finished_at);
+        let finished = queued(&mut queue);
+
+        assert_that(&created).is_empty();
+        assert_that(&funded).has_length(1);
+        assert_that(&finished).has_length(2);
+
+        let swap = &finished[1];
+        assert_that(&swap.name).is_equal_to("swap");
+        assert_that(&swap.context.trace_id).is_equal_to(parent.trace_id);
+        assert_that(&swap.parent_span_id).is_equal_to(Some(parent.span_id));
+        assert_that(&swap.start).is_equal_to(created_at);
+
+        let step = &funded[0];
+        assert_that(&step.name).is_equal_to("alpha-funded");
+        assert_that(&step.parent_span_id).is_equal_to(Some(swap.context.span_id));
+        assert_that(&step.start).is_equal_to(created_at);
+        assert_that(&step.end).is_equal_to(funded_at);
+        assert_that(&finished[0].start).is_equal_to(funded_at);
+        assert_that(&tracer.traces.is_empty()).is_true();
+    }
+
+    #[test]
+    fn swaps_whose_trace_is_not_sampled_are_not_recorded() {
+        let (tracer, mut queue) = Tracer::new();
+        let swap_id = SwapId::default();
+        let parent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00"
+            .parse::<TraceContext>()
+            .unwrap();
+
+        tracer.start_swap(swap_id, parent);
+        tracer.record(&SwapEvent::Created { swap_id }, SystemTime::now());
+        tracer.record_span(swap_id, "network.send-request", SystemTime::now());
+        assert_that(&tracer.scope(swap_id)).is_none();
+        tracer.record(&SwapEvent::Finished { swap_id }, SystemTime::now());
+
+        assert_that(&queued(&mut queue)).is_empty();
+    }
+
+    #[test]
+    fn spans_of_a_task_are_children_of_its_scope() {
+        let (tracer, mut queue) = Tracer::new();
+        let swap_id = SwapId::default();
+        let scope = tracer.scope(swap_id).unwrap();
+        let task = in_span("bitcoin.latest-block", async {})
+            .unit_error()
+            .boxed()
+            .compat();
+
+        InScope::new(Some(scope.clone()), task).wait().unwrap();
+
+        let spans = queued(&mut queue);
+        assert_that(&spans).has_length(1);
+        assert_that(&spans[0].name).is_equal_to("bitcoin.latest-block");
+        assert_that(&spans[0].context.trace_id).is_equal_to(scope.context.trace_id);
+        assert_that(&spans[0].parent_span_id).is_equal_to(Some(scope.context.span_id));
+        assert_that(&current_scope()).is_none();
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    btsieve::{bitcoin::BitcoinConnector, ethereum::Web3Connector},
+    http_api::{routes::rfc003::LedgerState as HttpLedgerState, Http},
+    swap_protocols::{
+        asset::Asset,
+        ledger::Bitcoin,
+        rfc003::{
+            self, events::HtlcEvents, state_machine::HtlcParams, HtlcState, Ledger, LedgerState,
+        },
+        SwapId,
+    },
+    timestamp::Timestamp,
+};
+use futures_core::compat::Future01CompatExt;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tokio::prelude::future::Either;
+
+/// What a cnd without a seed needs to follow swaps it does not take part in,
+/// e.g. to audit a trade.
+#[derive(Clone, Debug)]
+pub struct WatchOnly {
+    pub watched: WatchedSwaps,
+    pub bitcoin_connector: BitcoinConnector,
+    pub ethereum_connector: Web3Connector,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Alpha,
+    Beta,
+}
+
+/// A change of the HTLC on one of the ledgers of a watched swap.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Transition {
+    pub ledger: Side,
+    pub status: HtlcState,
+    pub seen_at: Timestamp,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchedSwap {
+    pub id: SwapId,
+    /// As in the `alpha` and `beta` sub-resources of a swap.
+    pub alpha_ledger: serde_json::Value,
+    pub beta_ledger: serde_json::Value,
+    pub transitions: Vec<Transition>,
+}
+
+/// The swaps imported via `POST /watch`, in the order they were imported.
+///
+/// Not persisted, swaps have to be imported again after a restart.
+#[derive(Clone, Debug, Default)]
+pub struct WatchedSwaps(Arc<Mutex<Vec<WatchedSwap>>>);
+
+impl WatchedSwaps {
+    pub fn insert(&self, id: SwapId) -> WatchedSwap {
+        // Nothing is known about either HTLC yet, hence the ledger does not matter.
+        let not_deployed =
+            serde_json::to_value(HttpLedgerState::from(LedgerState::<Bitcoin>::NotDeployed))
+                .expect("ledger states never fail to serialize");
+        let swap = WatchedSwap {
+            id,
+            alpha_ledger: not_deployed.clone(),
+            beta_ledger: not_deployed,
+            transitions: Vec::new(),
+        };
+
+        self.0.lock().expect("poisoned").push(swap.clone());
+
+        swap
+    }
+
+    pub fn get(&self, id: &SwapId) -> Option<WatchedSwap> {
+        self.0
+            .lock()
+            .expect("poisoned")
+            .iter()
+            .find(|swap| swap.id == *id)
+            .cloned()
+    }
+
+    pub fn all(&self) -> Vec<WatchedSwap> {
+        self.0.lock().expect("poisoned").clone()
+    }
+
+    fn transition<L>(&self, id: SwapId, side: Side, state: LedgerState<L>)
+    where
+        L: Ledger,
+        Http<L::HtlcLocation>: Serialize,
+        Http<L::Transaction>: Serialize,
+    {
+        let status = HtlcState::from(state.clone());
+        let ledger_state = match serde_json::to_value(HttpLedgerState::from(state)) {
+            Ok(ledger_state) => ledger_state,
+            Err(e) => {
+                log::error!("failed to serialize ledger state of swap {}: {:?}", id, e);
+                return;
+            }
+        };
+        log::info!("{:?} HTLC of watched swap {} is {:?}", side, id, status);
+
+        let mut swaps = self.0.lock().expect("poisoned");
+        if let Some(swap) = swaps.iter_mut().find(|swap| swap.id == id) {
+            match side {
+                Side::Alpha => swap.alpha_ledger = ledger_state,
+                Side::Beta => swap.beta_ledger = ledger_state,
+            }
+            swap.transitions.push(Transition {
+                ledger: side,
+                status,
+                seen_at: Timestamp::now(),
+            });
+        }
+    }
+}
+
+/// Follow the HTLC described by `htlc_params` from its deployment until it is
+/// redeemed or refunded.
+///
+/// An HTLC that was funded with the wrong amount is reported as such and still
+/// followed, it can be redeemed or refunded all the same.
+pub async fn watch_htlc<L, A, C>(
+    connector: C,
+    htlc_params: HtlcParams<L, A>,
+    id: SwapId,
+    side: Side,
+    watched: WatchedSwaps,
+) -> Result<(), rfc003::Error>
+where
+    L: Ledger,
+    A: Asset,
+    C: HtlcEvents<L, A>,
+    Http<L::HtlcLocation>: Serialize,
+    Http<L::Transaction>: Serialize,
+{
+    let deployed = connector
+        .htlc_deployed(htlc_params.clone())
+        .compat()
+        .await?;
+    watched.transition::<L>(id, side, LedgerState::Deployed {
+        htlc_location: deployed.location.clone(),
+        deploy_transaction: deployed.transaction.clone(),
+    });
+
+    let funded = connector
+        .htlc_funded(htlc_params.clone(), &deployed)
+        .compat()
+        .await?;
+    let funded_state = if funded.asset == htlc_params.asset {
+        LedgerState::Funded {
+            htlc_location: deployed.location.clone(),
+            deploy_transaction: deployed.transaction.clone(),
+            fund_transaction: funded.transaction.clone(),
+            top_ups: funded.top_ups.clone(),
+        }
+    } else {
+        LedgerState::IncorrectlyFunded {
+            htlc_location: deployed.location.clone(),
+            deploy_transaction: deployed.transaction.clone(),
+            fund_transaction: funded.transaction.clone(),
+            top_ups: funded.top_ups.clone(),
+        }
+    };
+    watched.transition::<L>(id, side, funded_state);
+
+    let outcome = connector
+        .htlc_redeemed_or_refunded(htlc_params, &deployed, &funded)
+        .compat()
+        .await?;
+    let final_state = match outcome {
+        Either::A(redeemed) => LedgerState::Redeemed {
+            htlc_location: deployed.location,
+            deploy_transaction: deployed.transaction,
+            fund_transaction: funded.transaction,
+            top_ups: funded.top_ups,
+            redeem_transaction: redeemed.transaction,
+        },
+        Either::B(refunded) => LedgerState::Refunded {
+            htlc_location: deployed.location,
+            deploy_transaction: deployed.transaction,
+            fund_transaction: funded.transaction,
+            top_ups: funded.top_ups,
+            refund_transaction: refunded.transaction,
+        },
+    };
+    watched.transition::<L>(id, side, final_state);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn transitions_are_recorded_for_the_given_side() {
+        let watched = WatchedSwaps::default();
+        let id = SwapId::default();
+        watched.insert(id);
+
+        watched.transition::<Bitcoin>(id, Side::Beta, LedgerState::Deployed {
+            htlc_location: bitcoin::OutPoint::null(),
+            deploy_transaction: bitcoin::Transaction {
+                version: 2,
+                lock_time: 0,
+                input: vec![],
+                output: vec![],
+            },
+        });
+
+        let swap = watched.get(&id).unwrap();
+        assert_that(&swap.transitions).has_length(1);
+        assert_that(&swap.transitions[0].ledger).is_equal_to(Side::Beta);
+        assert_that(&swap.transitions[0].status).is_equal_to(HtlcState::Deployed);
+        assert_that(&swap.beta_ledger["status"]).is_equal_to(serde_json::json!("DEPLOYED"));
+        assert_that(&swap.alpha_ledger["status"]).is_equal_to(serde_json::json!("NOT_DEPLOYED"));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    swap_protocols::{SwapEvent, SwapId},
+    timestamp::Timestamp,
+};
+use futures::{sync::mpsc, Future};
+use futures_core::{
+    compat::{Future01CompatExt, Stream01CompatExt},
+    stream::StreamExt,
+};
+use reqwest::{r#async::Client, Url};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug};
+
+/// An event that needs the attention of the user.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Event {
+    SwapRequestReceived {
+        swap_id: SwapId,
+    },
+    ActionRequired {
+        swap_id: SwapId,
+        actions: Vec<String>,
+    },
+    ExpiryApproaching {
+        swap_id: SwapId,
+        /// Either `alpha` or `beta`.
+        ledger: String,
+        expiry: Timestamp,
+    },
+    SwapCompleted {
+        swap_id: SwapId,
+    },
+    SwapFailed {
+        swap_id: SwapId,
+        reason: String,
+    },
+    AutoRefundBroadcast {
+        swap_id: SwapId,
+        transaction_id: String,
+    },
+    AutoRefundFailed {
+        swap_id: SwapId,
+        reason: String,
+    },
+}
+
+/// The type of an event, used to choose which events to be notified about.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    SwapRequestReceived,
+    ActionRequired,
+    ExpiryApproaching,
+    SwapCompleted,
+    SwapFailed,
+    AutoRefundBroadcast,
+    AutoRefundFailed,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::SwapRequestReceived { .. } => EventKind::SwapRequestReceived,
+            Event::ActionRequired { .. } => EventKind::ActionRequired,
+            Event::ExpiryApproaching { .. } => EventKind::ExpiryApproaching,
+            Event::SwapCompleted { .. } => EventKind::SwapCompleted,
+            Event::SwapFailed { .. } => EventKind::SwapFailed,
+            Event::AutoRefundBroadcast { .. } => EventKind::AutoRefundBroadcast,
+            Event::AutoRefundFailed { .. } => EventKind::AutoRefundFailed,
+        }
+    }
+}
+
+/// A one line summary for notifications that are read by humans.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::SwapRequestReceived { swap_id } => {
+                write!(
+                    f,
+                    "Swap {} was requested and waits to be accepted or declined",
+                    swap_id
+                )
+            }
+            Event::ActionRequired { swap_id, actions } => {
+                write!(
+                    f,
+                    "Swap {} waits for you to {}",
+                    swap_id,
+                    actions.join(" or ")
+                )
+            }
+            Event::ExpiryApproaching {
+                swap_id,
+                ledger,
+                expiry,
+            } => write!(
+                f,
+                "The {} HTLC of swap {} expires at {} UTC",
+                ledger,
+                swap_id,
+                chrono::NaiveDateTime::from_timestamp(i64::from(*expiry), 0)
+            ),
+            Event::SwapCompleted { swap_id } => write!(f, "Swap {} completed", swap_id),
+            Event::SwapFailed { swap_id, reason } => {
+                write!(f, "Swap {} failed: {}", swap_id, reason)
+            }
+            Event::AutoRefundBroadcast {
+                swap_id,
+                transaction_id,
+            } => write!(
+                f,
+                "Swap {} was refunded automatically in transaction {}",
+                swap_id, transaction_id
+            ),
+            Event::AutoRefundFailed { swap_id, reason } => {
+                write!(
+                    f,
+                    "Swap {} could not be refunded automatically: {}",
+                    swap_id, reason
+                )
+            }
+        }
+    }
+}
+
+/// POSTs events as JSON to the configured URL.
+///
+/// Delivery is best effort, failures are only logged because the events are
+/// logged anyway.
+#[derive(Clone, Debug)]
+pub struct Webhook {
+    url: Url,
+    client: Client,
+}
+
+impl Webhook {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            client: Client::new(),
+        }
+    }
+
+    pub async fn send<E: Serialize + Debug>(&self, event: E) {
+        let result = self
+            .client
+            .post(self.url.clone())
+            .json(&event)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .compat()
+            .await;
+
+        if let Err(e) = result {
+            log::warn!("failed to send {:?} to webhook {}: {}", event, self.url, e);
+        }
+    }
+
+    /// POST every swap event the subscription receives until cnd shuts down.
+    pub async fn forward_swap_events(self, events: mpsc::UnboundedReceiver<SwapEvent>) {
+        let mut events = events.compat();
+
+        while let Some(Ok(event)) = events.next().await {
+            self.send(event).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn event_is_tagged_with_its_type() {
+        let swap_id = SwapId::default();
+        let event = Event::AutoRefundBroadcast {
+            swap_id,
+            transaction_id: String::from("e3b0c442"),
+        };
+
+        assert_that(&serde_json::to_value(&event).unwrap()).is_equal_to(serde_json::json!({
+            "type": "auto-refund-broadcast",
+            "swap_id": swap_id,
+            "transaction_id": "e3b0c442",
+        }));
+    }
+}
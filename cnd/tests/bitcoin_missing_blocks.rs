@@ -2,12 +2,9 @@ pub mod bitcoin_helper;
 
 use bitcoin::Address;
 use bitcoin_helper::BitcoinConnectorMock;
-use cnd::{
-    btsieve::{bitcoin::TransactionPattern, MatchingTransactions},
-    first_or_else::StreamExt,
-};
+use cnd::btsieve::{bitcoin::TransactionPattern, MatchingTransactions};
+use futures_core::{executor::block_on, StreamExt};
 use std::str::FromStr;
-use tokio::prelude::Future;
 
 #[test]
 fn find_transaction_in_missing_block() {
@@ -25,24 +22,27 @@ fn find_transaction_in_missing_block() {
         ],
     );
 
-    let expected_transaction: bitcoin::Transaction = connector
-        .matching_transactions(
-            TransactionPattern {
-                to_address: Some(
-                    Address::from_str(
-                        include_str!("test_data/bitcoin/find_transaction_in_missing_block/address")
+    let expected_transaction: bitcoin::Transaction = block_on(
+        connector
+            .matching_transactions(
+                TransactionPattern {
+                    to_address: Some(
+                        Address::from_str(
+                            include_str!(
+                                "test_data/bitcoin/find_transaction_in_missing_block/address"
+                            )
                             .trim(),
-                    )
-                    .unwrap(),
-                ),
-                from_outpoint: None,
-                unlock_script: None,
-            },
-            None,
-        )
-        .first_or_else(|| panic!())
-        .wait()
-        .unwrap();
+                        )
+                        .unwrap(),
+                    ),
+                    from_outpoint: None,
+                    unlock_script: None,
+                },
+                None,
+            )
+            .next(),
+    )
+    .unwrap();
 
     assert_eq!(
         expected_transaction,
@@ -69,8 +69,9 @@ fn find_transaction_in_missing_block_with_big_gap() {
         ],
     );
 
-    let expected_transaction: bitcoin::Transaction = connector
-        .matching_transactions(
+    let expected_transaction: bitcoin::Transaction = block_on(
+        connector
+            .matching_transactions(
             TransactionPattern {
                 to_address: Some(
                     Address::from_str(
@@ -86,9 +87,9 @@ fn find_transaction_in_missing_block_with_big_gap() {
             },
             None,
         )
-        .first_or_else(|| panic!())
-        .wait()
-        .unwrap();
+        .next(),
+    )
+    .unwrap();
 
     assert_eq!(
         expected_transaction,
@@ -113,8 +114,9 @@ fn find_transaction_if_blockchain_reorganisation() {
         ],
     );
 
-    let expected_transaction: bitcoin::Transaction = connector
-        .matching_transactions(
+    let expected_transaction: bitcoin::Transaction = block_on(
+        connector
+            .matching_transactions(
             TransactionPattern {
                 to_address: Some(
                     Address::from_str(
@@ -130,9 +132,9 @@ fn find_transaction_if_blockchain_reorganisation() {
             },
             None,
         )
-        .first_or_else(|| panic!())
-        .wait()
-        .unwrap();
+        .next(),
+    )
+    .unwrap();
 
     assert_eq!(
         expected_transaction,
@@ -160,8 +162,9 @@ fn find_transaction_if_blockchain_reorganisation_with_long_chain() {
         ],
     );
 
-    let expected_transaction: bitcoin::Transaction = connector
-        .matching_transactions(TransactionPattern {
+    let expected_transaction: bitcoin::Transaction = block_on(
+        connector
+            .matching_transactions(TransactionPattern {
             to_address: Some(
                 Address::from_str(
                     include_str!(
@@ -174,9 +177,9 @@ fn find_transaction_if_blockchain_reorganisation_with_long_chain() {
             from_outpoint: None,
             unlock_script: None,
         }, None)
-        .first_or_else(|| panic!())
-        .wait()
-        .unwrap();
+        .next(),
+    )
+    .unwrap();
 
     assert_eq!(
         expected_transaction,
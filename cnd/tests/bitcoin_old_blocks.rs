@@ -2,12 +2,9 @@ pub mod bitcoin_helper;
 
 use bitcoin::Address;
 use bitcoin_helper::BitcoinConnectorMock;
-use cnd::{
-    btsieve::{bitcoin::TransactionPattern, MatchingTransactions},
-    first_or_else::StreamExt,
-};
+use cnd::btsieve::{bitcoin::TransactionPattern, MatchingTransactions};
+use futures_core::{executor::block_on, StreamExt};
 use std::str::FromStr;
-use tokio::prelude::Future;
 
 #[test]
 fn find_transaction_in_old_block() {
@@ -29,24 +26,25 @@ fn find_transaction_in_old_block() {
         ],
     );
 
-    let expected_transaction: bitcoin::Transaction = connector
-        .matching_transactions(
-            TransactionPattern {
-                to_address: Some(
-                    Address::from_str(
-                        include_str!("test_data/bitcoin/find_transaction_in_old_block/address")
-                            .trim(),
-                    )
-                    .unwrap(),
-                ),
-                from_outpoint: None,
-                unlock_script: None,
-            },
-            Some(block1_with_transaction.header.time),
-        )
-        .first_or_else(|| panic!())
-        .wait()
-        .unwrap();
+    let expected_transaction: bitcoin::Transaction = block_on(
+        connector
+            .matching_transactions(
+                TransactionPattern {
+                    to_address: Some(
+                        Address::from_str(
+                            include_str!("test_data/bitcoin/find_transaction_in_old_block/address")
+                                .trim(),
+                        )
+                        .unwrap(),
+                    ),
+                    from_outpoint: None,
+                    unlock_script: None,
+                },
+                Some(block1_with_transaction.header.time),
+            )
+            .next(),
+    )
+    .unwrap();
 
     assert_eq!(
         expected_transaction,
@@ -1,9 +1,12 @@
+#![cfg(feature = "bitcoind")]
+
 use bitcoin::{Amount, Network};
 use bitcoincore_rpc::RpcApi;
 use cnd::btsieve::{
     bitcoin::{BitcoindConnector, TransactionPattern},
     MatchingTransactions,
 };
+use futures_core::{StreamExt as _, TryStreamExt as _};
 use images::coblox_bitcoincore::BitcoinCore;
 use reqwest::Url;
 use std::{
@@ -49,6 +52,8 @@ fn bitcoin_transaction_pattern_e2e_test() {
             },
             None,
         )
+        .map(Ok::<_, ()>)
+        .compat()
         .take(1)
         .into_future()
         .map_err(|_| ());
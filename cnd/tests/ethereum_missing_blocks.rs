@@ -3,10 +3,9 @@ pub mod ethereum_helper;
 use cnd::{
     btsieve::{ethereum::TransactionPattern, MatchingTransactions},
     ethereum::{Transaction, TransactionAndReceipt, TransactionReceipt},
-    first_or_else::StreamExt,
 };
 use ethereum_helper::EthereumConnectorMock;
-use tokio::prelude::Future;
+use futures_core::{executor::block_on, StreamExt};
 
 #[test]
 fn find_transaction_in_missing_block() {
@@ -42,21 +41,22 @@ fn find_transaction_in_missing_block() {
         runtime.executor(),
     );
 
-    let expected_transaction_and_receipt: TransactionAndReceipt = connector
-        .matching_transactions(
-            TransactionPattern {
-                from_address: None,
-                to_address: Some(transaction.to.unwrap()),
-                is_contract_creation: None,
-                transaction_data: None,
-                transaction_data_length: None,
-                events: None,
-            },
-            None,
-        )
-        .first_or_else(|| panic!())
-        .wait()
-        .unwrap();
+    let expected_transaction_and_receipt: TransactionAndReceipt = block_on(
+        connector
+            .matching_transactions(
+                TransactionPattern {
+                    from_address: None,
+                    to_address: Some(transaction.to.unwrap()),
+                    is_contract_creation: None,
+                    transaction_data: None,
+                    transaction_data_length: None,
+                    events: None,
+                },
+                None,
+            )
+            .next(),
+    )
+    .unwrap();
 
     assert_eq!(expected_transaction_and_receipt, TransactionAndReceipt {
         transaction,
@@ -104,21 +104,22 @@ fn find_transaction_in_missing_block_with_big_gap() {
         runtime.executor(),
     );
 
-    let expected_transaction_and_receipt: TransactionAndReceipt = connector
-        .matching_transactions(
-            TransactionPattern {
-                from_address: None,
-                to_address: Some(transaction.to.unwrap()),
-                is_contract_creation: None,
-                transaction_data: None,
-                transaction_data_length: None,
-                events: None,
-            },
-            None,
-        )
-        .first_or_else(|| panic!())
-        .wait()
-        .unwrap();
+    let expected_transaction_and_receipt: TransactionAndReceipt = block_on(
+        connector
+            .matching_transactions(
+                TransactionPattern {
+                    from_address: None,
+                    to_address: Some(transaction.to.unwrap()),
+                    is_contract_creation: None,
+                    transaction_data: None,
+                    transaction_data_length: None,
+                    events: None,
+                },
+                None,
+            )
+            .next(),
+    )
+    .unwrap();
 
     assert_eq!(expected_transaction_and_receipt, TransactionAndReceipt {
         transaction,
@@ -1,3 +1,6 @@
+// Talks to the node with the HTTP transport of web3 directly.
+#![cfg(feature = "web3-http")]
+
 use cnd::{
     btsieve::{
         ethereum::{TransactionPattern, Web3Connector},
@@ -11,6 +14,7 @@ use cnd::{
         TransactionRequest, U256,
     },
 };
+use futures_core::{StreamExt as _, TryStreamExt as _};
 use reqwest::Url;
 use std::time::{Duration, Instant};
 use testcontainers::*;
@@ -59,6 +63,8 @@ fn ethereum_transaction_pattern_e2e_test() {
             },
             None,
         )
+        .map(Ok::<_, ()>)
+        .compat()
         .take(1)
         .into_future()
         .map_err(|_| ());
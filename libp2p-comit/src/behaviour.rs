@@ -1,10 +1,10 @@
 use crate::{
-    frame::{OutboundRequest, Response},
+    frame::{CodecError, Direction, FrameObserver, OutboundRequest, Response},
     handler::{
         self, InboundMessage, OutboundMessage, PendingInboundResponse, ProtocolInEvent,
-        ProtocolOutEvent,
+        ProtocolOutEvent, RequestError,
     },
-    ComitHandler, PendingInboundRequest, PendingOutboundRequest,
+    ComitHandler, PendingInboundRequest, PendingOutboundRequest, RequestKind, RequestMetrics,
 };
 use futures::{
     stream::Stream,
@@ -16,6 +16,8 @@ use libp2p_swarm::{NetworkBehaviour, NetworkBehaviourAction, PollParameters};
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::prelude::{AsyncRead, AsyncWrite};
 
@@ -40,6 +42,13 @@ pub enum BehaviourOutEvent {
     },
 }
 
+/// Something a peer sent that is not in our known request headers.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Unrecognized {
+    RequestType(String),
+    MandatoryHeader(String),
+}
+
 /// Network behaviour that handles the COMIT messaging protocol.
 #[derive(Debug)]
 pub struct Comit<TSubstream> {
@@ -49,7 +58,15 @@ pub struct Comit<TSubstream> {
     events: UnboundedReceiver<NetworkBehaviourAction<ProtocolInEvent, BehaviourOutEvent>>,
 
     known_request_headers: HashMap<String, HashSet<String>>,
+    max_inbound_substreams: Option<usize>,
+    frame_observer: Option<Arc<dyn FrameObserver>>,
+    max_frame_size: Option<usize>,
     connections: HashMap<PeerId, ConnectionState>,
+    last_seen: HashMap<PeerId, SystemTime>,
+    unrecognized: HashMap<Unrecognized, u64>,
+    /// Shared with the futures returned by `send_request` so they can record
+    /// the latency once the response arrives.
+    request_metrics: Arc<Mutex<HashMap<RequestKind, RequestMetrics>>>,
 }
 
 impl<TSubstream> Comit<TSubstream> {
@@ -61,7 +78,40 @@ impl<TSubstream> Comit<TSubstream> {
             events_sender: sender,
             events: receiver,
             known_request_headers,
+            max_inbound_substreams: None,
+            frame_observer: None,
+            max_frame_size: None,
             connections: HashMap::new(),
+            last_seen: HashMap::new(),
+            unrecognized: HashMap::new(),
+            request_metrics: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Limit how many inbound requests of a single connection may be pending
+    /// at the same time, further ones are dropped.
+    pub fn with_max_inbound_substreams(self, max_inbound_substreams: usize) -> Self {
+        Self {
+            max_inbound_substreams: Some(max_inbound_substreams),
+            ..self
+        }
+    }
+
+    /// Let `frame_observer` see every frame sent to or received from any
+    /// peer.
+    pub fn with_frame_observer(self, frame_observer: Arc<dyn FrameObserver>) -> Self {
+        Self {
+            frame_observer: Some(frame_observer),
+            ..self
+        }
+    }
+
+    /// Reject frames larger than `max_frame_size` bytes from any peer before
+    /// they are fully buffered, the substream is closed with an error.
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size: Some(max_frame_size),
+            ..self
         }
     }
 
@@ -69,10 +119,18 @@ impl<TSubstream> Comit<TSubstream> {
         &mut self,
         dial_information: (PeerId, Option<Multiaddr>),
         request: OutboundRequest,
-    ) -> Box<dyn Future<Item = Response, Error = ()> + Send> {
+    ) -> Box<dyn Future<Item = Response, Error = RequestError> + Send> {
         let (peer_id, address_hint) = dial_information;
         let (sender, receiver) = futures::oneshot();
 
+        let kind = RequestKind {
+            direction: Direction::Outbound,
+            request_type: request.request_type().to_owned(),
+        };
+        record_request(&self.request_metrics, kind.clone());
+        let request_metrics = Arc::clone(&self.request_metrics);
+        let sent_at = Instant::now();
+
         let request = PendingOutboundRequest {
             request,
             channel: sender,
@@ -126,10 +184,18 @@ impl<TSubstream> Comit<TSubstream> {
             }
         }
 
-        Box::new(receiver.map_err(|_| {
-            log::warn!(
-                "Sender of response future was unexpectedly dropped before response was received."
-            )
+        Box::new(receiver.then(move |result| match result {
+            Ok(Ok(response)) => {
+                record_latency(&request_metrics, kind, sent_at.elapsed());
+                Ok(response)
+            }
+            Ok(result) => result,
+            Err(_) => {
+                log::warn!(
+                    "Sender of response future was unexpectedly dropped before response was received."
+                );
+                Err(RequestError::ConnectionClosed)
+            }
         }))
     }
 
@@ -147,6 +213,41 @@ impl<TSubstream> Comit<TSubstream> {
 
         addresses.into_iter()
     }
+
+    pub fn is_connected(&self, peer_id: &PeerId) -> bool {
+        match self.connections.get(peer_id) {
+            Some(ConnectionState::Connected { .. }) => true,
+            _ => false,
+        }
+    }
+
+    /// The last time we were connected to or heard from the given peer.
+    pub fn last_seen(&self, peer_id: &PeerId) -> Option<SystemTime> {
+        self.last_seen.get(peer_id).cloned()
+    }
+
+    /// How often peers sent each request type or mandatory header that we did
+    /// not know, for diagnosing peers that speak a newer protocol.
+    pub fn unrecognized(&self) -> &HashMap<Unrecognized, u64> {
+        &self.unrecognized
+    }
+
+    /// How many requests of each type we sent and received, and how long
+    /// peers took to answer ours.
+    pub fn request_metrics(&self) -> HashMap<RequestKind, RequestMetrics> {
+        self.request_metrics
+            .lock()
+            .expect("no one panics while holding the lock")
+            .clone()
+    }
+
+    fn count_unrecognized(&mut self, unrecognized: Unrecognized) {
+        *self.unrecognized.entry(unrecognized).or_insert(0) += 1;
+    }
+
+    fn saw(&mut self, peer_id: &PeerId) {
+        self.last_seen.insert(peer_id.clone(), SystemTime::now());
+    }
 }
 
 impl<TSubstream> NetworkBehaviour for Comit<TSubstream>
@@ -157,7 +258,21 @@ where
     type OutEvent = BehaviourOutEvent;
 
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
-        ComitHandler::new(self.known_request_headers.clone())
+        let handler = ComitHandler::new(self.known_request_headers.clone());
+        let handler = match self.max_inbound_substreams {
+            Some(max) => handler.with_max_inbound_substreams(max),
+            None => handler,
+        };
+
+        let handler = match &self.frame_observer {
+            Some(frame_observer) => handler.with_frame_observer(Arc::clone(frame_observer)),
+            None => handler,
+        };
+
+        match self.max_frame_size {
+            Some(max) => handler.with_max_frame_size(max),
+            None => handler,
+        }
     }
 
     fn addresses_of_peer(&mut self, peer_id: &PeerId) -> Vec<Multiaddr> {
@@ -179,6 +294,7 @@ where
 
     fn inject_connected(&mut self, peer_id: PeerId, endpoint: ConnectedPoint) {
         log::debug!(target: "sub-libp2p", "connected to {} at {:?}", peer_id, endpoint);
+        self.saw(&peer_id);
 
         let address = match endpoint {
             ConnectedPoint::Dialer { address } => address,
@@ -226,6 +342,7 @@ where
 
     fn inject_disconnected(&mut self, peer_id: &PeerId, endpoint: ConnectedPoint) {
         log::debug!(target: "sub-libp2p", "disconnected from {} at {:?}", peer_id, endpoint);
+        self.saw(peer_id);
 
         let address = match endpoint {
             ConnectedPoint::Dialer { address } => address,
@@ -243,9 +360,33 @@ where
         }
     }
 
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        log::debug!(target: "sub-libp2p", "failed to dial {}", peer_id);
+
+        let pending_events = match self.connections.get_mut(peer_id) {
+            Some(ConnectionState::Connecting { pending_events, .. }) => {
+                std::mem::replace(pending_events, Vec::new())
+            }
+            _ => return,
+        };
+        self.connections.remove(peer_id);
+
+        for event in pending_events {
+            let ProtocolInEvent::Message(OutboundMessage::Request(request)) = event;
+            let _ = request.channel.send(Err(RequestError::PeerUnreachable));
+        }
+    }
+
     fn inject_node_event(&mut self, peer: PeerId, event: ProtocolOutEvent) {
+        self.saw(&peer);
+
         match event {
             ProtocolOutEvent::Message(InboundMessage::Request(request)) => {
+                record_request(&self.request_metrics, RequestKind {
+                    direction: Direction::Inbound,
+                    request_type: request.request.request_type().to_owned(),
+                });
+
                 self.events_sender
                     .unbounded_send(NetworkBehaviourAction::GenerateEvent(
                         BehaviourOutEvent::PendingInboundRequest {
@@ -259,7 +400,12 @@ where
                 response,
                 channel,
             })) => {
-                let _ = channel.send(response);
+                let _ = channel.send(Ok(response));
+            }
+            ProtocolOutEvent::Error(handler::Error::MalformedJson(
+                error @ CodecError::FrameTooLarge { .. },
+            )) => {
+                log::warn!(target: "sub-libp2p", "closing substream with {}: {}", peer, error);
             }
             ProtocolOutEvent::Error(handler::Error::MalformedJson(error)) => {
                 log::error!(target: "sub-libp2p", "failure in communication with {}: {:?}", peer, error);
@@ -274,9 +420,13 @@ where
             }
             ProtocolOutEvent::Error(handler::Error::UnknownMandatoryHeader(error)) => {
                 log::error!(target: "sub-libp2p", "received frame with unexpected mandatory header from {}, {:?}", peer, error);
+                for header in error.iter() {
+                    self.count_unrecognized(Unrecognized::MandatoryHeader(header.clone()));
+                }
             }
             ProtocolOutEvent::Error(handler::Error::UnknownRequestType(error)) => {
                 log::error!(target: "sub-libp2p", "received frame with unknown request type from {}, {:?}", peer, error);
+                self.count_unrecognized(Unrecognized::RequestType(error));
             }
             ProtocolOutEvent::Error(handler::Error::UnknownFrameType) => {
                 log::error!(target: "sub-libp2p", "received frame with unknown type from {}", peer);
@@ -303,3 +453,29 @@ where
             .map(|item| item.expect("unbounded channel never ends"))
     }
 }
+
+fn record_request(
+    request_metrics: &Mutex<HashMap<RequestKind, RequestMetrics>>,
+    kind: RequestKind,
+) {
+    request_metrics
+        .lock()
+        .expect("no one panics while holding the lock")
+        .entry(kind)
+        .or_default()
+        .count += 1;
+}
+
+fn record_latency(
+    request_metrics: &Mutex<HashMap<RequestKind, RequestMetrics>>,
+    kind: RequestKind,
+    latency: Duration,
+) {
+    request_metrics
+        .lock()
+        .expect("no one panics while holding the lock")
+        .entry(kind)
+        .or_default()
+        .latency
+        .observe(latency);
+}
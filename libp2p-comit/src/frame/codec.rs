@@ -1,6 +1,6 @@
 use crate::Frame;
 use bytes::BytesMut;
-use std::io;
+use std::{fmt, io, sync::Arc};
 use tokio_codec::{Decoder, Encoder};
 
 #[derive(Debug, thiserror::Error)]
@@ -9,14 +9,50 @@ pub enum CodecError {
     Json(#[from] serde_json::Error),
     #[error("io: ")]
     IO(#[from] io::Error),
+    #[error("frame is larger than {max} bytes")]
+    FrameTooLarge { max: usize },
 }
 
-#[derive(Debug)]
-pub struct JsonFrameCodec;
+/// Whether a frame was received from or sent to the peer.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, strum_macros::Display)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// Gets to see every frame that passes through a `JsonFrameCodec`, e.g. to
+/// log it.
+pub trait FrameObserver: fmt::Debug + Send + Sync {
+    fn observe(&self, direction: Direction, frame: &Frame);
+}
 
-impl Default for JsonFrameCodec {
-    fn default() -> Self {
-        Self {}
+#[derive(Debug, Default)]
+pub struct JsonFrameCodec {
+    observer: Option<Arc<dyn FrameObserver>>,
+    max_frame_size: Option<usize>,
+}
+
+impl JsonFrameCodec {
+    pub fn with_observer(observer: Arc<dyn FrameObserver>) -> Self {
+        Self {
+            observer: Some(observer),
+            max_frame_size: None,
+        }
+    }
+
+    /// Fail as soon as more than `max_frame_size` bytes arrive without the
+    /// frame being terminated, instead of buffering them.
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size: Some(max_frame_size),
+            ..self
+        }
+    }
+
+    fn observe(&self, direction: Direction, frame: &Frame) {
+        if let Some(observer) = &self.observer {
+            observer.observe(direction, frame)
+        }
     }
 }
 
@@ -25,6 +61,8 @@ impl Encoder for JsonFrameCodec {
     type Error = CodecError;
 
     fn encode(&mut self, item: Frame, dst: &mut BytesMut) -> Result<(), CodecError> {
+        self.observe(Direction::Outbound, &item);
+
         let mut bytes = serde_json::to_vec(&item)?;
         bytes.push(b'\n');
 
@@ -39,10 +77,20 @@ impl Decoder for JsonFrameCodec {
     type Error = CodecError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, CodecError> {
-        match src.iter().position(|b| *b == b'\n') {
+        let position = src.iter().position(|b| *b == b'\n');
+
+        if let Some(max) = self.max_frame_size {
+            if position.unwrap_or_else(|| src.len()) > max {
+                return Err(CodecError::FrameTooLarge { max });
+            }
+        }
+
+        match position {
             Some(position) => {
                 let frame_bytes = src.split_to(position + 1);
                 let frame = serde_json::from_slice(frame_bytes.as_ref())?;
+                self.observe(Direction::Inbound, &frame);
+
                 Ok(Some(frame))
             }
             None => Ok(None),
@@ -56,6 +104,19 @@ mod tests {
     use super::*;
     use crate::FrameType;
     use spectral::prelude::*;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct RecordingObserver {
+        frames: Mutex<Vec<(Direction, String)>>,
+    }
+
+    impl FrameObserver for RecordingObserver {
+        fn observe(&self, direction: Direction, frame: &Frame) {
+            let frame = serde_json::to_string(frame).unwrap();
+            self.frames.lock().unwrap().push((direction, frame));
+        }
+    }
 
     #[test]
     fn should_encode_frame_to_bytes() {
@@ -136,4 +197,43 @@ mod tests {
             .is_some()
             .is_equal_to(&expected_frame);
     }
+
+    #[test]
+    fn given_more_bytes_than_the_max_frame_size_without_newline_should_fail() {
+        let mut codec = JsonFrameCodec::default().with_max_frame_size(16);
+
+        let mut bytes = BytesMut::new();
+        bytes.extend(br#"{"type":"REQUEST","#.as_ref());
+
+        assert_that(&codec.decode(&mut bytes)).is_err();
+
+        let mut codec = JsonFrameCodec::default().with_max_frame_size(64);
+
+        assert_that(&codec.decode(&mut bytes)).is_ok().is_none();
+    }
+
+    #[test]
+    fn observer_sees_encoded_and_decoded_frames() {
+        let observer = Arc::new(RecordingObserver::default());
+        let mut codec = JsonFrameCodec::with_observer(observer.clone());
+
+        let request = Frame::new(FrameType::Request, serde_json::Value::Null);
+        assert!(codec.encode(request, &mut BytesMut::new()).is_ok());
+
+        let mut bytes = BytesMut::new();
+        bytes.extend(br#"{"type":"RESPONSE","payload":null}"#.as_ref());
+        bytes.extend(b"\n");
+        assert_that(&codec.decode(&mut bytes)).is_ok().is_some();
+
+        assert_that(&*observer.frames.lock().unwrap()).is_equal_to(vec![
+            (
+                Direction::Outbound,
+                r#"{"type":"REQUEST","payload":null}"#.to_string(),
+            ),
+            (
+                Direction::Inbound,
+                r#"{"type":"RESPONSE","payload":null}"#.to_string(),
+            ),
+        ]);
+    }
 }
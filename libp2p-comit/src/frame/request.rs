@@ -69,6 +69,10 @@ impl OutboundRequest {
             inner: Request { body, ..self.inner },
         }
     }
+
+    pub fn request_type(&self) -> &str {
+        self.inner.request_type.as_str()
+    }
 }
 
 impl UnvalidatedInboundRequest {
@@ -120,6 +124,10 @@ impl UnknownMandatoryHeaders {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.0.iter()
+    }
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -1,6 +1,6 @@
 use crate::{
     frame::{
-        self, JsonFrameCodec, OutboundRequest, Response, UnknownMandatoryHeaders,
+        self, FrameObserver, JsonFrameCodec, OutboundRequest, Response, UnknownMandatoryHeaders,
         ValidatedInboundRequest,
     },
     protocol::ComitProtocolConfig,
@@ -18,6 +18,7 @@ use std::{
     collections::{HashMap, HashSet},
     convert::Infallible,
     fmt::Display,
+    sync::Arc,
 };
 use tokio::{
     codec::Framed,
@@ -36,6 +37,8 @@ pub struct ComitHandler<TSubstream> {
     current_task: Option<Task>,
 
     known_headers: HashMap<String, HashSet<String>>,
+    max_inbound_substreams: Option<usize>,
+    protocol: ComitProtocolConfig,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -71,6 +74,35 @@ impl<TSubstream> ComitHandler<TSubstream> {
             inbound_substreams: Vec::new(),
             outbound_substreams: Vec::new(),
             current_task: None,
+            max_inbound_substreams: None,
+            protocol: ComitProtocolConfig::default(),
+        }
+    }
+
+    /// Drop inbound substreams the peer opens while this many are still
+    /// being processed.
+    pub fn with_max_inbound_substreams(self, max_inbound_substreams: usize) -> Self {
+        Self {
+            max_inbound_substreams: Some(max_inbound_substreams),
+            ..self
+        }
+    }
+
+    /// Let `frame_observer` see every frame sent or received on the
+    /// substreams of this connection.
+    pub fn with_frame_observer(self, frame_observer: Arc<dyn FrameObserver>) -> Self {
+        Self {
+            protocol: self.protocol.with_frame_observer(frame_observer),
+            ..self
+        }
+    }
+
+    /// Reject frames larger than `max_frame_size` bytes on the substreams of
+    /// this connection.
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            protocol: self.protocol.with_max_frame_size(max_frame_size),
+            ..self
         }
     }
 }
@@ -78,7 +110,7 @@ impl<TSubstream> ComitHandler<TSubstream> {
 #[derive(Debug)]
 pub struct PendingOutboundRequest {
     pub request: OutboundRequest,
-    pub channel: oneshot::Sender<Response>,
+    pub channel: oneshot::Sender<Result<Response, RequestError>>,
 }
 
 #[derive(Debug)]
@@ -90,7 +122,18 @@ pub struct PendingInboundRequest {
 #[derive(Debug)]
 pub struct PendingInboundResponse {
     pub response: Response,
-    pub channel: oneshot::Sender<Response>,
+    pub channel: oneshot::Sender<Result<Response, RequestError>>,
+}
+
+/// Why an outbound request did not yield a response.
+#[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+pub enum RequestError {
+    #[error("failed to connect to the peer")]
+    PeerUnreachable,
+    #[error("the connection closed before a response was received")]
+    ConnectionClosed,
+    #[error("the peer answered with something other than a valid response")]
+    InvalidResponse,
 }
 
 /// Events that occur 'in' this node (as opposed to events from a peer node).
@@ -135,13 +178,23 @@ impl<TSubstream: AsyncRead + AsyncWrite> ProtocolsHandler for ComitHandler<TSubs
     type OutboundOpenInfo = ProtocolOutboundOpenInfo;
 
     fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol> {
-        SubstreamProtocol::new(ComitProtocolConfig {})
+        SubstreamProtocol::new(self.protocol.clone())
     }
 
     fn inject_fully_negotiated_inbound(
         &mut self,
         stream: Framed<Negotiated<TSubstream>, JsonFrameCodec>,
     ) {
+        if let Some(max) = self.max_inbound_substreams {
+            if self.inbound_substreams.len() >= max {
+                log::warn!(
+                    "dropping inbound substream because {} are still pending",
+                    self.inbound_substreams.len()
+                );
+                return;
+            }
+        }
+
         self.inbound_substreams
             .push(substream::inbound::State::WaitingMessage { stream });
 
@@ -177,7 +230,10 @@ impl<TSubstream: AsyncRead + AsyncWrite> ProtocolsHandler for ComitHandler<TSubs
         match event {
             ProtocolInEvent::Message(OutboundMessage::Request(request)) => {
                 self.outbound_substreams
-                    .push(substream::outbound::State::WaitingOpen { request });
+                    .push(substream::outbound::State::WaitingOpen {
+                        request,
+                        protocol: self.protocol.clone(),
+                    });
             }
         }
 
@@ -283,6 +339,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn inbound_substreams_beyond_the_limit_are_dropped() {
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        let (_, first_listener) = runtime.block_on(setup_substream_with_json_codec()).unwrap();
+        let (_, second_listener) = runtime.block_on(setup_substream_with_json_codec()).unwrap();
+        let mut handler =
+            ComitHandler::new(request_with_no_headers("PING")).with_max_inbound_substreams(1);
+
+        handler.inject_fully_negotiated_inbound(first_listener);
+        handler.inject_fully_negotiated_inbound(second_listener);
+
+        assert_that(&handler.inbound_substreams.len()).is_equal_to(1);
+    }
+
     #[test]
     fn given_inbound_substream_when_unknown_request_should_emit_unknown_request_type() {
         let mut runtime = tokio::runtime::Runtime::new().unwrap();
@@ -303,9 +373,9 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(
-                ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(Error::UnknownRequestType(_))),
-            )
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::UnknownRequestType(_)
+            )),)
         )
     }
 
@@ -339,11 +409,9 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(
-                ProtocolsHandlerEvent::Custom(
-                    ProtocolOutEvent::Error(Error::UnknownMandatoryHeader(_)),
-                ),
-            )
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::UnknownMandatoryHeader(_)
+            ),),)
         )
     }
 
@@ -375,7 +443,9 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(Error::MalformedFrame(_))))
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::MalformedFrame(_)
+            )))
         )
     }
 
@@ -407,9 +477,9 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(
-                ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Message(InboundMessage::Request(_))),
-            )
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Message(
+                InboundMessage::Request(_)
+            )),)
         )
     }
 
@@ -441,7 +511,9 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(Error::UnexpectedFrame(_))))
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::UnexpectedFrame(_)
+            )))
         )
     }
 
@@ -507,7 +579,9 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(Error::MalformedJson(_))))
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::MalformedJson(_)
+            )))
         )
     }
 
@@ -587,10 +661,96 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(Error::UnexpectedFrame(_))))
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::UnexpectedFrame(_)
+            )))
         )
     }
 
+    #[test]
+    fn given_an_outbound_request_when_request_should_fail_with_invalid_response() {
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        let (dialer, listener) = runtime
+            .block_on(setup_substream(
+                JsonFrameCodec::default(),
+                LinesCodec::new(),
+            ))
+            .unwrap();
+        let mut handler = ComitHandler::new(request_with_no_headers("PING"));
+
+        // given an outbound substream
+        let (sender, receiver) = oneshot::channel();
+        handler.inject_fully_negotiated_outbound(
+            dialer,
+            ProtocolOutboundOpenInfo::Message(OutboundMessage::Request(PendingOutboundRequest {
+                request: OutboundRequest::new("PING"),
+                channel: sender,
+            })),
+        );
+
+        // when receiving a request instead of a response
+        let send = listener
+            .send(r#"{"type": "REQUEST", "payload":{}}"#.to_owned())
+            .map(|_| ())
+            .map_err(|_| ());
+        let _ = runtime.spawn(send);
+
+        let _ = runtime
+            .block_on(handler.into_event_stream().take(1).collect())
+            .unwrap();
+
+        // then
+        matches::assert_matches!(
+            runtime.block_on(receiver),
+            Ok(Err(RequestError::InvalidResponse))
+        );
+    }
+
+    #[test]
+    fn given_an_outbound_request_when_malformed_response_should_fail_with_invalid_response() {
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        let (dialer, listener) = runtime
+            .block_on(setup_substream(
+                JsonFrameCodec::default(),
+                LinesCodec::new(),
+            ))
+            .unwrap();
+        let mut handler = ComitHandler::new(request_with_no_headers("PING"));
+
+        // given an outbound substream
+        let (sender, receiver) = oneshot::channel();
+        handler.inject_fully_negotiated_outbound(
+            dialer,
+            ProtocolOutboundOpenInfo::Message(OutboundMessage::Request(PendingOutboundRequest {
+                request: OutboundRequest::new("PING"),
+                channel: sender,
+            })),
+        );
+
+        // when receiving a response whose payload is not a response
+        let send = listener
+            .send(r#"{"type": "RESPONSE", "payload": "corrupted"}"#.to_owned())
+            .map(|_| ())
+            .map_err(|_| ());
+        let _ = runtime.spawn(send);
+
+        let events = runtime
+            .block_on(handler.into_event_stream().take(1).collect())
+            .unwrap();
+
+        // then
+        matches::assert_matches!(
+            events.get(0),
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::MalformedFrame(_)
+            )))
+        );
+        matches::assert_matches!(
+            runtime.block_on(receiver),
+            Ok(Err(RequestError::InvalidResponse))
+        );
+    }
+
     #[test]
     fn given_an_outbound_request_when_invalid_json_should_emit_malformed_json() {
         let mut runtime = tokio::runtime::Runtime::new().unwrap();
@@ -626,7 +786,9 @@ mod tests {
         // then
         matches::assert_matches!(
             events.get(0),
-            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(Error::MalformedJson(_))))
+            Some(ProtocolsHandlerEvent::Custom(ProtocolOutEvent::Error(
+                Error::MalformedJson(_)
+            )))
         )
     }
 }
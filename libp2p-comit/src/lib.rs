@@ -5,6 +5,7 @@
 pub mod frame;
 mod behaviour;
 mod handler;
+mod metrics;
 mod protocol;
 mod substream;
 #[cfg(test)]
@@ -14,8 +15,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 
 pub use self::{
-    behaviour::{BehaviourOutEvent, Comit},
-    handler::{ComitHandler, PendingInboundRequest, PendingOutboundRequest},
+    behaviour::{BehaviourOutEvent, Comit, Unrecognized},
+    handler::{ComitHandler, PendingInboundRequest, PendingOutboundRequest, RequestError},
+    metrics::{Histogram, RequestKind, RequestMetrics, LATENCY_BUCKETS},
     protocol::{ComitProtocolConfig, Frames},
 };
 use crate::handler::{ProtocolOutEvent, ProtocolOutboundOpenInfo};
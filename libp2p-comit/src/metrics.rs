@@ -0,0 +1,87 @@
+use crate::frame::Direction;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the buckets request latencies are sorted
+/// into.
+pub const LATENCY_BUCKETS: [f64; 8] = [0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0];
+
+/// Identifies the requests of one type that went in one direction.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RequestKind {
+    pub direction: Direction,
+    pub request_type: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestMetrics {
+    pub count: u64,
+    /// How long peers took to answer our requests, always empty for inbound
+    /// ones.
+    pub latency: Histogram,
+}
+
+/// Cumulative histogram over `LATENCY_BUCKETS`, the way Prometheus expects
+/// it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Histogram {
+    buckets: [u64; 8],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+
+        for (bucket, upper_bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS.iter()) {
+            if seconds <= *upper_bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    /// The upper bound of each bucket together with the number of
+    /// observations that did not exceed it.
+    pub fn buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        LATENCY_BUCKETS
+            .iter()
+            .cloned()
+            .zip(self.buckets.iter().cloned())
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The sum of all observations in seconds.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+
+    #[test]
+    fn observations_are_counted_in_every_bucket_they_fit_into() {
+        let mut histogram = Histogram::default();
+
+        histogram.observe(Duration::from_millis(500));
+        histogram.observe(Duration::from_secs(2));
+        histogram.observe(Duration::from_secs(60));
+
+        assert_that(
+            &histogram
+                .buckets()
+                .map(|(_, count)| count)
+                .collect::<Vec<_>>(),
+        )
+        .is_equal_to(vec![0, 0, 0, 1, 1, 2, 2, 2]);
+        assert_that(&histogram.count()).is_equal_to(3);
+        assert_that(&histogram.sum()).is_equal_to(62.5);
+    }
+}
@@ -1,7 +1,7 @@
-use crate::frame::{self, JsonFrameCodec};
+use crate::frame::{FrameObserver, JsonFrameCodec};
 use futures::future::FutureResult;
 use libp2p_core::{InboundUpgrade, Negotiated, OutboundUpgrade, UpgradeInfo};
-use std::{convert::Infallible, iter};
+use std::{convert::Infallible, iter, sync::Arc};
 use tokio::{
     codec::{Decoder, Framed},
     prelude::*,
@@ -9,8 +9,43 @@ use tokio::{
 
 pub type Frames<TSubstream> = Framed<Negotiated<TSubstream>, JsonFrameCodec>;
 
-#[derive(Clone, Copy, Debug)]
-pub struct ComitProtocolConfig {}
+#[derive(Clone, Debug, Default)]
+pub struct ComitProtocolConfig {
+    frame_observer: Option<Arc<dyn FrameObserver>>,
+    max_frame_size: Option<usize>,
+}
+
+impl ComitProtocolConfig {
+    /// Let `frame_observer` see every frame sent or received on the
+    /// substreams negotiated with this config.
+    pub fn with_frame_observer(self, frame_observer: Arc<dyn FrameObserver>) -> Self {
+        Self {
+            frame_observer: Some(frame_observer),
+            ..self
+        }
+    }
+
+    /// Reject frames larger than `max_frame_size` bytes on the substreams
+    /// negotiated with this config.
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size: Some(max_frame_size),
+            ..self
+        }
+    }
+
+    fn codec(self) -> JsonFrameCodec {
+        let codec = match self.frame_observer {
+            Some(frame_observer) => JsonFrameCodec::with_observer(frame_observer),
+            None => JsonFrameCodec::default(),
+        };
+
+        match self.max_frame_size {
+            Some(max_frame_size) => codec.with_max_frame_size(max_frame_size),
+            None => codec,
+        }
+    }
+}
 
 impl UpgradeInfo for ComitProtocolConfig {
     type Info = &'static [u8];
@@ -31,8 +66,7 @@ where
 
     #[inline]
     fn upgrade_inbound(self, socket: Negotiated<TSubstream>, _: Self::Info) -> Self::Future {
-        let codec = frame::JsonFrameCodec::default();
-        futures::future::ok(codec.framed(socket))
+        futures::future::ok(self.codec().framed(socket))
     }
 }
 
@@ -46,7 +80,6 @@ where
 
     #[inline]
     fn upgrade_outbound(self, socket: Negotiated<TSubstream>, _: Self::Info) -> Self::Future {
-        let codec = frame::JsonFrameCodec::default();
-        futures::future::ok(codec.framed(socket))
+        futures::future::ok(self.codec().framed(socket))
     }
 }
@@ -2,7 +2,7 @@ use crate::{
     frame::Response,
     handler::{
         self, InboundMessage, OutboundMessage, PendingInboundResponse, PendingOutboundRequest,
-        ProtocolOutEvent, ProtocolOutboundOpenInfo,
+        ProtocolOutEvent, ProtocolOutboundOpenInfo, RequestError,
     },
     protocol::{ComitProtocolConfig, Frames},
     substream::{Advance, Advanced, CloseStream},
@@ -18,21 +18,24 @@ use tokio::prelude::*;
 /// States of an outbound substream i.e. from us to peer node.
 pub enum State<TSubstream> {
     /// We haven't started opening the outgoing substream yet.
-    WaitingOpen { request: PendingOutboundRequest },
+    WaitingOpen {
+        request: PendingOutboundRequest,
+        protocol: ComitProtocolConfig,
+    },
     /// Waiting to send a message to the remote.
     WaitingSend {
         frame: Frame,
-        response_sender: oneshot::Sender<Response>,
+        response_sender: oneshot::Sender<Result<Response, RequestError>>,
         stream: Frames<TSubstream>,
     },
     /// Waiting to flush the substream so that the data arrives at the remote.
     WaitingFlush {
-        response_sender: oneshot::Sender<Response>,
+        response_sender: oneshot::Sender<Result<Response, RequestError>>,
         stream: Frames<TSubstream>,
     },
     /// Waiting for the answer to our message.
     WaitingAnswer {
-        response_sender: oneshot::Sender<Response>,
+        response_sender: oneshot::Sender<Result<Response, RequestError>>,
         stream: Frames<TSubstream>,
     },
     /// The substream is being closed.
@@ -54,9 +57,9 @@ impl<TSubstream: AsyncRead + AsyncWrite> Advance for State<TSubstream> {
     ) -> Advanced<State<TSubstream>> {
         use self::State::*;
         match self {
-            WaitingOpen { request } => {
+            WaitingOpen { request, protocol } => {
                 Advanced::emit_event(ProtocolsHandlerEvent::OutboundSubstreamRequest {
-                    protocol: SubstreamProtocol::new(ComitProtocolConfig {}),
+                    protocol: SubstreamProtocol::new(protocol),
                     info: ProtocolOutboundOpenInfo::Message(OutboundMessage::Request(request)),
                 })
             }
@@ -98,17 +101,20 @@ impl<TSubstream: AsyncRead + AsyncWrite> Advance for State<TSubstream> {
             } => match stream.poll() {
                 Ok(Async::Ready(Some(frame))) => match frame.frame_type {
                     FrameType::Response => {
-                        let event = serde_json::from_value(frame.payload)
-                            .map(|response| {
-                                ProtocolOutEvent::Message(InboundMessage::Response(
-                                    PendingInboundResponse {
-                                        response,
-                                        channel: response_sender,
-                                    },
-                                ))
-                            })
-                            .map_err(handler::Error::MalformedFrame)
-                            .unwrap_or_else(ProtocolOutEvent::Error);
+                        let event = match serde_json::from_value(frame.payload.clone()) {
+                            Ok(response) => ProtocolOutEvent::Message(InboundMessage::Response(
+                                PendingInboundResponse {
+                                    response,
+                                    channel: response_sender,
+                                },
+                            )),
+                            Err(error) => {
+                                log::warn!(target: "sub-libp2p", "received malformed response {:?}: {:?}", frame, error);
+                                let _ = response_sender.send(Err(RequestError::InvalidResponse));
+
+                                ProtocolOutEvent::Error(handler::Error::MalformedFrame(error))
+                            }
+                        };
 
                         Advanced {
                             new_state: Some(WaitingClose { stream }),
@@ -116,9 +122,17 @@ impl<TSubstream: AsyncRead + AsyncWrite> Advance for State<TSubstream> {
                         }
                     }
                     FrameType::Request => {
+                        log::warn!(target: "sub-libp2p", "received request instead of response: {:?}", frame);
+                        let _ = response_sender.send(Err(RequestError::InvalidResponse));
+
                         Advanced::error(stream, handler::Error::UnexpectedFrame(frame))
                     }
-                    FrameType::Unknown => Advanced::error(stream, handler::Error::UnknownFrameType),
+                    FrameType::Unknown => {
+                        log::warn!(target: "sub-libp2p", "received frame of unknown type instead of response: {:?}", frame);
+                        let _ = response_sender.send(Err(RequestError::InvalidResponse));
+
+                        Advanced::error(stream, handler::Error::UnknownFrameType)
+                    }
                 },
                 Ok(Async::NotReady) => Advanced::transition_to(WaitingAnswer {
                     response_sender,